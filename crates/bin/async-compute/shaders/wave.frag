@@ -0,0 +1,10 @@
+#version 450
+
+layout (location = 0) in float i_height;
+
+layout (location = 0) out vec4 uFragColor;
+
+void main() {
+    float t = clamp(i_height * 2.0 + 0.5, 0.0, 1.0);
+    uFragColor = vec4(mix(vec3(0.05, 0.2, 0.5), vec3(0.6, 0.9, 1.0), t), 1.0);
+}
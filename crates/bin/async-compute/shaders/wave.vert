@@ -0,0 +1,19 @@
+#version 460
+
+layout (location = 0) out float o_height;
+
+layout (set = 0, binding = 0) uniform UniformBufferObject {
+    mat4 view;
+    mat4 proj;
+} ubo;
+
+layout (set = 0, binding = 1) readonly buffer Positions {
+    vec4 positions[];
+};
+
+void main() {
+    vec4 pos = positions[gl_VertexIndex];
+    o_height = pos.y;
+
+    gl_Position = ubo.proj * ubo.view * pos;
+}
@@ -0,0 +1,332 @@
+use anyhow::Result;
+use cinder::{
+    App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData,
+    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, ComputePipeline, Format,
+    GraphicsPipeline, GraphicsPipelineDescription, Image, ImageDescription, ImageUsage,
+    InitContext, InputState, Layout, PipelineError, RenderAttachmentDesc, RenderGraph, RenderPass,
+    Renderer, ResourceId, MAX_FRAMES_IN_FLIGHT,
+};
+use math::{size::Size2D, vec::Vec3};
+use std::path::Path;
+use util::{SdlContext, WindowDescription};
+
+pub const WINDOW_WIDTH: u32 = 1280;
+pub const WINDOW_HEIGHT: u32 = 1280;
+
+// Vertices per side of the wave grid, must be a multiple of the compute workgroup size
+const GRID_SIZE: u32 = 128;
+const WORKGROUP_SIZE: u32 = 8;
+
+include!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/gen/wave_shader_structs.rs"
+));
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct WaveConstants {
+    time: f32,
+    grid_size: u32,
+}
+
+fn grid_indices() -> Vec<u32> {
+    let mut indices = Vec::with_capacity(((GRID_SIZE - 1) * (GRID_SIZE - 1) * 6) as usize);
+    for z in 0..GRID_SIZE - 1 {
+        for x in 0..GRID_SIZE - 1 {
+            let i = z * GRID_SIZE + x;
+            indices.extend([
+                i,
+                i + GRID_SIZE,
+                i + 1,
+                i + 1,
+                i + GRID_SIZE,
+                i + GRID_SIZE + 1,
+            ]);
+        }
+    }
+    indices
+}
+
+pub struct AsyncCompute {
+    depth_image_handle: ResourceId<Image>,
+    // In the resource manager, so the hot reloader can recreate it when wave.comp changes
+    compute_pipeline_handle: ResourceId<ComputePipeline>,
+    graphics_pipeline: GraphicsPipeline,
+    // Each frame in flight gets its own positions, so the compute queue never writes to a buffer
+    // that a previous frame may still be drawing from
+    position_buffers: Vec<Buffer>,
+    compute_bind_groups: Vec<BindGroup>,
+    graphics_bind_groups: Vec<BindGroup>,
+    index_buffer: Buffer,
+    ubo_buffer: Buffer,
+}
+
+impl App for AsyncCompute {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create App Resources
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+        let depth_image = context.renderer.device.create_image(
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            ImageDescription {
+                format: Format::D32_SFLOAT,
+                usage: ImageUsage::Depth,
+                ..Default::default()
+            },
+        )?;
+
+        let compute_shader_handle = context.renderer.resource_manager.insert_shader(
+            context.renderer.device.create_shader(
+                include_bytes!("../shaders/spv/wave.comp.spv"),
+                Default::default(),
+            )?,
+        );
+        let vertex_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/wave.vert.spv"),
+            Default::default(),
+        )?;
+        let fragment_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/wave.frag.spv"),
+            Default::default(),
+        )?;
+        let compute_pipeline = context.renderer.device.create_compute_pipeline(
+            context
+                .renderer
+                .resource_manager
+                .shaders
+                .get(compute_shader_handle)
+                .unwrap(),
+            Default::default(),
+        )?;
+        let graphics_pipeline = context.renderer.device.create_graphics_pipeline(
+            &vertex_shader,
+            Some(&fragment_shader),
+            GraphicsPipelineDescription {
+                name: Some("Wave Pipeline".to_owned()),
+                depth_format: Some(Format::D32_SFLOAT),
+                ..Default::default()
+            },
+        )?;
+
+        let ubo_buffer = context.renderer.device.create_buffer_with_data(
+            &[
+                camera::look_to(
+                    Vec3::new(0.0, 3.0, -5.0),
+                    Vec3::new(0.0, -0.6, 1.0),
+                    Vec3::new(0.0, 1.0, 0.0),
+                ),
+                camera::new_infinite_perspective_proj(
+                    surface_rect.width() as f32 / surface_rect.height() as f32,
+                    30.0,
+                    0.01,
+                ),
+            ],
+            BufferDescription {
+                usage: BufferUsage::UNIFORM,
+                ..Default::default()
+            },
+        )?;
+
+        let mut position_buffers = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+        let mut compute_bind_groups = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+        let mut graphics_bind_groups = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+        for _ in 0..MAX_FRAMES_IN_FLIGHT {
+            let position_buffer = context.renderer.device.create_buffer(
+                (GRID_SIZE * GRID_SIZE) as u64 * std::mem::size_of::<[f32; 4]>() as u64,
+                BufferDescription {
+                    usage: BufferUsage::STORAGE,
+                    ..Default::default()
+                },
+            )?;
+            let compute_bind_group = BindGroup::new(
+                &context.renderer.device,
+                compute_pipeline.bind_group_data(0).unwrap(),
+            )?;
+            let graphics_bind_group = BindGroup::new(
+                &context.renderer.device,
+                graphics_pipeline.bind_group_data(0).unwrap(),
+            )?;
+            context.renderer.device.write_bind_group(&[
+                BindGroupBindInfo {
+                    group: compute_bind_group,
+                    dst_binding: 0,
+                    data: BindGroupWriteData::Storage(position_buffer.bind_info()),
+                },
+                BindGroupBindInfo {
+                    group: graphics_bind_group,
+                    dst_binding: 0,
+                    data: BindGroupWriteData::Uniform(ubo_buffer.bind_info()),
+                },
+                BindGroupBindInfo {
+                    group: graphics_bind_group,
+                    dst_binding: 1,
+                    data: BindGroupWriteData::Storage(position_buffer.bind_info()),
+                },
+            ])?;
+            position_buffers.push(position_buffer);
+            compute_bind_groups.push(compute_bind_group);
+            graphics_bind_groups.push(graphics_bind_group);
+        }
+
+        let index_buffer = context.renderer.device.create_buffer_with_data(
+            &grid_indices(),
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+
+        vertex_shader.destroy(&context.renderer.device);
+        fragment_shader.destroy(&context.renderer.device);
+
+        let depth_image_handle = context.renderer.resource_manager.insert_image(depth_image);
+        let compute_pipeline_handle = context
+            .renderer
+            .resource_manager
+            .insert_compute_pipeline(compute_pipeline);
+        context.shader_hot_reloader.set_compute(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("shaders")
+                .join("wave.comp")
+                .canonicalize()?,
+            compute_shader_handle,
+            compute_pipeline_handle,
+        )?;
+
+        Ok(Self {
+            depth_image_handle,
+            compute_pipeline_handle,
+            graphics_pipeline,
+            position_buffers,
+            compute_bind_groups,
+            graphics_bind_groups,
+            index_buffer,
+            ubo_buffer,
+        })
+    }
+
+    fn update(&mut self, renderer: &mut Renderer, _input: &InputState) -> Result<()> {
+        // Animate the wave on the compute queue, the graphics submission for this frame waits on it
+        let frame = renderer.device.current_frame_in_flight();
+        let compute_pipeline = renderer
+            .resource_manager
+            .compute_pipelines
+            .get(self.compute_pipeline_handle)
+            .ok_or(PipelineError::InvalidPipelineHandle)?;
+        let cmd_list = renderer
+            .command_queue
+            .get_compute_command_list(&renderer.device)?;
+        cmd_list.bind_compute_pipeline(&renderer.device, compute_pipeline);
+        cmd_list.bind_compute_descriptor_sets(
+            &renderer.device,
+            compute_pipeline,
+            0,
+            &[self.compute_bind_groups[frame]],
+        );
+        cmd_list.set_compute_bytes(
+            &renderer.device,
+            compute_pipeline,
+            &WaveConstants {
+                time: renderer.time.elapsed_seconds(),
+                grid_size: GRID_SIZE,
+            },
+            0,
+        )?;
+        cmd_list.dispatch(
+            &renderer.device,
+            GRID_SIZE / WORKGROUP_SIZE,
+            GRID_SIZE / WORKGROUP_SIZE,
+            1,
+        );
+        renderer
+            .command_queue
+            .submit_compute(&renderer.device, cmd_list)?;
+        Ok(())
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(
+                    AttachmentType::SwapchainImage,
+                    RenderAttachmentDesc::clear(ClearValue::Color {
+                        color: [0.02, 0.02, 0.05, 1.0],
+                    }),
+                )
+                .set_depth_attachment(
+                    AttachmentType::Reference(self.depth_image_handle),
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::DontCare)
+                        .layout(Layout::DepthAttachment),
+                )
+                .set_callback(allocator, |renderer, cmd_list| {
+                    let frame = renderer.device.current_frame_in_flight();
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.graphics_pipeline)?;
+                    cmd_list.bind_index_buffer(&renderer.device, &self.index_buffer);
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.graphics_pipeline,
+                        0,
+                        &[self.graphics_bind_groups[frame]],
+                    );
+                    cmd_list.draw_offset(
+                        &renderer.device,
+                        self.index_buffer.num_elements.unwrap(),
+                        0,
+                        0,
+                    );
+
+                    Ok(())
+                }),
+        );
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        let depth_image = renderer
+            .resource_manager
+            .images
+            .get_mut(self.depth_image_handle)
+            .unwrap();
+        depth_image.resize(&renderer.device, Size2D::new(width, height))?;
+        self.ubo_buffer.mem_copy(
+            util::offset_of!(WaveUniformBufferObject, proj) as u64,
+            &[camera::new_infinite_perspective_proj(
+                width as f32 / height as f32,
+                30.0,
+                0.01,
+            )],
+        )?;
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        for buffer in &self.position_buffers {
+            buffer.destroy(&renderer.device);
+        }
+        self.index_buffer.destroy(&renderer.device);
+        self.ubo_buffer.destroy(&renderer.device);
+        self.graphics_pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "async-compute",
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let mut cinder = Cinder::<AsyncCompute>::new(&sdl.window).unwrap();
+    cinder.run_game_loop(&mut sdl).unwrap();
+}
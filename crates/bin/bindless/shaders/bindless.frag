@@ -12,10 +12,11 @@ layout (location = 0) out vec4 uFragColor;
 
 layout(push_constant) uniform constants
 {
-	layout (offset=16) uint texture_idx;
+	layout (offset=112) uint texture_idx;
     uint pad;
 };
 
 void main() {
+    // Linear color, tonemapped and encoded for the swapchain by the post-process pass
     uFragColor = texture(textures[texture_idx], i_uv) * i_color;
 }
\ No newline at end of file
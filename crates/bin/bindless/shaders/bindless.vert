@@ -3,13 +3,15 @@
 
 #extension GL_GOOGLE_include_directive: require
 #extension GL_EXT_scalar_block_layout: enable
+#extension GL_EXT_buffer_reference : require
 
+// `scene::QuantizedVertex`, decoded in `main`
 struct Vertex
 {
-	vec4 pos;
-    vec3 color;
-    vec3 normal;
-    vec2 uv;
+    uvec2 position;
+    uvec2 normal;
+    uint uv;
+    uint color;
 };
 
 layout(binding = 0) uniform UniformBufferObject {
@@ -18,22 +20,36 @@ layout(binding = 0) uniform UniformBufferObject {
     mat4 proj;
 } ubo;
 
-layout(set = 0, binding = 1, scalar) readonly buffer Vertices
+// Read through the vertex buffer's device address instead of a descriptor
+layout(buffer_reference, scalar) readonly buffer Vertices
 {
 	Vertex vertices[];
 };
 
+layout(push_constant) uniform constants
+{
+    Vertices vertex_buffer;
+    mat4 mesh_transform;
+    // Local bounds of the mesh, positions are quantized relative to them
+    vec4 bounds_min;
+    vec4 bounds_max;
+};
+
 layout (location = 0) out vec4 o_color;
 layout (location = 1) out vec4 o_normal;
 layout (location = 2) out vec2 o_uv;
 
 
 void main() {
-    Vertex v = vertices[gl_VertexIndex];
+    Vertex v = vertex_buffer.vertices[gl_VertexIndex];
+
+    vec3 normalized_pos = vec3(unpackUnorm2x16(v.position.x), unpackUnorm2x16(v.position.y).x);
+    vec4 pos = vec4(mix(bounds_min.xyz, bounds_max.xyz, normalized_pos), 1.0);
+    vec3 normal = vec3(unpackSnorm2x16(v.normal.x), unpackSnorm2x16(v.normal.y).x);
 
-    o_color = vec4(v.color, 1.0);
-    o_normal = vec4(v.normal, 1.0);
-    o_uv = v.uv;
+    o_color = unpackUnorm4x8(v.color);
+    o_normal = vec4(normal, 1.0);
+    o_uv = unpackHalf2x16(v.uv);
 
-    gl_Position = ubo.proj * ubo.view * ubo.model * v.pos;
+    gl_Position = ubo.proj * ubo.view * ubo.model * mesh_transform * pos;
 }
\ No newline at end of file
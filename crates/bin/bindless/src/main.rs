@@ -1,82 +1,41 @@
 use anyhow::Result;
-use camera::{
-    input::{KeyboardState, MouseState},
-    Camera, CameraDescription,
-};
+use camera::{input::InputMap, Camera, CameraAction, CameraDescription};
 use cinder::{
     App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData,
-    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, Format, GraphicsPipeline,
-    GraphicsPipelineDescription, Image, ImageDescription, ImageUsage, InitContext, Layout,
-    RenderAttachmentDesc, RenderGraph, RenderPass, Renderer, ResourceId,
+    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, DebugDraw,
+    DebugDrawDescription, DebugUiContext, Format, GraphicsPipeline, GraphicsPipelineDescription,
+    Image, ImageDescription, ImageUsage, InitContext, InputState, Layout, PostProcess,
+    RenderAttachmentDesc, RenderGraph, RenderPass, RenderPassResource, Renderer, ResourceId,
+    SamplerDescription, Skybox, SkyboxDescription, TextureData, TextureStreamer,
+    TextureStreamerDescription, TonemapOperator, DEFAULT_TEXTURE_COUNT, WHITE_TEXTURE_INDEX,
 };
+use egui_integration::egui;
 use math::{mat::Mat4, size::Size2D, vec::Vec3};
-use rayon::iter::*;
-use scene::{ObjMesh, Scene, Vertex};
-use sdl2::event::Event;
-use std::path::PathBuf;
+use math_ext::Mat4Ext;
+use rkyv::{de::deserializers::SharedDeserializeMap, Deserialize};
+use scene::{Aabb, ColorSpace, Material, MaterialSlot, QuantizedVertex, Scene, TextureSlots};
+use std::{path::PathBuf, process::ExitCode, time::Instant};
 use util::{SdlContext, WindowDescription};
 
 pub const WINDOW_WIDTH: u32 = 1280;
 pub const WINDOW_HEIGHT: u32 = 1280;
 
+const HDR_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+
 include!(concat!(
     env!("CARGO_MANIFEST_DIR"),
     "/gen/bindless_shader_structs.rs"
 ));
 
-impl Vertex for BindlessVertex {
-    fn from_obj_mesh_index(mesh: &ObjMesh, i: usize) -> Self {
-        let pos = [
-            mesh.positions[i * 3],
-            mesh.positions[i * 3 + 1],
-            mesh.positions[i * 3 + 2],
-            1.0,
-        ];
-
-        let color = if !mesh.vertex_color.is_empty() {
-            [
-                mesh.vertex_color[i * 3],
-                mesh.vertex_color[i * 3 + 1],
-                mesh.vertex_color[i * 3 + 2],
-            ]
-        } else {
-            [1.0; 3]
-        };
-
-        let normal = if !mesh.normals.is_empty() {
-            [
-                mesh.normals[i * 3],
-                mesh.normals[i * 3 + 1],
-                mesh.normals[i * 3 + 2],
-            ]
-        } else {
-            [1.0; 3]
-        };
-
-        let uv = if !mesh.texcoords.is_empty() {
-            [mesh.texcoords[i * 2], 1.0 - mesh.texcoords[i * 2 + 1]]
-        } else {
-            [0.0; 2]
-        };
-
-        Self {
-            pos,
-            color,
-            normal,
-            uv,
-        }
-    }
-
-    fn pos_3d(&self) -> [f32; 3] {
-        [self.pos[0], self.pos[1], self.pos[2]]
-    }
-
-    fn set_pos_3d(mut self, x: f32, y: f32, z: f32) -> Self {
-        self.pos[0] = x;
-        self.pos[1] = y;
-        self.pos[2] = z;
-        self
-    }
+// Must match the vertex shader's push constants
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct MeshConstants {
+    vertex_buffer_address: u64,
+    _pad: u64,
+    mesh_transform: [f32; 16],
+    bounds_min: [f32; 4],
+    bounds_max: [f32; 4],
 }
 
 #[derive(Debug)]
@@ -84,17 +43,32 @@ pub struct MeshDraw {
     vertex_buffer_offset: i32,
     index_buffer_offset: u32,
     num_indices: u32,
-    image_index: Option<u32>,
+    // Element of the bindless texture array, the renderer's white texture for untextured materials
+    texture_slot: u32,
+    transform: [f32; 16],
+    bounds: Aabb,
 }
 
 pub struct BindlessSample {
     camera: Camera,
-    keyboard_state: KeyboardState,
-    mouse_state: MouseState,
+    input_map: InputMap<CameraAction>,
     mesh_draws: Vec<MeshDraw>,
     depth_image_handle: ResourceId<Image>,
+    // Lit scene, tonemapped into the swapchain by `post_process`
+    hdr_image_handle: ResourceId<Image>,
+    post_process: PostProcess,
+    debug_draw: DebugDraw,
+    // `None` when the environment map isn't in the assets folder
+    skybox: Option<Skybox>,
+    show_bounds: bool,
+    // Column-major, for `debug_draw`
+    view_proj: [f32; 16],
     pipeline: GraphicsPipeline,
     bind_group: BindGroup,
+    texture_streamer: TextureStreamer,
+    // Cleared once every texture is resident
+    streaming_start: Option<Instant>,
+    vertex_buffer_address: u64,
     index_buffer: Buffer,
     ubo_buffer: Buffer,
 }
@@ -108,11 +82,21 @@ impl App for BindlessSample {
         let depth_image = context.renderer.device.create_image(
             Size2D::new(surface_rect.width(), surface_rect.height()),
             ImageDescription {
+                name: Some("Depth"),
                 format: Format::D32_SFLOAT,
                 usage: ImageUsage::Depth,
                 ..Default::default()
             },
         )?;
+        let hdr_image = context.renderer.device.create_image(
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            ImageDescription {
+                name: Some("HDR Color"),
+                format: HDR_FORMAT,
+                usage: ImageUsage::ColorAttachment,
+                ..Default::default()
+            },
+        )?;
         let vertex_shader = context.renderer.device.create_shader(
             include_bytes!("../shaders/spv/bindless.vert.spv"),
             Default::default(),
@@ -125,50 +109,90 @@ impl App for BindlessSample {
             &vertex_shader,
             Some(&fragment_shader),
             GraphicsPipelineDescription {
+                color_formats: vec![HDR_FORMAT],
                 depth_format: Some(Format::D32_SFLOAT),
                 ..Default::default()
             },
         )?;
-        let bind_group = BindGroup::new(
+        let mut bind_group = BindGroup::new(
             &context.renderer.device,
             pipeline.bind_group_data(0).unwrap(),
         )?;
 
         let init_time = std::time::Instant::now();
-        let scene = zero_copy_assets::try_decoded_file::<Scene<BindlessVertex>>(
-            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-                .join("assets")
-                .join("sponza")
-                .join("sponza.obj"),
-            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-                .join("assets")
-                .join("gen")
-                .join("sponza.adm"),
-        )?;
+        // Mapped instead of deserialized, so vertices and indices are copied straight from the file
+        // into the GPU buffers. The decoded file is only ever written by this sample, for this type
+        let mapped_scene = unsafe {
+            zero_copy_assets::try_mapped_file::<Scene<QuantizedVertex>>(
+                PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                    .join("assets")
+                    .join("sponza")
+                    .join("sponza.obj"),
+                PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                    .join("assets")
+                    .join("gen")
+                    .join("sponza_quantized.adm"),
+            )
+        }?;
+        let scene = mapped_scene.archived();
         println!("Scene creation: {:?}ms", init_time.elapsed().as_millis());
+        let meshlet_counts = scene
+            .meshes
+            .iter()
+            .map(|mesh| mesh.meshlets.meshlets.len())
+            .collect::<Vec<_>>();
+        println!(
+            "Meshlets: {} total, per mesh: {meshlet_counts:?}",
+            meshlet_counts.iter().sum::<usize>()
+        );
 
-        let (vertices, indices, mesh_draws) = {
-            let mut vertices: Vec<BindlessVertex> = Default::default();
-            let mut indices: Vec<u32> = Default::default();
-            let mut mesh_draws: Vec<MeshDraw> = Default::default();
-            for mesh in scene.meshes {
-                let first_vertex = vertices.len();
-
-                let first_index = indices.len();
-                let num_indices = mesh.indices.len() as u32;
-
-                vertices.extend(mesh.vertices);
-                indices.extend(mesh.indices);
-
-                mesh_draws.push(MeshDraw {
-                    vertex_buffer_offset: first_vertex as i32,
-                    index_buffer_offset: first_index as u32,
-                    num_indices,
-                    image_index: mesh.material_index,
-                });
-            }
-            (vertices, indices, mesh_draws)
-        };
+        // Make room for every diffuse texture in the bindless array, up to what the device
+        // supports. The first elements hold the renderer's default textures, whose white texture
+        // is both the streamer's placeholder and the fallback for materials without a texture
+        let bind_group_data = pipeline.bind_group_data(0).unwrap();
+        let max_textures = bind_group_data.max_count.min(
+            context
+                .renderer
+                .device
+                .capabilities()
+                .max_per_stage_descriptor_sampled_images,
+        ) - DEFAULT_TEXTURE_COUNT;
+        // Textures are moved into the streamer's requests, so materials are still deserialized
+        let materials: Vec<Material> = scene
+            .materials
+            .deserialize(&mut SharedDeserializeMap::new())?;
+        let texture_slots = TextureSlots::new(
+            &materials,
+            MaterialSlot::Diffuse,
+            DEFAULT_TEXTURE_COUNT,
+            max_textures,
+            WHITE_TEXTURE_INDEX,
+        );
+        if texture_slots.array_len() > bind_group_data.count {
+            bind_group.grow(
+                &context.renderer.device,
+                bind_group_data,
+                bind_group_data.count,
+                texture_slots.array_len(),
+            )?;
+        }
+        context.renderer.write_default_textures(bind_group, 2)?;
+
+        let mut vertex_count = 0;
+        let mut index_count = 0;
+        let mut mesh_draws: Vec<MeshDraw> = Vec::with_capacity(scene.meshes.len());
+        for mesh in scene.meshes.iter() {
+            mesh_draws.push(MeshDraw {
+                vertex_buffer_offset: vertex_count as i32,
+                index_buffer_offset: index_count as u32,
+                num_indices: mesh.indices.len() as u32,
+                texture_slot: texture_slots.slot(mesh.material_index()),
+                transform: mesh.transform,
+                bounds: mesh.bounds(),
+            });
+            vertex_count += mesh.vertices.len();
+            index_count += mesh.indices.len();
+        }
         let camera = Camera::new(
             Vec3::new(0.0, 50.0, 0.0),
             Vec3::new(1.0, 0.0, 0.0),
@@ -180,6 +204,7 @@ impl App for BindlessSample {
         let ubo_buffer = context.renderer.device.create_buffer(
             std::mem::size_of::<BindlessUniformBufferObject>() as u64,
             BufferDescription {
+                name: Some("Uniform Buffer"),
                 usage: BufferUsage::UNIFORM,
                 ..Default::default()
             },
@@ -192,85 +217,95 @@ impl App for BindlessSample {
                 camera.projection(surface_rect.width() as f32, surface_rect.height() as f32),
             ],
         )?;
-        let index_buffer = context.renderer.device.create_buffer_with_data(
-            &indices,
+        let mut index_buffer = context.renderer.device.create_buffer(
+            (index_count * std::mem::size_of::<u32>()) as u64,
             BufferDescription {
+                name: Some("Sponza Indices"),
                 usage: BufferUsage::INDEX,
                 ..Default::default()
             },
         )?;
-        let vertex_buffer = context.renderer.device.create_buffer_with_data(
-            &vertices,
+        index_buffer.num_elements = Some(index_count as u32);
+        let mut vertex_buffer = context.renderer.device.create_buffer(
+            (vertex_count * std::mem::size_of::<QuantizedVertex>()) as u64,
             BufferDescription {
-                usage: BufferUsage::STORAGE | BufferUsage::TRANSFER_DST,
+                name: Some("Sponza Vertices"),
+                usage: BufferUsage::SHADER_DEVICE_ADDRESS | BufferUsage::TRANSFER_DST,
                 ..Default::default()
             },
         )?;
-        context.renderer.device.write_bind_group(&[
-            BindGroupBindInfo {
+        vertex_buffer.num_elements = Some(vertex_count as u32);
+        // The archived vertices have the same layout as `QuantizedVertex`
+        for (mesh, draw) in scene.meshes.iter().zip(&mesh_draws) {
+            index_buffer.mem_copy(
+                (draw.index_buffer_offset as usize * std::mem::size_of::<u32>()) as u64,
+                mesh.indices.as_slice(),
+            )?;
+            vertex_buffer.mem_copy(
+                (draw.vertex_buffer_offset as usize * std::mem::size_of::<QuantizedVertex>())
+                    as u64,
+                mesh.vertices.as_slice(),
+            )?;
+        }
+        let vertex_buffer_address = vertex_buffer.device_address(&context.renderer.device);
+        context
+            .renderer
+            .device
+            .write_bind_group(&[BindGroupBindInfo {
                 group: bind_group,
                 dst_binding: 0,
                 data: BindGroupWriteData::Uniform(ubo_buffer.bind_info()),
-            },
-            BindGroupBindInfo {
-                group: bind_group,
-                dst_binding: 1,
-                data: BindGroupWriteData::Storage(vertex_buffer.bind_info()),
-            },
-        ])?;
-
-        let image_data = scene
-            .materials
-            .par_iter()
-            .enumerate()
-            .filter(|(_, material)| material.diffuse.is_some())
-            .map(|(idx, material)| (idx, material.diffuse.as_ref().unwrap()))
-            .collect::<Vec<_>>();
+            }])?;
 
-        let sampler = context.renderer.device.create_sampler(Default::default())?;
-        let images = image_data
-            .into_iter()
-            .map(|(idx, image_data)| {
-                let texture = context
-                    .renderer
-                    .device
-                    .create_image_with_data_immediate(
-                        Size2D::new(image_data.width, image_data.height),
-                        &image_data.bytes,
-                        &context.renderer.command_queue,
-                        Default::default(),
-                    )
-                    .unwrap();
-
-                context
-                    .renderer
-                    .device
-                    .write_bind_group(&[BindGroupBindInfo {
-                        group: bind_group,
-                        dst_binding: 2,
-                        data: BindGroupWriteData::SampledImage(texture.bind_info(
-                            &sampler,
-                            Layout::ShaderReadOnly,
-                            Some(idx as u32),
-                        )),
-                    }])
-                    .unwrap();
-
-                texture
-            })
-            .collect::<Vec<_>>();
+        // Textures are uploaded over the first frames, so the scene shows up right away
+        let mut texture_streamer = TextureStreamer::new(
+            &context.renderer.device,
+            bind_group,
+            TextureStreamerDescription {
+                dst_binding: 2,
+                placeholder_index: texture_slots.fallback_slot(),
+                sampler: SamplerDescription {
+                    max_anisotropy: Some(16.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )?;
+        for (idx, material) in materials.into_iter().enumerate() {
+            if let (Some(slot), Some(image_data)) =
+                (texture_slots.texture_slot(idx), material.diffuse)
+            {
+                let format = match MaterialSlot::Diffuse.color_space() {
+                    ColorSpace::Srgb => Format::R8G8B8A8_SRGB,
+                    ColorSpace::Linear => Format::R8G8B8A8_UNORM,
+                };
+                texture_streamer.request(slot, move || {
+                    Ok(TextureData {
+                        size: Size2D::new(image_data.width, image_data.height),
+                        bytes: image_data.bytes,
+                        format: Some(format),
+                    })
+                })?;
+            }
+        }
 
         //
         // Add resources to ResourceManager
         //
-        for image in images {
-            context.renderer.resource_manager.insert_image(image);
-        }
         context
             .renderer
             .resource_manager
             .insert_buffer(vertex_buffer);
-        context.renderer.resource_manager.insert_sampler(sampler);
+
+        // Every texture and buffer in the scene shares a handful of device allocations
+        let memory_stats = context.renderer.device.memory_stats();
+        println!(
+            "Device memory: {} allocations in {} blocks, {:.1}/{:.1} MiB used",
+            memory_stats.allocation_count,
+            memory_stats.block_count,
+            memory_stats.used_bytes as f64 / (1024.0 * 1024.0),
+            memory_stats.reserved_bytes as f64 / (1024.0 * 1024.0),
+        );
 
         //
         // Cleanup
@@ -279,38 +314,82 @@ impl App for BindlessSample {
         fragment_shader.destroy(&context.renderer.device);
 
         let depth_image_handle = context.renderer.resource_manager.insert_image(depth_image);
+        let hdr_image_handle = context.renderer.resource_manager.insert_image(hdr_image);
+        let post_process = PostProcess::new(context.renderer)?;
+        post_process.set_input(context.renderer, hdr_image_handle)?;
+        let debug_draw = DebugDraw::new(
+            context.renderer,
+            DebugDrawDescription {
+                flipped_viewport: false,
+                // Thin lines get lost over the scene at high resolutions
+                line_width: 2.0,
+                ..Default::default()
+            },
+        )?;
+        let view_proj = view_proj(
+            &camera,
+            surface_rect.width() as f32,
+            surface_rect.height() as f32,
+        );
+        let environment_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("assets")
+            .join("environment.hdr");
+        let skybox = if environment_path.exists() {
+            Some(Skybox::new(
+                context.renderer,
+                &environment_path,
+                SkyboxDescription {
+                    color_format: HDR_FORMAT,
+                    depth_format: Format::D32_SFLOAT,
+                    ..Default::default()
+                },
+            )?)
+        } else {
+            println!(
+                "No environment map at {}, drawing without a skybox",
+                environment_path.display()
+            );
+            None
+        };
 
         Ok(Self {
             camera,
-            keyboard_state: Default::default(),
-            mouse_state: Default::default(),
+            input_map: CameraAction::default_input_map(),
             mesh_draws,
             depth_image_handle,
+            hdr_image_handle,
+            post_process,
+            debug_draw,
+            skybox,
+            show_bounds: false,
+            view_proj,
             pipeline,
             bind_group,
+            texture_streamer,
+            streaming_start: Some(Instant::now()),
+            vertex_buffer_address,
             index_buffer,
             ubo_buffer,
         })
     }
 
-    fn on_frame_start(&mut self) -> anyhow::Result<()> {
-        self.mouse_state.reset_delta();
-        Ok(())
-    }
-
-    fn on_event(&mut self, event: &Event) -> anyhow::Result<()> {
-        self.keyboard_state.on_event(&event);
-        self.mouse_state.on_event(&event);
-        Ok(())
-    }
+    fn update(&mut self, renderer: &mut Renderer, input: &InputState) -> Result<()> {
+        self.texture_streamer
+            .update(&renderer.device, &renderer.command_queue)?;
+        if self.texture_streamer.pending_count() == 0 {
+            if let Some(start) = self.streaming_start.take() {
+                println!("Texture streaming: {:?}ms", start.elapsed().as_millis());
+                for (slot, err) in self.texture_streamer.failed() {
+                    println!("Failed to stream texture {slot}: {err:?}");
+                }
+            }
+        }
 
-    fn update(&mut self, renderer: &mut Renderer) -> Result<()> {
         let surface_rect = renderer.device.surface_rect();
         self.camera.update(
-            &self.keyboard_state,
-            &self.mouse_state,
-            surface_rect.width(),
-            surface_rect.height(),
+            &self.input_map,
+            input,
+            Size2D::new(surface_rect.width(), surface_rect.height()),
             renderer.last_dt(),
         );
         self.ubo_buffer.mem_copy(
@@ -321,6 +400,11 @@ impl App for BindlessSample {
                     .projection(surface_rect.width() as f32, surface_rect.height() as f32),
             ],
         )?;
+        self.view_proj = view_proj(
+            &self.camera,
+            surface_rect.width() as f32,
+            surface_rect.height() as f32,
+        );
         Ok(())
     }
 
@@ -333,18 +417,21 @@ impl App for BindlessSample {
             allocator,
             RenderPass::new(allocator)
                 .with_flipped_viewport(false)
-                .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
+                .add_color_attachment(
+                    AttachmentType::Reference(self.hdr_image_handle),
+                    RenderAttachmentDesc::clear(ClearValue::Color {
+                        color: [0.0, 0.0, 0.0, 1.0],
+                    }),
+                )
+                .add_output(RenderPassResource::Image(self.hdr_image_handle))
                 .set_depth_attachment(
                     AttachmentType::Reference(self.depth_image_handle),
-                    RenderAttachmentDesc {
-                        store_op: AttachmentStoreOp::DontCare,
-                        layout: Layout::DepthAttachment,
-                        clear_value: ClearValue::default_depth(),
-                        ..Default::default()
-                    },
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::DontCare)
+                        .layout(Layout::DepthAttachment),
                 )
                 .set_callback(allocator, |renderer, cmd_list| {
-                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipeline);
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipeline)?;
                     cmd_list.bind_index_buffer(&renderer.device, &self.index_buffer);
                     cmd_list.bind_descriptor_sets(
                         &renderer.device,
@@ -353,14 +440,34 @@ impl App for BindlessSample {
                         &[self.bind_group],
                     );
                     for mesh_draw in &self.mesh_draws {
-                        if let Some(index) = mesh_draw.image_index {
-                            cmd_list.set_fragment_bytes(
-                                &renderer.device,
-                                &self.pipeline,
-                                &[index],
-                                0,
-                            )?;
-                        }
+                        cmd_list.set_vertex_bytes(
+                            &renderer.device,
+                            &self.pipeline,
+                            &MeshConstants {
+                                vertex_buffer_address: self.vertex_buffer_address,
+                                _pad: 0,
+                                mesh_transform: mesh_draw.transform,
+                                bounds_min: [
+                                    mesh_draw.bounds.min[0],
+                                    mesh_draw.bounds.min[1],
+                                    mesh_draw.bounds.min[2],
+                                    0.0,
+                                ],
+                                bounds_max: [
+                                    mesh_draw.bounds.max[0],
+                                    mesh_draw.bounds.max[1],
+                                    mesh_draw.bounds.max[2],
+                                    0.0,
+                                ],
+                            },
+                            0,
+                        )?;
+                        cmd_list.set_fragment_bytes(
+                            &renderer.device,
+                            &self.pipeline,
+                            &[self.texture_streamer.texture_index(mesh_draw.texture_slot)],
+                            0,
+                        )?;
                         cmd_list.draw_offset(
                             &renderer.device,
                             mesh_draw.num_indices,
@@ -368,12 +475,48 @@ impl App for BindlessSample {
                             mesh_draw.vertex_buffer_offset,
                         );
                     }
+                    if let Some(skybox) = &self.skybox {
+                        skybox.draw(renderer, cmd_list, &self.view_proj)?;
+                    }
                     Ok(())
                 }),
         );
+        self.post_process
+            .tonemap(allocator, graph, self.hdr_image_handle);
+
+        if self.show_bounds {
+            for mesh_draw in &self.mesh_draws {
+                let bounds = mesh_draw
+                    .bounds
+                    .transformed(&Mat4::from_cols_array(&mesh_draw.transform));
+                self.debug_draw
+                    .aabb(bounds.min, bounds.max, [0.0, 1.0, 0.0, 1.0]);
+            }
+        }
+        self.debug_draw.draw(allocator, graph, self.view_proj);
         Ok(())
     }
 
+    fn draw_debug_ui(&mut self, context: &DebugUiContext) {
+        egui::Window::new("Tonemapping").show(context, |ui| {
+            ui.add(egui::Slider::new(&mut self.post_process.exposure, 0.0..=8.0).text("Exposure"));
+            egui::ComboBox::from_label("Operator")
+                .selected_text(self.post_process.operator.name())
+                .show_ui(ui, |ui| {
+                    for operator in TonemapOperator::ALL {
+                        ui.selectable_value(
+                            &mut self.post_process.operator,
+                            operator,
+                            operator.name(),
+                        );
+                    }
+                });
+        });
+        egui::Window::new("Debug Draw").show(context, |ui| {
+            ui.checkbox(&mut self.show_bounds, "Show Mesh Bounds");
+        });
+    }
+
     fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
         let depth_image = renderer
             .resource_manager
@@ -381,10 +524,24 @@ impl App for BindlessSample {
             .get_mut(self.depth_image_handle)
             .unwrap();
         depth_image.resize(&renderer.device, Size2D::new(width, height))?;
+        renderer
+            .resource_manager
+            .images
+            .get_mut(self.hdr_image_handle)
+            .unwrap()
+            .resize(&renderer.device, Size2D::new(width, height))?;
+        self.post_process
+            .set_input(renderer, self.hdr_image_handle)?;
         Ok(())
     }
 
     fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.texture_streamer.destroy(&renderer.device)?;
+        self.post_process.destroy(renderer);
+        self.debug_draw.destroy(renderer);
+        if let Some(skybox) = &mut self.skybox {
+            skybox.destroy(renderer);
+        }
         self.pipeline.destroy(&renderer.device);
         self.index_buffer.destroy(&renderer.device);
         self.ubo_buffer.destroy(&renderer.device);
@@ -392,7 +549,11 @@ impl App for BindlessSample {
     }
 }
 
-fn main() {
+fn view_proj(camera: &Camera, width: f32, height: f32) -> [f32; 16] {
+    (camera.projection(width, height) * camera.view()).to_cols_array()
+}
+
+fn main() -> ExitCode {
     let mut sdl = SdlContext::new(
         WINDOW_WIDTH,
         WINDOW_HEIGHT,
@@ -402,6 +563,14 @@ fn main() {
         },
     )
     .unwrap();
-    let mut cinder = Cinder::<BindlessSample>::new(&sdl.window).unwrap();
+    // Missing assets are reported instead of panicking, as they're not part of the repository
+    let mut cinder = match Cinder::<BindlessSample>::new(&sdl.window) {
+        Ok(cinder) => cinder,
+        Err(err) => {
+            eprintln!("Failed to start the bindless sample: {err:#}");
+            return ExitCode::FAILURE;
+        }
+    };
     cinder.run_game_loop(&mut sdl).unwrap();
+    ExitCode::SUCCESS
 }
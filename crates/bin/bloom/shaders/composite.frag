@@ -0,0 +1,23 @@
+#version 450
+
+layout (location = 0) in vec2 i_uv;
+
+layout (location = 0) out vec4 uFragColor;
+
+layout (set = 0, binding = 0) uniform sampler2D scene_texture;
+layout (set = 0, binding = 1) uniform sampler2D bloom_texture;
+
+// Must match `BLOOM_MIPS` in main.rs
+const int BLOOM_MIPS = 5;
+const float BLOOM_INTENSITY = 0.6;
+
+void main() {
+    vec3 bloom = vec3(0.0);
+    for (int level = 0; level < BLOOM_MIPS; ++level) {
+        bloom += textureLod(bloom_texture, i_uv, float(level)).rgb;
+    }
+
+    vec3 color = texture(scene_texture, i_uv).rgb + bloom / float(BLOOM_MIPS) * BLOOM_INTENSITY;
+    // Reinhard
+    uFragColor = vec4(color / (1.0 + color), 1.0);
+}
@@ -0,0 +1,29 @@
+#version 460
+
+layout (location = 0) out vec3 o_color;
+
+layout( push_constant ) uniform constants
+{
+    float time;
+    float aspect_ratio;
+} PushConstants;
+
+// Must match `QUAD_COUNT` in main.rs
+const uint QUAD_COUNT = 12;
+
+// Spinning quads on a ring, each built from four consecutive vertex indices
+void main() {
+    uint quad = gl_VertexIndex / 4;
+    uint corner = gl_VertexIndex % 4;
+    vec2 local = vec2(corner & 1, corner >> 1) * 2.0 - 1.0;
+
+    float angle = float(quad) / float(QUAD_COUNT) * 6.2831853;
+    float spin = PushConstants.time + angle;
+    mat2 rotation = mat2(cos(spin), sin(spin), -sin(spin), cos(spin));
+    vec2 position = vec2(cos(angle), sin(angle)) * 0.6 + rotation * local * 0.08;
+    position.x /= PushConstants.aspect_ratio;
+    gl_Position = vec4(position, 0.0, 1.0);
+
+    // Well above 1.0, so the quads bleed into their surroundings
+    o_color = (0.5 + 0.5 * cos(vec3(0.0, 2.0, 4.0) + angle)) * 4.0;
+}
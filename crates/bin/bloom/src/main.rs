@@ -0,0 +1,381 @@
+use anyhow::Result;
+use cinder::{
+    AddressMode, App, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData, Buffer,
+    BufferDescription, BufferUsage, Bump, Cinder, ClearValue, CommandList, Filter, Format,
+    GraphicsPipeline, GraphicsPipelineDescription, Image, ImageDescription, ImageRegion,
+    ImageUsage, InitContext, Layout, MipmapMode, RenderAttachmentDesc, RenderGraph, RenderPass,
+    RenderPassResource, Renderer, ResourceId, SampleCount, Sampler, SamplerDescription,
+    FULLSCREEN_VERTEX_SHADER,
+};
+use math::size::Size2D;
+use util::{SdlContext, WindowDescription};
+
+pub const WINDOW_WIDTH: u32 = 1280;
+pub const WINDOW_HEIGHT: u32 = 720;
+
+// Must match `QUAD_COUNT` in scene.vert
+const QUAD_COUNT: u32 = 12;
+// Must match `BLOOM_MIPS` in composite.frag
+const BLOOM_MIPS: u32 = 5;
+
+const HDR_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct SceneConstants {
+    time: f32,
+    aspect_ratio: f32,
+}
+
+fn quad_indices() -> Vec<u32> {
+    (0..QUAD_COUNT)
+        .flat_map(|quad| {
+            let base = quad * 4;
+            [base, base + 1, base + 2, base + 2, base + 1, base + 3]
+        })
+        .collect()
+}
+
+// The bloom chain starts at half resolution
+fn bloom_size(width: u32, height: u32) -> Size2D<u32> {
+    Size2D::new((width / 2).max(1), (height / 2).max(1))
+}
+
+pub struct BloomSample {
+    msaa_image_handle: ResourceId<Image>,
+    scene_image_handle: ResourceId<Image>,
+    bloom_image_handle: ResourceId<Image>,
+    scene_pipeline: GraphicsPipeline,
+    composite_pipeline: GraphicsPipeline,
+    composite_bind_group: BindGroup,
+    sampler: Sampler,
+    quad_index_buffer: Buffer,
+}
+
+impl BloomSample {
+    fn write_composite_bind_group(&self, renderer: &Renderer) -> Result<()> {
+        let infos = [self.scene_image_handle, self.bloom_image_handle]
+            .into_iter()
+            .enumerate()
+            .map(|(idx, handle)| {
+                let image = renderer.resource_manager.images.get(handle).unwrap();
+                BindGroupBindInfo {
+                    group: self.composite_bind_group,
+                    dst_binding: idx as u32,
+                    data: BindGroupWriteData::SampledImage(image.bind_info(
+                        &self.sampler,
+                        Layout::ShaderReadOnly,
+                        None,
+                    )),
+                }
+            })
+            .collect::<Vec<_>>();
+        renderer.device.write_bind_group(&infos)?;
+        Ok(())
+    }
+
+    // Resolves the multisampled scene, then downsamples it through the bloom mip chain
+    fn resolve_and_downsample(&self, renderer: &Renderer, cmd_list: &CommandList) {
+        let images = &renderer.resource_manager.images;
+        let msaa_image = images.get(self.msaa_image_handle).unwrap();
+        let scene_image = images.get(self.scene_image_handle).unwrap();
+        let bloom_image = images.get(self.bloom_image_handle).unwrap();
+        let device = &renderer.device;
+
+        cmd_list.transition_image(
+            device,
+            msaa_image,
+            Layout::ShaderReadOnly,
+            Layout::TransferSrc,
+        );
+        cmd_list.transition_image(device, scene_image, Layout::Undefined, Layout::TransferDst);
+        cmd_list.resolve_image(device, msaa_image, scene_image);
+        cmd_list.transition_image(
+            device,
+            scene_image,
+            Layout::TransferDst,
+            Layout::TransferSrc,
+        );
+
+        cmd_list.transition_image(device, bloom_image, Layout::Undefined, Layout::TransferDst);
+        cmd_list.blit_image(
+            device,
+            scene_image,
+            bloom_image,
+            ImageRegion::mip(scene_image, 0),
+            ImageRegion::mip(bloom_image, 0),
+            Filter::Linear,
+        );
+        cmd_list.transition_image(
+            device,
+            scene_image,
+            Layout::TransferSrc,
+            Layout::ShaderReadOnly,
+        );
+
+        for level in 1..BLOOM_MIPS {
+            cmd_list.transition_image_mip(
+                device,
+                bloom_image,
+                level - 1,
+                Layout::TransferDst,
+                Layout::TransferSrc,
+            );
+            cmd_list.blit_image(
+                device,
+                bloom_image,
+                bloom_image,
+                ImageRegion::mip(bloom_image, level - 1),
+                ImageRegion::mip(bloom_image, level),
+                Filter::Linear,
+            );
+            cmd_list.transition_image_mip(
+                device,
+                bloom_image,
+                level - 1,
+                Layout::TransferSrc,
+                Layout::ShaderReadOnly,
+            );
+        }
+        cmd_list.transition_image_mip(
+            device,
+            bloom_image,
+            BLOOM_MIPS - 1,
+            Layout::TransferDst,
+            Layout::ShaderReadOnly,
+        );
+    }
+}
+
+impl App for BloomSample {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create Images
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+        let surface_size = Size2D::new(surface_rect.width(), surface_rect.height());
+        let msaa_image = context.renderer.device.create_image(
+            surface_size,
+            ImageDescription {
+                name: Some("MSAA Scene"),
+                format: HDR_FORMAT,
+                usage: ImageUsage::ColorAttachment,
+                samples: SampleCount::X4,
+                ..Default::default()
+            },
+        )?;
+        let scene_image = context.renderer.device.create_image(
+            surface_size,
+            ImageDescription {
+                name: Some("Resolved Scene"),
+                format: HDR_FORMAT,
+                usage: ImageUsage::ColorAttachment,
+                ..Default::default()
+            },
+        )?;
+        let bloom_image = context.renderer.device.create_image(
+            bloom_size(surface_rect.width(), surface_rect.height()),
+            ImageDescription {
+                name: Some("Bloom Chain"),
+                format: HDR_FORMAT,
+                mip_levels: BLOOM_MIPS,
+                ..Default::default()
+            },
+        )?;
+        let sampler = context.renderer.device.create_sampler(SamplerDescription {
+            address_mode: AddressMode::ClampToEdge,
+            mipmap_mode: MipmapMode::Linear,
+            ..Default::default()
+        })?;
+
+        //
+        // Create Pipelines
+        //
+        let scene_vs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/scene.vert.spv"),
+            Default::default(),
+        )?;
+        let scene_fs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/scene.frag.spv"),
+            Default::default(),
+        )?;
+        let fullscreen_vs = context
+            .renderer
+            .device
+            .create_shader(FULLSCREEN_VERTEX_SHADER, Default::default())?;
+        let composite_fs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/composite.frag.spv"),
+            Default::default(),
+        )?;
+        let scene_pipeline = context.renderer.device.create_graphics_pipeline(
+            &scene_vs,
+            Some(&scene_fs),
+            GraphicsPipelineDescription {
+                name: Some("Scene Pipeline".to_owned()),
+                color_formats: vec![HDR_FORMAT],
+                samples: SampleCount::X4,
+                ..Default::default()
+            },
+        )?;
+        let composite_pipeline = context.renderer.device.create_graphics_pipeline(
+            &fullscreen_vs,
+            Some(&composite_fs),
+            GraphicsPipelineDescription {
+                name: Some("Composite Pipeline".to_owned()),
+                ..Default::default()
+            },
+        )?;
+        let composite_bind_group = BindGroup::new(
+            &context.renderer.device,
+            composite_pipeline.bind_group_data(0).unwrap(),
+        )?;
+
+        //
+        // Create Buffers
+        //
+        let quad_index_buffer = context.renderer.device.create_buffer_with_data(
+            &quad_indices(),
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+        //
+        // Cleanup
+        //
+        scene_vs.destroy(&context.renderer.device);
+        scene_fs.destroy(&context.renderer.device);
+        fullscreen_vs.destroy(&context.renderer.device);
+        composite_fs.destroy(&context.renderer.device);
+
+        let msaa_image_handle = context.renderer.resource_manager.insert_image(msaa_image);
+        let scene_image_handle = context.renderer.resource_manager.insert_image(scene_image);
+        let bloom_image_handle = context.renderer.resource_manager.insert_image(bloom_image);
+
+        let sample = Self {
+            msaa_image_handle,
+            scene_image_handle,
+            bloom_image_handle,
+            scene_pipeline,
+            composite_pipeline,
+            composite_bind_group,
+            sampler,
+            quad_index_buffer,
+        };
+        sample.write_composite_bind_group(context.renderer)?;
+        Ok(sample)
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(
+                    AttachmentType::Reference(self.msaa_image_handle),
+                    RenderAttachmentDesc::clear(ClearValue::Color {
+                        color: [0.0, 0.0, 0.0, 1.0],
+                    }),
+                )
+                .add_output(RenderPassResource::Image(self.msaa_image_handle))
+                .set_callback(allocator, |renderer, cmd_list| {
+                    let surface_rect = renderer.device.surface_rect();
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.scene_pipeline)?;
+                    cmd_list.bind_index_buffer(&renderer.device, &self.quad_index_buffer);
+                    cmd_list.set_vertex_bytes(
+                        &renderer.device,
+                        &self.scene_pipeline,
+                        &SceneConstants {
+                            time: renderer.time.elapsed_seconds(),
+                            aspect_ratio: surface_rect.width() as f32
+                                / surface_rect.height() as f32,
+                        },
+                        0,
+                    )?;
+                    cmd_list.draw_offset(&renderer.device, QUAD_COUNT * 6, 0, 0);
+
+                    Ok(())
+                }),
+        );
+
+        // No attachments, so the graph records the transfers outside of dynamic rendering
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_input(RenderPassResource::Image(self.msaa_image_handle))
+                .add_output(RenderPassResource::Image(self.scene_image_handle))
+                .add_output(RenderPassResource::Image(self.bloom_image_handle))
+                .set_callback(allocator, |renderer, cmd_list| {
+                    self.resolve_and_downsample(renderer, cmd_list);
+
+                    Ok(())
+                }),
+        );
+
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
+                .add_input(RenderPassResource::Image(self.scene_image_handle))
+                .add_input(RenderPassResource::Image(self.bloom_image_handle))
+                .add_output(RenderPassResource::SwapchainImage)
+                .set_callback(allocator, |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.composite_pipeline)?;
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.composite_pipeline,
+                        0,
+                        &[self.composite_bind_group],
+                    );
+                    cmd_list.draw_fullscreen_triangle(&renderer.device);
+
+                    Ok(())
+                }),
+        );
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        for handle in [self.msaa_image_handle, self.scene_image_handle] {
+            renderer
+                .resource_manager
+                .images
+                .get_mut(handle)
+                .unwrap()
+                .resize(&renderer.device, Size2D::new(width, height))?;
+        }
+        renderer
+            .resource_manager
+            .images
+            .get_mut(self.bloom_image_handle)
+            .unwrap()
+            .resize(&renderer.device, bloom_size(width, height))?;
+        self.write_composite_bind_group(renderer)?;
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.quad_index_buffer.destroy(&renderer.device);
+        self.sampler.destroy(&renderer.device);
+        self.scene_pipeline.destroy(&renderer.device);
+        self.composite_pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "bloom",
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let mut cinder = Cinder::<BloomSample>::new(&sdl.window).unwrap();
+    cinder.run_game_loop(&mut sdl).unwrap();
+}
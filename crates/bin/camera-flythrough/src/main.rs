@@ -0,0 +1,340 @@
+use anyhow::Result;
+use cinder::{
+    App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData,
+    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, Format, GraphicsPipeline,
+    GraphicsPipelineDescription, Image, ImageDescription, ImageUsage, InitContext, InputState,
+    Layout, RenderAttachmentDesc, RenderGraph, RenderPass, Renderer, ResourceId,
+};
+use geometry::{SurfaceMesh, Vertex};
+use math::{mat::Mat4, size::Size2D, vec::Vec3};
+use math_ext::Quat;
+use util::{SdlContext, WindowDescription};
+
+pub const WINDOW_WIDTH: u32 = 1280;
+pub const WINDOW_HEIGHT: u32 = 1280;
+
+// Time the camera takes to move from one keyframe to the next
+const SECONDS_PER_KEYFRAME: f32 = 3.0;
+
+include!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/gen/flythrough_shader_structs.rs"
+));
+
+#[rustfmt::skip]
+fn translate(pos: Vec3) -> Mat4 {
+    Mat4::from_data(
+        1.0, 0.0, 0.0, pos.x(),
+        0.0, 1.0, 0.0, pos.y(),
+        0.0, 0.0, 1.0, pos.z(),
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+fn lerp(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+    Vec3::new(
+        a.x() + (b.x() - a.x()) * t,
+        a.y() + (b.y() - a.y()) * t,
+        a.z() + (b.z() - a.z()) * t,
+    )
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Keyframe {
+    position: Vec3,
+    orientation: Quat,
+}
+
+impl Keyframe {
+    // Directions the camera looks at and considers up, before it is rotated by `orientation`
+    const FORWARD: [f32; 3] = [0.0, 0.0, -1.0];
+    const UP: [f32; 3] = [0.0, 1.0, 0.0];
+
+    // Camera on a circle of `radius` around the origin, looking at it from `height`. The
+    // orientation rotates `Keyframe::FORWARD` around the world up axis by `yaw`, then tilts it
+    // down and rolls it around its own axes
+    fn orbit(yaw: f32, radius: f32, height: f32, roll: f32) -> Self {
+        let pitch = -height.atan2(radius);
+        Self {
+            position: Vec3::new(radius * yaw.sin(), height, radius * yaw.cos()),
+            orientation: Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), yaw)
+                * Quat::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), pitch)
+                * Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), roll),
+        }
+    }
+
+    fn view(&self) -> Mat4 {
+        let [fx, fy, fz] = Self::FORWARD;
+        let [ux, uy, uz] = Self::UP;
+        camera::look_to(
+            self.position,
+            self.orientation.rotate(Vec3::new(fx, fy, fz)),
+            self.orientation.rotate(Vec3::new(ux, uy, uz)),
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct ShapeDraw {
+    position: Vec3,
+    vertex_buffer_offset: i32,
+    index_buffer_offset: u32,
+    num_indices: u32,
+}
+
+pub struct CameraFlythrough {
+    depth_image_handle: ResourceId<Image>,
+    pipeline: GraphicsPipeline,
+    bind_group: BindGroup,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    ubo_buffer: Buffer,
+    shape_draws: Vec<ShapeDraw>,
+    keyframes: Vec<Keyframe>,
+}
+
+impl CameraFlythrough {
+    // Camera at `time` seconds into the loop through every keyframe
+    fn camera_at(&self, time: f32) -> Keyframe {
+        let progress = time / SECONDS_PER_KEYFRAME;
+        let from = progress.floor() as usize % self.keyframes.len();
+        let to = (from + 1) % self.keyframes.len();
+        // Eases in and out of every keyframe, so the camera never changes direction abruptly
+        let t = progress.fract();
+        let t = t * t * (3.0 - 2.0 * t);
+
+        let (from, to) = (self.keyframes[from], self.keyframes[to]);
+        Keyframe {
+            position: lerp(from.position, to.position, t),
+            orientation: Quat::slerp(from.orientation, to.orientation, t),
+        }
+    }
+}
+
+impl App for CameraFlythrough {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create App Resources
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+        let depth_image = context.renderer.device.create_image(
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            ImageDescription {
+                format: Format::D32_SFLOAT,
+                usage: ImageUsage::Depth,
+                ..Default::default()
+            },
+        )?;
+
+        let vertex_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/flythrough.vert.spv"),
+            Default::default(),
+        )?;
+        let fragment_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/flythrough.frag.spv"),
+            Default::default(),
+        )?;
+        let pipeline = context.renderer.device.create_graphics_pipeline(
+            &vertex_shader,
+            Some(&fragment_shader),
+            GraphicsPipelineDescription {
+                depth_format: Some(Format::D32_SFLOAT),
+                ..Default::default()
+            },
+        )?;
+        let bind_group = BindGroup::new(
+            &context.renderer.device,
+            pipeline.bind_group_data(0).unwrap(),
+        )?;
+
+        let keyframes = vec![
+            Keyframe::orbit(0.0, 8.0, 3.0, 0.0),
+            Keyframe::orbit(std::f32::consts::FRAC_PI_2, 5.0, 1.0, 0.3),
+            Keyframe::orbit(std::f32::consts::PI, 9.0, 6.0, 0.0),
+            Keyframe::orbit(3.0 * std::f32::consts::FRAC_PI_2, 4.0, 0.5, -0.3),
+        ];
+
+        let ubo_buffer = context.renderer.device.create_buffer_with_data(
+            &[
+                keyframes[0].view(),
+                camera::new_infinite_perspective_proj(
+                    surface_rect.width() as f32 / surface_rect.height() as f32,
+                    30.0,
+                    0.01,
+                ),
+            ],
+            BufferDescription {
+                usage: BufferUsage::UNIFORM,
+                ..Default::default()
+            },
+        )?;
+        context
+            .renderer
+            .device
+            .write_bind_group(&[BindGroupBindInfo {
+                group: bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::Uniform(ubo_buffer.bind_info()),
+            }])?;
+
+        //
+        // Create Shapes
+        //
+        let shapes: [(Vec3, SurfaceMesh<Vertex>); 5] = [
+            (
+                Vec3::new(0.0, 0.5, 0.0),
+                SurfaceMesh::uv_sphere::<16, 32>(0.5),
+            ),
+            (
+                Vec3::new(2.0, 0.0, 0.0),
+                SurfaceMesh::cylinder::<32>(1.0, 0.5),
+            ),
+            (Vec3::new(-2.0, 0.0, 0.0), SurfaceMesh::cone::<32>(1.0, 0.5)),
+            (
+                Vec3::new(0.0, 0.5, 2.0),
+                SurfaceMesh::torus::<32, 16>(0.5, 0.2),
+            ),
+            (Vec3::new(0.0, 0.0, 0.0), SurfaceMesh::plane(6.0, 6.0, 4)),
+        ];
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut shape_draws = Vec::with_capacity(shapes.len());
+        for (position, shape) in shapes {
+            shape_draws.push(ShapeDraw {
+                position,
+                vertex_buffer_offset: vertices.len() as i32,
+                index_buffer_offset: indices.len() as u32,
+                num_indices: shape.indices.len() as u32,
+            });
+            vertices.extend(shape.vertices);
+            indices.extend(shape.indices);
+        }
+
+        let vertex_buffer = context.renderer.device.create_buffer_with_data(
+            &vertices,
+            BufferDescription {
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+        let index_buffer = context.renderer.device.create_buffer_with_data(
+            &indices,
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+
+        vertex_shader.destroy(&context.renderer.device);
+        fragment_shader.destroy(&context.renderer.device);
+
+        let depth_image_handle = context.renderer.resource_manager.insert_image(depth_image);
+
+        Ok(Self {
+            depth_image_handle,
+            pipeline,
+            bind_group,
+            vertex_buffer,
+            index_buffer,
+            ubo_buffer,
+            shape_draws,
+            keyframes,
+        })
+    }
+
+    fn update(&mut self, renderer: &mut Renderer, _input: &InputState) -> Result<()> {
+        let camera = self.camera_at(renderer.time.elapsed_seconds());
+        self.ubo_buffer.mem_copy(
+            util::offset_of!(FlythroughUniformBufferObject, view) as u64,
+            &[camera.view()],
+        )?;
+        Ok(())
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
+                .set_depth_attachment(
+                    AttachmentType::Reference(self.depth_image_handle),
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::DontCare)
+                        .layout(Layout::DepthAttachment),
+                )
+                .set_callback(allocator, |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipeline)?;
+                    cmd_list.bind_index_buffer(&renderer.device, &self.index_buffer);
+                    cmd_list.bind_vertex_buffer(&renderer.device, &self.vertex_buffer);
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.pipeline,
+                        0,
+                        &[self.bind_group],
+                    );
+                    for draw in &self.shape_draws {
+                        cmd_list.set_vertex_bytes(
+                            &renderer.device,
+                            &self.pipeline,
+                            &translate(draw.position),
+                            0,
+                        )?;
+                        cmd_list.draw_offset(
+                            &renderer.device,
+                            draw.num_indices,
+                            draw.index_buffer_offset,
+                            draw.vertex_buffer_offset,
+                        );
+                    }
+
+                    Ok(())
+                }),
+        );
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        let depth_image = renderer
+            .resource_manager
+            .images
+            .get_mut(self.depth_image_handle)
+            .unwrap();
+        depth_image.resize(&renderer.device, Size2D::new(width, height))?;
+        self.ubo_buffer.mem_copy(
+            util::offset_of!(FlythroughUniformBufferObject, proj) as u64,
+            &[camera::new_infinite_perspective_proj(
+                width as f32 / height as f32,
+                30.0,
+                0.01,
+            )],
+        )?;
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.index_buffer.destroy(&renderer.device);
+        self.vertex_buffer.destroy(&renderer.device);
+        self.ubo_buffer.destroy(&renderer.device);
+        self.pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "camera-flythrough",
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let mut cinder = Cinder::<CameraFlythrough>::new(&sdl.window).unwrap();
+    cinder.run_game_loop(&mut sdl).unwrap();
+}
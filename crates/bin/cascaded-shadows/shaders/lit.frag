@@ -0,0 +1,59 @@
+#version 460
+
+#define CASCADE_COUNT 4
+
+float AMBIENT_LIGHT_STRENGTH = 0.2;
+
+const vec3 CASCADE_COLORS[CASCADE_COUNT] = vec3[](
+    vec3(1.0, 0.4, 0.4),
+    vec3(0.4, 1.0, 0.4),
+    vec3(0.4, 0.4, 1.0),
+    vec3(1.0, 1.0, 0.4)
+);
+
+layout (location = 0) in vec3 i_normal;
+layout (location = 1) in float i_view_depth;
+layout (location = 2) flat in vec4 i_cascade_splits;
+layout (location = 3) flat in vec3 i_light_dir;
+layout (location = 4) flat in uint i_show_cascades;
+layout (location = 5) in vec4 i_light_space_pos[CASCADE_COUNT];
+
+layout (location = 0) out vec4 uFragColor;
+
+layout (set = 1, binding = 0) uniform sampler2DArray shadow_map;
+
+// Returns 1.0 when lit, 0.0 when in shadow
+float compute_light_factor(int cascade, vec3 normal, vec3 to_light)
+{
+   vec3 light_space_ndc = i_light_space_pos[cascade].xyz / i_light_space_pos[cascade].w;
+   vec2 shadow_map_coord = light_space_ndc.xy * 0.5 + 0.5;
+
+   // Depth is reversed, so the closest occluder has the largest value
+   float bias = max(0.002 * (1.0 - dot(normal, to_light)), 0.0005);
+   float closest_depth = texture(shadow_map, vec3(shadow_map_coord, float(cascade))).x;
+   return light_space_ndc.z + bias < closest_depth ? 0.0 : 1.0;
+}
+
+void main() {
+    vec3 norm = normalize(i_normal);
+    vec3 to_light = normalize(-i_light_dir);
+
+    // First cascade that contains the fragment, past the last one there are no shadows
+    int cascade = CASCADE_COUNT;
+    for (int i = 0; i < CASCADE_COUNT; ++i) {
+        if (i_view_depth < i_cascade_splits[i]) {
+            cascade = i;
+            break;
+        }
+    }
+    float light_factor = cascade < CASCADE_COUNT ? compute_light_factor(cascade, norm, to_light) : 1.0;
+
+    vec3 color = vec3(0.8);
+    if (i_show_cascades != 0 && cascade < CASCADE_COUNT) {
+        color *= CASCADE_COLORS[cascade];
+    }
+
+    vec3 ambient = AMBIENT_LIGHT_STRENGTH * color;
+    vec3 diffuse = max(dot(norm, to_light), 0.0) * color;
+    uFragColor = vec4(ambient + diffuse * light_factor, 1.0);
+}
@@ -0,0 +1,53 @@
+#version 460
+
+// Must match `CASCADE_COUNT` in main.rs
+#define CASCADE_COUNT 4
+
+layout(location = 0) in vec3 i_pos;
+layout(location = 1) in vec3 i_normal;
+layout(location = 2) in vec2 i_uv;
+
+layout (location = 0) out vec3 o_normal;
+layout (location = 1) out float o_view_depth;
+layout (location = 2) flat out vec4 o_cascade_splits;
+layout (location = 3) flat out vec3 o_light_dir;
+layout (location = 4) flat out uint o_show_cascades;
+layout (location = 5) out vec4 o_light_space_pos[CASCADE_COUNT];
+
+layout(set = 0, binding = 0) uniform CameraUniformBufferObject {
+    mat4 view;
+    mat4 proj;
+} ubo;
+
+layout(set = 0, binding = 1) uniform CascadeUniformBufferObject {
+    mat4 light_view_projs[CASCADE_COUNT];
+    vec4 cascade_splits;
+    vec4 light_dir;
+    uint show_cascades;
+} c_ubo;
+
+layout( push_constant ) uniform constants
+{
+    mat4 model;
+    vec4 normal_matrix[3];
+} PushConstants;
+
+void main() {
+    vec4 world_pos = PushConstants.model * vec4(i_pos, 1.0);
+    vec4 view_pos = ubo.view * world_pos;
+
+    o_normal = mat3(
+        PushConstants.normal_matrix[0].xyz,
+        PushConstants.normal_matrix[1].xyz,
+        PushConstants.normal_matrix[2].xyz
+    ) * i_normal;
+    o_view_depth = view_pos.z;
+    o_cascade_splits = c_ubo.cascade_splits;
+    o_light_dir = c_ubo.light_dir.xyz;
+    o_show_cascades = c_ubo.show_cascades;
+    for (int i = 0; i < CASCADE_COUNT; ++i) {
+        o_light_space_pos[i] = c_ubo.light_view_projs[i] * world_pos;
+    }
+
+    gl_Position = ubo.proj * view_pos;
+}
@@ -0,0 +1,23 @@
+#version 460
+
+// Must match `CASCADE_COUNT` in main.rs
+#define CASCADE_COUNT 4
+
+layout(location = 0) in vec3 i_pos;
+
+layout(set = 0, binding = 0) uniform CascadeUniformBufferObject {
+    mat4 light_view_projs[CASCADE_COUNT];
+    vec4 cascade_splits;
+    vec4 light_dir;
+    uint show_cascades;
+} c_ubo;
+
+layout( push_constant ) uniform constants
+{
+    mat4 model;
+    uint cascade;
+} PushConstants;
+
+void main() {
+    gl_Position = c_ubo.light_view_projs[PushConstants.cascade] * PushConstants.model * vec4(i_pos, 1.0);
+}
@@ -0,0 +1,521 @@
+use anyhow::Result;
+use camera::{input::InputMap, Camera, CameraAction, CameraDescription, ShadowCascades};
+use cinder::{
+    AddressMode, App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo,
+    BindGroupWriteData, Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue,
+    DebugUiContext, Format, GraphicsPipeline, GraphicsPipelineDescription, Image, ImageDescription,
+    ImageUsage, InitContext, InputState, Layout, MipmapMode, RenderAttachmentDesc, RenderGraph,
+    RenderPass, RenderPassResource, Renderer, ResourceId, Sampler, SamplerDescription,
+    VertexAttributeDescription, VertexBindingDesc, VertexDescription, VertexInputRate,
+};
+use egui_integration::egui;
+use geometry::{SurfaceMesh, Vertex};
+use math::{mat::Mat4, rect::Rect2D, size::Size2D, vec::Vec3};
+use math_ext::{Mat3Padded, Mat4Ext};
+use util::{SdlContext, WindowDescription};
+
+pub const WINDOW_WIDTH: u32 = 1280;
+pub const WINDOW_HEIGHT: u32 = 720;
+
+// Must match `CASCADE_COUNT` in the shaders
+const CASCADE_COUNT: usize = 4;
+const SHADOW_MAP_SIZE: u32 = 2048;
+const SHADOW_DISTANCE: f32 = 150.0;
+
+include!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/gen/shadow_shader_structs.rs"
+));
+include!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/gen/lit_shader_structs.rs"
+));
+
+#[rustfmt::skip]
+fn translate(pos: Vec3) -> Mat4 {
+    Mat4::from_data(
+        1.0, 0.0, 0.0, pos.x(),
+        0.0, 1.0, 0.0, pos.y(),
+        0.0, 0.0, 1.0, pos.z(),
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+// Must match the push constants in lit.vert
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ModelConstants {
+    model: [f32; 16],
+    normal_matrix: Mat3Padded,
+}
+
+impl ModelConstants {
+    fn new(model: &Mat4) -> Self {
+        Self {
+            model: model.to_cols_array(),
+            normal_matrix: model.normal_matrix(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ShapeDraw {
+    position: Vec3,
+    vertex_buffer_offset: i32,
+    index_buffer_offset: u32,
+    num_indices: u32,
+}
+
+pub struct CascadedShadowsSample {
+    depth_image_handle: ResourceId<Image>,
+    shadow_map_image_handle: ResourceId<Image>,
+    shadow_pipeline: GraphicsPipeline,
+    lit_pipeline: GraphicsPipeline,
+    shadow_bind_group: BindGroup,
+    lit_bind_group: BindGroup,
+    shadow_map_bind_group: BindGroup,
+    shadow_map_sampler: Sampler,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    camera_ubo_buffer: Buffer,
+    cascade_ubo_buffer: Buffer,
+    shape_draws: Vec<ShapeDraw>,
+    cascades: ShadowCascades<CASCADE_COUNT>,
+    light_dir: Vec3,
+    show_cascades: bool,
+    camera: Camera,
+    input_map: InputMap<CameraAction>,
+}
+
+impl App for CascadedShadowsSample {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create Images
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+        let depth_image = context.renderer.device.create_image(
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            ImageDescription {
+                format: Format::D32_SFLOAT,
+                usage: ImageUsage::Depth,
+                ..Default::default()
+            },
+        )?;
+        // One layer per cascade, each rendered by its own pass
+        let shadow_map_image = context.renderer.device.create_image(
+            Size2D::new(SHADOW_MAP_SIZE, SHADOW_MAP_SIZE),
+            ImageDescription {
+                name: Some("Shadow Cascades"),
+                format: Format::D32_SFLOAT,
+                usage: ImageUsage::DepthSampled,
+                array_layers: CASCADE_COUNT as u32,
+                ..Default::default()
+            },
+        )?;
+        let shadow_map_sampler = context.renderer.device.create_sampler(SamplerDescription {
+            address_mode: AddressMode::ClampToEdge,
+            mipmap_mode: MipmapMode::Nearest,
+            ..Default::default()
+        })?;
+
+        //
+        // Create Pipelines
+        //
+        let shadow_vs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/shadow.vert.spv"),
+            Default::default(),
+        )?;
+        let lit_vs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/lit.vert.spv"),
+            Default::default(),
+        )?;
+        let lit_fs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/lit.frag.spv"),
+            Default::default(),
+        )?;
+        // Only positions are read, but the vertex buffer is shared with the lit pipeline
+        let shadow_pipeline = context.renderer.device.create_graphics_pipeline(
+            &shadow_vs,
+            None,
+            GraphicsPipelineDescription {
+                name: Some("Shadow Pipeline".to_owned()),
+                color_formats: vec![],
+                depth_format: Some(Format::D32_SFLOAT),
+                vertex_desc: Some(VertexDescription {
+                    binding_desc: vec![VertexBindingDesc {
+                        binding: 0,
+                        stride: std::mem::size_of::<Vertex>() as u32,
+                        input_rate: VertexInputRate::VERTEX,
+                    }],
+                    attribute_desc: vec![VertexAttributeDescription {
+                        location: 0,
+                        binding: 0,
+                        format: Format::R32G32B32_SFLOAT.into(),
+                        offset: 0,
+                    }],
+                }),
+                ..Default::default()
+            },
+        )?;
+        let lit_pipeline = context.renderer.device.create_graphics_pipeline(
+            &lit_vs,
+            Some(&lit_fs),
+            GraphicsPipelineDescription {
+                name: Some("Lit Pipeline".to_owned()),
+                depth_format: Some(Format::D32_SFLOAT),
+                ..Default::default()
+            },
+        )?;
+
+        //
+        // Create Camera and Cascades, both updated every frame
+        //
+        let camera = Camera::new(
+            Vec3::new(-90.0, 6.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            CameraDescription {
+                movement_per_sec: 20.0,
+                ..Default::default()
+            },
+        );
+        let light_dir = Vec3::new(0.5, -1.0, 0.3).normalized();
+        let cascades = ShadowCascades::new(SHADOW_DISTANCE);
+
+        let camera_ubo_buffer = context.renderer.device.create_buffer(
+            std::mem::size_of::<LitCameraUniformBufferObject>() as u64,
+            BufferDescription {
+                usage: BufferUsage::UNIFORM,
+                ..Default::default()
+            },
+        )?;
+        let cascade_ubo_buffer = context.renderer.device.create_buffer(
+            std::mem::size_of::<LitCascadeUniformBufferObject>() as u64,
+            BufferDescription {
+                usage: BufferUsage::UNIFORM,
+                ..Default::default()
+            },
+        )?;
+
+        //
+        // Create Bind Groups
+        //
+        let shadow_bind_group = BindGroup::new(
+            &context.renderer.device,
+            shadow_pipeline.bind_group_data(0).unwrap(),
+        )?;
+        let lit_bind_group = BindGroup::new(
+            &context.renderer.device,
+            lit_pipeline.bind_group_data(0).unwrap(),
+        )?;
+        let shadow_map_bind_group = BindGroup::new(
+            &context.renderer.device,
+            lit_pipeline.bind_group_data(1).unwrap(),
+        )?;
+        context.renderer.device.write_bind_group(&[
+            BindGroupBindInfo {
+                group: shadow_bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::Uniform(cascade_ubo_buffer.bind_info()),
+            },
+            BindGroupBindInfo {
+                group: lit_bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::Uniform(camera_ubo_buffer.bind_info()),
+            },
+            BindGroupBindInfo {
+                group: lit_bind_group,
+                dst_binding: 1,
+                data: BindGroupWriteData::Uniform(cascade_ubo_buffer.bind_info()),
+            },
+            // Shadow passes leave each layer ready to be sampled
+            BindGroupBindInfo {
+                group: shadow_map_bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::SampledImage(shadow_map_image.bind_info(
+                    &shadow_map_sampler,
+                    Layout::ShaderReadOnly,
+                    None,
+                )),
+            },
+        ])?;
+
+        //
+        // Create Shapes
+        //
+        // Rows of shapes spread over the whole shadow distance, on top of a large ground plane
+        let mut shapes: Vec<(Vec3, SurfaceMesh<Vertex>)> = vec![(
+            Vec3::zero(),
+            SurfaceMesh::plane(SHADOW_DISTANCE * 1.5, SHADOW_DISTANCE * 1.5, 31),
+        )];
+        for x in -6..=6 {
+            for z in -3..=3 {
+                let shape = match (x + z).rem_euclid(3) {
+                    0 => SurfaceMesh::cylinder::<32>(4.0, 0.5),
+                    1 => SurfaceMesh::capsule::<32>(3.0, 1.0),
+                    _ => SurfaceMesh::uv_sphere::<16, 32>(1.5),
+                };
+                shapes.push((Vec3::new(x as f32 * 15.0, 2.0, z as f32 * 15.0), shape));
+            }
+        }
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut shape_draws = Vec::with_capacity(shapes.len());
+        for (position, shape) in shapes {
+            shape_draws.push(ShapeDraw {
+                position,
+                vertex_buffer_offset: vertices.len() as i32,
+                index_buffer_offset: indices.len() as u32,
+                num_indices: shape.indices.len() as u32,
+            });
+            vertices.extend(shape.vertices);
+            indices.extend(shape.indices);
+        }
+
+        let vertex_buffer = context.renderer.device.create_buffer_with_data(
+            &vertices,
+            BufferDescription {
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+        let index_buffer = context.renderer.device.create_buffer_with_data(
+            &indices,
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+
+        //
+        // Cleanup
+        //
+        shadow_vs.destroy(&context.renderer.device);
+        lit_vs.destroy(&context.renderer.device);
+        lit_fs.destroy(&context.renderer.device);
+
+        let depth_image_handle = context.renderer.resource_manager.insert_image(depth_image);
+        let shadow_map_image_handle = context
+            .renderer
+            .resource_manager
+            .insert_image(shadow_map_image);
+
+        Ok(Self {
+            depth_image_handle,
+            shadow_map_image_handle,
+            shadow_pipeline,
+            lit_pipeline,
+            shadow_bind_group,
+            lit_bind_group,
+            shadow_map_bind_group,
+            shadow_map_sampler,
+            vertex_buffer,
+            index_buffer,
+            camera_ubo_buffer,
+            cascade_ubo_buffer,
+            shape_draws,
+            cascades,
+            light_dir,
+            show_cascades: false,
+            camera,
+            input_map: CameraAction::default_input_map(),
+        })
+    }
+
+    fn update(&mut self, renderer: &mut Renderer, input: &InputState) -> Result<()> {
+        let surface_rect = renderer.device.surface_rect();
+        self.camera.update(
+            &self.input_map,
+            input,
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            renderer.last_dt(),
+        );
+        self.camera_ubo_buffer.mem_copy(
+            util::offset_of!(LitCameraUniformBufferObject, view) as u64,
+            &[
+                self.camera.view(),
+                self.camera
+                    .projection(surface_rect.width() as f32, surface_rect.height() as f32),
+            ],
+        )?;
+
+        self.cascades.update(
+            &self.camera,
+            surface_rect.width() as f32 / surface_rect.height() as f32,
+            self.light_dir,
+        );
+        self.cascade_ubo_buffer.mem_copy(
+            util::offset_of!(LitCascadeUniformBufferObject, light_view_projs) as u64,
+            &self.cascades.view_projs()[..],
+        )?;
+        self.cascade_ubo_buffer.mem_copy(
+            util::offset_of!(LitCascadeUniformBufferObject, cascade_splits) as u64,
+            &[*self.cascades.splits()],
+        )?;
+        self.cascade_ubo_buffer.mem_copy(
+            util::offset_of!(LitCascadeUniformBufferObject, light_dir) as u64,
+            &[[
+                self.light_dir.x(),
+                self.light_dir.y(),
+                self.light_dir.z(),
+                0.0,
+            ]],
+        )?;
+        self.cascade_ubo_buffer.mem_copy(
+            util::offset_of!(LitCascadeUniformBufferObject, show_cascades) as u64,
+            &[self.show_cascades as u32],
+        )?;
+        Ok(())
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        let this: &'a Self = self;
+        let shadow_rect = Rect2D::from_width_height(SHADOW_MAP_SIZE, SHADOW_MAP_SIZE);
+
+        for cascade in 0..CASCADE_COUNT {
+            graph.add_pass(
+                allocator,
+                RenderPass::new(allocator)
+                    .set_depth_attachment(
+                        AttachmentType::ReferenceLayer(
+                            this.shadow_map_image_handle,
+                            cascade as u32,
+                        ),
+                        RenderAttachmentDesc::clear(ClearValue::default_depth())
+                            .store_op(AttachmentStoreOp::Store)
+                            .layout(Layout::DepthAttachment),
+                    )
+                    .add_output(RenderPassResource::Image(this.shadow_map_image_handle))
+                    .with_render_area(shadow_rect)
+                    .set_callback(allocator, move |renderer, cmd_list| {
+                        // The graph binds the viewport to the surface, not the render area
+                        cmd_list.bind_viewport(&renderer.device, shadow_rect, false);
+                        cmd_list.bind_scissor(&renderer.device, shadow_rect);
+
+                        cmd_list.bind_graphics_pipeline(&renderer.device, &this.shadow_pipeline)?;
+                        cmd_list.bind_descriptor_sets(
+                            &renderer.device,
+                            &this.shadow_pipeline,
+                            0,
+                            &[this.shadow_bind_group],
+                        );
+                        cmd_list.bind_index_buffer(&renderer.device, &this.index_buffer);
+                        cmd_list.bind_vertex_buffer(&renderer.device, &this.vertex_buffer);
+                        for draw in &this.shape_draws {
+                            cmd_list.set_vertex_bytes(
+                                &renderer.device,
+                                &this.shadow_pipeline,
+                                &[ShadowConstants {
+                                    model: translate(draw.position).into(),
+                                    cascade: cascade as u32,
+                                }],
+                                0,
+                            )?;
+                            cmd_list.draw_offset(
+                                &renderer.device,
+                                draw.num_indices,
+                                draw.index_buffer_offset,
+                                draw.vertex_buffer_offset,
+                            );
+                        }
+
+                        Ok(())
+                    }),
+            );
+        }
+
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(
+                    AttachmentType::SwapchainImage,
+                    RenderAttachmentDesc::clear(ClearValue::Color {
+                        color: [0.5, 0.7, 0.9, 1.0],
+                    }),
+                )
+                .set_depth_attachment(
+                    AttachmentType::Reference(this.depth_image_handle),
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::DontCare)
+                        .layout(Layout::DepthAttachment),
+                )
+                .add_input(RenderPassResource::Image(this.shadow_map_image_handle))
+                .set_callback(allocator, |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &this.lit_pipeline)?;
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &this.lit_pipeline,
+                        0,
+                        &[this.lit_bind_group, this.shadow_map_bind_group],
+                    );
+                    cmd_list.bind_index_buffer(&renderer.device, &this.index_buffer);
+                    cmd_list.bind_vertex_buffer(&renderer.device, &this.vertex_buffer);
+                    for draw in &this.shape_draws {
+                        cmd_list.set_vertex_bytes(
+                            &renderer.device,
+                            &this.lit_pipeline,
+                            &ModelConstants::new(&translate(draw.position)),
+                            0,
+                        )?;
+                        cmd_list.draw_offset(
+                            &renderer.device,
+                            draw.num_indices,
+                            draw.index_buffer_offset,
+                            draw.vertex_buffer_offset,
+                        );
+                    }
+
+                    Ok(())
+                }),
+        );
+        Ok(())
+    }
+
+    fn draw_debug_ui(&mut self, context: &DebugUiContext) {
+        egui::Window::new("Shadow Cascades").show(context, |ui| {
+            ui.checkbox(&mut self.show_cascades, "Show Cascades");
+            ui.add(
+                egui::Slider::new(&mut self.cascades.split_lambda, 0.0..=1.0).text("Split Lambda"),
+            );
+        });
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        renderer
+            .resource_manager
+            .images
+            .get_mut(self.depth_image_handle)
+            .unwrap()
+            .resize(&renderer.device, Size2D::new(width, height))?;
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.index_buffer.destroy(&renderer.device);
+        self.vertex_buffer.destroy(&renderer.device);
+        self.camera_ubo_buffer.destroy(&renderer.device);
+        self.cascade_ubo_buffer.destroy(&renderer.device);
+        self.shadow_map_sampler.destroy(&renderer.device);
+        self.shadow_pipeline.destroy(&renderer.device);
+        self.lit_pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "cascaded-shadows",
+            capture_mouse: true,
+        },
+    )
+    .unwrap();
+    let mut cinder = Cinder::<CascadedShadowsSample>::new(&sdl.window).unwrap();
+    cinder.run_game_loop(&mut sdl).unwrap();
+}
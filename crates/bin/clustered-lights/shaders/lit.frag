@@ -0,0 +1,57 @@
+#version 460
+
+// Must match `TILE_SIZE` and `MAX_LIGHTS_PER_TILE` in main.rs
+#define TILE_SIZE 16
+#define MAX_LIGHTS_PER_TILE 63
+
+float AMBIENT_LIGHT_STRENGTH = 0.05;
+
+struct PointLight {
+    vec4 position_radius;
+    vec4 color;
+};
+
+layout (location = 0) in vec3 i_pos;
+layout (location = 1) in vec3 i_normal;
+
+layout (location = 0) out vec4 uFragColor;
+
+layout (set = 1, binding = 0) readonly buffer Lights {
+    PointLight lights[];
+};
+
+layout (set = 1, binding = 1) readonly buffer TileLights {
+    uint tile_lights[];
+};
+
+layout(push_constant) uniform constants
+{
+    layout (offset = 112) uint tiles_x;
+    uint show_heatmap;
+} PushConstants;
+
+void main() {
+    uvec2 tile = uvec2(gl_FragCoord.xy) / TILE_SIZE;
+    uint tile_offset = (tile.y * PushConstants.tiles_x + tile.x) * (MAX_LIGHTS_PER_TILE + 1);
+    uint light_count = tile_lights[tile_offset];
+
+    if (PushConstants.show_heatmap != 0) {
+        float heat = float(light_count) / float(MAX_LIGHTS_PER_TILE);
+        uFragColor = vec4(heat, 1.0 - abs(heat * 2.0 - 1.0), 1.0 - heat, 1.0);
+        return;
+    }
+
+    vec3 norm = normalize(i_normal);
+    vec3 color = vec3(0.8);
+    vec3 lighting = AMBIENT_LIGHT_STRENGTH * color;
+    for (uint i = 0; i < light_count; ++i) {
+        PointLight light = lights[tile_lights[tile_offset + 1 + i]];
+        vec3 to_light = light.position_radius.xyz - i_pos;
+        float distance = length(to_light);
+        float attenuation = clamp(1.0 - distance / light.position_radius.w, 0.0, 1.0);
+        attenuation *= attenuation;
+        lighting += max(dot(norm, to_light / distance), 0.0) * attenuation * light.color.rgb * color;
+    }
+
+    uFragColor = vec4(lighting, 1.0);
+}
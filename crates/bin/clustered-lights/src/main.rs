@@ -0,0 +1,605 @@
+use anyhow::Result;
+use camera::{input::InputMap, Camera, CameraAction, CameraDescription};
+use cinder::{
+    App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData,
+    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, ComputePipeline,
+    DebugUiContext, Format, GraphicsPipeline, GraphicsPipelineDescription, Image, ImageDescription,
+    ImageUsage, InitContext, InputState, Layout, RenderAttachmentDesc, RenderGraph, RenderPass,
+    Renderer, ResourceId, Shader, MAX_FRAMES_IN_FLIGHT,
+};
+use egui_integration::egui;
+use geometry::{SurfaceMesh, Vertex};
+use math::{mat::Mat4, size::Size2D, vec::Vec3};
+use math_ext::{Mat3Padded, Mat4Ext};
+use util::{SdlContext, WindowDescription};
+
+pub const WINDOW_WIDTH: u32 = 1280;
+pub const WINDOW_HEIGHT: u32 = 720;
+
+// Must match the defines in light_culling.comp and lit.frag
+const TILE_SIZE: u32 = 16;
+const MAX_LIGHTS_PER_TILE: u32 = 63;
+
+const LIGHT_COUNT: usize = 256;
+
+include!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/gen/lit_shader_structs.rs"
+));
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PointLight {
+    pub position_radius: [f32; 4],
+    pub color: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct CullingConstants {
+    screen_size: [u32; 2],
+    light_count: u32,
+}
+
+// Must match the vertex push constants in lit.vert
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ModelConstants {
+    model: [f32; 16],
+    normal_matrix: Mat3Padded,
+}
+
+impl ModelConstants {
+    fn new(model: &Mat4) -> Self {
+        Self {
+            model: model.to_cols_array(),
+            normal_matrix: model.normal_matrix(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct LitFragmentConstants {
+    tiles_x: u32,
+    show_heatmap: u32,
+}
+
+#[rustfmt::skip]
+fn translate(pos: Vec3) -> Mat4 {
+    Mat4::from_data(
+        1.0, 0.0, 0.0, pos.x(),
+        0.0, 1.0, 0.0, pos.y(),
+        0.0, 0.0, 1.0, pos.z(),
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+fn hue_to_rgb(hue: f32) -> [f32; 3] {
+    let channel = |offset: f32| {
+        let k = (hue * 6.0 + offset) % 6.0;
+        1.0 - (k.min(4.0 - k).clamp(0.0, 1.0))
+    };
+    [channel(5.0), channel(3.0), channel(1.0)]
+}
+
+// Lights orbit the origin in rings, each ring spinning in the opposite direction of the last
+fn point_lights(time: f32) -> Vec<PointLight> {
+    const LIGHTS_PER_RING: usize = 32;
+    (0..LIGHT_COUNT)
+        .map(|idx| {
+            let ring = idx / LIGHTS_PER_RING;
+            let direction = if ring % 2 == 0 { 1.0 } else { -1.0 };
+            let angle = (idx % LIGHTS_PER_RING) as f32 / LIGHTS_PER_RING as f32
+                * (2.0 * std::f32::consts::PI)
+                + direction * time * 0.2;
+            let distance = 3.0 + ring as f32 * 3.0;
+            let color = hue_to_rgb((idx as f32 * 0.618_034).fract());
+            PointLight {
+                position_radius: [
+                    angle.cos() * distance,
+                    0.75 + (ring % 3) as f32 * 0.5,
+                    angle.sin() * distance,
+                    3.0,
+                ],
+                color: [color[0], color[1], color[2], 1.0],
+            }
+        })
+        .collect()
+}
+
+// Divides the screen into `TILE_SIZE` pixel tiles, and builds the list of point lights that touch
+// each tile every frame with a light culling compute pass.
+// Each tile stores its light count followed by up to `MAX_LIGHTS_PER_TILE` light indices.
+pub struct LightGrid {
+    pipeline: ComputePipeline,
+    light_buffer: Buffer,
+    light_capacity: u32,
+    light_count: u32,
+    // Each frame in flight gets its own tile lists, so the compute queue never writes to a buffer
+    // that a previous frame may still be reading from
+    tile_buffers: Vec<Buffer>,
+    bind_groups: Vec<BindGroup>,
+    screen_size: [u32; 2],
+    tiles: [u32; 2],
+}
+
+impl LightGrid {
+    pub fn new(
+        renderer: &Renderer,
+        shader: &Shader,
+        camera_ubo_buffer: &Buffer,
+        lights: &[PointLight],
+        width: u32,
+        height: u32,
+    ) -> Result<Self> {
+        let pipeline = renderer
+            .device
+            .create_compute_pipeline(shader, Default::default())?;
+        let light_buffer = renderer.device.create_buffer_with_data(
+            lights,
+            BufferDescription {
+                usage: BufferUsage::STORAGE,
+                ..Default::default()
+            },
+        )?;
+        let mut bind_groups = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+        for _ in 0..MAX_FRAMES_IN_FLIGHT {
+            let bind_group =
+                BindGroup::new(&renderer.device, pipeline.bind_group_data(0).unwrap())?;
+            renderer.device.write_bind_group(&[
+                BindGroupBindInfo {
+                    group: bind_group,
+                    dst_binding: 0,
+                    data: BindGroupWriteData::Uniform(camera_ubo_buffer.bind_info()),
+                },
+                BindGroupBindInfo {
+                    group: bind_group,
+                    dst_binding: 1,
+                    data: BindGroupWriteData::Storage(light_buffer.bind_info()),
+                },
+            ])?;
+            bind_groups.push(bind_group);
+        }
+
+        let mut grid = Self {
+            pipeline,
+            light_buffer,
+            light_capacity: lights.len() as u32,
+            light_count: lights.len() as u32,
+            tile_buffers: Vec::with_capacity(MAX_FRAMES_IN_FLIGHT),
+            bind_groups,
+            screen_size: [0, 0],
+            tiles: [0, 0],
+        };
+        grid.create_tile_buffers(renderer, width, height)?;
+        Ok(grid)
+    }
+
+    fn create_tile_buffers(&mut self, renderer: &Renderer, width: u32, height: u32) -> Result<()> {
+        self.screen_size = [width, height];
+        self.tiles = [
+            (width + TILE_SIZE - 1) / TILE_SIZE,
+            (height + TILE_SIZE - 1) / TILE_SIZE,
+        ];
+        let tile_count = (self.tiles[0] * self.tiles[1]) as u64;
+        for bind_group in &self.bind_groups {
+            let tile_buffer = renderer.device.create_buffer(
+                tile_count * (MAX_LIGHTS_PER_TILE + 1) as u64 * std::mem::size_of::<u32>() as u64,
+                BufferDescription {
+                    usage: BufferUsage::STORAGE,
+                    ..Default::default()
+                },
+            )?;
+            renderer.device.write_bind_group(&[BindGroupBindInfo {
+                group: *bind_group,
+                dst_binding: 2,
+                data: BindGroupWriteData::Storage(tile_buffer.bind_info()),
+            }])?;
+            self.tile_buffers.push(tile_buffer);
+        }
+        Ok(())
+    }
+
+    // Bind groups reading the tile lists have to be rewritten afterwards
+    pub fn resize(&mut self, renderer: &Renderer, width: u32, height: u32) -> Result<()> {
+        renderer.device.wait_idle()?;
+        for buffer in self.tile_buffers.drain(..) {
+            buffer.destroy(&renderer.device);
+        }
+        self.create_tile_buffers(renderer, width, height)
+    }
+
+    pub fn set_lights(&mut self, lights: &[PointLight]) -> Result<()> {
+        debug_assert!(lights.len() as u32 <= self.light_capacity);
+        self.light_buffer.mem_copy(0, lights)?;
+        self.light_count = lights.len() as u32;
+        Ok(())
+    }
+
+    // Culls the lights on the compute queue, the graphics submission for this frame waits on it
+    pub fn cull(&self, renderer: &Renderer) -> Result<()> {
+        let frame = renderer.device.current_frame_in_flight();
+        let cmd_list = renderer
+            .command_queue
+            .get_compute_command_list(&renderer.device)?;
+        cmd_list.bind_compute_pipeline(&renderer.device, &self.pipeline);
+        cmd_list.bind_compute_descriptor_sets(
+            &renderer.device,
+            &self.pipeline,
+            0,
+            &[self.bind_groups[frame]],
+        );
+        cmd_list.set_compute_bytes(
+            &renderer.device,
+            &self.pipeline,
+            &CullingConstants {
+                screen_size: self.screen_size,
+                light_count: self.light_count,
+            },
+            0,
+        )?;
+        // One workgroup per tile
+        cmd_list.dispatch(&renderer.device, self.tiles[0], self.tiles[1], 1);
+        renderer
+            .command_queue
+            .submit_compute(&renderer.device, cmd_list)?;
+        Ok(())
+    }
+
+    pub fn light_buffer(&self) -> &Buffer {
+        &self.light_buffer
+    }
+
+    pub fn tile_buffer(&self, frame: usize) -> &Buffer {
+        &self.tile_buffers[frame]
+    }
+
+    pub fn tiles_x(&self) -> u32 {
+        self.tiles[0]
+    }
+
+    pub fn destroy(&self, renderer: &Renderer) {
+        for buffer in &self.tile_buffers {
+            buffer.destroy(&renderer.device);
+        }
+        self.light_buffer.destroy(&renderer.device);
+        self.pipeline.destroy(&renderer.device);
+    }
+}
+
+#[derive(Debug)]
+pub struct ShapeDraw {
+    position: Vec3,
+    vertex_buffer_offset: i32,
+    index_buffer_offset: u32,
+    num_indices: u32,
+}
+
+pub struct ClusteredLightsSample {
+    depth_image_handle: ResourceId<Image>,
+    lit_pipeline: GraphicsPipeline,
+    camera_bind_group: BindGroup,
+    light_bind_groups: Vec<BindGroup>,
+    light_grid: LightGrid,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    ubo_buffer: Buffer,
+    shape_draws: Vec<ShapeDraw>,
+    show_heatmap: bool,
+    camera: Camera,
+    input_map: InputMap<CameraAction>,
+}
+
+impl ClusteredLightsSample {
+    fn write_light_bind_groups(&self, renderer: &Renderer) -> Result<()> {
+        for (frame, bind_group) in self.light_bind_groups.iter().enumerate() {
+            renderer.device.write_bind_group(&[
+                BindGroupBindInfo {
+                    group: *bind_group,
+                    dst_binding: 0,
+                    data: BindGroupWriteData::Storage(self.light_grid.light_buffer().bind_info()),
+                },
+                BindGroupBindInfo {
+                    group: *bind_group,
+                    dst_binding: 1,
+                    data: BindGroupWriteData::Storage(
+                        self.light_grid.tile_buffer(frame).bind_info(),
+                    ),
+                },
+            ])?;
+        }
+        Ok(())
+    }
+}
+
+impl App for ClusteredLightsSample {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create Images
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+        let depth_image = context.renderer.device.create_image(
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            ImageDescription {
+                format: Format::D32_SFLOAT,
+                usage: ImageUsage::Depth,
+                ..Default::default()
+            },
+        )?;
+
+        //
+        // Create Pipelines
+        //
+        let culling_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/light_culling.comp.spv"),
+            Default::default(),
+        )?;
+        let lit_vs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/lit.vert.spv"),
+            Default::default(),
+        )?;
+        let lit_fs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/lit.frag.spv"),
+            Default::default(),
+        )?;
+        let lit_pipeline = context.renderer.device.create_graphics_pipeline(
+            &lit_vs,
+            Some(&lit_fs),
+            GraphicsPipelineDescription {
+                name: Some("Lit Pipeline".to_owned()),
+                depth_format: Some(Format::D32_SFLOAT),
+                ..Default::default()
+            },
+        )?;
+
+        //
+        // Create Camera and Lights
+        //
+        let camera = Camera::new(
+            Vec3::new(-30.0, 8.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            CameraDescription {
+                movement_per_sec: 10.0,
+                ..Default::default()
+            },
+        );
+        let ubo_buffer = context.renderer.device.create_buffer(
+            std::mem::size_of::<LitCameraUniformBufferObject>() as u64,
+            BufferDescription {
+                usage: BufferUsage::UNIFORM,
+                ..Default::default()
+            },
+        )?;
+        let light_grid = LightGrid::new(
+            context.renderer,
+            &culling_shader,
+            &ubo_buffer,
+            &point_lights(0.0),
+            surface_rect.width(),
+            surface_rect.height(),
+        )?;
+
+        //
+        // Create Bind Groups
+        //
+        let camera_bind_group = BindGroup::new(
+            &context.renderer.device,
+            lit_pipeline.bind_group_data(0).unwrap(),
+        )?;
+        context
+            .renderer
+            .device
+            .write_bind_group(&[BindGroupBindInfo {
+                group: camera_bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::Uniform(ubo_buffer.bind_info()),
+            }])?;
+        let light_bind_groups = (0..MAX_FRAMES_IN_FLIGHT)
+            .map(|_| {
+                BindGroup::new(
+                    &context.renderer.device,
+                    lit_pipeline.bind_group_data(1).unwrap(),
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        //
+        // Create Shapes
+        //
+        let mut shapes: Vec<(Vec3, SurfaceMesh<Vertex>)> =
+            vec![(Vec3::zero(), SurfaceMesh::plane(60.0, 60.0, 59))];
+        for x in -3..=3 {
+            for z in -3..=3 {
+                let shape = match (x + z).rem_euclid(2) {
+                    0 => SurfaceMesh::cylinder::<32>(3.0, 0.6),
+                    _ => SurfaceMesh::uv_sphere::<16, 32>(1.0),
+                };
+                shapes.push((Vec3::new(x as f32 * 6.0, 1.0, z as f32 * 6.0), shape));
+            }
+        }
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut shape_draws = Vec::with_capacity(shapes.len());
+        for (position, shape) in shapes {
+            shape_draws.push(ShapeDraw {
+                position,
+                vertex_buffer_offset: vertices.len() as i32,
+                index_buffer_offset: indices.len() as u32,
+                num_indices: shape.indices.len() as u32,
+            });
+            vertices.extend(shape.vertices);
+            indices.extend(shape.indices);
+        }
+
+        let vertex_buffer = context.renderer.device.create_buffer_with_data(
+            &vertices,
+            BufferDescription {
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+        let index_buffer = context.renderer.device.create_buffer_with_data(
+            &indices,
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+
+        //
+        // Cleanup
+        //
+        culling_shader.destroy(&context.renderer.device);
+        lit_vs.destroy(&context.renderer.device);
+        lit_fs.destroy(&context.renderer.device);
+
+        let depth_image_handle = context.renderer.resource_manager.insert_image(depth_image);
+
+        let sample = Self {
+            depth_image_handle,
+            lit_pipeline,
+            camera_bind_group,
+            light_bind_groups,
+            light_grid,
+            vertex_buffer,
+            index_buffer,
+            ubo_buffer,
+            shape_draws,
+            show_heatmap: false,
+            camera,
+            input_map: CameraAction::default_input_map(),
+        };
+        sample.write_light_bind_groups(context.renderer)?;
+        Ok(sample)
+    }
+
+    fn update(&mut self, renderer: &mut Renderer, input: &InputState) -> Result<()> {
+        let surface_rect = renderer.device.surface_rect();
+        self.camera.update(
+            &self.input_map,
+            input,
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            renderer.last_dt(),
+        );
+        self.ubo_buffer.mem_copy(
+            util::offset_of!(LitCameraUniformBufferObject, view) as u64,
+            &[
+                self.camera.view(),
+                self.camera
+                    .projection(surface_rect.width() as f32, surface_rect.height() as f32),
+            ],
+        )?;
+
+        self.light_grid
+            .set_lights(&point_lights(renderer.time.elapsed_seconds()))?;
+        self.light_grid.cull(renderer)?;
+        Ok(())
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(
+                    AttachmentType::SwapchainImage,
+                    RenderAttachmentDesc::clear(ClearValue::Color {
+                        color: [0.0, 0.0, 0.0, 1.0],
+                    }),
+                )
+                .set_depth_attachment(
+                    AttachmentType::Reference(self.depth_image_handle),
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::DontCare)
+                        .layout(Layout::DepthAttachment),
+                )
+                .set_callback(allocator, |renderer, cmd_list| {
+                    let frame = renderer.device.current_frame_in_flight();
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.lit_pipeline)?;
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.lit_pipeline,
+                        0,
+                        &[self.camera_bind_group, self.light_bind_groups[frame]],
+                    );
+                    cmd_list.set_fragment_bytes(
+                        &renderer.device,
+                        &self.lit_pipeline,
+                        &LitFragmentConstants {
+                            tiles_x: self.light_grid.tiles_x(),
+                            show_heatmap: self.show_heatmap as u32,
+                        },
+                        0,
+                    )?;
+                    cmd_list.bind_index_buffer(&renderer.device, &self.index_buffer);
+                    cmd_list.bind_vertex_buffer(&renderer.device, &self.vertex_buffer);
+                    for draw in &self.shape_draws {
+                        cmd_list.set_vertex_bytes(
+                            &renderer.device,
+                            &self.lit_pipeline,
+                            &ModelConstants::new(&translate(draw.position)),
+                            0,
+                        )?;
+                        cmd_list.draw_offset(
+                            &renderer.device,
+                            draw.num_indices,
+                            draw.index_buffer_offset,
+                            draw.vertex_buffer_offset,
+                        );
+                    }
+
+                    Ok(())
+                }),
+        );
+        Ok(())
+    }
+
+    fn draw_debug_ui(&mut self, context: &DebugUiContext) {
+        egui::Window::new("Light Grid").show(context, |ui| {
+            ui.label(format!(
+                "{LIGHT_COUNT} point lights, {TILE_SIZE}x{TILE_SIZE} tiles"
+            ));
+            ui.checkbox(&mut self.show_heatmap, "Show Light Heatmap");
+        });
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        renderer
+            .resource_manager
+            .images
+            .get_mut(self.depth_image_handle)
+            .unwrap()
+            .resize(&renderer.device, Size2D::new(width, height))?;
+        self.light_grid.resize(renderer, width, height)?;
+        self.write_light_bind_groups(renderer)?;
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.light_grid.destroy(renderer);
+        self.index_buffer.destroy(&renderer.device);
+        self.vertex_buffer.destroy(&renderer.device);
+        self.ubo_buffer.destroy(&renderer.device);
+        self.lit_pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "clustered-lights",
+            capture_mouse: true,
+        },
+    )
+    .unwrap();
+    let mut cinder = Cinder::<ClusteredLightsSample>::new(&sdl.window).unwrap();
+    cinder.run_game_loop(&mut sdl).unwrap();
+}
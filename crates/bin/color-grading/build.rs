@@ -0,0 +1,19 @@
+use std::path::PathBuf;
+
+use rust_shader_tools::{EnvVersion, OptimizationLevel, ShaderCompiler, ShaderStage};
+
+fn main() {
+    let shader_compiler = ShaderCompiler::new(
+        EnvVersion::Vulkan1_0,
+        OptimizationLevel::Zero,
+        Some(PathBuf::from("shaders")),
+    )
+    .expect("Could not create shader compiler");
+
+    shader_compiler
+        .compile_and_write_shader("shaders/scene.frag", ShaderStage::Fragment)
+        .expect("Could not compile shader");
+    shader_compiler
+        .compile_and_write_shader("shaders/tonemap.frag", ShaderStage::Fragment)
+        .expect("Could not compile shader");
+}
@@ -0,0 +1,21 @@
+#version 450
+
+layout (location = 0) in vec2 i_uv;
+
+layout (location = 0) out vec4 uFragColor;
+
+vec3 hue_to_rgb(float hue) {
+    vec3 rgb = abs(fract(hue + vec3(0.0, 2.0 / 3.0, 1.0 / 3.0)) * 6.0 - 3.0) - 1.0;
+    return clamp(rgb, 0.0, 1.0);
+}
+
+// Hue sweep from left to right, fading to white at the top and black at the bottom
+void main() {
+    vec3 color = hue_to_rgb(i_uv.x);
+    if (i_uv.y < 0.5) {
+        color = mix(vec3(1.0), color, i_uv.y * 2.0);
+    } else {
+        color = mix(color, vec3(0.0), i_uv.y * 2.0 - 1.0);
+    }
+    uFragColor = vec4(color, 1.0);
+}
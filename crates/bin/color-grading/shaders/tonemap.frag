@@ -0,0 +1,30 @@
+#version 450
+
+layout (location = 0) in vec2 i_uv;
+
+layout (location = 0) out vec4 uFragColor;
+
+// Sampled through an SRGB view, so the color is already linear
+layout (set = 0, binding = 0) uniform sampler2D scene_texture;
+layout (set = 0, binding = 1) uniform sampler3D color_lut;
+
+// Must match `LUT_SIZE` in main.rs
+const float LUT_SIZE = 16.0;
+
+// The swapchain is UNORM, so the output is encoded by hand
+vec3 linear_to_srgb(vec3 color) {
+    vec3 low = color * 12.92;
+    vec3 high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return mix(high, low, lessThanEqual(color, vec3(0.0031308)));
+}
+
+// Shows the ungraded scene on the left and the graded scene on the right
+void main() {
+    vec3 color = texture(scene_texture, i_uv).rgb;
+    if (i_uv.x > 0.5) {
+        // Remap so that texel centers line up with the LUT entries
+        vec3 lut_uv = color * ((LUT_SIZE - 1.0) / LUT_SIZE) + 0.5 / LUT_SIZE;
+        color = texture(color_lut, lut_uv).rgb;
+    }
+    uFragColor = vec4(linear_to_srgb(color), 1.0);
+}
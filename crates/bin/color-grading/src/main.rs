@@ -0,0 +1,276 @@
+use anyhow::Result;
+use cinder::{
+    AddressMode, App, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData, Bump,
+    Cinder, ClearValue, Format, GraphicsPipeline, GraphicsPipelineDescription, Image,
+    ImageDescription, ImageDimension, ImageUsage, ImageViewDescription, ImageViewId, InitContext,
+    Layout, RenderAttachmentDesc, RenderGraph, RenderPass, RenderPassResource, Renderer,
+    ResourceId, Sampler, SamplerDescription, FULLSCREEN_VERTEX_SHADER,
+};
+use math::size::Size2D;
+use util::{SdlContext, WindowDescription};
+
+pub const WINDOW_WIDTH: u32 = 1280;
+pub const WINDOW_HEIGHT: u32 = 720;
+
+// Entries per axis of the color LUT, must match `LUT_SIZE` in tonemap.frag
+const LUT_SIZE: u32 = 16;
+
+// The scene is rendered through an UNORM view, and read back through an SRGB view of the same
+// memory, which decodes it to linear color when sampled
+const SCENE_ATTACHMENT_VIEW: ImageViewDescription = ImageViewDescription {
+    format: Some(Format::R8G8B8A8_UNORM),
+    range: None,
+    view_type: None,
+};
+const SCENE_SAMPLED_VIEW: ImageViewDescription = ImageViewDescription {
+    format: Some(Format::R8G8B8A8_SRGB),
+    range: None,
+    view_type: None,
+};
+
+// Warm, high contrast grade, stored as an RGBA8 volume indexed by the linear input color
+fn color_lut() -> Vec<u8> {
+    let max = (LUT_SIZE - 1) as f32;
+    let contrast = |v: f32| v * v * (3.0 - 2.0 * v);
+    let mut bytes = Vec::with_capacity((LUT_SIZE * LUT_SIZE * LUT_SIZE * 4) as usize);
+    for b in 0..LUT_SIZE {
+        for g in 0..LUT_SIZE {
+            for r in 0..LUT_SIZE {
+                let graded = [
+                    contrast(r as f32 / max) * 1.1,
+                    contrast(g as f32 / max),
+                    contrast(b as f32 / max) * 0.8,
+                ];
+                bytes.extend(graded.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8));
+                bytes.push(255);
+            }
+        }
+    }
+    bytes
+}
+
+pub struct ColorGradingSample {
+    scene_image_handle: ResourceId<Image>,
+    // `SCENE_ATTACHMENT_VIEW` of the scene image, recreated on resize
+    scene_attachment_view: ImageViewId,
+    lut_image_handle: ResourceId<Image>,
+    scene_pipeline: GraphicsPipeline,
+    tonemap_pipeline: GraphicsPipeline,
+    tonemap_bind_group: BindGroup,
+    sampler: Sampler,
+}
+
+impl ColorGradingSample {
+    fn create_scene_views(&mut self, renderer: &Renderer) -> Result<()> {
+        let scene_image = renderer
+            .resource_manager
+            .images
+            .get(self.scene_image_handle)
+            .unwrap();
+        self.scene_attachment_view =
+            scene_image.create_view(&renderer.device, SCENE_ATTACHMENT_VIEW)?;
+        let linear_view = scene_image.create_view(&renderer.device, SCENE_SAMPLED_VIEW)?;
+        let lut_image = renderer
+            .resource_manager
+            .images
+            .get(self.lut_image_handle)
+            .unwrap();
+        renderer.device.write_bind_group(&[
+            BindGroupBindInfo {
+                group: self.tonemap_bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::SampledImage(scene_image.bind_info_view(
+                    &self.sampler,
+                    Layout::ShaderReadOnly,
+                    linear_view,
+                    None,
+                )),
+            },
+            BindGroupBindInfo {
+                group: self.tonemap_bind_group,
+                dst_binding: 1,
+                data: BindGroupWriteData::SampledImage(lut_image.bind_info(
+                    &self.sampler,
+                    Layout::ShaderReadOnly,
+                    None,
+                )),
+            },
+        ])?;
+        Ok(())
+    }
+}
+
+impl App for ColorGradingSample {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create Images
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+        let scene_image = context.renderer.device.create_image(
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            ImageDescription {
+                name: Some("Scene"),
+                format: Format::R8G8B8A8_UNORM,
+                usage: ImageUsage::ColorAttachment,
+                mutable_format: true,
+                ..Default::default()
+            },
+        )?;
+        let lut_image = context.renderer.device.create_image_with_data_immediate(
+            Size2D::new(LUT_SIZE, LUT_SIZE),
+            &color_lut(),
+            &context.renderer.command_queue,
+            ImageDescription {
+                name: Some("Color LUT"),
+                dimension: ImageDimension::D3,
+                depth: Some(LUT_SIZE),
+                ..Default::default()
+            },
+        )?;
+        let sampler = context.renderer.device.create_sampler(SamplerDescription {
+            address_mode: AddressMode::ClampToEdge,
+            ..Default::default()
+        })?;
+
+        //
+        // Create Pipelines
+        //
+        let fullscreen_vs = context
+            .renderer
+            .device
+            .create_shader(FULLSCREEN_VERTEX_SHADER, Default::default())?;
+        let scene_fs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/scene.frag.spv"),
+            Default::default(),
+        )?;
+        let tonemap_fs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/tonemap.frag.spv"),
+            Default::default(),
+        )?;
+        let scene_pipeline = context.renderer.device.create_graphics_pipeline(
+            &fullscreen_vs,
+            Some(&scene_fs),
+            GraphicsPipelineDescription {
+                name: Some("Scene Pipeline".to_owned()),
+                color_formats: vec![Format::R8G8B8A8_UNORM],
+                ..Default::default()
+            },
+        )?;
+        let tonemap_pipeline = context.renderer.device.create_graphics_pipeline(
+            &fullscreen_vs,
+            Some(&tonemap_fs),
+            GraphicsPipelineDescription {
+                name: Some("Tonemap Pipeline".to_owned()),
+                ..Default::default()
+            },
+        )?;
+        let tonemap_bind_group = BindGroup::new(
+            &context.renderer.device,
+            tonemap_pipeline.bind_group_data(0).unwrap(),
+        )?;
+
+        //
+        // Cleanup
+        //
+        fullscreen_vs.destroy(&context.renderer.device);
+        scene_fs.destroy(&context.renderer.device);
+        tonemap_fs.destroy(&context.renderer.device);
+
+        let scene_attachment_view =
+            scene_image.create_view(&context.renderer.device, SCENE_ATTACHMENT_VIEW)?;
+        let scene_image_handle = context.renderer.resource_manager.insert_image(scene_image);
+        let lut_image_handle = context.renderer.resource_manager.insert_image(lut_image);
+
+        let mut sample = Self {
+            scene_image_handle,
+            scene_attachment_view,
+            lut_image_handle,
+            scene_pipeline,
+            tonemap_pipeline,
+            tonemap_bind_group,
+            sampler,
+        };
+        sample.create_scene_views(context.renderer)?;
+        Ok(sample)
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(
+                    AttachmentType::ReferenceView(
+                        self.scene_image_handle,
+                        self.scene_attachment_view,
+                    ),
+                    RenderAttachmentDesc::clear(ClearValue::Color {
+                        color: [0.0, 0.0, 0.0, 1.0],
+                    }),
+                )
+                .add_output(RenderPassResource::Image(self.scene_image_handle))
+                .set_callback(allocator, |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.scene_pipeline)?;
+                    cmd_list.draw_fullscreen_triangle(&renderer.device);
+
+                    Ok(())
+                }),
+        );
+
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
+                .add_input(RenderPassResource::Image(self.scene_image_handle))
+                .add_output(RenderPassResource::SwapchainImage)
+                .set_callback(allocator, |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.tonemap_pipeline)?;
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.tonemap_pipeline,
+                        0,
+                        &[self.tonemap_bind_group],
+                    );
+                    cmd_list.draw_fullscreen_triangle(&renderer.device);
+
+                    Ok(())
+                }),
+        );
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        renderer
+            .resource_manager
+            .images
+            .get_mut(self.scene_image_handle)
+            .unwrap()
+            .resize(&renderer.device, Size2D::new(width, height))?;
+        self.create_scene_views(renderer)?;
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.sampler.destroy(&renderer.device);
+        self.scene_pipeline.destroy(&renderer.device);
+        self.tonemap_pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "color-grading",
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let mut cinder = Cinder::<ColorGradingSample>::new(&sdl.window).unwrap();
+    cinder.run_game_loop(&mut sdl).unwrap();
+}
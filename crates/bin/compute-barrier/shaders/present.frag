@@ -0,0 +1,11 @@
+#version 450
+
+layout (location = 0) in vec2 i_uv;
+
+layout (location = 0) out vec4 uFragColor;
+
+layout (set = 0, binding = 0) uniform sampler2D compute_texture;
+
+void main() {
+    uFragColor = texture(compute_texture, i_uv);
+}
@@ -0,0 +1,302 @@
+use anyhow::Result;
+use cinder::{
+    Access, AddressMode, App, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData,
+    Bump, Cinder, ComputePipeline, DeviceDescription, Format, GraphicsPipeline,
+    GraphicsPipelineDescription, Image, ImageBarrier, ImageDescription, ImageUsage, InitContext,
+    Layout, PipelineStage, RenderGraph, RenderPass, RenderPassResource, Renderer, ResourceId,
+    Sampler, SamplerDescription, ValidationConfig, FULLSCREEN_VERTEX_SHADER,
+};
+use math::size::Size2D;
+use util::{SdlContext, WindowDescription};
+
+pub const WINDOW_WIDTH: u32 = 1280;
+pub const WINDOW_HEIGHT: u32 = 720;
+
+// Must match `local_size_x`/`local_size_y` in plasma.comp
+const WORKGROUP_SIZE: u32 = 8;
+// Dispatches accumulated every frame, each one a bit further back in time, leaving a trail
+const TRAIL_LENGTH: u32 = 6;
+const TRAIL_SPACING: f32 = 0.08;
+const BACKGROUND_COLOR: [f32; 4] = [0.02, 0.02, 0.05, 1.0];
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct PlasmaConstants {
+    time: f32,
+    weight: f32,
+}
+
+pub struct ComputeBarrierSample {
+    compute_image_handle: ResourceId<Image>,
+    compute_pipeline: ComputePipeline,
+    compute_bind_group: BindGroup,
+    present_pipeline: GraphicsPipeline,
+    present_bind_group: BindGroup,
+    sampler: Sampler,
+}
+
+impl ComputeBarrierSample {
+    fn write_bind_groups(&self, renderer: &Renderer) -> Result<()> {
+        let image = renderer
+            .resource_manager
+            .images
+            .get(self.compute_image_handle)
+            .unwrap();
+        renderer.device.write_bind_group(&[
+            BindGroupBindInfo {
+                group: self.compute_bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::StorageImage(image.storage_bind_info(None)),
+            },
+            BindGroupBindInfo {
+                group: self.present_bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::SampledImage(image.bind_info(
+                    &self.sampler,
+                    Layout::ShaderReadOnly,
+                    None,
+                )),
+            },
+        ])?;
+        Ok(())
+    }
+}
+
+impl App for ComputeBarrierSample {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create Images
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+        let compute_image = context.renderer.device.create_image(
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            ImageDescription {
+                name: Some("Compute Image"),
+                format: Format::R8G8B8A8_UNORM,
+                usage: ImageUsage::StorageTexture,
+                ..Default::default()
+            },
+        )?;
+        let sampler = context.renderer.device.create_sampler(SamplerDescription {
+            address_mode: AddressMode::ClampToEdge,
+            ..Default::default()
+        })?;
+
+        //
+        // Create Pipelines
+        //
+        let compute_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/plasma.comp.spv"),
+            Default::default(),
+        )?;
+        let fullscreen_vs = context
+            .renderer
+            .device
+            .create_shader(FULLSCREEN_VERTEX_SHADER, Default::default())?;
+        let present_fs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/present.frag.spv"),
+            Default::default(),
+        )?;
+        let compute_pipeline = context
+            .renderer
+            .device
+            .create_compute_pipeline(&compute_shader, Default::default())?;
+        let present_pipeline = context.renderer.device.create_graphics_pipeline(
+            &fullscreen_vs,
+            Some(&present_fs),
+            GraphicsPipelineDescription {
+                name: Some("Present Pipeline".to_owned()),
+                ..Default::default()
+            },
+        )?;
+        let compute_bind_group = BindGroup::new(
+            &context.renderer.device,
+            compute_pipeline.bind_group_data(0).unwrap(),
+        )?;
+        let present_bind_group = BindGroup::new(
+            &context.renderer.device,
+            present_pipeline.bind_group_data(0).unwrap(),
+        )?;
+
+        //
+        // Cleanup
+        //
+        compute_shader.destroy(&context.renderer.device);
+        fullscreen_vs.destroy(&context.renderer.device);
+        present_fs.destroy(&context.renderer.device);
+
+        let compute_image_handle = context
+            .renderer
+            .resource_manager
+            .insert_image(compute_image);
+
+        let sample = Self {
+            compute_image_handle,
+            compute_pipeline,
+            compute_bind_group,
+            present_pipeline,
+            present_bind_group,
+            sampler,
+        };
+        sample.write_bind_groups(context.renderer)?;
+        Ok(sample)
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        // No attachments, so the graph records this pass outside of dynamic rendering
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_output(RenderPassResource::Image(self.compute_image_handle))
+                .set_callback(allocator, |renderer, cmd_list| {
+                    let image = renderer
+                        .resource_manager
+                        .images
+                        .get(self.compute_image_handle)
+                        .unwrap();
+
+                    // The accumulation starts over from the background every frame. The clear
+                    // waits on last frame's fragment reads
+                    cmd_list.clear_color_image(&renderer.device, image, BACKGROUND_COLOR);
+                    cmd_list.pipeline_barrier(
+                        &renderer.device,
+                        &[],
+                        &[
+                            ImageBarrier::new(image, Layout::TransferDst, Layout::General)
+                                .with_access(
+                                    Access::TRANSFER_WRITE,
+                                    Access::SHADER_READ | Access::SHADER_WRITE,
+                                ),
+                        ],
+                        PipelineStage::TRANSFER,
+                        PipelineStage::COMPUTE_SHADER,
+                    );
+
+                    cmd_list.bind_compute_pipeline(&renderer.device, &self.compute_pipeline);
+                    cmd_list.bind_compute_descriptor_sets(
+                        &renderer.device,
+                        &self.compute_pipeline,
+                        0,
+                        &[self.compute_bind_group],
+                    );
+                    let time = renderer.time.elapsed_seconds();
+                    let size = image.size;
+                    for step in 0..TRAIL_LENGTH {
+                        // Each dispatch reads what the previous one wrote
+                        if step > 0 {
+                            cmd_list.pipeline_barrier(
+                                &renderer.device,
+                                &[],
+                                &[ImageBarrier::new(image, Layout::General, Layout::General)
+                                    .with_access(
+                                        Access::SHADER_WRITE,
+                                        Access::SHADER_READ | Access::SHADER_WRITE,
+                                    )],
+                                PipelineStage::COMPUTE_SHADER,
+                                PipelineStage::COMPUTE_SHADER,
+                            );
+                        }
+                        cmd_list.set_compute_bytes(
+                            &renderer.device,
+                            &self.compute_pipeline,
+                            &PlasmaConstants {
+                                time: time - step as f32 * TRAIL_SPACING,
+                                // Older steps fade out
+                                weight: 1.0 - step as f32 / TRAIL_LENGTH as f32,
+                            },
+                            0,
+                        )?;
+                        cmd_list.dispatch(
+                            &renderer.device,
+                            (size.width() + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+                            (size.height() + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+                            1,
+                        );
+                    }
+
+                    // Compute writes must be visible to the fragment shader sampling the image
+                    cmd_list.pipeline_barrier(
+                        &renderer.device,
+                        &[],
+                        &[
+                            ImageBarrier::new(image, Layout::General, Layout::ShaderReadOnly)
+                                .with_access(Access::SHADER_WRITE, Access::SHADER_READ),
+                        ],
+                        PipelineStage::COMPUTE_SHADER,
+                        PipelineStage::FRAGMENT_SHADER,
+                    );
+
+                    Ok(())
+                }),
+        );
+
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
+                .add_input(RenderPassResource::Image(self.compute_image_handle))
+                .add_output(RenderPassResource::SwapchainImage)
+                .set_callback(allocator, |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.present_pipeline)?;
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.present_pipeline,
+                        0,
+                        &[self.present_bind_group],
+                    );
+                    cmd_list.draw_fullscreen_triangle(&renderer.device);
+
+                    Ok(())
+                }),
+        );
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        renderer
+            .resource_manager
+            .images
+            .get_mut(self.compute_image_handle)
+            .unwrap()
+            .resize(&renderer.device, Size2D::new(width, height))?;
+        self.write_bind_groups(renderer)?;
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.sampler.destroy(&renderer.device);
+        self.compute_pipeline.destroy(&renderer.device);
+        self.present_pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "compute-barrier",
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    // Synchronization validation reports any hazard the explicit barriers miss
+    let mut cinder = Cinder::<ComputeBarrierSample>::with_description(
+        &sdl.window,
+        DeviceDescription {
+            validation: ValidationConfig {
+                enabled: true,
+                synchronization: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    cinder.run_game_loop(&mut sdl).unwrap();
+}
@@ -157,7 +157,7 @@ impl App for DebugSample {
             RenderPass::new(allocator)
                 .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
                 .set_callback(allocator, |renderer, cmd_list| {
-                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipeline);
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipeline)?;
                     cmd_list.bind_index_buffer(&renderer.device, &self.index_buffer);
                     cmd_list.bind_vertex_buffer(&renderer.device, &self.vertex_buffer);
                     cmd_list.bind_descriptor_sets(
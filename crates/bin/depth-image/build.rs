@@ -23,16 +23,7 @@ fn main() {
         false,
     );
 
-    shader_compiler
-        .compile_and_write_shader("shaders/depth_texture.vert", ShaderStage::Vertex)
-        .expect("Could not compile shader");
     shader_compiler
         .compile_and_write_shader("shaders/depth_texture.frag", ShaderStage::Fragment)
         .expect("Could not compile shader");
-    rust_shader_tools::write_shader_structs(
-        &std::fs::read("./shaders/spv/depth_texture.vert.spv").unwrap(),
-        "depth_texture",
-        PathBuf::from("gen").join("depth_texture_shader_structs.rs"),
-        false,
-    );
 }
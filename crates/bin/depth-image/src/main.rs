@@ -1,12 +1,12 @@
 use anyhow::Result;
 use cinder::{
-    App, AttachmentLoadOp, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo,
-    BindGroupWriteData, Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, Format,
-    GraphicsPipeline, GraphicsPipelineDescription, Image, ImageDescription, ImageUsage,
-    InitContext, Layout, RenderAttachmentDesc, RenderGraph, RenderPass, RenderPassResource,
-    Renderer, ResourceId, Sampler,
+    App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData,
+    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, GraphicsPipeline,
+    GraphicsPipelineDescription, Image, ImageDescription, ImageUsage, InitContext, InputState,
+    Layout, RenderAttachmentDesc, RenderGraph, RenderPass, RenderPassResource, Renderer,
+    ResourceId, Sampler, FULLSCREEN_VERTEX_SHADER,
 };
-use math::{mat::Mat4, size::Size2D, vec::Vec3};
+use math::{mat::Mat4, point::Point2D, rect::Rect2D, size::Size2D, vec::Vec3};
 use util::{SdlContext, WindowDescription};
 
 pub const WINDOW_WIDTH: u32 = 1280;
@@ -16,10 +16,6 @@ include!(concat!(
     env!("CARGO_MANIFEST_DIR"),
     "/gen/depth_mesh_shader_structs.rs"
 ));
-include!(concat!(
-    env!("CARGO_MANIFEST_DIR"),
-    "/gen/depth_texture_shader_structs.rs"
-));
 
 pub struct DepthImageSample {
     depth_image_handle: ResourceId<Image>,
@@ -30,9 +26,22 @@ pub struct DepthImageSample {
     cube_vertex_buffer: Buffer,
     cube_index_buffer: Buffer,
     ubo_buffer: Buffer,
-    quad_vertex_buffer: Buffer,
-    quad_index_buffer: Buffer,
     sampler: Sampler,
+    surface_size: Size2D<u32>,
+}
+
+impl DepthImageSample {
+    // Bottom-left corner of the window, where the depth image is drawn
+    fn overlay_rect(&self) -> Rect2D<i32, u32> {
+        let size = Size2D::new(
+            self.surface_size.width() * 3 / 8,
+            self.surface_size.height() * 3 / 8,
+        );
+        Rect2D::from_offset_and_size(
+            Point2D::new(0, (self.surface_size.height() - size.height()) as i32),
+            size,
+        )
+    }
 }
 
 impl App for DepthImageSample {
@@ -41,10 +50,12 @@ impl App for DepthImageSample {
         // Create App Resources
         //
         let surface_rect = context.renderer.device.surface_rect();
+        // Not every GPU supports D32_SFLOAT
+        let depth_format = context.renderer.device.preferred_depth_format(false);
         let depth_image = context.renderer.device.create_image(
             Size2D::new(surface_rect.width(), surface_rect.height()),
             ImageDescription {
-                format: Format::D32_SFLOAT,
+                format: depth_format,
                 usage: ImageUsage::DepthSampled,
                 ..Default::default()
             },
@@ -68,7 +79,7 @@ impl App for DepthImageSample {
             &mesh_vertex_shader,
             Some(&mesh_fragment_shader),
             GraphicsPipelineDescription {
-                depth_format: Some(Format::D32_SFLOAT),
+                depth_format: Some(depth_format),
                 ..Default::default()
             },
         )?;
@@ -77,10 +88,10 @@ impl App for DepthImageSample {
             mesh_pipeline.bind_group_data(0).unwrap(),
         )?;
 
-        let texture_vertex_shader = context.renderer.device.create_shader(
-            include_bytes!("../shaders/spv/depth_texture.vert.spv"),
-            Default::default(),
-        )?;
+        let texture_vertex_shader = context
+            .renderer
+            .device
+            .create_shader(FULLSCREEN_VERTEX_SHADER, Default::default())?;
         let texture_fragment_shader = context.renderer.device.create_shader(
             include_bytes!("../shaders/spv/depth_texture.frag.spv"),
             Default::default(),
@@ -252,37 +263,6 @@ impl App for DepthImageSample {
                     data: BindGroupWriteData::Uniform(ubo_buffer.bind_info()),
                 }])?;
         }
-        let quad_vertex_buffer = context.renderer.device.create_buffer_with_data(
-            &[
-                DepthTextureVertex {
-                    i_pos: [-1.0, -1.0],
-                    i_uv: [0.0, 1.0],
-                },
-                DepthTextureVertex {
-                    i_pos: [-0.25, -1.0],
-                    i_uv: [1.0, 1.0],
-                },
-                DepthTextureVertex {
-                    i_pos: [-0.25, -0.25],
-                    i_uv: [1.0, 0.0],
-                },
-                DepthTextureVertex {
-                    i_pos: [-1.0, -0.25],
-                    i_uv: [0.0, 0.0],
-                },
-            ],
-            BufferDescription {
-                usage: BufferUsage::VERTEX,
-                ..Default::default()
-            },
-        )?;
-        let quad_index_buffer = context.renderer.device.create_buffer_with_data(
-            &[0, 1, 2, 2, 3, 0],
-            BufferDescription {
-                usage: BufferUsage::INDEX,
-                ..Default::default()
-            },
-        )?;
 
         let sampler = context.renderer.device.create_sampler(Default::default())?;
         context
@@ -317,15 +297,13 @@ impl App for DepthImageSample {
             cube_vertex_buffer,
             cube_index_buffer,
             ubo_buffer,
-            quad_vertex_buffer,
-            quad_index_buffer,
             sampler,
+            surface_size: Size2D::new(surface_rect.width(), surface_rect.height()),
         })
     }
 
-    fn update(&mut self, renderer: &mut Renderer) -> Result<()> {
-        let scale =
-            (renderer.init_time().elapsed().as_secs_f32() / 5.0) * (2.0 * std::f32::consts::PI);
+    fn update(&mut self, renderer: &mut Renderer, _input: &InputState) -> Result<()> {
+        let scale = (renderer.time.elapsed_seconds() / 5.0) * (2.0 * std::f32::consts::PI);
         self.ubo_buffer.mem_copy(
             util::offset_of!(DepthMeshUniformBufferObject, model) as u64,
             &[Mat4::rotate(scale, Vec3::new(1.0, 1.0, 0.0))],
@@ -344,16 +322,13 @@ impl App for DepthImageSample {
                 .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
                 .set_depth_attachment(
                     AttachmentType::Reference(self.depth_image_handle),
-                    RenderAttachmentDesc {
-                        store_op: AttachmentStoreOp::Store,
-                        layout: Layout::DepthAttachment,
-                        clear_value: ClearValue::default_depth(),
-                        ..Default::default()
-                    },
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::Store)
+                        .layout(Layout::DepthAttachment),
                 )
                 .add_output(RenderPassResource::Image(self.depth_image_handle))
                 .set_callback(allocator, |renderer, cmd_list| {
-                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.mesh_pipeline);
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.mesh_pipeline)?;
                     cmd_list.bind_index_buffer(&renderer.device, &self.cube_index_buffer);
                     cmd_list.bind_vertex_buffer(&renderer.device, &self.cube_vertex_buffer);
                     cmd_list.bind_descriptor_sets(
@@ -371,25 +346,20 @@ impl App for DepthImageSample {
         graph.add_pass(
             allocator,
             RenderPass::new(allocator)
-                .add_color_attachment(
-                    AttachmentType::SwapchainImage,
-                    RenderAttachmentDesc {
-                        load_op: AttachmentLoadOp::Load,
-                        ..Default::default()
-                    },
-                )
+                .add_color_attachment(AttachmentType::SwapchainImage, RenderAttachmentDesc::load())
                 .add_input(RenderPassResource::Image(self.depth_image_handle))
+                // Unflipped, so the top of the depth image is at the top of the overlay
+                .with_flipped_viewport(false)
+                .with_viewport(self.overlay_rect())
                 .set_callback(allocator, |renderer, cmd_list| {
-                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.texture_pipeline);
-                    cmd_list.bind_index_buffer(&renderer.device, &self.quad_index_buffer);
-                    cmd_list.bind_vertex_buffer(&renderer.device, &self.quad_vertex_buffer);
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.texture_pipeline)?;
                     cmd_list.bind_descriptor_sets(
                         &renderer.device,
                         &self.texture_pipeline,
                         0,
                         &[self.texture_bind_group],
                     );
-                    cmd_list.draw_offset(&renderer.device, 6, 0, 0);
+                    cmd_list.draw_fullscreen_triangle(&renderer.device);
 
                     Ok(())
                 }),
@@ -398,6 +368,7 @@ impl App for DepthImageSample {
     }
 
     fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        self.surface_size = Size2D::new(width, height);
         let depth_image = renderer
             .resource_manager
             .images
@@ -431,8 +402,6 @@ impl App for DepthImageSample {
         self.cube_vertex_buffer.destroy(&renderer.device);
         self.cube_index_buffer.destroy(&renderer.device);
         self.ubo_buffer.destroy(&renderer.device);
-        self.quad_vertex_buffer.destroy(&renderer.device);
-        self.quad_index_buffer.destroy(&renderer.device);
         self.sampler.destroy(&renderer.device);
         Ok(())
     }
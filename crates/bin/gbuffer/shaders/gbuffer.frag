@@ -0,0 +1,16 @@
+#version 450
+
+layout (location = 0) in vec3 i_pos;
+layout (location = 1) in vec3 i_normal;
+layout (location = 2) in vec2 i_uv;
+
+layout (location = 0) out vec4 o_albedo;
+layout (location = 1) out vec4 o_normal;
+layout (location = 2) out vec4 o_position;
+
+void main() {
+    float checker = mod(floor(i_uv.x * 16.0) + floor(i_uv.y * 8.0), 2.0);
+    o_albedo = vec4(mix(vec3(0.8, 0.3, 0.2), vec3(0.9, 0.9, 0.8), checker), 1.0);
+    o_normal = vec4(normalize(i_normal), 1.0);
+    o_position = vec4(i_pos, 1.0);
+}
@@ -0,0 +1,33 @@
+#version 460
+
+layout(location = 0) in vec3 i_pos;
+layout(location = 1) in vec3 i_normal;
+layout(location = 2) in vec2 i_uv;
+
+layout (location = 0) out vec3 o_pos;
+layout (location = 1) out vec3 o_normal;
+layout (location = 2) out vec2 o_uv;
+
+layout(binding = 0) uniform UniformBufferObject {
+    mat4 view;
+    mat4 proj;
+} ubo;
+
+layout( push_constant ) uniform constants
+{
+    mat4 model;
+    vec4 normal_matrix[3];
+} PushConstants;
+
+void main() {
+    vec4 world_pos = PushConstants.model * vec4(i_pos, 1.0);
+    o_pos = world_pos.xyz;
+    o_normal = mat3(
+        PushConstants.normal_matrix[0].xyz,
+        PushConstants.normal_matrix[1].xyz,
+        PushConstants.normal_matrix[2].xyz
+    ) * i_normal;
+    o_uv = i_uv;
+
+    gl_Position = ubo.proj * ubo.view * world_pos;
+}
@@ -0,0 +1,21 @@
+#version 450
+
+layout (location = 0) in vec2 i_uv;
+
+layout (location = 0) out vec4 uFragColor;
+
+layout (set = 0, binding = 0) uniform sampler2D albedo_texture;
+layout (set = 0, binding = 1) uniform sampler2D normal_texture;
+layout (set = 0, binding = 2) uniform sampler2D position_texture;
+
+// Shows albedo, normals and positions side by side
+void main() {
+    vec2 uv = vec2(fract(i_uv.x * 3.0), i_uv.y);
+    if (i_uv.x < 1.0 / 3.0) {
+        uFragColor = texture(albedo_texture, uv);
+    } else if (i_uv.x < 2.0 / 3.0) {
+        uFragColor = vec4(texture(normal_texture, uv).xyz * 0.5 + 0.5, 1.0);
+    } else {
+        uFragColor = vec4(fract(texture(position_texture, uv).xyz), 1.0);
+    }
+}
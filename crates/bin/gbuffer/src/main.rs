@@ -0,0 +1,404 @@
+use anyhow::Result;
+use cinder::{
+    App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData,
+    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, Format, GraphicsPipeline,
+    GraphicsPipelineDescription, Image, ImageDescription, ImageUsage, InitContext, Layout,
+    RenderAttachmentDesc, RenderGraph, RenderPass, RenderPassResource, Renderer, ResourceId,
+    Sampler, FULLSCREEN_VERTEX_SHADER,
+};
+use geometry::{SurfaceMesh, Vertex};
+use math::{mat::Mat4, size::Size2D, vec::Vec3};
+use math_ext::{Mat3Padded, Mat4Ext};
+use util::{SdlContext, WindowDescription};
+
+pub const WINDOW_WIDTH: u32 = 1920;
+pub const WINDOW_HEIGHT: u32 = 640;
+
+// Albedo, normal and position targets, in attachment order
+const GBUFFER_FORMATS: [Format; 3] = [
+    Format::R8G8B8A8_UNORM,
+    Format::R16G16B16A16_SFLOAT,
+    Format::R32G32B32A32_SFLOAT,
+];
+
+include!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/gen/gbuffer_shader_structs.rs"
+));
+
+#[rustfmt::skip]
+fn translate(pos: Vec3) -> Mat4 {
+    Mat4::from_data(
+        1.0, 0.0, 0.0, pos.x(),
+        0.0, 1.0, 0.0, pos.y(),
+        0.0, 0.0, 1.0, pos.z(),
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+// Must match the push constants in gbuffer.vert
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ModelConstants {
+    model: [f32; 16],
+    normal_matrix: Mat3Padded,
+}
+
+impl ModelConstants {
+    fn new(model: &Mat4) -> Self {
+        Self {
+            model: model.to_cols_array(),
+            normal_matrix: model.normal_matrix(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ShapeDraw {
+    position: Vec3,
+    vertex_buffer_offset: i32,
+    index_buffer_offset: u32,
+    num_indices: u32,
+}
+
+pub struct GBufferSample {
+    depth_image_handle: ResourceId<Image>,
+    gbuffer_image_handles: [ResourceId<Image>; 3],
+    gbuffer_pipeline: GraphicsPipeline,
+    present_pipeline: GraphicsPipeline,
+    gbuffer_bind_group: BindGroup,
+    present_bind_group: BindGroup,
+    sampler: Sampler,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    ubo_buffer: Buffer,
+    shape_draws: Vec<ShapeDraw>,
+}
+
+impl GBufferSample {
+    fn write_present_bind_group(&self, renderer: &Renderer) -> Result<()> {
+        let infos = self
+            .gbuffer_image_handles
+            .iter()
+            .enumerate()
+            .map(|(idx, handle)| {
+                let image = renderer.resource_manager.images.get(*handle).unwrap();
+                BindGroupBindInfo {
+                    group: self.present_bind_group,
+                    dst_binding: idx as u32,
+                    data: BindGroupWriteData::SampledImage(image.bind_info(
+                        &self.sampler,
+                        Layout::ShaderReadOnly,
+                        None,
+                    )),
+                }
+            })
+            .collect::<Vec<_>>();
+        renderer.device.write_bind_group(&infos)?;
+        Ok(())
+    }
+}
+
+impl App for GBufferSample {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create Images
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+        let surface_size = Size2D::new(surface_rect.width(), surface_rect.height());
+        let depth_image = context.renderer.device.create_image(
+            surface_size,
+            ImageDescription {
+                format: Format::D32_SFLOAT,
+                usage: ImageUsage::Depth,
+                ..Default::default()
+            },
+        )?;
+        let gbuffer_images = GBUFFER_FORMATS.map(|format| {
+            context.renderer.device.create_image(
+                surface_size,
+                ImageDescription {
+                    format,
+                    usage: ImageUsage::ColorAttachment,
+                    ..Default::default()
+                },
+            )
+        });
+        let sampler = context.renderer.device.create_sampler(Default::default())?;
+
+        //
+        // Create Pipelines
+        //
+        let gbuffer_vs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/gbuffer.vert.spv"),
+            Default::default(),
+        )?;
+        let gbuffer_fs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/gbuffer.frag.spv"),
+            Default::default(),
+        )?;
+        let present_vs = context
+            .renderer
+            .device
+            .create_shader(FULLSCREEN_VERTEX_SHADER, Default::default())?;
+        let present_fs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/present.frag.spv"),
+            Default::default(),
+        )?;
+        let gbuffer_pipeline = context.renderer.device.create_graphics_pipeline(
+            &gbuffer_vs,
+            Some(&gbuffer_fs),
+            GraphicsPipelineDescription {
+                name: Some("GBuffer Pipeline".to_owned()),
+                color_formats: GBUFFER_FORMATS.to_vec(),
+                depth_format: Some(Format::D32_SFLOAT),
+                ..Default::default()
+            },
+        )?;
+        let present_pipeline = context.renderer.device.create_graphics_pipeline(
+            &present_vs,
+            Some(&present_fs),
+            GraphicsPipelineDescription {
+                name: Some("GBuffer Present Pipeline".to_owned()),
+                ..Default::default()
+            },
+        )?;
+        let gbuffer_bind_group = BindGroup::new(
+            &context.renderer.device,
+            gbuffer_pipeline.bind_group_data(0).unwrap(),
+        )?;
+        let present_bind_group = BindGroup::new(
+            &context.renderer.device,
+            present_pipeline.bind_group_data(0).unwrap(),
+        )?;
+
+        let ubo_buffer = context.renderer.device.create_buffer_with_data(
+            &[
+                camera::look_to(
+                    Vec3::new(0.0, 2.5, -6.0),
+                    Vec3::new(0.0, -0.4, 1.0),
+                    Vec3::new(0.0, 1.0, 0.0),
+                ),
+                camera::new_infinite_perspective_proj(
+                    surface_rect.width() as f32 / 3.0 / surface_rect.height() as f32,
+                    30.0,
+                    0.01,
+                ),
+            ],
+            BufferDescription {
+                usage: BufferUsage::UNIFORM,
+                ..Default::default()
+            },
+        )?;
+        context
+            .renderer
+            .device
+            .write_bind_group(&[BindGroupBindInfo {
+                group: gbuffer_bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::Uniform(ubo_buffer.bind_info()),
+            }])?;
+
+        //
+        // Create Shapes
+        //
+        let shapes: [(Vec3, SurfaceMesh<Vertex>); 3] = [
+            (
+                Vec3::new(-1.2, 0.5, 0.0),
+                SurfaceMesh::uv_sphere::<16, 32>(0.6),
+            ),
+            (
+                Vec3::new(1.2, 0.5, 0.0),
+                SurfaceMesh::torus::<32, 16>(0.6, 0.25),
+            ),
+            (Vec3::new(0.0, -0.5, 0.0), SurfaceMesh::plane(5.0, 5.0, 4)),
+        ];
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut shape_draws = Vec::with_capacity(shapes.len());
+        for (position, shape) in shapes {
+            shape_draws.push(ShapeDraw {
+                position,
+                vertex_buffer_offset: vertices.len() as i32,
+                index_buffer_offset: indices.len() as u32,
+                num_indices: shape.indices.len() as u32,
+            });
+            vertices.extend(shape.vertices);
+            indices.extend(shape.indices);
+        }
+
+        let vertex_buffer = context.renderer.device.create_buffer_with_data(
+            &vertices,
+            BufferDescription {
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+        let index_buffer = context.renderer.device.create_buffer_with_data(
+            &indices,
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+        //
+        // Cleanup
+        //
+        gbuffer_vs.destroy(&context.renderer.device);
+        gbuffer_fs.destroy(&context.renderer.device);
+        present_vs.destroy(&context.renderer.device);
+        present_fs.destroy(&context.renderer.device);
+
+        let depth_image_handle = context.renderer.resource_manager.insert_image(depth_image);
+        let mut gbuffer_image_handles = Vec::with_capacity(gbuffer_images.len());
+        for image in gbuffer_images {
+            gbuffer_image_handles.push(context.renderer.resource_manager.insert_image(image?));
+        }
+
+        let sample = Self {
+            depth_image_handle,
+            gbuffer_image_handles: gbuffer_image_handles.try_into().unwrap(),
+            gbuffer_pipeline,
+            present_pipeline,
+            gbuffer_bind_group,
+            present_bind_group,
+            sampler,
+            vertex_buffer,
+            index_buffer,
+            ubo_buffer,
+            shape_draws,
+        };
+        sample.write_present_bind_group(context.renderer)?;
+        Ok(sample)
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        let mut gbuffer_pass = RenderPass::new(allocator);
+        for handle in self.gbuffer_image_handles {
+            gbuffer_pass = gbuffer_pass
+                .add_color_attachment(
+                    AttachmentType::Reference(handle),
+                    RenderAttachmentDesc::clear(ClearValue::Color {
+                        color: [0.0, 0.0, 0.0, 1.0],
+                    }),
+                )
+                .add_output(RenderPassResource::Image(handle));
+        }
+        graph.add_pass(
+            allocator,
+            gbuffer_pass
+                .set_depth_attachment(
+                    AttachmentType::Reference(self.depth_image_handle),
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::DontCare)
+                        .layout(Layout::DepthAttachment),
+                )
+                .set_callback(allocator, |renderer, cmd_list| {
+                    let rotation =
+                        (renderer.time.elapsed_seconds() / 5.0) * (2.0 * std::f32::consts::PI);
+
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.gbuffer_pipeline)?;
+                    cmd_list.bind_index_buffer(&renderer.device, &self.index_buffer);
+                    cmd_list.bind_vertex_buffer(&renderer.device, &self.vertex_buffer);
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.gbuffer_pipeline,
+                        0,
+                        &[self.gbuffer_bind_group],
+                    );
+                    for draw in &self.shape_draws {
+                        cmd_list.set_vertex_bytes(
+                            &renderer.device,
+                            &self.gbuffer_pipeline,
+                            &ModelConstants::new(
+                                &(translate(draw.position)
+                                    * Mat4::rotate(rotation, Vec3::new(0.0, 1.0, 0.0))),
+                            ),
+                            0,
+                        )?;
+                        cmd_list.draw_offset(
+                            &renderer.device,
+                            draw.num_indices,
+                            draw.index_buffer_offset,
+                            draw.vertex_buffer_offset,
+                        );
+                    }
+
+                    Ok(())
+                }),
+        );
+
+        let mut present_pass = RenderPass::new(allocator)
+            .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
+            .add_output(RenderPassResource::SwapchainImage);
+        for handle in self.gbuffer_image_handles {
+            present_pass = present_pass.add_input(RenderPassResource::Image(handle));
+        }
+        graph.add_pass(
+            allocator,
+            present_pass.set_callback(allocator, |renderer, cmd_list| {
+                cmd_list.bind_graphics_pipeline(&renderer.device, &self.present_pipeline)?;
+                cmd_list.bind_descriptor_sets(
+                    &renderer.device,
+                    &self.present_pipeline,
+                    0,
+                    &[self.present_bind_group],
+                );
+                cmd_list.draw_fullscreen_triangle(&renderer.device);
+
+                Ok(())
+            }),
+        );
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        for handle in std::iter::once(self.depth_image_handle).chain(self.gbuffer_image_handles) {
+            renderer
+                .resource_manager
+                .images
+                .get_mut(handle)
+                .unwrap()
+                .resize(&renderer.device, Size2D::new(width, height))?;
+        }
+        self.write_present_bind_group(renderer)?;
+        self.ubo_buffer.mem_copy(
+            util::offset_of!(GbufferUniformBufferObject, proj) as u64,
+            &[camera::new_infinite_perspective_proj(
+                width as f32 / 3.0 / height as f32,
+                30.0,
+                0.01,
+            )],
+        )?;
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.index_buffer.destroy(&renderer.device);
+        self.vertex_buffer.destroy(&renderer.device);
+        self.ubo_buffer.destroy(&renderer.device);
+        self.sampler.destroy(&renderer.device);
+        self.gbuffer_pipeline.destroy(&renderer.device);
+        self.present_pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "gbuffer",
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let mut cinder = Cinder::<GBufferSample>::new(&sdl.window).unwrap();
+    cinder.run_game_loop(&mut sdl).unwrap();
+}
@@ -0,0 +1,13 @@
+#version 450
+
+layout (location = 0) in vec3 i_normal;
+layout (location = 1) in vec2 i_uv;
+
+layout (location = 0) out vec4 uFragColor;
+
+void main() {
+    vec3 normal = normalize(i_normal);
+    float checker = mod(floor(i_uv.x * 16.0) + floor(i_uv.y * 8.0), 2.0);
+    vec3 base_color = (normal * 0.5 + 0.5) * mix(0.75, 1.0, checker);
+    uFragColor = vec4(base_color, 1.0);
+}
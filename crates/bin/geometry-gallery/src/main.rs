@@ -0,0 +1,331 @@
+use anyhow::Result;
+use cinder::{
+    App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData,
+    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, Format, GraphicsPipeline,
+    GraphicsPipelineDescription, Image, ImageDescription, ImageUsage, InitContext, Layout,
+    PrimitiveTopology, RenderAttachmentDesc, RenderGraph, RenderPass, Renderer, ResourceId,
+};
+use geometry::{SurfaceMesh, Vertex};
+use math::{mat::Mat4, size::Size2D, vec::Vec3};
+use math_ext::{Mat3Padded, Mat4Ext};
+use util::{SdlContext, WindowDescription};
+
+pub const WINDOW_WIDTH: u32 = 1280;
+pub const WINDOW_HEIGHT: u32 = 1280;
+
+include!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/gen/gallery_shader_structs.rs"
+));
+
+#[rustfmt::skip]
+fn translate(pos: Vec3) -> Mat4 {
+    Mat4::from_data(
+        1.0, 0.0, 0.0, pos.x(),
+        0.0, 1.0, 0.0, pos.y(),
+        0.0, 0.0, 1.0, pos.z(),
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+// Must match the push constants in gallery.vert
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ModelConstants {
+    model: [f32; 16],
+    normal_matrix: Mat3Padded,
+}
+
+impl ModelConstants {
+    fn new(model: &Mat4) -> Self {
+        Self {
+            model: model.to_cols_array(),
+            normal_matrix: model.normal_matrix(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ShapeDraw {
+    position: Vec3,
+    vertex_buffer_offset: i32,
+    index_buffer_offset: u32,
+    num_indices: u32,
+}
+
+pub struct GeometryGallery {
+    depth_image_handle: ResourceId<Image>,
+    pipeline: GraphicsPipeline,
+    // Same shaders as `pipeline`, for `strip_draw`
+    strip_pipeline: GraphicsPipeline,
+    bind_group: BindGroup,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    ubo_buffer: Buffer,
+    shape_draws: Vec<ShapeDraw>,
+    strip_draw: ShapeDraw,
+}
+
+impl App for GeometryGallery {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create App Resources
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+        let depth_image = context.renderer.device.create_image(
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            ImageDescription {
+                format: Format::D32_SFLOAT,
+                usage: ImageUsage::Depth,
+                ..Default::default()
+            },
+        )?;
+
+        let vertex_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/gallery.vert.spv"),
+            Default::default(),
+        )?;
+        let fragment_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/gallery.frag.spv"),
+            Default::default(),
+        )?;
+        let pipeline = context.renderer.device.create_graphics_pipeline(
+            &vertex_shader,
+            Some(&fragment_shader),
+            GraphicsPipelineDescription {
+                depth_format: Some(Format::D32_SFLOAT),
+                ..Default::default()
+            },
+        )?;
+        let strip_pipeline = context.renderer.device.create_graphics_pipeline(
+            &vertex_shader,
+            Some(&fragment_shader),
+            GraphicsPipelineDescription {
+                depth_format: Some(Format::D32_SFLOAT),
+                topology: PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+        )?;
+        let bind_group = BindGroup::new(
+            &context.renderer.device,
+            pipeline.bind_group_data(0).unwrap(),
+        )?;
+
+        let ubo_buffer = context.renderer.device.create_buffer_with_data(
+            &[
+                camera::look_to(
+                    Vec3::new(0.0, 2.5, -6.0),
+                    Vec3::new(0.0, -0.4, 1.0),
+                    Vec3::new(0.0, 1.0, 0.0),
+                ),
+                camera::new_infinite_perspective_proj(
+                    surface_rect.width() as f32 / surface_rect.height() as f32,
+                    30.0,
+                    0.01,
+                ),
+            ],
+            BufferDescription {
+                usage: BufferUsage::UNIFORM,
+                ..Default::default()
+            },
+        )?;
+        context
+            .renderer
+            .device
+            .write_bind_group(&[BindGroupBindInfo {
+                group: bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::Uniform(ubo_buffer.bind_info()),
+            }])?;
+
+        //
+        // Create Shapes
+        //
+        let shapes: [(Vec3, SurfaceMesh<Vertex>); 6] = [
+            (
+                Vec3::new(-2.0, 0.0, 1.0),
+                SurfaceMesh::cylinder::<32>(1.0, 0.5),
+            ),
+            (Vec3::new(0.0, 0.0, 1.0), SurfaceMesh::cone::<32>(1.0, 0.5)),
+            (
+                Vec3::new(2.0, 0.5, 1.0),
+                SurfaceMesh::uv_sphere::<16, 32>(0.5),
+            ),
+            (
+                Vec3::new(-2.0, 0.5, -1.0),
+                SurfaceMesh::capsule::<32>(0.5, 0.3),
+            ),
+            (
+                Vec3::new(0.0, 0.5, -1.0),
+                SurfaceMesh::torus::<32, 16>(0.5, 0.2),
+            ),
+            (Vec3::new(2.0, 0.0, -1.0), SurfaceMesh::plane(1.0, 1.0, 4)),
+        ];
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut shape_draws = Vec::with_capacity(shapes.len());
+        for (position, shape) in shapes {
+            shape_draws.push(ShapeDraw {
+                position,
+                vertex_buffer_offset: vertices.len() as i32,
+                index_buffer_offset: indices.len() as u32,
+                num_indices: shape.indices.len() as u32,
+            });
+            vertices.extend(shape.vertices);
+            indices.extend(shape.indices);
+        }
+
+        let ribbon = SurfaceMesh::<Vertex>::ribbon(0.5, 3.0, 16);
+        let strip_draw = ShapeDraw {
+            position: Vec3::new(0.0, 0.0, 3.0),
+            vertex_buffer_offset: vertices.len() as i32,
+            index_buffer_offset: indices.len() as u32,
+            num_indices: ribbon.indices.len() as u32,
+        };
+        vertices.extend(ribbon.vertices);
+        indices.extend(ribbon.indices);
+
+        let vertex_buffer = context.renderer.device.create_buffer_with_data(
+            &vertices,
+            BufferDescription {
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+        let index_buffer = context.renderer.device.create_buffer_with_data(
+            &indices,
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+
+        vertex_shader.destroy(&context.renderer.device);
+        fragment_shader.destroy(&context.renderer.device);
+
+        let depth_image_handle = context.renderer.resource_manager.insert_image(depth_image);
+
+        Ok(Self {
+            depth_image_handle,
+            pipeline,
+            strip_pipeline,
+            bind_group,
+            vertex_buffer,
+            index_buffer,
+            ubo_buffer,
+            shape_draws,
+            strip_draw,
+        })
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
+                .set_depth_attachment(
+                    AttachmentType::Reference(self.depth_image_handle),
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::DontCare)
+                        .layout(Layout::DepthAttachment),
+                )
+                .set_callback(allocator, |renderer, cmd_list| {
+                    let rotation =
+                        (renderer.time.elapsed_seconds() / 5.0) * (2.0 * std::f32::consts::PI);
+
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipeline)?;
+                    cmd_list.bind_index_buffer(&renderer.device, &self.index_buffer);
+                    cmd_list.bind_vertex_buffer(&renderer.device, &self.vertex_buffer);
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.pipeline,
+                        0,
+                        &[self.bind_group],
+                    );
+                    for draw in &self.shape_draws {
+                        cmd_list.set_vertex_bytes(
+                            &renderer.device,
+                            &self.pipeline,
+                            &ModelConstants::new(
+                                &(translate(draw.position)
+                                    * Mat4::rotate(rotation, Vec3::new(1.0, 1.0, 0.0))),
+                            ),
+                            0,
+                        )?;
+                        cmd_list.draw_offset(
+                            &renderer.device,
+                            draw.num_indices,
+                            draw.index_buffer_offset,
+                            draw.vertex_buffer_offset,
+                        );
+                    }
+
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.strip_pipeline)?;
+                    cmd_list.set_vertex_bytes(
+                        &renderer.device,
+                        &self.strip_pipeline,
+                        &ModelConstants::new(
+                            &(translate(self.strip_draw.position)
+                                * Mat4::rotate(rotation, Vec3::new(0.0, 0.0, 1.0))),
+                        ),
+                        0,
+                    )?;
+                    cmd_list.draw_offset(
+                        &renderer.device,
+                        self.strip_draw.num_indices,
+                        self.strip_draw.index_buffer_offset,
+                        self.strip_draw.vertex_buffer_offset,
+                    );
+
+                    Ok(())
+                }),
+        );
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        let depth_image = renderer
+            .resource_manager
+            .images
+            .get_mut(self.depth_image_handle)
+            .unwrap();
+        depth_image.resize(&renderer.device, Size2D::new(width, height))?;
+        self.ubo_buffer.mem_copy(
+            util::offset_of!(GalleryUniformBufferObject, proj) as u64,
+            &[camera::new_infinite_perspective_proj(
+                width as f32 / height as f32,
+                30.0,
+                0.01,
+            )],
+        )?;
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.index_buffer.destroy(&renderer.device);
+        self.vertex_buffer.destroy(&renderer.device);
+        self.ubo_buffer.destroy(&renderer.device);
+        self.pipeline.destroy(&renderer.device);
+        self.strip_pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "geometry-gallery",
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let mut cinder = Cinder::<GeometryGallery>::new(&sdl.window).unwrap();
+    cinder.run_game_loop(&mut sdl).unwrap();
+}
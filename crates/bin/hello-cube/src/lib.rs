@@ -0,0 +1,267 @@
+use anyhow::Result;
+use cinder::{
+    App, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData, Buffer,
+    BufferDescription, BufferUsage, Bump, Format, GraphicsPipeline, GraphicsPipelineDescription,
+    InitContext, InputState, RenderGraph, RenderPass, Renderer,
+};
+use math::{mat::Mat4, vec::Vec3};
+
+include!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/gen/cube_shader_structs.rs"
+));
+
+pub struct HelloCube {
+    pipeline: GraphicsPipeline,
+    bind_group: BindGroup,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    ubo_buffer: Buffer,
+}
+
+impl App for HelloCube {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create App Resources
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+
+        let vertex_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/cube.vert.spv"),
+            Default::default(),
+        )?;
+        let fragment_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/cube.frag.spv"),
+            Default::default(),
+        )?;
+        let pipeline = context.renderer.device.create_graphics_pipeline(
+            &vertex_shader,
+            Some(&fragment_shader),
+            GraphicsPipelineDescription {
+                depth_format: Some(Format::D32_SFLOAT),
+                ..Default::default()
+            },
+        )?;
+        let bind_group = BindGroup::new(
+            &context.renderer.device,
+            pipeline.bind_group_data(0).unwrap(),
+        )?;
+
+        let ubo_buffer = context.renderer.device.create_buffer(
+            std::mem::size_of::<CubeUniformBufferObject>() as u64,
+            BufferDescription {
+                usage: BufferUsage::UNIFORM,
+                ..Default::default()
+            },
+        )?;
+        ubo_buffer.mem_copy(
+            util::offset_of!(CubeUniformBufferObject, view) as u64,
+            &[
+                camera::look_to(
+                    Vec3::new(2.0, 0.0, 0.0),
+                    Vec3::new(-1.0, 0.0, 0.0),
+                    Vec3::new(0.0, 1.0, 0.0),
+                ),
+                camera::new_infinite_perspective_proj(
+                    surface_rect.width() as f32 / surface_rect.height() as f32,
+                    30.0,
+                    0.01,
+                ),
+            ],
+        )?;
+        context
+            .renderer
+            .device
+            .write_bind_group(&[BindGroupBindInfo {
+                group: bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::Uniform(ubo_buffer.bind_info()),
+            }])?;
+
+        let vertex_buffer = context.renderer.device.create_buffer_with_data(
+            &[
+                // Plane at z: -0.5
+                CubeVertex {
+                    i_pos: [-0.5, 0.5, -0.5],
+                    i_normal: [1.0, 0.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, 0.5, -0.5],
+                    i_normal: [1.0, 0.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [-0.5, -0.5, -0.5],
+                    i_normal: [1.0, 0.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, -0.5, -0.5],
+                    i_normal: [1.0, 0.0, 0.0],
+                },
+                // Plane at z: 0.5
+                CubeVertex {
+                    i_pos: [-0.5, 0.5, 0.5],
+                    i_normal: [0.0, 0.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, 0.5, 0.5],
+                    i_normal: [0.0, 0.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [-0.5, -0.5, 0.5],
+                    i_normal: [0.0, 0.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, -0.5, 0.5],
+                    i_normal: [0.0, 0.0, 1.0],
+                },
+                // Plane at x: -0.5
+                CubeVertex {
+                    i_pos: [-0.5, -0.5, 0.5],
+                    i_normal: [0.0, 1.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [-0.5, 0.5, 0.5],
+                    i_normal: [0.0, 1.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [-0.5, -0.5, -0.5],
+                    i_normal: [0.0, 1.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [-0.5, 0.5, -0.5],
+                    i_normal: [0.0, 1.0, 0.0],
+                },
+                // Plane at x: 0.5
+                CubeVertex {
+                    i_pos: [0.5, -0.5, 0.5],
+                    i_normal: [1.0, 1.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, 0.5, 0.5],
+                    i_normal: [1.0, 1.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, -0.5, -0.5],
+                    i_normal: [1.0, 1.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, 0.5, -0.5],
+                    i_normal: [1.0, 1.0, 0.0],
+                },
+                // Plane at y: -0.5
+                CubeVertex {
+                    i_pos: [-0.5, -0.5, 0.5],
+                    i_normal: [0.0, 1.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, -0.5, 0.5],
+                    i_normal: [0.0, 1.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [-0.5, -0.5, -0.5],
+                    i_normal: [0.0, 1.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, -0.5, -0.5],
+                    i_normal: [0.0, 1.0, 1.0],
+                },
+                // Plane at y: 0.5
+                CubeVertex {
+                    i_pos: [-0.5, 0.5, 0.5],
+                    i_normal: [1.0, 1.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, 0.5, 0.5],
+                    i_normal: [1.0, 1.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [-0.5, 0.5, -0.5],
+                    i_normal: [1.0, 1.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, 0.5, -0.5],
+                    i_normal: [1.0, 1.0, 1.0],
+                },
+            ],
+            BufferDescription {
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+        let index_buffer = context.renderer.device.create_buffer_with_data(
+            &[
+                0, 1, 2, 2, 1, 3, // First plane
+                4, 5, 6, 6, 5, 7, // Second plane
+                8, 9, 10, 10, 9, 11, // Third plane
+                12, 13, 14, 14, 13, 15, // Fourth plane
+                16, 17, 18, 18, 17, 19, // Fifth plane
+                20, 21, 22, 22, 21, 23, // Sixth plane
+            ],
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+
+        vertex_shader.destroy(&context.renderer.device);
+        fragment_shader.destroy(&context.renderer.device);
+
+        Ok(Self {
+            pipeline,
+            bind_group,
+            vertex_buffer,
+            index_buffer,
+            ubo_buffer,
+        })
+    }
+
+    fn update(&mut self, renderer: &mut Renderer, _input: &InputState) -> Result<()> {
+        let scale = (renderer.time.elapsed_seconds() / 5.0) * (2.0 * std::f32::consts::PI);
+        self.ubo_buffer.mem_copy(
+            util::offset_of!(CubeUniformBufferObject, model) as u64,
+            &[Mat4::rotate(scale, Vec3::new(1.0, 1.0, 0.0))],
+        )?;
+        Ok(())
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
+                .with_managed_depth(Format::D32_SFLOAT)
+                .set_callback(allocator, |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipeline)?;
+                    cmd_list.bind_index_buffer(&renderer.device, &self.index_buffer);
+                    cmd_list.bind_vertex_buffer(&renderer.device, &self.vertex_buffer);
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.pipeline,
+                        0,
+                        &[self.bind_group],
+                    );
+                    cmd_list.draw_offset(
+                        &renderer.device,
+                        self.index_buffer.num_elements().unwrap(),
+                        0,
+                        0,
+                    );
+
+                    Ok(())
+                }),
+        );
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.index_buffer.destroy(&renderer.device);
+        self.vertex_buffer.destroy(&renderer.device);
+        self.ubo_buffer.destroy(&renderer.device);
+        self.pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
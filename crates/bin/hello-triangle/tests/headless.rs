@@ -0,0 +1,28 @@
+use cinder::testing::{check_against_reference, try_render_to_image};
+use hello_triangle::HelloTriangle;
+use std::path::PathBuf;
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 256;
+// Per-channel difference allowed between drivers
+const TOLERANCE: u8 = 2;
+
+// Skipped on machines without a Vulkan device. Run with `CINDER_UPDATE_REFERENCES=1` to
+// regenerate the reference image. The checked-in reference was rasterized on the CPU following
+// Vulkan's rules: covered pixel centers, linearly interpolated colors and the default magenta clear
+#[test]
+fn hello_triangle_matches_reference() {
+    let Some(pixels) = try_render_to_image::<HelloTriangle>(WIDTH, HEIGHT).unwrap() else {
+        eprintln!("Skipping hello_triangle_matches_reference: no Vulkan device");
+        return;
+    };
+    check_against_reference(
+        &pixels,
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("reference")
+            .join("hello_triangle.bgra"),
+        TOLERANCE,
+    )
+    .unwrap();
+}
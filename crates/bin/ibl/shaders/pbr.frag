@@ -0,0 +1,46 @@
+#version 460
+
+layout (location = 0) in vec3 i_normal;
+layout (location = 1) in vec3 i_view_dir;
+layout (location = 2) flat in vec4 i_base_color;
+layout (location = 3) flat in vec2 i_metallic_roughness;
+
+layout (location = 0) out vec4 uFragColor;
+
+// Written from `IblMaps`
+layout (binding = 1) uniform samplerCube irradiance_map;
+layout (binding = 2) uniform samplerCube prefiltered_map;
+layout (binding = 3) uniform sampler2D brdf_lut;
+
+// Dielectrics reflect about 4% of light head-on
+const vec3 DIELECTRIC_F0 = vec3(0.04);
+
+// Fresnel at `cos_theta`, with the grazing reflectance damped for rough surfaces
+vec3 fresnel_schlick_roughness(float cos_theta, vec3 f0, float roughness) {
+    return f0 + (max(vec3(1.0 - roughness), f0) - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+void main() {
+    vec3 normal = normalize(i_normal);
+    vec3 view = normalize(i_view_dir);
+    vec3 reflection = reflect(-view, normal);
+    float n_dot_v = max(dot(normal, view), 0.0);
+
+    vec3 base_color = i_base_color.rgb;
+    float metallic = i_metallic_roughness.x;
+    float roughness = i_metallic_roughness.y;
+    vec3 f0 = mix(DIELECTRIC_F0, base_color, metallic);
+
+    // Light that isn't reflected is refracted into diffuse, which metals don't have
+    vec3 f = fresnel_schlick_roughness(n_dot_v, f0, roughness);
+    vec3 k_diffuse = (1.0 - f) * (1.0 - metallic);
+    vec3 diffuse = texture(irradiance_map, normal).rgb * base_color;
+
+    float max_lod = float(textureQueryLevels(prefiltered_map) - 1);
+    vec3 prefiltered = textureLod(prefiltered_map, reflection, roughness * max_lod).rgb;
+    vec2 brdf = texture(brdf_lut, vec2(n_dot_v, roughness)).rg;
+    vec3 specular = prefiltered * (f * brdf.x + brdf.y);
+
+    // Linear HDR, tonemapped into the swapchain afterwards
+    uFragColor = vec4(k_diffuse * diffuse + specular, 1.0);
+}
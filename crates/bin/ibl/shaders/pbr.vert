@@ -0,0 +1,36 @@
+#version 460
+
+layout(location = 0) in vec3 i_pos;
+layout(location = 1) in vec3 i_normal;
+layout(location = 2) in vec2 i_uv;
+
+layout (location = 0) out vec3 o_normal;
+layout (location = 1) out vec3 o_view_dir;
+layout (location = 2) flat out vec4 o_base_color;
+layout (location = 3) flat out vec2 o_metallic_roughness;
+
+layout(binding = 0) uniform UniformBufferObject {
+    mat4 view;
+    mat4 proj;
+    vec4 camera_position;
+} ubo;
+
+// Must match `SphereConstants` in main.rs
+layout( push_constant ) uniform constants
+{
+    mat4 model;
+    vec4 base_color;
+    vec4 metallic_roughness;
+} PushConstants;
+
+void main() {
+    vec4 world_pos = PushConstants.model * vec4(i_pos, 1.0);
+    // Models are only translated and rotated, so their upper 3x3 already transforms normals.
+    // There is no room left in the push constants for a separate normal matrix
+    o_normal = mat3(PushConstants.model) * i_normal;
+    o_view_dir = ubo.camera_position.xyz - world_pos.xyz;
+    o_base_color = PushConstants.base_color;
+    o_metallic_roughness = PushConstants.metallic_roughness.xy;
+
+    gl_Position = ubo.proj * ubo.view * world_pos;
+}
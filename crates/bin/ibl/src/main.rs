@@ -0,0 +1,370 @@
+use anyhow::Result;
+use cinder::{
+    App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData,
+    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, Format, GraphicsPipeline,
+    GraphicsPipelineDescription, IblDescription, IblMaps, Image, ImageDescription, ImageUsage,
+    InitContext, InputState, Layout, PostProcess, RenderAttachmentDesc, RenderGraph, RenderPass,
+    RenderPassResource, Renderer, ResourceId, Skybox, SkyboxDescription,
+};
+use geometry::{SurfaceMesh, Vertex};
+use math::{mat::Mat4, size::Size2D, vec::Vec3};
+use math_ext::Mat4Ext;
+use std::{path::PathBuf, process::ExitCode};
+use util::{SdlContext, WindowDescription};
+
+pub const WINDOW_WIDTH: u32 = 1280;
+pub const WINDOW_HEIGHT: u32 = 1280;
+
+const HDR_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+
+const Y_FOV: f32 = 30.0;
+const Z_NEAR: f32 = 0.01;
+
+// Spheres are laid out in a `GRID_SIZE` x `GRID_SIZE` grid, with metallic going up the rows and
+// roughness along the columns
+const GRID_SIZE: usize = 7;
+const SPHERE_SPACING: f32 = 1.2;
+
+include!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/gen/pbr_shader_structs.rs"
+));
+
+#[rustfmt::skip]
+fn translate(pos: Vec3) -> Mat4 {
+    Mat4::from_data(
+        1.0, 0.0, 0.0, pos.x(),
+        0.0, 1.0, 0.0, pos.y(),
+        0.0, 0.0, 1.0, pos.z(),
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+// Must match the push constants in pbr.vert
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SphereConstants {
+    model: Mat4,
+    base_color: [f32; 4],
+    // Metallic in x and roughness in y
+    metallic_roughness: [f32; 4],
+}
+
+pub struct IblSample {
+    depth_image_handle: ResourceId<Image>,
+    // Lit scene, tonemapped into the swapchain by `post_process`
+    hdr_image_handle: ResourceId<Image>,
+    post_process: PostProcess,
+    skybox: Skybox,
+    ibl_maps: IblMaps,
+    pipeline: GraphicsPipeline,
+    bind_group: BindGroup,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    ubo_buffer: Buffer,
+    spheres: Vec<SphereConstants>,
+    // Column-major, for `skybox`
+    view_proj: [f32; 16],
+}
+
+impl App for IblSample {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create App Resources
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+        let depth_image = context.renderer.device.create_image(
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            ImageDescription {
+                name: Some("Depth"),
+                format: Format::D32_SFLOAT,
+                usage: ImageUsage::Depth,
+                ..Default::default()
+            },
+        )?;
+        let hdr_image = context.renderer.device.create_image(
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            ImageDescription {
+                name: Some("HDR Color"),
+                format: HDR_FORMAT,
+                usage: ImageUsage::ColorAttachment,
+                ..Default::default()
+            },
+        )?;
+
+        let environment_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("assets")
+            .join("environment.hdr");
+        if !environment_path.exists() {
+            anyhow::bail!(
+                "No environment map at {}, any equirectangular .hdr works",
+                environment_path.display()
+            );
+        }
+        let skybox = Skybox::new(
+            context.renderer,
+            &environment_path,
+            SkyboxDescription {
+                color_format: HDR_FORMAT,
+                depth_format: Format::D32_SFLOAT,
+                ..Default::default()
+            },
+        )?;
+        let ibl_maps = IblMaps::new(
+            context.renderer,
+            skybox.cubemap(),
+            IblDescription::default(),
+        )?;
+
+        let vertex_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/pbr.vert.spv"),
+            Default::default(),
+        )?;
+        let fragment_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/pbr.frag.spv"),
+            Default::default(),
+        )?;
+        let pipeline = context.renderer.device.create_graphics_pipeline(
+            &vertex_shader,
+            Some(&fragment_shader),
+            GraphicsPipelineDescription {
+                color_formats: vec![HDR_FORMAT],
+                depth_format: Some(Format::D32_SFLOAT),
+                ..Default::default()
+            },
+        )?;
+        let bind_group = BindGroup::new(
+            &context.renderer.device,
+            pipeline.bind_group_data(0).unwrap(),
+        )?;
+        let ubo_buffer = context.renderer.device.create_buffer(
+            std::mem::size_of::<PbrUniformBufferObject>() as u64,
+            BufferDescription {
+                name: Some("Uniform Buffer"),
+                usage: BufferUsage::UNIFORM,
+                ..Default::default()
+            },
+        )?;
+        context.renderer.device.write_bind_group(&[
+            BindGroupBindInfo {
+                group: bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::Uniform(ubo_buffer.bind_info()),
+            },
+            BindGroupBindInfo {
+                group: bind_group,
+                dst_binding: 1,
+                data: BindGroupWriteData::SampledImage(ibl_maps.irradiance.bind_info(
+                    &ibl_maps.sampler,
+                    Layout::ShaderReadOnly,
+                    None,
+                )),
+            },
+            BindGroupBindInfo {
+                group: bind_group,
+                dst_binding: 2,
+                data: BindGroupWriteData::SampledImage(ibl_maps.prefiltered.bind_info(
+                    &ibl_maps.sampler,
+                    Layout::ShaderReadOnly,
+                    None,
+                )),
+            },
+            BindGroupBindInfo {
+                group: bind_group,
+                dst_binding: 3,
+                data: BindGroupWriteData::SampledImage(ibl_maps.brdf_lut.bind_info(
+                    &ibl_maps.sampler,
+                    Layout::ShaderReadOnly,
+                    None,
+                )),
+            },
+        ])?;
+
+        //
+        // Create Spheres
+        //
+        let sphere = SurfaceMesh::<Vertex>::uv_sphere::<32, 64>(0.5);
+        let vertex_buffer = context.renderer.device.create_buffer_with_data(
+            &sphere.vertices,
+            BufferDescription {
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+        let index_buffer = context.renderer.device.create_buffer_with_data(
+            &sphere.indices,
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+        let offset = (GRID_SIZE - 1) as f32 / 2.0;
+        let spheres = (0..GRID_SIZE * GRID_SIZE)
+            .map(|idx| {
+                let (row, col) = (idx / GRID_SIZE, idx % GRID_SIZE);
+                let metallic = row as f32 / (GRID_SIZE - 1) as f32;
+                // Perfectly smooth spheres reflect a single direction per pixel, which aliases
+                let roughness = (col as f32 / (GRID_SIZE - 1) as f32).max(0.05);
+                SphereConstants {
+                    model: translate(Vec3::new(
+                        (col as f32 - offset) * SPHERE_SPACING,
+                        (row as f32 - offset) * SPHERE_SPACING,
+                        0.0,
+                    )),
+                    base_color: [1.0, 0.78, 0.34, 1.0],
+                    metallic_roughness: [metallic, roughness, 0.0, 0.0],
+                }
+            })
+            .collect::<Vec<_>>();
+
+        //
+        // Cleanup
+        //
+        vertex_shader.destroy(&context.renderer.device);
+        fragment_shader.destroy(&context.renderer.device);
+
+        let depth_image_handle = context.renderer.resource_manager.insert_image(depth_image);
+        let hdr_image_handle = context.renderer.resource_manager.insert_image(hdr_image);
+        let post_process = PostProcess::new(context.renderer)?;
+        post_process.set_input(context.renderer, hdr_image_handle)?;
+
+        Ok(Self {
+            depth_image_handle,
+            hdr_image_handle,
+            post_process,
+            skybox,
+            ibl_maps,
+            pipeline,
+            bind_group,
+            vertex_buffer,
+            index_buffer,
+            ubo_buffer,
+            spheres,
+            view_proj: Default::default(),
+        })
+    }
+
+    fn update(&mut self, renderer: &mut Renderer, _input: &InputState) -> Result<()> {
+        // Orbit the spheres, so reflections can be seen moving across them
+        let angle = (renderer.time.elapsed_seconds() / 30.0) * (2.0 * std::f32::consts::PI);
+        let eye = Vec3::new(14.0 * angle.sin(), 1.0, -14.0 * angle.cos());
+        let front = Vec3::new(-eye.x(), -eye.y(), -eye.z());
+        let view = camera::look_to(eye, front, Vec3::new(0.0, 1.0, 0.0));
+        let proj = camera::new_infinite_perspective_proj(
+            renderer.device.surface_aspect_ratio(),
+            Y_FOV,
+            Z_NEAR,
+        );
+
+        self.ubo_buffer.mem_copy(
+            util::offset_of!(PbrUniformBufferObject, view) as u64,
+            &[view, proj],
+        )?;
+        self.ubo_buffer.mem_copy(
+            util::offset_of!(PbrUniformBufferObject, camera_position) as u64,
+            &[[eye.x(), eye.y(), eye.z(), 1.0]],
+        )?;
+        self.view_proj = (proj * view).to_cols_array();
+        Ok(())
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(
+                    AttachmentType::Reference(self.hdr_image_handle),
+                    Default::default(),
+                )
+                .add_output(RenderPassResource::Image(self.hdr_image_handle))
+                .set_depth_attachment(
+                    AttachmentType::Reference(self.depth_image_handle),
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::DontCare)
+                        .layout(Layout::DepthAttachment),
+                )
+                .set_callback(allocator, |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipeline)?;
+                    cmd_list.bind_index_buffer(&renderer.device, &self.index_buffer);
+                    cmd_list.bind_vertex_buffer(&renderer.device, &self.vertex_buffer);
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.pipeline,
+                        0,
+                        &[self.bind_group],
+                    );
+                    for sphere in &self.spheres {
+                        cmd_list.set_vertex_bytes(&renderer.device, &self.pipeline, sphere, 0)?;
+                        cmd_list.draw_offset(
+                            &renderer.device,
+                            self.index_buffer.num_elements().unwrap(),
+                            0,
+                            0,
+                        );
+                    }
+                    self.skybox.draw(renderer, cmd_list, &self.view_proj)?;
+
+                    Ok(())
+                }),
+        );
+        self.post_process
+            .tonemap(allocator, graph, self.hdr_image_handle);
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        renderer
+            .resource_manager
+            .images
+            .get_mut(self.depth_image_handle)
+            .unwrap()
+            .resize(&renderer.device, Size2D::new(width, height))?;
+        renderer
+            .resource_manager
+            .images
+            .get_mut(self.hdr_image_handle)
+            .unwrap()
+            .resize(&renderer.device, Size2D::new(width, height))?;
+        self.post_process
+            .set_input(renderer, self.hdr_image_handle)?;
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.post_process.destroy(renderer);
+        self.skybox.destroy(renderer);
+        self.ibl_maps.destroy(renderer);
+        self.index_buffer.destroy(&renderer.device);
+        self.vertex_buffer.destroy(&renderer.device);
+        self.ubo_buffer.destroy(&renderer.device);
+        self.pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() -> ExitCode {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "ibl",
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    // The environment map isn't part of the repository, so a missing one is reported instead
+    // of panicking
+    let mut cinder = match Cinder::<IblSample>::new(&sdl.window) {
+        Ok(cinder) => cinder,
+        Err(err) => {
+            eprintln!("Failed to start the ibl sample: {err:#}");
+            return ExitCode::FAILURE;
+        }
+    };
+    cinder.run_game_loop(&mut sdl).unwrap();
+    ExitCode::SUCCESS
+}
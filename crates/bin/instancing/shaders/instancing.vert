@@ -0,0 +1,23 @@
+#version 460
+
+// Per-vertex, binding 0
+layout(location = 0) in vec3 i_pos;
+// Per-instance model matrix columns, binding 1
+layout(location = 1) in vec4 i_model_0;
+layout(location = 2) in vec4 i_model_1;
+layout(location = 3) in vec4 i_model_2;
+layout(location = 4) in vec4 i_model_3;
+
+layout (location = 0) out vec3 o_color;
+
+layout(binding = 0) uniform UniformBufferObject {
+    mat4 view;
+    mat4 proj;
+} ubo;
+
+void main() {
+    mat4 model = mat4(i_model_0, i_model_1, i_model_2, i_model_3);
+    // Tint each instance by where it was placed
+    o_color = clamp(i_model_3.xyz * 0.15 + 0.5, 0.0, 1.0);
+    gl_Position = ubo.proj * ubo.view * model * vec4(i_pos, 1.0);
+}
@@ -0,0 +1,323 @@
+use anyhow::Result;
+use cinder::{
+    App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData,
+    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, ColorBlendState,
+    ColorComponents, CommandList, CompareOp, Format, GraphicsPipeline, GraphicsPipelineDescription,
+    Image, ImageDescription, ImageUsage, IndexType, InitContext, Layout, RenderAttachmentDesc,
+    RenderGraph, RenderPass, RenderPassResource, Renderer, ResourceId, VertexAttributeDescription,
+    VertexBindingDesc, VertexDescription, VertexInputRate,
+};
+use geometry::{PositionVertex, SurfaceMesh};
+use math::{mat::Mat4, size::Size2D, vec::Vec3};
+use util::{SdlContext, WindowDescription};
+
+pub const WINDOW_WIDTH: u32 = 1280;
+pub const WINDOW_HEIGHT: u32 = 1280;
+
+// Instances are laid out in a `GRID_SIZE` x `GRID_SIZE` grid
+const GRID_SIZE: usize = 16;
+
+include!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/gen/instancing_shader_structs.rs"
+));
+
+#[rustfmt::skip]
+fn translate(pos: Vec3) -> Mat4 {
+    Mat4::from_data(
+        1.0, 0.0, 0.0, pos.x(),
+        0.0, 1.0, 0.0, pos.y(),
+        0.0, 0.0, 1.0, pos.z(),
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+pub struct InstancingSample {
+    depth_image_handle: ResourceId<Image>,
+    // Writes the depth of every instance, so `pipeline` only shades the visible fragments
+    prepass_pipeline: GraphicsPipeline,
+    pipeline: GraphicsPipeline,
+    bind_group: BindGroup,
+    vertex_buffer: Buffer,
+    instance_buffer: Buffer,
+    index_buffer: Buffer,
+    ubo_buffer: Buffer,
+}
+
+impl InstancingSample {
+    fn draw_instances(
+        &self,
+        renderer: &Renderer,
+        cmd_list: &CommandList,
+        pipeline: &GraphicsPipeline,
+    ) -> Result<()> {
+        cmd_list.bind_graphics_pipeline(&renderer.device, pipeline)?;
+        cmd_list.bind_index_buffer_typed(&renderer.device, &self.index_buffer, IndexType::U16);
+        cmd_list.bind_vertex_buffers(
+            &renderer.device,
+            0,
+            &[&self.vertex_buffer, &self.instance_buffer],
+            &[0, 0],
+        );
+        cmd_list.bind_descriptor_sets(&renderer.device, pipeline, 0, &[self.bind_group]);
+        cmd_list.draw_instanced(
+            &renderer.device,
+            self.index_buffer.num_elements().unwrap(),
+            0,
+            0,
+            self.instance_buffer.num_elements().unwrap(),
+        );
+        Ok(())
+    }
+}
+
+impl App for InstancingSample {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create App Resources
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+        let depth_image = context.renderer.device.create_image(
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            ImageDescription {
+                format: Format::D32_SFLOAT,
+                usage: ImageUsage::Depth,
+                ..Default::default()
+            },
+        )?;
+
+        let vertex_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/instancing.vert.spv"),
+            Default::default(),
+        )?;
+        let fragment_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/instancing.frag.spv"),
+            Default::default(),
+        )?;
+        // Positions come from binding 0, and each instance's model matrix from binding 1, one
+        // column per attribute
+        let mut attribute_desc = vec![VertexAttributeDescription {
+            location: 0,
+            binding: 0,
+            format: Format::R32G32B32_SFLOAT.into(),
+            offset: 0,
+        }];
+        for column in 0..4 {
+            attribute_desc.push(VertexAttributeDescription {
+                location: 1 + column,
+                binding: 1,
+                format: Format::R32G32B32A32_SFLOAT.into(),
+                offset: column * std::mem::size_of::<[f32; 4]>() as u32,
+            });
+        }
+        let vertex_desc = VertexDescription {
+            binding_desc: vec![
+                VertexBindingDesc {
+                    binding: 0,
+                    stride: std::mem::size_of::<PositionVertex>() as u32,
+                    input_rate: VertexInputRate::VERTEX,
+                },
+                VertexBindingDesc {
+                    binding: 1,
+                    stride: std::mem::size_of::<Mat4>() as u32,
+                    input_rate: VertexInputRate::INSTANCE,
+                },
+            ],
+            attribute_desc,
+        };
+        // No fragment shader, and nothing written to the color attachment
+        let prepass_pipeline = context.renderer.device.create_graphics_pipeline(
+            &vertex_shader,
+            None,
+            GraphicsPipelineDescription {
+                name: Some("Depth Prepass Pipeline".to_owned()),
+                blending: vec![
+                    ColorBlendState::default().with_color_write_mask(ColorComponents::empty())
+                ],
+                depth_format: Some(Format::D32_SFLOAT),
+                vertex_desc: Some(vertex_desc.clone()),
+                ..Default::default()
+            },
+        )?;
+        // Depth is final after the prepass, only the nearest fragment of each pixel passes
+        let pipeline = context.renderer.device.create_graphics_pipeline(
+            &vertex_shader,
+            Some(&fragment_shader),
+            GraphicsPipelineDescription {
+                depth_format: Some(Format::D32_SFLOAT),
+                depth_write: false,
+                depth_compare: CompareOp::Equal,
+                vertex_desc: Some(vertex_desc),
+                ..Default::default()
+            },
+        )?;
+        let bind_group = BindGroup::new(
+            &context.renderer.device,
+            pipeline.bind_group_data(0).unwrap(),
+        )?;
+
+        let ubo_buffer = context.renderer.device.create_buffer_with_data(
+            &[
+                camera::look_to(
+                    Vec3::new(0.0, 12.0, -14.0),
+                    Vec3::new(0.0, -0.8, 1.0),
+                    Vec3::new(0.0, 1.0, 0.0),
+                ),
+                camera::new_infinite_perspective_proj(
+                    surface_rect.width() as f32 / surface_rect.height() as f32,
+                    30.0,
+                    0.01,
+                ),
+            ],
+            BufferDescription {
+                usage: BufferUsage::UNIFORM,
+                ..Default::default()
+            },
+        )?;
+        context
+            .renderer
+            .device
+            .write_bind_group(&[BindGroupBindInfo {
+                group: bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::Uniform(ubo_buffer.bind_info()),
+            }])?;
+
+        //
+        // Create Buffers
+        //
+        let sphere = SurfaceMesh::<PositionVertex>::uv_sphere::<8, 16>(0.3);
+        let vertex_buffer = context.renderer.device.create_buffer_with_data(
+            &sphere.vertices,
+            BufferDescription {
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+        // The sphere has few enough vertices to halve the size of its indices
+        let index_buffer = context.renderer.device.create_buffer_with_data(
+            &sphere.indices_u16().unwrap(),
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+        let transforms = (0..GRID_SIZE * GRID_SIZE)
+            .map(|idx| {
+                let x = (idx % GRID_SIZE) as f32 - (GRID_SIZE - 1) as f32 / 2.0;
+                let z = (idx / GRID_SIZE) as f32 - (GRID_SIZE - 1) as f32 / 2.0;
+                // Vary the height, so the grid doesn't look like a single flat mesh
+                let y = ((x * 0.5).sin() + (z * 0.5).cos()) * 0.5;
+                translate(Vec3::new(x, y, z))
+            })
+            .collect::<Vec<_>>();
+        let instance_buffer = context.renderer.device.create_buffer_with_data(
+            &transforms,
+            BufferDescription {
+                name: Some("Instance Transforms"),
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+
+        vertex_shader.destroy(&context.renderer.device);
+        fragment_shader.destroy(&context.renderer.device);
+
+        let depth_image_handle = context.renderer.resource_manager.insert_image(depth_image);
+
+        Ok(Self {
+            depth_image_handle,
+            prepass_pipeline,
+            pipeline,
+            bind_group,
+            vertex_buffer,
+            instance_buffer,
+            index_buffer,
+            ubo_buffer,
+        })
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .with_name("Depth Prepass")
+                .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
+                .set_depth_attachment(
+                    AttachmentType::Reference(self.depth_image_handle),
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::Store)
+                        .layout(Layout::DepthAttachment),
+                )
+                .add_output(RenderPassResource::Image(self.depth_image_handle))
+                .add_output(RenderPassResource::SwapchainImage)
+                .set_callback(allocator, |renderer, cmd_list| {
+                    self.draw_instances(renderer, cmd_list, &self.prepass_pipeline)
+                }),
+        );
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .with_name("Shading")
+                .add_color_attachment(AttachmentType::SwapchainImage, RenderAttachmentDesc::load())
+                .set_depth_attachment(
+                    AttachmentType::Reference(self.depth_image_handle),
+                    RenderAttachmentDesc::load()
+                        .store_op(AttachmentStoreOp::DontCare)
+                        .layout(Layout::DepthAttachment),
+                )
+                .add_input(RenderPassResource::Image(self.depth_image_handle))
+                .add_input(RenderPassResource::SwapchainImage)
+                .set_callback(allocator, |renderer, cmd_list| {
+                    self.draw_instances(renderer, cmd_list, &self.pipeline)
+                }),
+        );
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        renderer
+            .resource_manager
+            .images
+            .get_mut(self.depth_image_handle)
+            .unwrap()
+            .resize(&renderer.device, Size2D::new(width, height))?;
+        self.ubo_buffer.mem_copy(
+            util::offset_of!(InstancingUniformBufferObject, proj) as u64,
+            &[camera::new_infinite_perspective_proj(
+                width as f32 / height as f32,
+                30.0,
+                0.01,
+            )],
+        )?;
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.index_buffer.destroy(&renderer.device);
+        self.vertex_buffer.destroy(&renderer.device);
+        self.instance_buffer.destroy(&renderer.device);
+        self.ubo_buffer.destroy(&renderer.device);
+        self.prepass_pipeline.destroy(&renderer.device);
+        self.pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "instancing",
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let mut cinder = Cinder::<InstancingSample>::new(&sdl.window).unwrap();
+    cinder.run_game_loop(&mut sdl).unwrap();
+}
@@ -1,12 +1,12 @@
 use anyhow::Result;
 use cinder::{
-    App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData,
-    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, Format, GraphicsPipeline,
-    GraphicsPipelineDescription, Image, ImageDescription, ImageUsage, InitContext, Layout,
-    RenderAttachmentDesc, RenderGraph, RenderPass, Renderer, ResourceId,
+    App, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData, Buffer,
+    BufferDescription, BufferUsage, Bump, Cinder, Format, FrameBuffered, GraphicsPipeline,
+    GraphicsPipelineDescription, InitContext, InputState, Layout, RenderGraph, RenderPass,
+    Renderer, MAX_FRAMES_IN_FLIGHT,
 };
 use math::{mat::Mat4, size::Size2D, vec::Vec3};
-use scene::{ObjMesh, Scene, Vertex};
+use scene::{Aabb, ObjMesh, Scene, Vertex};
 
 use std::path::PathBuf;
 use util::{SdlContext, WindowDescription};
@@ -20,7 +20,7 @@ include!(concat!(
 ));
 
 impl Vertex for MeshVertex {
-    fn from_obj_mesh_index(mesh: &ObjMesh, i: usize) -> Self {
+    fn from_obj_mesh_index(mesh: &ObjMesh, i: usize, _bounds: &Aabb) -> Self {
         let i_pos = [
             mesh.positions[i * 3],
             mesh.positions[i * 3 + 1],
@@ -48,12 +48,12 @@ impl Vertex for MeshVertex {
 
 pub struct MeshSample {
     index_count: u32,
+    mesh_transform: Mat4,
     pipeline: GraphicsPipeline,
-    bind_group: BindGroup,
-    depth_image_handle: ResourceId<Image>,
+    bind_groups: FrameBuffered<BindGroup>,
     vertex_buffer: Buffer,
     index_buffer: Buffer,
-    ubo_buffer: Buffer,
+    ubo_buffers: FrameBuffered<Buffer>,
 }
 
 impl App for MeshSample {
@@ -62,14 +62,6 @@ impl App for MeshSample {
         // Create App Resources
         //
         let surface_rect = context.renderer.device.surface_rect();
-        let depth_image = context.renderer.device.create_image(
-            Size2D::new(surface_rect.width(), surface_rect.height()),
-            ImageDescription {
-                format: Format::D32_SFLOAT,
-                usage: ImageUsage::Depth,
-                ..Default::default()
-            },
-        )?;
         let vertex_shader = context.renderer.device.create_shader(
             include_bytes!("../shaders/spv/mesh.vert.spv"),
             Default::default(),
@@ -86,19 +78,23 @@ impl App for MeshSample {
                 ..Default::default()
             },
         )?;
-        let bind_group = BindGroup::new(
-            &context.renderer.device,
-            pipeline.bind_group_data(0).unwrap(),
-        )?;
+        let bind_groups = FrameBuffered::new(|_| {
+            BindGroup::new(
+                &context.renderer.device,
+                pipeline.bind_group_data(0).unwrap(),
+            )
+        })?;
 
-        let ubo_buffer = context.renderer.device.create_buffer(
+        // The model matrix changes every frame, so each frame in flight gets its own copy
+        let ubo_buffers = FrameBuffered::create_buffers(
+            &context.renderer.device,
             std::mem::size_of::<MeshUniformBufferObject>() as u64,
             BufferDescription {
                 usage: BufferUsage::UNIFORM,
                 ..Default::default()
             },
         )?;
-        ubo_buffer.mem_copy(
+        ubo_buffers.write_all(
             util::offset_of!(MeshUniformBufferObject, view) as u64,
             &[
                 camera::look_to(
@@ -126,22 +122,25 @@ impl App for MeshSample {
             &context.renderer.command_queue,
             Default::default(),
         )?;
-        context.renderer.device.write_bind_group(&[
-            BindGroupBindInfo {
-                group: bind_group,
-                dst_binding: 0,
-                data: BindGroupWriteData::Uniform(ubo_buffer.bind_info()),
-            },
-            BindGroupBindInfo {
-                group: bind_group,
-                dst_binding: 1,
-                data: BindGroupWriteData::SampledImage(texture.bind_info(
-                    &sampler,
-                    Layout::ShaderReadOnly,
-                    None,
-                )),
-            },
-        ])?;
+        for frame_index in 0..MAX_FRAMES_IN_FLIGHT {
+            let bind_group = *bind_groups.get(frame_index);
+            context.renderer.device.write_bind_group(&[
+                BindGroupBindInfo {
+                    group: bind_group,
+                    dst_binding: 0,
+                    data: BindGroupWriteData::Uniform(ubo_buffers.current_bind_info(frame_index)),
+                },
+                BindGroupBindInfo {
+                    group: bind_group,
+                    dst_binding: 1,
+                    data: BindGroupWriteData::SampledImage(texture.bind_info(
+                        &sampler,
+                        Layout::ShaderReadOnly,
+                        None,
+                    )),
+                },
+            ])?;
+        }
 
         let scene = Scene::<MeshVertex>::from_obj(
             PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -177,27 +176,26 @@ impl App for MeshSample {
         vertex_shader.destroy(&context.renderer.device);
         fragment_shader.destroy(&context.renderer.device);
 
-        let depth_image_handle = context.renderer.resource_manager.insert_image(depth_image);
-
         Ok(Self {
             index_count: mesh.indices.len() as u32,
-            depth_image_handle,
+            mesh_transform: mesh.transform(),
             pipeline,
-            bind_group,
+            bind_groups,
             vertex_buffer,
             index_buffer,
-            ubo_buffer,
+            ubo_buffers,
         })
     }
 
-    fn update(&mut self, renderer: &mut Renderer) -> Result<()> {
-        let scale =
-            (renderer.init_time().elapsed().as_secs_f32() / 5.0) * (2.0 * std::f32::consts::PI);
-        self.ubo_buffer.mem_copy(
+    fn update(&mut self, renderer: &mut Renderer, _input: &InputState) -> Result<()> {
+        let scale = (renderer.time.elapsed_seconds() / 5.0) * (2.0 * std::f32::consts::PI);
+        self.ubo_buffers.write(
+            renderer.current_frame_in_flight(),
             util::offset_of!(MeshUniformBufferObject, model) as u64,
             &[
                 Mat4::rotate(std::f32::consts::PI / 2.0, Vec3::new(1.0, 0.0, 0.0))
-                    * Mat4::rotate(scale, Vec3::new(0.0, 0.0, 1.0)),
+                    * Mat4::rotate(scale, Vec3::new(0.0, 0.0, 1.0))
+                    * self.mesh_transform,
             ],
         )?;
         Ok(())
@@ -212,24 +210,16 @@ impl App for MeshSample {
             allocator,
             RenderPass::new(allocator)
                 .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
-                .set_depth_attachment(
-                    AttachmentType::Reference(self.depth_image_handle),
-                    RenderAttachmentDesc {
-                        store_op: AttachmentStoreOp::DontCare,
-                        layout: Layout::DepthAttachment,
-                        clear_value: ClearValue::default_depth(),
-                        ..Default::default()
-                    },
-                )
+                .with_managed_depth(Format::D32_SFLOAT)
                 .set_callback(allocator, |renderer, cmd_list| {
-                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipeline);
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipeline)?;
                     cmd_list.bind_index_buffer(&renderer.device, &self.index_buffer);
                     cmd_list.bind_vertex_buffer(&renderer.device, &self.vertex_buffer);
                     cmd_list.bind_descriptor_sets(
                         &renderer.device,
                         &self.pipeline,
                         0,
-                        &[self.bind_group],
+                        &[*self.bind_groups.get(renderer.current_frame_in_flight())],
                     );
                     cmd_list.draw_offset(&renderer.device, self.index_count, 0, 0);
                     Ok(())
@@ -240,19 +230,13 @@ impl App for MeshSample {
 
     fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
         renderer.resize(width, height)?;
-        let depth_image = renderer
-            .resource_manager
-            .images
-            .get_mut(self.depth_image_handle)
-            .unwrap();
-        depth_image.resize(&renderer.device, Size2D::new(width, height))?;
         Ok(())
     }
 
     fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
         self.index_buffer.destroy(&renderer.device);
         self.vertex_buffer.destroy(&renderer.device);
-        self.ubo_buffer.destroy(&renderer.device);
+        self.ubo_buffers.destroy(&renderer.device);
         self.pipeline.destroy(&renderer.device);
         Ok(())
     }
@@ -169,7 +169,7 @@ impl App for ShaderHotReloadSample {
                         .graphics_pipelines
                         .get(self.pipeline_handle)
                         .ok_or(PipelineError::InvalidPipelineHandle)?;
-                    cmd_list.bind_graphics_pipeline(&renderer.device, pipeline);
+                    cmd_list.bind_graphics_pipeline(&renderer.device, pipeline)?;
                     cmd_list.bind_index_buffer(&renderer.device, &self.index_buffer);
                     cmd_list.bind_vertex_buffer(&renderer.device, &self.vertex_buffer);
                     cmd_list.bind_descriptor_sets(
@@ -26,6 +26,7 @@ layout(set = 0, binding = 1) uniform GlobalLightData {
 
 layout(set = 1, binding = 0 ) uniform ModelUniformBufferObject {
     mat4 model;
+    vec4 normal_matrix[3];
 } m_ubo;
 
 layout( push_constant ) uniform constants
@@ -38,7 +39,11 @@ layout( push_constant ) uniform constants
 void main() {
     o_pos = vec3(m_ubo.model * vec4(i_pos, 1.0));
     o_color = vec3(PushConstants.color);
-    o_normal = mat3(transpose(inverse(m_ubo.model))) * i_normal;
+    o_normal = mat3(
+        m_ubo.normal_matrix[0].xyz,
+        m_ubo.normal_matrix[1].xyz,
+        m_ubo.normal_matrix[2].xyz
+    ) * i_normal;
     o_light_pos = l_ubo.position.xyz;
     o_view_from = vec3(PushConstants.view_from);
     o_light_look_at = l_ubo.look_at.xyz;
@@ -1,19 +1,27 @@
 use anyhow::Result;
 use cinder::{
-    AddressMode, App, AttachmentLoadOp, AttachmentStoreOp, AttachmentType, BindGroup,
-    BindGroupBindInfo, BindGroupData, BindGroupWriteData, BorderColor, Buffer, BufferDescription,
-    BufferUsage, Bump, Cinder, ClearValue, Format, GraphicsPipeline, GraphicsPipelineDescription,
-    Image, ImageDescription, ImageUsage, InitContext, Layout, MipmapMode, RenderAttachmentDesc,
-    RenderGraph, RenderPass, RenderPassResource, Renderer, ResourceId, Sampler, SamplerDescription,
-    VertexAttributeDescription, VertexBindingDesc, VertexDescription, VertexInputRate,
+    AddressMode, App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo,
+    BindGroupData, BindGroupPushInfo, BindGroupWriteData, BorderColor, Buffer, BufferDescription,
+    BufferUsage, Bump, Cinder, ClearValue, CommandList, CullMode, DebugUiContext, Format,
+    FrontFace, GraphicsPipeline, GraphicsPipelineDescription, GraphicsPipelineInfo, Image,
+    ImageDescription, ImageUsage, InitContext, InputState, Layout, MipmapMode,
+    RenderAttachmentDesc, RenderGraph, RenderPass, RenderPassResource, Renderer, ResourceId,
+    Sampler, SamplerDescription, VertexAttributeDescription, VertexBindingDesc, VertexDescription,
+    VertexInputRate, FULLSCREEN_VERTEX_SHADER,
 };
-use math::{mat::Mat4, point::Point2D, size::Size2D, vec::Vec3};
+use egui_integration::egui;
+use geometry::PositionVertex;
+use math::{mat::Mat4, point::Point2D, rect::Rect2D, size::Size2D, vec::Vec3};
+use math_ext::{Mat3Padded, Mat4Ext};
 
 use util::{SdlContext, WindowDescription};
 
 pub const WINDOW_WIDTH: u32 = 1280;
 pub const WINDOW_HEIGHT: u32 = 1280;
 
+// Occlusion query that counts the visible samples of the light's bulb
+const LIGHT_BULB_QUERY: u32 = 0;
+
 include!(concat!(
     env!("CARGO_MANIFEST_DIR"),
     "/gen/light_shader_structs.rs"
@@ -26,93 +34,129 @@ include!(concat!(
     env!("CARGO_MANIFEST_DIR"),
     "/gen/shadow_map_shader_structs.rs"
 ));
-include!(concat!(
-    env!("CARGO_MANIFEST_DIR"),
-    "/gen/shadow_map_quad_shader_structs.rs"
-));
 
-struct TexturedQuadData {
-    index_buffer: Buffer,
-    vertex_buffer: Buffer,
+// Must match `ModelUniformBufferObject` in lit_mesh.vert
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ModelUniforms {
+    model: [f32; 16],
+    normal_matrix: Mat3Padded,
+}
+
+// Model transforms for every mesh, packed in a single uniform buffer.
+// Each draw either pushes its slice of the buffer as a push descriptor, or binds a shared
+// bind group with a dynamic offset when push descriptors are not supported.
+struct ModelTransforms {
+    buffer: Buffer,
+    bind_group: Option<BindGroup>,
+    size: u64,
+    stride: u64,
 }
 
-impl TexturedQuadData {
+impl ModelTransforms {
     pub fn new(
         renderer: &Renderer,
-        bind_group: BindGroup,
-        image: &Image,
-        sampler: &Sampler,
+        pipeline: &GraphicsPipeline,
+        count: usize,
+        use_push_descriptors: bool,
     ) -> Result<Self> {
-        let vertex_buffer = renderer.device.create_buffer_with_data(
-            &[
-                ShadowMapQuadVertex {
-                    i_pos: [-1.0, -1.0],
-                    i_uv: [0.0, 0.0],
-                },
-                ShadowMapQuadVertex {
-                    i_pos: [-0.25, -1.0],
-                    i_uv: [1.0, 0.0],
-                },
-                ShadowMapQuadVertex {
-                    i_pos: [-0.25, -0.25],
-                    i_uv: [1.0, 1.0],
-                },
-                ShadowMapQuadVertex {
-                    i_pos: [-1.0, -0.25],
-                    i_uv: [0.0, 1.0],
-                },
-            ],
-            BufferDescription {
-                usage: BufferUsage::VERTEX,
-                ..Default::default()
-            },
-        )?;
-        let index_buffer = renderer.device.create_buffer_with_data(
-            &[0, 1, 2, 2, 3, 0],
+        let size = std::mem::size_of::<LitMeshModelUniformBufferObject>() as u64;
+        let stride = size.next_multiple_of(renderer.device.min_uniform_buffer_offset_alignment());
+
+        let buffer = renderer.device.create_buffer(
+            stride * count as u64,
             BufferDescription {
-                usage: BufferUsage::INDEX,
+                usage: BufferUsage::UNIFORM,
                 ..Default::default()
             },
         )?;
+        let bind_group = if use_push_descriptors {
+            None
+        } else {
+            let bind_group =
+                BindGroup::new(&renderer.device, pipeline.bind_group_data(1).unwrap())?;
+            renderer.device.write_bind_group(&[BindGroupBindInfo {
+                group: bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::DynamicUniform(buffer.bind_info_range(0, size)),
+            }])?;
+            Some(bind_group)
+        };
 
-        renderer.device.write_bind_group(&[BindGroupBindInfo {
-            group: bind_group,
-            dst_binding: 0,
-            data: BindGroupWriteData::SampledImage(image.bind_info(
-                sampler,
-                Layout::DepthStencilReadOnly,
-                None,
-            )),
-        }])?;
+        let ret = Self {
+            buffer,
+            bind_group,
+            size,
+            stride,
+        };
+        for idx in 0..count {
+            ret.set(idx, Mat4::identity())?;
+        }
+        Ok(ret)
+    }
 
-        Ok(Self {
-            index_buffer,
-            vertex_buffer,
-        })
+    pub fn set(&self, idx: usize, model: Mat4) -> Result<()> {
+        self.buffer.mem_copy(
+            idx as u64 * self.stride,
+            &[ModelUniforms {
+                model: model.to_cols_array(),
+                normal_matrix: model.normal_matrix(),
+            }],
+        )?;
+        Ok(())
+    }
+
+    pub fn bind(
+        &self,
+        renderer: &Renderer,
+        cmd_list: &CommandList,
+        pipeline: &GraphicsPipeline,
+        idx: usize,
+    ) -> Result<()> {
+        let offset = idx as u64 * self.stride;
+        match self.bind_group {
+            Some(bind_group) => cmd_list.bind_descriptor_sets_dynamic(
+                &renderer.device,
+                pipeline,
+                1,
+                &[bind_group],
+                &[offset as u32],
+            ),
+            None => cmd_list.push_descriptor_set(
+                &renderer.device,
+                pipeline,
+                1,
+                &[BindGroupPushInfo {
+                    dst_binding: 0,
+                    data: BindGroupWriteData::Uniform(
+                        self.buffer.bind_info_range(offset, self.size),
+                    ),
+                }],
+            )?,
+        }
+        Ok(())
     }
 
     pub fn cleanup(&self, renderer: &Renderer) {
-        self.index_buffer.destroy(&renderer.device);
-        self.vertex_buffer.destroy(&renderer.device);
+        self.buffer.destroy(&renderer.device);
     }
 }
 
+const CUBE_MODEL_INDEX: usize = 0;
+const PLANE_MODEL_INDEX: usize = 1;
+
 struct MeshData {
     vertex_buffer: Buffer,
     index_buffer: Buffer,
-    model_bind_group: BindGroup,
-    shadow_texture_bind_group: BindGroup,
-    model_transform_buffer: Buffer,
+    model_index: usize,
 }
 
 impl MeshData {
     pub fn new<T: Copy>(
         renderer: &Renderer,
-        pipeline: &GraphicsPipeline,
-        shadow_texture: &Image,
-        sampler: &Sampler,
         vertex_buffer_data: &[T],
         index_buffer_data: &[u32],
+        model_index: usize,
     ) -> Result<Self> {
         let vertex_buffer = renderer.device.create_buffer_with_data(
             vertex_buffer_data,
@@ -129,65 +173,16 @@ impl MeshData {
             },
         )?;
 
-        let model_bind_group =
-            BindGroup::new(&renderer.device, pipeline.bind_group_data(1).unwrap())?;
-        let shadow_texture_bind_group =
-            BindGroup::new(&renderer.device, pipeline.bind_group_data(2).unwrap())?;
-
-        let model_transform_buffer = renderer.device.create_buffer(
-            std::mem::size_of::<LitMeshModelUniformBufferObject>() as u64,
-            BufferDescription {
-                usage: BufferUsage::UNIFORM,
-                ..Default::default()
-            },
-        )?;
-        model_transform_buffer.mem_copy(0, &[Mat4::identity()])?;
-        renderer.device.write_bind_group(&[BindGroupBindInfo {
-            group: model_bind_group,
-            dst_binding: 0,
-            data: BindGroupWriteData::Uniform(model_transform_buffer.bind_info()),
-        }])?;
-        renderer.device.write_bind_group(&[BindGroupBindInfo {
-            group: shadow_texture_bind_group,
-            dst_binding: 0,
-            data: BindGroupWriteData::SampledImage(shadow_texture.bind_info(
-                sampler,
-                Layout::DepthStencilReadOnly,
-                None,
-            )),
-        }])?;
-
         Ok(Self {
             vertex_buffer,
             index_buffer,
-            model_bind_group,
-            shadow_texture_bind_group,
-            model_transform_buffer,
+            model_index,
         })
     }
 
-    pub fn resize(
-        &self,
-        renderer: &Renderer,
-        shadow_texture: &Image,
-        sampler: &Sampler,
-    ) -> Result<()> {
-        renderer.device.write_bind_group(&[BindGroupBindInfo {
-            group: self.shadow_texture_bind_group,
-            dst_binding: 0,
-            data: BindGroupWriteData::SampledImage(shadow_texture.bind_info(
-                sampler,
-                Layout::DepthStencilReadOnly,
-                None,
-            )),
-        }])?;
-        Ok(())
-    }
-
     pub fn cleanup(&self, renderer: &Renderer) {
         self.index_buffer.destroy(&renderer.device);
         self.vertex_buffer.destroy(&renderer.device);
-        self.model_transform_buffer.destroy(&renderer.device);
     }
 }
 
@@ -211,11 +206,13 @@ struct FlashligthMesh {
     cylinder_ib: Buffer,
     cone_vb: Buffer,
     cone_ib: Buffer,
+    bulb_vb: Buffer,
+    bulb_ib: Buffer,
 }
 
 impl FlashligthMesh {
     fn new(renderer: &Renderer) -> Result<Self> {
-        let cylinder = geometry::SurfaceMesh::cylinder::<30>(0.3, 0.05);
+        let cylinder = geometry::SurfaceMesh::<PositionVertex>::cylinder::<30>(0.3, 0.05);
         let cylinder_vb = renderer.device.create_buffer_with_data(
             &cylinder.vertices,
             BufferDescription {
@@ -231,7 +228,7 @@ impl FlashligthMesh {
             },
         )?;
 
-        let cone = geometry::SurfaceMesh::cone::<30>(0.125, 0.1);
+        let cone = geometry::SurfaceMesh::<PositionVertex>::cone::<30>(0.125, 0.1);
         let cone_vb = renderer.device.create_buffer_with_data(
             &cone.vertices,
             BufferDescription {
@@ -247,11 +244,29 @@ impl FlashligthMesh {
             },
         )?;
 
+        let bulb = geometry::SurfaceMesh::<PositionVertex>::uv_sphere::<12, 24>(0.06);
+        let bulb_vb = renderer.device.create_buffer_with_data(
+            &bulb.vertices,
+            BufferDescription {
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+        let bulb_ib = renderer.device.create_buffer_with_data(
+            &bulb.indices,
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+
         Ok(Self {
             cylinder_vb,
             cylinder_ib,
             cone_vb,
             cone_ib,
+            bulb_vb,
+            bulb_ib,
         })
     }
 
@@ -261,6 +276,9 @@ impl FlashligthMesh {
 
         self.cone_vb.destroy(&renderer.device);
         self.cone_ib.destroy(&renderer.device);
+
+        self.bulb_vb.destroy(&renderer.device);
+        self.bulb_ib.destroy(&renderer.device);
     }
 }
 
@@ -409,11 +427,51 @@ pub struct SimpleLightSample {
     eye_camera: CameraData,
     light_data: LightData,
     light_camera: CameraData,
-    texture_bind_group: BindGroup,
-    quad_data: TexturedQuadData,
+    surface_size: Size2D<u32>,
+    model_transforms: ModelTransforms,
     cube_mesh_data: MeshData,
     plane_mesh_data: MeshData,
     show_shadow_map_image: bool,
+    light_bulb_samples: Option<u64>,
+}
+
+impl SimpleLightSample {
+    // Looked up every frame, so bind groups for a resized shadow map replace the old ones
+    fn shadow_map_bind_group(
+        &self,
+        renderer: &Renderer,
+        bind_group_data: &BindGroupData,
+        sampler: &Sampler,
+    ) -> Result<BindGroup> {
+        let shadow_map_image = renderer
+            .resource_manager
+            .images
+            .get(self.shadow_map_image_handle)
+            .unwrap();
+        renderer.bind_group_cache.get(
+            &renderer.device,
+            bind_group_data,
+            &[BindGroupPushInfo {
+                dst_binding: 0,
+                data: BindGroupWriteData::SampledImage(shadow_map_image.bind_info(
+                    sampler,
+                    Layout::DepthStencilReadOnly,
+                    None,
+                )),
+            }],
+        )
+    }
+
+    // Top-left corner of the window, where the shadow map is drawn
+    fn overlay_rect(&self) -> Rect2D<i32, u32> {
+        Rect2D::from_offset_and_size(
+            Point2D::new(0, 0),
+            Size2D::new(
+                self.surface_size.width() * 3 / 8,
+                self.surface_size.height() * 3 / 8,
+            ),
+        )
+    }
 }
 
 impl App for SimpleLightSample {
@@ -444,67 +502,93 @@ impl App for SimpleLightSample {
             Default::default(),
         )?;
 
-        let shadow_map_quad_vs = context.renderer.device.create_shader(
-            include_bytes!("../shaders/spv/shadow_map_quad.vert.spv"),
-            Default::default(),
-        )?;
+        let shadow_map_quad_vs = context
+            .renderer
+            .device
+            .create_shader(FULLSCREEN_VERTEX_SHADER, Default::default())?;
         let shadow_map_quad_fs = context.renderer.device.create_shader(
             include_bytes!("../shaders/spv/shadow_map_quad.frag.spv"),
             Default::default(),
         )?;
 
-        let lit_mesh_pipeline = context.renderer.device.create_graphics_pipeline(
-            &lit_mesh_vs,
-            Some(&lit_mesh_fs),
-            GraphicsPipelineDescription {
-                depth_format: Some(Format::D32_SFLOAT),
-                ..Default::default()
-            },
-        )?;
+        // The per-mesh model transform in set 1 is pushed per draw when supported,
+        // otherwise it is bound with a dynamic offset into a shared uniform buffer
+        let use_push_descriptors = context.renderer.device.supports_push_descriptor();
+        let (dynamic_uniforms, push_descriptor_set) = if use_push_descriptors {
+            (vec![], Some(1))
+        } else {
+            (vec![(1, 0)], None)
+        };
 
-        let light_caster_pipeline = context.renderer.device.create_graphics_pipeline(
-            &light_vs,
-            Some(&light_fs),
-            GraphicsPipelineDescription {
-                depth_format: Some(Format::D32_SFLOAT),
-                ..Default::default()
+        // The pipelines differ only slightly, so they are created together as derivatives of the
+        // first one
+        let pipelines = context.renderer.device.create_graphics_pipelines(&[
+            GraphicsPipelineInfo {
+                vertex_shader: &lit_mesh_vs,
+                fragment_shader: Some(&lit_mesh_fs),
+                desc: GraphicsPipelineDescription {
+                    depth_format: Some(Format::D32_SFLOAT),
+                    cull_mode: CullMode::Back,
+                    front_face: FrontFace::CounterClockwise,
+                    dynamic_uniforms: dynamic_uniforms.clone(),
+                    push_descriptor_set,
+                    ..Default::default()
+                },
             },
-        )?;
-
-        let shadow_map_depth_pipeline = context.renderer.device.create_graphics_pipeline(
-            &shadow_map_vs,
-            None,
-            GraphicsPipelineDescription {
-                color_format: None,
-                depth_format: Some(Format::D32_SFLOAT),
-                vertex_desc: Some(VertexDescription {
-                    binding_desc: vec![VertexBindingDesc {
-                        binding: 0,
-                        stride: std::mem::size_of::<LitMeshVertex>() as u32,
-                        input_rate: VertexInputRate::VERTEX,
-                    }],
-                    attribute_desc: vec![VertexAttributeDescription {
-                        location: 0,
-                        binding: 0,
-                        format: Format::R32G32B32_SFLOAT.into(),
-                        offset: 0,
-                    }],
-                }),
-                ..Default::default()
+            GraphicsPipelineInfo {
+                vertex_shader: &light_vs,
+                fragment_shader: Some(&light_fs),
+                desc: GraphicsPipelineDescription {
+                    depth_format: Some(Format::D32_SFLOAT),
+                    ..Default::default()
+                },
             },
-        )?;
-
-        let shadow_map_quad_pipeline = context.renderer.device.create_graphics_pipeline(
-            &shadow_map_quad_vs,
-            Some(&shadow_map_quad_fs),
-            Default::default(),
-        )?;
+            GraphicsPipelineInfo {
+                vertex_shader: &shadow_map_vs,
+                fragment_shader: None,
+                desc: GraphicsPipelineDescription {
+                    color_formats: vec![],
+                    depth_format: Some(Format::D32_SFLOAT),
+                    // Only back faces are written to the shadow map, which keeps lit front
+                    // faces from shadowing themselves without a depth bias that detaches
+                    // shadows from their casters
+                    cull_mode: CullMode::Front,
+                    front_face: FrontFace::CounterClockwise,
+                    dynamic_uniforms,
+                    push_descriptor_set,
+                    vertex_desc: Some(VertexDescription {
+                        binding_desc: vec![VertexBindingDesc {
+                            binding: 0,
+                            stride: std::mem::size_of::<LitMeshVertex>() as u32,
+                            input_rate: VertexInputRate::VERTEX,
+                        }],
+                        attribute_desc: vec![VertexAttributeDescription {
+                            location: 0,
+                            binding: 0,
+                            format: Format::R32G32B32_SFLOAT.into(),
+                            offset: 0,
+                        }],
+                    }),
+                    ..Default::default()
+                },
+            },
+            GraphicsPipelineInfo {
+                vertex_shader: &shadow_map_quad_vs,
+                fragment_shader: Some(&shadow_map_quad_fs),
+                desc: Default::default(),
+            },
+        ])?;
+        let Ok([lit_mesh, light_caster, shadow_map_depth, shadow_map_quad]) =
+            <[GraphicsPipeline; 4]>::try_from(pipelines)
+        else {
+            unreachable!("one pipeline is created per info");
+        };
 
         let pipelines = Pipelines {
-            lit_mesh: lit_mesh_pipeline,
-            light_caster: light_caster_pipeline,
-            shadow_map_depth: shadow_map_depth_pipeline,
-            shadow_map_quad: shadow_map_quad_pipeline,
+            lit_mesh,
+            light_caster,
+            shadow_map_depth,
+            shadow_map_quad,
         };
 
         //
@@ -537,14 +621,6 @@ impl App for SimpleLightSample {
             Some(&light_data),
         )?;
 
-        //
-        // Create Bind Groups
-        //
-        let texture_bind_group = BindGroup::new(
-            &context.renderer.device,
-            pipelines.shadow_map_quad.bind_group_data(0).unwrap(),
-        )?;
-
         //
         // Create Images
         //
@@ -584,18 +660,15 @@ impl App for SimpleLightSample {
         // Create Meshes
         //
 
-        let quad_data = TexturedQuadData::new(
+        let model_transforms = ModelTransforms::new(
             context.renderer,
-            texture_bind_group,
-            &shadow_map_image,
-            &sampler,
+            &pipelines.lit_mesh,
+            2,
+            use_push_descriptors,
         )?;
 
         let cube_mesh_data = MeshData::new(
             context.renderer,
-            &pipelines.lit_mesh,
-            &shadow_map_image,
-            &shadow_map_sampler,
             &[
                 // Plane 1
                 LitMeshVertex {
@@ -701,39 +774,30 @@ impl App for SimpleLightSample {
                 },
             ],
             &[
-                0, 1, 2, 2, 1, 3, // First plane
-                5, 4, 7, 7, 4, 6, // Second plane
-                9, 8, 11, 11, 8, 10, // Third Plane
+                0, 2, 1, 2, 3, 1, // First plane
+                5, 7, 4, 7, 6, 4, // Second plane
+                9, 11, 8, 11, 10, 8, // Third Plane
                 13, 12, 15, 15, 12, 14, // Fourth Plane
-                17, 16, 19, 19, 16, 18, // Fifth Plane
+                17, 19, 16, 19, 18, 16, // Fifth Plane
                 21, 20, 23, 23, 20, 22, // Sixth Plane
             ],
+            CUBE_MODEL_INDEX,
         )?;
 
+        let plane = geometry::SurfaceMesh::<geometry::Vertex>::plane(10.0, 10.0, 9);
+        let plane_vertices = plane
+            .vertices
+            .iter()
+            .map(|vertex| LitMeshVertex {
+                i_pos: [vertex.pos[0], vertex.pos[1] - 1.0, vertex.pos[2]],
+                i_normal: vertex.normal,
+            })
+            .collect::<Vec<_>>();
         let plane_mesh_data = MeshData::new(
             &context.renderer,
-            &pipelines.lit_mesh,
-            &shadow_map_image,
-            &shadow_map_sampler,
-            &[
-                LitMeshVertex {
-                    i_pos: [-5.0, -1.0, 5.0],
-                    i_normal: [0.0, 1.0, 0.0],
-                },
-                LitMeshVertex {
-                    i_pos: [5.0, -1.0, 5.0],
-                    i_normal: [0.0, 1.0, 0.0],
-                },
-                LitMeshVertex {
-                    i_pos: [-5.0, -1.0, -5.0],
-                    i_normal: [0.0, 1.0, 0.0],
-                },
-                LitMeshVertex {
-                    i_pos: [5.0, -1.0, -5.0],
-                    i_normal: [0.0, 1.0, 0.0],
-                },
-            ],
-            &[0, 1, 2, 2, 1, 3],
+            &plane_vertices,
+            &plane.indices,
+            PLANE_MODEL_INDEX,
         )?;
 
         //
@@ -752,6 +816,23 @@ impl App for SimpleLightSample {
             .renderer
             .resource_manager
             .insert_image(shadow_map_image);
+        // Both images follow the surface size, and the shadow map needs to be back in the layout
+        // its reads expect after being recreated
+        context
+            .renderer
+            .register_screen_sized_image(depth_image_handle, None);
+        context.renderer.register_screen_sized_image(
+            shadow_map_image_handle,
+            Some(Box::new(|renderer: &Renderer, image: &Image| {
+                renderer.command_queue.transition_image(
+                    &renderer.device,
+                    image,
+                    ImageUsage::Depth,
+                    Layout::Undefined,
+                    Layout::DepthStencilReadOnly,
+                )
+            })),
+        );
 
         Ok(Self {
             pipelines,
@@ -763,21 +844,25 @@ impl App for SimpleLightSample {
             eye_camera,
             light_data,
             light_camera,
-            texture_bind_group,
-            quad_data,
+            surface_size: Size2D::new(surface_rect.width(), surface_rect.height()),
+            model_transforms,
             cube_mesh_data,
             plane_mesh_data,
             show_shadow_map_image: false,
+            light_bulb_samples: None,
         })
     }
 
-    fn update(&mut self, renderer: &mut Renderer) -> Result<()> {
-        let elapsed = renderer.init_time().elapsed().as_secs_f32();
+    fn update(&mut self, renderer: &mut Renderer, _input: &InputState) -> Result<()> {
+        self.light_bulb_samples = renderer.occlusion_queries.sample_count(LIGHT_BULB_QUERY);
+
+        let elapsed = renderer.time.elapsed_seconds();
         let scale = (elapsed / 2.5) * (2.0 * std::f32::consts::PI);
 
-        self.cube_mesh_data
-            .model_transform_buffer
-            .mem_copy(0, &[Mat4::rotate(scale, Vec3::new(0.0, 1.0, 0.0))])?;
+        self.model_transforms.set(
+            self.cube_mesh_data.model_index,
+            Mat4::rotate(scale, Vec3::new(0.0, 1.0, 0.0)),
+        )?;
 
         let aspect_ratio = renderer.device.surface_aspect_ratio();
         self.light_data.update(elapsed, aspect_ratio)?;
@@ -807,14 +892,12 @@ impl App for SimpleLightSample {
             RenderPass::new(&allocator)
                 .set_depth_attachment(
                     AttachmentType::Reference(self.shadow_map_image_handle),
-                    RenderAttachmentDesc {
-                        store_op: AttachmentStoreOp::Store,
-                        layout: Layout::DepthAttachment,
-                        clear_value: ClearValue::default_depth(),
-                        ..Default::default()
-                    },
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::Store)
+                        .layout(Layout::DepthAttachment),
                 )
                 .add_output(RenderPassResource::Image(self.shadow_map_image_handle))
+                .with_name("Shadow Map")
                 .with_flipped_viewport(false)
                 .set_callback(&allocator, |renderer, cmd_list| {
                     cmd_list.bind_descriptor_sets(
@@ -823,16 +906,18 @@ impl App for SimpleLightSample {
                         0,
                         &[self.light_camera.bind_group],
                     );
-                    cmd_list
-                        .bind_graphics_pipeline(&renderer.device, &self.pipelines.shadow_map_depth);
+                    cmd_list.bind_graphics_pipeline(
+                        &renderer.device,
+                        &self.pipelines.shadow_map_depth,
+                    )?;
 
                     // Draw Cube
-                    cmd_list.bind_descriptor_sets(
-                        &renderer.device,
+                    self.model_transforms.bind(
+                        renderer,
+                        cmd_list,
                         &self.pipelines.shadow_map_depth,
-                        1,
-                        &[self.cube_mesh_data.model_bind_group],
-                    );
+                        self.cube_mesh_data.model_index,
+                    )?;
                     cmd_list.bind_index_buffer(&renderer.device, &self.cube_mesh_data.index_buffer);
                     cmd_list
                         .bind_vertex_buffer(&renderer.device, &self.cube_mesh_data.vertex_buffer);
@@ -844,12 +929,12 @@ impl App for SimpleLightSample {
                     );
 
                     // Draw Plane
-                    cmd_list.bind_descriptor_sets(
-                        &renderer.device,
+                    self.model_transforms.bind(
+                        renderer,
+                        cmd_list,
                         &self.pipelines.shadow_map_depth,
-                        1,
-                        &[self.plane_mesh_data.model_bind_group],
-                    );
+                        self.plane_mesh_data.model_index,
+                    )?;
                     cmd_list
                         .bind_index_buffer(&renderer.device, &self.plane_mesh_data.index_buffer);
                     cmd_list
@@ -870,24 +955,19 @@ impl App for SimpleLightSample {
             RenderPass::new(&allocator)
                 .add_color_attachment(
                     AttachmentType::SwapchainImage,
-                    RenderAttachmentDesc {
-                        clear_value: ClearValue::Color {
-                            color: [0.4, 0.4, 0.4, 1.0],
-                        },
-                        ..Default::default()
-                    },
+                    RenderAttachmentDesc::clear(ClearValue::Color {
+                        color: [0.4, 0.4, 0.4, 1.0],
+                    }),
                 )
                 .set_depth_attachment(
                     AttachmentType::Reference(self.depth_image_handle),
-                    RenderAttachmentDesc {
-                        store_op: AttachmentStoreOp::DontCare,
-                        layout: Layout::DepthAttachment,
-                        clear_value: ClearValue::default_depth(),
-                        ..Default::default()
-                    },
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::DontCare)
+                        .layout(Layout::DepthAttachment),
                 )
                 .add_input(RenderPassResource::Image(self.shadow_map_image_handle))
                 .add_output(RenderPassResource::SwapchainImage)
+                .with_name("Lit Meshes")
                 .with_flipped_viewport(false)
                 .set_callback(allocator, |renderer, cmd_list| {
                     // Bind Mesh Data
@@ -897,26 +977,36 @@ impl App for SimpleLightSample {
                         0,
                         &[self.eye_camera.bind_group],
                     );
-                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipelines.lit_mesh);
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipelines.lit_mesh)?;
 
-                    let scale = (renderer.init_time().elapsed().as_secs_f32() / 5.0)
-                        * (2.0 * std::f32::consts::PI);
+                    let scale =
+                        (renderer.time.elapsed_seconds() / 5.0) * (2.0 * std::f32::consts::PI);
                     let light_color = [
                         (scale.sin() + 1.0) / 2.0,
                         (scale.cos() + 1.0) / 2.0,
                         ((scale * 1.5).cos() + 1.0) / 2.0,
                     ];
 
-                    // Draw Cube
+                    // Both meshes sample the same shadow map
+                    let shadow_map_bind_group = self.shadow_map_bind_group(
+                        renderer,
+                        self.pipelines.lit_mesh.bind_group_data(2).unwrap(),
+                        &self.shadow_map_sampler,
+                    )?;
                     cmd_list.bind_descriptor_sets(
                         &renderer.device,
                         &self.pipelines.lit_mesh,
-                        1,
-                        &[
-                            self.cube_mesh_data.model_bind_group,
-                            self.cube_mesh_data.shadow_texture_bind_group,
-                        ],
+                        2,
+                        &[shadow_map_bind_group],
                     );
+
+                    // Draw Cube
+                    self.model_transforms.bind(
+                        renderer,
+                        cmd_list,
+                        &self.pipelines.lit_mesh,
+                        self.cube_mesh_data.model_index,
+                    )?;
                     cmd_list.bind_index_buffer(&renderer.device, &self.cube_mesh_data.index_buffer);
                     cmd_list
                         .bind_vertex_buffer(&renderer.device, &self.cube_mesh_data.vertex_buffer);
@@ -938,15 +1028,12 @@ impl App for SimpleLightSample {
                     );
 
                     // Draw Plane
-                    cmd_list.bind_descriptor_sets(
-                        &renderer.device,
+                    self.model_transforms.bind(
+                        renderer,
+                        cmd_list,
                         &self.pipelines.lit_mesh,
-                        1,
-                        &[
-                            self.plane_mesh_data.model_bind_group,
-                            self.plane_mesh_data.shadow_texture_bind_group,
-                        ],
-                    );
+                        self.plane_mesh_data.model_index,
+                    )?;
 
                     cmd_list
                         .bind_index_buffer(&renderer.device, &self.plane_mesh_data.index_buffer);
@@ -976,7 +1063,8 @@ impl App for SimpleLightSample {
                         0,
                         &[self.eye_camera.bind_group],
                     );
-                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipelines.light_caster);
+                    cmd_list
+                        .bind_graphics_pipeline(&renderer.device, &self.pipelines.light_caster)?;
                     cmd_list.set_vertex_bytes(
                         &renderer.device,
                         &self.pipelines.light_caster,
@@ -1014,100 +1102,88 @@ impl App for SimpleLightSample {
                         0,
                     );
 
+                    // Drawn last, so anything that could occlude it is already in the depth buffer
+                    let query_pool = renderer.occlusion_queries.query_pool();
+                    cmd_list.begin_occlusion_query(&renderer.device, query_pool, LIGHT_BULB_QUERY);
+                    cmd_list
+                        .bind_index_buffer(&renderer.device, &self.light_data.flashlight.bulb_ib);
+                    cmd_list
+                        .bind_vertex_buffer(&renderer.device, &self.light_data.flashlight.bulb_vb);
+                    cmd_list.draw_offset(
+                        &renderer.device,
+                        self.light_data.flashlight.bulb_ib.num_elements().unwrap(),
+                        0,
+                        0,
+                    );
+                    cmd_list.end_occlusion_query(&renderer.device, query_pool, LIGHT_BULB_QUERY);
+
                     Ok(())
                 }),
         );
 
-        if self.show_shadow_map_image {
-            graph.add_pass(
-                &allocator,
-                RenderPass::new(&allocator)
-                    .add_color_attachment(
-                        AttachmentType::SwapchainImage,
-                        RenderAttachmentDesc {
-                            load_op: AttachmentLoadOp::Load,
-                            ..Default::default()
-                        },
-                    )
-                    .add_input(RenderPassResource::Image(self.shadow_map_image_handle))
-                    .add_input(RenderPassResource::SwapchainImage)
-                    .with_flipped_viewport(false)
-                    .set_callback(allocator, |renderer, cmd_list| {
-                        cmd_list.bind_graphics_pipeline(
-                            &renderer.device,
-                            &self.pipelines.shadow_map_quad,
-                        );
-                        cmd_list.bind_descriptor_sets(
-                            &renderer.device,
-                            &self.pipelines.shadow_map_quad,
-                            0,
-                            &[self.texture_bind_group],
-                        );
-                        cmd_list.bind_index_buffer(&renderer.device, &self.quad_data.index_buffer);
-                        cmd_list
-                            .bind_vertex_buffer(&renderer.device, &self.quad_data.vertex_buffer);
-                        cmd_list.draw_offset(
-                            &renderer.device,
-                            self.quad_data.index_buffer.num_elements().unwrap(),
-                            0,
-                            0,
-                        );
-
-                        Ok(())
-                    }),
-            );
-        }
+        graph.add_pass(
+            &allocator,
+            RenderPass::new(&allocator)
+                .add_color_attachment(AttachmentType::SwapchainImage, RenderAttachmentDesc::load())
+                .add_input(RenderPassResource::Image(self.shadow_map_image_handle))
+                .add_input(RenderPassResource::SwapchainImage)
+                .with_name("Shadow Map Quad")
+                .with_flipped_viewport(false)
+                .with_viewport(self.overlay_rect())
+                .enabled(self.show_shadow_map_image)
+                .set_callback(allocator, |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(
+                        &renderer.device,
+                        &self.pipelines.shadow_map_quad,
+                    )?;
+                    let shadow_map_bind_group = self.shadow_map_bind_group(
+                        renderer,
+                        self.pipelines.shadow_map_quad.bind_group_data(0).unwrap(),
+                        &self.sampler,
+                    )?;
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.pipelines.shadow_map_quad,
+                        0,
+                        &[shadow_map_bind_group],
+                    );
+                    cmd_list.draw_fullscreen_triangle(&renderer.device);
+
+                    Ok(())
+                }),
+        );
         Ok(())
     }
 
-    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
-        renderer
-            .resource_manager
-            .images
-            .get_mut(self.depth_image_handle)
-            .unwrap()
-            .resize(&renderer.device, Size2D::new(width, height))?;
-        renderer
-            .resource_manager
-            .images
-            .get_mut(self.shadow_map_image_handle)
-            .unwrap()
-            .resize(&renderer.device, Size2D::new(width, height))?;
-
-        let shadow_map_image = renderer
-            .resource_manager
-            .images
-            .get(self.shadow_map_image_handle)
-            .unwrap();
-        renderer.command_queue.transition_image(
-            &renderer.device,
-            shadow_map_image,
-            ImageUsage::Depth,
-            Layout::Undefined,
-            Layout::DepthStencilReadOnly,
-        )?;
-        renderer.device.write_bind_group(&[BindGroupBindInfo {
-            group: self.texture_bind_group,
-            dst_binding: 0,
-            data: BindGroupWriteData::SampledImage(shadow_map_image.bind_info(
-                &self.sampler,
-                Layout::DepthStencilReadOnly,
-                None,
-            )),
-        }])?;
+    fn draw_debug_ui(&mut self, context: &DebugUiContext) {
+        egui::Window::new("Shadow Map").show(context, |ui| {
+            ui.checkbox(&mut self.show_shadow_map_image, "Show Shadow Map");
+        });
+        egui::Window::new("Light").show(context, |ui| match self.light_bulb_samples {
+            Some(samples) => {
+                ui.label(format!("Visible bulb samples: {samples}"));
+                ui.label(if samples > 0 {
+                    "Light is visible"
+                } else {
+                    "Light is occluded"
+                });
+            }
+            None => {
+                ui.label("Visible bulb samples: pending");
+            }
+        });
+    }
 
-        self.cube_mesh_data
-            .resize(&renderer, shadow_map_image, &self.shadow_map_sampler)?;
-        self.plane_mesh_data
-            .resize(&renderer, shadow_map_image, &self.shadow_map_sampler)?;
+    fn resize(&mut self, _renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        self.surface_size = Size2D::new(width, height);
         Ok(())
     }
 
     fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
         self.cube_mesh_data.cleanup(&renderer);
         self.plane_mesh_data.cleanup(&renderer);
+        self.model_transforms.cleanup(&renderer);
         self.light_data.cleanup(&renderer);
-        self.quad_data.cleanup(&renderer);
         self.eye_camera.cleanup(&renderer);
         self.light_camera.cleanup(&renderer);
         self.sampler.destroy(&renderer.device);
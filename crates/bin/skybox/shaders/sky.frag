@@ -0,0 +1,32 @@
+#version 460
+
+layout (location = 0) in vec2 i_ndc;
+
+layout (location = 0) out vec4 uFragColor;
+
+// Must match `SkyConstants` in main.rs
+layout(push_constant) uniform constants
+{
+    // Camera basis, `right` and `up` are scaled by the extent of the view at distance 1
+    vec4 right;
+    vec4 up;
+    vec4 forward;
+    vec4 sun_direction;
+};
+
+const vec3 HORIZON_COLOR = vec3(0.85, 0.9, 1.0);
+const vec3 ZENITH_COLOR = vec3(0.2, 0.45, 0.85);
+const vec3 GROUND_COLOR = vec3(0.3, 0.28, 0.25);
+const vec3 SUN_COLOR = vec3(1.0, 0.95, 0.8);
+
+void main() {
+    vec3 dir = normalize(forward.xyz + i_ndc.x * right.xyz + i_ndc.y * up.xyz);
+
+    vec3 color = dir.y > 0.0
+        ? mix(HORIZON_COLOR, ZENITH_COLOR, pow(dir.y, 0.5))
+        : mix(HORIZON_COLOR, GROUND_COLOR, pow(-dir.y, 0.3));
+    float sun = smoothstep(0.998, 0.999, dot(dir, normalize(sun_direction.xyz)));
+    color = mix(color, SUN_COLOR, sun);
+
+    uFragColor = vec4(color, 1.0);
+}
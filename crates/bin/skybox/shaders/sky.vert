@@ -0,0 +1,10 @@
+#version 460
+
+layout (location = 0) out vec2 o_ndc;
+
+void main() {
+    vec2 uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    o_ndc = uv * 2.0 - 1.0;
+    // Depth is reversed, so the far plane is at 0
+    gl_Position = vec4(o_ndc, 0.0, 1.0);
+}
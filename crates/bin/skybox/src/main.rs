@@ -0,0 +1,410 @@
+use anyhow::Result;
+use cinder::{
+    App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData,
+    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, CompareOp, Format,
+    GraphicsPipeline, GraphicsPipelineDescription, Image, ImageDescription, ImageUsage,
+    InitContext, InputState, Layout, RenderAttachmentDesc, RenderGraph, RenderPass, Renderer,
+    ResourceId,
+};
+use geometry::{SurfaceMesh, Vertex};
+use math::{mat::Mat4, size::Size2D, vec::Vec3};
+use math_ext::{Mat3Padded, Mat4Ext};
+use util::{SdlContext, WindowDescription};
+
+pub const WINDOW_WIDTH: u32 = 1280;
+pub const WINDOW_HEIGHT: u32 = 1280;
+
+const Y_FOV: f32 = 30.0;
+const Z_NEAR: f32 = 0.01;
+
+// Index of the sphere in `shape_draws`, which bounces on the plane
+const BALL_SHAPE: usize = 1;
+const BALL_RADIUS: f32 = 0.5;
+const GRAVITY: f32 = -9.8;
+
+include!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/gen/mesh_shader_structs.rs"
+));
+
+#[rustfmt::skip]
+fn translate(pos: Vec3) -> Mat4 {
+    Mat4::from_data(
+        1.0, 0.0, 0.0, pos.x(),
+        0.0, 1.0, 0.0, pos.y(),
+        0.0, 0.0, 1.0, pos.z(),
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+// Must match the push constants in mesh.vert
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ModelConstants {
+    model: [f32; 16],
+    normal_matrix: Mat3Padded,
+}
+
+impl ModelConstants {
+    fn new(model: &Mat4) -> Self {
+        Self {
+            model: model.to_cols_array(),
+            normal_matrix: model.normal_matrix(),
+        }
+    }
+}
+
+// Must match the push constants in sky.frag
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct SkyConstants {
+    right: [f32; 4],
+    up: [f32; 4],
+    forward: [f32; 4],
+    sun_direction: [f32; 4],
+}
+
+impl SkyConstants {
+    // Inverts the projection for directions, so the sky lines up with the scene
+    fn new(front: Vec3, aspect_ratio: f32) -> Self {
+        let world_up = Vec3::new(0.0, 1.0, 0.0);
+        let front = front.normalized();
+        let side = world_up.cross(&front).normalized();
+        let up = front.cross(&side);
+        // Matches `camera::new_infinite_perspective_proj`
+        let f = 1.0 / (Y_FOV / 2.0).tan();
+        let sun_direction = Vec3::new(4.0, 3.0, 2.0).normalized();
+
+        Self {
+            right: [
+                side.x() * aspect_ratio / f,
+                side.y() * aspect_ratio / f,
+                side.z() * aspect_ratio / f,
+                0.0,
+            ],
+            up: [up.x() / f, up.y() / f, up.z() / f, 0.0],
+            forward: [front.x(), front.y(), front.z(), 0.0],
+            sun_direction: [sun_direction.x(), sun_direction.y(), sun_direction.z(), 0.0],
+        }
+    }
+}
+
+// Advanced in fixed steps, so it bounces the same way at any frame rate
+#[derive(Debug)]
+struct Ball {
+    height: f32,
+    previous_height: f32,
+    velocity: f32,
+}
+
+impl Ball {
+    fn new(height: f32) -> Self {
+        Self {
+            height,
+            previous_height: height,
+            velocity: 0.0,
+        }
+    }
+
+    fn step(&mut self, dt: f32) {
+        self.previous_height = self.height;
+        self.velocity += GRAVITY * dt;
+        self.height += self.velocity * dt;
+        // Bounces without losing energy, reflecting the distance it went through the plane
+        if self.height < BALL_RADIUS {
+            self.height = 2.0 * BALL_RADIUS - self.height;
+            self.velocity = -self.velocity;
+        }
+    }
+
+    // Frames usually land between fixed steps
+    fn interpolated_height(&self, alpha: f32) -> f32 {
+        self.previous_height + (self.height - self.previous_height) * alpha
+    }
+}
+
+#[derive(Debug)]
+pub struct ShapeDraw {
+    position: Vec3,
+    vertex_buffer_offset: i32,
+    index_buffer_offset: u32,
+    num_indices: u32,
+}
+
+pub struct SkyboxSample {
+    depth_image_handle: ResourceId<Image>,
+    mesh_pipeline: GraphicsPipeline,
+    sky_pipeline: GraphicsPipeline,
+    bind_group: BindGroup,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    ubo_buffer: Buffer,
+    shape_draws: Vec<ShapeDraw>,
+    ball: Ball,
+    sky_constants: SkyConstants,
+}
+
+impl App for SkyboxSample {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create App Resources
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+        let depth_image = context.renderer.device.create_image(
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            ImageDescription {
+                format: Format::D32_SFLOAT,
+                usage: ImageUsage::Depth,
+                ..Default::default()
+            },
+        )?;
+
+        let mesh_vs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/mesh.vert.spv"),
+            Default::default(),
+        )?;
+        let mesh_fs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/mesh.frag.spv"),
+            Default::default(),
+        )?;
+        let mesh_pipeline = context.renderer.device.create_graphics_pipeline(
+            &mesh_vs,
+            Some(&mesh_fs),
+            GraphicsPipelineDescription {
+                depth_format: Some(Format::D32_SFLOAT),
+                ..Default::default()
+            },
+        )?;
+
+        let sky_vs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/sky.vert.spv"),
+            Default::default(),
+        )?;
+        let sky_fs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/sky.frag.spv"),
+            Default::default(),
+        )?;
+        // The sky is drawn at the far plane after the scene, and only shows where nothing else was
+        // drawn. Depth is reversed, so this is the usual less-or-equal test
+        let sky_pipeline = context.renderer.device.create_graphics_pipeline(
+            &sky_vs,
+            Some(&sky_fs),
+            GraphicsPipelineDescription {
+                depth_format: Some(Format::D32_SFLOAT),
+                depth_write: false,
+                depth_compare: CompareOp::GreaterOrEqual,
+                ..Default::default()
+            },
+        )?;
+
+        let bind_group = BindGroup::new(
+            &context.renderer.device,
+            mesh_pipeline.bind_group_data(0).unwrap(),
+        )?;
+        let ubo_buffer = context.renderer.device.create_buffer(
+            std::mem::size_of::<MeshUniformBufferObject>() as u64,
+            BufferDescription {
+                usage: BufferUsage::UNIFORM,
+                ..Default::default()
+            },
+        )?;
+        context
+            .renderer
+            .device
+            .write_bind_group(&[BindGroupBindInfo {
+                group: bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::Uniform(ubo_buffer.bind_info()),
+            }])?;
+
+        //
+        // Create Shapes
+        //
+        let shapes: [(Vec3, SurfaceMesh<Vertex>); 4] = [
+            (Vec3::new(0.0, 0.0, 0.0), SurfaceMesh::plane(8.0, 8.0, 7)),
+            (
+                Vec3::new(-1.5, BALL_RADIUS, 0.0),
+                SurfaceMesh::uv_sphere::<16, 32>(BALL_RADIUS),
+            ),
+            (
+                Vec3::new(0.0, 0.5, 1.5),
+                SurfaceMesh::cylinder::<32>(1.0, 0.5),
+            ),
+            (
+                Vec3::new(1.5, 0.5, 0.0),
+                SurfaceMesh::capsule::<32>(0.5, 0.3),
+            ),
+        ];
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut shape_draws = Vec::with_capacity(shapes.len());
+        for (position, shape) in shapes {
+            shape_draws.push(ShapeDraw {
+                position,
+                vertex_buffer_offset: vertices.len() as i32,
+                index_buffer_offset: indices.len() as u32,
+                num_indices: shape.indices.len() as u32,
+            });
+            vertices.extend(shape.vertices);
+            indices.extend(shape.indices);
+        }
+
+        let vertex_buffer = context.renderer.device.create_buffer_with_data(
+            &vertices,
+            BufferDescription {
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+        let index_buffer = context.renderer.device.create_buffer_with_data(
+            &indices,
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+
+        //
+        // Cleanup
+        //
+        mesh_vs.destroy(&context.renderer.device);
+        mesh_fs.destroy(&context.renderer.device);
+        sky_vs.destroy(&context.renderer.device);
+        sky_fs.destroy(&context.renderer.device);
+
+        let depth_image_handle = context.renderer.resource_manager.insert_image(depth_image);
+
+        Ok(Self {
+            depth_image_handle,
+            mesh_pipeline,
+            sky_pipeline,
+            bind_group,
+            vertex_buffer,
+            index_buffer,
+            ubo_buffer,
+            shape_draws,
+            ball: Ball::new(2.5),
+            sky_constants: Default::default(),
+        })
+    }
+
+    fn fixed_update(&mut self, dt: f32) -> Result<()> {
+        self.ball.step(dt);
+        Ok(())
+    }
+
+    fn update(&mut self, renderer: &mut Renderer, _input: &InputState) -> Result<()> {
+        let ball_draw = &mut self.shape_draws[BALL_SHAPE];
+        ball_draw.position = Vec3::new(
+            ball_draw.position.x(),
+            self.ball
+                .interpolated_height(renderer.time.fixed_step_alpha()),
+            ball_draw.position.z(),
+        );
+
+        // Orbit the scene, so the sky can be seen from every side
+        let angle = (renderer.time.elapsed_seconds() / 20.0) * (2.0 * std::f32::consts::PI);
+        let eye = Vec3::new(6.0 * angle.sin(), 2.0, -6.0 * angle.cos());
+        let front = Vec3::new(-eye.x(), -1.0, -eye.z());
+        let aspect_ratio = renderer.device.surface_aspect_ratio();
+
+        self.ubo_buffer.mem_copy(
+            util::offset_of!(MeshUniformBufferObject, view) as u64,
+            &[
+                camera::look_to(eye, front, Vec3::new(0.0, 1.0, 0.0)),
+                camera::new_infinite_perspective_proj(aspect_ratio, Y_FOV, Z_NEAR),
+            ],
+        )?;
+        self.sky_constants = SkyConstants::new(front, aspect_ratio);
+        Ok(())
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
+                .set_depth_attachment(
+                    AttachmentType::Reference(self.depth_image_handle),
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::DontCare)
+                        .layout(Layout::DepthAttachment),
+                )
+                .set_callback(allocator, |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.mesh_pipeline)?;
+                    cmd_list.bind_index_buffer(&renderer.device, &self.index_buffer);
+                    cmd_list.bind_vertex_buffer(&renderer.device, &self.vertex_buffer);
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.mesh_pipeline,
+                        0,
+                        &[self.bind_group],
+                    );
+                    for draw in &self.shape_draws {
+                        cmd_list.set_vertex_bytes(
+                            &renderer.device,
+                            &self.mesh_pipeline,
+                            &ModelConstants::new(&translate(draw.position)),
+                            0,
+                        )?;
+                        cmd_list.draw_offset(
+                            &renderer.device,
+                            draw.num_indices,
+                            draw.index_buffer_offset,
+                            draw.vertex_buffer_offset,
+                        );
+                    }
+
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.sky_pipeline)?;
+                    cmd_list.set_fragment_bytes(
+                        &renderer.device,
+                        &self.sky_pipeline,
+                        &self.sky_constants,
+                        0,
+                    )?;
+                    cmd_list.draw_fullscreen_triangle(&renderer.device);
+
+                    Ok(())
+                }),
+        );
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        renderer
+            .resource_manager
+            .images
+            .get_mut(self.depth_image_handle)
+            .unwrap()
+            .resize(&renderer.device, Size2D::new(width, height))?;
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.index_buffer.destroy(&renderer.device);
+        self.vertex_buffer.destroy(&renderer.device);
+        self.ubo_buffer.destroy(&renderer.device);
+        self.mesh_pipeline.destroy(&renderer.device);
+        self.sky_pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "skybox",
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let mut cinder = Cinder::<SkyboxSample>::new(&sdl.window).unwrap();
+    cinder.run_game_loop(&mut sdl).unwrap();
+}
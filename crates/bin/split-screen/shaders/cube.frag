@@ -0,0 +1,9 @@
+#version 450
+
+layout (location = 0) in vec4 i_color;
+
+layout (location = 0) out vec4 uFragColor;
+
+void main() {
+    uFragColor = i_color;
+}
\ No newline at end of file
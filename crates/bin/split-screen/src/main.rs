@@ -0,0 +1,368 @@
+use anyhow::Result;
+use cinder::{
+    App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData,
+    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, Format, GraphicsPipeline,
+    GraphicsPipelineDescription, Image, ImageDescription, ImageUsage, InitContext, InputState,
+    Layout, RenderAttachmentDesc, RenderGraph, RenderPass, Renderer, ResourceId,
+};
+use math::{mat::Mat4, point::Point2D, rect::Rect2D, size::Size2D, vec::Vec3};
+use util::{SdlContext, WindowDescription};
+
+include!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/gen/cube_shader_structs.rs"
+));
+
+pub const WINDOW_WIDTH: u32 = 1280;
+pub const WINDOW_HEIGHT: u32 = 720;
+
+// Eye and look direction of the left and right views
+fn view_camera(index: usize) -> (Vec3, Vec3) {
+    match index {
+        0 => (Vec3::new(2.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0)),
+        _ => (Vec3::new(1.5, 1.5, 1.5), Vec3::new(-1.0, -1.0, -1.0)),
+    }
+}
+
+struct View {
+    ubo_buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+impl View {
+    fn write_camera(&self, index: usize, surface_size: Size2D<u32>) -> Result<()> {
+        let (eye, front) = view_camera(index);
+        self.ubo_buffer.mem_copy(
+            util::offset_of!(CubeUniformBufferObject, view) as u64,
+            &[
+                camera::look_to(eye, front, Vec3::new(0.0, 1.0, 0.0)),
+                // Each view only covers half of the surface
+                camera::new_infinite_perspective_proj(
+                    (surface_size.width() / 2) as f32 / surface_size.height() as f32,
+                    30.0,
+                    0.01,
+                ),
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+pub struct SplitScreenSample {
+    depth_image_handle: ResourceId<Image>,
+    pipeline: GraphicsPipeline,
+    views: [View; 2],
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    surface_size: Size2D<u32>,
+}
+
+impl SplitScreenSample {
+    fn view_rect(&self, index: usize) -> Rect2D<i32, u32> {
+        let half_width = self.surface_size.width() / 2;
+        Rect2D::from_offset_and_size(
+            Point2D::new((index as u32 * half_width) as i32, 0),
+            Size2D::new(half_width, self.surface_size.height()),
+        )
+    }
+}
+
+impl App for SplitScreenSample {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create App Resources
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+        let surface_size = Size2D::new(surface_rect.width(), surface_rect.height());
+        let depth_image = context.renderer.device.create_image(
+            surface_size,
+            ImageDescription {
+                format: Format::D32_SFLOAT,
+                usage: ImageUsage::Depth,
+                ..Default::default()
+            },
+        )?;
+
+        let vertex_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/cube.vert.spv"),
+            Default::default(),
+        )?;
+        let fragment_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/cube.frag.spv"),
+            Default::default(),
+        )?;
+        let pipeline = context.renderer.device.create_graphics_pipeline(
+            &vertex_shader,
+            Some(&fragment_shader),
+            GraphicsPipelineDescription {
+                name: Some("Cube Pipeline".to_owned()),
+                depth_format: Some(Format::D32_SFLOAT),
+                ..Default::default()
+            },
+        )?;
+
+        // Each view gets its own camera uniforms, the model matrix is written to both
+        let create_view = |index: usize| -> Result<View> {
+            let ubo_buffer = context.renderer.device.create_buffer(
+                std::mem::size_of::<CubeUniformBufferObject>() as u64,
+                BufferDescription {
+                    usage: BufferUsage::UNIFORM,
+                    ..Default::default()
+                },
+            )?;
+            let bind_group = BindGroup::new(
+                &context.renderer.device,
+                pipeline.bind_group_data(0).unwrap(),
+            )?;
+            context
+                .renderer
+                .device
+                .write_bind_group(&[BindGroupBindInfo {
+                    group: bind_group,
+                    dst_binding: 0,
+                    data: BindGroupWriteData::Uniform(ubo_buffer.bind_info()),
+                }])?;
+            let view = View {
+                ubo_buffer,
+                bind_group,
+            };
+            view.write_camera(index, surface_size)?;
+            Ok(view)
+        };
+        let views = [create_view(0)?, create_view(1)?];
+
+        let vertex_buffer = context.renderer.device.create_buffer_with_data(
+            &[
+                // Plane at z: -0.5
+                CubeVertex {
+                    i_pos: [-0.5, 0.5, -0.5],
+                    i_normal: [1.0, 0.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, 0.5, -0.5],
+                    i_normal: [1.0, 0.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [-0.5, -0.5, -0.5],
+                    i_normal: [1.0, 0.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, -0.5, -0.5],
+                    i_normal: [1.0, 0.0, 0.0],
+                },
+                // Plane at z: 0.5
+                CubeVertex {
+                    i_pos: [-0.5, 0.5, 0.5],
+                    i_normal: [0.0, 0.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, 0.5, 0.5],
+                    i_normal: [0.0, 0.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [-0.5, -0.5, 0.5],
+                    i_normal: [0.0, 0.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, -0.5, 0.5],
+                    i_normal: [0.0, 0.0, 1.0],
+                },
+                // Plane at x: -0.5
+                CubeVertex {
+                    i_pos: [-0.5, -0.5, 0.5],
+                    i_normal: [0.0, 1.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [-0.5, 0.5, 0.5],
+                    i_normal: [0.0, 1.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [-0.5, -0.5, -0.5],
+                    i_normal: [0.0, 1.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [-0.5, 0.5, -0.5],
+                    i_normal: [0.0, 1.0, 0.0],
+                },
+                // Plane at x: 0.5
+                CubeVertex {
+                    i_pos: [0.5, -0.5, 0.5],
+                    i_normal: [1.0, 1.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, 0.5, 0.5],
+                    i_normal: [1.0, 1.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, -0.5, -0.5],
+                    i_normal: [1.0, 1.0, 0.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, 0.5, -0.5],
+                    i_normal: [1.0, 1.0, 0.0],
+                },
+                // Plane at y: -0.5
+                CubeVertex {
+                    i_pos: [-0.5, -0.5, 0.5],
+                    i_normal: [0.0, 1.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, -0.5, 0.5],
+                    i_normal: [0.0, 1.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [-0.5, -0.5, -0.5],
+                    i_normal: [0.0, 1.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, -0.5, -0.5],
+                    i_normal: [0.0, 1.0, 1.0],
+                },
+                // Plane at y: 0.5
+                CubeVertex {
+                    i_pos: [-0.5, 0.5, 0.5],
+                    i_normal: [1.0, 1.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, 0.5, 0.5],
+                    i_normal: [1.0, 1.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [-0.5, 0.5, -0.5],
+                    i_normal: [1.0, 1.0, 1.0],
+                },
+                CubeVertex {
+                    i_pos: [0.5, 0.5, -0.5],
+                    i_normal: [1.0, 1.0, 1.0],
+                },
+            ],
+            BufferDescription {
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+        let index_buffer = context.renderer.device.create_buffer_with_data(
+            &[
+                0, 1, 2, 2, 1, 3, // First plane
+                4, 5, 6, 6, 5, 7, // Second plane
+                8, 9, 10, 10, 9, 11, // Third plane
+                12, 13, 14, 14, 13, 15, // Fourth plane
+                16, 17, 18, 18, 17, 19, // Fifth plane
+                20, 21, 22, 22, 21, 23, // Sixth plane
+            ],
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+
+        vertex_shader.destroy(&context.renderer.device);
+        fragment_shader.destroy(&context.renderer.device);
+
+        let depth_image_handle = context.renderer.resource_manager.insert_image(depth_image);
+
+        Ok(Self {
+            depth_image_handle,
+            pipeline,
+            views,
+            vertex_buffer,
+            index_buffer,
+            surface_size,
+        })
+    }
+
+    fn update(&mut self, renderer: &mut Renderer, _input: &InputState) -> Result<()> {
+        let scale = (renderer.time.elapsed_seconds() / 5.0) * (2.0 * std::f32::consts::PI);
+        for view in &self.views {
+            view.ubo_buffer.mem_copy(
+                util::offset_of!(CubeUniformBufferObject, model) as u64,
+                &[Mat4::rotate(scale, Vec3::new(1.0, 1.0, 0.0))],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        // Both passes render to the same swapchain and depth images. The render area follows the
+        // viewport, so each pass only clears its own half
+        for (index, view) in self.views.iter().enumerate() {
+            let pipeline = &self.pipeline;
+            let vertex_buffer = &self.vertex_buffer;
+            let index_buffer = &self.index_buffer;
+            graph.add_pass(
+                allocator,
+                RenderPass::new(allocator)
+                    .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
+                    .set_depth_attachment(
+                        AttachmentType::Reference(self.depth_image_handle),
+                        RenderAttachmentDesc::clear(ClearValue::default_depth())
+                            .store_op(AttachmentStoreOp::DontCare)
+                            .layout(Layout::DepthAttachment),
+                    )
+                    .with_viewport(self.view_rect(index))
+                    .set_callback(allocator, move |renderer, cmd_list| {
+                        cmd_list.bind_graphics_pipeline(&renderer.device, pipeline)?;
+                        cmd_list.bind_index_buffer(&renderer.device, index_buffer);
+                        cmd_list.bind_vertex_buffer(&renderer.device, vertex_buffer);
+                        cmd_list.bind_descriptor_sets(
+                            &renderer.device,
+                            pipeline,
+                            0,
+                            &[view.bind_group],
+                        );
+                        cmd_list.draw_offset(
+                            &renderer.device,
+                            index_buffer.num_elements().unwrap(),
+                            0,
+                            0,
+                        );
+
+                        Ok(())
+                    }),
+            );
+        }
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        self.surface_size = Size2D::new(width, height);
+        renderer
+            .resource_manager
+            .images
+            .get_mut(self.depth_image_handle)
+            .unwrap()
+            .resize(&renderer.device, self.surface_size)?;
+        for (index, view) in self.views.iter().enumerate() {
+            view.write_camera(index, self.surface_size)?;
+        }
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.index_buffer.destroy(&renderer.device);
+        self.vertex_buffer.destroy(&renderer.device);
+        for view in &self.views {
+            view.ubo_buffer.destroy(&renderer.device);
+        }
+        self.pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "split-screen",
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let mut cinder = Cinder::<SplitScreenSample>::new(&sdl.window).unwrap();
+    cinder.run_game_loop(&mut sdl).unwrap();
+}
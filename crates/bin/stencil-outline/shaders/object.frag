@@ -0,0 +1,10 @@
+#version 450
+
+layout (location = 0) in vec3 i_normal;
+
+layout (location = 0) out vec4 uFragColor;
+
+void main() {
+    vec3 normal = normalize(i_normal);
+    uFragColor = vec4(normal * 0.5 + 0.5, 1.0);
+}
@@ -0,0 +1,9 @@
+#version 450
+
+layout (location = 0) in vec3 i_normal;
+
+layout (location = 0) out vec4 uFragColor;
+
+void main() {
+    uFragColor = vec4(1.0, 0.6, 0.1, 1.0);
+}
@@ -0,0 +1,24 @@
+#version 460
+
+layout (location = 0) in vec3 i_normal;
+layout (location = 1) in vec2 i_uv;
+layout (location = 2) in vec4 i_clip_pos;
+layout (location = 3) in vec4 i_prev_clip_pos;
+
+layout (location = 0) out vec4 o_color;
+layout (location = 1) out vec2 o_velocity;
+
+const vec3 LIGHT_DIR = normalize(vec3(0.4, 1.0, -0.3));
+
+void main() {
+    // High-frequency checkerboard, which aliases badly in the distance without TAA
+    float checker = mod(floor(i_uv.x) + floor(i_uv.y), 2.0);
+    vec3 albedo = mix(vec3(0.05), vec3(0.9), checker);
+    float diffuse = max(dot(normalize(i_normal), LIGHT_DIR), 0.0);
+    o_color = vec4(albedo * (diffuse + 0.1), 1.0);
+
+    // NDC span 2 units across the screen, UVs span 1
+    vec2 ndc = i_clip_pos.xy / i_clip_pos.w;
+    vec2 prev_ndc = i_prev_clip_pos.xy / i_prev_clip_pos.w;
+    o_velocity = (ndc - prev_ndc) * 0.5;
+}
@@ -0,0 +1,37 @@
+#version 460
+
+layout(location = 0) in vec3 i_pos;
+layout(location = 1) in vec3 i_normal;
+layout(location = 2) in vec2 i_uv;
+
+layout (location = 0) out vec3 o_normal;
+layout (location = 1) out vec2 o_uv;
+layout (location = 2) out vec4 o_clip_pos;
+layout (location = 3) out vec4 o_prev_clip_pos;
+
+layout(binding = 0) uniform UniformBufferObject {
+    // Jittered, only used for rasterization
+    mat4 jittered_view_proj;
+    mat4 view_proj;
+    mat4 prev_view_proj;
+} ubo;
+
+layout( push_constant ) uniform constants
+{
+    mat4 model;
+    mat4 prev_model;
+    // Scales the UVs of the checkerboard
+    vec4 uv_scale;
+} PushConstants;
+
+void main() {
+    vec4 world_pos = PushConstants.model * vec4(i_pos, 1.0);
+    // Models are only translated and rotated, so their upper 3x3 already transforms normals.
+    // There is no room left in the push constants for a separate normal matrix
+    o_normal = mat3(PushConstants.model) * i_normal;
+    o_uv = i_uv * PushConstants.uv_scale.xy;
+    o_clip_pos = ubo.view_proj * world_pos;
+    o_prev_clip_pos = ubo.prev_view_proj * PushConstants.prev_model * vec4(i_pos, 1.0);
+
+    gl_Position = ubo.jittered_view_proj * world_pos;
+}
@@ -0,0 +1,380 @@
+use anyhow::Result;
+use camera::{input::InputMap, Camera, CameraAction, CameraDescription};
+use cinder::{
+    App, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData, Buffer,
+    BufferDescription, BufferUsage, Bump, Cinder, DebugUiContext, Format, GraphicsPipeline,
+    GraphicsPipelineDescription, Image, ImageDescription, ImageUsage, InitContext, InputState,
+    PostProcess, RenderGraph, RenderPass, RenderPassResource, Renderer, ResourceId, Taa,
+    TaaDescription, VELOCITY_FORMAT,
+};
+use egui_integration::egui;
+use geometry::{SurfaceMesh, Vertex};
+use math::{mat::Mat4, size::Size2D, vec::Vec3};
+use util::{SdlContext, WindowDescription};
+
+pub const WINDOW_WIDTH: u32 = 1280;
+pub const WINDOW_HEIGHT: u32 = 720;
+
+const HDR_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+const DEPTH_FORMAT: Format = Format::D32_SFLOAT;
+
+include!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/gen/scene_shader_structs.rs"
+));
+
+#[rustfmt::skip]
+fn translate(pos: Vec3) -> Mat4 {
+    Mat4::from_data(
+        1.0, 0.0, 0.0, pos.x(),
+        0.0, 1.0, 0.0, pos.y(),
+        0.0, 0.0, 1.0, pos.z(),
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+// Must match the push constants in scene.vert
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct ShapeConstants {
+    model: Mat4,
+    // Model matrix of the last frame, for the motion vectors of moving shapes
+    prev_model: Mat4,
+    uv_scale: [f32; 4],
+}
+
+#[derive(Debug)]
+pub struct ShapeDraw {
+    position: Vec3,
+    spins: bool,
+    uv_scale: f32,
+    vertex_buffer_offset: i32,
+    index_buffer_offset: u32,
+    num_indices: u32,
+}
+
+impl ShapeDraw {
+    fn model(&self, rotation: f32) -> Mat4 {
+        let rotation = if self.spins { rotation } else { 0.0 };
+        translate(self.position) * Mat4::rotate(rotation, Vec3::new(0.0, 1.0, 0.0))
+    }
+}
+
+pub struct TaaSample {
+    // Jittered scene, resolved by `taa` and then tonemapped into the swapchain by `post_process`
+    hdr_image_handle: ResourceId<Image>,
+    taa: Taa,
+    post_process: PostProcess,
+    pipeline: GraphicsPipeline,
+    bind_group: BindGroup,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    ubo_buffer: Buffer,
+    shape_draws: Vec<ShapeDraw>,
+    shape_constants: Vec<ShapeConstants>,
+    rotation: f32,
+    camera: Camera,
+    input_map: InputMap<CameraAction>,
+}
+
+impl App for TaaSample {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create Images
+        //
+        let surface_rect = context.renderer.device.surface_rect();
+        let hdr_image = context.renderer.device.create_image(
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            ImageDescription {
+                name: Some("HDR Color"),
+                format: HDR_FORMAT,
+                usage: ImageUsage::ColorAttachment,
+                ..Default::default()
+            },
+        )?;
+        let taa = Taa::new(
+            context.renderer,
+            TaaDescription {
+                color_format: HDR_FORMAT,
+            },
+        )?;
+
+        //
+        // Create Pipeline
+        //
+        let vertex_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/scene.vert.spv"),
+            Default::default(),
+        )?;
+        let fragment_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/scene.frag.spv"),
+            Default::default(),
+        )?;
+        let pipeline = context.renderer.device.create_graphics_pipeline(
+            &vertex_shader,
+            Some(&fragment_shader),
+            GraphicsPipelineDescription {
+                name: Some("Scene Pipeline".to_owned()),
+                color_formats: vec![HDR_FORMAT, VELOCITY_FORMAT],
+                depth_format: Some(DEPTH_FORMAT),
+                ..Default::default()
+            },
+        )?;
+        let bind_group = BindGroup::new(
+            &context.renderer.device,
+            pipeline.bind_group_data(0).unwrap(),
+        )?;
+        let ubo_buffer = context.renderer.device.create_buffer(
+            std::mem::size_of::<SceneUniformBufferObject>() as u64,
+            BufferDescription {
+                name: Some("Uniform Buffer"),
+                usage: BufferUsage::UNIFORM,
+                ..Default::default()
+            },
+        )?;
+        context
+            .renderer
+            .device
+            .write_bind_group(&[BindGroupBindInfo {
+                group: bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::Uniform(ubo_buffer.bind_info()),
+            }])?;
+
+        //
+        // Create Shapes
+        //
+        // The floor's checkerboard has 1m squares, thin enough to alias a few meters away
+        let shapes: [(Vec3, bool, f32, SurfaceMesh<Vertex>); 4] = [
+            (
+                Vec3::new(0.0, 0.0, 0.0),
+                false,
+                200.0,
+                SurfaceMesh::plane(200.0, 200.0, 0),
+            ),
+            (
+                Vec3::new(-2.0, 1.0, 6.0),
+                true,
+                8.0,
+                SurfaceMesh::uv_sphere::<16, 32>(1.0),
+            ),
+            (
+                Vec3::new(2.0, 1.0, 6.0),
+                true,
+                16.0,
+                SurfaceMesh::torus::<32, 16>(1.0, 0.3),
+            ),
+            (
+                Vec3::new(0.0, 1.5, 12.0),
+                true,
+                4.0,
+                SurfaceMesh::cylinder::<32>(3.0, 0.2),
+            ),
+        ];
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut shape_draws = Vec::with_capacity(shapes.len());
+        for (position, spins, uv_scale, shape) in shapes {
+            shape_draws.push(ShapeDraw {
+                position,
+                spins,
+                uv_scale,
+                vertex_buffer_offset: vertices.len() as i32,
+                index_buffer_offset: indices.len() as u32,
+                num_indices: shape.indices.len() as u32,
+            });
+            vertices.extend(shape.vertices);
+            indices.extend(shape.indices);
+        }
+
+        let vertex_buffer = context.renderer.device.create_buffer_with_data(
+            &vertices,
+            BufferDescription {
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+        let index_buffer = context.renderer.device.create_buffer_with_data(
+            &indices,
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+
+        //
+        // Create Camera
+        //
+        let camera = Camera::new(
+            Vec3::new(0.0, 2.0, -4.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            CameraDescription {
+                movement_per_sec: 4.0,
+                ..Default::default()
+            },
+        );
+
+        //
+        // Cleanup
+        //
+        vertex_shader.destroy(&context.renderer.device);
+        fragment_shader.destroy(&context.renderer.device);
+
+        let hdr_image_handle = context.renderer.resource_manager.insert_image(hdr_image);
+        let post_process = PostProcess::new(context.renderer)?;
+        post_process.set_input(context.renderer, taa.output())?;
+
+        Ok(Self {
+            hdr_image_handle,
+            taa,
+            post_process,
+            pipeline,
+            bind_group,
+            vertex_buffer,
+            index_buffer,
+            ubo_buffer,
+            shape_constants: Vec::with_capacity(shape_draws.len()),
+            shape_draws,
+            rotation: 0.0,
+            camera,
+            input_map: CameraAction::default_input_map(),
+        })
+    }
+
+    fn update(&mut self, renderer: &mut Renderer, input: &InputState) -> Result<()> {
+        let surface_rect = renderer.device.surface_rect();
+        self.camera.update(
+            &self.input_map,
+            input,
+            Size2D::new(surface_rect.width(), surface_rect.height()),
+            renderer.last_dt(),
+        );
+
+        let (width, height) = (surface_rect.width() as f32, surface_rect.height() as f32);
+        let view = self.camera.view();
+        let view_proj = self.camera.projection(width, height) * view;
+        let jittered_view_proj = if self.taa.enabled {
+            self.camera
+                .jittered_projection(self.taa.frame_index(), width, height)
+                * view
+        } else {
+            view_proj
+        };
+        let prev_view_proj = self.camera.prev_view_proj().unwrap_or(view_proj);
+        self.ubo_buffer.mem_copy(
+            util::offset_of!(SceneUniformBufferObject, jittered_view_proj) as u64,
+            &[jittered_view_proj, view_proj, prev_view_proj],
+        )?;
+
+        let prev_rotation = self.rotation;
+        self.rotation = (renderer.time.elapsed_seconds() / 8.0) * (2.0 * std::f32::consts::PI);
+        self.shape_constants.clear();
+        for draw in &self.shape_draws {
+            self.shape_constants.push(ShapeConstants {
+                model: draw.model(self.rotation),
+                prev_model: draw.model(prev_rotation),
+                uv_scale: [draw.uv_scale, draw.uv_scale, 0.0, 0.0],
+            });
+        }
+        Ok(())
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        let velocity = self.taa.velocity();
+        let output = self.taa.output();
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .with_name("Scene")
+                .add_color_attachment(
+                    AttachmentType::Reference(self.hdr_image_handle),
+                    Default::default(),
+                )
+                .add_color_attachment(
+                    AttachmentType::Reference(velocity),
+                    Taa::velocity_attachment_desc(),
+                )
+                .add_output(RenderPassResource::Image(self.hdr_image_handle))
+                .add_output(RenderPassResource::Image(velocity))
+                .with_managed_depth(DEPTH_FORMAT)
+                .set_callback(allocator, |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipeline)?;
+                    cmd_list.bind_index_buffer(&renderer.device, &self.index_buffer);
+                    cmd_list.bind_vertex_buffer(&renderer.device, &self.vertex_buffer);
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.pipeline,
+                        0,
+                        &[self.bind_group],
+                    );
+                    for (draw, constants) in self.shape_draws.iter().zip(&self.shape_constants) {
+                        cmd_list.set_vertex_bytes(
+                            &renderer.device,
+                            &self.pipeline,
+                            constants,
+                            0,
+                        )?;
+                        cmd_list.draw_offset(
+                            &renderer.device,
+                            draw.num_indices,
+                            draw.index_buffer_offset,
+                            draw.vertex_buffer_offset,
+                        );
+                    }
+
+                    Ok(())
+                }),
+        );
+        self.taa.resolve(allocator, graph, self.hdr_image_handle);
+        self.post_process.tonemap(allocator, graph, output);
+        Ok(())
+    }
+
+    fn draw_debug_ui(&mut self, context: &DebugUiContext) {
+        egui::Window::new("TAA").show(context, |ui| {
+            ui.checkbox(&mut self.taa.enabled, "Enabled");
+            ui.add(egui::Slider::new(&mut self.taa.blend_factor, 0.02..=1.0).text("Blend Factor"));
+        });
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        renderer
+            .resource_manager
+            .images
+            .get_mut(self.hdr_image_handle)
+            .unwrap()
+            .resize(&renderer.device, Size2D::new(width, height))?;
+        self.taa.resize(renderer, width, height)?;
+        self.post_process.set_input(renderer, self.taa.output())?;
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.taa.destroy(renderer);
+        self.post_process.destroy(renderer);
+        self.index_buffer.destroy(&renderer.device);
+        self.vertex_buffer.destroy(&renderer.device);
+        self.ubo_buffer.destroy(&renderer.device);
+        self.pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "taa",
+            capture_mouse: true,
+        },
+    )
+    .unwrap();
+    let mut cinder = Cinder::<TaaSample>::new(&sdl.window).unwrap();
+    cinder.run_game_loop(&mut sdl).unwrap();
+}
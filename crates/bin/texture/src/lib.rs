@@ -0,0 +1,287 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use cinder::{
+    max_mip_levels, App, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData, Buffer,
+    BufferDescription, BufferUsage, Bump, DebugUiContext, Format, GraphicsPipeline, Image,
+    ImageDescription, InitContext, InputState, Layout, MipmapMode, RenderGraph, RenderPass,
+    Renderer, ResourceId, Sampler, SamplerDescription, SubresourceRange,
+};
+use egui_integration::egui;
+use math::size::Size2D;
+
+include!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/gen/texture_shader_structs.rs"
+));
+
+fn sampler_description(lod_bias: f32) -> SamplerDescription {
+    SamplerDescription {
+        mipmap_mode: MipmapMode::Linear,
+        mip_lod_bias: lod_bias,
+        ..Default::default()
+    }
+}
+
+pub struct TextureSample {
+    lod_bias: f32,
+    // Bias the current sampler was created with
+    sampler_lod_bias: f32,
+    // Shows a single mip level instead of the whole chain
+    mip_level: Option<u32>,
+    mip_levels: u32,
+    // Level the current bind group was written with
+    bound_mip_level: Option<u32>,
+    sampler_handle: ResourceId<Sampler>,
+    texture_handle: ResourceId<Image>,
+    pipeline: GraphicsPipeline,
+    bind_group: BindGroup,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+}
+
+impl App for TextureSample {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        //
+        // Create App Resources
+        //
+        let vertex_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/texture.vert.spv"),
+            Default::default(),
+        )?;
+        let fragment_shader = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/texture.frag.spv"),
+            Default::default(),
+        )?;
+        let pipeline = context.renderer.device.create_graphics_pipeline(
+            &vertex_shader,
+            Some(&fragment_shader),
+            Default::default(),
+        )?;
+        let bind_group = BindGroup::new(
+            &context.renderer.device,
+            pipeline.bind_group_data(0).unwrap(),
+        )?;
+        let sampler = context
+            .renderer
+            .device
+            .create_sampler(sampler_description(0.0))?;
+        let assets_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets");
+        // A block-compressed variant is uploaded as-is when present, e.g. a BC7 export from
+        // Compressonator
+        let compressed_path = assets_path.join("rust.ktx2");
+        let texture = if compressed_path.exists() {
+            let image_data = zero_copy_assets::try_decoded_file::<
+                zero_copy_assets::CompressedImageData,
+            >(
+                &compressed_path, assets_path.join("gen").join("rust.adc")
+            )?;
+            let levels = image_data
+                .levels
+                .iter()
+                .map(Vec::as_slice)
+                .collect::<Vec<_>>();
+            context
+                .renderer
+                .device
+                .create_image_with_mip_data_immediate(
+                    Size2D::new(image_data.width, image_data.height),
+                    &levels,
+                    &context.renderer.command_queue,
+                    ImageDescription {
+                        format: Format::from_vk_raw(image_data.vk_format).ok_or_else(|| {
+                            anyhow::anyhow!("Unsupported KTX2 format: {}", image_data.vk_format)
+                        })?,
+                        mip_levels: levels.len() as u32,
+                        ..Default::default()
+                    },
+                )?
+        } else {
+            let image_data = zero_copy_assets::try_decoded_file::<zero_copy_assets::ImageData>(
+                assets_path.join("rust.png"),
+                assets_path.join("gen").join("rust.adi"),
+            )
+            .unwrap();
+            context.renderer.device.create_image_with_data_immediate(
+                Size2D::new(image_data.width, image_data.height),
+                &image_data.bytes,
+                &context.renderer.command_queue,
+                ImageDescription {
+                    mip_levels: max_mip_levels(Size2D::new(image_data.width, image_data.height)),
+                    ..Default::default()
+                },
+            )?
+        };
+        context
+            .renderer
+            .device
+            .write_bind_group(&[BindGroupBindInfo {
+                group: bind_group,
+                dst_binding: 0,
+                data: BindGroupWriteData::SampledImage(texture.bind_info(
+                    &sampler,
+                    Layout::ShaderReadOnly,
+                    None,
+                )),
+            }])?;
+        let vertex_buffer = context.renderer.device.create_buffer_with_data(
+            &[
+                // Top-left
+                TextureVertex {
+                    i_pos: [-0.5, -0.5],
+                    i_uv: [0.0, 1.0],
+                },
+                // Top-right
+                TextureVertex {
+                    i_pos: [0.5, -0.5],
+                    i_uv: [1.0, 1.0],
+                },
+                // Bottom-right
+                TextureVertex {
+                    i_pos: [0.5, 0.5],
+                    i_uv: [1.0, 0.0],
+                },
+                // Bottom-left
+                TextureVertex {
+                    i_pos: [-0.5, 0.5],
+                    i_uv: [0.0, 0.0],
+                },
+            ],
+            BufferDescription {
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+        let index_buffer = context.renderer.device.create_buffer_with_data(
+            &[0, 1, 2, 2, 3, 0],
+            BufferDescription {
+                usage: BufferUsage::INDEX,
+                ..Default::default()
+            },
+        )?;
+
+        //
+        // Add resources to ResourceManager
+        //
+        let mip_levels = texture.mip_levels();
+        let sampler_handle = context.renderer.resource_manager.insert_sampler(sampler);
+        let texture_handle = context.renderer.resource_manager.insert_image(texture);
+
+        //
+        // Cleanup
+        //
+        vertex_shader.destroy(&context.renderer.device);
+        fragment_shader.destroy(&context.renderer.device);
+
+        Ok(Self {
+            lod_bias: 0.0,
+            sampler_lod_bias: 0.0,
+            mip_level: None,
+            mip_levels,
+            bound_mip_level: None,
+            sampler_handle,
+            texture_handle,
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+            bind_group,
+        })
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        graph.add_pass(
+            &allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
+                .set_callback(allocator, |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipeline)?;
+                    cmd_list.bind_index_buffer(&renderer.device, &self.index_buffer);
+                    cmd_list.bind_vertex_buffer(&renderer.device, &self.vertex_buffer);
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.pipeline,
+                        0,
+                        &[self.bind_group],
+                    );
+                    cmd_list.draw_offset(&renderer.device, 6, 0, 0);
+
+                    Ok(())
+                }),
+        );
+        Ok(())
+    }
+
+    fn update(&mut self, renderer: &mut Renderer, _input: &InputState) -> anyhow::Result<()> {
+        if self.lod_bias == self.sampler_lod_bias && self.mip_level == self.bound_mip_level {
+            return Ok(());
+        }
+
+        // The bind group may still be in use by frames in flight
+        renderer.device.wait_idle()?;
+        if self.lod_bias != self.sampler_lod_bias {
+            let sampler = renderer
+                .device
+                .create_sampler(sampler_description(self.lod_bias))?;
+            let submit_value = renderer.device.pending_submit_value();
+            renderer
+                .resource_manager
+                .delete_sampler(self.sampler_handle, submit_value);
+            self.sampler_handle = renderer.resource_manager.insert_sampler(sampler);
+            self.sampler_lod_bias = self.lod_bias;
+        }
+        let sampler = renderer
+            .resource_manager
+            .samplers
+            .get(self.sampler_handle)
+            .unwrap();
+        let texture = renderer
+            .resource_manager
+            .images
+            .get(self.texture_handle)
+            .unwrap();
+        let bind_info = match self.mip_level {
+            // A view of the single level, so the sampler has no other levels to pick from
+            Some(level) => texture.bind_info_range(
+                &renderer.device,
+                sampler,
+                Layout::ShaderReadOnly,
+                SubresourceRange::mip_level(level, texture.array_layers()),
+                None,
+            )?,
+            None => texture.bind_info(sampler, Layout::ShaderReadOnly, None),
+        };
+        renderer.device.write_bind_group(&[BindGroupBindInfo {
+            group: self.bind_group,
+            dst_binding: 0,
+            data: BindGroupWriteData::SampledImage(bind_info),
+        }])?;
+        self.bound_mip_level = self.mip_level;
+
+        Ok(())
+    }
+
+    fn draw_debug_ui(&mut self, context: &DebugUiContext) {
+        egui::Window::new("Sampler").show(context, |ui| {
+            ui.add(egui::Slider::new(&mut self.lod_bias, -4.0..=4.0).text("LOD Bias"));
+            let mut single_level = self.mip_level.is_some();
+            ui.checkbox(&mut single_level, "Single Mip Level");
+            if single_level != self.mip_level.is_some() {
+                self.mip_level = single_level.then_some(0);
+            }
+            if let Some(level) = &mut self.mip_level {
+                ui.add(egui::Slider::new(level, 0..=self.mip_levels - 1).text("Mip Level"));
+            }
+        });
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.index_buffer.destroy(&renderer.device);
+        self.vertex_buffer.destroy(&renderer.device);
+        self.pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
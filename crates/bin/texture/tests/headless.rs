@@ -0,0 +1,28 @@
+use cinder::testing::{check_against_reference, render_to_image};
+use std::path::PathBuf;
+use texture::TextureSample;
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 256;
+// Per-channel difference allowed between drivers
+const TOLERANCE: u8 = 2;
+
+// Needs a Vulkan device, so only runs with `cargo test -- --ignored`. Run with
+// `CINDER_UPDATE_REFERENCES=1` to regenerate the reference image.
+// No reference image is checked in for this sample yet, so until one is rendered on a machine
+// with a Vulkan device and committed under `tests/reference`, this test fails with
+// `MissingReference` and plain `cargo test` doesn't catch any drift
+#[test]
+#[ignore = "needs a Vulkan device"]
+fn texture_matches_reference() {
+    let pixels = render_to_image::<TextureSample>(WIDTH, HEIGHT).unwrap();
+    check_against_reference(
+        &pixels,
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("reference")
+            .join("texture.bgra"),
+        TOLERANCE,
+    )
+    .unwrap();
+}
@@ -0,0 +1,26 @@
+#version 450
+
+layout (location = 0) in vec2 i_uv;
+
+layout (location = 0) out vec4 uFragColor;
+
+layout (set = 0, binding = 0) uniform sampler2D source_texture;
+
+layout( push_constant ) uniform constants
+{
+    // Offset in uv between two taps, along the blur axis
+    vec2 direction;
+} PushConstants;
+
+// 9 tap separable gaussian
+const float WEIGHTS[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main() {
+    vec3 color = texture(source_texture, i_uv).rgb * WEIGHTS[0];
+    for (int i = 1; i < 5; ++i) {
+        vec2 offset = PushConstants.direction * float(i);
+        color += texture(source_texture, i_uv + offset).rgb * WEIGHTS[i];
+        color += texture(source_texture, i_uv - offset).rgb * WEIGHTS[i];
+    }
+    uFragColor = vec4(color, 1.0);
+}
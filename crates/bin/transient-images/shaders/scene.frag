@@ -0,0 +1,20 @@
+#version 450
+
+layout (location = 0) in vec2 i_uv;
+
+layout (location = 0) out vec4 uFragColor;
+
+layout( push_constant ) uniform constants
+{
+    float time;
+} PushConstants;
+
+void main() {
+    vec2 uv = i_uv * 8.0;
+    float t = PushConstants.time;
+    float v = sin(uv.x + t) + sin(uv.y * 0.7 - t) + sin(length(uv - 4.0) * 1.5 + t * 2.0);
+    // Thin bright bands, so the blur is easy to see
+    float bands = smoothstep(0.9, 1.0, abs(sin(v * 3.0)));
+    vec3 color = (0.5 + 0.5 * cos(vec3(0.0, 2.0, 4.0) + v * 1.5)) * bands;
+    uFragColor = vec4(color, 1.0);
+}
@@ -0,0 +1,322 @@
+use anyhow::Result;
+use cinder::{
+    AddressMode, App, AttachmentType, BindGroupPushInfo, BindGroupWriteData, Bump, Cinder,
+    CommandList, DebugUiContext, Format, GraphicsPipeline, GraphicsPipelineDescription,
+    ImageDescription, ImageUsage, InitContext, InputState, Layout, RenderGraph, RenderPass,
+    RenderPassResource, Renderer, Sampler, SamplerDescription, TransientId,
+    FULLSCREEN_VERTEX_SHADER,
+};
+use egui_integration::egui;
+use math::{rect::Rect2D, size::Size2D};
+use util::{SdlContext, WindowDescription};
+
+pub const WINDOW_WIDTH: u32 = 1280;
+pub const WINDOW_HEIGHT: u32 = 720;
+
+// Format of every intermediate image
+const HDR_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+// Distance between blur taps, in texels of the source image
+const BLUR_SPREAD: f32 = 2.0;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct SceneConstants {
+    time: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct BlurConstants {
+    direction: [f32; 2],
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct TransientStats {
+    image_count: usize,
+    slot_count: usize,
+    reserved_bytes: u64,
+}
+
+pub struct TransientImagesSample {
+    scene_pipeline: GraphicsPipeline,
+    blur_pipeline: GraphicsPipeline,
+    present_pipeline: GraphicsPipeline,
+    sampler: Sampler,
+    surface_size: Size2D<u32>,
+    transient_stats: TransientStats,
+}
+
+impl TransientImagesSample {
+    fn draw_fullscreen(
+        &self,
+        renderer: &Renderer,
+        cmd_list: &CommandList,
+        pipeline: &GraphicsPipeline,
+        source: Option<TransientId>,
+    ) -> Result<()> {
+        // Transient images can change every frame, so they are pushed instead of written to a
+        // bind group the GPU may still be reading from
+        if let Some(source) = source {
+            let image = renderer.transient_images.get(source).unwrap();
+            cmd_list.push_descriptor_set(
+                &renderer.device,
+                pipeline,
+                0,
+                &[BindGroupPushInfo {
+                    dst_binding: 0,
+                    data: BindGroupWriteData::SampledImage(image.bind_info(
+                        &self.sampler,
+                        Layout::ShaderReadOnly,
+                        None,
+                    )),
+                }],
+            )?;
+        }
+        cmd_list.draw_fullscreen_triangle(&renderer.device);
+        Ok(())
+    }
+}
+
+impl App for TransientImagesSample {
+    fn new(context: InitContext<'_>) -> Result<Self> {
+        anyhow::ensure!(
+            context.renderer.device.supports_push_descriptor(),
+            "The transient-images sample requires VK_KHR_push_descriptor"
+        );
+
+        //
+        // Create Pipelines
+        //
+        let fullscreen_vs = context
+            .renderer
+            .device
+            .create_shader(FULLSCREEN_VERTEX_SHADER, Default::default())?;
+        let scene_fs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/scene.frag.spv"),
+            Default::default(),
+        )?;
+        let blur_fs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/blur.frag.spv"),
+            Default::default(),
+        )?;
+        let present_fs = context.renderer.device.create_shader(
+            include_bytes!("../shaders/spv/present.frag.spv"),
+            Default::default(),
+        )?;
+        let scene_pipeline = context.renderer.device.create_graphics_pipeline(
+            &fullscreen_vs,
+            Some(&scene_fs),
+            GraphicsPipelineDescription {
+                name: Some("Scene Pipeline".to_owned()),
+                color_formats: vec![HDR_FORMAT],
+                ..Default::default()
+            },
+        )?;
+        let blur_pipeline = context.renderer.device.create_graphics_pipeline(
+            &fullscreen_vs,
+            Some(&blur_fs),
+            GraphicsPipelineDescription {
+                name: Some("Blur Pipeline".to_owned()),
+                color_formats: vec![HDR_FORMAT],
+                push_descriptor_set: Some(0),
+                ..Default::default()
+            },
+        )?;
+        let present_pipeline = context.renderer.device.create_graphics_pipeline(
+            &fullscreen_vs,
+            Some(&present_fs),
+            GraphicsPipelineDescription {
+                name: Some("Present Pipeline".to_owned()),
+                push_descriptor_set: Some(0),
+                ..Default::default()
+            },
+        )?;
+        let sampler = context.renderer.device.create_sampler(SamplerDescription {
+            address_mode: AddressMode::ClampToEdge,
+            ..Default::default()
+        })?;
+
+        //
+        // Cleanup
+        //
+        fullscreen_vs.destroy(&context.renderer.device);
+        scene_fs.destroy(&context.renderer.device);
+        blur_fs.destroy(&context.renderer.device);
+        present_fs.destroy(&context.renderer.device);
+
+        let surface_rect = context.renderer.device.surface_rect();
+        Ok(Self {
+            scene_pipeline,
+            blur_pipeline,
+            present_pipeline,
+            sampler,
+            surface_size: Size2D::new(surface_rect.width(), surface_rect.height()),
+            transient_stats: Default::default(),
+        })
+    }
+
+    fn update(&mut self, renderer: &mut Renderer, _input: &InputState) -> Result<()> {
+        // Reflects the graph run last frame
+        self.transient_stats = TransientStats {
+            image_count: renderer.transient_images.frame_image_count(),
+            slot_count: renderer.transient_images.slot_count(),
+            reserved_bytes: renderer.transient_images.reserved_bytes(),
+        };
+        Ok(())
+    }
+
+    fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+    ) -> anyhow::Result<()> {
+        //
+        // Transient Images
+        //
+        // The scene is only read by the horizontal blur, so the vertical blur, which runs after
+        // it, reuses the scene's memory
+        // Callbacks only read from the sample, and need to own the sizes and ids they use
+        let this = &*self;
+        let full_size = this.surface_size;
+        let half_size = Size2D::new(
+            (full_size.width() / 2).max(1),
+            (full_size.height() / 2).max(1),
+        );
+        let hdr_desc = |name: &'static str| ImageDescription {
+            name: Some(name),
+            format: HDR_FORMAT,
+            usage: ImageUsage::ColorAttachment,
+            ..Default::default()
+        };
+        let scene = graph.create_transient_image(full_size, hdr_desc("Scene"));
+        let blur_h = graph.create_transient_image(half_size, hdr_desc("Horizontal Blur"));
+        let blur_v = graph.create_transient_image(half_size, hdr_desc("Vertical Blur"));
+        let half_rect = Rect2D::from_width_height(half_size.width(), half_size.height());
+
+        //
+        // Passes
+        //
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(AttachmentType::Transient(scene), Default::default())
+                .add_output(RenderPassResource::Transient(scene))
+                .with_name("Scene")
+                .with_flipped_viewport(false)
+                .set_callback(allocator, move |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &this.scene_pipeline)?;
+                    cmd_list.set_fragment_bytes(
+                        &renderer.device,
+                        &this.scene_pipeline,
+                        &SceneConstants {
+                            time: renderer.time.elapsed_seconds(),
+                        },
+                        0,
+                    )?;
+                    this.draw_fullscreen(renderer, cmd_list, &this.scene_pipeline, None)
+                }),
+        );
+
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(AttachmentType::Transient(blur_h), Default::default())
+                .add_input(RenderPassResource::Transient(scene))
+                .add_output(RenderPassResource::Transient(blur_h))
+                .with_viewport(half_rect)
+                .with_name("Horizontal Blur")
+                .with_flipped_viewport(false)
+                .set_callback(allocator, move |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &this.blur_pipeline)?;
+                    cmd_list.set_fragment_bytes(
+                        &renderer.device,
+                        &this.blur_pipeline,
+                        &BlurConstants {
+                            direction: [BLUR_SPREAD / full_size.width() as f32, 0.0],
+                        },
+                        0,
+                    )?;
+                    this.draw_fullscreen(renderer, cmd_list, &this.blur_pipeline, Some(scene))
+                }),
+        );
+
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(AttachmentType::Transient(blur_v), Default::default())
+                .add_input(RenderPassResource::Transient(blur_h))
+                .add_output(RenderPassResource::Transient(blur_v))
+                .with_viewport(half_rect)
+                .with_name("Vertical Blur")
+                .with_flipped_viewport(false)
+                .set_callback(allocator, move |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &this.blur_pipeline)?;
+                    cmd_list.set_fragment_bytes(
+                        &renderer.device,
+                        &this.blur_pipeline,
+                        &BlurConstants {
+                            direction: [0.0, BLUR_SPREAD / half_size.height() as f32],
+                        },
+                        0,
+                    )?;
+                    this.draw_fullscreen(renderer, cmd_list, &this.blur_pipeline, Some(blur_h))
+                }),
+        );
+
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
+                .add_input(RenderPassResource::Transient(blur_v))
+                .add_output(RenderPassResource::SwapchainImage)
+                .with_name("Present")
+                .with_flipped_viewport(false)
+                .set_callback(allocator, move |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &this.present_pipeline)?;
+                    this.draw_fullscreen(renderer, cmd_list, &this.present_pipeline, Some(blur_v))
+                }),
+        );
+        Ok(())
+    }
+
+    fn draw_debug_ui(&mut self, context: &DebugUiContext) {
+        let stats = self.transient_stats;
+        egui::Window::new("Transient Images").show(context, |ui| {
+            ui.label(format!(
+                "{} images in {} allocations",
+                stats.image_count, stats.slot_count
+            ));
+            ui.label(format!(
+                "Reserved: {:.1} MiB",
+                stats.reserved_bytes as f64 / (1024.0 * 1024.0)
+            ));
+        });
+    }
+
+    fn resize(&mut self, _renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        self.surface_size = Size2D::new(width, height);
+        Ok(())
+    }
+
+    fn cleanup(&mut self, renderer: &mut Renderer) -> anyhow::Result<()> {
+        self.sampler.destroy(&renderer.device);
+        self.scene_pipeline.destroy(&renderer.device);
+        self.blur_pipeline.destroy(&renderer.device);
+        self.present_pipeline.destroy(&renderer.device);
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut sdl = SdlContext::new(
+        WINDOW_WIDTH,
+        WINDOW_HEIGHT,
+        WindowDescription {
+            title: "transient-images",
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let mut cinder = Cinder::<TransientImagesSample>::new(&sdl.window).unwrap();
+    cinder.run_game_loop(&mut sdl).unwrap();
+}
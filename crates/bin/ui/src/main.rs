@@ -1,9 +1,10 @@
 use anyhow::Result;
 use cinder::{
     App, AttachmentStoreOp, AttachmentType, BindGroup, BindGroupBindInfo, BindGroupWriteData,
-    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, DebugUiContext, Format,
-    GraphicsPipeline, GraphicsPipelineDescription, Image, ImageDescription, ImageUsage,
-    InitContext, Layout, RenderAttachmentDesc, RenderGraph, RenderPass, Renderer, ResourceId,
+    Buffer, BufferDescription, BufferUsage, Bump, Cinder, ClearValue, DebugUiContext,
+    DeviceDescription, Format, GraphicsPipeline, GraphicsPipelineDescription, Image,
+    ImageDescription, ImageUsage, InitContext, InputState, Layout, RenderAttachmentDesc,
+    RenderGraph, RenderPass, Renderer, ResourceId,
 };
 use egui_integration::egui;
 use math::{mat::Mat4, size::Size2D, vec::Vec3};
@@ -250,7 +251,7 @@ impl App for UiSample {
         })
     }
 
-    fn update(&mut self, _renderer: &mut Renderer) -> Result<()> {
+    fn update(&mut self, _renderer: &mut Renderer, _input: &InputState) -> Result<()> {
         let scale = self.model_data.scale;
         self.ubo_buffer.mem_copy(
             util::offset_of!(UiUniformBufferObject, model) as u64,
@@ -271,15 +272,12 @@ impl App for UiSample {
                 .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
                 .set_depth_attachment(
                     AttachmentType::Reference(self.depth_image_handle),
-                    RenderAttachmentDesc {
-                        store_op: AttachmentStoreOp::DontCare,
-                        layout: Layout::DepthAttachment,
-                        clear_value: ClearValue::default_depth(),
-                        ..Default::default()
-                    },
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::DontCare)
+                        .layout(Layout::DepthAttachment),
                 )
                 .set_callback(allocator, |cinder, cmd_list| {
-                    cmd_list.bind_graphics_pipeline(&cinder.device, &self.pipeline);
+                    cmd_list.bind_graphics_pipeline(&cinder.device, &self.pipeline)?;
                     cmd_list.bind_index_buffer(&cinder.device, &self.index_buffer);
                     cmd_list.bind_vertex_buffer(&cinder.device, &self.vertex_buffer);
                     cmd_list.bind_descriptor_sets(
@@ -333,6 +331,14 @@ fn main() {
         },
     )
     .unwrap();
-    let mut cinder = Cinder::<UiSample>::new(&sdl.window).unwrap();
+    // Double buffered, for lower input latency while interacting with the UI
+    let mut cinder = Cinder::<UiSample>::with_description(
+        &sdl.window,
+        DeviceDescription {
+            preferred_image_count: Some(2),
+            ..Default::default()
+        },
+    )
+    .unwrap();
     cinder.run_game_loop(&mut sdl).unwrap();
 }
@@ -0,0 +1,101 @@
+use crate::{look_to, new_orthographic_proj, Camera};
+use math::{mat::Mat4, vec::Vec3};
+
+// Splits the camera frustum into `N` depth slices and fits a directional light's orthographic
+// projection tightly around each one, for rendering into the layers of an array shadow map
+pub struct ShadowCascades<const N: usize> {
+    // Blends between uniform (0.0) and logarithmic (1.0) split distances
+    pub split_lambda: f32,
+    // View distance covered by the last cascade, since the camera projection has no far plane
+    pub shadow_distance: f32,
+    // Extends each cascade towards the light, so casters outside the view frustum still cast shadows
+    pub caster_margin: f32,
+    splits: [f32; N],
+    view_projs: [Mat4; N],
+}
+
+impl<const N: usize> ShadowCascades<N> {
+    pub fn new(shadow_distance: f32) -> Self {
+        Self {
+            split_lambda: 0.75,
+            shadow_distance,
+            caster_margin: shadow_distance,
+            splits: [0.0; N],
+            view_projs: std::array::from_fn(|_| Mat4::identity()),
+        }
+    }
+
+    pub fn update(&mut self, camera: &Camera, aspect_ratio: f32, light_dir: Vec3) {
+        let z_near = camera.z_near();
+        let z_far = self.shadow_distance;
+        for (idx, split) in self.splits.iter_mut().enumerate() {
+            let t = (idx + 1) as f32 / N as f32;
+            let log = z_near * (z_far / z_near).powf(t);
+            let uniform = z_near + (z_far - z_near) * t;
+            *split = self.split_lambda * log + (1.0 - self.split_lambda) * uniform;
+        }
+
+        // Same basis as `look_to`
+        let front = camera.front().normalized();
+        let side = camera.world_up().cross(&front).normalized();
+        let up = front.cross(&side);
+        let tan_half_fov = (camera.y_fov() / 2.0).tan().abs();
+
+        let light_front = light_dir.normalized();
+        let light_world_up = if light_front.y().abs() > 0.99 {
+            Vec3::new(0.0, 0.0, 1.0)
+        } else {
+            Vec3::new(0.0, 1.0, 0.0)
+        };
+        let light_side = light_world_up.cross(&light_front).normalized();
+        let light_up = light_front.cross(&light_side);
+
+        let mut slice_near = z_near;
+        for idx in 0..N {
+            let slice_far = self.splits[idx];
+
+            // Bounds of the slice's corners in light space
+            let mut min = [f32::MAX; 3];
+            let mut max = [f32::MIN; 3];
+            for distance in [slice_near, slice_far] {
+                let half_height = distance * tan_half_fov;
+                let half_width = half_height * aspect_ratio;
+                for (sx, sy) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+                    let mut corner = camera.position();
+                    corner += front * distance;
+                    corner += side * (sx * half_width);
+                    corner += up * (sy * half_height);
+
+                    let light_space = [
+                        light_side.dot(&corner),
+                        light_up.dot(&corner),
+                        light_front.dot(&corner),
+                    ];
+                    for axis in 0..3 {
+                        min[axis] = min[axis].min(light_space[axis]);
+                        max[axis] = max[axis].max(light_space[axis]);
+                    }
+                }
+            }
+
+            self.view_projs[idx] = new_orthographic_proj(
+                min[0],
+                max[0],
+                min[1],
+                max[1],
+                min[2] - self.caster_margin,
+                max[2],
+            ) * look_to(Vec3::zero(), light_front, light_world_up);
+            slice_near = slice_far;
+        }
+    }
+
+    // View distance at which each cascade ends
+    pub fn splits(&self) -> &[f32; N] {
+        &self.splits
+    }
+
+    pub fn view_projs(&self) -> &[Mat4; N] {
+        &self.view_projs
+    }
+}
@@ -1,9 +1,15 @@
-use input::{KeyboardState, MouseState};
-use math::{mat::Mat4, vec::Vec3};
-use sdl2::keyboard::Keycode;
+use input::{InputMap, InputState};
+use math::{mat::Mat4, size::Size2D, vec::Vec3};
+use sdl2::{controller::Button, keyboard::Keycode};
 
+pub mod cascades;
+
+pub use cascades::ShadowCascades;
 pub use input;
 
+// Depth is reversed, mapping `z_near` to 1 and infinity to 0. Floats are densest near 0, which
+// balances out the precision perspective division loses with distance. Pipelines test with
+// `CompareOp::Greater`, and depth is cleared to 0
 #[rustfmt::skip]
 pub fn new_infinite_perspective_proj(aspect_ratio: f32, y_fov: f32, z_near: f32) -> Mat4 {
     let f = 1.0 / (y_fov / 2.0).tan();
@@ -15,6 +21,52 @@ pub fn new_infinite_perspective_proj(aspect_ratio: f32, y_fov: f32, z_near: f32)
     )
 }
 
+// Same as `new_infinite_perspective_proj`, with `z_far` mapped to 0. Nothing past it is drawn, but
+// the matrix can be inverted into a closed frustum, e.g. for culling or `DebugDraw::frustum`
+#[rustfmt::skip]
+pub fn new_perspective_proj(aspect_ratio: f32, y_fov: f32, z_near: f32, z_far: f32) -> Mat4 {
+    let f = 1.0 / (y_fov / 2.0).tan();
+    Mat4::from_data(
+        f / aspect_ratio, 0., 0.0, 0.0,
+        0.0,              f,  0.0, 0.0,
+        0.0,              0., -z_near / (z_far - z_near), z_near * z_far / (z_far - z_near),
+        0.0,              0., 1.0, 0.0,
+    )
+}
+
+// Maps `z_near` to a depth of 1 and `z_far` to 0, matching the reversed depth of the perspective projection
+#[rustfmt::skip]
+pub fn new_orthographic_proj(left: f32, right: f32, bottom: f32, top: f32, z_near: f32, z_far: f32) -> Mat4 {
+    Mat4::from_data(
+        2.0 / (right - left), 0.0,                  0.0,                      -(right + left) / (right - left),
+        0.0,                  2.0 / (top - bottom), 0.0,                      -(top + bottom) / (top - bottom),
+        0.0,                  0.0,                  -1.0 / (z_far - z_near),  z_far / (z_far - z_near),
+        0.0,                  0.0,                  0.0,                      1.0,
+    )
+}
+
+// Element `index` of the Halton sequence in `base`, between 0 and 1
+fn halton(mut index: usize, base: usize) -> f32 {
+    let mut fraction = 1.0;
+    let mut ret = 0.0;
+    while index > 0 {
+        fraction /= base as f32;
+        ret += fraction * (index % base) as f32;
+        index /= base;
+    }
+    ret
+}
+
+// Number of distinct subpixel offsets `Camera::jittered_projection` cycles through
+pub const JITTER_SEQUENCE_LENGTH: usize = 8;
+
+// Subpixel offset for `frame_index`, between -0.5 and 0.5 pixels on each axis. Uses the (2, 3)
+// Halton sequence, skipping its first element which is always at the origin
+pub fn jitter_offset(frame_index: usize) -> (f32, f32) {
+    let index = frame_index % JITTER_SEQUENCE_LENGTH + 1;
+    (halton(index, 2) - 0.5, halton(index, 3) - 0.5)
+}
+
 #[rustfmt::skip]
 pub fn look_to(eye: Vec3, front: Vec3, world_up: Vec3) -> Mat4 {
     let front = front.normalized();
@@ -29,10 +81,34 @@ pub fn look_to(eye: Vec3, front: Vec3, world_up: Vec3) -> Mat4 {
     )
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CameraAction {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+}
+
+impl CameraAction {
+    pub fn default_input_map() -> InputMap<CameraAction> {
+        InputMap::default()
+            .with_binding(CameraAction::MoveForward, Keycode::W)
+            .with_binding(CameraAction::MoveBackward, Keycode::S)
+            .with_binding(CameraAction::MoveRight, Keycode::D)
+            .with_binding(CameraAction::MoveLeft, Keycode::A)
+            .with_binding(CameraAction::MoveUp, Keycode::Space)
+            .with_binding(CameraAction::MoveDown, Keycode::LShift)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct CameraDescription {
     pub y_fov: f32,
     pub z_near: f32,
+    // `None` for an infinite far plane
+    pub z_far: Option<f32>,
     pub world_up: Vec3,
     pub movement_per_sec: f32,
     // Amount we will rotate the camera by if we move the curse of screens's woth in the respective dimension
@@ -45,6 +121,7 @@ impl Default for CameraDescription {
         Self {
             y_fov: 30.0,
             z_near: 0.01,
+            z_far: None,
             world_up: Vec3::new(0.0, 1.0, 0.0),
             movement_per_sec: 1.0,
             rotation_speed: 180.0_f32.to_radians(),
@@ -53,6 +130,15 @@ impl Default for CameraDescription {
     }
 }
 
+// What `Camera::prev_view_proj` is built from
+#[derive(Debug, Clone, Copy)]
+struct PrevFrame {
+    position: Vec3,
+    front: Vec3,
+    surface_width: f32,
+    surface_height: f32,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Camera {
     position: Vec3,
@@ -62,9 +148,11 @@ pub struct Camera {
     pitch: f32,
     y_fov: f32,
     z_near: f32,
+    z_far: Option<f32>,
     movement_per_sec: f32,
     rotation_speed: f32,
     flipped_y: bool,
+    prev_frame: Option<PrevFrame>,
 }
 
 impl Camera {
@@ -85,34 +173,113 @@ impl Camera {
             pitch: 0.0,
             y_fov: desc.y_fov,
             z_near: desc.z_near,
+            z_far: desc.z_far,
             movement_per_sec: desc.movement_per_sec,
             rotation_speed: desc.rotation_speed,
             flipped_y: desc.flipped_y,
+            prev_frame: None,
         }
     }
 
     pub fn projection(&self, surface_width: f32, surface_height: f32) -> Mat4 {
-        new_infinite_perspective_proj(surface_width / surface_height, self.y_fov, self.z_near)
+        let aspect_ratio = surface_width / surface_height;
+        self.projection_with_aspect(aspect_ratio)
+    }
+
+    fn projection_with_aspect(&self, aspect_ratio: f32) -> Mat4 {
+        match self.z_far {
+            Some(z_far) => new_perspective_proj(aspect_ratio, self.y_fov, self.z_near, z_far),
+            None => new_infinite_perspective_proj(aspect_ratio, self.y_fov, self.z_near),
+        }
+    }
+
+    // `projection` offset by a different subpixel amount every frame, see `jitter_offset`. Only the
+    // rasterized positions should use it, motion vectors come from the unjittered matrices
+    #[rustfmt::skip]
+    pub fn jittered_projection(
+        &self,
+        frame_index: usize,
+        surface_width: f32,
+        surface_height: f32,
+    ) -> Mat4 {
+        let (x, y) = jitter_offset(frame_index);
+        // From pixels to NDC, which span 2 units across the surface
+        let (x, y) = (2.0 * x / surface_width, 2.0 * y / surface_height);
+        let jitter = Mat4::from_data(
+            1.0, 0.0, 0.0, x,
+            0.0, 1.0, 0.0, y,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        );
+        jitter * self.projection(surface_width, surface_height)
+    }
+
+    // Unjittered view-projection of the camera at the start of the last `update`, for reprojecting
+    // into the previous frame. `None` before the first `update`
+    pub fn prev_view_proj(&self) -> Option<Mat4> {
+        self.prev_frame.map(|prev| {
+            self.projection_with_aspect(prev.surface_width / prev.surface_height)
+                * look_to(prev.position, prev.front, self.world_up)
+        })
     }
 
     pub fn view(&self) -> Mat4 {
         look_to(self.position, self.front, self.world_up)
     }
 
+    pub fn position(&self) -> Vec3 {
+        self.position
+    }
+
+    pub fn front(&self) -> Vec3 {
+        self.front
+    }
+
+    pub fn world_up(&self) -> Vec3 {
+        self.world_up
+    }
+
+    pub fn y_fov(&self) -> f32 {
+        self.y_fov
+    }
+
+    pub fn z_near(&self) -> f32 {
+        self.z_near
+    }
+
+    pub fn z_far(&self) -> Option<f32> {
+        self.z_far
+    }
+
     pub fn update(
         &mut self,
-        keyboard_state: &KeyboardState,
-        mouse_state: &MouseState,
-        screen_width: u32,
-        screen_height: u32,
+        input_map: &InputMap<CameraAction>,
+        input: &InputState,
+        screen_size: Size2D<u32>,
         last_dt: Option<u128>,
     ) {
+        let InputState {
+            keyboard: keyboard_state,
+            mouse: mouse_state,
+            gamepad: gamepad_state,
+        } = input;
+        let (screen_width, screen_height) = (screen_size.width(), screen_size.height());
+        self.prev_frame = Some(PrevFrame {
+            position: self.position,
+            front: self.front,
+            surface_width: screen_width as f32,
+            surface_height: screen_height as f32,
+        });
         if let Some(dt) = last_dt {
+            let dt_scale = dt as f32 / 1000.0;
             let mouse_delta = mouse_state.delta();
+            let right_stick = gamepad_state.right_stick();
 
-            self.yaw += mouse_delta.x() as f32 / screen_width as f32 * self.rotation_speed;
-            let scaled_y_delta =
-                mouse_delta.y() as f32 / screen_height as f32 * self.rotation_speed;
+            self.yaw += mouse_delta.x() as f32 / screen_width as f32 * self.rotation_speed
+                + right_stick.x() * dt_scale * self.rotation_speed;
+            let scaled_y_delta = mouse_delta.y() as f32 / screen_height as f32
+                * self.rotation_speed
+                + right_stick.y() * dt_scale * self.rotation_speed;
             self.pitch += if self.flipped_y {
                 scaled_y_delta
             } else {
@@ -131,28 +298,57 @@ impl Camera {
             let disp = {
                 let mut disp = Vec3::zero();
 
-                if keyboard_state.is_down(Keycode::W) {
+                if input_map.is_active_with_mouse(
+                    CameraAction::MoveForward,
+                    keyboard_state,
+                    mouse_state,
+                ) {
                     disp += self.front;
                 }
-                if keyboard_state.is_down(Keycode::S) {
+                if input_map.is_active_with_mouse(
+                    CameraAction::MoveBackward,
+                    keyboard_state,
+                    mouse_state,
+                ) {
                     disp -= self.front;
                 }
 
-                if keyboard_state.is_down(Keycode::D) {
+                if input_map.is_active_with_mouse(
+                    CameraAction::MoveRight,
+                    keyboard_state,
+                    mouse_state,
+                ) {
                     disp += right;
                 }
-                if keyboard_state.is_down(Keycode::A) {
+                if input_map.is_active_with_mouse(
+                    CameraAction::MoveLeft,
+                    keyboard_state,
+                    mouse_state,
+                ) {
                     disp -= right;
                 }
 
-                if keyboard_state.is_down(Keycode::Space) {
+                if input_map.is_active_with_mouse(CameraAction::MoveUp, keyboard_state, mouse_state)
+                {
                     disp -= down;
                 }
-                if keyboard_state.is_down(Keycode::LShift) {
+                if input_map.is_active_with_mouse(
+                    CameraAction::MoveDown,
+                    keyboard_state,
+                    mouse_state,
+                ) {
                     disp += down;
                 }
 
-                let dt_scale = dt as f32 / 1000.0;
+                let left_stick = gamepad_state.left_stick();
+                disp += self.front * -left_stick.y();
+                disp += right * left_stick.x();
+                if gamepad_state.is_down(Button::A) {
+                    disp -= down;
+                }
+                if gamepad_state.is_down(Button::B) {
+                    disp += down;
+                }
 
                 if disp == Vec3::zero() {
                     disp
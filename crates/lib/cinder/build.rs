@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+use rust_shader_tools::{EnvVersion, OptimizationLevel, ShaderCompiler, ShaderStage};
+
+fn main() {
+    let shader_compiler = ShaderCompiler::new(
+        EnvVersion::Vulkan1_0,
+        OptimizationLevel::Zero,
+        Some(PathBuf::from("shaders")),
+    )
+    .expect("Could not create shader compiler");
+
+    shader_compiler
+        .compile_and_write_shader("shaders/fullscreen.vert", ShaderStage::Vertex)
+        .expect("Could not compile shader");
+    shader_compiler
+        .compile_and_write_shader("shaders/tonemap.frag", ShaderStage::Fragment)
+        .expect("Could not compile shader");
+    shader_compiler
+        .compile_and_write_shader("shaders/debug_draw.vert", ShaderStage::Vertex)
+        .expect("Could not compile shader");
+    shader_compiler
+        .compile_and_write_shader("shaders/debug_draw.frag", ShaderStage::Fragment)
+        .expect("Could not compile shader");
+    shader_compiler
+        .compile_and_write_shader("shaders/skybox.vert", ShaderStage::Vertex)
+        .expect("Could not compile shader");
+    shader_compiler
+        .compile_and_write_shader("shaders/skybox.frag", ShaderStage::Fragment)
+        .expect("Could not compile shader");
+    shader_compiler
+        .compile_and_write_shader("shaders/equirect_to_cube.comp", ShaderStage::Compute)
+        .expect("Could not compile shader");
+    shader_compiler
+        .compile_and_write_shader("shaders/ibl_irradiance.comp", ShaderStage::Compute)
+        .expect("Could not compile shader");
+    shader_compiler
+        .compile_and_write_shader("shaders/ibl_prefilter.comp", ShaderStage::Compute)
+        .expect("Could not compile shader");
+    shader_compiler
+        .compile_and_write_shader("shaders/brdf_lut.comp", ShaderStage::Compute)
+        .expect("Could not compile shader");
+    shader_compiler
+        .compile_and_write_shader("shaders/taa_resolve.frag", ShaderStage::Fragment)
+        .expect("Could not compile shader");
+}
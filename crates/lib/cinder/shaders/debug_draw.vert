@@ -0,0 +1,17 @@
+#version 460
+
+layout (location = 0) in vec3 i_pos;
+layout (location = 1) in vec4 i_color;
+
+layout (location = 0) out vec4 o_color;
+
+// Must match `DebugDraw::draw` in debug_draw.rs
+layout(push_constant) uniform constants
+{
+    mat4 view_proj;
+};
+
+void main() {
+    gl_Position = view_proj * vec4(i_pos, 1.0);
+    o_color = i_color;
+}
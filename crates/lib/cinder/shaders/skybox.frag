@@ -0,0 +1,11 @@
+#version 460
+
+layout (location = 0) in vec3 i_direction;
+
+layout (location = 0) out vec4 uFragColor;
+
+layout (set = 0, binding = 0) uniform samplerCube environment;
+
+void main() {
+    uFragColor = vec4(texture(environment, normalize(i_direction)).rgb, 1.0);
+}
@@ -0,0 +1,16 @@
+#version 460
+
+layout (location = 0) out vec3 o_direction;
+
+// Pushed by `Skybox::draw`
+layout(push_constant) uniform constants
+{
+    mat4 inverse_view_proj;
+};
+
+void main() {
+    vec2 ndc = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2) * 2.0 - 1.0;
+    // Depth is reversed with an infinite far plane, so points at depth 0 unproject to directions
+    o_direction = (inverse_view_proj * vec4(ndc, 0.0, 1.0)).xyz;
+    gl_Position = vec4(ndc, 0.0, 1.0);
+}
@@ -0,0 +1,57 @@
+#version 460
+
+layout (location = 0) in vec2 i_uv;
+
+// The same color goes to the history read next frame, and to the output read by later passes
+layout (location = 0) out vec4 o_history;
+layout (location = 1) out vec4 o_output;
+
+layout (set = 0, binding = 0) uniform sampler2D color_texture;
+// Current UV minus the UV the same surface had in the previous frame
+layout (set = 0, binding = 1) uniform sampler2D velocity_texture;
+layout (set = 0, binding = 2) uniform sampler2D history_texture;
+
+// Must match `ResolveConstants` in taa.rs
+layout(push_constant) uniform constants
+{
+    float blend_factor;
+    uint history_valid;
+};
+
+float luminance(vec3 color) {
+    return dot(color, vec3(0.2126, 0.7152, 0.0722));
+}
+
+void main() {
+    ivec2 size = textureSize(color_texture, 0);
+    ivec2 texel = ivec2(i_uv * vec2(size));
+    vec3 current = texelFetch(color_texture, texel, 0).rgb;
+
+    // Bounds of the colors around the pixel, history outside of them belongs to a surface that
+    // is no longer visible here
+    vec3 neighborhood_min = current;
+    vec3 neighborhood_max = current;
+    for (int y = -1; y <= 1; ++y) {
+        for (int x = -1; x <= 1; ++x) {
+            ivec2 neighbor = clamp(texel + ivec2(x, y), ivec2(0), size - 1);
+            vec3 color = texelFetch(color_texture, neighbor, 0).rgb;
+            neighborhood_min = min(neighborhood_min, color);
+            neighborhood_max = max(neighborhood_max, color);
+        }
+    }
+
+    vec2 prev_uv = i_uv - texelFetch(velocity_texture, texel, 0).xy;
+    bool on_screen = all(greaterThanEqual(prev_uv, vec2(0.0))) && all(lessThanEqual(prev_uv, vec2(1.0)));
+    vec3 result = current;
+    if (history_valid != 0 && on_screen) {
+        vec3 history = texture(history_texture, prev_uv).rgb;
+        history = clamp(history, neighborhood_min, neighborhood_max);
+        // Weighting by inverse luminance keeps single bright HDR samples from flickering
+        float current_weight = blend_factor / (1.0 + luminance(current));
+        float history_weight = (1.0 - blend_factor) / (1.0 + luminance(history));
+        result = (current * current_weight + history * history_weight) / (current_weight + history_weight);
+    }
+
+    o_history = vec4(result, 1.0);
+    o_output = vec4(result, 1.0);
+}
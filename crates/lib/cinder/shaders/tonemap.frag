@@ -0,0 +1,36 @@
+#version 460
+
+layout (location = 0) in vec2 i_uv;
+
+layout (location = 0) out vec4 uFragColor;
+
+layout (set = 0, binding = 0) uniform sampler2D hdr_texture;
+
+// Must match `TonemapConstants` in post_process.rs
+layout(push_constant) uniform constants
+{
+    float exposure;
+    // Index of `TonemapOperator`
+    uint operator;
+};
+
+vec3 reinhard(vec3 color) {
+    return color / (1.0 + color);
+}
+
+// Krzysztof Narkowicz's fit of the ACES filmic curve
+vec3 aces(vec3 color) {
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return clamp((color * (a * color + b)) / (color * (c * color + d) + e), 0.0, 1.0);
+}
+
+void main() {
+    vec3 color = texture(hdr_texture, i_uv).rgb * exposure;
+    color = operator == 0 ? reinhard(color) : aces(color);
+    // Lighting is done in linear space, and the swapchain is UNORM
+    uFragColor = vec4(pow(color, vec3(1.0 / 2.2)), 1.0);
+}
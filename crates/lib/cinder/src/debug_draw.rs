@@ -0,0 +1,206 @@
+use anyhow::Result;
+use bumpalo::Bump;
+use math::mat::Mat4;
+use math_ext::Mat4Ext;
+use render_graph::{AttachmentType, RenderGraph, RenderPass, RenderPassResource};
+use renderer::{
+    command_queue::RenderAttachmentDesc,
+    resources::{
+        buffer::{Buffer, BufferDescription, BufferUsage},
+        frame_buffered::FrameBuffered,
+        pipeline::graphics::{GraphicsPipeline, GraphicsPipelineDescription, PrimitiveTopology},
+    },
+    Renderer,
+};
+
+// Must match the vertex inputs in debug_draw.vert
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct DebugVertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DebugDrawDescription {
+    // Lines added past this in a single frame are dropped
+    pub max_lines: usize,
+    // Must match the passes that use the same `view_proj`
+    pub flipped_viewport: bool,
+    // In pixels. Widths above 1.0 need `DeviceCapabilities::max_line_width`, and fall back to 1.0
+    pub line_width: f32,
+}
+
+impl Default for DebugDrawDescription {
+    fn default() -> Self {
+        Self {
+            max_lines: 1 << 16,
+            flipped_viewport: true,
+            line_width: 1.0,
+        }
+    }
+}
+
+// Immediate mode line drawing for debug visualizations. Shapes are accumulated during the frame
+// and drawn over the swapchain by `draw`, without depth testing
+pub struct DebugDraw {
+    desc: DebugDrawDescription,
+    vertices: Vec<DebugVertex>,
+    vertex_buffers: FrameBuffered<Buffer>,
+    pipeline: GraphicsPipeline,
+}
+
+impl DebugDraw {
+    pub fn new(renderer: &Renderer, desc: DebugDrawDescription) -> Result<Self> {
+        let line_width = renderer
+            .device
+            .capabilities()
+            .supported_line_width(desc.line_width);
+        if line_width != desc.line_width {
+            println!(
+                "Debug draw line width {} is not supported, using {line_width}",
+                desc.line_width
+            );
+        }
+        let vertex_shader = renderer.device.create_shader(
+            include_bytes!("../shaders/spv/debug_draw.vert.spv"),
+            Default::default(),
+        )?;
+        let fragment_shader = renderer.device.create_shader(
+            include_bytes!("../shaders/spv/debug_draw.frag.spv"),
+            Default::default(),
+        )?;
+        let pipeline = renderer.device.create_graphics_pipeline(
+            &vertex_shader,
+            Some(&fragment_shader),
+            GraphicsPipelineDescription {
+                name: Some("Debug Draw Pipeline".to_owned()),
+                topology: PrimitiveTopology::LineList,
+                dynamic_line_width: true,
+                ..Default::default()
+            },
+        )?;
+        vertex_shader.destroy(&renderer.device);
+        fragment_shader.destroy(&renderer.device);
+
+        // Rewritten every frame, so each frame in flight gets its own copy
+        let vertex_buffers = FrameBuffered::create_buffers(
+            &renderer.device,
+            (desc.max_lines * 2 * std::mem::size_of::<DebugVertex>()) as u64,
+            BufferDescription {
+                name: Some("Debug Draw Vertices"),
+                usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+        )?;
+
+        Ok(Self {
+            desc,
+            vertices: Default::default(),
+            vertex_buffers,
+            pipeline,
+        })
+    }
+
+    pub fn line(&mut self, a: [f32; 3], b: [f32; 3], color: [f32; 4]) {
+        if self.vertices.len() / 2 < self.desc.max_lines {
+            self.vertices.push(DebugVertex { position: a, color });
+            self.vertices.push(DebugVertex { position: b, color });
+        }
+    }
+
+    pub fn aabb(&mut self, min: [f32; 3], max: [f32; 3], color: [f32; 4]) {
+        let corners = std::array::from_fn::<_, 8, _>(|corner| {
+            [
+                if corner & 1 == 0 { min[0] } else { max[0] },
+                if corner & 2 == 0 { min[1] } else { max[1] },
+                if corner & 4 == 0 { min[2] } else { max[2] },
+            ]
+        });
+        self.box_edges(&corners, color);
+    }
+
+    // Outlines the volume `view_proj` maps to clip space. Its corners are unprojected from depths
+    // 0 and 1, so infinite projections need a finite far plane to be drawn
+    pub fn frustum(&mut self, view_proj: &[f32; 16], color: [f32; 4]) {
+        let Some(inverse) = invert(view_proj) else {
+            return;
+        };
+        let corners = std::array::from_fn::<_, 8, _>(|corner| {
+            let ndc = [
+                if corner & 1 == 0 { -1.0 } else { 1.0 },
+                if corner & 2 == 0 { -1.0 } else { 1.0 },
+                if corner & 4 == 0 { 0.0 } else { 1.0 },
+                1.0,
+            ];
+            let mut p = [0.0; 4];
+            for (row, value) in p.iter_mut().enumerate() {
+                *value = (0..4).map(|col| inverse[col * 4 + row] * ndc[col]).sum();
+            }
+            [p[0] / p[3], p[1] / p[3], p[2] / p[3]]
+        });
+        self.box_edges(&corners, color);
+    }
+
+    // `corners` is indexed by a bit per axis, set for the corner's max side
+    fn box_edges(&mut self, corners: &[[f32; 3]; 8], color: [f32; 4]) {
+        for (corner, start) in corners.iter().enumerate() {
+            for axis in [1, 2, 4] {
+                if corner & axis == 0 {
+                    self.line(*start, corners[corner | axis], color);
+                }
+            }
+        }
+    }
+
+    // Draws every shape added since the last call over the swapchain. `view_proj` is a
+    // column-major matrix
+    pub fn draw<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+        view_proj: [f32; 16],
+    ) {
+        if self.vertices.is_empty() {
+            return;
+        }
+        let vertices = allocator.alloc_slice_copy(&self.vertices);
+        self.vertices.clear();
+
+        let this: &'a Self = self;
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .with_name("Debug Draw")
+                .with_flipped_viewport(this.desc.flipped_viewport)
+                .add_color_attachment(AttachmentType::SwapchainImage, RenderAttachmentDesc::load())
+                .add_input(RenderPassResource::SwapchainImage)
+                .add_output(RenderPassResource::SwapchainImage)
+                .set_callback(allocator, move |renderer, cmd_list| {
+                    let frame_index = renderer.current_frame_in_flight();
+                    this.vertex_buffers.write(frame_index, 0, vertices)?;
+
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &this.pipeline)?;
+                    cmd_list.set_line_width(&renderer.device, this.desc.line_width);
+                    cmd_list
+                        .bind_vertex_buffer(&renderer.device, this.vertex_buffers.get(frame_index));
+                    cmd_list.set_vertex_bytes(&renderer.device, &this.pipeline, &view_proj, 0)?;
+                    cmd_list.draw_vertices(&renderer.device, vertices.len() as u32, 0);
+
+                    Ok(())
+                }),
+        );
+    }
+
+    pub fn destroy(&mut self, renderer: &Renderer) {
+        self.vertex_buffers.destroy(&renderer.device);
+        self.pipeline.destroy(&renderer.device);
+    }
+}
+
+// Inverse of a column-major 4x4 matrix
+pub(crate) fn invert(m: &[f32; 16]) -> Option<[f32; 16]> {
+    Mat4::from_cols_array(m)
+        .inverse()
+        .map(|inverse| inverse.to_cols_array())
+}
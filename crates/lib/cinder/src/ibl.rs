@@ -0,0 +1,270 @@
+use anyhow::Result;
+use math::size::Size2D;
+use renderer::{
+    command_queue::CommandList,
+    resources::{
+        bind_group::{BindGroupPushInfo, BindGroupWriteData},
+        image::{Format, Image, ImageDescription, ImageDimension, ImageUsage, Layout},
+        pipeline::compute::{ComputePipeline, ComputePipelineDescription},
+        sampler::{AddressMode, MipmapMode, Sampler, SamplerDescription},
+    },
+    Renderer,
+};
+
+// Must match the local size of the IBL compute shaders
+const WORKGROUP_SIZE: u32 = 8;
+
+// Must match the storage image formats of the IBL compute shaders. The BRDF LUT only uses two
+// channels, but `rgba16f` storage is supported everywhere while `rg16f` isn't
+const CUBEMAP_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+const BRDF_LUT_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+
+// Must match the push constants in ibl_prefilter.comp
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct PrefilterConstants {
+    roughness: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct IblDescription {
+    // Irradiance varies slowly with direction, so its faces can be tiny
+    pub irradiance_size: u32,
+    // Size of the first level of the prefiltered cubemap, used for roughness 0
+    pub prefiltered_size: u32,
+    // Roughness goes from 0 at the first level to 1 at the last
+    pub prefiltered_mip_levels: u32,
+    pub brdf_lut_size: u32,
+}
+
+impl Default for IblDescription {
+    fn default() -> Self {
+        Self {
+            irradiance_size: 32,
+            prefiltered_size: 128,
+            prefiltered_mip_levels: 5,
+            brdf_lut_size: 512,
+        }
+    }
+}
+
+// Precomputed image-based lighting for an environment cubemap, split into a diffuse and a
+// specular part. Ambient light for a surface is
+// `irradiance(N) * albedo + prefiltered(R, roughness) * (F0 * lut.x + lut.y)`, with the LUT
+// sampled at `(dot(N, V), roughness)`
+pub struct IblMaps {
+    // Cubemap of the diffuse light arriving at a surface facing each direction
+    pub irradiance: Image,
+    // Cubemap of the environment convolved with GGX lobes of increasing roughness, one per level
+    pub prefiltered: Image,
+    // Scale and bias to F0 in the red and green channels
+    pub brdf_lut: Image,
+    // Clamped to the edges, and filtering between the prefiltered levels
+    pub sampler: Sampler,
+}
+
+impl IblMaps {
+    // `environment` must be a cubemap in `Layout::ShaderReadOnly`, like `Skybox::cubemap`
+    pub fn new(renderer: &Renderer, environment: &Image, desc: IblDescription) -> Result<Self> {
+        let sampler = renderer.device.create_sampler(SamplerDescription {
+            name: Some("IBL Sampler"),
+            address_mode: AddressMode::ClampToEdge,
+            mipmap_mode: MipmapMode::Linear,
+            ..Default::default()
+        })?;
+        let irradiance = renderer.device.create_image(
+            Size2D::new(desc.irradiance_size, desc.irradiance_size),
+            ImageDescription {
+                name: Some("IBL Irradiance"),
+                format: CUBEMAP_FORMAT,
+                usage: ImageUsage::StorageTexture,
+                dimension: ImageDimension::Cube,
+                ..Default::default()
+            },
+        )?;
+        let prefiltered = renderer.device.create_image(
+            Size2D::new(desc.prefiltered_size, desc.prefiltered_size),
+            ImageDescription {
+                name: Some("IBL Prefiltered"),
+                format: CUBEMAP_FORMAT,
+                usage: ImageUsage::StorageTexture,
+                dimension: ImageDimension::Cube,
+                mip_levels: desc.prefiltered_mip_levels.max(1),
+                ..Default::default()
+            },
+        )?;
+        let brdf_lut = renderer.device.create_image(
+            Size2D::new(desc.brdf_lut_size, desc.brdf_lut_size),
+            ImageDescription {
+                name: Some("IBL BRDF LUT"),
+                format: BRDF_LUT_FORMAT,
+                usage: ImageUsage::StorageTexture,
+                ..Default::default()
+            },
+        )?;
+
+        let create_pipeline = |bytes: &[u8], name: &str| -> Result<ComputePipeline> {
+            let shader = renderer.device.create_shader(bytes, Default::default())?;
+            let pipeline = renderer.device.create_compute_pipeline(
+                &shader,
+                ComputePipelineDescription {
+                    name: Some(name.to_owned()),
+                    ..Default::default()
+                },
+            )?;
+            shader.destroy(&renderer.device);
+            Ok(pipeline)
+        };
+        let irradiance_pipeline = create_pipeline(
+            include_bytes!("../shaders/spv/ibl_irradiance.comp.spv"),
+            "IBL Irradiance Pipeline",
+        )?;
+        let prefilter_pipeline = create_pipeline(
+            include_bytes!("../shaders/spv/ibl_prefilter.comp.spv"),
+            "IBL Prefilter Pipeline",
+        )?;
+        let brdf_lut_pipeline = create_pipeline(
+            include_bytes!("../shaders/spv/brdf_lut.comp.spv"),
+            "BRDF LUT Pipeline",
+        )?;
+
+        let cmd_list = renderer
+            .command_queue
+            .get_immediate_command_list(&renderer.device)?;
+        for image in [&irradiance, &prefiltered, &brdf_lut] {
+            cmd_list.transition_image(&renderer.device, image, Layout::Undefined, Layout::General);
+        }
+
+        let environment_info = || BindGroupPushInfo {
+            dst_binding: 0,
+            data: BindGroupWriteData::SampledImage(environment.bind_info(
+                &sampler,
+                Layout::ShaderReadOnly,
+                None,
+            )),
+        };
+        cmd_list.bind_compute_pipeline(&renderer.device, &irradiance_pipeline);
+        dispatch_cube_level(
+            renderer,
+            &cmd_list,
+            &irradiance_pipeline,
+            &[
+                environment_info(),
+                BindGroupPushInfo {
+                    dst_binding: 1,
+                    data: BindGroupWriteData::StorageImage(irradiance.mip_bind_info(
+                        &renderer.device,
+                        None,
+                        Layout::General,
+                        0,
+                    )?),
+                },
+            ],
+            desc.irradiance_size,
+        )?;
+
+        let max_level = prefiltered.mip_levels() - 1;
+        cmd_list.bind_compute_pipeline(&renderer.device, &prefilter_pipeline);
+        for level in 0..prefiltered.mip_levels() {
+            cmd_list.set_compute_bytes(
+                &renderer.device,
+                &prefilter_pipeline,
+                &PrefilterConstants {
+                    roughness: level as f32 / max_level.max(1) as f32,
+                },
+                0,
+            )?;
+            dispatch_cube_level(
+                renderer,
+                &cmd_list,
+                &prefilter_pipeline,
+                &[
+                    environment_info(),
+                    BindGroupPushInfo {
+                        dst_binding: 1,
+                        data: BindGroupWriteData::StorageImage(prefiltered.mip_bind_info(
+                            &renderer.device,
+                            None,
+                            Layout::General,
+                            level,
+                        )?),
+                    },
+                ],
+                (desc.prefiltered_size >> level).max(1),
+            )?;
+        }
+
+        cmd_list.bind_compute_pipeline(&renderer.device, &brdf_lut_pipeline);
+        let bind_group = renderer.bind_group_cache.get(
+            &renderer.device,
+            brdf_lut_pipeline.bind_group_data(0).unwrap(),
+            &[BindGroupPushInfo {
+                dst_binding: 0,
+                data: BindGroupWriteData::StorageImage(brdf_lut.storage_bind_info(None)),
+            }],
+        )?;
+        cmd_list.bind_compute_descriptor_sets(
+            &renderer.device,
+            &brdf_lut_pipeline,
+            0,
+            &[bind_group],
+        );
+        let groups = desc.brdf_lut_size.div_ceil(WORKGROUP_SIZE);
+        cmd_list.dispatch(&renderer.device, groups, groups, 1);
+
+        for image in [&irradiance, &prefiltered, &brdf_lut] {
+            cmd_list.transition_image(
+                &renderer.device,
+                image,
+                Layout::General,
+                Layout::ShaderReadOnly,
+            );
+        }
+        cmd_list.end(&renderer.device)?;
+        cmd_list.immediate_submit(&renderer.device, renderer.device.present_queue())?;
+        cmd_list.reset(&renderer.device)?;
+
+        irradiance_pipeline.destroy(&renderer.device);
+        prefilter_pipeline.destroy(&renderer.device);
+        brdf_lut_pipeline.destroy(&renderer.device);
+
+        Ok(Self {
+            irradiance,
+            prefiltered,
+            brdf_lut,
+            sampler,
+        })
+    }
+
+    // Level of the prefiltered cubemap for roughness 1
+    pub fn max_reflection_lod(&self) -> f32 {
+        (self.prefiltered.mip_levels() - 1) as f32
+    }
+
+    pub fn destroy(&mut self, renderer: &Renderer) {
+        self.sampler.destroy(&renderer.device);
+        self.irradiance.destroy(&renderer.device);
+        self.prefiltered.destroy(&renderer.device);
+        self.brdf_lut.destroy(&renderer.device);
+    }
+}
+
+// Runs the bound `pipeline` over every face of a `size` x `size` cubemap level, one face per
+// workgroup layer
+fn dispatch_cube_level(
+    renderer: &Renderer,
+    cmd_list: &CommandList,
+    pipeline: &ComputePipeline,
+    writes: &[BindGroupPushInfo],
+    size: u32,
+) -> Result<()> {
+    let bind_group = renderer.bind_group_cache.get(
+        &renderer.device,
+        pipeline.bind_group_data(0).unwrap(),
+        writes,
+    )?;
+    cmd_list.bind_compute_descriptor_sets(&renderer.device, pipeline, 0, &[bind_group]);
+    let groups = size.div_ceil(WORKGROUP_SIZE);
+    cmd_list.dispatch(&renderer.device, groups, groups, 6);
+    Ok(())
+}
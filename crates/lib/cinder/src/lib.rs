@@ -1,32 +1,84 @@
+pub mod debug_draw;
+pub mod ibl;
+pub mod post_process;
+pub mod skybox;
+pub mod taa;
+pub mod testing;
+
 use egui_integration::{EguiIntegration, SharedEguiMenu};
 use render_graph::PresentContext;
-use renderer::shader_hot_reloader::HotReloaderState;
+use renderer::{
+    shader_hot_reloader::{HotReloaderState, PipelineShaderIdSet},
+    swapchain::SwapchainStatus,
+};
 use sdl2::{event::Event, keyboard::Keycode, video::Window};
 use util::SdlContext;
 
+pub use debug_draw::{DebugDraw, DebugDrawDescription};
 pub use egui_integration::egui::Context as DebugUiContext;
-pub use render_graph::{AttachmentType, RenderGraph, RenderPass, RenderPassResource};
+pub use ibl::{IblDescription, IblMaps};
+pub use input::InputState;
+pub use post_process::{PostProcess, TonemapOperator, FULLSCREEN_VERTEX_SHADER};
+pub use render_graph::{
+    AttachmentType, RenderGraph, RenderGraphError, RenderPass, RenderPassResource,
+};
 pub use renderer::{
-    command_queue::{AttachmentLoadOp, AttachmentStoreOp, ClearValue, RenderAttachmentDesc},
+    command_queue::{
+        Access, AttachmentLoadOp, AttachmentStoreOp, BufferBarrier, ClearValue, CommandList,
+        ImageBarrier, ImageRegion, PipelineStage, RenderAttachmentDesc, RenderingError,
+    },
+    device::{
+        clear_debug_callback, set_debug_callback, DebugMessage, DebugMessageSeverity,
+        DebugMessageType, DeviceCapabilities, DeviceDescription, Fence, SampleCount, Semaphore,
+        ValidationConfig, MAX_FRAMES_IN_FLIGHT,
+    },
+    profiling::{OcclusionQueries, QueryPool},
+    renderer::time::Time,
     resources::{
-        bind_group::{BindGroup, BindGroupBindInfo, BindGroupData, BindGroupWriteData},
-        buffer::{Buffer, BufferDescription, BufferUsage},
-        image::{Format, Image, ImageDescription, ImageUsage, Layout},
+        bind_group::{
+            cache::BindGroupCache, BindGroup, BindGroupBindInfo, BindGroupData, BindGroupPushInfo,
+            BindGroupWriteData,
+        },
+        buffer::{Buffer, BufferDescription, BufferUsage, IndexType},
+        frame_buffered::FrameBuffered,
+        image::{
+            max_mip_levels, Format, Image, ImageDescription, ImageDimension, ImageUsage,
+            ImageViewDescription, ImageViewId, ImageViewType, Layout, SubresourceRange,
+        },
+        memory::{HeapBudget, MemoryStats},
         pipeline::{
+            compute::{ComputePipeline, ComputePipelineDescription},
             graphics::{
-                GraphicsPipeline, GraphicsPipelineDescription, VertexAttributeDescription,
+                ColorBlendState, ColorComponents, CompareOp, CullMode, FrontFace, GraphicsPipeline,
+                GraphicsPipelineDescription, GraphicsPipelineInfo, PrimitiveTopology,
+                StencilFaceState, StencilOp, StencilState, VertexAttributeDescription,
                 VertexBindingDesc, VertexDescription, VertexInputRate,
             },
             PipelineError,
         },
-        sampler::{AddressMode, BorderColor, MipmapMode, Sampler, SamplerDescription},
-        shader::ShaderDesc,
+        sampler::{AddressMode, BorderColor, Filter, MipmapMode, Sampler, SamplerDescription},
+        shader::{Shader, ShaderDesc},
+        texture_streamer::{
+            TextureData, TextureLoader, TextureStreamer, TextureStreamerDescription,
+            TextureStreamerError,
+        },
+        transient::TransientId,
+        ManagedResource,
     },
-    Renderer, ResourceId,
+    Renderer, ResizeCallback, ResourceId, DEFAULT_TEXTURE_COUNT, FLAT_NORMAL_TEXTURE_INDEX,
+    WHITE_TEXTURE_INDEX,
 };
+pub use skybox::{Skybox, SkyboxDescription};
+pub use taa::{Taa, TaaDescription, VELOCITY_FORMAT};
 // TODO: Wrap
 pub use bumpalo::Bump;
 
+// Written to the working directory from the shared menu's "Dump Render Graph" button
+const RENDER_GRAPH_DOT_PATH: &str = "render_graph.dot";
+
+// Frees a captured mouse, e.g. to use the debug UI. Clicking outside of the UI captures it again
+const RELEASE_MOUSE_KEY: Keycode = Keycode::LAlt;
+
 pub struct InitContext<'a> {
     pub renderer: &'a mut Renderer,
     pub shader_hot_reloader: &'a mut HotReloaderState,
@@ -46,7 +98,12 @@ pub trait App: Sized {
     fn on_frame_start(&mut self) -> anyhow::Result<()> {
         Ok(())
     }
-    fn update(&mut self, _renderer: &mut Renderer) -> anyhow::Result<()> {
+    // Called zero or more times per frame, before `update`, once for every `Time::fixed_timestep`
+    // that passed since the last frame
+    fn fixed_update(&mut self, _dt: f32) -> anyhow::Result<()> {
+        Ok(())
+    }
+    fn update(&mut self, _renderer: &mut Renderer, _input: &InputState) -> anyhow::Result<()> {
         Ok(())
     }
     fn on_event(&mut self, _event: &Event) -> anyhow::Result<()> {
@@ -72,6 +129,7 @@ pub struct Cinder<A: App> {
     shared_egui_menu: SharedEguiMenu,
     // TODO: feature flag to disable, off by default in release (i.e. shader-hot-reload and shader-hot-reload-release features)
     shader_hot_reloader: HotReloaderState,
+    input: InputState,
     app: A,
 }
 
@@ -80,15 +138,25 @@ where
     A: App,
 {
     pub fn new(window: &Window) -> anyhow::Result<Self> {
+        Self::with_description(window, Default::default())
+    }
+
+    pub fn with_description(window: &Window, desc: DeviceDescription) -> anyhow::Result<Self> {
         let (width, height) = window.drawable_size();
         // TODO: Pull ResourceManager out of renderer
-        let mut renderer = Renderer::new(window, width, height)?;
+        let renderer = Renderer::new(window, width, height, desc)?;
+        Self::with_renderer(renderer)
+    }
+
+    // Runs the app without a window, see `render_headless_frame`
+    pub fn new_headless(width: u32, height: u32) -> anyhow::Result<Self> {
+        let renderer = Renderer::new_headless(width, height, Default::default())?;
+        Self::with_renderer(renderer)
+    }
+
+    fn with_renderer(mut renderer: Renderer) -> anyhow::Result<Self> {
         let allocator = Bump::new();
-        let egui = EguiIntegration::new(
-            &mut renderer.resource_manager,
-            &renderer.device,
-            &renderer.swapchain,
-        )?;
+        let egui = EguiIntegration::new(&mut renderer.resource_manager, &renderer.device)?;
         let shared_egui_menu = SharedEguiMenu::default();
         let mut shader_hot_reloader = HotReloaderState::new()?;
 
@@ -104,20 +172,49 @@ where
             egui,
             shared_egui_menu,
             shader_hot_reloader,
+            input: Default::default(),
             app,
         })
     }
 
     // TODO: Update function
 
-    fn draw(&mut self) -> anyhow::Result<bool> {
-        let present_context: anyhow::Result<PresentContext> = {
+    // Renders a single frame of a headless app, without the debug UI, and returns its pixels
+    pub fn render_headless_frame(&mut self) -> anyhow::Result<Vec<u8>> {
+        self.allocator.reset();
+        self.renderer.start_frame()?;
+        self.app.on_frame_start()?;
+        self.fixed_update()?;
+        self.app.update(&mut self.renderer, &self.input)?;
+
+        let present_context = {
             let mut graph = RenderGraph::new(&self.allocator);
             self.app.draw(&self.allocator, &mut graph)?;
-            let present_context = graph.run(&self.allocator, &mut self.renderer)?;
-            Ok(present_context)
+            graph.run(&self.allocator, &mut self.renderer)?
+        };
+        present_context.present(&mut self.renderer)?;
+
+        self.renderer.end_frame();
+        self.renderer.read_headless_frame()
+    }
+
+    fn draw(&mut self) -> anyhow::Result<SwapchainStatus> {
+        let present_context: Result<PresentContext, RenderGraphError> = {
+            let mut graph = RenderGraph::new(&self.allocator);
+            self.app.draw(&self.allocator, &mut graph)?;
+            if self.shared_egui_menu.take_dump_render_graph() {
+                std::fs::write(RENDER_GRAPH_DOT_PATH, graph.to_dot())?;
+                println!("Wrote render graph to {RENDER_GRAPH_DOT_PATH}");
+            }
+            graph.run(&self.allocator, &mut self.renderer)
+        };
+        let present_context = match present_context {
+            Ok(present_context) => present_context,
+            Err(RenderGraphError::SwapchainOutOfDate) => {
+                return Ok(SwapchainStatus::OutOfDate);
+            }
+            Err(err) => return Err(err.into()),
         };
-        let present_context = present_context?;
 
         self.egui.run(
             &mut self.renderer.resource_manager,
@@ -135,9 +232,24 @@ where
         present_context.present(&mut self.renderer)
     }
 
+    fn fixed_update(&mut self) -> anyhow::Result<()> {
+        let dt = self.renderer.time.fixed_delta_seconds();
+        while self.renderer.time.consume_fixed_step() {
+            self.app.fixed_update(dt)?;
+        }
+        Ok(())
+    }
+
     fn update(&mut self) -> anyhow::Result<()> {
+        self.fixed_update()?;
         self.shared_egui_menu.update(&mut self.egui);
-        self.app.update(&mut self.renderer)
+        self.shared_egui_menu
+            .set_gpu_timings(self.renderer.gpu_profiler.results());
+        self.shared_egui_menu
+            .set_frame_stats(self.renderer.frame_stats());
+        self.shared_egui_menu
+            .set_memory_budget(self.renderer.device.memory_budget());
+        self.app.update(&mut self.renderer, &self.input)
     }
 
     fn resize(&mut self, width: u32, height: u32) -> anyhow::Result<()> {
@@ -164,30 +276,51 @@ where
                     update_data.shader_handle,
                     &update_data.bytes,
                 )?;
-                self.renderer.device.recreate_graphics_pipeline(
-                    &mut self.renderer.resource_manager,
-                    pipeline_shader_set.pipeline_handle,
-                    pipeline_shader_set.vertex_handle,
-                    Some(pipeline_shader_set.fragment_handle),
-                )?;
+                match pipeline_shader_set {
+                    PipelineShaderIdSet::Graphics(set) => {
+                        self.renderer.device.recreate_graphics_pipeline(
+                            &mut self.renderer.resource_manager,
+                            set.pipeline_handle,
+                            set.vertex_handle,
+                            Some(set.fragment_handle),
+                        )?;
+                    }
+                    PipelineShaderIdSet::Compute(set) => {
+                        self.renderer.device.recreate_compute_pipeline(
+                            &mut self.renderer.resource_manager,
+                            set.pipeline_handle,
+                            set.shader_handle,
+                        )?;
+                    }
+                }
             }
         }
+        self.shared_egui_menu
+            .set_shader_error(self.shader_hot_reloader.error());
         Ok(())
     }
 
     pub fn run_game_loop(&mut self, sdl: &mut SdlContext) -> anyhow::Result<()> {
         self.init_hot_reloader();
+        // Only apps that start with the mouse captured get it captured again
+        let capture_mouse = sdl.is_mouse_captured();
 
         'running: loop {
             self.allocator.reset();
             self.renderer.start_frame()?;
 
             self.app.on_frame_start()?;
+            self.input.on_frame_start();
 
+            // Applied after the events, which borrow the SDL context
+            let mut mouse_captured = None;
             for event in sdl.event_pump.poll_iter() {
-                self.app.on_event(&event)?;
+                sdl.game_controllers.on_event(&event);
+                // Events used by the debug UI, e.g. dragging a slider, don't reach the app
                 let response = self.egui.on_event(&event);
                 if !response.consumed {
+                    self.input.on_event(&event);
+                    self.app.on_event(&event)?;
                     match event {
                         Event::Quit { .. }
                         | Event::KeyDown {
@@ -196,6 +329,21 @@ where
                         } => {
                             break 'running;
                         }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::F3),
+                            ..
+                        } => {
+                            self.shared_egui_menu.toggle_stats();
+                        }
+                        Event::KeyDown {
+                            keycode: Some(RELEASE_MOUSE_KEY),
+                            ..
+                        } => {
+                            mouse_captured = Some(false);
+                        }
+                        Event::MouseButtonDown { .. } if capture_mouse => {
+                            mouse_captured = Some(true);
+                        }
                         Event::Window {
                             win_event: sdl2::event::WindowEvent::SizeChanged(width, height),
                             ..
@@ -204,13 +352,28 @@ where
                         }
                         _ => {}
                     }
+                } else if matches!(event, Event::MouseButtonUp { .. }) {
+                    // Buttons pressed outside of the debug UI still need to be released
+                    self.input.on_event(&event);
                 }
             }
 
+            if let Some(captured) = mouse_captured {
+                sdl.set_mouse_captured(captured);
+            }
+            // The cursor moving over the debug UI shouldn't move the camera
+            if capture_mouse && !sdl.is_mouse_captured() {
+                self.input.mouse.reset_delta();
+            }
+
             self.update_hot_reloader()?;
             self.update()?;
 
-            self.draw()?;
+            // The swapchain can go stale without a resize event, e.g. when moving between monitors
+            if self.draw()?.needs_recreation() {
+                let (width, height) = sdl.window.drawable_size();
+                self.resize(width, height)?;
+            }
 
             self.renderer.end_frame();
         }
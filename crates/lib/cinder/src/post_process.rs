@@ -0,0 +1,148 @@
+use anyhow::Result;
+use bumpalo::Bump;
+use render_graph::{AttachmentType, RenderGraph, RenderPass, RenderPassResource};
+use renderer::{
+    resources::{
+        bind_group::{BindGroup, BindGroupBindInfo, BindGroupWriteData},
+        image::{Image, Layout},
+        pipeline::graphics::{GraphicsPipeline, GraphicsPipelineDescription},
+        sampler::{AddressMode, Sampler, SamplerDescription},
+    },
+    Renderer, ResourceId,
+};
+
+// Generates a triangle covering the viewport from `gl_VertexIndex`, and outputs its UVs at
+// location 0. Draw it with `CommandList::draw_fullscreen_triangle`
+pub const FULLSCREEN_VERTEX_SHADER: &[u8] = include_bytes!("../shaders/spv/fullscreen.vert.spv");
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TonemapOperator {
+    Reinhard,
+    #[default]
+    Aces,
+}
+
+impl TonemapOperator {
+    pub const ALL: [TonemapOperator; 2] = [TonemapOperator::Reinhard, TonemapOperator::Aces];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TonemapOperator::Reinhard => "Reinhard",
+            TonemapOperator::Aces => "ACES",
+        }
+    }
+}
+
+// Must match the push constants in tonemap.frag
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct TonemapConstants {
+    exposure: f32,
+    operator: u32,
+}
+
+// Full-screen passes that read an image and write the swapchain
+pub struct PostProcess {
+    pub operator: TonemapOperator,
+    // Scales the HDR color before tonemapping
+    pub exposure: f32,
+    tonemap_pipeline: GraphicsPipeline,
+    tonemap_bind_group: BindGroup,
+    sampler: Sampler,
+}
+
+impl PostProcess {
+    pub fn new(renderer: &Renderer) -> Result<Self> {
+        let fullscreen_vs = renderer
+            .device
+            .create_shader(FULLSCREEN_VERTEX_SHADER, Default::default())?;
+        let tonemap_fs = renderer.device.create_shader(
+            include_bytes!("../shaders/spv/tonemap.frag.spv"),
+            Default::default(),
+        )?;
+        let tonemap_pipeline = renderer.device.create_graphics_pipeline(
+            &fullscreen_vs,
+            Some(&tonemap_fs),
+            GraphicsPipelineDescription {
+                name: Some("Tonemap Pipeline".to_owned()),
+                ..Default::default()
+            },
+        )?;
+        fullscreen_vs.destroy(&renderer.device);
+        tonemap_fs.destroy(&renderer.device);
+
+        let tonemap_bind_group = BindGroup::new(
+            &renderer.device,
+            tonemap_pipeline.bind_group_data(0).unwrap(),
+        )?;
+        let sampler = renderer.device.create_sampler(SamplerDescription {
+            address_mode: AddressMode::ClampToEdge,
+            ..Default::default()
+        })?;
+
+        Ok(Self {
+            operator: Default::default(),
+            exposure: 1.0,
+            tonemap_pipeline,
+            tonemap_bind_group,
+            sampler,
+        })
+    }
+
+    // Must be called before the first `tonemap`, and again whenever `input` is resized
+    pub fn set_input(&self, renderer: &Renderer, input: ResourceId<Image>) -> Result<()> {
+        let image = renderer.resource_manager.images.get(input).unwrap();
+        renderer.device.write_bind_group(&[BindGroupBindInfo {
+            group: self.tonemap_bind_group,
+            dst_binding: 0,
+            data: BindGroupWriteData::SampledImage(image.bind_info(
+                &self.sampler,
+                Layout::ShaderReadOnly,
+                None,
+            )),
+        }])?;
+        Ok(())
+    }
+
+    // Maps the HDR `input`, set with `set_input`, to the swapchain
+    pub fn tonemap<'a>(
+        &'a self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+        input: ResourceId<Image>,
+    ) {
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .add_color_attachment(AttachmentType::SwapchainImage, Default::default())
+                .add_input(RenderPassResource::Image(input))
+                .add_output(RenderPassResource::SwapchainImage)
+                .set_callback(allocator, |renderer, cmd_list| {
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &self.tonemap_pipeline)?;
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &self.tonemap_pipeline,
+                        0,
+                        &[self.tonemap_bind_group],
+                    );
+                    cmd_list.set_fragment_bytes(
+                        &renderer.device,
+                        &self.tonemap_pipeline,
+                        &TonemapConstants {
+                            exposure: self.exposure,
+                            operator: self.operator as u32,
+                        },
+                        0,
+                    )?;
+                    cmd_list.draw_fullscreen_triangle(&renderer.device);
+
+                    Ok(())
+                }),
+        );
+    }
+
+    pub fn destroy(&mut self, renderer: &Renderer) {
+        self.sampler.destroy(&renderer.device);
+        self.tonemap_pipeline.destroy(&renderer.device);
+    }
+}
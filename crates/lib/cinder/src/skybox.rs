@@ -0,0 +1,239 @@
+use crate::debug_draw::invert;
+use anyhow::Result;
+use math::size::Size2D;
+use renderer::{
+    command_queue::CommandList,
+    resources::{
+        bind_group::{BindGroup, BindGroupBindInfo, BindGroupPushInfo, BindGroupWriteData},
+        image::{Format, Image, ImageDescription, ImageDimension, ImageUsage, Layout},
+        pipeline::{
+            compute::ComputePipelineDescription,
+            graphics::{CompareOp, GraphicsPipeline, GraphicsPipelineDescription},
+        },
+        sampler::{AddressMode, Sampler, SamplerDescription},
+    },
+    Renderer,
+};
+use std::path::Path;
+
+// Must match the local size in equirect_to_cube.comp
+const WORKGROUP_SIZE: u32 = 8;
+
+// Half floats keep the HDR range, and can be both filtered and written from compute everywhere.
+// Must match the storage image format in equirect_to_cube.comp
+const CUBEMAP_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+
+// Must match the push constants in equirect_to_cube.comp
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct CubeFaceConstants {
+    face: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct SkyboxDescription {
+    // Size of each cubemap face, a quarter of the equirectangular map's width by default
+    pub face_size: Option<u32>,
+    // Must match the pass the skybox is drawn in
+    pub color_format: Format,
+    pub depth_format: Format,
+}
+
+impl Default for SkyboxDescription {
+    fn default() -> Self {
+        Self {
+            face_size: None,
+            color_format: Format::B8G8R8A8_UNORM,
+            depth_format: Format::D32_SFLOAT,
+        }
+    }
+}
+
+// An HDR environment drawn behind the scene. The equirectangular map is converted to a cubemap
+// with compute when the skybox is created
+pub struct Skybox {
+    cubemap: Image,
+    pipeline: GraphicsPipeline,
+    bind_group: BindGroup,
+    sampler: Sampler,
+}
+
+impl Skybox {
+    pub fn new(
+        renderer: &Renderer,
+        hdr_path: impl AsRef<Path>,
+        desc: SkyboxDescription,
+    ) -> Result<Self> {
+        let equirect = image::open(hdr_path.as_ref())?.into_rgba32f();
+        let equirect_size = Size2D::new(equirect.width(), equirect.height());
+        let face_size = desc.face_size.unwrap_or(equirect_size.width() / 4).max(1);
+
+        let sampler = renderer.device.create_sampler(SamplerDescription {
+            address_mode: AddressMode::ClampToEdge,
+            ..Default::default()
+        })?;
+        let cubemap = renderer.device.create_image(
+            Size2D::new(face_size, face_size),
+            ImageDescription {
+                name: Some("Skybox Cubemap"),
+                format: CUBEMAP_FORMAT,
+                usage: ImageUsage::StorageTexture,
+                dimension: ImageDimension::Cube,
+                ..Default::default()
+            },
+        )?;
+
+        let shader = renderer.device.create_shader(
+            include_bytes!("../shaders/spv/equirect_to_cube.comp.spv"),
+            Default::default(),
+        )?;
+        let convert_pipeline = renderer.device.create_compute_pipeline(
+            &shader,
+            ComputePipelineDescription {
+                name: Some("Equirect To Cube Pipeline".to_owned()),
+                ..Default::default()
+            },
+        )?;
+        shader.destroy(&renderer.device);
+
+        let cmd_list = renderer
+            .command_queue
+            .get_immediate_command_list(&renderer.device)?;
+        let (mut equirect_image, staging_buffer) = renderer.device.create_image_with_data(
+            equirect_size,
+            util::typed_to_bytes(equirect.as_raw().as_slice()),
+            &cmd_list,
+            ImageDescription {
+                name: Some("Skybox Equirect"),
+                format: Format::R32G32B32A32_SFLOAT,
+                ..Default::default()
+            },
+        )?;
+        cmd_list.transition_image(
+            &renderer.device,
+            &cubemap,
+            Layout::Undefined,
+            Layout::General,
+        );
+        cmd_list.bind_compute_pipeline(&renderer.device, &convert_pipeline);
+        for face in 0..cubemap.array_layers() {
+            let face_info = cubemap.storage_bind_info_layer(&renderer.device, face, None)?;
+            let bind_group = renderer.bind_group_cache.get(
+                &renderer.device,
+                convert_pipeline.bind_group_data(0).unwrap(),
+                &[
+                    BindGroupPushInfo {
+                        dst_binding: 0,
+                        data: BindGroupWriteData::SampledImage(equirect_image.bind_info(
+                            &sampler,
+                            Layout::ShaderReadOnly,
+                            None,
+                        )),
+                    },
+                    BindGroupPushInfo {
+                        dst_binding: 1,
+                        data: BindGroupWriteData::StorageImage(face_info),
+                    },
+                ],
+            )?;
+            cmd_list.bind_compute_descriptor_sets(
+                &renderer.device,
+                &convert_pipeline,
+                0,
+                &[bind_group],
+            );
+            cmd_list.set_compute_bytes(
+                &renderer.device,
+                &convert_pipeline,
+                &CubeFaceConstants { face },
+                0,
+            )?;
+            let groups = face_size.div_ceil(WORKGROUP_SIZE);
+            cmd_list.dispatch(&renderer.device, groups, groups, 1);
+        }
+        cmd_list.transition_image(
+            &renderer.device,
+            &cubemap,
+            Layout::General,
+            Layout::ShaderReadOnly,
+        );
+        cmd_list.end(&renderer.device)?;
+        cmd_list.immediate_submit(&renderer.device, renderer.device.present_queue())?;
+        cmd_list.reset(&renderer.device)?;
+
+        staging_buffer.destroy(&renderer.device);
+        equirect_image.destroy(&renderer.device);
+        convert_pipeline.destroy(&renderer.device);
+
+        let vertex_shader = renderer.device.create_shader(
+            include_bytes!("../shaders/spv/skybox.vert.spv"),
+            Default::default(),
+        )?;
+        let fragment_shader = renderer.device.create_shader(
+            include_bytes!("../shaders/spv/skybox.frag.spv"),
+            Default::default(),
+        )?;
+        // Drawn at the far plane after the scene, so it only shows where nothing else was drawn.
+        // Depth is reversed, so this is the usual less-or-equal test
+        let pipeline = renderer.device.create_graphics_pipeline(
+            &vertex_shader,
+            Some(&fragment_shader),
+            GraphicsPipelineDescription {
+                name: Some("Skybox Pipeline".to_owned()),
+                color_formats: vec![desc.color_format],
+                depth_format: Some(desc.depth_format),
+                depth_write: false,
+                depth_compare: CompareOp::GreaterOrEqual,
+                ..Default::default()
+            },
+        )?;
+        vertex_shader.destroy(&renderer.device);
+        fragment_shader.destroy(&renderer.device);
+
+        let bind_group = BindGroup::new(&renderer.device, pipeline.bind_group_data(0).unwrap())?;
+        renderer.device.write_bind_group(&[BindGroupBindInfo {
+            group: bind_group,
+            dst_binding: 0,
+            data: BindGroupWriteData::SampledImage(cubemap.bind_info(
+                &sampler,
+                Layout::ShaderReadOnly,
+                None,
+            )),
+        }])?;
+
+        Ok(Self {
+            cubemap,
+            pipeline,
+            bind_group,
+            sampler,
+        })
+    }
+
+    pub fn cubemap(&self) -> &Image {
+        &self.cubemap
+    }
+
+    // Records the skybox into the current pass, after the scene. `view_proj` is the column-major
+    // matrix the scene was drawn with, and must have an infinite far plane
+    pub fn draw(
+        &self,
+        renderer: &Renderer,
+        cmd_list: &CommandList,
+        view_proj: &[f32; 16],
+    ) -> Result<()> {
+        let Some(inverse_view_proj) = invert(view_proj) else {
+            return Ok(());
+        };
+        cmd_list.bind_graphics_pipeline(&renderer.device, &self.pipeline)?;
+        cmd_list.bind_descriptor_sets(&renderer.device, &self.pipeline, 0, &[self.bind_group]);
+        cmd_list.set_vertex_bytes(&renderer.device, &self.pipeline, &inverse_view_proj, 0)?;
+        cmd_list.draw_fullscreen_triangle(&renderer.device);
+        Ok(())
+    }
+
+    pub fn destroy(&mut self, renderer: &Renderer) {
+        self.sampler.destroy(&renderer.device);
+        self.pipeline.destroy(&renderer.device);
+        self.cubemap.destroy(&renderer.device);
+    }
+}
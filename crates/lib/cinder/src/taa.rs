@@ -0,0 +1,263 @@
+use crate::post_process::FULLSCREEN_VERTEX_SHADER;
+use anyhow::Result;
+use bumpalo::Bump;
+use math::size::Size2D;
+use render_graph::{AttachmentType, RenderGraph, RenderPass, RenderPassResource};
+use renderer::{
+    command_queue::{ClearValue, RenderAttachmentDesc},
+    resources::{
+        bind_group::{BindGroupPushInfo, BindGroupWriteData},
+        image::{Format, Image, ImageDescription, ImageUsage, Layout},
+        pipeline::graphics::{GraphicsPipeline, GraphicsPipelineDescription},
+        sampler::{AddressMode, Sampler, SamplerDescription},
+    },
+    Renderer, ResourceId,
+};
+
+// Format of `Taa::velocity`, current UV minus previous UV in the red and green channels
+pub const VELOCITY_FORMAT: Format = Format::R16G16_SFLOAT;
+
+// Must match the push constants in taa_resolve.frag
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ResolveConstants {
+    blend_factor: f32,
+    history_valid: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TaaDescription {
+    // Format of the color images passed to `resolve`, and of the resolved output
+    pub color_format: Format,
+}
+
+impl Default for TaaDescription {
+    fn default() -> Self {
+        Self {
+            color_format: Format::R16G16B16A16_SFLOAT,
+        }
+    }
+}
+
+// Temporal anti-aliasing. The scene is drawn with `Camera::jittered_projection` for
+// `frame_index`, writing motion vectors to `velocity`, and `resolve` blends it with the
+// reprojected result of the previous frames.
+// The history is a pair of images that swap every frame, one read while the other is written
+pub struct Taa {
+    // Disabled, `resolve` passes the color through and the history is dropped
+    pub enabled: bool,
+    // Weight of the current frame in the result. Lower values are smoother, but ghost more
+    pub blend_factor: f32,
+    velocity: ResourceId<Image>,
+    history: [ResourceId<Image>; 2],
+    output: ResourceId<Image>,
+    pipeline: GraphicsPipeline,
+    sampler: Sampler,
+    frame_index: usize,
+    history_valid: bool,
+}
+
+impl Taa {
+    pub fn new(renderer: &mut Renderer, desc: TaaDescription) -> Result<Self> {
+        let surface_rect = renderer.device.surface_rect();
+        let surface_size = Size2D::new(surface_rect.width(), surface_rect.height());
+        let create_image = |name, format| {
+            renderer.device.create_image(
+                surface_size,
+                ImageDescription {
+                    name: Some(name),
+                    format,
+                    usage: ImageUsage::ColorAttachment,
+                    ..Default::default()
+                },
+            )
+        };
+        let velocity = create_image("TAA Velocity", VELOCITY_FORMAT)?;
+        let history = [
+            create_image("TAA History 0", desc.color_format)?,
+            create_image("TAA History 1", desc.color_format)?,
+        ];
+        let output = create_image("TAA Output", desc.color_format)?;
+
+        let fullscreen_vs = renderer
+            .device
+            .create_shader(FULLSCREEN_VERTEX_SHADER, Default::default())?;
+        let resolve_fs = renderer.device.create_shader(
+            include_bytes!("../shaders/spv/taa_resolve.frag.spv"),
+            Default::default(),
+        )?;
+        let pipeline = renderer.device.create_graphics_pipeline(
+            &fullscreen_vs,
+            Some(&resolve_fs),
+            GraphicsPipelineDescription {
+                name: Some("TAA Resolve Pipeline".to_owned()),
+                color_formats: vec![desc.color_format, desc.color_format],
+                ..Default::default()
+            },
+        )?;
+        fullscreen_vs.destroy(&renderer.device);
+        resolve_fs.destroy(&renderer.device);
+        let sampler = renderer.device.create_sampler(SamplerDescription {
+            name: Some("TAA Sampler"),
+            address_mode: AddressMode::ClampToEdge,
+            ..Default::default()
+        })?;
+
+        let velocity = renderer.resource_manager.insert_image(velocity);
+        let history = history.map(|image| renderer.resource_manager.insert_image(image));
+        let output = renderer.resource_manager.insert_image(output);
+        let taa = Self {
+            enabled: true,
+            blend_factor: 0.1,
+            velocity,
+            history,
+            output,
+            pipeline,
+            sampler,
+            frame_index: 0,
+            history_valid: false,
+        };
+        taa.prepare_history(renderer)?;
+        Ok(taa)
+    }
+
+    // The previous frame's history is read before it was ever written, so both start out in the
+    // layout `resolve` reads them in
+    fn prepare_history(&self, renderer: &Renderer) -> Result<()> {
+        let cmd_list = renderer
+            .command_queue
+            .get_immediate_command_list(&renderer.device)?;
+        for handle in self.history {
+            let image = renderer.resource_manager.images.get(handle).unwrap();
+            cmd_list.transition_image(
+                &renderer.device,
+                image,
+                Layout::Undefined,
+                Layout::ShaderReadOnly,
+            );
+        }
+        cmd_list.end(&renderer.device)?;
+        cmd_list.immediate_submit(&renderer.device, renderer.device.present_queue())?;
+        cmd_list.reset(&renderer.device)?;
+        Ok(())
+    }
+
+    // Pass to `Camera::jittered_projection`, advanced by every `resolve`
+    pub fn frame_index(&self) -> usize {
+        self.frame_index
+    }
+
+    // Written by the scene pass as an extra color attachment, and cleared to zero
+    pub fn velocity(&self) -> ResourceId<Image> {
+        self.velocity
+    }
+
+    // Anti-aliased color, in `Layout::ShaderReadOnly` after `resolve`. The same image every frame,
+    // so it can be bound once, e.g. with `PostProcess::set_input`
+    pub fn output(&self) -> ResourceId<Image> {
+        self.output
+    }
+
+    // Attachment description for `velocity`, clearing it for pixels the scene doesn't cover
+    pub fn velocity_attachment_desc() -> RenderAttachmentDesc {
+        RenderAttachmentDesc::clear(ClearValue::Color {
+            color: [0.0, 0.0, 0.0, 0.0],
+        })
+    }
+
+    // Blends `color`, a pass output drawn with this frame's jitter, into the history and `output`
+    pub fn resolve<'a>(
+        &'a mut self,
+        allocator: &'a Bump,
+        graph: &mut RenderGraph<'a>,
+        color: ResourceId<Image>,
+    ) {
+        let history_valid = self.enabled && self.history_valid;
+        let write_history = self.history[self.frame_index % 2];
+        let read_history = self.history[(self.frame_index + 1) % 2];
+        self.history_valid = self.enabled;
+        self.frame_index += 1;
+
+        let this: &'a Self = self;
+        graph.add_pass(
+            allocator,
+            RenderPass::new(allocator)
+                .with_name("TAA Resolve")
+                .add_color_attachment(AttachmentType::Reference(write_history), Default::default())
+                .add_color_attachment(AttachmentType::Reference(this.output), Default::default())
+                .add_input(RenderPassResource::Image(color))
+                .add_input(RenderPassResource::Image(this.velocity))
+                .add_input(RenderPassResource::Image(read_history))
+                .add_output(RenderPassResource::Image(write_history))
+                .add_output(RenderPassResource::Image(this.output))
+                .set_callback(allocator, move |renderer, cmd_list| {
+                    let bind_info = |handle: ResourceId<Image>| {
+                        let image = renderer.resource_manager.images.get(handle).unwrap();
+                        BindGroupWriteData::SampledImage(image.bind_info(
+                            &this.sampler,
+                            Layout::ShaderReadOnly,
+                            None,
+                        ))
+                    };
+                    // Looked up every frame, as the history images swap
+                    let bind_group = renderer.bind_group_cache.get(
+                        &renderer.device,
+                        this.pipeline.bind_group_data(0).unwrap(),
+                        &[
+                            BindGroupPushInfo {
+                                dst_binding: 0,
+                                data: bind_info(color),
+                            },
+                            BindGroupPushInfo {
+                                dst_binding: 1,
+                                data: bind_info(this.velocity),
+                            },
+                            BindGroupPushInfo {
+                                dst_binding: 2,
+                                data: bind_info(read_history),
+                            },
+                        ],
+                    )?;
+                    cmd_list.bind_graphics_pipeline(&renderer.device, &this.pipeline)?;
+                    cmd_list.bind_descriptor_sets(
+                        &renderer.device,
+                        &this.pipeline,
+                        0,
+                        &[bind_group],
+                    );
+                    cmd_list.set_fragment_bytes(
+                        &renderer.device,
+                        &this.pipeline,
+                        &ResolveConstants {
+                            blend_factor: this.blend_factor,
+                            history_valid: history_valid as u32,
+                        },
+                        0,
+                    )?;
+                    cmd_list.draw_fullscreen_triangle(&renderer.device);
+
+                    Ok(())
+                }),
+        );
+    }
+
+    // Resizes every image to the new surface size, dropping the history
+    pub fn resize(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> Result<()> {
+        for handle in [self.velocity, self.output].into_iter().chain(self.history) {
+            renderer
+                .resource_manager
+                .images
+                .get_mut(handle)
+                .unwrap()
+                .resize(&renderer.device, Size2D::new(width, height))?;
+        }
+        self.prepare_history(renderer)?;
+        self.history_valid = false;
+        Ok(())
+    }
+
+    pub fn destroy(&mut self, renderer: &Renderer) {
+        self.sampler.destroy(&renderer.device);
+        self.pipeline.destroy(&renderer.device);
+    }
+}
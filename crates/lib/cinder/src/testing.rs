@@ -0,0 +1,20 @@
+use crate::{App, Cinder, Renderer};
+
+pub use renderer::testing::*;
+
+// Creates `A` on a headless renderer and returns the pixels of its first frame.
+// Pixels are B8G8R8A8, 4 bytes each, row by row.
+pub fn render_to_image<A: App>(width: u32, height: u32) -> anyhow::Result<Vec<u8>> {
+    let mut cinder = Cinder::<A>::new_headless(width, height)?;
+    cinder.render_headless_frame()
+}
+
+// Like `render_to_image`, but returns `None` when no headless Vulkan device can be created, so
+// tests can skip on machines without one instead of failing
+pub fn try_render_to_image<A: App>(width: u32, height: u32) -> anyhow::Result<Option<Vec<u8>>> {
+    let Ok(renderer) = Renderer::new_headless(width, height, Default::default()) else {
+        return Ok(None);
+    };
+    let mut cinder = Cinder::<A>::with_renderer(renderer)?;
+    cinder.render_headless_frame().map(Some)
+}
@@ -1,10 +1,21 @@
 use egui::Context;
+use renderer::{profiling::FrameStats, resources::memory::HeapBudget};
+use std::collections::VecDeque;
 
 use crate::{EguiIntegration, DEFAULT_PPP};
 
+const FRAME_TIME_HISTORY: usize = 60;
+
 pub struct SharedEguiMenu {
     pixels_per_point: f32,
     should_set_ppp: bool,
+    shader_error: Option<String>,
+    gpu_timings: Vec<(String, f64)>,
+    show_stats: bool,
+    frame_stats: FrameStats,
+    frame_times: VecDeque<f64>,
+    memory_budget: Vec<HeapBudget>,
+    dump_render_graph: bool,
 }
 
 impl Default for SharedEguiMenu {
@@ -12,21 +23,122 @@ impl Default for SharedEguiMenu {
         Self {
             pixels_per_point: DEFAULT_PPP,
             should_set_ppp: false,
+            shader_error: None,
+            gpu_timings: Default::default(),
+            show_stats: false,
+            frame_stats: Default::default(),
+            frame_times: VecDeque::with_capacity(FRAME_TIME_HISTORY),
+            memory_budget: Default::default(),
+            dump_render_graph: false,
         }
     }
 }
 
 impl SharedEguiMenu {
     pub fn draw(&mut self, context: &Context) {
+        if self.show_stats {
+            self.draw_stats(context);
+        }
         egui::Window::new("Shared Menu").show(context, |ui| {
             let ret =
                 ui.add(egui::Slider::new(&mut self.pixels_per_point, 1.0..=4.0).text("UI Scale"));
             if ret.drag_released() {
                 self.should_set_ppp = true;
             }
+            if ui.button("Dump Render Graph").clicked() {
+                self.dump_render_graph = true;
+            }
+            if !self.gpu_timings.is_empty() {
+                ui.separator();
+                ui.label("GPU Timings");
+                for (label, ms) in &self.gpu_timings {
+                    ui.label(format!("{label}: {ms:.3} ms"));
+                }
+            }
+            if let Some(error) = &self.shader_error {
+                ui.separator();
+                ui.colored_label(egui::Color32::RED, error);
+            }
+        });
+    }
+
+    fn draw_stats(&self, context: &Context) {
+        let average_ms = if self.frame_times.is_empty() {
+            0.0
+        } else {
+            self.frame_times.iter().sum::<f64>() / self.frame_times.len() as f64
+        };
+        let fps = if average_ms > 0.0 {
+            1000.0 / average_ms
+        } else {
+            0.0
+        };
+
+        egui::Window::new("Frame Stats").show(context, |ui| {
+            ui.label(format!(
+                "CPU Frame Time: {:.3} ms",
+                self.frame_stats.cpu_frame_time_ms
+            ));
+            ui.label(format!("FPS: {fps:.1}"));
+            ui.label(format!(
+                "Draw Calls: {}",
+                self.frame_stats.draw_stats.draw_calls
+            ));
+            ui.label(format!(
+                "Triangles: {}",
+                self.frame_stats.draw_stats.triangles
+            ));
+            if !self.memory_budget.is_empty() {
+                ui.separator();
+                ui.label("Memory Budget");
+                for heap in &self.memory_budget {
+                    let to_mib = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+                    let kind = if heap.device_local { "VRAM" } else { "System" };
+                    ui.add(
+                        egui::ProgressBar::new(heap.used as f32 / heap.budget.max(1) as f32).text(
+                            format!(
+                                "Heap {} ({kind}): {:.0} / {:.0} MiB",
+                                heap.heap,
+                                to_mib(heap.used),
+                                to_mib(heap.budget)
+                            ),
+                        ),
+                    );
+                }
+            }
         });
     }
 
+    pub fn toggle_stats(&mut self) {
+        self.show_stats = !self.show_stats;
+    }
+
+    pub fn set_frame_stats(&mut self, stats: FrameStats) {
+        if self.frame_times.len() == FRAME_TIME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(stats.cpu_frame_time_ms);
+        self.frame_stats = stats;
+    }
+
+    // Returns true once per click of the "Dump Render Graph" button
+    pub fn take_dump_render_graph(&mut self) -> bool {
+        std::mem::take(&mut self.dump_render_graph)
+    }
+
+    pub fn set_memory_budget(&mut self, budget: Vec<HeapBudget>) {
+        self.memory_budget = budget;
+    }
+
+    pub fn set_gpu_timings(&mut self, timings: &[(String, f64)]) {
+        self.gpu_timings.clear();
+        self.gpu_timings.extend_from_slice(timings);
+    }
+
+    pub fn set_shader_error(&mut self, error: Option<String>) {
+        self.shader_error = error;
+    }
+
     pub fn update(&mut self, integration: &mut EguiIntegration) {
         if self.should_set_ppp {
             integration.set_pixels_per_point(self.pixels_per_point);
@@ -9,7 +9,7 @@ use egui::{
 };
 use math::{point::Point2D, rect::Rect2D, size::Size2D};
 use renderer::{
-    command_queue::{AttachmentLoadOp, CommandList, RenderAttachment, RenderAttachmentDesc},
+    command_queue::{CommandList, RenderAttachment, RenderAttachmentDesc},
     device::Device,
     resources::{
         bind_group::{BindGroup, BindGroupBindInfo, BindGroupWriteData},
@@ -22,7 +22,7 @@ use renderer::{
         sampler::{AddressMode, Sampler, SamplerDescription},
         ResourceManager,
     },
-    swapchain::{Swapchain, SwapchainImage},
+    swapchain::SwapchainImage,
     util::MemoryMappablePointer,
     ResourceId,
 };
@@ -51,11 +51,7 @@ pub struct EguiIntegration {
 }
 
 impl EguiIntegration {
-    pub fn new(
-        resource_manager: &mut ResourceManager,
-        device: &Device,
-        swapchain: &Swapchain,
-    ) -> Result<Self> {
+    pub fn new(resource_manager: &mut ResourceManager, device: &Device) -> Result<Self> {
         let egui_context = egui::Context::default();
         let mut egui_sdl = EguiSdl::new();
         egui_context.set_visuals(egui::Visuals::light());
@@ -74,8 +70,8 @@ impl EguiIntegration {
             &vertex_shader,
             Some(&fragment_shader),
             GraphicsPipelineDescription {
-                blending: ColorBlendState::pma(),
-                color_format: Some(device.surface_data().format()),
+                blending: vec![ColorBlendState::pma()],
+                color_formats: vec![device.surface_data().format()],
                 vertex_desc: Some(VertexDescription {
                     binding_desc: vec![VertexBindingDesc {
                         binding: 0,
@@ -118,7 +114,7 @@ impl EguiIntegration {
             })?);
 
         let (vertex_buffers, index_buffers) = {
-            let len = swapchain.num_images();
+            let len = device.frames_in_flight();
             let mut vertex_buffers = Vec::with_capacity(len);
             let mut index_buffers = Vec::with_capacity(len);
             for _ in 0..len {
@@ -204,7 +200,7 @@ impl EguiIntegration {
             &clipped_primitives,
         )?;
 
-        self.free_textures(textures_delta);
+        self.free_textures(resource_manager, device, textures_delta);
 
         Ok(())
     }
@@ -220,14 +216,15 @@ impl EguiIntegration {
         pixels_per_point: f32,
         clipped_primitives: &[ClippedPrimitive],
     ) -> Result<()> {
-        let present_index = swapchain_image.index();
+        // The GPU may still be reading the buffers of other frames in flight
+        let frame_index = device.current_frame_in_flight();
         let vertex_buffer = resource_manager
             .buffers
-            .get(self.vertex_buffers[present_index as usize])
+            .get(self.vertex_buffers[frame_index])
             .unwrap();
         let index_buffer = resource_manager
             .buffers
-            .get(self.index_buffers[present_index as usize])
+            .get(self.index_buffers[frame_index])
             .unwrap();
         let pipeline = resource_manager
             .graphics_pipelines
@@ -246,14 +243,12 @@ impl EguiIntegration {
             render_area,
             &[RenderAttachment::color(
                 swapchain_image,
-                RenderAttachmentDesc {
-                    load_op: AttachmentLoadOp::Load,
-                    ..Default::default()
-                },
+                RenderAttachmentDesc::load(),
             )],
             None,
-        );
-        command_list.bind_graphics_pipeline(device, pipeline);
+            None,
+        )?;
+        command_list.bind_graphics_pipeline(device, pipeline)?;
         command_list.bind_vertex_buffer(device, vertex_buffer);
         command_list.bind_index_buffer(device, index_buffer);
         command_list.bind_viewport(
@@ -309,7 +304,7 @@ impl EguiIntegration {
                         resource_manager,
                         device,
                         command_list,
-                        present_index,
+                        frame_index,
                         mesh,
                         &mut vertex_buffer_ptr,
                         &mut vertex_base,
@@ -332,7 +327,7 @@ impl EguiIntegration {
         resource_manager: &ResourceManager,
         device: &Device,
         command_list: &CommandList,
-        present_index: u32,
+        frame_index: usize,
         mesh: &Mesh,
         vertex_buffer_ptr: &mut MemoryMappablePointer,
         vertex_base: &mut i32,
@@ -353,11 +348,11 @@ impl EguiIntegration {
 
         let vertex_buffer = resource_manager
             .buffers
-            .get(self.vertex_buffers[present_index as usize])
+            .get(self.vertex_buffers[frame_index])
             .unwrap();
         let index_buffer = resource_manager
             .buffers
-            .get(self.index_buffers[present_index as usize])
+            .get(self.index_buffers[frame_index])
             .unwrap();
         if vertex_buffer_ptr_next >= vertex_buffer.end_ptr().unwrap()
             || index_buffer_ptr_next >= index_buffer.end_ptr().unwrap()
@@ -409,7 +404,7 @@ impl EguiIntegration {
             command_list,
             Default::default(),
         )?;
-        resource_manager.delete_buffer_raw(buffer, device.current_frame_in_flight());
+        resource_manager.delete_buffer_raw(buffer, device.pending_submit_value());
 
         let index = match id {
             TextureId::Managed(index) => *index,
@@ -431,8 +426,11 @@ impl EguiIntegration {
             )),
         }])?;
 
+        // The old image may still be used by frames in flight
         let image_handle = resource_manager.insert_image(image);
-        self.image_map.insert(*id, image_handle);
+        if let Some(old) = self.image_map.insert(*id, image_handle) {
+            resource_manager.delete(old, device.pending_submit_value());
+        }
 
         Ok(())
     }
@@ -482,7 +480,18 @@ impl EguiIntegration {
         Ok(())
     }
 
-    fn free_textures(&mut self, _textures_delta: TexturesDelta) {}
+    fn free_textures(
+        &mut self,
+        resource_manager: &mut ResourceManager,
+        device: &Device,
+        textures_delta: TexturesDelta,
+    ) {
+        for id in &textures_delta.free {
+            if let Some(image) = self.image_map.remove(id) {
+                resource_manager.delete(image, device.pending_submit_value());
+            }
+        }
+    }
 
     pub fn set_pixels_per_point(&mut self, ppp: f32) {
         self.egui_context.set_pixels_per_point(ppp);
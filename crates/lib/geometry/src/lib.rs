@@ -1,112 +1,347 @@
 mod vertex;
-use vertex::Vertex;
+pub use vertex::{PositionVertex, SurfaceVertex, Vertex};
 
-pub struct SurfaceMesh {
-    pub vertices: Vec<Vertex>,
+fn unit_circle(i: usize, n: usize) -> (f32, f32) {
+    let ratio = i as f32 / n as f32;
+    let angle = ratio * std::f32::consts::PI * 2.0;
+    (angle.cos(), angle.sin())
+}
+
+fn normalized(v: [f32; 3]) -> [f32; 3] {
+    let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if len == 0.0 {
+        v
+    } else {
+        [v[0] / len, v[1] / len, v[2] / len]
+    }
+}
+
+pub struct SurfaceMesh<V: SurfaceVertex = Vertex> {
+    pub vertices: Vec<V>,
     pub indices: Vec<u32>,
 }
 
-impl SurfaceMesh {
-    pub fn cylinder<const N: usize>(height: f32, radius: f32) -> Self {
-        let top_offset = N + 1;
-        let mut vertices: Vec<Vertex> = vec![Default::default(); (N + 1) * 2];
-        let mut indices: Vec<u32> = vec![0; N * 6 * 2];
+impl<V: SurfaceVertex> SurfaceMesh<V> {
+    // 16-bit copy of `indices`, for meshes with few enough vertices
+    pub fn indices_u16(&self) -> Option<Vec<u16>> {
+        if self.vertices.len() > u16::MAX as usize + 1 {
+            return None;
+        }
+        Some(self.indices.iter().map(|index| *index as u16).collect())
+    }
 
+    // Pushes a flat disk facing `normal_y`, with planar UVs. Returns the index of the center vertex.
+    fn push_cap<const N: usize>(&mut self, y: f32, radius: f32, normal_y: f32) -> u32 {
+        let center_index = self.vertices.len() as u32;
+        self.vertices
+            .push(V::new([0.0, y, 0.0], [0.0, normal_y, 0.0], [0.5, 0.5]));
         for i in 0..N {
-            let ratio = i as f32 / N as f32;
-            let angle = ratio * std::f32::consts::PI * 2.0;
-            let x = angle.cos() * radius;
-            let z = angle.sin() * radius;
-
-            // Create outer ring vertices for bottom and top plane
-            vertices[i] = Vertex { pos: [x, 0.0, z] };
-            vertices[i + top_offset] = Vertex {
-                pos: [x, height, z],
-            };
-        }
-        // Create center vertices for bottom and top plane
-        vertices[N] = Vertex {
-            pos: [0.0, 0.0, 0.0],
-        };
-        vertices[N + top_offset] = Vertex {
-            pos: [0.0, height, 0.0],
-        };
+            let (c, s) = unit_circle(i, N);
+            self.vertices.push(V::new(
+                [c * radius, y, s * radius],
+                [0.0, normal_y, 0.0],
+                [0.5 + c * 0.5, 0.5 + s * 0.5],
+            ));
+        }
 
-        // Generate indices
-        let bottom_center_index = N as u32;
-        let top_center_index = top_offset as u32 + N as u32;
-        let wall_size = N * 6;
+        let ring_index = |i: usize| center_index + 1 + (i % N) as u32;
         for i in 0..N {
-            let bottom_index = i as u32;
-            let next_bottom_index = (i as u32 + 1) % N as u32;
+            if normal_y < 0.0 {
+                // Bottom Triangle
+                self.indices
+                    .extend_from_slice(&[ring_index(i), ring_index(i + 1), center_index]);
+            } else {
+                // Top Triangle
+                self.indices
+                    .extend_from_slice(&[ring_index(i + 1), ring_index(i), center_index]);
+            }
+        }
 
-            let top_index = top_offset as u32 + i as u32;
-            let next_top_index = top_offset as u32 + (i as u32 + 1) % N as u32;
+        center_index
+    }
+
+    pub fn cylinder<const N: usize>(height: f32, radius: f32) -> Self {
+        let mut mesh = Self {
+            vertices: Vec::with_capacity((N + 1) * 4),
+            indices: Vec::with_capacity(N * 6 * 2),
+        };
 
-            //Bottom triangle
-            indices[i * 3] = bottom_index;
-            indices[i * 3 + 1] = next_bottom_index;
-            indices[i * 3 + 2] = bottom_center_index;
+        mesh.push_cap::<N>(0.0, radius, -1.0);
+        mesh.push_cap::<N>(height, radius, 1.0);
 
-            // Top Triangle
-            indices[N * 3 + i * 3] = next_top_index;
-            indices[N * 3 + i * 3 + 1] = top_index;
-            indices[N * 3 + i * 3 + 2] = top_center_index;
+        // Wall, with a duplicated seam column so the cylindrical UVs wrap cleanly
+        let wall_offset = mesh.vertices.len() as u32;
+        for i in 0..=N {
+            let (c, s) = unit_circle(i, N);
+            let u = i as f32 / N as f32;
+            mesh.vertices
+                .push(V::new([c * radius, 0.0, s * radius], [c, 0.0, s], [u, 1.0]));
+            mesh.vertices.push(V::new(
+                [c * radius, height, s * radius],
+                [c, 0.0, s],
+                [u, 0.0],
+            ));
+        }
+        for i in 0..N as u32 {
+            let bottom_index = wall_offset + i * 2;
+            let top_index = bottom_index + 1;
+            let next_bottom_index = bottom_index + 2;
+            let next_top_index = bottom_index + 3;
 
-            // Wall
             // First Triangle
-            indices[wall_size + i * 6] = bottom_index;
-            indices[wall_size + i * 6 + 1] = top_index;
-            indices[wall_size + i * 6 + 2] = next_bottom_index;
+            mesh.indices
+                .extend_from_slice(&[bottom_index, top_index, next_bottom_index]);
             // Second Triangle
-            indices[wall_size + i * 6 + 3] = next_bottom_index;
-            indices[wall_size + i * 6 + 4] = top_index;
-            indices[wall_size + i * 6 + 5] = next_top_index;
+            mesh.indices
+                .extend_from_slice(&[next_bottom_index, top_index, next_top_index]);
         }
 
-        Self { vertices, indices }
+        mesh
     }
 
     pub fn cone<const N: usize>(height: f32, radius: f32) -> Self {
-        let _top_offset = N + 1;
-        let mut vertices: Vec<Vertex> = vec![Default::default(); N + 2];
-        let mut indices: Vec<u32> = vec![0; N * 6];
-
-        for i in 0..N {
-            let ratio = i as f32 / N as f32;
-            let angle = ratio * std::f32::consts::PI * 2.0;
-            let x = angle.cos() * radius;
-            let z = angle.sin() * radius;
-            // Create outer ring vertices for bottom plane
-            vertices[i] = Vertex { pos: [x, 0.0, z] };
-        }
-        // Create center vertices for bottom plane
-        vertices[N] = Vertex {
-            pos: [0.0, 0.0, 0.0],
-        };
-        // Tip vertex
-        vertices[N + 1] = Vertex {
-            pos: [0.0, height, 0.0],
+        let mut mesh = Self {
+            vertices: Vec::with_capacity(N * 3 + 2),
+            indices: Vec::with_capacity(N * 6),
         };
 
-        // Generate indices
-        let bottom_center_index = N as u32;
-        let top_center_index = (N + 1) as u32;
-        let ws = N * 3;
+        mesh.push_cap::<N>(0.0, radius, -1.0);
+
+        // Wall, with one tip vertex per segment so each face gets its own normal and UV
+        let slant_normal = |c: f32, s: f32| normalized([c * height, radius, s * height]);
+        let ring_offset = mesh.vertices.len() as u32;
+        for i in 0..=N {
+            let (c, s) = unit_circle(i, N);
+            mesh.vertices.push(V::new(
+                [c * radius, 0.0, s * radius],
+                slant_normal(c, s),
+                [i as f32 / N as f32, 1.0],
+            ));
+        }
+        let tip_offset = mesh.vertices.len() as u32;
         for i in 0..N {
-            let bottom_index = i as u32;
-            let next_bottom_index = (i as u32 + 1) % N as u32;
+            let (c, s) = unit_circle(2 * i + 1, 2 * N);
+            mesh.vertices.push(V::new(
+                [0.0, height, 0.0],
+                slant_normal(c, s),
+                [(i as f32 + 0.5) / N as f32, 0.0],
+            ));
+        }
+        for i in 0..N as u32 {
+            mesh.indices
+                .extend_from_slice(&[ring_offset + i, tip_offset + i, ring_offset + i + 1]);
+        }
+
+        mesh
+    }
+
+    // Builds a closed surface of revolution: a top pole, one ring per `(theta, y_offset)` entry
+    // and a bottom pole. Each ring has (SECTORS + 1) vertices, the last column duplicating the
+    // first so the UVs wrap cleanly.
+    fn lat_long<const SECTORS: usize>(
+        radius: f32,
+        rings: &[(f32, f32)],
+        top_pole_y: f32,
+        bottom_pole_y: f32,
+    ) -> Self {
+        debug_assert!(!rings.is_empty(), "lat_long needs at least 1 ring");
+        debug_assert!(SECTORS >= 3, "lat_long needs at least 3 sectors");
+        let ring_count = rings.len();
+        let ring_size = SECTORS + 1;
+        let bottom_pole_index = (1 + ring_count * ring_size) as u32;
+        let mut vertices: Vec<V> = Vec::with_capacity(ring_count * ring_size + 2);
+        let mut indices: Vec<u32> = Vec::with_capacity(SECTORS * 6 * (ring_count + 1));
+
+        vertices.push(V::new([0.0, top_pole_y, 0.0], [0.0, 1.0, 0.0], [0.5, 0.0]));
+        for (ring, (theta, y_offset)) in rings.iter().enumerate() {
+            let v = (ring + 1) as f32 / (ring_count + 1) as f32;
+            let y = theta.cos();
+            let ring_radius = theta.sin();
+            for sector in 0..=SECTORS {
+                let (c, s) = unit_circle(sector, SECTORS);
+                let normal = [c * ring_radius, y, s * ring_radius];
+                vertices.push(V::new(
+                    [
+                        normal[0] * radius,
+                        normal[1] * radius + y_offset,
+                        normal[2] * radius,
+                    ],
+                    normal,
+                    [sector as f32 / SECTORS as f32, v],
+                ));
+            }
+        }
+        vertices.push(V::new(
+            [0.0, bottom_pole_y, 0.0],
+            [0.0, -1.0, 0.0],
+            [0.5, 1.0],
+        ));
+
+        let ring_index = |ring: usize, sector: usize| (1 + ring * ring_size + sector) as u32;
+        for sector in 0..SECTORS {
+            // Top cap
+            indices.push(ring_index(0, sector + 1));
+            indices.push(ring_index(0, sector));
+            indices.push(0);
+
+            // Bottom cap
+            indices.push(ring_index(ring_count - 1, sector));
+            indices.push(ring_index(ring_count - 1, sector + 1));
+            indices.push(bottom_pole_index);
+        }
+        for ring in 0..ring_count - 1 {
+            for sector in 0..SECTORS {
+                let top_index = ring_index(ring, sector);
+                let next_top_index = ring_index(ring, sector + 1);
+                let bottom_index = ring_index(ring + 1, sector);
+                let next_bottom_index = ring_index(ring + 1, sector + 1);
+
+                // First Triangle
+                indices.push(bottom_index);
+                indices.push(top_index);
+                indices.push(next_bottom_index);
+                // Second Triangle
+                indices.push(next_bottom_index);
+                indices.push(top_index);
+                indices.push(next_top_index);
+            }
+        }
+
+        Self { vertices, indices }
+    }
+
+    pub fn uv_sphere<const RINGS: usize, const SECTORS: usize>(radius: f32) -> Self {
+        debug_assert!(RINGS >= 2, "uv_sphere needs at least 2 rings");
+        let rings = (1..RINGS)
+            .map(|ring| (ring as f32 / RINGS as f32 * std::f32::consts::PI, 0.0))
+            .collect::<Vec<_>>();
+        Self::lat_long::<SECTORS>(radius, &rings, radius, -radius)
+    }
+
+    // `height` is the length of the cylindrical body, not counting the hemispheres
+    pub fn capsule<const N: usize>(height: f32, radius: f32) -> Self {
+        let hemisphere_rings = (N / 4).max(1);
+        let half_height = height / 2.0;
+        let quarter_turn = std::f32::consts::FRAC_PI_2;
+
+        let mut rings = Vec::with_capacity(hemisphere_rings * 2);
+        // Top hemisphere, ending at its equator
+        for ring in 1..=hemisphere_rings {
+            let theta = ring as f32 / hemisphere_rings as f32 * quarter_turn;
+            rings.push((theta, half_height));
+        }
+        // Bottom hemisphere, starting at its equator. The body is the band between the two equators.
+        for ring in 0..hemisphere_rings {
+            let theta = quarter_turn + ring as f32 / hemisphere_rings as f32 * quarter_turn;
+            rings.push((theta, -half_height));
+        }
+
+        Self::lat_long::<N>(radius, &rings, half_height + radius, -half_height - radius)
+    }
+
+    pub fn torus<const MAJOR: usize, const MINOR: usize>(
+        major_radius: f32,
+        minor_radius: f32,
+    ) -> Self {
+        debug_assert!(MAJOR >= 3, "torus needs at least 3 major segments");
+        debug_assert!(MINOR >= 3, "torus needs at least 3 minor segments");
+        // (MAJOR + 1) x (MINOR + 1) grid, duplicating the seams so the UVs wrap cleanly
+        let ring_size = MINOR + 1;
+        let mut vertices: Vec<V> = Vec::with_capacity((MAJOR + 1) * ring_size);
+        let mut indices: Vec<u32> = Vec::with_capacity(MAJOR * MINOR * 6);
+
+        for i in 0..=MAJOR {
+            let (major_c, major_s) = unit_circle(i, MAJOR);
+            for j in 0..=MINOR {
+                let (minor_c, minor_s) = unit_circle(j, MINOR);
+                let normal = [minor_c * major_c, minor_s, minor_c * major_s];
+                vertices.push(V::new(
+                    [
+                        major_c * major_radius + normal[0] * minor_radius,
+                        normal[1] * minor_radius,
+                        major_s * major_radius + normal[2] * minor_radius,
+                    ],
+                    normal,
+                    [i as f32 / MAJOR as f32, j as f32 / MINOR as f32],
+                ));
+            }
+        }
+
+        let grid_index = |i: usize, j: usize| (i * ring_size + j) as u32;
+        for i in 0..MAJOR {
+            for j in 0..MINOR {
+                let index = grid_index(i, j);
+                let up_index = grid_index(i, j + 1);
+                let next_index = grid_index(i + 1, j);
+                let next_up_index = grid_index(i + 1, j + 1);
+
+                // First Triangle
+                indices.push(index);
+                indices.push(up_index);
+                indices.push(next_index);
+                // Second Triangle
+                indices.push(next_index);
+                indices.push(up_index);
+                indices.push(next_up_index);
+            }
+        }
+
+        Self { vertices, indices }
+    }
+
+    pub fn plane(width: f32, depth: f32, subdivisions: u32) -> Self {
+        // Number of quads along each side
+        let quads = subdivisions as usize + 1;
+        let row = quads + 1;
+        let mut vertices: Vec<V> = Vec::with_capacity(row * row);
+        let mut indices: Vec<u32> = Vec::with_capacity(quads * quads * 6);
+
+        for z in 0..row {
+            let v = z as f32 / quads as f32;
+            for x in 0..row {
+                let u = x as f32 / quads as f32;
+                vertices.push(V::new(
+                    [(u - 0.5) * width, 0.0, (v - 0.5) * depth],
+                    [0.0, 1.0, 0.0],
+                    [u, v],
+                ));
+            }
+        }
+
+        for z in 0..quads {
+            for x in 0..quads {
+                let index = (z * row + x) as u32;
+                let next_x_index = index + 1;
+                let next_z_index = index + row as u32;
+                let next_xz_index = next_z_index + 1;
+
+                // First Triangle
+                indices.push(index);
+                indices.push(next_z_index);
+                indices.push(next_x_index);
+                // Second Triangle
+                indices.push(next_x_index);
+                indices.push(next_z_index);
+                indices.push(next_xz_index);
+            }
+        }
+
+        Self { vertices, indices }
+    }
 
-            //Bottom triangle
-            indices[i * 3] = bottom_index;
-            indices[i * 3 + 1] = next_bottom_index;
-            indices[i * 3 + 2] = bottom_center_index;
+    // Flat strip along the z axis, facing up. Its indices are a triangle strip, so it must be drawn
+    // with a `TriangleStrip` pipeline
+    pub fn ribbon(width: f32, length: f32, segments: u32) -> Self {
+        let segments = segments.max(1) as usize;
+        let mut vertices: Vec<V> = Vec::with_capacity((segments + 1) * 2);
 
-            // Wall
-            indices[ws + i * 3] = bottom_index;
-            indices[ws + i * 3 + 1] = top_center_index;
-            indices[ws + i * 3 + 2] = next_bottom_index;
+        for i in 0..=segments {
+            let v = i as f32 / segments as f32;
+            let z = (v - 0.5) * length;
+            vertices.push(V::new([-0.5 * width, 0.0, z], [0.0, 1.0, 0.0], [0.0, v]));
+            vertices.push(V::new([0.5 * width, 0.0, z], [0.0, 1.0, 0.0], [1.0, v]));
         }
+        let indices = (0..vertices.len() as u32).collect();
 
         Self { vertices, indices }
     }
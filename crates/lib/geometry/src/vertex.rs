@@ -1,6 +1,31 @@
 // TODO: In the future this will be replaced by the Vertex trait, after I make it better
+pub trait SurfaceVertex: Default + Copy {
+    fn new(pos: [f32; 3], normal: [f32; 3], uv: [f32; 2]) -> Self;
+}
+
 #[repr(C)]
 #[derive(Default, Clone, Copy)]
 pub struct Vertex {
     pub pos: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+impl SurfaceVertex for Vertex {
+    fn new(pos: [f32; 3], normal: [f32; 3], uv: [f32; 2]) -> Self {
+        Self { pos, normal, uv }
+    }
+}
+
+// Position-only layout, for meshes that are never lit or textured
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+pub struct PositionVertex {
+    pub pos: [f32; 3],
+}
+
+impl SurfaceVertex for PositionVertex {
+    fn new(pos: [f32; 3], _normal: [f32; 3], _uv: [f32; 2]) -> Self {
+        Self { pos }
+    }
 }
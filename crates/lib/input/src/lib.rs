@@ -1,6 +1,14 @@
 use math::point::Point2D;
-use sdl2::{event::Event, keyboard::Keycode};
-use std::collections::{HashMap, HashSet};
+use sdl2::{
+    controller::{Axis, Button},
+    event::Event,
+    keyboard::Keycode,
+    mouse::MouseButton,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
 
 #[derive(Clone)]
 pub struct KeyState {
@@ -63,6 +71,7 @@ impl KeyboardState {
 pub struct MouseState {
     position: Point2D<i32>,
     delta: Point2D<i32>,
+    buttons_down: HashSet<MouseButton>,
 }
 
 impl Default for MouseState {
@@ -70,6 +79,7 @@ impl Default for MouseState {
         Self {
             position: Point2D::zero(),
             delta: Point2D::zero(),
+            buttons_down: Default::default(),
         }
     }
 }
@@ -87,17 +97,236 @@ impl MouseState {
         self.delta
     }
 
+    pub fn is_down(&self, button: MouseButton) -> bool {
+        self.buttons_down.contains(&button)
+    }
+
     pub fn reset_delta(&mut self) {
         self.delta = Point2D::new(0, 0);
     }
 
     pub fn on_event(&mut self, event: &Event) {
         match event {
-            Event::MouseMotion { x, y, .. } => {
-                self.delta = Point2D::new(x - self.position.x(), y - self.position.y());
+            // Relative motion keeps being reported at the edges of the window when the mouse is
+            // captured, unlike the position
+            Event::MouseMotion {
+                x, y, xrel, yrel, ..
+            } => {
+                self.delta = Point2D::new(self.delta.x() + xrel, self.delta.y() + yrel);
                 self.position = Point2D::new(*x, *y);
             }
+            Event::MouseButtonDown { mouse_btn, .. } => {
+                self.buttons_down.insert(*mouse_btn);
+            }
+            Event::MouseButtonUp { mouse_btn, .. } => {
+                self.buttons_down.remove(mouse_btn);
+            }
             _ => {}
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(Keycode),
+    Mouse(MouseButton),
+}
+
+impl From<Keycode> for Binding {
+    fn from(keycode: Keycode) -> Self {
+        Self::Key(keycode)
+    }
+}
+
+impl From<MouseButton> for Binding {
+    fn from(button: MouseButton) -> Self {
+        Self::Mouse(button)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InputMap<A> {
+    bindings: HashMap<A, Vec<Binding>>,
+}
+
+impl<A> Default for InputMap<A> {
+    fn default() -> Self {
+        Self {
+            bindings: Default::default(),
+        }
+    }
+}
+
+impl<A> InputMap<A>
+where
+    A: Copy + Eq + Hash,
+{
+    pub fn bind(&mut self, action: A, binding: impl Into<Binding>) {
+        let binding = binding.into();
+        let bindings = self.bindings.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn with_binding(mut self, action: A, binding: impl Into<Binding>) -> Self {
+        self.bind(action, binding);
+        self
+    }
+
+    pub fn unbind(&mut self, action: A, binding: impl Into<Binding>) {
+        let binding = binding.into();
+        if let Some(bindings) = self.bindings.get_mut(&action) {
+            bindings.retain(|b| *b != binding);
+        }
+    }
+
+    pub fn clear(&mut self, action: A) {
+        self.bindings.remove(&action);
+    }
+
+    pub fn bindings(&self, action: A) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], |bindings| bindings)
+    }
+
+    // Only considers keyboard bindings, use `is_active_with_mouse` to also check mouse buttons
+    pub fn is_active(&self, action: A, keyboard_state: &KeyboardState) -> bool {
+        self.bindings(action).iter().any(|binding| match binding {
+            Binding::Key(keycode) => keyboard_state.is_down(*keycode),
+            Binding::Mouse(_) => false,
+        })
+    }
+
+    pub fn is_active_with_mouse(
+        &self,
+        action: A,
+        keyboard_state: &KeyboardState,
+        mouse_state: &MouseState,
+    ) -> bool {
+        self.bindings(action).iter().any(|binding| match binding {
+            Binding::Key(keycode) => keyboard_state.is_down(*keycode),
+            Binding::Mouse(button) => mouse_state.is_down(*button),
+        })
+    }
+}
+
+const GAMEPAD_DEADZONE: f32 = 0.15;
+
+#[derive(Debug, Default, Clone)]
+pub struct ControllerState {
+    axes: HashMap<Axis, f32>,
+    buttons_down: HashSet<Button>,
+}
+
+impl ControllerState {
+    pub fn axis(&self, axis: Axis) -> f32 {
+        let value = self.axes.get(&axis).copied().unwrap_or(0.0);
+        if value.abs() < GAMEPAD_DEADZONE {
+            0.0
+        } else {
+            value
+        }
+    }
+
+    pub fn is_down(&self, button: Button) -> bool {
+        self.buttons_down.contains(&button)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct GamepadState {
+    controllers: HashMap<u32, ControllerState>,
+}
+
+impl GamepadState {
+    pub fn is_connected(&self) -> bool {
+        !self.controllers.is_empty()
+    }
+
+    pub fn controller(&self, instance_id: u32) -> Option<&ControllerState> {
+        self.controllers.get(&instance_id)
+    }
+
+    // Returns the value with the largest magnitude across all connected controllers
+    pub fn axis(&self, axis: Axis) -> f32 {
+        self.controllers
+            .values()
+            .map(|controller| controller.axis(axis))
+            .fold(
+                0.0,
+                |acc, value| {
+                    if value.abs() > acc.abs() {
+                        value
+                    } else {
+                        acc
+                    }
+                },
+            )
+    }
+
+    pub fn left_stick(&self) -> Point2D<f32> {
+        Point2D::new(self.axis(Axis::LeftX), self.axis(Axis::LeftY))
+    }
+
+    pub fn right_stick(&self) -> Point2D<f32> {
+        Point2D::new(self.axis(Axis::RightX), self.axis(Axis::RightY))
+    }
+
+    pub fn is_down(&self, button: Button) -> bool {
+        self.controllers
+            .values()
+            .any(|controller| controller.is_down(button))
+    }
+
+    pub fn on_event(&mut self, event: &Event) {
+        match event {
+            Event::ControllerDeviceRemoved { which, .. } => {
+                self.controllers.remove(which);
+            }
+            Event::ControllerAxisMotion {
+                which, axis, value, ..
+            } => {
+                let normalized = (*value as f32 / i16::MAX as f32).clamp(-1.0, 1.0);
+                self.controllers
+                    .entry(*which)
+                    .or_default()
+                    .axes
+                    .insert(*axis, normalized);
+            }
+            Event::ControllerButtonDown { which, button, .. } => {
+                self.controllers
+                    .entry(*which)
+                    .or_default()
+                    .buttons_down
+                    .insert(*button);
+            }
+            Event::ControllerButtonUp { which, button, .. } => {
+                if let Some(controller) = self.controllers.get_mut(which) {
+                    controller.buttons_down.remove(button);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Input from every device, kept up to date by whoever owns the event loop
+#[derive(Default)]
+pub struct InputState {
+    pub keyboard: KeyboardState,
+    pub mouse: MouseState,
+    pub gamepad: GamepadState,
+}
+
+impl InputState {
+    // Must be called before the frame's events are processed
+    pub fn on_frame_start(&mut self) {
+        self.mouse.reset_delta();
+    }
+
+    pub fn on_event(&mut self, event: &Event) {
+        self.keyboard.on_event(event);
+        self.mouse.on_event(event);
+        self.gamepad.on_event(event);
+    }
+}
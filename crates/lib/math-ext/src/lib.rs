@@ -0,0 +1,6 @@
+// Helpers over the types of the external `math` crate, until they are available there
+mod mat4;
+mod quat;
+
+pub use mat4::{Mat3Padded, Mat4Ext};
+pub use quat::Quat;
@@ -0,0 +1,207 @@
+use math::{mat::Mat4, vec::Vec3};
+
+use crate::Quat;
+
+// `Mat4` is uploaded to shaders as-is, so its memory is a column-major `mat4`. Checked here once,
+// instead of at every cast
+const _: () = assert!(std::mem::size_of::<Mat4>() == std::mem::size_of::<[f32; 16]>());
+const _: () = assert!(std::mem::align_of::<Mat4>() >= std::mem::align_of::<[f32; 16]>());
+
+// Columns of a 3x3 matrix padded to 4 floats, laid out like a GLSL `mat3` or `vec4[3]` in a uniform
+// buffer or push constant block
+pub type Mat3Padded = [[f32; 4]; 3];
+
+pub trait Mat4Ext: Sized {
+    // Builds a matrix from 16 column-major values, e.g. read from an archive or a GPU buffer
+    fn from_cols_array(cols: &[f32; 16]) -> Self;
+    // Column-major values of the matrix, as uploaded to shaders
+    fn to_cols_array(&self) -> [f32; 16];
+    // `None` for singular matrices
+    fn inverse(&self) -> Option<Self>;
+    // Inverse-transpose of the upper-left 3x3, which keeps normals perpendicular to surfaces under
+    // non-uniform scale. Computed once per draw instead of once per vertex in shaders
+    fn normal_matrix(&self) -> Mat3Padded;
+    // Splits an affine transform into `(translation, rotation, scale)`, applied as scale, then
+    // rotation, then translation. Shear is not representable and is lost
+    fn decompose(&self) -> (Vec3, Quat, Vec3);
+}
+
+impl Mat4Ext for Mat4 {
+    #[rustfmt::skip]
+    fn from_cols_array(cols: &[f32; 16]) -> Self {
+        let c = cols;
+        // `from_data` takes its values row by row
+        Mat4::from_data(
+            c[0], c[4], c[8],  c[12],
+            c[1], c[5], c[9],  c[13],
+            c[2], c[6], c[10], c[14],
+            c[3], c[7], c[11], c[15],
+        )
+    }
+
+    fn to_cols_array(&self) -> [f32; 16] {
+        // Same size and at least the same alignment, asserted above
+        unsafe { *(self as *const Mat4 as *const [f32; 16]) }
+    }
+
+    fn inverse(&self) -> Option<Self> {
+        invert(&self.to_cols_array()).map(|cols| Self::from_cols_array(&cols))
+    }
+
+    fn normal_matrix(&self) -> Mat3Padded {
+        let m = self.to_cols_array();
+        let column = |col: usize| [m[col * 4], m[col * 4 + 1], m[col * 4 + 2]];
+        let (c0, c1, c2) = (column(0), column(1), column(2));
+        // The inverse-transpose is the cofactor matrix over the determinant, and the columns of
+        // the cofactor matrix are cross products of the other two columns
+        let cofactors = [cross(c1, c2), cross(c2, c0), cross(c0, c1)];
+        let determinant = dot(c0, cofactors[0]);
+        // Degenerate scales have no inverse, the cofactors still give usable directions
+        let scale = if determinant == 0.0 {
+            1.0
+        } else {
+            1.0 / determinant
+        };
+        cofactors.map(|c| [c[0] * scale, c[1] * scale, c[2] * scale, 0.0])
+    }
+
+    fn decompose(&self) -> (Vec3, Quat, Vec3) {
+        let m = self.to_cols_array();
+        let column = |col: usize| [m[col * 4], m[col * 4 + 1], m[col * 4 + 2]];
+        let (c0, c1, c2) = (column(0), column(1), column(2));
+        let length = |c: [f32; 3]| dot(c, c).sqrt();
+        let mut scale = [length(c0), length(c1), length(c2)];
+        // A mirrored transform can't be a rotation, so the flip is moved into the scale
+        if dot(c0, cross(c1, c2)) < 0.0 {
+            scale[0] = -scale[0];
+        }
+
+        let unscaled = |c: [f32; 3], s: f32| {
+            let s = if s == 0.0 { 1.0 } else { s };
+            c.map(|v| v / s)
+        };
+        let rotation = Quat::from_rotation_cols(
+            unscaled(c0, scale[0]),
+            unscaled(c1, scale[1]),
+            unscaled(c2, scale[2]),
+        );
+        (
+            Vec3::new(m[12], m[13], m[14]),
+            rotation,
+            Vec3::new(scale[0], scale[1], scale[2]),
+        )
+    }
+}
+
+pub(crate) fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+// Inverse of a column-major 4x4 matrix, through its adjugate
+fn invert(m: &[f32; 16]) -> Option<[f32; 16]> {
+    let at = |col: usize, row: usize| m[col * 4 + row];
+    // Determinant of the 3x3 matrix left after removing `col` and `row`
+    let minor = |col: usize, row: usize| {
+        let m3 = |c: usize, r: usize| {
+            at(
+                if c < col { c } else { c + 1 },
+                if r < row { r } else { r + 1 },
+            )
+        };
+        m3(0, 0) * (m3(1, 1) * m3(2, 2) - m3(2, 1) * m3(1, 2))
+            - m3(1, 0) * (m3(0, 1) * m3(2, 2) - m3(2, 1) * m3(0, 2))
+            + m3(2, 0) * (m3(0, 1) * m3(1, 2) - m3(1, 1) * m3(0, 2))
+    };
+    let cofactor = |col: usize, row: usize| {
+        let sign = if (col + row).is_multiple_of(2) { 1.0 } else { -1.0 };
+        sign * minor(col, row)
+    };
+
+    let determinant = (0..4).map(|col| at(col, 0) * cofactor(col, 0)).sum::<f32>();
+    if determinant == 0.0 {
+        return None;
+    }
+    // The inverse is the transposed cofactor matrix over the determinant
+    Some(std::array::from_fn(|i| {
+        let (col, row) = (i / 4, i % 4);
+        cofactor(row, col) / determinant
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mat4, Mat4Ext};
+    use crate::Quat;
+    use math::vec::Vec3;
+
+    #[rustfmt::skip]
+    const SCALED_TRANSLATION: [f32; 16] = [
+        2.0, 0.0, 0.0, 0.0,
+        0.0, 4.0, 0.0, 0.0,
+        0.0, 0.0, 0.5, 0.0,
+        1.0, 2.0, 3.0, 1.0,
+    ];
+
+    fn assert_close(a: &[f32], b: &[f32]) {
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).abs() < 1e-5, "{a:?} != {b:?}");
+        }
+    }
+
+    #[test]
+    fn cols_array_round_trips() {
+        let m = Mat4::from_cols_array(&SCALED_TRANSLATION);
+        assert_eq!(m.to_cols_array(), SCALED_TRANSLATION);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn inverse_undoes_scale_and_translation() {
+        let inverse = Mat4::from_cols_array(&SCALED_TRANSLATION).inverse().unwrap();
+        assert_close(
+            &inverse.to_cols_array(),
+            &[
+                0.5,  0.0,  0.0,  0.0,
+                0.0,  0.25, 0.0,  0.0,
+                0.0,  0.0,  2.0,  0.0,
+                -0.5, -0.5, -6.0, 1.0,
+            ],
+        );
+        assert!(Mat4::from_cols_array(&[0.0; 16]).inverse().is_none());
+    }
+
+    #[test]
+    fn normal_matrix_inverts_scale() {
+        let normal = Mat4::from_cols_array(&SCALED_TRANSLATION).normal_matrix();
+        assert_close(&normal[0], &[0.5, 0.0, 0.0, 0.0]);
+        assert_close(&normal[1], &[0.0, 0.25, 0.0, 0.0]);
+        assert_close(&normal[2], &[0.0, 0.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn decompose_recovers_translation_rotation_and_scale() {
+        let rotation = Quat::from_axis_angle(Vec3::new(1.0, 1.0, 0.0), 0.7);
+        let r = rotation.to_mat4().to_cols_array();
+        let scale = [2.0, 0.5, 3.0];
+        let mut cols = [0.0; 16];
+        for col in 0..3 {
+            for row in 0..3 {
+                cols[col * 4 + row] = r[col * 4 + row] * scale[col];
+            }
+        }
+        cols[12..16].copy_from_slice(&[1.0, -2.0, 3.0, 1.0]);
+
+        let (t, q, s) = Mat4::from_cols_array(&cols).decompose();
+        assert_close(&[t.x(), t.y(), t.z()], &[1.0, -2.0, 3.0]);
+        assert_close(&[s.x(), s.y(), s.z()], &scale);
+        assert!((q.dot(&rotation).abs() - 1.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,232 @@
+use std::ops::{Mul, Neg};
+
+use math::{mat::Mat4, vec::Vec3};
+
+use crate::mat4::cross;
+
+// Below this angle between two orientations `slerp` falls back to a normalized lerp, since
+// `sin(theta)` gets too close to zero to divide by
+const SLERP_LINEAR_THRESHOLD: f32 = 0.9995;
+
+// Unit quaternion representing a rotation, following the right-hand rule. Separate from the
+// external `math` crate's quaternion, which has no interpolation and isn't used in this repo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Quat {
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Self { x, y, z, w }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    // Rotation of `angle` radians around `axis`, which does not need to be normalized
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self {
+        let axis = axis.normalized();
+        let (sin, cos) = (angle * 0.5).sin_cos();
+        Self::new(axis.x() * sin, axis.y() * sin, axis.z() * sin, cos)
+    }
+
+    // Rotation with the given columns of a 3x3 rotation matrix
+    pub(crate) fn from_rotation_cols(c0: [f32; 3], c1: [f32; 3], c2: [f32; 3]) -> Self {
+        // Picks the largest component to divide by, so the result stays stable near 180 degrees
+        let trace = c0[0] + c1[1] + c2[2];
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Self::new(
+                (c1[2] - c2[1]) / s,
+                (c2[0] - c0[2]) / s,
+                (c0[1] - c1[0]) / s,
+                0.25 * s,
+            )
+        } else if c0[0] > c1[1] && c0[0] > c2[2] {
+            let s = (1.0 + c0[0] - c1[1] - c2[2]).sqrt() * 2.0;
+            Self::new(
+                0.25 * s,
+                (c1[0] + c0[1]) / s,
+                (c2[0] + c0[2]) / s,
+                (c1[2] - c2[1]) / s,
+            )
+        } else if c1[1] > c2[2] {
+            let s = (1.0 + c1[1] - c0[0] - c2[2]).sqrt() * 2.0;
+            Self::new(
+                (c1[0] + c0[1]) / s,
+                0.25 * s,
+                (c2[1] + c1[2]) / s,
+                (c2[0] - c0[2]) / s,
+            )
+        } else {
+            let s = (1.0 + c2[2] - c0[0] - c1[1]).sqrt() * 2.0;
+            Self::new(
+                (c2[0] + c0[2]) / s,
+                (c2[1] + c1[2]) / s,
+                0.25 * s,
+                (c0[1] - c1[0]) / s,
+            )
+        };
+        q.normalized()
+    }
+
+    pub fn dot(&self, other: &Self) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn normalized(&self) -> Self {
+        let length = self.dot(self).sqrt();
+        Self::new(
+            self.x / length,
+            self.y / length,
+            self.z / length,
+            self.w / length,
+        )
+    }
+
+    // Spherical interpolation from `a` at `t = 0.0` to `b` at `t = 1.0`, at a constant angular
+    // speed and always through the shortest arc
+    pub fn slerp(a: Self, b: Self, t: f32) -> Self {
+        // `b` and `-b` are the same rotation, the one closest to `a` takes the short way around
+        let (b, cos_theta) = {
+            let cos_theta = a.dot(&b);
+            if cos_theta < 0.0 {
+                (-b, -cos_theta)
+            } else {
+                (b, cos_theta)
+            }
+        };
+
+        let (weight_a, weight_b) = if cos_theta > SLERP_LINEAR_THRESHOLD {
+            (1.0 - t, t)
+        } else {
+            let theta = cos_theta.acos();
+            let sin_theta = theta.sin();
+            (
+                ((1.0 - t) * theta).sin() / sin_theta,
+                (t * theta).sin() / sin_theta,
+            )
+        };
+        Self::new(
+            a.x * weight_a + b.x * weight_b,
+            a.y * weight_a + b.y * weight_b,
+            a.z * weight_a + b.z * weight_b,
+            a.w * weight_a + b.w * weight_b,
+        )
+        .normalized()
+    }
+
+    pub fn rotate(&self, v: Vec3) -> Vec3 {
+        let q = [self.x, self.y, self.z];
+        let v = [v.x(), v.y(), v.z()];
+        // v + 2w(q x v) + 2q x (q x v)
+        let t = cross(q, v).map(|c| c * 2.0);
+        let u = cross(q, t);
+        Vec3::new(
+            v[0] + self.w * t[0] + u[0],
+            v[1] + self.w * t[1] + u[1],
+            v[2] + self.w * t[2] + u[2],
+        )
+    }
+
+    #[rustfmt::skip]
+    pub fn to_mat4(&self) -> Mat4 {
+        let Self { x, y, z, w } = *self;
+        let (xx, yy, zz) = (x * x, y * y, z * z);
+        let (xy, xz, yz) = (x * y, x * z, y * z);
+        let (wx, wy, wz) = (w * x, w * y, w * z);
+        // `from_data` takes its values row by row
+        Mat4::from_data(
+            1.0 - 2.0 * (yy + zz), 2.0 * (xy - wz),       2.0 * (xz + wy),       0.0,
+            2.0 * (xy + wz),       1.0 - 2.0 * (xx + zz), 2.0 * (yz - wx),       0.0,
+            2.0 * (xz - wy),       2.0 * (yz + wx),       1.0 - 2.0 * (xx + yy), 0.0,
+            0.0,                   0.0,                   0.0,                   1.0,
+        )
+    }
+}
+
+impl Neg for Quat {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z, -self.w)
+    }
+}
+
+// Rotation by `rhs` followed by `self`
+impl Mul for Quat {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Quat;
+    use crate::Mat4Ext;
+    use math::vec::Vec3;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_close(a: &[f32], b: &[f32]) {
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).abs() < 1e-5, "{a:?} != {b:?}");
+        }
+    }
+
+    fn components(v: Vec3) -> [f32; 3] {
+        [v.x(), v.y(), v.z()]
+    }
+
+    #[test]
+    fn axis_angle_follows_right_hand_rule() {
+        let q = Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), FRAC_PI_2);
+        assert_close(
+            &components(q.rotate(Vec3::new(1.0, 0.0, 0.0))),
+            &[0.0, 0.0, -1.0],
+        );
+        let m = q.to_mat4().to_cols_array();
+        assert_close(&m[0..3], &[0.0, 0.0, -1.0]);
+        assert_close(&m[8..11], &[1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn slerp_moves_at_constant_speed() {
+        let axis = Vec3::new(0.0, 0.0, 1.0);
+        let a = Quat::identity();
+        let b = Quat::from_axis_angle(axis, FRAC_PI_2);
+        for t in [0.0, 0.25, 0.5, 1.0] {
+            let expected = Quat::from_axis_angle(axis, FRAC_PI_2 * t);
+            let q = Quat::slerp(a, b, t);
+            assert_close(
+                &[q.x, q.y, q.z, q.w],
+                &[expected.x, expected.y, expected.z, expected.w],
+            );
+        }
+    }
+
+    #[test]
+    fn slerp_takes_shortest_arc() {
+        let a = Quat::identity();
+        let b = -Quat::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), FRAC_PI_2);
+        let q = Quat::slerp(a, b, 0.5);
+        let expected = Quat::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), FRAC_PI_2 * 0.5);
+        assert!((q.dot(&expected).abs() - 1.0).abs() < 1e-5);
+    }
+}
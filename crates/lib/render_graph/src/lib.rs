@@ -1,16 +1,24 @@
 #![feature(allocator_api)]
 
-use anyhow::{Ok, Result};
+use anyhow::Result;
 use bumpalo::{collections::Vec as BumpVec, Bump};
 use hashbrown::{hash_map::DefaultHashBuilder, HashMap, HashSet};
-use math::rect::Rect2D;
+use math::{rect::Rect2D, size::Size2D};
 use renderer::{
-    command_queue::{CommandList, RenderAttachment, RenderAttachmentDesc},
-    resources::image::Image,
-    swapchain::SwapchainImage,
+    command_queue::{
+        Access, AttachmentStoreOp, ClearValue, CommandList, ImageBarrier, PipelineStage,
+        RenderAttachment, RenderAttachmentDesc, RenderingError,
+    },
+    device::Semaphore,
+    resources::{
+        image::{Format, Image, ImageDescription, ImageUsage, ImageViewId, Layout},
+        transient::{TransientId, TransientImageRequest},
+    },
+    swapchain::{SwapchainError, SwapchainImage, SwapchainStatus},
     Renderer,
 };
 use resource_manager::ResourceId;
+use thiserror::Error;
 
 type BumpHashSet<'a, T> = HashSet<T, DefaultHashBuilder, &'a Bump>;
 type BumpHashMap<'a, K, V> = HashMap<K, V, DefaultHashBuilder, &'a Bump>;
@@ -18,6 +26,40 @@ type BumpBox<'a, T> = Box<T, &'a Bump>;
 
 static DEBUG_LABELS: bool = false;
 
+#[derive(Debug, Error)]
+pub enum RenderGraphError {
+    #[error("Render graph has a cycle between its passes")]
+    CycleDetected,
+    #[error("Could not find attachment image {0:?}")]
+    MissingImage(ResourceId<Image>),
+    #[error("Transient image {0:?} was not created by this render graph")]
+    MissingTransient(TransientId),
+    #[error("Swapchain Image not yet supported for depth attachment")]
+    UnsupportedSwapchainDepth,
+    #[error("Image views are not yet supported for depth attachments")]
+    UnsupportedDepthView,
+    #[error(
+        "Image {image:?} with format {format:?} can't be used as a {} attachment",
+        if *expected_depth { "depth" } else { "color" }
+    )]
+    FormatMismatch {
+        image: RenderPassResource,
+        format: Format,
+        expected_depth: bool,
+    },
+    // The frame was abandoned, recreate the swapchain and try again
+    #[error("Swapchain is out of date and must be recreated")]
+    SwapchainOutOfDate,
+    #[error(transparent)]
+    Rendering(#[from] RenderingError),
+    // Returned by a pass callback
+    #[error(transparent)]
+    Pass(anyhow::Error),
+    // Failure of the device while preparing or recording the frame
+    #[error(transparent)]
+    Device(anyhow::Error),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct RenderPassId(usize);
 
@@ -40,23 +82,36 @@ impl<'a> RenderGraphNode<'a> {
 pub enum RenderPassResource {
     SwapchainImage,
     Image(ResourceId<Image>),
+    Transient(TransientId),
 }
 
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub enum AttachmentType {
     SwapchainImage,
     Reference(ResourceId<Image>),
+    // A single layer of an array image
+    ReferenceLayer(ResourceId<Image>, u32),
+    // A view returned by `Image::create_view`, e.g. in another format. Color attachments only
+    ReferenceView(ResourceId<Image>, ImageViewId),
+    // Created with `RenderGraph::create_transient_image`, its contents are discarded on first use
+    Transient(TransientId),
 }
 
 type RenderPassCallback<'a> = dyn Fn(&Renderer, &CommandList) -> Result<()> + 'a;
 
 pub struct RenderPass<'a> {
-    color_attachments: BumpHashMap<'a, AttachmentType, RenderAttachmentDesc>,
+    // Bound in the order they are added, matching `GraphicsPipelineDescription::color_formats`
+    color_attachments: BumpVec<'a, (AttachmentType, RenderAttachmentDesc)>,
     depth_attachment: Option<(AttachmentType, RenderAttachmentDesc)>,
+    // Format of a depth attachment created by the graph, see `with_managed_depth`
+    managed_depth: Option<Format>,
     inputs: BumpVec<'a, RenderPassResource>,
     outputs: BumpVec<'a, RenderPassResource>,
     render_area: Option<Rect2D<i32, u32>>,
+    viewport: Option<Rect2D<i32, u32>>,
+    scissor: Option<Rect2D<i32, u32>>,
     flipped_viewport: bool,
+    enabled: bool,
     callback: BumpBox<'a, RenderPassCallback<'a>>,
     name: Option<&'a str>,
 }
@@ -66,10 +121,14 @@ impl<'a> std::fmt::Debug for RenderPass<'a> {
         f.debug_struct("RenderPass")
             .field("color_attachments", &self.color_attachments)
             .field("depth_attachment", &self.depth_attachment)
+            .field("managed_depth", &self.managed_depth)
             .field("inputs", &self.inputs)
             .field("outputs", &self.outputs)
             .field("render_area", &self.render_area)
+            .field("viewport", &self.viewport)
+            .field("scissor", &self.scissor)
             .field("flipped_viewport", &self.flipped_viewport)
+            .field("enabled", &self.enabled)
             .field("name", &self.name)
             .finish()
     }
@@ -78,12 +137,16 @@ impl<'a> std::fmt::Debug for RenderPass<'a> {
 impl<'a> RenderPass<'a> {
     pub fn new(bump: &'a Bump) -> Self {
         Self {
-            color_attachments: BumpHashMap::new_in(bump),
+            color_attachments: BumpVec::new_in(bump),
             depth_attachment: Default::default(),
+            managed_depth: None,
             inputs: BumpVec::new_in(bump),
             outputs: BumpVec::new_in(bump),
             render_area: None,
+            viewport: None,
+            scissor: None,
             flipped_viewport: true,
+            enabled: true,
             callback: Box::new_in(|_, _| Ok(()), bump),
             name: None,
         }
@@ -94,7 +157,7 @@ impl<'a> RenderPass<'a> {
         attachment: impl Into<AttachmentType>,
         desc: RenderAttachmentDesc,
     ) -> Self {
-        self.color_attachments.insert(attachment.into(), desc);
+        self.color_attachments.push((attachment.into(), desc));
         self
     }
 
@@ -104,6 +167,21 @@ impl<'a> RenderPass<'a> {
         desc: RenderAttachmentDesc,
     ) -> Self {
         self.depth_attachment = Some((attachment.into(), desc));
+        self.managed_depth = None;
+        self
+    }
+
+    // Depth attachment that the graph creates every frame at the surface's size, cleared on load
+    // and discarded after the pass. Images are cached by size, so resizing the window needs no
+    // handling from the app
+    pub fn with_managed_depth(mut self, format: Format) -> Self {
+        self.depth_attachment = None;
+        self.managed_depth = Some(format);
+        self
+    }
+
+    pub fn with_name(mut self, name: &'a str) -> Self {
+        self.name = Some(name);
         self
     }
 
@@ -112,11 +190,29 @@ impl<'a> RenderPass<'a> {
         self
     }
 
+    // Also the default render area, so load ops only clear the viewport's region of the attachments
+    pub fn with_viewport(mut self, viewport: Rect2D<i32, u32>) -> Self {
+        self.viewport = Some(viewport);
+        self
+    }
+
+    // Defaults to the viewport
+    pub fn with_scissor(mut self, scissor: Rect2D<i32, u32>) -> Self {
+        self.scissor = Some(scissor);
+        self
+    }
+
     pub fn with_flipped_viewport(mut self, flipped: bool) -> Self {
         self.flipped_viewport = flipped;
         self
     }
 
+    // Disabled passes stay in the graph but are neither recorded nor used to order other passes
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
     pub fn add_input(mut self, input: RenderPassResource) -> Self {
         self.inputs.push(input);
         self
@@ -144,10 +240,24 @@ pub struct PresentContext {
 }
 
 impl PresentContext {
-    pub fn present(self, cinder: &mut Renderer) -> Result<bool> {
-        let ret = cinder
-            .swapchain
-            .present(&cinder.device, self.cmd_list, self.swapchain_image);
+    pub fn present(self, cinder: &mut Renderer) -> Result<SwapchainStatus> {
+        self.present_with_semaphores(cinder, &[], &[])
+    }
+
+    // See `Swapchain::present_with_semaphores`
+    pub fn present_with_semaphores(
+        self,
+        cinder: &mut Renderer,
+        wait: &[Semaphore],
+        signal: &[Semaphore],
+    ) -> Result<SwapchainStatus> {
+        let ret = cinder.swapchain.present_with_semaphores(
+            &cinder.device,
+            self.cmd_list,
+            self.swapchain_image,
+            wait,
+            signal,
+        );
         if DEBUG_LABELS {
             cinder.device.end_queue_label();
         }
@@ -158,6 +268,7 @@ impl PresentContext {
 #[derive(Debug)]
 pub struct RenderGraph<'a> {
     passes: BumpVec<'a, RenderPass<'a>>,
+    transients: BumpVec<'a, (Size2D<u32>, ImageDescription)>,
     // Instead of a set, could maybe be a vector of bool
     input_map: BumpHashMap<'a, RenderPassResource, BumpHashSet<'a, RenderPassId>>,
     output_map: BumpHashMap<'a, RenderPassResource, BumpHashSet<'a, RenderPassId>>,
@@ -167,13 +278,30 @@ impl<'a> RenderGraph<'a> {
     pub fn new(bump: &'a Bump) -> Self {
         Self {
             passes: BumpVec::new_in(bump),
+            transients: BumpVec::new_in(bump),
             input_map: BumpHashMap::new_in(bump),
             output_map: BumpHashMap::new_in(bump),
         }
     }
 
+    // The image only exists for this frame, fetch it in pass callbacks with
+    // `renderer.transient_images.get(id)`. Transients used by passes that never run at the same time
+    // share memory, with lifetimes taken from the attachments, inputs and outputs of each pass
+    pub fn create_transient_image(
+        &mut self,
+        size: Size2D<u32>,
+        desc: ImageDescription,
+    ) -> TransientId {
+        self.transients.push((size, desc));
+        TransientId(self.transients.len() - 1)
+    }
+
     pub fn add_pass(&mut self, bump: &'a Bump, pass: RenderPass<'a>) {
         let id = RenderPassId(self.passes.len());
+        if !pass.enabled {
+            self.passes.push(pass);
+            return;
+        }
         for input in &pass.inputs {
             self.input_map
                 .entry(*input)
@@ -189,16 +317,67 @@ impl<'a> RenderGraph<'a> {
         self.passes.push(pass)
     }
 
+    // Graphviz digraph of the passes and the resources linking them, render with `dot -Tsvg`
+    pub fn to_dot(&self) -> String {
+        use std::fmt::Write;
+
+        let mut dot = String::from("digraph RenderGraph {\n    rankdir=LR;\n");
+        for (idx, pass) in self.passes.iter().enumerate() {
+            let style = if pass.enabled { "solid" } else { "dashed" };
+            let _ = writeln!(
+                dot,
+                "    pass_{idx} [shape=box, style={style}, label=\"{}\"];",
+                pass.name
+                    .unwrap_or(&format!("Pass #{idx}"))
+                    .replace('"', "\\\"")
+            );
+        }
+
+        // Maps are iterated in sorted order so the output is stable between frames
+        let mut resources = self
+            .input_map
+            .keys()
+            .chain(self.output_map.keys())
+            .copied()
+            .collect::<Vec<_>>();
+        resources.sort();
+        resources.dedup();
+        for (resource_idx, resource) in resources.iter().enumerate() {
+            let _ = writeln!(
+                dot,
+                "    resource_{resource_idx} [shape=ellipse, label=\"{resource:?}\"];"
+            );
+            if let Some(writers) = self.output_map.get(resource) {
+                let mut writers = writers.iter().collect::<Vec<_>>();
+                writers.sort();
+                for pass_id in writers {
+                    let _ = writeln!(dot, "    pass_{} -> resource_{resource_idx};", pass_id.0);
+                }
+            }
+            if let Some(readers) = self.input_map.get(resource) {
+                let mut readers = readers.iter().collect::<Vec<_>>();
+                readers.sort();
+                for pass_id in readers {
+                    let _ = writeln!(dot, "    resource_{resource_idx} -> pass_{};", pass_id.0);
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    // A pass that reads and writes the same resource gets no edge to itself
     fn compile_nodes<'b>(&self, bump: &'b Bump) -> BumpVec<RenderGraphNode<'b>> {
         let mut nodes = BumpVec::with_capacity_in(self.passes.len(), bump);
-        for (_idx, pass) in self.passes.iter().enumerate() {
+        for (idx, pass) in self.passes.iter().enumerate() {
+            let id = RenderPassId(idx);
             let mut node = RenderGraphNode::new(bump);
 
             // If an input of this node is used as an output by another node, then
             // that node must have an edge pointing to this node.
             for input in &pass.inputs {
                 if let Some(uses_as_output) = self.output_map.get(input) {
-                    for input_pass in uses_as_output {
+                    for input_pass in uses_as_output.iter().filter(|pass| **pass != id) {
                         node.input_nodes.push(*input_pass);
                     }
                 }
@@ -208,22 +387,31 @@ impl<'a> RenderGraph<'a> {
             // this node must have an edge pointing to that node.
             for output in &pass.outputs {
                 if let Some(uses_as_input) = self.input_map.get(output) {
-                    for output_pass in uses_as_input {
+                    for output_pass in uses_as_input.iter().filter(|pass| **pass != id) {
                         node.output_nodes.push(*output_pass);
                     }
                 }
             }
 
+            // The maps' hash sets iterate in a randomly seeded order, which would otherwise decide
+            // the order of independent passes differently on every run
+            node.input_nodes.sort();
+            node.input_nodes.dedup();
+            node.output_nodes.sort();
+            node.output_nodes.dedup();
+
             nodes.push(node);
         }
 
         nodes
     }
 
+    // Nodes with a visit count of 1 are still being expanded, reaching one again means there is a
+    // cycle
     fn sorted_nodes<'b>(
         bump: &'a Bump,
         nodes: &BumpVec<'b, RenderGraphNode>,
-    ) -> BumpVec<'a, RenderPassId> {
+    ) -> Result<BumpVec<'a, RenderPassId>, RenderGraphError> {
         let mut sorted_nodes: BumpVec<'a, RenderPassId> =
             BumpVec::with_capacity_in(nodes.len(), bump);
         let mut stack: BumpVec<RenderPassId> = BumpVec::new_in(&bump);
@@ -240,8 +428,10 @@ impl<'a> RenderGraph<'a> {
                         *visit_count = 1;
                         let to_visit_node = &nodes[to_visit.0];
                         for child_id in &to_visit_node.output_nodes {
-                            if visited[child_id.0] == 0 {
-                                stack.push(*child_id);
+                            match visited[child_id.0] {
+                                0 => stack.push(*child_id),
+                                1 => return Err(RenderGraphError::CycleDetected),
+                                _ => {}
                             }
                         }
                     }
@@ -258,27 +448,277 @@ impl<'a> RenderGraph<'a> {
             }
         }
 
-        sorted_nodes
+        Ok(sorted_nodes)
     }
 
-    pub fn run(self, bump: &'a Bump, cinder: &mut Renderer) -> Result<PresentContext> {
-        // TODO: Label colors, flag to disable it
-
+    // Indices of the enabled passes, in the order they were added, sorted so every pass comes
+    // after the passes writing its inputs. This is the order `run` records them in
+    pub fn execution_order(&self, bump: &'a Bump) -> Result<BumpVec<'a, usize>, RenderGraphError> {
         let nodes = self.compile_nodes(bump);
-        let sorted_nodes = Self::sorted_nodes(bump, &nodes);
+        let sorted_nodes = Self::sorted_nodes(bump, &nodes)?;
+        let mut order = BumpVec::with_capacity_in(sorted_nodes.len(), bump);
+        order.extend(
+            sorted_nodes
+                .iter()
+                .rev()
+                .filter(|pass_id| self.passes[pass_id.0].enabled)
+                .map(|pass_id| pass_id.0),
+        );
+        Ok(order)
+    }
+
+    // Checks every attachment up front, so an invalid graph fails before a frame is started
+    fn validate(&self, cinder: &Renderer) -> Result<(), RenderGraphError> {
+        for pass in self.passes.iter().filter(|pass| pass.enabled) {
+            let attachments = pass
+                .color_attachments
+                .iter()
+                .map(|(ty, _)| (ty, false))
+                .chain(pass.depth_attachment.iter().map(|(ty, _)| (ty, true)));
+            for (ty, expected_depth) in attachments {
+                let (resource, format) = match ty {
+                    AttachmentType::SwapchainImage if expected_depth => {
+                        return Err(RenderGraphError::UnsupportedSwapchainDepth)
+                    }
+                    AttachmentType::SwapchainImage => continue,
+                    AttachmentType::Reference(id) | AttachmentType::ReferenceLayer(id, _) => {
+                        let image = cinder
+                            .resource_manager
+                            .images
+                            .get(*id)
+                            .ok_or(RenderGraphError::MissingImage(*id))?;
+                        (RenderPassResource::Image(*id), image.format())
+                    }
+                    AttachmentType::ReferenceView(_, _) if expected_depth => {
+                        return Err(RenderGraphError::UnsupportedDepthView)
+                    }
+                    AttachmentType::ReferenceView(id, view) => {
+                        let image = cinder
+                            .resource_manager
+                            .images
+                            .get(*id)
+                            .ok_or(RenderGraphError::MissingImage(*id))?;
+                        let format = image
+                            .view_description(*view)
+                            .format
+                            .unwrap_or(image.format());
+                        (RenderPassResource::Image(*id), format)
+                    }
+                    AttachmentType::Transient(id) => {
+                        let (_, desc) = self
+                            .transients
+                            .get(id.0)
+                            .ok_or(RenderGraphError::MissingTransient(*id))?;
+                        (RenderPassResource::Transient(*id), desc.format)
+                    }
+                };
+                if format.is_depth() != expected_depth {
+                    return Err(RenderGraphError::FormatMismatch {
+                        image: resource,
+                        format,
+                        expected_depth,
+                    });
+                }
+            }
+            for resource in pass.inputs.iter().chain(pass.outputs.iter()) {
+                if let RenderPassResource::Transient(id) = resource {
+                    if id.0 >= self.transients.len() {
+                        return Err(RenderGraphError::MissingTransient(*id));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn pass_transients<'b>(pass: &'b RenderPass) -> impl Iterator<Item = TransientId> + 'b {
+        let attachments = pass
+            .color_attachments
+            .iter()
+            .chain(pass.depth_attachment.iter())
+            .filter_map(|(ty, _)| match ty {
+                AttachmentType::Transient(id) => Some(*id),
+                _ => None,
+            });
+        let resources = pass
+            .inputs
+            .iter()
+            .chain(pass.outputs.iter())
+            .filter_map(|resource| match resource {
+                RenderPassResource::Transient(id) => Some(*id),
+                _ => None,
+            });
+        attachments.chain(resources)
+    }
+
+    // Assigns every transient a memory slot, greedily reusing a slot once the last pass using its
+    // previous transient has run
+    fn transient_requests(
+        &self,
+        bump: &'a Bump,
+        sorted_nodes: &[RenderPassId],
+    ) -> BumpVec<'a, TransientImageRequest> {
+        // First and last position in execution order of the passes using each transient. Unused
+        // transients overlap everything, so they get a slot of their own
+        let mut lifetimes = bumpalo::vec![in bump; (usize::MAX, 0); self.transients.len()];
+        for (position, pass_id) in sorted_nodes.iter().rev().enumerate() {
+            let pass = &self.passes[pass_id.0];
+            if !pass.enabled {
+                continue;
+            }
+            for id in Self::pass_transients(pass) {
+                let (first, last) = &mut lifetimes[id.0];
+                *first = (*first).min(position);
+                *last = (*last).max(position);
+            }
+        }
+        for lifetime in lifetimes.iter_mut() {
+            if lifetime.0 == usize::MAX {
+                *lifetime = (0, usize::MAX);
+            }
+        }
+
+        let mut by_first_use = (0..self.transients.len()).collect::<Vec<_>>();
+        by_first_use.sort_by_key(|idx| lifetimes[*idx].0);
+
+        let mut requests = BumpVec::with_capacity_in(self.transients.len(), bump);
+        for (size, desc) in &self.transients {
+            requests.push(TransientImageRequest {
+                size: *size,
+                desc: *desc,
+                slot: 0,
+            });
+        }
+        // Last use and memory type of the transient currently in each slot
+        let mut slots: BumpVec<(usize, _)> = BumpVec::new_in(bump);
+        for idx in by_first_use {
+            let (first, last) = lifetimes[idx];
+            let memory_ty = requests[idx].desc.memory_ty;
+            let slot = match slots
+                .iter()
+                .position(|(slot_last, slot_ty)| *slot_last < first && *slot_ty == memory_ty)
+            {
+                Some(slot) => {
+                    slots[slot].0 = last;
+                    slot
+                }
+                None => {
+                    slots.push((last, memory_ty));
+                    slots.len() - 1
+                }
+            };
+            requests[idx].slot = slot;
+        }
+        requests
+    }
+
+    // The first use of a transient waits on every earlier access to the memory it aliases
+    fn transition_transient<'b>(
+        cinder: &'b Renderer,
+        cmd_list: &CommandList,
+        layouts: &mut [Option<Layout>],
+        id: TransientId,
+        layout: Layout,
+    ) -> Result<&'b Image, RenderGraphError> {
+        let image = cinder
+            .transient_images
+            .get(id)
+            .ok_or(RenderGraphError::MissingTransient(id))?;
+        let old_layout = layouts[id.0].unwrap_or(Layout::Undefined);
+        if layouts[id.0] != Some(layout) {
+            cmd_list.pipeline_barrier(
+                &cinder.device,
+                &[],
+                &[ImageBarrier::new(image, old_layout, layout).with_access(
+                    Access::MEMORY_WRITE,
+                    Access::MEMORY_READ | Access::MEMORY_WRITE,
+                )],
+                PipelineStage::ALL_COMMANDS,
+                PipelineStage::ALL_COMMANDS,
+            );
+            layouts[id.0] = Some(layout);
+        }
+        Ok(image)
+    }
+
+    // Turns managed depth attachments into transients, which only live for their pass
+    fn resolve_managed_depth(&mut self, surface_size: Size2D<u32>) {
+        for pass in self.passes.iter_mut().filter(|pass| pass.enabled) {
+            if let Some(format) = pass.managed_depth {
+                self.transients.push((
+                    surface_size,
+                    ImageDescription {
+                        name: Some("Managed Depth"),
+                        format,
+                        usage: ImageUsage::Depth,
+                        ..Default::default()
+                    },
+                ));
+                let id = TransientId(self.transients.len() - 1);
+                pass.depth_attachment = Some((
+                    AttachmentType::Transient(id),
+                    RenderAttachmentDesc::clear(ClearValue::default_depth())
+                        .store_op(AttachmentStoreOp::DontCare)
+                        .layout(Layout::DepthAttachment),
+                ));
+            }
+        }
+    }
+
+    pub fn run(
+        mut self,
+        bump: &'a Bump,
+        cinder: &mut Renderer,
+    ) -> Result<PresentContext, RenderGraphError> {
+        // TODO: Label colors, flag to disable it
 
         let surface_rect = cinder.device.surface_rect();
+        self.resolve_managed_depth(Size2D::new(surface_rect.width(), surface_rect.height()));
+        self.validate(cinder)?;
+        let nodes = self.compile_nodes(bump);
+        let sorted_nodes = Self::sorted_nodes(bump, &nodes)?;
+        let transient_requests = self.transient_requests(bump, &sorted_nodes);
+        cinder
+            .transient_images
+            .prepare(&cinder.device, &transient_requests)
+            .map_err(RenderGraphError::Device)?;
+        let mut transient_layouts = bumpalo::vec![in bump; None; self.transients.len()];
 
         if DEBUG_LABELS {
             cinder
                 .device
                 .begin_queue_label("Frame Begin", [0.0, 0.0, 1.0, 1.0]);
         }
-        let cmd_list = cinder.command_queue.get_command_list(&cinder.device)?;
-        let swapchain_image = cinder.swapchain.acquire_image(&cinder.device, &cmd_list)?;
+        let cmd_list = cinder
+            .command_queue
+            .get_command_list(&cinder.device)
+            .map_err(RenderGraphError::Device)?;
+        let swapchain_image = match cinder.swapchain.acquire_image(&cinder.device, &cmd_list) {
+            Ok(swapchain_image) => swapchain_image,
+            Err(err) => {
+                if DEBUG_LABELS {
+                    cinder.device.end_queue_label();
+                }
+                return Err(match err.downcast_ref() {
+                    Some(SwapchainError::OutOfDate) => RenderGraphError::SwapchainOutOfDate,
+                    _ => RenderGraphError::Device(err),
+                });
+            }
+        };
+        cinder
+            .gpu_profiler
+            .begin_frame(&cinder.device, &cmd_list)
+            .map_err(RenderGraphError::Device)?;
+        cinder
+            .occlusion_queries
+            .begin_frame(&cinder.device, &cmd_list)
+            .map_err(RenderGraphError::Device)?;
 
         for pass_id in sorted_nodes.iter().rev() {
             let pass = self.passes.get(pass_id.0).unwrap();
+            if !pass.enabled {
+                continue;
+            }
 
             let mut compiled_passes = BumpVec::new_in(bump);
             for (ty, desc) in pass.color_attachments.iter() {
@@ -286,23 +726,149 @@ impl<'a> RenderGraph<'a> {
                     AttachmentType::SwapchainImage => {
                         compiled_passes.push(RenderAttachment::color(swapchain_image, *desc));
                     }
-                    AttachmentType::Reference(_) => todo!(),
+                    AttachmentType::Reference(id) => {
+                        let image = cinder
+                            .resource_manager
+                            .images
+                            .get(*id)
+                            .ok_or(RenderGraphError::MissingImage(*id))?;
+                        // Referenced color attachments are not tracked between passes, so their
+                        // previous contents are discarded
+                        cmd_list.transition_image(
+                            &cinder.device,
+                            image,
+                            Layout::Undefined,
+                            desc.layout,
+                        );
+                        compiled_passes.push(RenderAttachment::color_image(image, *desc));
+                    }
+                    AttachmentType::ReferenceLayer(id, layer) => {
+                        let image = cinder
+                            .resource_manager
+                            .images
+                            .get(*id)
+                            .ok_or(RenderGraphError::MissingImage(*id))?;
+                        // Other layers of the image keep their contents
+                        cmd_list.transition_image_layer(
+                            &cinder.device,
+                            image,
+                            *layer,
+                            Layout::Undefined,
+                            desc.layout,
+                        );
+                        compiled_passes.push(
+                            RenderAttachment::color_image_layer(
+                                &cinder.device,
+                                image,
+                                *layer,
+                                *desc,
+                            )
+                            .map_err(RenderGraphError::Device)?,
+                        );
+                    }
+                    AttachmentType::ReferenceView(id, view) => {
+                        let image = cinder
+                            .resource_manager
+                            .images
+                            .get(*id)
+                            .ok_or(RenderGraphError::MissingImage(*id))?;
+                        // Like `Reference`, the whole image is transitioned and its contents are
+                        // discarded
+                        cmd_list.transition_image(
+                            &cinder.device,
+                            image,
+                            Layout::Undefined,
+                            desc.layout,
+                        );
+                        compiled_passes
+                            .push(RenderAttachment::color_image_view(image, *view, *desc));
+                    }
+                    AttachmentType::Transient(id) => {
+                        let image = Self::transition_transient(
+                            cinder,
+                            &cmd_list,
+                            &mut transient_layouts,
+                            *id,
+                            desc.layout,
+                        )?;
+                        compiled_passes.push(RenderAttachment::color_image(image, *desc));
+                    }
                 }
             }
 
-            let depth_attachment = pass.depth_attachment.as_ref().map(|(ty, desc)| match ty {
-                AttachmentType::SwapchainImage => {
-                    panic!("Swapchain Image not yet supported for depth attachment")
-                }
-                AttachmentType::Reference(id) => {
-                    let image = cinder
-                        .resource_manager
-                        .images
-                        .get(*id)
-                        .expect("Could not find depth attachment image");
-                    RenderAttachment::depth(image, *desc)
+            let (depth_attachment, stencil_attachment) = match &pass.depth_attachment {
+                None => (None, None),
+                Some((ty, desc)) => match ty {
+                    AttachmentType::SwapchainImage => {
+                        return Err(RenderGraphError::UnsupportedSwapchainDepth)
+                    }
+                    AttachmentType::ReferenceView(_, _) => {
+                        return Err(RenderGraphError::UnsupportedDepthView)
+                    }
+                    AttachmentType::Reference(id) => {
+                        let image = cinder
+                            .resource_manager
+                            .images
+                            .get(*id)
+                            .ok_or(RenderGraphError::MissingImage(*id))?;
+                        (
+                            Some(RenderAttachment::depth(image, *desc)),
+                            RenderAttachment::stencil(image, *desc),
+                        )
+                    }
+                    AttachmentType::ReferenceLayer(id, layer) => {
+                        let image = cinder
+                            .resource_manager
+                            .images
+                            .get(*id)
+                            .ok_or(RenderGraphError::MissingImage(*id))?;
+                        // Like color layers, the previous contents of the layer are discarded
+                        cmd_list.transition_image_layer(
+                            &cinder.device,
+                            image,
+                            *layer,
+                            Layout::Undefined,
+                            desc.layout,
+                        );
+                        (
+                            Some(
+                                RenderAttachment::depth_layer(&cinder.device, image, *layer, *desc)
+                                    .map_err(RenderGraphError::Device)?,
+                            ),
+                            RenderAttachment::stencil_layer(&cinder.device, image, *layer, *desc)
+                                .map_err(RenderGraphError::Device)?,
+                        )
+                    }
+                    AttachmentType::Transient(id) => {
+                        let image = Self::transition_transient(
+                            cinder,
+                            &cmd_list,
+                            &mut transient_layouts,
+                            *id,
+                            desc.layout,
+                        )?;
+                        (
+                            Some(RenderAttachment::depth(image, *desc)),
+                            RenderAttachment::stencil(image, *desc),
+                        )
+                    }
+                },
+            };
+            // Transients first used outside of an attachment, i.e. written by compute, start out
+            // in `Layout::General`
+            for resource in pass.inputs.iter().chain(pass.outputs.iter()) {
+                if let RenderPassResource::Transient(id) = resource {
+                    if transient_layouts[id.0].is_none() {
+                        Self::transition_transient(
+                            cinder,
+                            &cmd_list,
+                            &mut transient_layouts,
+                            *id,
+                            Layout::General,
+                        )?;
+                    }
                 }
-            });
+            }
 
             if DEBUG_LABELS {
                 cmd_list.begin_label(
@@ -314,21 +880,106 @@ impl<'a> RenderGraph<'a> {
                     [1.0, 0.0, 0.0, 1.0],
                 );
             }
-            cmd_list.begin_rendering(
+            cmd_list.write_timestamp(
                 &cinder.device,
-                pass.render_area.unwrap_or(surface_rect),
-                &compiled_passes,
-                depth_attachment,
+                &mut cinder.gpu_profiler,
+                pass.name.unwrap_or(&format!("Pass #{}", pass_id.0)),
             );
-            // TODO: Figure out something with viewport/scissor as well
-            cmd_list.bind_viewport(&cinder.device, surface_rect, pass.flipped_viewport);
-            cmd_list.bind_scissor(&cinder.device, surface_rect);
-            (pass.callback)(cinder, &cmd_list)?;
-            cmd_list.end_rendering(&cinder.device);
+            // Passes without attachments are recorded outside of dynamic rendering, so their
+            // callbacks can dispatch compute work and insert their own barriers
+            let is_rendering = !compiled_passes.is_empty() || depth_attachment.is_some();
+            if is_rendering {
+                let viewport = pass.viewport.unwrap_or(surface_rect);
+                cmd_list.begin_rendering(
+                    &cinder.device,
+                    pass.render_area.or(pass.viewport).unwrap_or(surface_rect),
+                    &compiled_passes,
+                    depth_attachment,
+                    stencil_attachment,
+                )?;
+                cmd_list.bind_viewport(&cinder.device, viewport, pass.flipped_viewport);
+                cmd_list.bind_scissor(&cinder.device, pass.scissor.unwrap_or(viewport));
+            }
+            (pass.callback)(cinder, &cmd_list).map_err(RenderGraphError::Pass)?;
+            if is_rendering {
+                cmd_list.end_rendering(&cinder.device);
+            }
+            // Referenced color attachments that are pass outputs get read by later passes
+            for (ty, desc) in pass.color_attachments.iter() {
+                let (id, layer) = match ty {
+                    AttachmentType::SwapchainImage | AttachmentType::Transient(_) => continue,
+                    AttachmentType::Reference(id) | AttachmentType::ReferenceView(id, _) => {
+                        (id, None)
+                    }
+                    AttachmentType::ReferenceLayer(id, layer) => (id, Some(*layer)),
+                };
+                if pass.outputs.contains(&RenderPassResource::Image(*id)) {
+                    let image = cinder
+                        .resource_manager
+                        .images
+                        .get(*id)
+                        .ok_or(RenderGraphError::MissingImage(*id))?;
+                    match layer {
+                        Some(layer) => cmd_list.transition_image_layer(
+                            &cinder.device,
+                            image,
+                            layer,
+                            desc.layout,
+                            Layout::ShaderReadOnly,
+                        ),
+                        None => cmd_list.transition_image(
+                            &cinder.device,
+                            image,
+                            desc.layout,
+                            Layout::ShaderReadOnly,
+                        ),
+                    }
+                }
+            }
+            // Depth layers that are pass outputs get sampled by later passes, i.e. shadow cascades
+            if let Some((AttachmentType::ReferenceLayer(id, layer), desc)) = &pass.depth_attachment
+            {
+                if pass.outputs.contains(&RenderPassResource::Image(*id)) {
+                    let image = cinder
+                        .resource_manager
+                        .images
+                        .get(*id)
+                        .ok_or(RenderGraphError::MissingImage(*id))?;
+                    cmd_list.transition_image_layer(
+                        &cinder.device,
+                        image,
+                        *layer,
+                        desc.layout,
+                        Layout::ShaderReadOnly,
+                    );
+                }
+            }
+            // Transient attachments that are pass outputs get read by later passes, whether they
+            // are color or depth
+            let transient_attachments = pass
+                .color_attachments
+                .iter()
+                .chain(pass.depth_attachment.iter())
+                .filter_map(|(ty, _)| match ty {
+                    AttachmentType::Transient(id) => Some(*id),
+                    _ => None,
+                });
+            for id in transient_attachments {
+                if pass.outputs.contains(&RenderPassResource::Transient(id)) {
+                    Self::transition_transient(
+                        cinder,
+                        &cmd_list,
+                        &mut transient_layouts,
+                        id,
+                        Layout::ShaderReadOnly,
+                    )?;
+                }
+            }
             if DEBUG_LABELS {
                 cmd_list.end_label(&cinder.device);
             }
         }
+        cmd_list.write_timestamp(&cinder.device, &mut cinder.gpu_profiler, "Frame End");
 
         Ok(PresentContext {
             present_rect: surface_rect,
@@ -337,3 +988,23 @@ impl<'a> RenderGraph<'a> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{RenderGraph, RenderPass, RenderPassResource};
+    use bumpalo::Bump;
+    use resource_manager::ResourceId;
+
+    #[test]
+    fn dependent_passes_run_after_their_inputs() {
+        let bump = Bump::new();
+        let mut graph = RenderGraph::new(&bump);
+        let image = RenderPassResource::Image(ResourceId::new(0, 0));
+        // Added before the pass it depends on
+        graph.add_pass(&bump, RenderPass::new(&bump).add_input(image));
+        graph.add_pass(&bump, RenderPass::new(&bump).add_output(image));
+
+        let order = graph.execution_order(&bump).unwrap();
+        assert_eq!(order.as_slice(), &[1, 0]);
+    }
+}
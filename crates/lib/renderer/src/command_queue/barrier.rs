@@ -0,0 +1,177 @@
+use crate::resources::{
+    buffer::Buffer,
+    image::{Image, Layout},
+};
+use ash::vk;
+use bitflags::bitflags;
+
+bitflags! {
+    #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+    pub struct PipelineStage: u32 {
+        const TOP_OF_PIPE = 0x00000001;
+        const DRAW_INDIRECT = 0x00000002;
+        const VERTEX_INPUT = 0x00000004;
+        const VERTEX_SHADER = 0x00000008;
+        const FRAGMENT_SHADER = 0x00000080;
+        const EARLY_FRAGMENT_TESTS = 0x00000100;
+        const LATE_FRAGMENT_TESTS = 0x00000200;
+        const COLOR_ATTACHMENT_OUTPUT = 0x00000400;
+        const COMPUTE_SHADER = 0x00000800;
+        const TRANSFER = 0x00001000;
+        const BOTTOM_OF_PIPE = 0x00002000;
+        const HOST = 0x00004000;
+        const ALL_GRAPHICS = 0x00008000;
+        const ALL_COMMANDS = 0x00010000;
+    }
+}
+
+impl From<PipelineStage> for vk::PipelineStageFlags {
+    fn from(value: PipelineStage) -> Self {
+        vk::PipelineStageFlags::from_raw(value.bits())
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+    pub struct Access: u32 {
+        const INDIRECT_COMMAND_READ = 0x00000001;
+        const INDEX_READ = 0x00000002;
+        const VERTEX_ATTRIBUTE_READ = 0x00000004;
+        const UNIFORM_READ = 0x00000008;
+        const SHADER_READ = 0x00000020;
+        const SHADER_WRITE = 0x00000040;
+        const COLOR_ATTACHMENT_READ = 0x00000080;
+        const COLOR_ATTACHMENT_WRITE = 0x00000100;
+        const DEPTH_STENCIL_ATTACHMENT_READ = 0x00000200;
+        const DEPTH_STENCIL_ATTACHMENT_WRITE = 0x00000400;
+        const TRANSFER_READ = 0x00000800;
+        const TRANSFER_WRITE = 0x00001000;
+        const HOST_READ = 0x00002000;
+        const HOST_WRITE = 0x00004000;
+        const MEMORY_READ = 0x00008000;
+        const MEMORY_WRITE = 0x00010000;
+    }
+}
+
+impl From<Access> for vk::AccessFlags {
+    fn from(value: Access) -> Self {
+        vk::AccessFlags::from_raw(value.bits())
+    }
+}
+
+// Makes writes to (a range of) `buffer` done with `src_access` visible to `dst_access`
+#[derive(Clone, Copy)]
+pub struct BufferBarrier<'a> {
+    pub buffer: &'a Buffer,
+    pub src_access: Access,
+    pub dst_access: Access,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl<'a> BufferBarrier<'a> {
+    // Covers the whole buffer, with no access masks set
+    pub fn new(buffer: &'a Buffer) -> Self {
+        Self {
+            buffer,
+            src_access: Access::empty(),
+            dst_access: Access::empty(),
+            offset: 0,
+            size: vk::WHOLE_SIZE,
+        }
+    }
+
+    pub fn with_access(mut self, src_access: Access, dst_access: Access) -> Self {
+        self.src_access = src_access;
+        self.dst_access = dst_access;
+        self
+    }
+
+    pub fn with_range(mut self, offset: u64, size: u64) -> Self {
+        self.offset = offset;
+        self.size = size;
+        self
+    }
+
+    pub(crate) fn raw(&self) -> vk::BufferMemoryBarrier {
+        vk::BufferMemoryBarrier {
+            src_access_mask: self.src_access.into(),
+            dst_access_mask: self.dst_access.into(),
+            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            buffer: self.buffer.raw,
+            offset: self.offset,
+            size: self.size,
+            ..Default::default()
+        }
+    }
+}
+
+// Same as `BufferBarrier`, also transitioning the image from `old_layout` to `new_layout`
+#[derive(Clone, Copy)]
+pub struct ImageBarrier<'a> {
+    pub image: &'a Image,
+    pub old_layout: Layout,
+    pub new_layout: Layout,
+    pub src_access: Access,
+    pub dst_access: Access,
+    pub base_mip_level: u32,
+    pub level_count: u32,
+    pub base_array_layer: u32,
+    pub layer_count: u32,
+}
+
+impl<'a> ImageBarrier<'a> {
+    // Covers every mip level and layer, with no access masks set
+    pub fn new(image: &'a Image, old_layout: Layout, new_layout: Layout) -> Self {
+        Self {
+            image,
+            old_layout,
+            new_layout,
+            src_access: Access::empty(),
+            dst_access: Access::empty(),
+            base_mip_level: 0,
+            level_count: vk::REMAINING_MIP_LEVELS,
+            base_array_layer: 0,
+            layer_count: vk::REMAINING_ARRAY_LAYERS,
+        }
+    }
+
+    pub fn with_access(mut self, src_access: Access, dst_access: Access) -> Self {
+        self.src_access = src_access;
+        self.dst_access = dst_access;
+        self
+    }
+
+    pub fn with_mip_levels(mut self, base_mip_level: u32, level_count: u32) -> Self {
+        self.base_mip_level = base_mip_level;
+        self.level_count = level_count;
+        self
+    }
+
+    pub fn with_layers(mut self, base_array_layer: u32, layer_count: u32) -> Self {
+        self.base_array_layer = base_array_layer;
+        self.layer_count = layer_count;
+        self
+    }
+
+    pub(crate) fn raw(&self) -> vk::ImageMemoryBarrier {
+        vk::ImageMemoryBarrier {
+            src_access_mask: self.src_access.into(),
+            dst_access_mask: self.dst_access.into(),
+            old_layout: self.old_layout.into(),
+            new_layout: self.new_layout.into(),
+            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            image: self.image.raw,
+            subresource_range: vk::ImageSubresourceRange {
+                aspect_mask: self.image.desc.view_aspect_mask(),
+                base_mip_level: self.base_mip_level,
+                level_count: self.level_count,
+                base_array_layer: self.base_array_layer,
+                layer_count: self.layer_count,
+            },
+            ..Default::default()
+        }
+    }
+}
@@ -1,10 +1,17 @@
 use crate::{
-    device::{cmd_begin_label, cmd_end_label, cmd_insert_label, Device, MAX_FRAMES_IN_FLIGHT},
+    device::{
+        cmd_begin_label, cmd_end_label, cmd_insert_label, Device, DeviceError, Fence, Semaphore,
+        MAX_FRAMES_IN_FLIGHT,
+    },
+    profiling::{GpuProfiler, QueryPool},
     resources::{
-        bind_group::BindGroup,
-        buffer::Buffer,
-        image::{Image, ImageUsage, Layout},
-        pipeline::{graphics::GraphicsPipeline, PipelineCommon, PipelineError},
+        bind_group::{BindGroup, BindGroupPushInfo},
+        buffer::{Buffer, IndexType},
+        image::{Image, ImageUsage, ImageViewId, Layout},
+        pipeline::{
+            compute::ComputePipeline, graphics::GraphicsPipeline, PipelineCommon, PipelineError,
+        },
+        sampler::Filter,
         shader::ShaderStage,
     },
     swapchain::SwapchainImage,
@@ -13,6 +20,66 @@ use anyhow::Result;
 use ash::vk;
 use math::rect::Rect2D;
 use serde::Deserialize;
+use thiserror::Error;
+
+mod barrier;
+
+pub use barrier::{Access, BufferBarrier, ImageBarrier, PipelineStage};
+
+#[derive(Debug, Error)]
+pub enum RenderingError {
+    #[error("color attachment {index} has the depth format {format:?}")]
+    DepthFormatAsColor { index: usize, format: vk::Format },
+    #[error("depth attachment has the color format {0:?}")]
+    ColorFormatAsDepth(vk::Format),
+    #[error("color attachment {index} is cleared with a depth value")]
+    DepthClearOnColor { index: usize },
+    #[error("depth or stencil attachment is cleared with a color value")]
+    ColorClearOnDepth,
+    #[error(
+        "pipeline {name:?} renders to color formats {expected_color:?} and depth format \
+         {expected_depth:?}, but the attachments being rendered to are {found_color:?} and \
+         {found_depth:?}"
+    )]
+    PipelineFormatMismatch {
+        name: Option<String>,
+        expected_color: Vec<vk::Format>,
+        expected_depth: Option<vk::Format>,
+        found_color: Vec<vk::Format>,
+        found_depth: Option<vk::Format>,
+    },
+}
+
+// Attachment formats between `begin_rendering` and `end_rendering`, which bound graphics
+// pipelines have to match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RenderingFormats {
+    color: Vec<vk::Format>,
+    depth: Option<vk::Format>,
+}
+
+fn is_depth_format(format: vk::Format) -> bool {
+    matches!(
+        format,
+        vk::Format::D16_UNORM
+            | vk::Format::D16_UNORM_S8_UINT
+            | vk::Format::X8_D24_UNORM_PACK32
+            | vk::Format::D24_UNORM_S8_UINT
+            | vk::Format::D32_SFLOAT
+            | vk::Format::D32_SFLOAT_S8_UINT
+    )
+}
+
+// Every mip level and layer of `image`, for the clear commands
+fn clear_range(image: &Image) -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange {
+        aspect_mask: image.desc.view_aspect_mask(),
+        base_mip_level: 0,
+        level_count: vk::REMAINING_MIP_LEVELS,
+        base_array_layer: 0,
+        layer_count: vk::REMAINING_ARRAY_LAYERS,
+    }
+}
 
 ///
 /// TEMP START: Not convinced about this, keeping it for now
@@ -59,24 +126,35 @@ impl From<ClearValue> for vk::ClearValue {
     }
 }
 
+// The clear value is part of `Clear`, so an attachment can't clear without saying to what, and
+// `Load` and `DontCare` can't carry a value that would be ignored
 #[derive(Debug, Clone, Copy, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum AttachmentLoadOp {
-    Clear,
+    Clear(ClearValue),
     Load,
     DontCare,
 }
 
 impl Default for AttachmentLoadOp {
     fn default() -> Self {
-        Self::Clear
+        Self::Clear(ClearValue::default_color())
+    }
+}
+
+impl AttachmentLoadOp {
+    pub fn clear_value(&self) -> Option<ClearValue> {
+        match self {
+            AttachmentLoadOp::Clear(clear_value) => Some(*clear_value),
+            AttachmentLoadOp::Load | AttachmentLoadOp::DontCare => None,
+        }
     }
 }
 
 impl From<AttachmentLoadOp> for vk::AttachmentLoadOp {
     fn from(op: AttachmentLoadOp) -> Self {
         match op {
-            AttachmentLoadOp::Clear => vk::AttachmentLoadOp::CLEAR,
+            AttachmentLoadOp::Clear(_) => vk::AttachmentLoadOp::CLEAR,
             AttachmentLoadOp::Load => vk::AttachmentLoadOp::LOAD,
             AttachmentLoadOp::DontCare => vk::AttachmentLoadOp::DONT_CARE,
         }
@@ -109,31 +187,168 @@ pub struct RenderAttachmentDesc {
     pub load_op: AttachmentLoadOp,
     pub store_op: AttachmentStoreOp,
     pub layout: Layout,
-    pub clear_value: ClearValue,
 }
 
-#[repr(transparent)]
-pub struct RenderAttachment(vk::RenderingAttachmentInfo);
+// Built fluently, e.g. `RenderAttachmentDesc::load().store_op(AttachmentStoreOp::DontCare)`
+impl RenderAttachmentDesc {
+    pub fn clear(clear_value: ClearValue) -> Self {
+        Self {
+            load_op: AttachmentLoadOp::Clear(clear_value),
+            ..Default::default()
+        }
+    }
+
+    pub fn load() -> Self {
+        Self {
+            load_op: AttachmentLoadOp::Load,
+            ..Default::default()
+        }
+    }
+
+    pub fn dont_care() -> Self {
+        Self {
+            load_op: AttachmentLoadOp::DontCare,
+            ..Default::default()
+        }
+    }
+
+    pub fn load_op(mut self, load_op: AttachmentLoadOp) -> Self {
+        self.load_op = load_op;
+        self
+    }
+
+    pub fn store_op(mut self, store_op: AttachmentStoreOp) -> Self {
+        self.store_op = store_op;
+        self
+    }
+
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+}
+
+pub struct RenderAttachment {
+    info: vk::RenderingAttachmentInfo,
+    format: vk::Format,
+    desc: RenderAttachmentDesc,
+}
 
 impl RenderAttachment {
-    fn from_parts(image_view: vk::ImageView, desc: RenderAttachmentDesc) -> Self {
-        Self(
-            vk::RenderingAttachmentInfo::builder()
+    fn from_parts(
+        image_view: vk::ImageView,
+        format: vk::Format,
+        desc: RenderAttachmentDesc,
+    ) -> Self {
+        Self {
+            info: vk::RenderingAttachmentInfo::builder()
                 .image_view(image_view)
                 .load_op(desc.load_op.into())
                 .store_op(desc.store_op.into())
-                .clear_value(desc.clear_value.into())
+                .clear_value(desc.load_op.clear_value().unwrap_or_default().into())
                 .image_layout(desc.layout.into())
                 .build(),
-        )
+            format,
+            desc,
+        }
     }
 
     pub fn color(swapchain_image: SwapchainImage, desc: RenderAttachmentDesc) -> Self {
-        Self::from_parts(swapchain_image.image_view, desc)
+        Self::from_parts(swapchain_image.image_view, swapchain_image.format, desc)
+    }
+
+    pub fn color_image(image: &Image, desc: RenderAttachmentDesc) -> Self {
+        Self::from_parts(image.view, image.format().into(), desc)
+    }
+
+    // Renders into a view returned by `Image::create_view`, in the view's format
+    pub fn color_image_view(image: &Image, view: ImageViewId, desc: RenderAttachmentDesc) -> Self {
+        let format = image
+            .view_description(view)
+            .format
+            .unwrap_or(image.format());
+        Self::from_parts(image.view(view), format.into(), desc)
+    }
+
+    // Renders into a single layer of an array image, see `Image::layer_view`
+    pub fn color_image_layer(
+        device: &Device,
+        image: &Image,
+        layer: u32,
+        desc: RenderAttachmentDesc,
+    ) -> Result<Self> {
+        Ok(Self::from_parts(
+            image.layer_view(device, layer)?,
+            image.format().into(),
+            desc,
+        ))
     }
 
     pub fn depth(depth_image: &Image, desc: RenderAttachmentDesc) -> Self {
-        Self::from_parts(depth_image.view, desc)
+        Self::from_parts(depth_image.view, depth_image.format().into(), desc)
+    }
+
+    pub fn depth_layer(
+        device: &Device,
+        depth_image: &Image,
+        layer: u32,
+        desc: RenderAttachmentDesc,
+    ) -> Result<Self> {
+        Ok(Self::from_parts(
+            depth_image.layer_view(device, layer)?,
+            depth_image.format().into(),
+            desc,
+        ))
+    }
+
+    // Only returns an attachment if the depth image has a stencil component
+    pub fn stencil(depth_image: &Image, desc: RenderAttachmentDesc) -> Option<Self> {
+        depth_image
+            .format()
+            .has_stencil()
+            .then(|| Self::from_parts(depth_image.view, depth_image.format().into(), desc))
+    }
+
+    pub fn stencil_layer(
+        device: &Device,
+        depth_image: &Image,
+        layer: u32,
+        desc: RenderAttachmentDesc,
+    ) -> Result<Option<Self>> {
+        if !depth_image.format().has_stencil() {
+            return Ok(None);
+        }
+        Self::depth_layer(device, depth_image, layer, desc).map(Some)
+    }
+
+    fn is_depth_cleared(&self) -> Option<bool> {
+        match self.desc.load_op.clear_value()? {
+            ClearValue::Color { .. } => Some(false),
+            ClearValue::Depth { .. } => Some(true),
+        }
+    }
+
+    fn validate_color(&self, index: usize) -> Result<(), RenderingError> {
+        if is_depth_format(self.format) {
+            return Err(RenderingError::DepthFormatAsColor {
+                index,
+                format: self.format,
+            });
+        }
+        if self.is_depth_cleared() == Some(true) {
+            return Err(RenderingError::DepthClearOnColor { index });
+        }
+        Ok(())
+    }
+
+    fn validate_depth(&self) -> Result<(), RenderingError> {
+        if !is_depth_format(self.format) {
+            return Err(RenderingError::ColorFormatAsDepth(self.format));
+        }
+        if self.is_depth_cleared() == Some(false) {
+            return Err(RenderingError::ColorClearOnDepth);
+        }
+        Ok(())
     }
 }
 
@@ -159,6 +374,49 @@ impl Default for ImageBarrierDescription {
     }
 }
 
+// Area of a single mip level, across every layer of the image
+#[derive(Debug, Clone, Copy)]
+pub struct ImageRegion {
+    pub mip_level: u32,
+    pub rect: Rect2D<i32, u32>,
+}
+
+impl ImageRegion {
+    // The whole of `mip_level`
+    pub fn mip(image: &Image, mip_level: u32) -> Self {
+        Self {
+            mip_level,
+            rect: Rect2D::from_width_height(
+                (image.size.width() >> mip_level).max(1),
+                (image.size.height() >> mip_level).max(1),
+            ),
+        }
+    }
+
+    fn offsets(&self) -> [vk::Offset3D; 2] {
+        [
+            vk::Offset3D {
+                x: self.rect.offset().x(),
+                y: self.rect.offset().y(),
+                z: 0,
+            },
+            vk::Offset3D {
+                x: self.rect.offset().x() + self.rect.width() as i32,
+                y: self.rect.offset().y() + self.rect.height() as i32,
+                z: 1,
+            },
+        ]
+    }
+
+    fn subresource(&self, image: &Image) -> vk::ImageSubresourceLayers {
+        vk::ImageSubresourceLayers::builder()
+            .aspect_mask(image.desc.view_aspect_mask())
+            .mip_level(self.mip_level)
+            .layer_count(image.array_layers())
+            .build()
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct CommandList {
     command_buffer: vk::CommandBuffer,
@@ -271,25 +529,114 @@ impl CommandList {
         )
     }
 
+    pub fn transition_image(
+        &self,
+        device: &Device,
+        image: &Image,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) {
+        self.set_image_memory_barrier(
+            device,
+            image.raw,
+            image.desc.view_aspect_mask(),
+            old_layout.into(),
+            new_layout.into(),
+            Default::default(),
+        )
+    }
+
+    // Same as `transition_image`, for a single layer of an array image
+    pub fn transition_image_layer(
+        &self,
+        device: &Device,
+        image: &Image,
+        layer: u32,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) {
+        self.set_image_memory_barrier(
+            device,
+            image.raw,
+            image.desc.view_aspect_mask(),
+            old_layout.into(),
+            new_layout.into(),
+            ImageBarrierDescription {
+                base_array_layer: layer,
+                layer_count: 1,
+                ..Default::default()
+            },
+        )
+    }
+
+    // Same as `transition_image`, for a single mip level
+    pub fn transition_image_mip(
+        &self,
+        device: &Device,
+        image: &Image,
+        mip_level: u32,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) {
+        self.set_image_memory_barrier(
+            device,
+            image.raw,
+            image.desc.view_aspect_mask(),
+            old_layout.into(),
+            new_layout.into(),
+            ImageBarrierDescription {
+                base_mip_level: mip_level,
+                level_count: 1,
+                ..Default::default()
+            },
+        )
+    }
+
     pub fn begin_rendering(
         &self,
         device: &Device,
         render_area: Rect2D<i32, u32>,
         color_attachments: &[RenderAttachment],
         depth_attachment: Option<RenderAttachment>,
-    ) {
-        let color_attachments = unsafe {
-            std::mem::transmute::<&[RenderAttachment], &[vk::RenderingAttachmentInfo]>(
-                color_attachments,
-            )
-        };
-
+        stencil_attachment: Option<RenderAttachment>,
+    ) -> Result<(), RenderingError> {
+        // Mismatches would otherwise only show up as validation errors or garbage output
+        for (index, attachment) in color_attachments.iter().enumerate() {
+            attachment.validate_color(index)?;
+        }
+        for attachment in depth_attachment.iter().chain(&stencil_attachment) {
+            attachment.validate_depth()?;
+        }
+        *device
+            .rendering_formats
+            .lock()
+            .expect("Mutex lock poisoned") = Some(RenderingFormats {
+            color: color_attachments
+                .iter()
+                .map(|attachment| attachment.format)
+                .collect(),
+            depth: depth_attachment
+                .as_ref()
+                .map(|attachment| attachment.format),
+        });
+
+        let color_attachment_infos = color_attachments
+            .iter()
+            .map(|attachment| attachment.info)
+            .collect::<Vec<_>>();
         let rendering_info = vk::RenderingInfo::builder()
             .render_area(crate::util::rect_to_vk(render_area).unwrap())
-            .color_attachments(color_attachments)
+            .color_attachments(&color_attachment_infos)
             .layer_count(1);
         let rendering_info = if let Some(depth_attachment) = &depth_attachment {
-            rendering_info.depth_attachment(&depth_attachment.0).build()
+            rendering_info.depth_attachment(&depth_attachment.info)
+        } else {
+            rendering_info
+        };
+        let rendering_info = if let Some(stencil_attachment) = &stencil_attachment {
+            rendering_info
+                .stencil_attachment(&stencil_attachment.info)
+                .build()
         } else {
             rendering_info.build()
         };
@@ -299,6 +646,7 @@ impl CommandList {
                 .dynamic_rendering()
                 .cmd_begin_rendering(self.command_buffer, &rendering_info);
         }
+        Ok(())
     }
 
     pub fn end_rendering(&self, device: &Device) {
@@ -307,9 +655,40 @@ impl CommandList {
                 .dynamic_rendering()
                 .cmd_end_rendering(self.command_buffer)
         };
+        *device
+            .rendering_formats
+            .lock()
+            .expect("Mutex lock poisoned") = None;
     }
 
-    pub fn bind_graphics_pipeline(&self, device: &Device, pipeline: &GraphicsPipeline) {
+    // Inside of `begin_rendering`, the pipeline's formats have to match the attachments'
+    pub fn bind_graphics_pipeline(
+        &self,
+        device: &Device,
+        pipeline: &GraphicsPipeline,
+    ) -> Result<(), RenderingError> {
+        if let Some(formats) = &*device
+            .rendering_formats
+            .lock()
+            .expect("Mutex lock poisoned")
+        {
+            let expected_color = pipeline
+                .desc
+                .color_formats
+                .iter()
+                .map(|format| vk::Format::from(*format))
+                .collect::<Vec<_>>();
+            let expected_depth = pipeline.desc.depth_format.map(vk::Format::from);
+            if expected_color != formats.color || expected_depth != formats.depth {
+                return Err(RenderingError::PipelineFormatMismatch {
+                    name: pipeline.desc.name.clone(),
+                    expected_color,
+                    expected_depth,
+                    found_color: formats.color.clone(),
+                    found_depth: formats.depth,
+                });
+            }
+        }
         unsafe {
             device.raw().cmd_bind_pipeline(
                 self.command_buffer,
@@ -317,6 +696,17 @@ impl CommandList {
                 pipeline.common.pipeline(),
             )
         }
+        Ok(())
+    }
+
+    pub fn bind_compute_pipeline(&self, device: &Device, pipeline: &ComputePipeline) {
+        unsafe {
+            device.raw().cmd_bind_pipeline(
+                self.command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                pipeline.common.pipeline(),
+            )
+        }
     }
 
     pub fn bind_scissor(&self, device: &Device, rect: Rect2D<i32, u32>) {
@@ -341,7 +731,7 @@ impl CommandList {
     pub fn bind_viewport(&self, device: &Device, rect: Rect2D<i32, u32>, flipped: bool) {
         let (y, height) = if flipped {
             (
-                rect.height() as f32 - rect.offset().y() as f32,
+                rect.offset().y() as f32 + rect.height() as f32,
                 -(rect.height() as f32),
             )
         } else {
@@ -363,6 +753,26 @@ impl CommandList {
         }
     }
 
+    // Binds `buffers[i]` at binding `first_binding + i`, starting `offsets[i]` bytes in
+    pub fn bind_vertex_buffers(
+        &self,
+        device: &Device,
+        first_binding: u32,
+        buffers: &[&Buffer],
+        offsets: &[u64],
+    ) {
+        debug_assert_eq!(buffers.len(), offsets.len());
+        let raw_buffers = buffers.iter().map(|buffer| buffer.raw).collect::<Vec<_>>();
+        unsafe {
+            device.raw().cmd_bind_vertex_buffers(
+                self.command_buffer,
+                first_binding,
+                &raw_buffers,
+                offsets,
+            )
+        }
+    }
+
     pub fn bind_vertex_buffer(&self, device: &Device, buffer: &Buffer) {
         unsafe {
             device
@@ -372,12 +782,18 @@ impl CommandList {
     }
 
     pub fn bind_index_buffer(&self, device: &Device, buffer: &Buffer) {
+        self.bind_index_buffer_typed(device, buffer, IndexType::U32);
+    }
+
+    // `index_type` must match the buffer's elements, e.g. `IndexType::U16` for a buffer created
+    // from a `&[u16]`
+    pub fn bind_index_buffer_typed(&self, device: &Device, buffer: &Buffer, index_type: IndexType) {
         unsafe {
             device.raw().cmd_bind_index_buffer(
                 self.command_buffer,
                 buffer.raw,
                 0,
-                vk::IndexType::UINT32,
+                index_type.into(),
             );
         }
     }
@@ -403,6 +819,97 @@ impl CommandList {
         }
     }
 
+    // `dynamic_offsets` holds one offset per dynamic binding, in set and binding order
+    pub fn bind_descriptor_sets_dynamic(
+        &self,
+        device: &Device,
+        pipeline: &GraphicsPipeline,
+        first_set: u32,
+        bind_groups: &[BindGroup],
+        dynamic_offsets: &[u32],
+    ) {
+        let descriptor_sets =
+            unsafe { std::mem::transmute::<&[BindGroup], &[vk::DescriptorSet]>(bind_groups) };
+        unsafe {
+            device.raw().cmd_bind_descriptor_sets(
+                self.command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline.common.pipeline_layout(),
+                first_set,
+                descriptor_sets,
+                dynamic_offsets,
+            )
+        }
+    }
+
+    pub fn push_descriptor_set(
+        &self,
+        device: &Device,
+        pipeline: &GraphicsPipeline,
+        set: u32,
+        infos: &[BindGroupPushInfo],
+    ) -> Result<(), DeviceError> {
+        let push_descriptor = device
+            .push_descriptor()
+            .ok_or(DeviceError::PushDescriptorNotSupported)?;
+        let writes = infos
+            .iter()
+            .map(|info| {
+                info.data
+                    .write_descriptor(vk::DescriptorSet::null(), info.dst_binding)
+            })
+            .collect::<Vec<_>>();
+        unsafe {
+            push_descriptor.cmd_push_descriptor_set(
+                self.command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline.common.pipeline_layout(),
+                set,
+                &writes,
+            )
+        }
+        Ok(())
+    }
+
+    // Requires a pipeline created with `StencilState::dynamic_reference`
+    pub fn set_stencil_reference(&self, device: &Device, reference: u32) {
+        unsafe {
+            device.raw().cmd_set_stencil_reference(
+                self.command_buffer,
+                vk::StencilFaceFlags::FRONT_AND_BACK,
+                reference,
+            )
+        }
+    }
+
+    // Requires a pipeline created with `GraphicsPipelineDescription::dynamic_line_width`. Clamped to
+    // the widths the device supports, which is only 1.0 without `DeviceCapabilities::max_line_width`
+    pub fn set_line_width(&self, device: &Device, width: f32) {
+        let width = device.capabilities().supported_line_width(width);
+        unsafe { device.raw().cmd_set_line_width(self.command_buffer, width) }
+    }
+
+    pub fn bind_compute_descriptor_sets(
+        &self,
+        device: &Device,
+        pipeline: &ComputePipeline,
+        first_set: u32,
+        bind_groups: &[BindGroup],
+    ) {
+        let descriptor_sets =
+            unsafe { std::mem::transmute::<&[BindGroup], &[vk::DescriptorSet]>(bind_groups) };
+        unsafe {
+            device.raw().cmd_bind_descriptor_sets(
+                self.command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                pipeline.common.pipeline_layout(),
+                first_set,
+                descriptor_sets,
+                &[],
+            )
+        }
+    }
+
     fn push_constant(
         &self,
         device: &Device,
@@ -412,6 +919,14 @@ impl CommandList {
         data: &[u8],
     ) -> Result<(), PipelineError> {
         if let Some(push_constant) = pipeline_common.get_push_constant(shader_stage, idx) {
+            // Writing past the range is undefined behavior. The range itself was checked against
+            // `maxPushConstantsSize` when the pipeline layout was created
+            if data.len() > push_constant.size as usize {
+                return Err(PipelineError::PushConstantTooLarge {
+                    size: data.len(),
+                    max: push_constant.size,
+                });
+            }
             unsafe {
                 device.raw().cmd_push_constants(
                     self.command_buffer,
@@ -459,6 +974,83 @@ impl CommandList {
         )
     }
 
+    pub fn set_compute_bytes<T: Sized>(
+        &self,
+        device: &Device,
+        pipeline: &ComputePipeline,
+        data: &T,
+        idx: u32,
+    ) -> Result<(), PipelineError> {
+        self.push_constant(
+            device,
+            &pipeline.common,
+            ShaderStage::Compute,
+            idx,
+            util::as_u8_slice(data),
+        )
+    }
+
+    // Unlike `transition_image`, stages and access masks are explicit rather than inferred from layouts
+    pub fn pipeline_barrier(
+        &self,
+        device: &Device,
+        buffer_barriers: &[BufferBarrier],
+        image_barriers: &[ImageBarrier],
+        src_stage: PipelineStage,
+        dst_stage: PipelineStage,
+    ) {
+        let buffer_barriers = buffer_barriers
+            .iter()
+            .map(BufferBarrier::raw)
+            .collect::<Vec<_>>();
+        let image_barriers = image_barriers
+            .iter()
+            .map(ImageBarrier::raw)
+            .collect::<Vec<_>>();
+        unsafe {
+            device.raw().cmd_pipeline_barrier(
+                self.command_buffer,
+                src_stage.into(),
+                dst_stage.into(),
+                vk::DependencyFlags::empty(),
+                &[],
+                &buffer_barriers,
+                &image_barriers,
+            )
+        }
+    }
+
+    pub fn dispatch(&self, device: &Device, group_x: u32, group_y: u32, group_z: u32) {
+        unsafe {
+            device
+                .raw()
+                .cmd_dispatch(self.command_buffer, group_x, group_y, group_z)
+        }
+    }
+
+    // Draws `instance_count` copies of the indexed geometry, for vertex buffers with
+    // `VertexInputRate::INSTANCE` bindings or shaders that read `gl_InstanceIndex`
+    pub fn draw_instanced(
+        &self,
+        device: &Device,
+        index_count: u32,
+        first_index: u32,
+        vertex_offset: i32,
+        instance_count: u32,
+    ) {
+        device.draw_counters.record(index_count * instance_count);
+        unsafe {
+            device.raw().cmd_draw_indexed(
+                self.command_buffer,
+                index_count,
+                instance_count,
+                first_index,
+                vertex_offset,
+                0,
+            )
+        }
+    }
+
     pub fn draw_offset(
         &self,
         device: &Device,
@@ -466,6 +1058,7 @@ impl CommandList {
         first_index: u32,
         vertex_offset: i32,
     ) {
+        device.draw_counters.record(index_count);
         unsafe {
             device.raw().cmd_draw_indexed(
                 self.command_buffer,
@@ -478,18 +1071,60 @@ impl CommandList {
         }
     }
 
+    // Non-indexed draw of the bound vertex buffer
+    pub fn draw_vertices(&self, device: &Device, vertex_count: u32, first_vertex: u32) {
+        device.draw_counters.record(vertex_count);
+        unsafe {
+            device
+                .raw()
+                .cmd_draw(self.command_buffer, vertex_count, 1, first_vertex, 0)
+        }
+    }
+
+    // Draws one triangle covering the whole viewport with no vertex or index buffer bound. The
+    // vertex shader generates it from `gl_VertexIndex`, like cinder's `FULLSCREEN_VERTEX_SHADER`
+    pub fn draw_fullscreen_triangle(&self, device: &Device) {
+        self.draw_vertices(device, 3, 0);
+    }
+
     pub fn copy_buffer_to_image(&self, device: &Device, buffer: &Buffer, image: &Image) {
+        self.copy_buffer_to_image_offset(device, buffer, 0, image);
+    }
+
+    // Copies the image's data starting at `offset` bytes into `buffer`, e.g. one of several images
+    // staged in the same buffer
+    pub fn copy_buffer_to_image_offset(
+        &self,
+        device: &Device,
+        buffer: &Buffer,
+        offset: u64,
+        image: &Image,
+    ) {
+        self.copy_buffer_to_image_mip(device, buffer, offset, image, 0);
+    }
+
+    // Same as `copy_buffer_to_image_offset`, filling mip level `mip_level` instead of the first
+    pub fn copy_buffer_to_image_mip(
+        &self,
+        device: &Device,
+        buffer: &Buffer,
+        offset: u64,
+        image: &Image,
+        mip_level: u32,
+    ) {
         let buffer_copy_regions = vk::BufferImageCopy::builder()
+            .buffer_offset(offset)
             .image_subresource(
                 vk::ImageSubresourceLayers::builder()
                     .aspect_mask(image.desc.usage.into())
-                    .layer_count(1)
+                    .mip_level(mip_level)
+                    .layer_count(image.array_layers())
                     .build(),
             )
             .image_extent(vk::Extent3D {
-                width: image.size.width(),
-                height: image.size.height(),
-                depth: 1,
+                width: (image.size.width() >> mip_level).max(1),
+                height: (image.size.height() >> mip_level).max(1),
+                depth: (image.depth() >> mip_level).max(1),
             })
             .build();
 
@@ -504,6 +1139,207 @@ impl CommandList {
         };
     }
 
+    // Fills every mip level past the first by successively blitting down from the previous one.
+    // Expects all levels to be in `TRANSFER_DST_OPTIMAL` layout, and leaves them all in
+    // `SHADER_READ_ONLY_OPTIMAL`
+    pub fn generate_mipmaps(&self, device: &Device, image: &Image) {
+        let aspect_mask = image.desc.view_aspect_mask();
+        let level_barrier = |level: u32| ImageBarrierDescription {
+            base_mip_level: level,
+            level_count: 1,
+            ..Default::default()
+        };
+        let mip_offset = |level: u32| vk::Offset3D {
+            x: (image.size.width() >> level).max(1) as i32,
+            y: (image.size.height() >> level).max(1) as i32,
+            z: (image.depth() >> level).max(1) as i32,
+        };
+        let subresource = |level: u32| {
+            vk::ImageSubresourceLayers::builder()
+                .aspect_mask(aspect_mask)
+                .mip_level(level)
+                .layer_count(image.array_layers())
+                .build()
+        };
+
+        for level in 1..image.mip_levels() {
+            self.set_image_memory_barrier(
+                device,
+                image.raw,
+                aspect_mask,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                level_barrier(level - 1),
+            );
+            let blit = vk::ImageBlit::builder()
+                .src_subresource(subresource(level - 1))
+                .src_offsets([vk::Offset3D::default(), mip_offset(level - 1)])
+                .dst_subresource(subresource(level))
+                .dst_offsets([vk::Offset3D::default(), mip_offset(level)])
+                .build();
+            unsafe {
+                device.raw().cmd_blit_image(
+                    self.command_buffer,
+                    image.raw,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    image.raw,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[blit],
+                    vk::Filter::LINEAR,
+                )
+            };
+            self.set_image_memory_barrier(
+                device,
+                image.raw,
+                aspect_mask,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                level_barrier(level - 1),
+            );
+        }
+        self.set_image_memory_barrier(
+            device,
+            image.raw,
+            aspect_mask,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            level_barrier(image.mip_levels() - 1),
+        );
+    }
+
+    // Scales `src_region` into `dst_region`, converting formats if needed. Expects `src` to be in
+    // `Layout::TransferSrc` and `dst` in `Layout::TransferDst`, which can be different mip levels
+    // of the same image
+    pub fn blit_image(
+        &self,
+        device: &Device,
+        src: &Image,
+        dst: &Image,
+        src_region: ImageRegion,
+        dst_region: ImageRegion,
+        filter: Filter,
+    ) {
+        let blit = vk::ImageBlit::builder()
+            .src_subresource(src_region.subresource(src))
+            .src_offsets(src_region.offsets())
+            .dst_subresource(dst_region.subresource(dst))
+            .dst_offsets(dst_region.offsets())
+            .build();
+        unsafe {
+            device.raw().cmd_blit_image(
+                self.command_buffer,
+                src.raw,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                dst.raw,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[blit],
+                filter.into(),
+            )
+        };
+    }
+
+    // Resolves the first mip level of multisampled `src` into single sampled `dst` of the same size
+    // and format. Expects `src` to be in `Layout::TransferSrc` and `dst` in `Layout::TransferDst`
+    pub fn resolve_image(&self, device: &Device, src: &Image, dst: &Image) {
+        let region = ImageRegion::mip(src, 0);
+        let resolve = vk::ImageResolve::builder()
+            .src_subresource(region.subresource(src))
+            .dst_subresource(region.subresource(dst))
+            .extent(vk::Extent3D {
+                width: src.size.width(),
+                height: src.size.height(),
+                depth: 1,
+            })
+            .build();
+        unsafe {
+            device.raw().cmd_resolve_image(
+                self.command_buffer,
+                src.raw,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                dst.raw,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[resolve],
+            )
+        };
+    }
+
+    // Expects `image` to be in `TRANSFER_SRC_OPTIMAL` layout
+    pub fn copy_image_to_buffer(&self, device: &Device, image: &Image, buffer: &Buffer) {
+        let buffer_copy_regions = vk::BufferImageCopy::builder()
+            .image_subresource(
+                vk::ImageSubresourceLayers::builder()
+                    .aspect_mask(image.desc.usage.into())
+                    .layer_count(image.array_layers())
+                    .build(),
+            )
+            .image_extent(vk::Extent3D {
+                width: image.size.width(),
+                height: image.size.height(),
+                depth: image.depth(),
+            })
+            .build();
+
+        unsafe {
+            device.raw().cmd_copy_image_to_buffer(
+                self.command_buffer,
+                image.raw,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                buffer.raw,
+                &[buffer_copy_regions],
+            )
+        };
+    }
+
+    // Clears every mip level and layer of `image` outside of a render pass, discarding its
+    // previous contents. Waits on any earlier use of the image, and leaves it in
+    // `Layout::TransferDst`
+    pub fn clear_color_image(&self, device: &Device, image: &Image, color: [f32; 4]) {
+        self.prepare_clear(device, image);
+        unsafe {
+            device.raw().cmd_clear_color_image(
+                self.command_buffer,
+                image.raw,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &vk::ClearColorValue { float32: color },
+                &[clear_range(image)],
+            )
+        };
+    }
+
+    // Same as `clear_color_image`, for depth images. Stencil is cleared to 0 if the format has it
+    pub fn clear_depth_image(&self, device: &Device, image: &Image, depth: f32) {
+        debug_assert!(
+            image.desc.usage.is_depth(),
+            "Depth clear on a {:?} image",
+            image.desc.usage
+        );
+        self.prepare_clear(device, image);
+        unsafe {
+            device.raw().cmd_clear_depth_stencil_image(
+                self.command_buffer,
+                image.raw,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &vk::ClearDepthStencilValue { depth, stencil: 0 },
+                &[clear_range(image)],
+            )
+        };
+    }
+
+    fn prepare_clear(&self, device: &Device, image: &Image) {
+        // The clear overwrites whatever wrote the image before, e.g. a shader or an earlier
+        // clear, so those writes have to be made available first
+        self.pipeline_barrier(
+            device,
+            &[],
+            &[
+                ImageBarrier::new(image, Layout::Undefined, Layout::TransferDst)
+                    .with_access(Access::MEMORY_WRITE, Access::TRANSFER_WRITE),
+            ],
+            PipelineStage::ALL_COMMANDS,
+            PipelineStage::TRANSFER,
+        );
+    }
+
     pub fn begin_label(&self, device: &Device, name: &str, color: [f32; 4]) {
         cmd_begin_label(device.instance().debug(), self.command_buffer, name, color);
     }
@@ -515,11 +1351,48 @@ impl CommandList {
     pub fn insert_label(&self, device: &Device, name: &str, color: [f32; 4]) {
         cmd_insert_label(device.instance().debug(), self.command_buffer, name, color);
     }
+
+    // `query` must not have been used yet this frame. Queries can't span render passes
+    pub fn begin_occlusion_query(&self, device: &Device, query_pool: &QueryPool, query: u32) {
+        let flags = if device.capabilities().occlusion_query_precise {
+            vk::QueryControlFlags::PRECISE
+        } else {
+            vk::QueryControlFlags::empty()
+        };
+        unsafe {
+            device
+                .raw()
+                .cmd_begin_query(self.command_buffer, query_pool.raw, query, flags);
+        }
+    }
+
+    pub fn end_occlusion_query(&self, device: &Device, query_pool: &QueryPool, query: u32) {
+        unsafe {
+            device
+                .raw()
+                .cmd_end_query(self.command_buffer, query_pool.raw, query);
+        }
+    }
+
+    pub fn write_timestamp(&self, device: &Device, profiler: &mut GpuProfiler, label: &str) {
+        if let Some((query_pool, query)) = profiler.next_query(label) {
+            unsafe {
+                device.raw().cmd_write_timestamp(
+                    self.command_buffer,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    query_pool,
+                    query,
+                );
+            }
+        }
+    }
 }
 
 pub struct CommandQueue {
     command_pool: vk::CommandPool,
     command_lists: [CommandList; MAX_FRAMES_IN_FLIGHT],
+    compute_command_pool: vk::CommandPool,
+    compute_command_lists: [CommandList; MAX_FRAMES_IN_FLIGHT],
 }
 
 impl CommandQueue {
@@ -545,9 +1418,41 @@ impl CommandQueue {
             lists
         };
 
+        let compute_command_pool = unsafe {
+            device.raw().create_command_pool(
+                &vk::CommandPoolCreateInfo {
+                    flags: vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
+                    queue_family_index: device.compute_queue_family_index(),
+                    ..Default::default()
+                },
+                None,
+            )
+        }?;
+
+        device.set_name(
+            vk::ObjectType::COMMAND_POOL,
+            compute_command_pool,
+            "Compute Command Pool",
+        );
+
+        let compute_command_lists = {
+            let mut lists = [CommandList::default(); MAX_FRAMES_IN_FLIGHT];
+            for idx in 0..MAX_FRAMES_IN_FLIGHT {
+                lists[idx] = CommandList::new(device, compute_command_pool, Some(idx))?;
+                device.set_name(
+                    vk::ObjectType::COMMAND_BUFFER,
+                    lists[idx].command_buffer,
+                    &format!("Compute Command Buffer {idx}"),
+                );
+            }
+            lists
+        };
+
         Ok(Self {
             command_pool,
             command_lists,
+            compute_command_pool,
+            compute_command_lists,
         })
     }
 
@@ -557,6 +1462,73 @@ impl CommandQueue {
         Ok(cmd_list)
     }
 
+    // Command list recorded for `Device::compute_queue`, submitted with `submit_compute`
+    pub fn get_compute_command_list(&self, device: &Device) -> Result<CommandList> {
+        let cmd_list = self.compute_command_lists[device.current_frame_in_flight()];
+        cmd_list.begin(device)?;
+        Ok(cmd_list)
+    }
+
+    // Submits a compute command list to the compute queue. The next graphics submission waits on
+    // it before running any shaders, so its results are visible to the following graphics passes.
+    // At most one compute submission is allowed per frame.
+    pub fn submit_compute(&self, device: &Device, cmd_list: CommandList) -> Result<()> {
+        cmd_list.end(device)?;
+
+        let command_buffers = [cmd_list.buffer()];
+        let signal_semaphores = [device.compute_complete_semaphore()];
+        let submit_info = vk::SubmitInfo::builder()
+            .command_buffers(&command_buffers)
+            .signal_semaphores(&signal_semaphores)
+            .build();
+
+        unsafe {
+            device
+                .raw()
+                .queue_submit(device.compute_queue(), &[submit_info], vk::Fence::null())
+        }?;
+        device.set_compute_wait_pending();
+
+        Ok(())
+    }
+
+    // Submits a command list to the graphics queue outside of the frame, e.g. for uploads. Waits
+    // on `wait` before running any commands, and signals `signal` and `fence` once it completes.
+    pub fn submit(
+        &self,
+        device: &Device,
+        cmd_list: CommandList,
+        wait: &[Semaphore],
+        signal: &[Semaphore],
+        fence: Option<Fence>,
+    ) -> Result<()> {
+        cmd_list.end(device)?;
+
+        let command_buffers = [cmd_list.buffer()];
+        let wait_semaphores = wait.iter().map(|semaphore| semaphore.0).collect::<Vec<_>>();
+        let wait_dst_stage_mask = vec![vk::PipelineStageFlags::ALL_COMMANDS; wait.len()];
+        let signal_semaphores = signal
+            .iter()
+            .map(|semaphore| semaphore.0)
+            .collect::<Vec<_>>();
+        let submit_info = vk::SubmitInfo::builder()
+            .command_buffers(&command_buffers)
+            .wait_semaphores(&wait_semaphores)
+            .wait_dst_stage_mask(&wait_dst_stage_mask)
+            .signal_semaphores(&signal_semaphores)
+            .build();
+
+        unsafe {
+            device.raw().queue_submit(
+                device.present_queue(),
+                &[submit_info],
+                fence.map_or(vk::Fence::null(), |fence| fence.0),
+            )
+        }?;
+
+        Ok(())
+    }
+
     pub fn get_immediate_command_list(&self, device: &Device) -> Result<CommandList> {
         let cmd_list = CommandList::new(device, self.command_pool, None)?;
         cmd_list.begin(device)?;
@@ -590,6 +1562,9 @@ impl CommandQueue {
     pub fn destroy(&self, device: &Device) {
         unsafe {
             device.raw().destroy_command_pool(self.command_pool, None);
+            device
+                .raw()
+                .destroy_command_pool(self.compute_command_pool, None);
         }
     }
 }
@@ -0,0 +1,145 @@
+use super::properties::DeviceProperties;
+use ash::vk;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleCount {
+    X1,
+    X2,
+    X4,
+    X8,
+    X16,
+    X32,
+    X64,
+}
+
+impl SampleCount {
+    pub const ALL: [SampleCount; 7] = [
+        SampleCount::X1,
+        SampleCount::X2,
+        SampleCount::X4,
+        SampleCount::X8,
+        SampleCount::X16,
+        SampleCount::X32,
+        SampleCount::X64,
+    ];
+
+    pub fn count(self) -> u32 {
+        vk::SampleCountFlags::from(self).as_raw()
+    }
+}
+
+impl From<SampleCount> for vk::SampleCountFlags {
+    fn from(count: SampleCount) -> Self {
+        match count {
+            SampleCount::X1 => vk::SampleCountFlags::TYPE_1,
+            SampleCount::X2 => vk::SampleCountFlags::TYPE_2,
+            SampleCount::X4 => vk::SampleCountFlags::TYPE_4,
+            SampleCount::X8 => vk::SampleCountFlags::TYPE_8,
+            SampleCount::X16 => vk::SampleCountFlags::TYPE_16,
+            SampleCount::X32 => vk::SampleCountFlags::TYPE_32,
+            SampleCount::X64 => vk::SampleCountFlags::TYPE_64,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeviceCapabilities {
+    pub max_per_stage_descriptor_sampled_images: u32,
+    pub max_per_stage_descriptor_storage_images: u32,
+    pub max_per_stage_descriptor_storage_buffers: u32,
+    pub max_per_stage_descriptor_uniform_buffers: u32,
+    // Limits for update-after-bind (bindless) layouts
+    pub max_per_stage_update_after_bind_sampled_images: u32,
+    pub max_per_stage_update_after_bind_storage_buffers: u32,
+    // `None` when the `samplerAnisotropy` feature is not supported
+    pub max_sampler_anisotropy: Option<f32>,
+    // `None` when the `wideLines` feature is not supported, and lines are always 1 pixel wide
+    pub max_line_width: Option<f32>,
+    // Sample counts supported by both color and depth attachments, in increasing order
+    pub sample_counts: Vec<SampleCount>,
+    pub max_push_constants_size: u32,
+    // `GraphicsPipelineDescription::depth_clamp`
+    pub depth_clamp: bool,
+    // Without it, occlusion queries only tell whether any samples passed, not how many
+    pub occlusion_query_precise: bool,
+    pub draw_indirect_count: bool,
+    pub buffer_device_address: bool,
+    // Storage images can be written without a format in the shader, required to generate mip
+    // levels with compute
+    pub storage_image_write_without_format: bool,
+    pub push_descriptor: bool,
+    pub timeline_semaphore: bool,
+    // `VK_EXT_memory_budget`, without it `Device::memory_budget` only accounts for this renderer
+    pub memory_budget: bool,
+}
+
+impl DeviceCapabilities {
+    pub(crate) fn new(
+        instance: &ash::Instance,
+        p_device: vk::PhysicalDevice,
+        properties: &DeviceProperties,
+        push_descriptor: bool,
+        timeline_semaphore: bool,
+        memory_budget: bool,
+    ) -> Self {
+        let mut vulkan_12_features = vk::PhysicalDeviceVulkan12Features::default();
+        let mut features = vk::PhysicalDeviceFeatures2::builder()
+            .push_next(&mut vulkan_12_features)
+            .build();
+        unsafe { instance.get_physical_device_features2(p_device, &mut features) };
+
+        let limits = properties.properties().limits;
+        let descriptor_indexing = properties.descriptor_indexing_properties();
+        let supported_sample_counts =
+            limits.framebuffer_color_sample_counts & limits.framebuffer_depth_sample_counts;
+
+        Self {
+            max_per_stage_descriptor_sampled_images: limits.max_per_stage_descriptor_sampled_images,
+            max_per_stage_descriptor_storage_images: limits.max_per_stage_descriptor_storage_images,
+            max_per_stage_descriptor_storage_buffers: limits
+                .max_per_stage_descriptor_storage_buffers,
+            max_per_stage_descriptor_uniform_buffers: limits
+                .max_per_stage_descriptor_uniform_buffers,
+            max_per_stage_update_after_bind_sampled_images: descriptor_indexing
+                .max_per_stage_descriptor_update_after_bind_sampled_images,
+            max_per_stage_update_after_bind_storage_buffers: descriptor_indexing
+                .max_per_stage_descriptor_update_after_bind_storage_buffers,
+            max_sampler_anisotropy: (features.features.sampler_anisotropy == vk::TRUE)
+                .then_some(limits.max_sampler_anisotropy),
+            max_line_width: (features.features.wide_lines == vk::TRUE)
+                .then_some(limits.line_width_range[1]),
+            sample_counts: SampleCount::ALL
+                .into_iter()
+                .filter(|count| supported_sample_counts.contains((*count).into()))
+                .collect(),
+            max_push_constants_size: limits.max_push_constants_size,
+            depth_clamp: features.features.depth_clamp == vk::TRUE,
+            occlusion_query_precise: features.features.occlusion_query_precise == vk::TRUE,
+            draw_indirect_count: vulkan_12_features.draw_indirect_count == vk::TRUE,
+            buffer_device_address: vulkan_12_features.buffer_device_address == vk::TRUE,
+            storage_image_write_without_format: features
+                .features
+                .shader_storage_image_write_without_format
+                == vk::TRUE,
+            push_descriptor,
+            timeline_semaphore,
+            memory_budget,
+        }
+    }
+
+    pub fn supports_sample_count(&self, count: SampleCount) -> bool {
+        self.sample_counts.contains(&count)
+    }
+
+    // Closest width to `width` lines can be drawn with
+    pub fn supported_line_width(&self, width: f32) -> f32 {
+        width.clamp(1.0, self.max_line_width.unwrap_or(1.0))
+    }
+
+    pub fn max_sample_count(&self) -> SampleCount {
+        self.sample_counts
+            .last()
+            .copied()
+            .unwrap_or(SampleCount::X1)
+    }
+}
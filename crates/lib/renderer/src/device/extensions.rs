@@ -1,18 +1,28 @@
 use super::instance::Instance;
-use ash::extensions::khr::DynamicRendering;
+use ash::extensions::khr::{DynamicRendering, PushDescriptor};
 
 pub struct DeviceExtensions {
     dynamic_rendering: DynamicRendering,
+    push_descriptor: Option<PushDescriptor>,
 }
 
 impl DeviceExtensions {
-    pub fn new(instance: &Instance, device: &ash::Device) -> Self {
+    pub fn new(instance: &Instance, device: &ash::Device, supports_push_descriptor: bool) -> Self {
         let dynamic_rendering = DynamicRendering::new(instance.raw(), device);
+        let push_descriptor =
+            supports_push_descriptor.then(|| PushDescriptor::new(instance.raw(), device));
 
-        Self { dynamic_rendering }
+        Self {
+            dynamic_rendering,
+            push_descriptor,
+        }
     }
 
     pub fn dynamic_rendering(&self) -> &DynamicRendering {
         &self.dynamic_rendering
     }
+
+    pub fn push_descriptor(&self) -> Option<&PushDescriptor> {
+        self.push_descriptor.as_ref()
+    }
 }
@@ -1,5 +1,124 @@
 use ash::vk;
-use std::{borrow::Cow, ffi::CStr};
+use std::{borrow::Cow, ffi::CStr, sync::RwLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DebugMessageSeverity {
+    Verbose,
+    Info,
+    Warning,
+    Error,
+}
+
+impl From<vk::DebugUtilsMessageSeverityFlagsEXT> for DebugMessageSeverity {
+    fn from(flags: vk::DebugUtilsMessageSeverityFlagsEXT) -> Self {
+        if flags.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
+            DebugMessageSeverity::Error
+        } else if flags.contains(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING) {
+            DebugMessageSeverity::Warning
+        } else if flags.contains(vk::DebugUtilsMessageSeverityFlagsEXT::INFO) {
+            DebugMessageSeverity::Info
+        } else {
+            DebugMessageSeverity::Verbose
+        }
+    }
+}
+
+impl DebugMessageSeverity {
+    // All severities at or above `self`
+    fn and_above(self) -> vk::DebugUtilsMessageSeverityFlagsEXT {
+        [
+            (
+                DebugMessageSeverity::Verbose,
+                vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
+            ),
+            (
+                DebugMessageSeverity::Info,
+                vk::DebugUtilsMessageSeverityFlagsEXT::INFO,
+            ),
+            (
+                DebugMessageSeverity::Warning,
+                vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            ),
+            (
+                DebugMessageSeverity::Error,
+                vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            ),
+        ]
+        .into_iter()
+        .filter(|(severity, _)| *severity >= self)
+        .fold(
+            vk::DebugUtilsMessageSeverityFlagsEXT::empty(),
+            |acc, (_, flags)| acc | flags,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugMessageType {
+    General,
+    Validation,
+    Performance,
+}
+
+impl From<vk::DebugUtilsMessageTypeFlagsEXT> for DebugMessageType {
+    fn from(flags: vk::DebugUtilsMessageTypeFlagsEXT) -> Self {
+        if flags.contains(vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION) {
+            DebugMessageType::Validation
+        } else if flags.contains(vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE) {
+            DebugMessageType::Performance
+        } else {
+            DebugMessageType::General
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DebugMessage {
+    pub severity: DebugMessageSeverity,
+    pub ty: DebugMessageType,
+    pub id_name: String,
+    pub id_number: i32,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ValidationConfig {
+    // Enables `VK_LAYER_KHRONOS_validation` and the debug messenger
+    pub enabled: bool,
+    // Messages below this severity are not reported
+    pub severity_filter: DebugMessageSeverity,
+    // Also reports hazards from missing or incorrect barriers, at a noticeable CPU cost
+    pub synchronization: bool,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: cfg!(debug_assertions),
+            severity_filter: DebugMessageSeverity::Warning,
+            synchronization: false,
+        }
+    }
+}
+
+impl ValidationConfig {
+    pub(crate) fn message_severity(&self) -> vk::DebugUtilsMessageSeverityFlagsEXT {
+        self.severity_filter.and_above()
+    }
+}
+
+type DebugCallback = Box<dyn Fn(DebugMessage) + Send + Sync>;
+
+static DEBUG_CALLBACK: RwLock<Option<DebugCallback>> = RwLock::new(None);
+
+// Routes validation messages into `callback` instead of stdout, replacing any previous callback
+pub fn set_debug_callback(callback: impl Fn(DebugMessage) + Send + Sync + 'static) {
+    *DEBUG_CALLBACK.write().unwrap() = Some(Box::new(callback));
+}
+
+pub fn clear_debug_callback() {
+    *DEBUG_CALLBACK.write().unwrap() = None;
+}
 
 pub unsafe extern "system" fn vulkan_debug_callback(
     message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
@@ -22,14 +141,24 @@ pub unsafe extern "system" fn vulkan_debug_callback(
         CStr::from_ptr(callback_data.p_message).to_string_lossy()
     };
 
-    println!(
-        "{:?}:\n{:?} [{} ({})] : {}\n",
-        message_severity,
-        message_type,
-        message_id_name,
-        &message_id_number.to_string(),
-        message,
-    );
+    // Falls back to printing when no callback is set
+    match DEBUG_CALLBACK.read().as_deref() {
+        Ok(Some(callback)) => callback(DebugMessage {
+            severity: message_severity.into(),
+            ty: message_type.into(),
+            id_name: message_id_name.into_owned(),
+            id_number: message_id_number,
+            message: message.into_owned(),
+        }),
+        _ => println!(
+            "{:?}:\n{:?} [{} ({})] : {}\n",
+            message_severity,
+            message_type,
+            message_id_name,
+            &message_id_number.to_string(),
+            message,
+        ),
+    }
 
     vk::FALSE
 }
@@ -1,18 +1,22 @@
 pub mod debug;
 
-use self::debug::vulkan_debug_callback;
+use self::debug::{vulkan_debug_callback, ValidationConfig};
 use anyhow::Result;
 use ash::vk;
 #[cfg(any(target_os = "macos", target_os = "ios"))]
 use ash::vk::{KhrGetPhysicalDeviceProperties2Fn, KhrPortabilityEnumerationFn};
-use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
+use raw_window_handle::RawDisplayHandle;
 use std::{
     ffi::{CStr, CString},
     os::raw::c_char,
 };
 
-fn layer_names() -> Vec<CString> {
-    vec![CString::new("VK_LAYER_KHRONOS_validation").unwrap()]
+fn layer_names(validation: &ValidationConfig) -> Vec<CString> {
+    if validation.enabled {
+        vec![CString::new("VK_LAYER_KHRONOS_validation").unwrap()]
+    } else {
+        Vec::new()
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -44,17 +48,19 @@ pub struct Instance {
     entry: ash::Entry,
     instance: ash::Instance,
     debug_utils: ash::extensions::ext::DebugUtils,
-    debug_utils_messenger: vk::DebugUtilsMessengerEXT,
+    // `None` when validation is disabled
+    debug_utils_messenger: Option<vk::DebugUtilsMessengerEXT>,
 }
 
 impl Instance {
-    pub fn new<W>(window: &W) -> Result<Self>
-    where
-        W: HasRawWindowHandle + HasRawDisplayHandle,
-    {
+    // Surface extensions are only enabled when a display is provided
+    pub fn new(
+        display_handle: Option<RawDisplayHandle>,
+        validation: &ValidationConfig,
+    ) -> Result<Self> {
         let entry = unsafe { ash::Entry::load()? };
 
-        let layers = layer_names();
+        let layers = layer_names(validation);
         let layers = layers
             .iter()
             .map(|raw_name| raw_name.as_ptr())
@@ -65,9 +71,14 @@ impl Instance {
                 .iter()
                 .map(|raw_name| raw_name.as_ptr())
                 .collect::<Vec<*const c_char>>();
-            extensions.extend(
-                ash_window::enumerate_required_extensions(window.raw_display_handle())?.iter(),
-            );
+            if let Some(display_handle) = display_handle {
+                extensions
+                    .extend(ash_window::enumerate_required_extensions(display_handle)?.iter());
+            }
+            // Provided by the validation layer itself
+            if validation.enabled && validation.synchronization {
+                extensions.push(vk::ExtValidationFeaturesFn::name().as_ptr());
+            }
             extensions
         };
 
@@ -77,28 +88,38 @@ impl Instance {
         } else {
             vk::InstanceCreateFlags::default()
         };
-        let instance_ci = vk::InstanceCreateInfo::builder()
+        let enabled_validation_features =
+            [vk::ValidationFeatureEnableEXT::SYNCHRONIZATION_VALIDATION];
+        let mut validation_features = vk::ValidationFeaturesEXT::builder()
+            .enabled_validation_features(&enabled_validation_features);
+        let mut instance_ci = vk::InstanceCreateInfo::builder()
             .application_info(&app_info)
             .enabled_layer_names(&layers)
             .enabled_extension_names(&extensions)
             .flags(create_flags);
+        if validation.enabled && validation.synchronization {
+            instance_ci = instance_ci.push_next(&mut validation_features);
+        }
 
         let instance = unsafe { entry.create_instance(&instance_ci, None)? };
 
         let debug_utils = ash::extensions::ext::DebugUtils::new(&entry, &instance);
 
-        let debug_utils_messenger_ci = vk::DebugUtilsMessengerCreateInfoEXT::builder()
-            .message_severity(
-                vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
-                    | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
-            )
-            .message_type(
-                vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE
-                    | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
-            )
-            .pfn_user_callback(Some(vulkan_debug_callback));
-        let debug_utils_messenger =
-            unsafe { debug_utils.create_debug_utils_messenger(&debug_utils_messenger_ci, None)? };
+        // Debug utils stay enabled for object names and labels even without validation
+        let debug_utils_messenger = if validation.enabled {
+            let debug_utils_messenger_ci = vk::DebugUtilsMessengerCreateInfoEXT::builder()
+                .message_severity(validation.message_severity())
+                .message_type(
+                    vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE
+                        | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
+                )
+                .pfn_user_callback(Some(vulkan_debug_callback));
+            Some(unsafe {
+                debug_utils.create_debug_utils_messenger(&debug_utils_messenger_ci, None)?
+            })
+        } else {
+            None
+        };
 
         Ok(Self {
             entry,
@@ -124,8 +145,10 @@ impl Instance {
 impl Drop for Instance {
     fn drop(&mut self) {
         unsafe {
-            self.debug_utils
-                .destroy_debug_utils_messenger(self.debug_utils_messenger, None);
+            if let Some(debug_utils_messenger) = self.debug_utils_messenger {
+                self.debug_utils
+                    .destroy_debug_utils_messenger(debug_utils_messenger, None);
+            }
             self.instance.destroy_instance(None);
         }
     }
@@ -1,20 +1,31 @@
+mod capabilities;
 mod extensions;
 mod instance;
 mod properties;
 mod surface;
+mod sync;
 
 pub use self::instance::{debug::*, Instance};
+pub use self::{
+    capabilities::{DeviceCapabilities, SampleCount},
+    instance::Extension,
+    surface::SurfaceData,
+    sync::{Fence, Semaphore},
+};
 use self::{extensions::DeviceExtensions, properties::DeviceProperties, surface::Surface};
-pub use self::{instance::Extension, surface::SurfaceData};
 use crate::{
-    command_queue::{CommandList, CommandQueue},
-    profiling::QueryPool,
+    command_queue::{CommandList, CommandQueue, RenderingFormats},
+    profiling::{DrawCounters, QueryPool},
     resources::{
-        bind_group::{BindGroupBindInfo, BindGroupPool, BindGroupWriteData},
+        bind_group::{BindGroupBindInfo, BindGroupLayout, BindGroupPool},
         buffer::{Buffer, BufferDescription, BufferUsage},
-        image::{Image, ImageDescription, ImageError},
+        image::{Format, Image, ImageDescription, ImageDimension, ImageError, Layout},
         manager::ResourceManager,
-        pipeline::graphics::{GraphicsPipeline, GraphicsPipelineDescription},
+        memory::{AllocationKind, HeapBudget, Memory, MemoryAllocator, MemoryStats},
+        pipeline::{
+            compute::{ComputePipeline, ComputePipelineDescription},
+            graphics::{GraphicsPipeline, GraphicsPipelineDescription, GraphicsPipelineInfo},
+        },
         sampler::{Sampler, SamplerDescription},
         shader::{Shader, ShaderDesc},
     },
@@ -22,10 +33,17 @@ use crate::{
 use anyhow::Result;
 #[cfg(any(target_os = "macos", target_os = "ios"))]
 use ash::vk::KhrPortabilitySubsetFn;
-use ash::{extensions::khr::DynamicRendering, vk};
+use ash::{
+    extensions::khr::{DynamicRendering, PushDescriptor},
+    vk,
+};
 use math::{rect::Rect2D, size::Size2D};
 use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
 use resource_manager::ResourceId;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Mutex,
+};
 use thiserror::Error;
 use util::size_of_slice;
 
@@ -42,34 +60,97 @@ pub enum DeviceError {
     ResourceManagerError(#[from] crate::resources::manager::ResourceManagerError),
     #[error("Resource not in cache")]
     ResourceNotInCache,
+    #[error("VK_KHR_push_descriptor is not supported by this device")]
+    PushDescriptorNotSupported,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeviceDescription {
+    pub validation: ValidationConfig,
+    // Number of swapchain images to request, clamped to what the surface supports. Also limits
+    // the number of frames in flight, e.g. 2 for double buffering. One more than the surface
+    // minimum by default
+    pub preferred_image_count: Option<u32>,
 }
 
 pub struct Device {
     p_device: vk::PhysicalDevice,
     properties: DeviceProperties,
+    capabilities: DeviceCapabilities,
     device: ash::Device,
     queue_family_index: u32,
     present_queue: vk::Queue,
-    surface: Surface,
+    // Same as the graphics queue when the device has no dedicated compute queue family
+    compute_queue_family_index: u32,
+    compute_queue: vk::Queue,
+    // `None` for headless devices, which render into an offscreen image instead of a swapchain
+    surface: Option<Surface>,
     instance: Instance,
     pub(crate) pipeline_cache: vk::PipelineCache,
-    pub(crate) bind_group_pool: BindGroupPool,
+    // New pools are appended when the existing ones run out of space
+    bind_group_pools: Mutex<Vec<BindGroupPool>>,
+    memory_allocator: MemoryAllocator,
     pub(crate) surface_data: SurfaceData,
     extensions: DeviceExtensions,
     image_acquired_semaphores: [vk::Semaphore; MAX_FRAMES_IN_FLIGHT],
     render_complete_semaphores: [vk::Semaphore; MAX_FRAMES_IN_FLIGHT],
+    compute_complete_semaphores: [vk::Semaphore; MAX_FRAMES_IN_FLIGHT],
+    // Set when a compute submission has to be waited on by the next graphics submission
+    compute_wait_pending: AtomicBool,
+    // Semaphores from `CommandQueue::submit` calls the next frame submission has to wait on
+    external_waits: Mutex<Vec<vk::Semaphore>>,
     command_buffer_executed_fences: [vk::Fence; MAX_FRAMES_IN_FLIGHT],
+    // Signaled with the submit value of each frame, when VK_KHR_timeline_semaphore is supported
+    timeline_semaphore: Option<vk::Semaphore>,
+    // Value signaled by the most recent submission, submissions are numbered starting at 1
+    last_submit_value: AtomicU64,
+    // Submit value of the last frame recorded for each frame in flight
+    frame_submit_values: [u64; MAX_FRAMES_IN_FLIGHT],
+    // Without a timeline semaphore, completed submissions are tracked through the frame fences
+    fence_completed_value: u64,
     frame_index: usize,
+    // At most `MAX_FRAMES_IN_FLIGHT`, only that many of the per-frame objects are used
+    frames_in_flight: usize,
+    preferred_image_count: Option<u32>,
+    pub(crate) draw_counters: DrawCounters,
+    pub(crate) rendering_formats: Mutex<Option<RenderingFormats>>,
 }
 
 impl Device {
-    pub fn new<W>(window: &W, window_width: u32, window_height: u32) -> Result<Self>
+    pub fn new<W>(
+        window: &W,
+        window_width: u32,
+        window_height: u32,
+        desc: DeviceDescription,
+    ) -> Result<Self>
     where
         W: HasRawWindowHandle + HasRawDisplayHandle,
     {
-        let instance = Instance::new(window)?;
+        let instance = Instance::new(Some(window.raw_display_handle()), &desc.validation)?;
         let surface = Surface::new(window, &instance)?;
+        Self::create(
+            instance,
+            Some(surface),
+            window_width,
+            window_height,
+            desc.preferred_image_count,
+        )
+    }
+
+    // Creates a device without a window or surface, frames are rendered into an offscreen image
+    // of the given size that can be read back with `Swapchain::read_headless_image`
+    pub fn new_headless(width: u32, height: u32, desc: DeviceDescription) -> Result<Self> {
+        let instance = Instance::new(None, &desc.validation)?;
+        Self::create(instance, None, width, height, desc.preferred_image_count)
+    }
 
+    fn create(
+        instance: Instance,
+        surface: Option<Surface>,
+        window_width: u32,
+        window_height: u32,
+        preferred_image_count: Option<u32>,
+    ) -> Result<Self> {
         let p_devices = unsafe { instance.raw().enumerate_physical_devices() }?;
         let supported_device_data = p_devices
             .into_iter()
@@ -84,14 +165,16 @@ impl Device {
                 .filter_map(|(index, info)| {
                     let supports_graphic_and_surface =
                         info.queue_flags.contains(vk::QueueFlags::GRAPHICS)
-                            && unsafe {
-                                surface.surface_loader.get_physical_device_surface_support(
-                                    p_device,
-                                    index as u32,
-                                    surface.surface,
-                                )
-                            }
-                            .unwrap_or(false);
+                            && surface.as_ref().map_or(true, |surface| {
+                                unsafe {
+                                    surface.surface_loader.get_physical_device_surface_support(
+                                        p_device,
+                                        index as u32,
+                                        surface.surface,
+                                    )
+                                }
+                                .unwrap_or(false)
+                            });
                     if supports_graphic_and_surface {
                         let properties =
                             unsafe { instance.raw().get_physical_device_properties(p_device) };
@@ -116,8 +199,57 @@ impl Device {
 
         let properties = DeviceProperties::new(instance.raw(), p_device, p_device_properties);
 
-        let device_extension_names = [
-            ash::extensions::khr::Swapchain::name(),
+        let compute_queue_family_index = unsafe {
+            instance
+                .raw()
+                .get_physical_device_queue_family_properties(p_device)
+        }
+        .iter()
+        .enumerate()
+        .find(|(_, info)| {
+            info.queue_flags.contains(vk::QueueFlags::COMPUTE)
+                && !info.queue_flags.contains(vk::QueueFlags::GRAPHICS)
+        })
+        .map_or(queue_family_index, |(index, _)| index as u32);
+
+        let supports_timeline_semaphore = {
+            let mut timeline_semaphore = vk::PhysicalDeviceTimelineSemaphoreFeatures::default();
+            let mut features = vk::PhysicalDeviceFeatures2::builder()
+                .push_next(&mut timeline_semaphore)
+                .build();
+            unsafe {
+                instance
+                    .raw()
+                    .get_physical_device_features2(p_device, &mut features)
+            };
+            timeline_semaphore.timeline_semaphore == vk::TRUE
+        };
+
+        let available_extensions = unsafe {
+            instance
+                .raw()
+                .enumerate_device_extension_properties(p_device)
+        }?;
+        let supports_extension = |extension_name: &std::ffi::CStr| {
+            available_extensions.iter().any(|extension| {
+                let name = unsafe { std::ffi::CStr::from_ptr(extension.extension_name.as_ptr()) };
+                name == extension_name
+            })
+        };
+        let supports_push_descriptor =
+            supports_extension(ash::extensions::khr::PushDescriptor::name());
+        let supports_memory_budget = supports_extension(vk::ExtMemoryBudgetFn::name());
+
+        let capabilities = DeviceCapabilities::new(
+            instance.raw(),
+            p_device,
+            &properties,
+            supports_push_descriptor,
+            supports_timeline_semaphore,
+            supports_memory_budget,
+        );
+
+        let mut device_extension_names = vec![
             ash::extensions::khr::DynamicRendering::name(),
             vk::ExtDescriptorIndexingFn::name(),
             unsafe {
@@ -129,6 +261,15 @@ impl Device {
             #[cfg(any(target_os = "macos", target_os = "ios"))]
             KhrPortabilitySubsetFn::name(),
         ];
+        if surface.is_some() {
+            device_extension_names.push(ash::extensions::khr::Swapchain::name());
+        }
+        if supports_push_descriptor {
+            device_extension_names.push(ash::extensions::khr::PushDescriptor::name());
+        }
+        if supports_memory_budget {
+            device_extension_names.push(vk::ExtMemoryBudgetFn::name());
+        }
         let device_extension_names_raw: Vec<*const i8> = device_extension_names
             .iter()
             .map(|raw_name| raw_name.as_ptr())
@@ -140,23 +281,51 @@ impl Device {
         let mut descriptor_indexing = vk::PhysicalDeviceDescriptorIndexingFeaturesEXT::builder()
             .descriptor_binding_partially_bound(true)
             .descriptor_binding_sampled_image_update_after_bind(true)
+            .descriptor_binding_update_unused_while_pending(true)
             .descriptor_binding_variable_descriptor_count(true)
             .runtime_descriptor_array(true)
             .build();
         let mut dynamic_rendering = vk::PhysicalDeviceDynamicRenderingFeatures::builder()
             .dynamic_rendering(true)
             .build();
+        let mut timeline_semaphore = vk::PhysicalDeviceTimelineSemaphoreFeatures::builder()
+            .timeline_semaphore(supports_timeline_semaphore)
+            .build();
+        // Core since Vulkan 1.2, promoted from VK_KHR_buffer_device_address
+        let mut buffer_device_address = vk::PhysicalDeviceBufferDeviceAddressFeatures::builder()
+            .buffer_device_address(capabilities.buffer_device_address)
+            .build();
         let mut features = vk::PhysicalDeviceFeatures2::builder()
+            .features(vk::PhysicalDeviceFeatures {
+                sampler_anisotropy: capabilities.max_sampler_anisotropy.is_some().into(),
+                depth_clamp: capabilities.depth_clamp.into(),
+                wide_lines: capabilities.max_line_width.is_some().into(),
+                occlusion_query_precise: capabilities.occlusion_query_precise.into(),
+                shader_storage_image_write_without_format: capabilities
+                    .storage_image_write_without_format
+                    .into(),
+                ..Default::default()
+            })
             .push_next(&mut scalar_block)
             .push_next(&mut descriptor_indexing)
             .push_next(&mut dynamic_rendering)
+            .push_next(&mut timeline_semaphore)
+            .push_next(&mut buffer_device_address)
             .build();
 
         let priorities = [1.0];
-        let queue_info = [vk::DeviceQueueCreateInfo::builder()
+        let mut queue_info = vec![vk::DeviceQueueCreateInfo::builder()
             .queue_family_index(queue_family_index)
             .queue_priorities(&priorities)
             .build()];
+        if compute_queue_family_index != queue_family_index {
+            queue_info.push(
+                vk::DeviceQueueCreateInfo::builder()
+                    .queue_family_index(compute_queue_family_index)
+                    .queue_priorities(&priorities)
+                    .build(),
+            );
+        }
 
         let device_create_info = vk::DeviceCreateInfo::builder()
             .push_next(&mut features)
@@ -199,6 +368,19 @@ impl Device {
             present_queue,
             "Present Queue",
         );
+        let compute_queue = if compute_queue_family_index != queue_family_index {
+            let compute_queue = unsafe { device.get_device_queue(compute_queue_family_index, 0) };
+            instance::debug::set_object_name(
+                instance.debug(),
+                device.handle(),
+                vk::ObjectType::QUEUE,
+                compute_queue,
+                "Compute Queue",
+            );
+            compute_queue
+        } else {
+            present_queue
+        };
 
         let ci = vk::PipelineCacheCreateInfo::builder().build();
         let pipeline_cache = unsafe { device.create_pipeline_cache(&ci, None)? };
@@ -209,11 +391,25 @@ impl Device {
             pipeline_cache,
             "Pipeline Cache",
         );
-        let bind_group_pool = BindGroupPool::new(&instance, &device)?;
-
-        let surface_data = surface.get_data(p_device, window_width, window_height, false)?;
+        let bind_group_pool = BindGroupPool::new(&instance, &device, MAX_BINDLESS_RESOURCES)?;
+
+        let surface_data = match &surface {
+            Some(surface) => surface.get_data(
+                p_device,
+                window_width,
+                window_height,
+                false,
+                preferred_image_count,
+            )?,
+            None => SurfaceData::headless(window_width, window_height),
+        };
+        // Recording further ahead than there are images to render into only adds latency
+        let frames_in_flight = match &surface {
+            Some(_) => (surface_data.desired_image_count as usize).min(MAX_FRAMES_IN_FLIGHT),
+            None => MAX_FRAMES_IN_FLIGHT,
+        };
 
-        let extensions = DeviceExtensions::new(&instance, &device);
+        let extensions = DeviceExtensions::new(&instance, &device, supports_push_descriptor);
 
         let semaphore_create_info = vk::SemaphoreCreateInfo::default();
 
@@ -249,6 +445,22 @@ impl Device {
             semaphores
         };
 
+        let compute_complete_semaphores = {
+            let mut semaphores = [vk::Semaphore::null(); MAX_FRAMES_IN_FLIGHT];
+            for idx in 0..MAX_FRAMES_IN_FLIGHT {
+                let semaphore = unsafe { device.create_semaphore(&semaphore_create_info, None) }?;
+                instance::debug::set_object_name(
+                    instance.debug(),
+                    device.handle(),
+                    vk::ObjectType::SEMAPHORE,
+                    semaphore,
+                    &format!("Compute Complete Semaphore {idx}"),
+                );
+                semaphores[idx] = semaphore;
+            }
+            semaphores
+        };
+
         let fence_create_info = vk::FenceCreateInfo {
             flags: vk::FenceCreateFlags::SIGNALED,
             ..Default::default()
@@ -270,33 +482,118 @@ impl Device {
             fences
         };
 
+        let timeline_semaphore = if supports_timeline_semaphore {
+            let mut type_create_info = vk::SemaphoreTypeCreateInfo::builder()
+                .semaphore_type(vk::SemaphoreType::TIMELINE)
+                .initial_value(0)
+                .build();
+            let create_info = vk::SemaphoreCreateInfo::builder()
+                .push_next(&mut type_create_info)
+                .build();
+            let semaphore = unsafe { device.create_semaphore(&create_info, None) }?;
+            instance::debug::set_object_name(
+                instance.debug(),
+                device.handle(),
+                vk::ObjectType::SEMAPHORE,
+                semaphore,
+                "Timeline Semaphore",
+            );
+            Some(semaphore)
+        } else {
+            None
+        };
+
         Ok(Self {
             instance,
             surface,
             surface_data,
             p_device,
             properties,
+            capabilities,
             device,
             queue_family_index,
             present_queue,
+            compute_queue_family_index,
+            compute_queue,
             pipeline_cache,
-            bind_group_pool,
+            bind_group_pools: Mutex::new(vec![bind_group_pool]),
+            memory_allocator: Default::default(),
             extensions,
             render_complete_semaphores,
             image_acquired_semaphores,
+            compute_complete_semaphores,
+            compute_wait_pending: AtomicBool::new(false),
+            external_waits: Default::default(),
             command_buffer_executed_fences,
+            timeline_semaphore,
+            last_submit_value: AtomicU64::new(0),
+            frame_submit_values: [0; MAX_FRAMES_IN_FLIGHT],
+            fence_completed_value: 0,
             frame_index: 0,
+            frames_in_flight,
+            preferred_image_count,
+            draw_counters: Default::default(),
+            rendering_formats: Default::default(),
         })
     }
 
     pub fn new_frame(&mut self) -> Result<()> {
-        let render_complete_fence = self.command_buffer_executed_fence();
-        unsafe {
-            self.device
-                .wait_for_fences(&[render_complete_fence], true, std::u64::MAX)?;
-            self.device.reset_fences(&[render_complete_fence])?;
+        let frame_submit_value = self.frame_submit_values[self.current_frame_in_flight()];
+        if self.timeline_semaphore.is_some() {
+            self.wait_for_timeline(frame_submit_value)?;
+        } else {
+            let render_complete_fence = self.command_buffer_executed_fence();
+            unsafe {
+                self.device
+                    .wait_for_fences(&[render_complete_fence], true, std::u64::MAX)?;
+            }
+            self.fence_completed_value = self.fence_completed_value.max(frame_submit_value);
+        }
+
+        Ok(())
+    }
+
+    pub fn supports_timeline_semaphore(&self) -> bool {
+        self.timeline_semaphore.is_some()
+    }
+
+    pub(crate) fn timeline_semaphore(&self) -> Option<vk::Semaphore> {
+        self.timeline_semaphore
+    }
+
+    // Reserves the value signaled by the next frame submission
+    pub(crate) fn next_submit_value(&self) -> u64 {
+        self.last_submit_value.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    // Value that will be signaled once the work currently being recorded completes
+    pub fn pending_submit_value(&self) -> u64 {
+        self.last_submit_value.load(Ordering::Relaxed) + 1
+    }
+
+    // Highest submit value whose work is known to have completed on the GPU
+    pub fn completed_submit_value(&self) -> Result<u64> {
+        match self.timeline_semaphore {
+            Some(semaphore) => Ok(unsafe { self.device.get_semaphore_counter_value(semaphore) }?),
+            None => Ok(self.fence_completed_value),
         }
+    }
 
+    pub fn wait_for_timeline(&self, value: u64) -> Result<()> {
+        match self.timeline_semaphore {
+            Some(semaphore) => {
+                let wait_info = vk::SemaphoreWaitInfo::builder()
+                    .semaphores(std::slice::from_ref(&semaphore))
+                    .values(std::slice::from_ref(&value))
+                    .build();
+                unsafe { self.device.wait_semaphores(&wait_info, std::u64::MAX) }?;
+            }
+            None => {
+                if value > self.fence_completed_value {
+                    self.wait_idle()?;
+                }
+            }
+        }
         Ok(())
     }
 
@@ -331,8 +628,12 @@ impl Device {
         &self.instance
     }
 
-    pub fn surface(&self) -> &Surface {
-        &self.surface
+    pub fn surface(&self) -> Option<&Surface> {
+        self.surface.as_ref()
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
     }
 
     pub fn raw(&self) -> &ash::Device {
@@ -351,6 +652,70 @@ impl Device {
         self.properties.memory_properties()
     }
 
+    // What the GPU supports, to clamp requests for MSAA, anisotropy or bindless counts against
+    pub fn capabilities(&self) -> &DeviceCapabilities {
+        &self.capabilities
+    }
+
+    // Best depth format that can be both rendered to and sampled, preferring 32-bit depth.
+    // Vulkan requires D16_UNORM and one of the stencil formats to be usable as attachments, so
+    // the last candidate is the fallback
+    pub fn preferred_depth_format(&self, want_stencil: bool) -> Format {
+        let candidates: &[Format] = if want_stencil {
+            &[Format::D32_SFLOAT_S8_UINT, Format::D24_UNORM_S8_UINT]
+        } else {
+            &[
+                Format::D32_SFLOAT,
+                Format::D32_SFLOAT_S8_UINT,
+                Format::D24_UNORM_S8_UINT,
+                Format::D16_UNORM,
+            ]
+        };
+        let required_features = vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT
+            | vk::FormatFeatureFlags::SAMPLED_IMAGE;
+        candidates
+            .iter()
+            .copied()
+            .find(|format| self.format_features(*format).contains(required_features))
+            .unwrap_or(candidates[candidates.len() - 1])
+    }
+
+    fn format_features(&self, format: Format) -> vk::FormatFeatureFlags {
+        unsafe {
+            self.instance
+                .raw()
+                .get_physical_device_format_properties(self.p_device, format.into())
+        }
+        .optimal_tiling_features
+    }
+
+    // Whether `CommandList::generate_mipmaps` can blit between mip levels of the format
+    pub fn supports_linear_blit(&self, format: Format) -> bool {
+        self.format_features(format).contains(
+            vk::FormatFeatureFlags::BLIT_SRC
+                | vk::FormatFeatureFlags::BLIT_DST
+                | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR,
+        )
+    }
+
+    // Whether the mip levels of an image have to be generated with compute, by
+    // `Renderer::generate_mipmaps`, because its format can't be blitted. Only supported for
+    // single layer 2D images in formats that can be used as storage images
+    pub fn needs_compute_mipmaps(&self, desc: &ImageDescription) -> bool {
+        desc.mip_levels > 1
+            && desc.dimension == ImageDimension::D2
+            && desc.array_layers() == 1
+            && self.capabilities.storage_image_write_without_format
+            && !self.supports_linear_blit(desc.format)
+            && self.format_features(desc.format).contains(
+                vk::FormatFeatureFlags::STORAGE_IMAGE | vk::FormatFeatureFlags::SAMPLED_IMAGE,
+            )
+    }
+
+    pub fn min_uniform_buffer_offset_alignment(&self) -> u64 {
+        self.properties().limits.min_uniform_buffer_offset_alignment
+    }
+
     pub fn descriptor_indexing_properties(&self) -> vk::PhysicalDeviceDescriptorIndexingProperties {
         self.properties.descriptor_indexing_properties()
     }
@@ -363,10 +728,36 @@ impl Device {
         self.present_queue
     }
 
+    pub fn compute_queue_family_index(&self) -> u32 {
+        self.compute_queue_family_index
+    }
+
+    pub fn compute_queue(&self) -> vk::Queue {
+        self.compute_queue
+    }
+
+    pub fn has_async_compute(&self) -> bool {
+        self.compute_queue_family_index != self.queue_family_index
+    }
+
+    // Queue families that need concurrent access to resources shared between graphics and compute
+    pub(crate) fn shared_queue_family_indices(&self) -> Option<[u32; 2]> {
+        self.has_async_compute()
+            .then_some([self.queue_family_index, self.compute_queue_family_index])
+    }
+
     pub fn dynamic_rendering(&self) -> &DynamicRendering {
         self.extensions.dynamic_rendering()
     }
 
+    pub fn push_descriptor(&self) -> Option<&PushDescriptor> {
+        self.extensions.push_descriptor()
+    }
+
+    pub fn supports_push_descriptor(&self) -> bool {
+        self.extensions.push_descriptor().is_some()
+    }
+
     pub fn get_query_pool_results_u32(
         &self,
         query_pool: &QueryPool,
@@ -406,10 +797,73 @@ impl Device {
         Ok(results)
     }
 
+    pub(crate) fn allocate_memory(
+        &self,
+        req: vk::MemoryRequirements,
+        memory_type_index: u32,
+        kind: AllocationKind,
+    ) -> Result<Memory> {
+        self.memory_allocator
+            .allocate(self, req, memory_type_index, kind)
+    }
+
+    pub(crate) fn free_memory(&self, memory: &Memory) {
+        self.memory_allocator.free(&self.device, memory);
+    }
+
+    pub fn memory_stats(&self) -> MemoryStats {
+        self.memory_allocator.stats()
+    }
+
+    // Usage and budget of every memory heap, including other processes when `VK_EXT_memory_budget`
+    // is supported. Otherwise usage only counts this renderer's blocks, against the heap size
+    pub fn memory_budget(&self) -> Vec<HeapBudget> {
+        let mut budget_properties = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+        let memory_properties = if self.capabilities.memory_budget {
+            let mut memory_properties = vk::PhysicalDeviceMemoryProperties2::builder()
+                .push_next(&mut budget_properties)
+                .build();
+            unsafe {
+                self.instance
+                    .raw()
+                    .get_physical_device_memory_properties2(self.p_device, &mut memory_properties)
+            };
+            memory_properties.memory_properties
+        } else {
+            self.memopry_properties()
+        };
+        let reserved_bytes = self
+            .memory_allocator
+            .reserved_bytes_per_heap(&memory_properties);
+
+        memory_properties.memory_heaps[..memory_properties.memory_heap_count as usize]
+            .iter()
+            .enumerate()
+            .map(|(heap, properties)| {
+                let (used, budget) = if self.capabilities.memory_budget {
+                    (
+                        budget_properties.heap_usage[heap],
+                        budget_properties.heap_budget[heap],
+                    )
+                } else {
+                    (reserved_bytes[heap], properties.size)
+                };
+                HeapBudget {
+                    heap: heap as u32,
+                    device_local: properties.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL),
+                    used,
+                    budget,
+                }
+            })
+            .collect()
+    }
+
+    #[track_caller]
     pub fn create_buffer(&self, size: u64, desc: BufferDescription) -> Result<Buffer> {
         Buffer::create(self, size, desc)
     }
 
+    #[track_caller]
     pub fn create_buffer_with_data<T: Copy>(
         &self,
         data: &[T],
@@ -422,10 +876,12 @@ impl Device {
         Ok(buffer)
     }
 
+    #[track_caller]
     pub fn create_image(&self, size: Size2D<u32>, desc: ImageDescription) -> Result<Image> {
         Image::create(self, size, desc)
     }
 
+    #[track_caller]
     pub fn create_image_with_data(
         &self,
         size: Size2D<u32>,
@@ -433,6 +889,33 @@ impl Device {
         cmd_list: &CommandList,
         desc: ImageDescription,
     ) -> Result<(Image, Buffer)> {
+        if desc.mip_levels > 1 && !self.supports_linear_blit(desc.format) {
+            return Err(ImageError::LinearBlitNotSupported(desc.format).into());
+        }
+        let (image, image_buffer) = self.upload_image_data(size, bytes, cmd_list, desc)?;
+        cmd_list.generate_mipmaps(self, &image);
+
+        Ok((image, image_buffer))
+    }
+
+    // Creates the image and records the copy of `bytes` into its first mip level, leaving every
+    // level in `Layout::TransferDst` for the mip levels to be generated
+    #[track_caller]
+    pub(crate) fn upload_image_data(
+        &self,
+        size: Size2D<u32>,
+        bytes: &[u8],
+        cmd_list: &CommandList,
+        desc: ImageDescription,
+    ) -> Result<(Image, Buffer)> {
+        let expected = desc.data_size(size);
+        if bytes.len() != expected {
+            return Err(ImageError::DataSizeMismatch {
+                expected,
+                actual: bytes.len(),
+            }
+            .into());
+        }
         let image = Image::create(self, size, desc)?;
 
         let image_buffer = self.create_buffer_with_data(
@@ -452,18 +935,11 @@ impl Device {
             Default::default(),
         );
         cmd_list.copy_buffer_to_image(self, &image_buffer, &image);
-        cmd_list.set_image_memory_barrier(
-            self,
-            image.raw,
-            vk::ImageAspectFlags::COLOR,
-            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-            Default::default(),
-        );
 
         Ok((image, image_buffer))
     }
 
+    #[track_caller]
     pub fn create_image_with_data_immediate(
         &self,
         size: Size2D<u32>,
@@ -485,6 +961,173 @@ impl Device {
         Ok(image)
     }
 
+    // Uploads every mip level from `levels` instead of generating them, which is required for
+    // compressed formats since they can't be blitted. `desc.mip_levels` must match `levels`
+    #[track_caller]
+    pub fn create_image_with_mip_data(
+        &self,
+        size: Size2D<u32>,
+        levels: &[&[u8]],
+        cmd_list: &CommandList,
+        desc: ImageDescription,
+    ) -> Result<(Image, Buffer)> {
+        if levels.len() != desc.mip_levels as usize {
+            return Err(ImageError::MipLevelCountMismatch {
+                expected: desc.mip_levels as usize,
+                actual: levels.len(),
+            }
+            .into());
+        }
+        // Copy offsets must be a multiple of the block size, and of 4
+        let alignment = desc.format.bytes_per_block().max(4) as u64;
+        let mut offsets = Vec::with_capacity(levels.len());
+        let mut total_size = 0;
+        for (level, bytes) in levels.iter().enumerate() {
+            let expected = desc.mip_data_size(size, level as u32);
+            if bytes.len() != expected {
+                return Err(ImageError::DataSizeMismatch {
+                    expected,
+                    actual: bytes.len(),
+                }
+                .into());
+            }
+            total_size = total_size.next_multiple_of(alignment);
+            offsets.push(total_size);
+            total_size += bytes.len() as u64;
+        }
+
+        let staging_buffer = self.create_buffer(
+            total_size.max(1),
+            BufferDescription {
+                usage: BufferUsage::TRANSFER_SRC,
+                ..Default::default()
+            },
+        )?;
+        let image = Image::create(self, size, desc)?;
+        cmd_list.transition_image(self, &image, Layout::Undefined, Layout::TransferDst);
+        for (level, (bytes, offset)) in levels.iter().zip(offsets).enumerate() {
+            staging_buffer.mem_copy(offset, bytes)?;
+            cmd_list.copy_buffer_to_image_mip(self, &staging_buffer, offset, &image, level as u32);
+        }
+        cmd_list.transition_image(self, &image, Layout::TransferDst, Layout::ShaderReadOnly);
+
+        Ok((image, staging_buffer))
+    }
+
+    #[track_caller]
+    pub fn create_image_with_mip_data_immediate(
+        &self,
+        size: Size2D<u32>,
+        levels: &[&[u8]],
+        cmd_queue: &CommandQueue,
+        desc: ImageDescription,
+    ) -> Result<Image> {
+        let instant_command_list = cmd_queue.get_immediate_command_list(self)?;
+
+        let (image, buffer) =
+            self.create_image_with_mip_data(size, levels, &instant_command_list, desc)?;
+
+        instant_command_list.end(self)?;
+        instant_command_list.immediate_submit(self, self.present_queue)?;
+        instant_command_list.reset(self)?;
+
+        buffer.destroy(self);
+
+        Ok(image)
+    }
+
+    // Stages every image in a single buffer and records all of their copies into `cmd_list`
+    #[track_caller]
+    pub fn create_images_with_data(
+        &self,
+        images: &[(Size2D<u32>, &[u8])],
+        cmd_list: &CommandList,
+        desc: ImageDescription,
+    ) -> Result<(Vec<Image>, Buffer)> {
+        let images = images
+            .iter()
+            .map(|(size, bytes)| (*size, *bytes, desc))
+            .collect::<Vec<_>>();
+        self.create_images_with_descriptions(&images, cmd_list)
+    }
+
+    // Same as `create_images_with_data`, with a description per image, e.g. to mix sRGB and
+    // linear formats in a single upload
+    #[track_caller]
+    pub fn create_images_with_descriptions(
+        &self,
+        images: &[(Size2D<u32>, &[u8], ImageDescription)],
+        cmd_list: &CommandList,
+    ) -> Result<(Vec<Image>, Buffer)> {
+        let mut offsets = Vec::with_capacity(images.len());
+        let mut total_size = 0;
+        for (size, bytes, desc) in images {
+            if desc.mip_levels > 1 && !self.supports_linear_blit(desc.format) {
+                return Err(ImageError::LinearBlitNotSupported(desc.format).into());
+            }
+            let expected = desc.data_size(*size);
+            if bytes.len() != expected {
+                return Err(ImageError::DataSizeMismatch {
+                    expected,
+                    actual: bytes.len(),
+                }
+                .into());
+            }
+            // Copy offsets must be a multiple of the texel size, and of 4
+            let alignment = desc.format.bytes_per_block().max(4) as u64;
+            total_size = total_size.next_multiple_of(alignment);
+            offsets.push(total_size);
+            total_size += bytes.len() as u64;
+        }
+
+        let staging_buffer = self.create_buffer(
+            total_size.max(1),
+            BufferDescription {
+                usage: BufferUsage::TRANSFER_SRC,
+                ..Default::default()
+            },
+        )?;
+        let mut created = Vec::with_capacity(images.len());
+        for ((size, bytes, desc), offset) in images.iter().zip(offsets) {
+            staging_buffer.mem_copy(offset, bytes)?;
+            let image = Image::create(self, *size, *desc)?;
+            cmd_list.set_image_memory_barrier(
+                self,
+                image.raw,
+                vk::ImageAspectFlags::COLOR,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                Default::default(),
+            );
+            cmd_list.copy_buffer_to_image_offset(self, &staging_buffer, offset, &image);
+            cmd_list.generate_mipmaps(self, &image);
+            created.push(image);
+        }
+
+        Ok((created, staging_buffer))
+    }
+
+    // Uploads every image with a single submission, instead of one per image
+    #[track_caller]
+    pub fn create_images_with_data_batched(
+        &self,
+        images: &[(Size2D<u32>, &[u8])],
+        cmd_queue: &CommandQueue,
+        desc: ImageDescription,
+    ) -> Result<Vec<Image>> {
+        let instant_command_list = cmd_queue.get_immediate_command_list(self)?;
+
+        let (images, buffer) = self.create_images_with_data(images, &instant_command_list, desc)?;
+
+        instant_command_list.end(self)?;
+        instant_command_list.immediate_submit(self, self.present_queue)?;
+        instant_command_list.reset(self)?;
+
+        buffer.destroy(self);
+
+        Ok(images)
+    }
+
     pub fn create_shader(&self, bytes: &[u8], desc: ShaderDesc) -> Result<Shader> {
         Shader::create(self, bytes, desc)
     }
@@ -501,7 +1144,7 @@ impl Device {
             .map(|old| Shader::create(self, bytes, old.desc).unwrap());
 
         if let Some(new) = new {
-            manager.replace_shader(id, new, self.current_frame_in_flight());
+            manager.replace_shader(id, new, self.pending_submit_value());
             Ok(())
         } else {
             Err(DeviceError::ResourceNotInCache)
@@ -514,7 +1157,27 @@ impl Device {
         fragment_shader: Option<&Shader>,
         desc: GraphicsPipelineDescription,
     ) -> Result<GraphicsPipeline> {
-        GraphicsPipeline::create(self, vertex_shader, fragment_shader, desc)
+        GraphicsPipeline::create(self, vertex_shader, fragment_shader, desc, None)
+    }
+
+    // `base` must have been created with `allow_derivatives`
+    pub fn create_derived_graphics_pipeline(
+        &self,
+        base: &GraphicsPipeline,
+        vertex_shader: &Shader,
+        fragment_shader: Option<&Shader>,
+        desc: GraphicsPipelineDescription,
+    ) -> Result<GraphicsPipeline> {
+        GraphicsPipeline::create(self, vertex_shader, fragment_shader, desc, Some(base))
+    }
+
+    // Creates every pipeline in a single call, the first one is the base the rest derive from.
+    // Pipelines are returned in the same order as `infos`
+    pub fn create_graphics_pipelines(
+        &self,
+        infos: &[GraphicsPipelineInfo<'_>],
+    ) -> Result<Vec<GraphicsPipeline>> {
+        GraphicsPipeline::create_batch(self, infos)
     }
 
     pub fn recreate_graphics_pipeline(
@@ -533,7 +1196,29 @@ impl Device {
         Ok(())
     }
 
+    pub fn create_compute_pipeline(
+        &self,
+        shader: &Shader,
+        desc: ComputePipelineDescription,
+    ) -> Result<ComputePipeline> {
+        ComputePipeline::create(self, shader, desc)
+    }
+
+    pub fn recreate_compute_pipeline(
+        &self,
+        manager: &mut ResourceManager,
+        pipeline_handle: ResourceId<ComputePipeline>,
+        shader_handle: ResourceId<Shader>,
+    ) -> Result<()> {
+        manager.recreate_compute_pipeline(self, pipeline_handle, shader_handle)?;
+        Ok(())
+    }
+
     pub fn create_sampler(&self, desc: SamplerDescription) -> Result<Sampler> {
+        let max_anisotropy = desc
+            .max_anisotropy
+            .zip(self.capabilities.max_sampler_anisotropy)
+            .map(|(requested, max)| requested.clamp(1.0, max));
         let sampler_info = vk::SamplerCreateInfo {
             mag_filter: desc.filter.into(),
             min_filter: desc.filter.into(),
@@ -541,7 +1226,15 @@ impl Device {
             address_mode_u: desc.address_mode.into(),
             address_mode_v: desc.address_mode.into(),
             address_mode_w: desc.address_mode.into(),
-            max_anisotropy: 1.0,
+            mip_lod_bias: desc.mip_lod_bias.clamp(
+                -self.properties().limits.max_sampler_lod_bias,
+                self.properties().limits.max_sampler_lod_bias,
+            ),
+            min_lod: desc.min_lod,
+            max_lod: desc.max_lod,
+            // Anisotropy is silently disabled when the device does not support it
+            anisotropy_enable: max_anisotropy.is_some().into(),
+            max_anisotropy: max_anisotropy.unwrap_or(1.0),
             border_color: desc.border_color.into(),
             compare_enable: vk::FALSE,
             compare_op: vk::CompareOp::ALWAYS,
@@ -573,31 +1266,43 @@ impl Device {
             / self.surface_data.surface_resolution.height as f32
     }
 
+    pub(crate) fn allocate_bind_group(
+        &self,
+        layout: &BindGroupLayout,
+        count: u32,
+    ) -> Result<vk::DescriptorSet> {
+        let mut pools = self.bind_group_pools.lock().unwrap();
+
+        let mut count_info = vk::DescriptorSetVariableDescriptorCountAllocateInfo::builder()
+            .descriptor_counts(std::slice::from_ref(&count))
+            .build();
+        let mut desc_alloc_info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(pools.last().unwrap().0)
+            .set_layouts(std::slice::from_ref(&layout.0))
+            .push_next(&mut count_info)
+            .build();
+
+        match unsafe { self.raw().allocate_descriptor_sets(&desc_alloc_info) } {
+            Ok(sets) => Ok(sets[0]),
+            Err(vk::Result::ERROR_OUT_OF_POOL_MEMORY | vk::Result::ERROR_FRAGMENTED_POOL) => {
+                let pool = BindGroupPool::new(
+                    &self.instance,
+                    &self.device,
+                    count.max(MAX_BINDLESS_RESOURCES),
+                )?;
+                desc_alloc_info.descriptor_pool = pool.0;
+                pools.push(pool);
+                let sets = unsafe { self.raw().allocate_descriptor_sets(&desc_alloc_info) }?;
+                Ok(sets[0])
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
     pub fn write_bind_group(&self, infos: &[BindGroupBindInfo]) -> Result<(), DeviceError> {
         let writes = infos
             .iter()
-            .map(|info| {
-                let mut write = vk::WriteDescriptorSet::builder()
-                    .dst_set(info.group.0)
-                    .dst_binding(info.dst_binding);
-                write = match &info.data {
-                    BindGroupWriteData::Uniform(buffer_info) => write
-                        .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
-                        .buffer_info(std::slice::from_ref(&buffer_info.0)),
-                    BindGroupWriteData::Storage(buffer_info) => write
-                        .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
-                        .buffer_info(std::slice::from_ref(&buffer_info.0)),
-                    BindGroupWriteData::SampledImage(info) => write
-                        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-                        .dst_array_element(info.index)
-                        .image_info(std::slice::from_ref(&info.info)),
-                    BindGroupWriteData::StorageImage(info) => write
-                        .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
-                        .dst_array_element(info.index)
-                        .image_info(std::slice::from_ref(&info.info)),
-                };
-                write.build()
-            })
+            .map(|info| info.data.write_descriptor(info.group.0, info.dst_binding))
             .collect::<Vec<_>>();
 
         unsafe {
@@ -606,6 +1311,51 @@ impl Device {
         Ok(())
     }
 
+    pub fn create_fence(&self, signaled: bool) -> Result<Fence> {
+        let create_info = vk::FenceCreateInfo {
+            flags: if signaled {
+                vk::FenceCreateFlags::SIGNALED
+            } else {
+                vk::FenceCreateFlags::empty()
+            },
+            ..Default::default()
+        };
+        let fence = unsafe { self.raw().create_fence(&create_info, None) }?;
+        Ok(Fence(fence))
+    }
+
+    pub fn create_semaphore(&self) -> Result<Semaphore> {
+        let semaphore = unsafe { self.raw().create_semaphore(&Default::default(), None) }?;
+        Ok(Semaphore(semaphore))
+    }
+
+    // Blocks until every fence is signaled
+    pub fn wait_fences(&self, fences: &[Fence]) -> Result<()> {
+        let fences = fences.iter().map(|fence| fence.0).collect::<Vec<_>>();
+        unsafe { self.raw().wait_for_fences(&fences, true, std::u64::MAX) }?;
+        Ok(())
+    }
+
+    pub fn fence_signaled(&self, fence: Fence) -> Result<bool> {
+        Ok(unsafe { self.raw().get_fence_status(fence.0) }?)
+    }
+
+    pub fn reset_fences(&self, fences: &[Fence]) -> Result<()> {
+        let fences = fences.iter().map(|fence| fence.0).collect::<Vec<_>>();
+        unsafe { self.raw().reset_fences(&fences) }?;
+        Ok(())
+    }
+
+    // Makes the next frame submission wait on `semaphore`, e.g. one signaled by an upload
+    // submitted with `CommandQueue::submit`
+    pub fn wait_on_next_frame(&self, semaphore: Semaphore) {
+        self.external_waits.lock().unwrap().push(semaphore.0);
+    }
+
+    pub(crate) fn take_external_waits(&self) -> Vec<vk::Semaphore> {
+        std::mem::take(&mut *self.external_waits.lock().unwrap())
+    }
+
     pub fn wait_idle(&self) -> Result<()> {
         unsafe {
             self.raw().device_wait_idle()?;
@@ -615,7 +1365,16 @@ impl Device {
 
     pub fn resize(&mut self, width: u32, height: u32) -> Result<()> {
         self.wait_idle()?;
-        self.surface_data = self.surface.get_data(self.p_device, width, height, false)?;
+        self.surface_data = match &self.surface {
+            Some(surface) => surface.get_data(
+                self.p_device,
+                width,
+                height,
+                false,
+                self.preferred_image_count,
+            )?,
+            None => SurfaceData::headless(width, height),
+        };
         Ok(())
     }
 
@@ -627,15 +1386,59 @@ impl Device {
         self.image_acquired_semaphores[self.current_frame_in_flight()]
     }
 
+    pub(crate) fn compute_complete_semaphore(&self) -> vk::Semaphore {
+        self.compute_complete_semaphores[self.current_frame_in_flight()]
+    }
+
+    pub(crate) fn set_compute_wait_pending(&self) {
+        let was_pending = self.compute_wait_pending.swap(true, Ordering::Relaxed);
+        debug_assert!(
+            !was_pending,
+            "Compute was submitted twice without a graphics submission in between"
+        );
+    }
+
+    // Returns the semaphore the next graphics submission has to wait on, if any
+    pub(crate) fn take_compute_wait(&self) -> Option<vk::Semaphore> {
+        self.compute_wait_pending
+            .swap(false, Ordering::Relaxed)
+            .then(|| self.compute_complete_semaphore())
+    }
+
+    // Waits on a pending compute submission in an empty graphics submission, for frames that are
+    // abandoned before being submitted. Otherwise the semaphore would stay signaled, and the next
+    // compute submission could not signal it again
+    pub(crate) fn discard_compute_wait(&self) -> Result<()> {
+        if let Some(compute_complete_semaphore) = self.take_compute_wait() {
+            let wait_semaphores = [compute_complete_semaphore];
+            let wait_dst_stage_mask = [vk::PipelineStageFlags::ALL_COMMANDS];
+            let submit_info = vk::SubmitInfo::builder()
+                .wait_semaphores(&wait_semaphores)
+                .wait_dst_stage_mask(&wait_dst_stage_mask)
+                .build();
+            unsafe {
+                self.device
+                    .queue_submit(self.present_queue(), &[submit_info], vk::Fence::null())
+            }?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn command_buffer_executed_fence(&self) -> vk::Fence {
         self.command_buffer_executed_fences[self.current_frame_in_flight()]
     }
 
+    pub fn frames_in_flight(&self) -> usize {
+        self.frames_in_flight
+    }
+
     pub fn current_frame_in_flight(&self) -> usize {
-        self.frame_index % MAX_FRAMES_IN_FLIGHT
+        self.frame_index % self.frames_in_flight
     }
 
     pub fn bump_frame(&mut self) {
+        self.frame_submit_values[self.current_frame_in_flight()] =
+            self.last_submit_value.load(Ordering::Relaxed);
         self.frame_index += 1;
     }
 }
@@ -645,7 +1448,9 @@ impl Drop for Device {
         unsafe {
             self.wait_idle().ok();
 
-            self.bind_group_pool.destroy(&self.device);
+            for pool in self.bind_group_pools.get_mut().unwrap() {
+                pool.destroy(&self.device);
+            }
 
             self.device
                 .destroy_pipeline_cache(self.pipeline_cache, None);
@@ -660,6 +1465,13 @@ impl Drop for Device {
             for semaphore in &self.image_acquired_semaphores {
                 self.device.destroy_semaphore(*semaphore, None);
             }
+            for semaphore in &self.compute_complete_semaphores {
+                self.device.destroy_semaphore(*semaphore, None);
+            }
+            if let Some(semaphore) = self.timeline_semaphore {
+                self.device.destroy_semaphore(semaphore, None);
+            }
+            self.memory_allocator.destroy(&self.device);
             // MUST BE DESTROYED LAST!
             self.device.destroy_device(None);
         }
@@ -37,6 +37,7 @@ impl Surface {
         window_width: u32,
         window_height: u32,
         vsync: bool,
+        preferred_image_count: Option<u32>,
     ) -> Result<SurfaceData> {
         // TODO: Would be nice to not allocate here
         let surface_formats = unsafe {
@@ -61,7 +62,10 @@ impl Surface {
         }?;
 
         let desired_image_count = {
-            let mut desired_image_count = surface_capabilities.min_image_count + 1;
+            let mut desired_image_count = preferred_image_count
+                .unwrap_or(surface_capabilities.min_image_count + 1)
+                .max(surface_capabilities.min_image_count);
+            // A maximum of 0 means there is no limit
             if surface_capabilities.max_image_count > 0
                 && desired_image_count > surface_capabilities.max_image_count
             {
@@ -119,6 +123,20 @@ pub struct SurfaceData {
 }
 
 impl SurfaceData {
+    // Stand-in for headless devices, matching the default color format of graphics pipelines
+    pub(crate) fn headless(width: u32, height: u32) -> Self {
+        Self {
+            surface_format: vk::SurfaceFormatKHR {
+                format: vk::Format::B8G8R8A8_UNORM,
+                color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            },
+            surface_capabilities: Default::default(),
+            surface_resolution: vk::Extent2D { width, height },
+            present_mode: vk::PresentModeKHR::FIFO,
+            desired_image_count: 1,
+        }
+    }
+
     pub fn size(&self) -> Size2D<u32> {
         Size2D::new(
             self.surface_resolution.width,
@@ -127,6 +145,9 @@ impl SurfaceData {
     }
 
     pub fn format(&self) -> Format {
-        self.surface_format.format.into()
+        self.surface_format
+            .format
+            .try_into()
+            .expect("Unsupported surface format")
     }
 }
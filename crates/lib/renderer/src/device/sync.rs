@@ -0,0 +1,24 @@
+use super::Device;
+use ash::vk;
+
+// Created with `Device::create_fence`, signaled by `CommandQueue::submit` and waited on from the CPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct Fence(pub vk::Fence);
+
+impl Fence {
+    pub fn destroy(&self, device: &Device) {
+        unsafe { device.raw().destroy_fence(self.0, None) }
+    }
+}
+
+// Created with `Device::create_semaphore`, orders submissions on the GPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct Semaphore(pub vk::Semaphore);
+
+impl Semaphore {
+    pub fn destroy(&self, device: &Device) {
+        unsafe { device.raw().destroy_semaphore(self.0, None) }
+    }
+}
@@ -5,7 +5,10 @@ pub mod renderer;
 pub mod resources;
 pub mod shader_hot_reloader;
 pub mod swapchain;
+pub mod testing;
 pub mod util;
 
-pub use renderer::Renderer;
+pub use renderer::{
+    Renderer, ResizeCallback, DEFAULT_TEXTURE_COUNT, FLAT_NORMAL_TEXTURE_INDEX, WHITE_TEXTURE_INDEX,
+};
 pub use resource_manager::*;
@@ -1,36 +1,275 @@
-use crate::device::Device;
+use crate::{
+    command_queue::CommandList,
+    device::{Device, MAX_FRAMES_IN_FLIGHT},
+};
 use anyhow::Result;
 use ash::vk;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 
 const TIMESTAMP_COUNT: u32 = 128;
+const OCCLUSION_QUERY_COUNT: u32 = 64;
 
 pub struct QueryPool {
     pub raw: vk::QueryPool,
     pub count: u32,
 }
 
-pub struct Profiling {
-    pub timestamp_query_pool: QueryPool,
+impl QueryPool {
+    pub fn new(device: &Device, query_type: vk::QueryType, count: u32, name: &str) -> Result<Self> {
+        let query_pool_ci = vk::QueryPoolCreateInfo::builder()
+            .query_type(query_type)
+            .query_count(count)
+            .build();
+
+        let raw = unsafe { device.raw().create_query_pool(&query_pool_ci, None) }?;
+        device.set_name(vk::ObjectType::QUERY_POOL, raw, name);
+
+        Ok(Self { raw, count })
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        unsafe {
+            device.raw().destroy_query_pool(self.raw, None);
+        }
+    }
+}
+
+// One pool per frame in flight, so a frame's queries can be read back while the next ones record
+fn create_query_pools(
+    device: &Device,
+    query_type: vk::QueryType,
+    count: u32,
+    name: &str,
+) -> Result<[QueryPool; MAX_FRAMES_IN_FLIGHT]> {
+    let mut query_pools = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+    for frame_index in 0..MAX_FRAMES_IN_FLIGHT {
+        query_pools.push(QueryPool::new(
+            device,
+            query_type,
+            count,
+            &format!("{name} {frame_index}"),
+        )?);
+    }
+    Ok(query_pools
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("Created exactly `MAX_FRAMES_IN_FLIGHT` pools")))
+}
+
+// Each timestamp starts a span named by its label, which ends at the next timestamp written.
+pub struct GpuProfiler {
+    query_pools: [QueryPool; MAX_FRAMES_IN_FLIGHT],
+    labels: [Vec<String>; MAX_FRAMES_IN_FLIGHT],
+    timestamp_period: f32,
+    frame_index: usize,
+    results: Vec<(String, f64)>,
 }
 
-impl Profiling {
+impl GpuProfiler {
     pub fn new(device: &Device) -> Result<Self> {
-        let timestamp_query_pool_ci = vk::QueryPoolCreateInfo::builder()
-            .query_type(vk::QueryType::TIMESTAMP)
-            .query_count(TIMESTAMP_COUNT)
-            .build();
+        let query_pools = create_query_pools(
+            device,
+            vk::QueryType::TIMESTAMP,
+            TIMESTAMP_COUNT,
+            "Timestamp Query Pool",
+        )?;
 
-        let timestamp_query_pool = unsafe {
-            device
-                .raw()
-                .create_query_pool(&timestamp_query_pool_ci, None)
-        }?;
+        Ok(Self {
+            query_pools,
+            labels: Default::default(),
+            timestamp_period: device.properties().limits.timestamp_period,
+            frame_index: 0,
+            results: Default::default(),
+        })
+    }
+
+    // Must be called after the frame's fence has been waited on, before any `write_timestamp`.
+    pub fn begin_frame(&mut self, device: &Device, cmd_list: &CommandList) -> Result<()> {
+        self.frame_index = device.current_frame_in_flight();
+        let query_pool = &self.query_pools[self.frame_index];
+        let labels = &mut self.labels[self.frame_index];
+
+        let mut read = Ok(());
+        if !labels.is_empty() {
+            // Each timestamp is followed by its availability, a frame that was abandoned before
+            // submitting leaves its timestamps unwritten
+            let mut timestamps = vec![[0u64; 2]; labels.len()];
+            read = match unsafe {
+                device.raw().get_query_pool_results(
+                    query_pool.raw,
+                    0,
+                    labels.len() as u32,
+                    &mut timestamps,
+                    vk::QueryResultFlags::TYPE_64 | vk::QueryResultFlags::WITH_AVAILABILITY,
+                )
+            } {
+                Ok(()) | Err(vk::Result::NOT_READY) => Ok(()),
+                Err(err) => Err(err),
+            };
+            // Keeps the previous results until a frame with all of its timestamps comes back
+            if read.is_ok() && timestamps.iter().all(|[_, available]| *available != 0) {
+                self.results = labels
+                    .iter()
+                    .zip(timestamps.windows(2))
+                    .map(|(label, window)| {
+                        let ticks = window[1][0].saturating_sub(window[0][0]);
+                        let ms = ticks as f64 * self.timestamp_period as f64 / 1_000_000.0;
+                        (label.clone(), ms)
+                    })
+                    .collect();
+            }
+            labels.clear();
+        }
+
+        unsafe {
+            device.raw().cmd_reset_query_pool(
+                cmd_list.buffer(),
+                query_pool.raw,
+                0,
+                query_pool.count,
+            );
+        }
+
+        // The pool is reset even when the read fails, so this frame slot doesn't fail every time
+        read?;
+        Ok(())
+    }
+
+    pub(crate) fn next_query(&mut self, label: &str) -> Option<(vk::QueryPool, u32)> {
+        let query_pool = &self.query_pools[self.frame_index];
+        let labels = &mut self.labels[self.frame_index];
+        if labels.len() as u32 >= query_pool.count {
+            return None;
+        }
+        let index = labels.len() as u32;
+        labels.push(label.to_owned());
+        Some((query_pool.raw, index))
+    }
+
+    pub fn results(&self) -> &[(String, f64)] {
+        &self.results
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        for query_pool in &self.query_pools {
+            query_pool.destroy(device);
+        }
+    }
+}
+
+// Counts the samples that pass the depth and stencil tests between
+// `CommandList::begin_occlusion_query` and `end_occlusion_query`. Results are read back once the
+// frame that wrote them has finished, so they lag a few frames behind.
+pub struct OcclusionQueries {
+    query_pools: [QueryPool; MAX_FRAMES_IN_FLIGHT],
+    // Query pools start out unavailable, and can only be read after their first reset
+    reset: [bool; MAX_FRAMES_IN_FLIGHT],
+    frame_index: usize,
+    results: Vec<Option<u64>>,
+}
+
+impl OcclusionQueries {
+    pub fn new(device: &Device) -> Result<Self> {
+        let query_pools = create_query_pools(
+            device,
+            vk::QueryType::OCCLUSION,
+            OCCLUSION_QUERY_COUNT,
+            "Occlusion Query Pool",
+        )?;
 
         Ok(Self {
-            timestamp_query_pool: QueryPool {
-                raw: timestamp_query_pool,
-                count: TIMESTAMP_COUNT,
-            },
+            query_pools,
+            reset: Default::default(),
+            frame_index: 0,
+            results: vec![None; OCCLUSION_QUERY_COUNT as usize],
         })
     }
+
+    // Must be called after the frame's fence has been waited on, before any occlusion query.
+    pub fn begin_frame(&mut self, device: &Device, cmd_list: &CommandList) -> Result<()> {
+        self.frame_index = device.current_frame_in_flight();
+        let query_pool = &self.query_pools[self.frame_index];
+
+        if self.reset[self.frame_index] {
+            // Each result is followed by its availability, so queries that were not used that
+            // frame read as `None` instead of failing the whole read
+            let mut results = vec![[0u64; 2]; query_pool.count as usize];
+            match unsafe {
+                device.raw().get_query_pool_results(
+                    query_pool.raw,
+                    0,
+                    query_pool.count,
+                    &mut results,
+                    vk::QueryResultFlags::TYPE_64 | vk::QueryResultFlags::WITH_AVAILABILITY,
+                )
+            } {
+                Ok(()) | Err(vk::Result::NOT_READY) => {}
+                Err(err) => return Err(err.into()),
+            }
+            self.results = results
+                .iter()
+                .map(|[samples, available]| (*available != 0).then_some(*samples))
+                .collect();
+        }
+
+        unsafe {
+            device.raw().cmd_reset_query_pool(
+                cmd_list.buffer(),
+                query_pool.raw,
+                0,
+                query_pool.count,
+            );
+        }
+        self.reset[self.frame_index] = true;
+
+        Ok(())
+    }
+
+    // Pool to record this frame's queries into, each query index can be used once per frame
+    pub fn query_pool(&self) -> &QueryPool {
+        &self.query_pools[self.frame_index]
+    }
+
+    // Samples counted by `query` the last time its results were read back, `None` if it wasn't used
+    pub fn sample_count(&self, query: u32) -> Option<u64> {
+        self.results.get(query as usize).copied().flatten()
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        for query_pool in &self.query_pools {
+            query_pool.destroy(device);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DrawCounters {
+    draw_calls: AtomicU32,
+    indices: AtomicU64,
+}
+
+impl DrawCounters {
+    pub(crate) fn record(&self, index_count: u32) {
+        self.draw_calls.fetch_add(1, Ordering::Relaxed);
+        self.indices
+            .fetch_add(index_count as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn reset(&self) -> DrawStats {
+        DrawStats {
+            draw_calls: self.draw_calls.swap(0, Ordering::Relaxed),
+            triangles: self.indices.swap(0, Ordering::Relaxed) / 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DrawStats {
+    pub draw_calls: u32,
+    pub triangles: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameStats {
+    pub cpu_frame_time_ms: f64,
+    pub draw_stats: DrawStats,
 }
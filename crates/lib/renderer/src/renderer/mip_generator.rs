@@ -0,0 +1,118 @@
+use crate::{
+    command_queue::CommandList,
+    device::Device,
+    resources::{
+        bind_group::{cache::BindGroupCache, BindGroupPushInfo, BindGroupWriteData},
+        image::{Image, Layout},
+        pipeline::compute::{ComputePipeline, ComputePipelineDescription},
+        sampler::{Sampler, SamplerDescription},
+    },
+};
+use anyhow::Result;
+
+// Must match the local size in downsample.comp
+const WORKGROUP_SIZE: u32 = 8;
+
+// Generates mip levels with a compute downsample, for formats that can't be blitted
+pub(crate) struct MipGenerator {
+    pipeline: ComputePipeline,
+    // Only there to fill the combined image sampler, levels are read with `texelFetch`
+    sampler: Sampler,
+}
+
+impl MipGenerator {
+    pub(crate) fn new(device: &Device) -> Result<Self> {
+        let shader = device.create_shader(
+            include_bytes!("../../shaders/spv/downsample.comp.spv"),
+            Default::default(),
+        )?;
+        let pipeline = device.create_compute_pipeline(
+            &shader,
+            ComputePipelineDescription {
+                name: Some("Mip Downsample Pipeline".to_owned()),
+                ..Default::default()
+            },
+        )?;
+        shader.destroy(device);
+        let sampler = device.create_sampler(SamplerDescription::default())?;
+
+        Ok(Self { pipeline, sampler })
+    }
+
+    // Same layouts as `CommandList::generate_mipmaps`, every level goes from `Layout::TransferDst`
+    // to `Layout::ShaderReadOnly`. `image` must have been created with
+    // `Device::needs_compute_mipmaps`
+    pub(crate) fn generate(
+        &self,
+        device: &Device,
+        bind_group_cache: &BindGroupCache,
+        cmd_list: &CommandList,
+        image: &Image,
+    ) -> Result<()> {
+        cmd_list.transition_image_mip(
+            device,
+            image,
+            0,
+            Layout::TransferDst,
+            Layout::ShaderReadOnly,
+        );
+        if image.mip_levels() > 1 {
+            cmd_list.bind_compute_pipeline(device, &self.pipeline);
+        }
+        for level in 1..image.mip_levels() {
+            cmd_list.transition_image_mip(
+                device,
+                image,
+                level,
+                Layout::TransferDst,
+                Layout::General,
+            );
+            let bind_group = bind_group_cache.get(
+                device,
+                self.pipeline.bind_group_data(0).unwrap(),
+                &[
+                    BindGroupPushInfo {
+                        dst_binding: 0,
+                        data: BindGroupWriteData::SampledImage(image.mip_bind_info(
+                            device,
+                            Some(&self.sampler),
+                            Layout::ShaderReadOnly,
+                            level - 1,
+                        )?),
+                    },
+                    BindGroupPushInfo {
+                        dst_binding: 1,
+                        data: BindGroupWriteData::StorageImage(image.mip_bind_info(
+                            device,
+                            None,
+                            Layout::General,
+                            level,
+                        )?),
+                    },
+                ],
+            )?;
+            cmd_list.bind_compute_descriptor_sets(device, &self.pipeline, 0, &[bind_group]);
+            let width = (image.size.width() >> level).max(1);
+            let height = (image.size.height() >> level).max(1);
+            cmd_list.dispatch(
+                device,
+                width.div_ceil(WORKGROUP_SIZE),
+                height.div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+            cmd_list.transition_image_mip(
+                device,
+                image,
+                level,
+                Layout::General,
+                Layout::ShaderReadOnly,
+            );
+        }
+        Ok(())
+    }
+
+    pub(crate) fn destroy(&self, device: &Device) {
+        self.sampler.destroy(device);
+        self.pipeline.destroy(device);
+    }
+}
@@ -1,9 +1,35 @@
+mod mip_generator;
+pub mod time;
+
 use crate::{
-    command_queue::CommandQueue, device::Device, resources::ResourceManager, swapchain::Swapchain,
+    command_queue::{CommandList, CommandQueue},
+    device::{Device, DeviceDescription},
+    profiling::{DrawStats, FrameStats, GpuProfiler, OcclusionQueries},
+    renderer::{mip_generator::MipGenerator, time::Time},
+    resources::{
+        bind_group::{cache::BindGroupCache, BindGroup, BindGroupBindInfo, BindGroupWriteData},
+        buffer::Buffer,
+        image::{Image, ImageDescription, ImageError, Layout},
+        sampler::{Sampler, SamplerDescription},
+        transient::TransientImagePool,
+        ManagedResource, ResourceManager,
+    },
+    swapchain::Swapchain,
+    ResourceId,
 };
 use anyhow::Result;
+use math::size::Size2D;
 use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
-use std::time::Instant;
+use std::time::{Duration, Instant};
+
+// Bindless array elements `Renderer::write_default_textures` writes to. Arrays that use them start
+// their own textures at `DEFAULT_TEXTURE_COUNT`
+pub const WHITE_TEXTURE_INDEX: u32 = 0;
+pub const FLAT_NORMAL_TEXTURE_INDEX: u32 = 1;
+pub const DEFAULT_TEXTURE_COUNT: u32 = 2;
+
+// Runs after a screen-sized image is resized, e.g. to transition the new image
+pub type ResizeCallback = Box<dyn Fn(&Renderer, &Image) -> Result<()>>;
 
 #[derive(Debug, PartialEq, Eq)]
 enum FrameState {
@@ -25,46 +51,253 @@ pub struct Renderer {
     pub swapchain: Swapchain,
     pub command_queue: CommandQueue,
     pub resource_manager: ResourceManager,
-    init_time: Instant,
+    pub transient_images: TransientImagePool,
+    pub gpu_profiler: GpuProfiler,
+    pub occlusion_queries: OcclusionQueries,
+    pub bind_group_cache: BindGroupCache,
+    pub time: Time,
+    mip_generator: MipGenerator,
+    white_texture: ResourceId<Image>,
+    flat_normal_texture: ResourceId<Image>,
+    default_texture_sampler: Sampler,
+    screen_sized_images: Vec<(ResourceId<Image>, Option<ResizeCallback>)>,
     frame_state: FrameState,
     last_dt: Option<u128>,
+    last_frame_time: Option<Duration>,
+    last_draw_stats: DrawStats,
 }
 
 impl Renderer {
-    pub fn new<W>(window: &W, window_width: u32, window_height: u32) -> Result<Self>
+    pub fn new<W>(
+        window: &W,
+        window_width: u32,
+        window_height: u32,
+        desc: DeviceDescription,
+    ) -> Result<Self>
     where
         W: HasRawWindowHandle + HasRawDisplayHandle,
     {
-        let device = Device::new(window, window_width, window_height)?;
+        let device = Device::new(window, window_width, window_height, desc)?;
+        Self::with_device(device)
+    }
+
+    pub fn new_headless(width: u32, height: u32, desc: DeviceDescription) -> Result<Self> {
+        let device = Device::new_headless(width, height, desc)?;
+        Self::with_device(device)
+    }
+
+    fn with_device(device: Device) -> Result<Self> {
         let command_queue = CommandQueue::new(&device)?;
         let swapchain = Swapchain::new(&device)?;
-        let resource_manager = ResourceManager::default();
-
-        let init_time = Instant::now();
+        let mut resource_manager = ResourceManager::default();
+        let gpu_profiler = GpuProfiler::new(&device)?;
+        let occlusion_queries = OcclusionQueries::new(&device)?;
+        let time = Time::new(device.is_headless());
+        let mip_generator = MipGenerator::new(&device)?;
+        // Stand-ins for material textures that are missing. A normal of +Z in tangent space leaves
+        // the surface normal as-is
+        let white_texture = device.create_image_with_data_immediate(
+            Size2D::new(1, 1),
+            &[255u8; 4],
+            &command_queue,
+            ImageDescription {
+                name: Some("Default White"),
+                ..Default::default()
+            },
+        )?;
+        let flat_normal_texture = device.create_image_with_data_immediate(
+            Size2D::new(1, 1),
+            &[128u8, 128, 255, 255],
+            &command_queue,
+            ImageDescription {
+                name: Some("Default Flat Normal"),
+                ..Default::default()
+            },
+        )?;
+        let white_texture = resource_manager.insert_image(white_texture);
+        let flat_normal_texture = resource_manager.insert_image(flat_normal_texture);
+        let default_texture_sampler = device.create_sampler(SamplerDescription::default())?;
 
         Ok(Self {
             device,
             swapchain,
             command_queue,
             resource_manager,
-            init_time,
+            transient_images: Default::default(),
+            gpu_profiler,
+            occlusion_queries,
+            bind_group_cache: Default::default(),
+            time,
+            mip_generator,
+            white_texture,
+            flat_normal_texture,
+            default_texture_sampler,
+            screen_sized_images: Default::default(),
             frame_state: FrameState::NotRunning,
             last_dt: None,
+            last_frame_time: None,
+            last_draw_stats: Default::default(),
         })
     }
 
     pub fn init_time(&self) -> Instant {
-        self.init_time
+        self.time.init_time()
+    }
+
+    // Reads back the last frame rendered by a headless renderer
+    pub fn read_headless_frame(&self) -> Result<Vec<u8>> {
+        self.swapchain
+            .read_headless_image(&self.device, &self.command_queue)
+    }
+
+    // How many frames the CPU can record ahead of the GPU. Resources written by the CPU every frame
+    // need this many copies, like `FrameBuffered` keeps, so a copy isn't overwritten while in use
+    pub fn frames_in_flight(&self) -> usize {
+        self.device.frames_in_flight()
+    }
+
+    // Index of the copy of a per-frame resource that is safe to write this frame, from 0 to
+    // `frames_in_flight`. Unrelated to the swapchain image index, as the number of swapchain
+    // images can differ from the number of frames in flight
+    pub fn current_frame_in_flight(&self) -> usize {
+        self.device.current_frame_in_flight()
     }
 
     pub fn last_dt(&self) -> Option<u128> {
         self.last_dt
     }
 
+    pub fn frame_stats(&self) -> FrameStats {
+        FrameStats {
+            cpu_frame_time_ms: self
+                .last_frame_time
+                .map_or(0.0, |time| time.as_secs_f64() * 1000.0),
+            draw_stats: self.last_draw_stats,
+        }
+    }
+
+    // 1x1 white texture in `Layout::ShaderReadOnly`, to bind in place of missing color textures
+    pub fn white_texture(&self) -> ResourceId<Image> {
+        self.white_texture
+    }
+
+    // 1x1 tangent-space normal map pointing straight out of the surface, to bind in place of
+    // missing normal maps
+    pub fn flat_normal_texture(&self) -> ResourceId<Image> {
+        self.flat_normal_texture
+    }
+
+    // Writes `white_texture` and `flat_normal_texture` to elements `WHITE_TEXTURE_INDEX` and
+    // `FLAT_NORMAL_TEXTURE_INDEX` of a bindless binding, for materials without those textures
+    pub fn write_default_textures(&self, bind_group: BindGroup, dst_binding: u32) -> Result<()> {
+        let textures = [
+            (self.white_texture, WHITE_TEXTURE_INDEX),
+            (self.flat_normal_texture, FLAT_NORMAL_TEXTURE_INDEX),
+        ];
+        let writes = textures
+            .iter()
+            .map(|(texture, index)| BindGroupBindInfo {
+                group: bind_group,
+                dst_binding,
+                data: BindGroupWriteData::SampledImage(
+                    self.resource_manager
+                        .images
+                        .get(*texture)
+                        .unwrap()
+                        .bind_info(
+                            &self.default_texture_sampler,
+                            Layout::ShaderReadOnly,
+                            Some(*index),
+                        ),
+                ),
+            })
+            .collect::<Vec<_>>();
+        self.device.write_bind_group(&writes)?;
+        Ok(())
+    }
+
+    // Same as `Device::create_image_with_data`, generating the mip levels with compute for formats
+    // that can't be blitted
+    #[track_caller]
+    pub fn create_image_with_data(
+        &self,
+        size: Size2D<u32>,
+        bytes: &[u8],
+        cmd_list: &CommandList,
+        desc: ImageDescription,
+    ) -> Result<(Image, Buffer)> {
+        let (image, buffer) = self.device.upload_image_data(size, bytes, cmd_list, desc)?;
+        self.generate_mipmaps(cmd_list, &image)?;
+        Ok((image, buffer))
+    }
+
+    // Generates every mip level from the first, blitting between levels when the format supports
+    // it and downsampling with compute otherwise. Every level goes from `Layout::TransferDst` to
+    // `Layout::ShaderReadOnly`
+    pub fn generate_mipmaps(&self, cmd_list: &CommandList, image: &Image) -> Result<()> {
+        if self.device.needs_compute_mipmaps(&image.desc) {
+            self.generate_mipmaps_compute(cmd_list, image)
+        } else if image.mip_levels() > 1 && !self.device.supports_linear_blit(image.format()) {
+            Err(ImageError::LinearBlitNotSupported(image.format()).into())
+        } else {
+            cmd_list.generate_mipmaps(&self.device, image);
+            Ok(())
+        }
+    }
+
+    // Generates mip levels with compute, reading each level to write the next. Requires an image
+    // created while `Device::needs_compute_mipmaps` holds for its description
+    pub fn generate_mipmaps_compute(&self, cmd_list: &CommandList, image: &Image) -> Result<()> {
+        self.mip_generator
+            .generate(&self.device, &self.bind_group_cache, cmd_list, image)
+    }
+
+    // Removes the resource from the resource manager and destroys it once the GPU is done with the
+    // work submitted so far, including the current frame's. Checked at the start of every frame
+    pub fn destroy_later<T: ManagedResource>(&mut self, handle: ResourceId<T>) {
+        let submit_value = self.device.pending_submit_value();
+        self.resource_manager.delete(handle, submit_value);
+    }
+
+    // Same as `destroy_later`, for resources owned by the app instead of the resource manager
+    pub fn destroy_later_raw<T: ManagedResource>(&mut self, res: T) {
+        let submit_value = self.device.pending_submit_value();
+        self.resource_manager.delete_raw(res, submit_value);
+    }
+
+    // `image` is resized along with the surface by `resize`, before `App::resize` is called
+    pub fn register_screen_sized_image(
+        &mut self,
+        image: ResourceId<Image>,
+        on_resize: Option<ResizeCallback>,
+    ) {
+        self.screen_sized_images.push((image, on_resize));
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) -> Result<()> {
         self.device.resize(width, height)?;
         self.swapchain.resize(&self.device)?;
-        Ok(())
+        // Transients sized after the surface would otherwise stay cached at their old size
+        self.transient_images.destroy(&self.device);
+
+        for (id, _) in &self.screen_sized_images {
+            if let Some(image) = self.resource_manager.images.get_mut(*id) {
+                image.resize(&self.device, Size2D::new(width, height))?;
+            }
+        }
+        // Bind groups pointing to the old images can't be told apart from ones for the new images
+        self.bind_group_cache.invalidate();
+
+        // Callbacks get the whole renderer, so they are taken out of it while they run
+        let screen_sized_images = std::mem::take(&mut self.screen_sized_images);
+        let result = screen_sized_images.iter().try_for_each(|(id, on_resize)| {
+            match (on_resize, self.resource_manager.images.get(*id)) {
+                (Some(on_resize), Some(image)) => on_resize(self, image),
+                _ => Ok(()),
+            }
+        });
+        self.screen_sized_images = screen_sized_images;
+        result
     }
 
     pub fn start_frame(&mut self) -> Result<()> {
@@ -73,9 +306,12 @@ impl Renderer {
             "Called `start_frame` twice before calling `end_frame`"
         );
         self.frame_state = FrameState::Running(Instant::now());
+        self.time.begin_frame();
 
         self.device.new_frame()?;
-        self.resource_manager.consume(&self.device);
+        self.bind_group_cache.begin_frame();
+        self.last_draw_stats = self.device.draw_counters.reset();
+        self.resource_manager.consume(&self.device)?;
         Ok(())
     }
 
@@ -86,7 +322,9 @@ impl Renderer {
         );
         match self.frame_state {
             FrameState::Running(frame_start) => {
-                self.last_dt = Some(frame_start.elapsed().as_millis())
+                let elapsed = frame_start.elapsed();
+                self.last_dt = Some(elapsed.as_millis());
+                self.last_frame_time = Some(elapsed);
             }
             FrameState::NotRunning => unreachable!(),
         }
@@ -101,6 +339,11 @@ impl Drop for Renderer {
         self.device.wait_idle().ok();
         self.command_queue.destroy(&self.device);
         self.swapchain.destroy(&self.device);
+        self.gpu_profiler.destroy(&self.device);
+        self.occlusion_queries.destroy(&self.device);
+        self.mip_generator.destroy(&self.device);
+        self.default_texture_sampler.destroy(&self.device);
+        self.transient_images.destroy(&self.device);
         self.resource_manager.force_destroy(&self.device);
     }
 }
@@ -0,0 +1,99 @@
+use std::time::{Duration, Instant};
+
+// Longest frame the fixed timestep catches up on, so a long stall doesn't turn into a burst of
+// fixed updates that makes the next frame even longer
+const MAX_FRAME_DELTA: Duration = Duration::from_millis(250);
+
+const DEFAULT_FIXED_TIMESTEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+// Frame timing, measured between the starts of consecutive frames, and an accumulator that
+// splits it into fixed-size steps for updates that shouldn't depend on the frame rate
+#[derive(Debug)]
+pub struct Time {
+    init_time: Instant,
+    last_frame_start: Option<Instant>,
+    delta: Duration,
+    elapsed: Duration,
+    fixed_timestep: Duration,
+    accumulator: Duration,
+    // Headless frames are compared against reference images, so time never advances
+    frozen: bool,
+}
+
+impl Time {
+    pub(crate) fn new(frozen: bool) -> Self {
+        Self {
+            init_time: Instant::now(),
+            last_frame_start: None,
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            fixed_timestep: DEFAULT_FIXED_TIMESTEP,
+            accumulator: Duration::ZERO,
+            frozen,
+        }
+    }
+
+    pub(crate) fn begin_frame(&mut self) {
+        if self.frozen {
+            return;
+        }
+        let now = Instant::now();
+        if let Some(last_frame_start) = self.last_frame_start {
+            self.delta = now - last_frame_start;
+        }
+        self.last_frame_start = Some(now);
+        self.elapsed = now - self.init_time;
+        self.accumulator += self.delta.min(MAX_FRAME_DELTA);
+    }
+
+    pub fn init_time(&self) -> Instant {
+        self.init_time
+    }
+
+    // Zero on the first frame
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    // Time since the renderer was created, as of the start of the current frame
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+
+    pub fn fixed_timestep(&self) -> Duration {
+        self.fixed_timestep
+    }
+
+    pub fn fixed_delta_seconds(&self) -> f32 {
+        self.fixed_timestep.as_secs_f32()
+    }
+
+    pub fn set_fixed_timestep(&mut self, fixed_timestep: Duration) {
+        assert!(
+            !fixed_timestep.is_zero(),
+            "Fixed timestep must be greater than zero"
+        );
+        self.fixed_timestep = fixed_timestep;
+    }
+
+    // Takes a fixed step out of the accumulated time, if there is enough of it left. Called in a
+    // loop once per frame, running a fixed update for each step taken
+    pub fn consume_fixed_step(&mut self) -> bool {
+        if self.accumulator >= self.fixed_timestep {
+            self.accumulator -= self.fixed_timestep;
+            true
+        } else {
+            false
+        }
+    }
+
+    // How far the current frame is between the last fixed step and the next one, from 0 to 1, to
+    // interpolate state that only changes in fixed steps
+    pub fn fixed_step_alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.fixed_timestep.as_secs_f32()
+    }
+}
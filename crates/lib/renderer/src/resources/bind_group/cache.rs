@@ -0,0 +1,169 @@
+use super::{BindGroup, BindGroupData, BindGroupPushInfo, BindGroupWriteData};
+use crate::device::{Device, MAX_FRAMES_IN_FLIGHT};
+use anyhow::Result;
+use ash::vk;
+use std::{collections::HashMap, sync::Mutex};
+
+// Handles and ranges written by a `BindGroupWriteData`, which identify its contents
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum WriteKey {
+    Buffer(vk::DescriptorType, vk::Buffer, u64, u64),
+    Image(
+        vk::DescriptorType,
+        vk::ImageView,
+        vk::Sampler,
+        vk::ImageLayout,
+        u32,
+    ),
+}
+
+impl WriteKey {
+    fn new(data: &BindGroupWriteData) -> Self {
+        let buffer = |ty, info: &vk::DescriptorBufferInfo| {
+            WriteKey::Buffer(ty, info.buffer, info.offset, info.range)
+        };
+        let image = |ty, info: &vk::DescriptorImageInfo, index| {
+            WriteKey::Image(ty, info.image_view, info.sampler, info.image_layout, index)
+        };
+        match data {
+            BindGroupWriteData::Storage(info) => {
+                buffer(vk::DescriptorType::STORAGE_BUFFER, &info.0)
+            }
+            BindGroupWriteData::Uniform(info) => {
+                buffer(vk::DescriptorType::UNIFORM_BUFFER, &info.0)
+            }
+            BindGroupWriteData::DynamicUniform(info) => {
+                buffer(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, &info.0)
+            }
+            BindGroupWriteData::SampledImage(info) => image(
+                vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                &info.info,
+                info.index,
+            ),
+            BindGroupWriteData::StorageImage(info) => {
+                image(vk::DescriptorType::STORAGE_IMAGE, &info.info, info.index)
+            }
+        }
+    }
+}
+
+// Bind groups can only be recycled for the same layout and variable descriptor count
+type LayoutKey = (vk::DescriptorSetLayout, u32);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct BindGroupKey {
+    layout: LayoutKey,
+    writes: Vec<(u32, WriteKey)>,
+}
+
+#[derive(Debug)]
+struct CachedBindGroup {
+    group: BindGroup,
+    last_used_frame: u64,
+}
+
+#[derive(Debug, Default)]
+struct BindGroupCacheState {
+    frame: u64,
+    used: HashMap<BindGroupKey, CachedBindGroup>,
+    // Bind groups no longer requested, with the frame they were last used in
+    retired: Vec<(LayoutKey, CachedBindGroup)>,
+    free: HashMap<LayoutKey, Vec<BindGroup>>,
+}
+
+// Hands out bind groups by their contents, so requesting the same writes again reuses the same
+// descriptor set instead of allocating a new one.
+// Bind groups must be requested every frame they are used in. Groups that go unrequested for
+// `MAX_FRAMES_IN_FLIGHT` frames are no longer in use by the GPU, and are rewritten for new
+// contents instead of allocating more descriptor sets.
+#[derive(Debug, Default)]
+pub struct BindGroupCache {
+    state: Mutex<BindGroupCacheState>,
+}
+
+impl BindGroupCache {
+    // Must be called after the frame's fence has been waited on
+    pub fn begin_frame(&self) {
+        let mut state = self.state.lock().expect("Mutex lock poisoned");
+        state.frame += 1;
+        let frame = state.frame;
+        let is_done = |cached: &CachedBindGroup| {
+            frame - cached.last_used_frame >= MAX_FRAMES_IN_FLIGHT as u64
+        };
+
+        let BindGroupCacheState {
+            used,
+            retired,
+            free,
+            ..
+        } = &mut *state;
+        used.retain(|key, cached| {
+            if is_done(cached) {
+                free.entry(key.layout).or_default().push(cached.group);
+                false
+            } else {
+                true
+            }
+        });
+        retired.retain(|(layout, cached)| {
+            if is_done(cached) {
+                free.entry(*layout).or_default().push(cached.group);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    pub fn get(
+        &self,
+        device: &Device,
+        bind_group_data: &BindGroupData,
+        writes: &[BindGroupPushInfo],
+    ) -> Result<BindGroup> {
+        let key = BindGroupKey {
+            layout: (bind_group_data.layout.0, bind_group_data.count),
+            writes: writes
+                .iter()
+                .map(|write| (write.dst_binding, WriteKey::new(&write.data)))
+                .collect(),
+        };
+
+        let mut state = self.state.lock().expect("Mutex lock poisoned");
+        let frame = state.frame;
+        if let Some(cached) = state.used.get_mut(&key) {
+            cached.last_used_frame = frame;
+            return Ok(cached.group);
+        }
+
+        let group = match state.free.get_mut(&key.layout).and_then(Vec::pop) {
+            Some(group) => group,
+            None => BindGroup::new(device, bind_group_data)?,
+        };
+        let descriptor_writes = writes
+            .iter()
+            .map(|write| write.data.write_descriptor(group.0, write.dst_binding))
+            .collect::<Vec<_>>();
+        unsafe {
+            device.raw().update_descriptor_sets(&descriptor_writes, &[]);
+        }
+
+        state.used.insert(
+            key,
+            CachedBindGroup {
+                group,
+                last_used_frame: frame,
+            },
+        );
+        Ok(group)
+    }
+
+    // Stops handing out the current bind groups, e.g. after resources they point to were
+    // recreated, as new handles can have the same value as destroyed ones.
+    // Their descriptor sets are recycled once the GPU is done with them.
+    pub fn invalidate(&self) {
+        let mut state = self.state.lock().expect("Mutex lock poisoned");
+        let BindGroupCacheState { used, retired, .. } = &mut *state;
+        retired.extend(used.drain().map(|(key, cached)| (key.layout, cached)));
+    }
+}
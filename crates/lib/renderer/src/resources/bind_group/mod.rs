@@ -1,3 +1,5 @@
+pub mod cache;
+
 use crate::{
     device::{set_object_name, Device, Instance, MAX_BINDLESS_RESOURCES},
     resources::{buffer::BindBufferInfo, image::BindImageInfo, shader::ShaderStage},
@@ -11,6 +13,7 @@ pub enum BindGroupType {
     ImageSampler,
     StorageImage,
     UniformBuffer,
+    DynamicUniformBuffer,
     StorageBuffer,
 }
 
@@ -20,6 +23,7 @@ impl From<BindGroupType> for vk::DescriptorType {
             BindGroupType::ImageSampler => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
             BindGroupType::StorageImage => vk::DescriptorType::STORAGE_IMAGE,
             BindGroupType::UniformBuffer => vk::DescriptorType::UNIFORM_BUFFER,
+            BindGroupType::DynamicUniformBuffer => vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
             BindGroupType::StorageBuffer => vk::DescriptorType::STORAGE_BUFFER,
         }
     }
@@ -28,19 +32,27 @@ impl From<BindGroupType> for vk::DescriptorType {
 pub struct BindGroupPool(pub(crate) vk::DescriptorPool);
 
 impl BindGroupPool {
-    pub fn new(instance: &Instance, device: &ash::Device) -> Result<Self> {
+    pub fn new(instance: &Instance, device: &ash::Device, descriptor_count: u32) -> Result<Self> {
         let pool_sizes = [
             vk::DescriptorPoolSize {
                 ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-                descriptor_count: MAX_BINDLESS_RESOURCES,
+                descriptor_count,
+            },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::STORAGE_IMAGE,
+                descriptor_count,
             },
             vk::DescriptorPoolSize {
                 ty: vk::DescriptorType::STORAGE_BUFFER,
-                descriptor_count: MAX_BINDLESS_RESOURCES,
+                descriptor_count,
             },
             vk::DescriptorPoolSize {
                 ty: vk::DescriptorType::UNIFORM_BUFFER,
-                descriptor_count: MAX_BINDLESS_RESOURCES,
+                descriptor_count,
+            },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
+                descriptor_count,
             },
         ];
 
@@ -71,7 +83,7 @@ impl BindGroupPool {
 
 pub type BindGroupSet = u32;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BindGroupBindingData {
     pub binding: u32,
     pub ty: BindGroupType,
@@ -100,6 +112,7 @@ pub fn bindless_bind_group_flags() -> vk::DescriptorBindingFlags {
     vk::DescriptorBindingFlags::PARTIALLY_BOUND
         | vk::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT
         | vk::DescriptorBindingFlags::UPDATE_AFTER_BIND
+        | vk::DescriptorBindingFlags::UPDATE_UNUSED_WHILE_PENDING
 }
 
 #[repr(transparent)]
@@ -107,7 +120,11 @@ pub fn bindless_bind_group_flags() -> vk::DescriptorBindingFlags {
 pub struct BindGroupLayout(pub vk::DescriptorSetLayout);
 
 impl BindGroupLayout {
-    pub fn new(device: &Device, layout_data: &[BindGroupBindingData]) -> Result<Self> {
+    pub fn new(
+        device: &Device,
+        layout_data: &[BindGroupBindingData],
+        push_descriptor: bool,
+    ) -> Result<Self> {
         let bindings = layout_data
             .iter()
             .map(|data| {
@@ -128,9 +145,20 @@ impl BindGroupLayout {
             .binding_flags(&binding_flags)
             .build();
 
+        // Dynamic descriptors are not allowed in update-after-bind layouts
+        let layout_flags = if push_descriptor {
+            vk::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR_KHR
+        } else if binding_flags
+            .iter()
+            .any(|flags| flags.contains(vk::DescriptorBindingFlags::UPDATE_AFTER_BIND))
+        {
+            vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL
+        } else {
+            vk::DescriptorSetLayoutCreateFlags::empty()
+        };
         let layout_info = vk::DescriptorSetLayoutCreateInfo::builder()
             .bindings(&bindings)
-            .flags(vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL)
+            .flags(layout_flags)
             .push_next(&mut extended_info)
             .build();
 
@@ -156,10 +184,44 @@ impl BindGroupLayout {
 pub enum BindGroupWriteData {
     Storage(BindBufferInfo),
     Uniform(BindBufferInfo),
+    DynamicUniform(BindBufferInfo),
     SampledImage(BindImageInfo),
     StorageImage(BindImageInfo),
 }
 
+impl BindGroupWriteData {
+    // The returned write borrows from `self`, which must outlive it
+    pub(crate) fn write_descriptor(
+        &self,
+        dst_set: vk::DescriptorSet,
+        dst_binding: u32,
+    ) -> vk::WriteDescriptorSet {
+        let write = vk::WriteDescriptorSet::builder()
+            .dst_set(dst_set)
+            .dst_binding(dst_binding);
+        match self {
+            BindGroupWriteData::Uniform(buffer_info) => write
+                .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+                .buffer_info(std::slice::from_ref(&buffer_info.0)),
+            BindGroupWriteData::DynamicUniform(buffer_info) => write
+                .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
+                .buffer_info(std::slice::from_ref(&buffer_info.0)),
+            BindGroupWriteData::Storage(buffer_info) => write
+                .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                .buffer_info(std::slice::from_ref(&buffer_info.0)),
+            BindGroupWriteData::SampledImage(info) => write
+                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .dst_array_element(info.index)
+                .image_info(std::slice::from_ref(&info.info)),
+            BindGroupWriteData::StorageImage(info) => write
+                .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
+                .dst_array_element(info.index)
+                .image_info(std::slice::from_ref(&info.info)),
+        }
+        .build()
+    }
+}
+
 #[derive(Debug)]
 pub struct BindGroupBindInfo {
     pub group: BindGroup,
@@ -167,25 +229,78 @@ pub struct BindGroupBindInfo {
     pub data: BindGroupWriteData,
 }
 
+// Same as `BindGroupBindInfo`, for descriptors pushed directly into a command list or looked up
+// in a `BindGroupCache`
+#[derive(Debug)]
+pub struct BindGroupPushInfo {
+    pub dst_binding: u32,
+    pub data: BindGroupWriteData,
+}
+
 #[derive(Debug, Copy, Clone)]
 #[repr(transparent)]
 pub struct BindGroup(pub vk::DescriptorSet);
 
 impl BindGroup {
     pub fn new(device: &Device, bind_group_data: &BindGroupData) -> Result<Self> {
-        let mut count_info = vk::DescriptorSetVariableDescriptorCountAllocateInfo::builder()
-            .descriptor_counts(std::slice::from_ref(&bind_group_data.count))
-            .build();
+        Self::with_count(device, bind_group_data, bind_group_data.count)
+    }
 
-        let desc_alloc_info = vk::DescriptorSetAllocateInfo::builder()
-            .descriptor_pool(device.bind_group_pool.0)
-            .set_layouts(std::slice::from_ref(&bind_group_data.layout.0))
-            .push_next(&mut count_info)
-            .build();
+    // `count` is the number of descriptors allocated for the variable-sized last binding
+    pub fn with_count(
+        device: &Device,
+        bind_group_data: &BindGroupData,
+        count: u32,
+    ) -> Result<Self> {
+        debug_assert!(
+            count <= bind_group_data.max_count,
+            "Requested {count} descriptors, layout supports at most {}",
+            bind_group_data.max_count
+        );
+        let set = device.allocate_bind_group(&bind_group_data.layout, count)?;
+        Ok(Self(set))
+    }
 
-        let set = unsafe { device.raw().allocate_descriptor_sets(&desc_alloc_info) }?[0];
+    // Reallocates this bind group with room for `new_count` descriptors in the variable-sized
+    // last binding, copying over the existing descriptors.
+    // The old descriptor set is not freed, it is reclaimed when the device is destroyed.
+    pub fn grow(
+        &mut self,
+        device: &Device,
+        bind_group_data: &BindGroupData,
+        old_count: u32,
+        new_count: u32,
+    ) -> Result<()> {
+        if new_count <= old_count {
+            return Ok(());
+        }
+        let new = Self::with_count(device, bind_group_data, new_count)?;
 
-        Ok(Self(set))
+        let last_binding = bind_group_data.bindings.last().map(|data| data.binding);
+        let copies = bind_group_data
+            .bindings
+            .iter()
+            .map(|data| {
+                let descriptor_count = if Some(data.binding) == last_binding {
+                    old_count
+                } else {
+                    data.count
+                };
+                vk::CopyDescriptorSet::builder()
+                    .src_set(self.0)
+                    .src_binding(data.binding)
+                    .dst_set(new.0)
+                    .dst_binding(data.binding)
+                    .descriptor_count(descriptor_count)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        unsafe {
+            device.raw().update_descriptor_sets(&[], &copies);
+        }
+
+        *self = new;
+        Ok(())
     }
 
     pub fn set_name(&self, device: &Device, name: &str) {
@@ -195,7 +310,11 @@ impl BindGroup {
 
 #[derive(Debug)]
 pub struct BindGroupData {
+    // Descriptors allocated for the variable-sized last binding by `BindGroup::new`
     pub count: u32,
+    // Upper bound for the variable-sized last binding, as declared in the layout
+    pub max_count: u32,
+    pub bindings: Vec<BindGroupBindingData>,
     pub layout: BindGroupLayout,
 }
 
@@ -1,11 +1,12 @@
-use super::memory::{Memory, MemoryType};
+use super::memory::{AllocationKind, Memory, MemoryType};
 use crate::{
     device::Device,
-    util::{find_memory_type_index, MemoryMappablePointer},
+    util::{caller_name, find_memory_type_index, MemoryMappablePointer},
 };
 use anyhow::Result;
 pub use ash::vk;
 use bitflags::bitflags;
+use std::panic::Location;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -14,6 +15,8 @@ pub enum BufferError {
     NoSuitableMemoryType,
     #[error("Buffer is not mappable from CPU memory")]
     NotMemoryMappable,
+    #[error("Buffer device addresses are not supported by this device")]
+    DeviceAddressNotSupported,
 }
 
 bitflags! {
@@ -25,6 +28,8 @@ bitflags! {
         const STORAGE = 0x00000020;
         const TRANSFER_SRC = 0x00000001;
         const TRANSFER_DST = 0x00000002;
+        // Requires `DeviceCapabilities::buffer_device_address`
+        const SHADER_DEVICE_ADDRESS = 0x00020000;
     }
 }
 
@@ -34,6 +39,23 @@ impl From<BufferUsage> for vk::BufferUsageFlags {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum IndexType {
+    // Meshes with at most `u16::MAX + 1` vertices, at half the memory
+    U16,
+    #[default]
+    U32,
+}
+
+impl From<IndexType> for vk::IndexType {
+    fn from(value: IndexType) -> Self {
+        match value {
+            IndexType::U16 => vk::IndexType::UINT16,
+            IndexType::U32 => vk::IndexType::UINT32,
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct BufferDescription {
     pub name: Option<&'static str>,
@@ -43,6 +65,7 @@ pub struct BufferDescription {
 
 pub struct Buffer {
     pub raw: vk::Buffer,
+    pub usage: BufferUsage,
     pub memory: Memory,
     pub size_bytes: u64,
     pub num_elements: Option<u32>,
@@ -54,11 +77,26 @@ pub struct Buffer {
 pub struct BindBufferInfo(pub vk::DescriptorBufferInfo);
 
 impl Buffer {
+    #[track_caller]
     pub(crate) fn create(device: &Device, size: u64, desc: BufferDescription) -> Result<Self> {
-        let buffer_info = vk::BufferCreateInfo::builder()
-            .size(size)
-            .usage(desc.usage.into())
-            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        let location = Location::caller();
+        let device_address = desc.usage.contains(BufferUsage::SHADER_DEVICE_ADDRESS);
+        if device_address && !device.capabilities().buffer_device_address {
+            return Err(BufferError::DeviceAddressNotSupported.into());
+        }
+
+        // Storage buffers may be written on the async compute queue and read on the graphics queue
+        let shared_queue_family_indices = device
+            .shared_queue_family_indices()
+            .filter(|_| desc.usage.contains(BufferUsage::STORAGE));
+        let buffer_info = match &shared_queue_family_indices {
+            Some(indices) => vk::BufferCreateInfo::builder()
+                .sharing_mode(vk::SharingMode::CONCURRENT)
+                .queue_family_indices(indices),
+            None => vk::BufferCreateInfo::builder().sharing_mode(vk::SharingMode::EXCLUSIVE),
+        }
+        .size(size)
+        .usage(desc.usage.into());
 
         let buffer = unsafe { device.raw().create_buffer(&buffer_info, None) }?;
         let buffer_memory_req = unsafe { device.raw().get_buffer_memory_requirements(buffer) };
@@ -69,32 +107,25 @@ impl Buffer {
         )
         .ok_or(BufferError::NoSuitableMemoryType)?;
 
-        let allocate_info = vk::MemoryAllocateInfo {
-            allocation_size: buffer_memory_req.size,
-            memory_type_index: buffer_memory_index,
-            ..Default::default()
-        };
-        let buffer_memory = unsafe { device.raw().allocate_memory(&allocate_info, None) }?;
-        unsafe { device.raw().bind_buffer_memory(buffer, buffer_memory, 0) }?;
-
-        let memory = Memory {
-            raw: buffer_memory,
-            req: buffer_memory_req,
-        };
-
-        let ptr = if desc.memory_ty.is_cpu_visible() {
-            Some(memory.ptr(device.raw())?)
-        } else {
-            None
-        };
-
-        if let Some(name) = desc.name {
-            memory.set_name(device, name);
-            device.set_name(vk::ObjectType::BUFFER, buffer, &format!("{name} [Buffer]"));
-        }
+        let memory = device.allocate_memory(
+            buffer_memory_req,
+            buffer_memory_index,
+            AllocationKind::Buffer,
+        )?;
+        unsafe {
+            device
+                .raw()
+                .bind_buffer_memory(buffer, memory.raw, memory.offset)
+        }?;
+
+        let ptr = memory.ptr().filter(|_| desc.memory_ty.is_cpu_visible());
+
+        let name = desc.name.unwrap_or_else(|| caller_name("buffer", location));
+        device.set_name(vk::ObjectType::BUFFER, buffer, &format!("{name} [Buffer]"));
 
         Ok(Buffer {
             raw: buffer,
+            usage: desc.usage,
             memory,
             size_bytes: size,
             num_elements: None,
@@ -110,6 +141,25 @@ impl Buffer {
         })
     }
 
+    // Binds `range` bytes starting at `offset`, e.g. a single element of a dynamic uniform buffer
+    pub fn bind_info_range(&self, offset: u64, range: u64) -> BindBufferInfo {
+        BindBufferInfo(vk::DescriptorBufferInfo {
+            buffer: self.raw,
+            offset,
+            range,
+        })
+    }
+
+    // GPU pointer to the start of the buffer, for `buffer_reference` blocks in shaders
+    pub fn device_address(&self, device: &Device) -> u64 {
+        debug_assert!(
+            self.usage.contains(BufferUsage::SHADER_DEVICE_ADDRESS),
+            "Buffer was not created with `BufferUsage::SHADER_DEVICE_ADDRESS`"
+        );
+        let info = vk::BufferDeviceAddressInfo::builder().buffer(self.raw);
+        unsafe { device.raw().get_buffer_device_address(&info) }
+    }
+
     pub fn size_bytes(&self) -> u64 {
         self.size_bytes
     }
@@ -136,6 +186,14 @@ impl Buffer {
         )
     }
 
+    // Copies the whole buffer back to the CPU, e.g. after a GPU write into a `TRANSFER_DST` buffer
+    pub fn read_bytes(&self) -> Result<Vec<u8>, BufferError> {
+        self.ptr.map_or_else(
+            || Err(BufferError::NotMemoryMappable),
+            |ptr| Ok(ptr.read_bytes(self.size_bytes as usize)),
+        )
+    }
+
     pub fn destroy(&self, device: &Device) {
         unsafe {
             device.raw().destroy_buffer(self.raw, None);
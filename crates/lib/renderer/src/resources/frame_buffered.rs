@@ -0,0 +1,72 @@
+use super::buffer::{BindBufferInfo, Buffer, BufferDescription, BufferError};
+use crate::device::{Device, MAX_FRAMES_IN_FLIGHT};
+use anyhow::Result;
+
+// One copy of a resource per frame in flight, so the CPU can write the copy for the current frame
+// while the GPU may still be reading the others. Always holds `MAX_FRAMES_IN_FLIGHT` copies, of
+// which only the first `Renderer::frames_in_flight` are used. Index with
+// `Renderer::current_frame_in_flight`
+pub struct FrameBuffered<T> {
+    frames: [T; MAX_FRAMES_IN_FLIGHT],
+}
+
+impl<T> FrameBuffered<T> {
+    pub fn new(mut f: impl FnMut(usize) -> Result<T>) -> Result<Self> {
+        let mut frames = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+        for frame_index in 0..MAX_FRAMES_IN_FLIGHT {
+            frames.push(f(frame_index)?);
+        }
+        Ok(Self {
+            frames: frames
+                .try_into()
+                .unwrap_or_else(|_| unreachable!("Created exactly `MAX_FRAMES_IN_FLIGHT` frames")),
+        })
+    }
+
+    // `frame_index` is below `Renderer::frames_in_flight`, which is at most `MAX_FRAMES_IN_FLIGHT`
+    pub fn get(&self, frame_index: usize) -> &T {
+        &self.frames[frame_index]
+    }
+
+    pub fn get_mut(&mut self, frame_index: usize) -> &mut T {
+        &mut self.frames[frame_index]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.frames.iter()
+    }
+}
+
+impl FrameBuffered<Buffer> {
+    pub fn create_buffers(device: &Device, size: u64, desc: BufferDescription) -> Result<Self> {
+        Self::new(|_| device.create_buffer(size, desc))
+    }
+
+    // Only safe for the frame currently being recorded, whose previous use the GPU is done with
+    pub fn write<T: Copy>(
+        &self,
+        frame_index: usize,
+        offset: u64,
+        data: &[T],
+    ) -> Result<(), BufferError> {
+        self.get(frame_index).mem_copy(offset, data)
+    }
+
+    // For data that doesn't change between frames, before any of the copies are in use
+    pub fn write_all<T: Copy>(&self, offset: u64, data: &[T]) -> Result<(), BufferError> {
+        for buffer in self.iter() {
+            buffer.mem_copy(offset, data)?;
+        }
+        Ok(())
+    }
+
+    pub fn current_bind_info(&self, frame_index: usize) -> BindBufferInfo {
+        self.get(frame_index).bind_info()
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        for buffer in self.iter() {
+            buffer.destroy(device);
+        }
+    }
+}
@@ -1,16 +1,17 @@
 use super::{
-    memory::{Memory, MemoryType},
+    memory::{AllocationKind, Memory, MemoryType},
     sampler::Sampler,
 };
 use crate::{
-    device::Device,
-    util::{find_memory_type_index, MemoryMappablePointer},
+    device::{Device, SampleCount},
+    util::{caller_name, find_memory_type_index, MemoryMappablePointer},
 };
 use anyhow::Result;
 use ash::vk;
 use math::size::Size2D;
 use rust_shader_tools::ReflectFormat;
 use serde::Deserialize;
+use std::{panic::Location, sync::Mutex};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -19,6 +20,22 @@ pub enum ImageError {
     NoSuitableMemoryType,
     #[error("Buffer is not mappable from CPU memory")]
     NotMemoryMappable,
+    #[error("Image data is {actual} bytes, expected {expected}")]
+    DataSizeMismatch { expected: usize, actual: usize },
+    #[error("Image data has {actual} mip levels, expected {expected}")]
+    MipLevelCountMismatch { expected: usize, actual: usize },
+    #[error("Format {0:?} can't be blitted to generate mip levels, create the image with `Renderer::create_image_with_data` instead")]
+    LinearBlitNotSupported(Format),
+    #[error("Subresource range {range:?} is outside of an image with {mip_levels} mip levels and {array_layers} layers")]
+    SubresourceOutOfRange {
+        range: SubresourceRange,
+        mip_levels: u32,
+        array_layers: u32,
+    },
+    #[error("An image of format {image:?} can't be viewed as {view:?}, which needs `ImageDescription::mutable_format` and the same texel size")]
+    IncompatibleViewFormat { image: Format, view: Format },
+    #[error("Unsupported image format: {0:?}")]
+    UnsupportedFormat(vk::Format),
 }
 
 pub fn reflect_format_to_vk(fmt: ReflectFormat) -> vk::Format {
@@ -47,12 +64,18 @@ pub enum Format {
     B8G8R8A8_UNORM,
     D32_SFLOAT,
     D16_UNORM,
+    D24_UNORM_S8_UINT,
+    D32_SFLOAT_S8_UINT,
     R32G32B32A32_SFLOAT,
     R32G32B32_SFLOAT,
     R32G32_SFLOAT,
     R32_SFLOAT,
     R16G16B16A16_SFLOAT,
     R16G16_SFLOAT,
+    // Block-compressed, uploaded as-is with every mip level precomputed
+    BC5_UNORM,
+    BC7_UNORM,
+    BC7_SRGB,
 }
 
 impl Default for Format {
@@ -69,42 +92,121 @@ impl From<Format> for vk::Format {
             Format::B8G8R8A8_UNORM => vk::Format::B8G8R8A8_UNORM,
             Format::D32_SFLOAT => vk::Format::D32_SFLOAT,
             Format::D16_UNORM => vk::Format::D16_UNORM,
+            Format::D24_UNORM_S8_UINT => vk::Format::D24_UNORM_S8_UINT,
+            Format::D32_SFLOAT_S8_UINT => vk::Format::D32_SFLOAT_S8_UINT,
             Format::R32G32B32A32_SFLOAT => vk::Format::R32G32B32A32_SFLOAT,
             Format::R32G32B32_SFLOAT => vk::Format::R32G32B32_SFLOAT,
             Format::R32G32_SFLOAT => vk::Format::R32G32_SFLOAT,
             Format::R32_SFLOAT => vk::Format::R32_SFLOAT,
             Format::R16G16B16A16_SFLOAT => vk::Format::R16G16B16A16_SFLOAT,
             Format::R16G16_SFLOAT => vk::Format::R16G16_SFLOAT,
+            Format::BC5_UNORM => vk::Format::BC5_UNORM_BLOCK,
+            Format::BC7_UNORM => vk::Format::BC7_UNORM_BLOCK,
+            Format::BC7_SRGB => vk::Format::BC7_SRGB_BLOCK,
         }
     }
 }
 
-impl From<vk::Format> for Format {
-    fn from(vk: vk::Format) -> Self {
-        match vk {
+impl TryFrom<vk::Format> for Format {
+    type Error = ImageError;
+
+    fn try_from(vk: vk::Format) -> Result<Self, Self::Error> {
+        Ok(match vk {
             vk::Format::R8G8B8A8_UNORM => Self::R8G8B8A8_UNORM,
             vk::Format::R8G8B8A8_SRGB => Self::R8G8B8A8_SRGB,
             vk::Format::B8G8R8A8_UNORM => Self::B8G8R8A8_UNORM,
             vk::Format::D32_SFLOAT => Self::D32_SFLOAT,
             vk::Format::D16_UNORM => Self::D16_UNORM,
+            vk::Format::D24_UNORM_S8_UINT => Self::D24_UNORM_S8_UINT,
+            vk::Format::D32_SFLOAT_S8_UINT => Self::D32_SFLOAT_S8_UINT,
             vk::Format::R32G32B32A32_SFLOAT => Self::R32G32B32A32_SFLOAT,
             vk::Format::R32G32B32_SFLOAT => Self::R32G32B32_SFLOAT,
             vk::Format::R32G32_SFLOAT => Self::R32G32_SFLOAT,
             vk::Format::R32_SFLOAT => Self::R32_SFLOAT,
             vk::Format::R16G16B16A16_SFLOAT => Self::R16G16B16A16_SFLOAT,
             vk::Format::R16G16_SFLOAT => Self::R16G16_SFLOAT,
-            _ => panic!("Unsupported image format: {vk:?}"),
+            vk::Format::BC5_UNORM_BLOCK => Self::BC5_UNORM,
+            vk::Format::BC7_UNORM_BLOCK => Self::BC7_UNORM,
+            vk::Format::BC7_SRGB_BLOCK => Self::BC7_SRGB,
+            _ => return Err(ImageError::UnsupportedFormat(vk)),
+        })
+    }
+}
+
+impl Format {
+    pub fn is_depth(&self) -> bool {
+        matches!(
+            self,
+            Format::D32_SFLOAT
+                | Format::D16_UNORM
+                | Format::D24_UNORM_S8_UINT
+                | Format::D32_SFLOAT_S8_UINT
+        )
+    }
+
+    pub fn has_stencil(&self) -> bool {
+        matches!(self, Format::D24_UNORM_S8_UINT | Format::D32_SFLOAT_S8_UINT)
+    }
+
+    // `VkFormat` value, e.g. as stored in a KTX2 header. `None` for formats without a variant
+    pub fn from_vk_raw(raw: u32) -> Option<Self> {
+        Self::try_from(vk::Format::from_raw(raw as i32)).ok()
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        matches!(
+            self,
+            Format::BC5_UNORM | Format::BC7_UNORM | Format::BC7_SRGB
+        )
+    }
+
+    // Width and height in pixels of a compressed block, 1 for uncompressed formats
+    pub fn block_extent(&self) -> u32 {
+        if self.is_compressed() {
+            4
+        } else {
+            1
+        }
+    }
+
+    pub fn bytes_per_block(&self) -> u32 {
+        match self {
+            Format::D16_UNORM => 2,
+            Format::R8G8B8A8_UNORM
+            | Format::R8G8B8A8_SRGB
+            | Format::B8G8R8A8_UNORM
+            | Format::D32_SFLOAT
+            | Format::D24_UNORM_S8_UINT
+            | Format::R32_SFLOAT
+            | Format::R16G16_SFLOAT => 4,
+            // Texel block size from the spec, implementations may pad it
+            Format::D32_SFLOAT_S8_UINT => 5,
+            Format::R32G32_SFLOAT | Format::R16G16B16A16_SFLOAT => 8,
+            Format::R32G32B32_SFLOAT => 12,
+            Format::R32G32B32A32_SFLOAT
+            | Format::BC5_UNORM
+            | Format::BC7_UNORM
+            | Format::BC7_SRGB => 16,
         }
     }
+
+    pub fn bytes_per_pixel(&self) -> u32 {
+        debug_assert!(
+            !self.is_compressed(),
+            "{self:?} has no per-pixel size, use `bytes_per_block`"
+        );
+        self.bytes_per_block()
+    }
 }
 
-#[derive(Debug, Clone, Copy, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 pub enum Layout {
     Undefined,
     General,
     ColorAttachment,
     DepthAttachment,
     Present,
+    TransferSrc,
     TransferDst,
     ShaderReadOnly,
     DepthStencilReadOnly,
@@ -124,6 +226,7 @@ impl From<Layout> for vk::ImageLayout {
             Layout::ColorAttachment => vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
             Layout::DepthAttachment => vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
             Layout::Present => vk::ImageLayout::PRESENT_SRC_KHR,
+            Layout::TransferSrc => vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
             Layout::TransferDst => vk::ImageLayout::TRANSFER_DST_OPTIMAL,
             Layout::ShaderReadOnly => vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
             Layout::DepthStencilReadOnly => vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
@@ -137,6 +240,7 @@ pub enum ImageUsage {
     DepthSampled,
     Texture,
     StorageTexture,
+    ColorAttachment,
 }
 
 impl Default for ImageUsage {
@@ -154,12 +258,35 @@ impl ImageUsage {
 impl From<ImageUsage> for vk::ImageUsageFlags {
     fn from(usage: ImageUsage) -> Self {
         match usage {
-            ImageUsage::Depth => vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            // Transfer destination allows clearing outside of a render pass
+            ImageUsage::Depth => {
+                vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_DST
+            }
             ImageUsage::DepthSampled => {
-                vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::SAMPLED
+                vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT
+                    | vk::ImageUsageFlags::SAMPLED
+                    | vk::ImageUsageFlags::TRANSFER_DST
+            }
+            // Transfer source is needed to blit between mip levels
+            ImageUsage::Texture => {
+                vk::ImageUsageFlags::TRANSFER_SRC
+                    | vk::ImageUsageFlags::TRANSFER_DST
+                    | vk::ImageUsageFlags::SAMPLED
+            }
+            // Sampled so later passes can read what compute wrote, and transfer destination so
+            // it can be cleared before the writes
+            ImageUsage::StorageTexture => {
+                vk::ImageUsageFlags::STORAGE
+                    | vk::ImageUsageFlags::SAMPLED
+                    | vk::ImageUsageFlags::TRANSFER_DST
+            }
+            // Transfer usages allow resolving and blitting between attachments
+            ImageUsage::ColorAttachment => {
+                vk::ImageUsageFlags::COLOR_ATTACHMENT
+                    | vk::ImageUsageFlags::SAMPLED
+                    | vk::ImageUsageFlags::TRANSFER_SRC
+                    | vk::ImageUsageFlags::TRANSFER_DST
             }
-            ImageUsage::Texture => vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
-            ImageUsage::StorageTexture => vk::ImageUsageFlags::STORAGE,
         }
     }
 }
@@ -170,16 +297,72 @@ impl From<ImageUsage> for vk::ImageAspectFlags {
             ImageUsage::Depth | ImageUsage::DepthSampled => vk::ImageAspectFlags::DEPTH,
             ImageUsage::Texture => vk::ImageAspectFlags::COLOR,
             ImageUsage::StorageTexture => vk::ImageAspectFlags::COLOR,
+            ImageUsage::ColorAttachment => vk::ImageAspectFlags::COLOR,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageDimension {
+    D2,
+    D3,
+    // Six square 2D layers, one per face in +X, -X, +Y, -Y, +Z, -Z order
+    Cube,
+}
+
+impl Default for ImageDimension {
+    fn default() -> Self {
+        Self::D2
+    }
+}
+
+impl From<ImageDimension> for vk::ImageType {
+    fn from(dimension: ImageDimension) -> Self {
+        match dimension {
+            ImageDimension::D2 | ImageDimension::Cube => vk::ImageType::TYPE_2D,
+            ImageDimension::D3 => vk::ImageType::TYPE_3D,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageViewType {
+    D2,
+    // Sampled as `sampler2DArray`
+    Array2D,
+    D3,
+    // Sampled as `samplerCube`
+    Cube,
+}
+
+impl From<ImageViewType> for vk::ImageViewType {
+    fn from(ty: ImageViewType) -> Self {
+        match ty {
+            ImageViewType::D2 => vk::ImageViewType::TYPE_2D,
+            ImageViewType::Array2D => vk::ImageViewType::TYPE_2D_ARRAY,
+            ImageViewType::D3 => vk::ImageViewType::TYPE_3D,
+            ImageViewType::Cube => vk::ImageViewType::CUBE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ImageDescription {
     pub name: Option<&'static str>,
     pub format: Format,
     pub usage: ImageUsage,
     pub memory_ty: MemoryType,
+    // Levels past the first are generated when the image is created with data
+    pub mip_levels: u32,
+    pub dimension: ImageDimension,
+    // Only used by 3D images, sampled as `sampler3D`
+    pub depth: Option<u32>,
+    // Only used by 2D images, more than one layer makes an `ImageViewType::Array2D` image
+    pub array_layers: u32,
+    // More than one sample makes a multisampled attachment, resolved with `CommandList::resolve_image`
+    pub samples: SampleCount,
+    // Lets `Image::create_view` view the image with other formats of the same texel size
+    pub mutable_format: bool,
 }
 
 impl Default for ImageDescription {
@@ -189,39 +372,131 @@ impl Default for ImageDescription {
             format: Default::default(),
             usage: Default::default(),
             memory_ty: MemoryType::GpuOnly,
+            mip_levels: 1,
+            dimension: Default::default(),
+            depth: None,
+            array_layers: 1,
+            samples: SampleCount::X1,
+            mutable_format: false,
+        }
+    }
+}
+
+// Number of levels in a full mip chain for an image of the given size
+pub fn max_mip_levels(size: Size2D<u32>) -> u32 {
+    32 - size.width().max(size.height()).max(1).leading_zeros()
+}
+
+impl ImageDescription {
+    pub fn depth(&self) -> u32 {
+        match self.dimension {
+            ImageDimension::D2 | ImageDimension::Cube => 1,
+            ImageDimension::D3 => self.depth.unwrap_or(1),
+        }
+    }
+
+    pub fn array_layers(&self) -> u32 {
+        match self.dimension {
+            ImageDimension::D2 => self.array_layers.max(1),
+            ImageDimension::D3 => 1,
+            ImageDimension::Cube => 6,
+        }
+    }
+
+    pub fn view_type(&self) -> ImageViewType {
+        match self.dimension {
+            ImageDimension::D2 if self.array_layers() > 1 => ImageViewType::Array2D,
+            ImageDimension::D2 => ImageViewType::D2,
+            ImageDimension::D3 => ImageViewType::D3,
+            ImageDimension::Cube => ImageViewType::Cube,
+        }
+    }
+
+    // Bytes needed to fill the first mip level of every layer of an image of the given size
+    pub fn data_size(&self, size: Size2D<u32>) -> usize {
+        self.mip_data_size(size, 0)
+    }
+
+    // Same as `data_size`, for mip level `level`. Compressed formats round up to whole blocks
+    pub fn mip_data_size(&self, size: Size2D<u32>, level: u32) -> usize {
+        let block_extent = self.format.block_extent();
+        let blocks = |extent: u32| (extent >> level).max(1).div_ceil(block_extent) as usize;
+        blocks(size.width())
+            * blocks(size.height())
+            * (self.depth() >> level).max(1) as usize
+            * self.array_layers() as usize
+            * self.format.bytes_per_block() as usize
+    }
+
+    // Sampled views can only see a single aspect, so stencil is only included for attachments
+    pub(crate) fn view_aspect_mask(&self) -> vk::ImageAspectFlags {
+        let aspect_mask = vk::ImageAspectFlags::from(self.usage);
+        if self.usage == ImageUsage::Depth && self.format.has_stencil() {
+            aspect_mask | vk::ImageAspectFlags::STENCIL
+        } else {
+            aspect_mask
         }
     }
 }
 
+// Mip levels and array layers seen through a view, see `Image::create_view`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubresourceRange {
+    pub base_mip_level: u32,
+    pub level_count: u32,
+    pub base_array_layer: u32,
+    pub layer_count: u32,
+}
+
+impl SubresourceRange {
+    // Every layer of a single mip level
+    pub fn mip_level(level: u32, array_layers: u32) -> Self {
+        Self {
+            base_mip_level: level,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: array_layers,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImageViewDescription {
+    // Another format with the same texel size, e.g. SRGB for an UNORM image. Requires
+    // `ImageDescription::mutable_format`, `None` uses the image's format
+    pub format: Option<Format>,
+    // `None` views every level and layer
+    pub range: Option<SubresourceRange>,
+    // `None` picks one from the range, see `Image::create_view`
+    pub view_type: Option<ImageViewType>,
+}
+
+// A view cached on an `Image` by `Image::create_view`, valid until the image is resized or destroyed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageViewId(usize);
+
 pub struct Image {
     pub raw: vk::Image,
     pub size: Size2D<u32>,
     pub desc: ImageDescription,
     pub view: vk::ImageView,
-    pub memory: Memory,
+    // Created by `Image::create_view`, indexed by `ImageViewId`
+    views: Mutex<Vec<(ImageViewDescription, vk::ImageView)>>,
+    // `None` for transient images, which alias memory owned by the `TransientImagePool`
+    pub memory: Option<Memory>,
     pub ptr: Option<MemoryMappablePointer>,
 }
 
 impl Image {
+    #[track_caller]
     pub fn create(device: &Device, size: Size2D<u32>, desc: ImageDescription) -> Result<Self> {
-        let create_info = vk::ImageCreateInfo::builder()
-            .image_type(vk::ImageType::TYPE_2D)
-            .format(desc.format.into())
-            .extent(vk::Extent3D {
-                width: size.width(),
-                height: size.height(),
-                depth: 1,
-            })
-            .mip_levels(1)
-            .array_layers(1)
-            .samples(vk::SampleCountFlags::TYPE_1)
-            .tiling(vk::ImageTiling::OPTIMAL)
-            .usage(desc.usage.into())
-            .sharing_mode(vk::SharingMode::EXCLUSIVE)
-            .build();
-
-        let image = unsafe { device.raw().create_image(&create_info, None) }?;
-        let memory_req = unsafe { device.raw().get_image_memory_requirements(image) };
+        // Unnamed images are named after where they were created, which is kept across resizes
+        let location = Location::caller();
+        let desc = ImageDescription {
+            name: Some(desc.name.unwrap_or_else(|| caller_name("image", location))),
+            ..desc
+        };
+        let (image, memory_req) = Self::create_unbound(device, size, desc)?;
         let memory_index = find_memory_type_index(
             &memory_req,
             device.memopry_properties(),
@@ -229,42 +504,92 @@ impl Image {
         )
         .ok_or(ImageError::NoSuitableMemoryType)?;
 
-        let allocate_info = vk::MemoryAllocateInfo {
-            allocation_size: memory_req.size,
-            memory_type_index: memory_index,
-            ..Default::default()
-        };
-        let memory = unsafe { device.raw().allocate_memory(&allocate_info, None) }?;
+        let memory = device.allocate_memory(memory_req, memory_index, AllocationKind::Image)?;
         unsafe {
-            device.raw().bind_image_memory(image, memory, 0)?;
+            device
+                .raw()
+                .bind_image_memory(image, memory.raw, memory.offset)?;
         }
 
+        Self::from_bound(device, image, size, desc, Some(memory))
+    }
+
+    // Creates the image without any memory bound to it, along with what that memory needs to be
+    pub(crate) fn create_unbound(
+        device: &Device,
+        size: Size2D<u32>,
+        desc: ImageDescription,
+    ) -> Result<(vk::Image, vk::MemoryRequirements)> {
+        let mut usage = vk::ImageUsageFlags::from(desc.usage);
+        if device.needs_compute_mipmaps(&desc) {
+            usage |= vk::ImageUsageFlags::STORAGE;
+        }
+        // Storage images may be written on the async compute queue and read on the graphics queue
+        let shared_queue_family_indices = device
+            .shared_queue_family_indices()
+            .filter(|_| matches!(desc.usage, ImageUsage::StorageTexture));
+        let create_info = match &shared_queue_family_indices {
+            Some(indices) => vk::ImageCreateInfo::builder()
+                .sharing_mode(vk::SharingMode::CONCURRENT)
+                .queue_family_indices(indices),
+            None => vk::ImageCreateInfo::builder().sharing_mode(vk::SharingMode::EXCLUSIVE),
+        }
+        .flags({
+            let mut flags = vk::ImageCreateFlags::empty();
+            if desc.dimension == ImageDimension::Cube {
+                flags |= vk::ImageCreateFlags::CUBE_COMPATIBLE;
+            }
+            if desc.mutable_format {
+                flags |= vk::ImageCreateFlags::MUTABLE_FORMAT;
+            }
+            flags
+        })
+        .image_type(desc.dimension.into())
+        .format(desc.format.into())
+        .extent(vk::Extent3D {
+            width: size.width(),
+            height: size.height(),
+            depth: desc.depth(),
+        })
+        .mip_levels(desc.mip_levels)
+        .array_layers(desc.array_layers())
+        .samples(desc.samples.into())
+        .tiling(vk::ImageTiling::OPTIMAL)
+        .usage(usage)
+        .build();
+
+        let image = unsafe { device.raw().create_image(&create_info, None) }?;
+        let memory_req = unsafe { device.raw().get_image_memory_requirements(image) };
+        Ok((image, memory_req))
+    }
+
+    // Creates the views of an image that already has memory bound to it
+    pub(crate) fn from_bound(
+        device: &Device,
+        image: vk::Image,
+        size: Size2D<u32>,
+        desc: ImageDescription,
+        memory: Option<Memory>,
+    ) -> Result<Self> {
         let image_view_info = vk::ImageViewCreateInfo::builder()
             .subresource_range(
                 vk::ImageSubresourceRange::builder()
-                    .aspect_mask(desc.usage.into())
-                    .level_count(1)
-                    .layer_count(1)
+                    .aspect_mask(desc.view_aspect_mask())
+                    .level_count(desc.mip_levels)
+                    .layer_count(desc.array_layers())
                     .build(),
             )
             .image(image)
             .format(desc.format.into())
-            .view_type(vk::ImageViewType::TYPE_2D);
+            .view_type(desc.view_type().into());
         let view = unsafe { device.raw().create_image_view(&image_view_info, None) }?;
 
-        let memory = Memory {
-            raw: memory,
-            req: memory_req,
-        };
-
-        let ptr = if desc.memory_ty.is_cpu_visible() {
-            Some(memory.ptr(device.raw())?)
-        } else {
-            None
-        };
+        let ptr = memory
+            .as_ref()
+            .and_then(Memory::ptr)
+            .filter(|_| desc.memory_ty.is_cpu_visible());
 
         if let Some(name) = desc.name {
-            memory.set_name(device, name);
             device.set_name(vk::ObjectType::IMAGE, image, &format!("{name} [Image]"));
             device.set_name(
                 vk::ObjectType::IMAGE_VIEW,
@@ -277,6 +602,7 @@ impl Image {
             raw: image,
             size,
             view,
+            views: Default::default(),
             memory,
             desc,
             ptr,
@@ -291,6 +617,154 @@ impl Image {
         self.desc.format
     }
 
+    pub fn depth(&self) -> u32 {
+        self.desc.depth()
+    }
+
+    pub fn mip_levels(&self) -> u32 {
+        self.desc.mip_levels
+    }
+
+    pub fn array_layers(&self) -> u32 {
+        self.desc.array_layers()
+    }
+
+    // 2D view of the first mip level of a single layer, or the full view for single layer images.
+    // See `Image::create_view`
+    pub fn layer_view(&self, device: &Device, layer: u32) -> Result<vk::ImageView> {
+        if self.array_layers() == 1 && layer == 0 {
+            return Ok(self.view);
+        }
+        self.range_view(
+            device,
+            SubresourceRange {
+                base_mip_level: 0,
+                level_count: 1,
+                base_array_layer: layer,
+                layer_count: 1,
+            },
+        )
+    }
+
+    // View of every layer of a single mip level. Cubemaps are viewed as a 2D array, so each level
+    // can be written as an `image2DArray`. See `Image::create_view`
+    pub fn mip_view(&self, device: &Device, level: u32) -> Result<vk::ImageView> {
+        let view_type = (self.array_layers() > 1).then_some(ImageViewType::Array2D);
+        let id = self.create_view(
+            device,
+            ImageViewDescription {
+                range: Some(SubresourceRange::mip_level(level, self.array_layers())),
+                view_type,
+                ..Default::default()
+            },
+        )?;
+        Ok(self.view(id))
+    }
+
+    // Creates a view of the image, or finds the one created earlier with the same description.
+    // Without a `view_type`, single layers are viewed as 2D images, six layers of a cubemap as a
+    // cube and anything else as a 2D array
+    pub fn create_view(&self, device: &Device, desc: ImageViewDescription) -> Result<ImageViewId> {
+        let range = desc.range.unwrap_or(SubresourceRange {
+            base_mip_level: 0,
+            level_count: self.mip_levels(),
+            base_array_layer: 0,
+            layer_count: self.array_layers(),
+        });
+        let in_bounds = range.level_count > 0
+            && range.layer_count > 0
+            && range.base_mip_level.saturating_add(range.level_count) <= self.mip_levels()
+            && range.base_array_layer.saturating_add(range.layer_count) <= self.array_layers();
+        if !in_bounds {
+            return Err(ImageError::SubresourceOutOfRange {
+                range,
+                mip_levels: self.mip_levels(),
+                array_layers: self.array_layers(),
+            }
+            .into());
+        }
+        let format = desc.format.unwrap_or(self.format());
+        let compatible = format == self.format()
+            || (self.desc.mutable_format
+                && format.is_compressed() == self.format().is_compressed()
+                && format.bytes_per_block() == self.format().bytes_per_block());
+        if !compatible {
+            return Err(ImageError::IncompatibleViewFormat {
+                image: self.format(),
+                view: format,
+            }
+            .into());
+        }
+
+        let view_type = desc.view_type.unwrap_or(match self.desc.view_type() {
+            ImageViewType::D3 => ImageViewType::D3,
+            _ if range.layer_count == 1 => ImageViewType::D2,
+            ImageViewType::Cube if range.layer_count == 6 => ImageViewType::Cube,
+            _ => ImageViewType::Array2D,
+        });
+
+        // Cached with every field filled in, so `None` and the same explicit value share a view
+        let desc = ImageViewDescription {
+            format: Some(format),
+            range: Some(range),
+            view_type: Some(view_type),
+        };
+        let mut views = self.views.lock().expect("Mutex lock poisoned");
+        if let Some(idx) = views.iter().position(|(view_desc, _)| *view_desc == desc) {
+            return Ok(ImageViewId(idx));
+        }
+        let view_info = vk::ImageViewCreateInfo::builder()
+            .subresource_range(
+                vk::ImageSubresourceRange::builder()
+                    .aspect_mask(self.desc.view_aspect_mask())
+                    .base_mip_level(range.base_mip_level)
+                    .level_count(range.level_count)
+                    .base_array_layer(range.base_array_layer)
+                    .layer_count(range.layer_count)
+                    .build(),
+            )
+            .image(self.raw)
+            .format(format.into())
+            .view_type(view_type.into());
+        let view = unsafe { device.raw().create_image_view(&view_info, None) }?;
+        if let Some(name) = self.desc.name {
+            device.set_name(
+                vk::ObjectType::IMAGE_VIEW,
+                view,
+                &format!(
+                    "{name} [Image View {format:?} Mips {}..{} Layers {}..{}]",
+                    range.base_mip_level,
+                    range.base_mip_level + range.level_count,
+                    range.base_array_layer,
+                    range.base_array_layer + range.layer_count,
+                ),
+            );
+        }
+        views.push((desc, view));
+        Ok(ImageViewId(views.len() - 1))
+    }
+
+    // A view returned by `Image::create_view`
+    pub fn view(&self, id: ImageViewId) -> vk::ImageView {
+        self.views.lock().expect("Mutex lock poisoned")[id.0].1
+    }
+
+    pub fn view_description(&self, id: ImageViewId) -> ImageViewDescription {
+        self.views.lock().expect("Mutex lock poisoned")[id.0].0
+    }
+
+    // View of `range` in the image's own format, see `Image::create_view`
+    pub fn range_view(&self, device: &Device, range: SubresourceRange) -> Result<vk::ImageView> {
+        let id = self.create_view(
+            device,
+            ImageViewDescription {
+                range: Some(range),
+                ..Default::default()
+            },
+        )?;
+        Ok(self.view(id))
+    }
+
     pub fn mem_copy<T: Copy>(&self, offset: u64, data: &[T]) -> Result<(), ImageError> {
         self.ptr.map_or_else(
             || Err(ImageError::NotMemoryMappable),
@@ -311,7 +785,12 @@ impl Image {
         unsafe {
             device.raw().destroy_image(self.raw, None);
             device.raw().destroy_image_view(self.view, None);
-            self.memory.destroy(device);
+            for (_, view) in self.views.get_mut().expect("Mutex lock poisoned").drain(..) {
+                device.raw().destroy_image_view(view, None);
+            }
+            if let Some(memory) = &self.memory {
+                memory.destroy(device);
+            }
         }
     }
 }
@@ -338,4 +817,111 @@ impl Image {
             index: index.unwrap_or(0),
         }
     }
+
+    // Binds only `range` of the image to be sampled, e.g. a single mip level or some of its layers.
+    // See `Image::range_view`
+    pub fn bind_info_range(
+        &self,
+        device: &Device,
+        sampler: &Sampler,
+        image_layout: Layout,
+        range: SubresourceRange,
+        index: Option<u32>,
+    ) -> Result<BindImageInfo> {
+        Ok(BindImageInfo {
+            info: vk::DescriptorImageInfo {
+                image_layout: image_layout.into(),
+                image_view: self.range_view(device, range)?,
+                sampler: sampler.raw,
+            },
+            index: index.unwrap_or(0),
+        })
+    }
+
+    // Binds a view returned by `Image::create_view`, to be sampled
+    pub fn bind_info_view(
+        &self,
+        sampler: &Sampler,
+        image_layout: Layout,
+        view: ImageViewId,
+        index: Option<u32>,
+    ) -> BindImageInfo {
+        BindImageInfo {
+            info: vk::DescriptorImageInfo {
+                image_layout: image_layout.into(),
+                image_view: self.view(view),
+                sampler: sampler.raw,
+            },
+            index: index.unwrap_or(0),
+        }
+    }
+
+    // Binds the image for `BindGroupWriteData::StorageImage`, which is always accessed in the
+    // `General` layout and without a sampler
+    pub fn storage_bind_info(&self, index: Option<u32>) -> BindImageInfo {
+        BindImageInfo {
+            info: vk::DescriptorImageInfo {
+                image_layout: Layout::General.into(),
+                image_view: self.view,
+                sampler: vk::Sampler::null(),
+            },
+            index: index.unwrap_or(0),
+        }
+    }
+
+    // Binds a single mip level, read with `texelFetch` or written as a storage image while the
+    // other levels are generated. See `Image::mip_view`
+    pub fn mip_bind_info(
+        &self,
+        device: &Device,
+        sampler: Option<&Sampler>,
+        image_layout: Layout,
+        level: u32,
+    ) -> Result<BindImageInfo> {
+        Ok(BindImageInfo {
+            info: vk::DescriptorImageInfo {
+                image_layout: image_layout.into(),
+                image_view: self.mip_view(device, level)?,
+                sampler: sampler.map_or(vk::Sampler::null(), |sampler| sampler.raw),
+            },
+            index: 0,
+        })
+    }
+
+    // Binds a single layer of an array or cube image for `BindGroupWriteData::StorageImage`, to be
+    // written as an `image2D`
+    pub fn storage_bind_info_layer(
+        &self,
+        device: &Device,
+        layer: u32,
+        index: Option<u32>,
+    ) -> Result<BindImageInfo> {
+        Ok(BindImageInfo {
+            info: vk::DescriptorImageInfo {
+                image_layout: Layout::General.into(),
+                image_view: self.layer_view(device, layer)?,
+                sampler: vk::Sampler::null(),
+            },
+            index: index.unwrap_or(0),
+        })
+    }
+
+    // Binds a single layer of an array image, to be sampled as a `sampler2D`
+    pub fn bind_info_layer(
+        &self,
+        device: &Device,
+        sampler: &Sampler,
+        image_layout: Layout,
+        layer: u32,
+        index: Option<u32>,
+    ) -> Result<BindImageInfo> {
+        Ok(BindImageInfo {
+            info: vk::DescriptorImageInfo {
+                image_layout: image_layout.into(),
+                image_view: self.layer_view(device, layer)?,
+                sampler: sampler.raw,
+            },
+            index: index.unwrap_or(0),
+        })
+    }
 }
@@ -1,8 +1,12 @@
 use super::{
-    buffer::Buffer, image::Image, pipeline::graphics::GraphicsPipeline, sampler::Sampler,
+    buffer::Buffer,
+    image::Image,
+    pipeline::{compute::ComputePipeline, graphics::GraphicsPipeline},
+    sampler::Sampler,
     shader::Shader,
 };
-use crate::device::{Device, MAX_FRAMES_IN_FLIGHT};
+use crate::device::Device;
+use anyhow::Result;
 use ash::vk;
 use resource_manager::{ResourceId, ResourcePool};
 use thiserror::Error;
@@ -19,6 +23,7 @@ pub enum ResourceManagerError {
 
 pub enum Resource {
     GraphicsPipeline(GraphicsPipeline),
+    ComputePipeline(ComputePipeline),
     RawPipeline(vk::Pipeline),
     Shader(Shader),
     Image(Image),
@@ -26,7 +31,51 @@ pub enum Resource {
     Sampler(Sampler),
 }
 
-type DeleteQueue = Vec<Resource>;
+impl Resource {
+    fn destroy(&mut self, device: &Device) {
+        match self {
+            Resource::GraphicsPipeline(pipeline) => pipeline.destroy(device),
+            Resource::ComputePipeline(pipeline) => pipeline.destroy(device),
+            Resource::RawPipeline(pipeline) => unsafe {
+                device.raw().destroy_pipeline(*pipeline, None)
+            },
+            Resource::Shader(shader) => shader.destroy(device),
+            Resource::Image(image) => image.destroy(device),
+            Resource::Buffer(buffer) => buffer.destroy(device),
+            Resource::Sampler(sampler) => sampler.destroy(device),
+        }
+    }
+}
+
+// Resources owned by a `ResourceManager` pool, which can be queued for destruction by type
+pub trait ManagedResource: Sized {
+    fn pool(manager: &mut ResourceManager) -> &mut ResourcePool<Self>;
+    fn into_resource(self) -> Resource;
+}
+
+macro_rules! managed_resource {
+    ($t:ty, $field:ident, $k:ident) => {
+        impl ManagedResource for $t {
+            fn pool(manager: &mut ResourceManager) -> &mut ResourcePool<Self> {
+                &mut manager.$field
+            }
+
+            fn into_resource(self) -> Resource {
+                Resource::$k(self)
+            }
+        }
+    };
+}
+
+managed_resource!(GraphicsPipeline, graphics_pipelines, GraphicsPipeline);
+managed_resource!(ComputePipeline, compute_pipelines, ComputePipeline);
+managed_resource!(Shader, shaders, Shader);
+managed_resource!(Image, images, Image);
+managed_resource!(Buffer, buffers, Buffer);
+managed_resource!(Sampler, samplers, Sampler);
+
+// Resources are paired with the submit value after which the GPU no longer uses them
+type DeleteQueue = Vec<(u64, Resource)>;
 
 macro_rules! insert {
     ($fn_name:ident,  $field:ident, $t:ty) => {
@@ -39,14 +88,14 @@ macro_rules! insert {
 
 macro_rules! delete {
     ($fn_name:ident, $fn_name_raw:ident, $field:ident, $t:ty, $k:ident) => {
-        pub fn $fn_name(&mut self, handle: ResourceId<$t>, current_frame_index: usize) {
+        pub fn $fn_name(&mut self, handle: ResourceId<$t>, submit_value: u64) {
             if let Some(old) = self.$field.remove(handle) {
-                self.to_consume[current_frame_index].push(Resource::$k(old));
+                self.to_consume.push((submit_value, Resource::$k(old)));
             }
         }
 
-        pub fn $fn_name_raw(&mut self, res: $t, current_frame_index: usize) {
-            self.to_consume[current_frame_index].push(Resource::$k(res));
+        pub fn $fn_name_raw(&mut self, res: $t, submit_value: u64) {
+            self.to_consume.push((submit_value, Resource::$k(res)));
         }
     };
 }
@@ -54,11 +103,12 @@ macro_rules! delete {
 #[derive(Default)]
 pub struct ResourceManager {
     pub graphics_pipelines: ResourcePool<GraphicsPipeline>,
+    pub compute_pipelines: ResourcePool<ComputePipeline>,
     pub shaders: ResourcePool<Shader>,
     pub images: ResourcePool<Image>,
     pub buffers: ResourcePool<Buffer>,
     pub samplers: ResourcePool<Sampler>,
-    to_consume: [DeleteQueue; MAX_FRAMES_IN_FLIGHT],
+    to_consume: DeleteQueue,
 }
 
 impl ResourceManager {
@@ -66,6 +116,9 @@ impl ResourceManager {
         for res in self.graphics_pipelines.drain() {
             res.destroy(device);
         }
+        for res in self.compute_pipelines.drain() {
+            res.destroy(device);
+        }
         for res in self.shaders.drain() {
             res.destroy(device);
         }
@@ -79,36 +132,39 @@ impl ResourceManager {
             res.destroy(device);
         }
 
-        for _ in 0..MAX_FRAMES_IN_FLIGHT {
-            self.consume(device);
+        for (_, mut res) in self.to_consume.drain(..) {
+            res.destroy(device);
         }
     }
 
-    pub fn consume(&mut self, device: &Device) {
-        let consume_index = device.current_frame_in_flight();
-        for res in &mut self.to_consume[consume_index] {
-            match res {
-                Resource::GraphicsPipeline(pipeline) => pipeline.destroy(device),
-                Resource::RawPipeline(pipeline) => unsafe {
-                    device.raw().destroy_pipeline(*pipeline, None)
-                },
-                Resource::Shader(shader) => shader.destroy(device),
-                Resource::Image(image) => image.destroy(device),
-                Resource::Buffer(buffer) => buffer.destroy(device),
-                Resource::Sampler(sampler) => sampler.destroy(device),
+    pub fn consume(&mut self, device: &Device) -> Result<()> {
+        let completed_value = device.completed_submit_value()?;
+        self.to_consume.retain_mut(|(submit_value, res)| {
+            if *submit_value <= completed_value {
+                res.destroy(device);
+                false
+            } else {
+                true
             }
+        });
+        Ok(())
+    }
+
+    // Removes the resource from its pool, destroying it once `submit_value` has completed
+    pub fn delete<T: ManagedResource>(&mut self, handle: ResourceId<T>, submit_value: u64) {
+        if let Some(old) = T::pool(self).remove(handle) {
+            self.to_consume.push((submit_value, old.into_resource()));
         }
-        self.to_consume[consume_index].clear();
     }
 
-    pub fn replace_shader(
-        &mut self,
-        handle: ResourceId<Shader>,
-        new: Shader,
-        current_frame_index: usize,
-    ) {
+    // Same as `delete`, for resources that were never inserted into a pool
+    pub fn delete_raw<T: ManagedResource>(&mut self, res: T, submit_value: u64) {
+        self.to_consume.push((submit_value, res.into_resource()));
+    }
+
+    pub fn replace_shader(&mut self, handle: ResourceId<Shader>, new: Shader, submit_value: u64) {
         if let Some(old) = self.shaders.replace(handle, new) {
-            self.to_consume[current_frame_index].push(Resource::Shader(old));
+            self.to_consume.push((submit_value, Resource::Shader(old)));
         }
     }
 
@@ -135,8 +191,35 @@ impl ResourceManager {
             }
             .map_err(ResourceManagerError::FallbackError)?;
 
-            self.to_consume[device.current_frame_in_flight()]
-                .push(Resource::RawPipeline(old_raw_pipeline));
+            self.to_consume.push((
+                device.pending_submit_value(),
+                Resource::RawPipeline(old_raw_pipeline),
+            ));
+            Ok(())
+        } else {
+            Err(ResourceManagerError::InvalidPipelineHandle)
+        }
+    }
+
+    pub fn recreate_compute_pipeline(
+        &mut self,
+        device: &Device,
+        pipeline_handle: ResourceId<ComputePipeline>,
+        shader_handle: ResourceId<Shader>,
+    ) -> Result<(), ResourceManagerError> {
+        if let Some(old) = self.compute_pipelines.get_mut(pipeline_handle) {
+            let shader = self
+                .shaders
+                .get(shader_handle)
+                .ok_or(ResourceManagerError::ResourceNotInCache)?;
+            let old_raw_pipeline = old
+                .recreate(shader, device)
+                .map_err(ResourceManagerError::FallbackError)?;
+
+            self.to_consume.push((
+                device.pending_submit_value(),
+                Resource::RawPipeline(old_raw_pipeline),
+            ));
             Ok(())
         } else {
             Err(ResourceManagerError::InvalidPipelineHandle)
@@ -149,6 +232,7 @@ impl ResourceManager {
         graphics_pipelines,
         GraphicsPipeline
     );
+    insert!(insert_compute_pipeline, compute_pipelines, ComputePipeline);
     insert!(insert_shader, shaders, Shader);
     insert!(insert_image, images, Image);
     insert!(insert_buffer, buffers, Buffer);
@@ -162,6 +246,13 @@ impl ResourceManager {
         GraphicsPipeline,
         GraphicsPipeline
     );
+    delete!(
+        delete_compute_pipeline,
+        delete_compute_pipeline_raw,
+        compute_pipelines,
+        ComputePipeline,
+        ComputePipeline
+    );
     delete!(delete_shader, delete_shader_raw, shaders, Shader, Shader);
     delete!(delete_image, delete_image_raw, images, Image, Image);
     delete!(delete_buffer, delete_buffer_raw, buffers, Buffer, Buffer);
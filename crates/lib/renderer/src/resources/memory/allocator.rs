@@ -0,0 +1,366 @@
+use super::Memory;
+use crate::{device::Device, util::MemoryMappablePointer};
+use anyhow::Result;
+use ash::vk;
+use std::sync::Mutex;
+
+// Size of each shared `VkDeviceMemory` block, larger resources get a block of their own
+const BLOCK_SIZE: u64 = 64 * 1024 * 1024;
+
+// Buffers and optimal tiling images never share a block, which sidesteps `bufferImageGranularity`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AllocationKind {
+    Buffer,
+    Image,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FreeRange {
+    offset: u64,
+    size: u64,
+}
+
+struct MemoryBlock {
+    raw: vk::DeviceMemory,
+    memory_type_index: u32,
+    kind: AllocationKind,
+    size: u64,
+    // Sorted by offset, adjacent ranges are always merged
+    free_ranges: Vec<FreeRange>,
+    allocation_count: u32,
+    // Host visible blocks stay mapped for as long as they are alive
+    ptr: Option<MemoryMappablePointer>,
+}
+
+impl MemoryBlock {
+    // First fit, returns the offset of the allocation within the block
+    fn allocate(&mut self, size: u64, alignment: u64) -> Option<u64> {
+        let (idx, offset) = self
+            .free_ranges
+            .iter()
+            .enumerate()
+            .find_map(|(idx, range)| {
+                let offset = align_up(range.offset, alignment);
+                (offset + size <= range.offset + range.size).then_some((idx, offset))
+            })?;
+
+        // Alignment padding in front of the allocation and the space after it stay free
+        let range = self.free_ranges.remove(idx);
+        let mut insert_idx = idx;
+        if offset > range.offset {
+            self.free_ranges.insert(
+                insert_idx,
+                FreeRange {
+                    offset: range.offset,
+                    size: offset - range.offset,
+                },
+            );
+            insert_idx += 1;
+        }
+        let end = offset + size;
+        let range_end = range.offset + range.size;
+        if range_end > end {
+            self.free_ranges.insert(
+                insert_idx,
+                FreeRange {
+                    offset: end,
+                    size: range_end - end,
+                },
+            );
+        }
+
+        self.allocation_count += 1;
+        Some(offset)
+    }
+
+    fn free(&mut self, offset: u64, size: u64) {
+        let idx = self
+            .free_ranges
+            .partition_point(|range| range.offset < offset);
+        self.free_ranges.insert(idx, FreeRange { offset, size });
+
+        // Merge with the following range first, so `idx` stays valid for the preceding one
+        if idx + 1 < self.free_ranges.len() && offset + size == self.free_ranges[idx + 1].offset {
+            self.free_ranges[idx].size += self.free_ranges.remove(idx + 1).size;
+        }
+        if idx > 0 {
+            let previous = self.free_ranges[idx - 1];
+            if previous.offset + previous.size == offset {
+                self.free_ranges[idx - 1].size += self.free_ranges.remove(idx).size;
+            }
+        }
+
+        self.allocation_count -= 1;
+    }
+
+    fn free_bytes(&self) -> u64 {
+        self.free_ranges.iter().map(|range| range.size).sum()
+    }
+}
+
+fn align_up(value: u64, alignment: u64) -> u64 {
+    (value + alignment - 1) / alignment * alignment
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemoryStats {
+    // `VkDeviceMemory` objects currently allocated
+    pub block_count: u32,
+    // Buffers and images backed by those blocks
+    pub allocation_count: u32,
+    pub reserved_bytes: u64,
+    pub used_bytes: u64,
+    pub free_range_count: u32,
+    pub largest_free_range: u64,
+}
+
+impl MemoryStats {
+    // 0.0 when all free space is in a single range, approaching 1.0 as it gets split up
+    pub fn fragmentation(&self) -> f32 {
+        let free_bytes = self.reserved_bytes - self.used_bytes;
+        if free_bytes == 0 {
+            0.0
+        } else {
+            1.0 - self.largest_free_range as f32 / free_bytes as f32
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HeapBudget {
+    // Index into `VkPhysicalDeviceMemoryProperties::memoryHeaps`
+    pub heap: u32,
+    // VRAM on discrete GPUs
+    pub device_local: bool,
+    pub used: u64,
+    // How much the process can use before allocations start failing or degrading performance
+    pub budget: u64,
+}
+
+// Backs many buffers and images with each `VkDeviceMemory` allocation, keeping the allocation
+// count far below `maxMemoryAllocationCount`
+#[derive(Default)]
+pub struct MemoryAllocator {
+    blocks: Mutex<Vec<MemoryBlock>>,
+}
+
+impl MemoryAllocator {
+    pub(crate) fn allocate(
+        &self,
+        device: &Device,
+        req: vk::MemoryRequirements,
+        memory_type_index: u32,
+        kind: AllocationKind,
+    ) -> Result<Memory> {
+        let mut blocks = self.blocks.lock().unwrap();
+        let existing = blocks
+            .iter_mut()
+            .filter(|block| block.memory_type_index == memory_type_index && block.kind == kind)
+            .find_map(|block| {
+                block
+                    .allocate(req.size, req.alignment)
+                    .map(|offset| (block.raw, block.ptr, offset))
+            });
+        let (raw, block_ptr, offset) = match existing {
+            Some(allocation) => allocation,
+            None => {
+                let mut block =
+                    Self::create_block(device, memory_type_index, kind, req.size.max(BLOCK_SIZE))?;
+                let offset = block
+                    .allocate(req.size, req.alignment)
+                    .expect("New memory block is too small for its first allocation");
+                let allocation = (block.raw, block.ptr, offset);
+                blocks.push(block);
+                allocation
+            }
+        };
+
+        Ok(Memory {
+            raw,
+            req,
+            offset,
+            ptr: block_ptr.map(|ptr| ptr.add(offset as usize)),
+        })
+    }
+
+    fn create_block(
+        device: &Device,
+        memory_type_index: u32,
+        kind: AllocationKind,
+        size: u64,
+    ) -> Result<MemoryBlock> {
+        // Any buffer block may end up backing a `BufferUsage::SHADER_DEVICE_ADDRESS` buffer
+        let mut allocate_flags_info = vk::MemoryAllocateFlagsInfo::builder()
+            .flags(vk::MemoryAllocateFlags::DEVICE_ADDRESS)
+            .build();
+        let mut allocate_info = vk::MemoryAllocateInfo::builder()
+            .allocation_size(size)
+            .memory_type_index(memory_type_index);
+        if kind == AllocationKind::Buffer && device.capabilities().buffer_device_address {
+            allocate_info = allocate_info.push_next(&mut allocate_flags_info);
+        }
+        let raw = unsafe { device.raw().allocate_memory(&allocate_info, None) }?;
+
+        let property_flags =
+            device.memopry_properties().memory_types[memory_type_index as usize].property_flags;
+        let ptr = if property_flags.contains(vk::MemoryPropertyFlags::HOST_VISIBLE) {
+            let ptr = unsafe {
+                device
+                    .raw()
+                    .map_memory(raw, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty())
+            }?;
+            Some(MemoryMappablePointer::from_raw_ptr(ptr))
+        } else {
+            None
+        };
+
+        device.set_name(
+            vk::ObjectType::DEVICE_MEMORY,
+            raw,
+            &format!("{kind:?} Block (Type {memory_type_index}) [Device Memory]"),
+        );
+
+        Ok(MemoryBlock {
+            raw,
+            memory_type_index,
+            kind,
+            size,
+            free_ranges: vec![FreeRange { offset: 0, size }],
+            allocation_count: 0,
+            ptr,
+        })
+    }
+
+    // Empty blocks are released, except for the last regular sized block of each memory type and
+    // kind, so short lived staging buffers don't reallocate it every time
+    pub(crate) fn free(&self, device: &ash::Device, memory: &Memory) {
+        let mut blocks = self.blocks.lock().unwrap();
+        if let Some(idx) = blocks.iter().position(|block| block.raw == memory.raw) {
+            blocks[idx].free(memory.offset, memory.req.size);
+
+            let block = &blocks[idx];
+            let is_last_of_type = !blocks.iter().enumerate().any(|(other_idx, other)| {
+                other_idx != idx
+                    && other.memory_type_index == block.memory_type_index
+                    && other.kind == block.kind
+            });
+            if block.allocation_count == 0 && (block.size > BLOCK_SIZE || !is_last_of_type) {
+                let block = blocks.swap_remove(idx);
+                unsafe { device.free_memory(block.raw, None) };
+            }
+        }
+    }
+
+    pub(crate) fn reserved_bytes_per_heap(
+        &self,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    ) -> Vec<u64> {
+        let mut reserved_bytes = vec![0; memory_properties.memory_heap_count as usize];
+        for block in self.blocks.lock().unwrap().iter() {
+            let heap_index = memory_properties.memory_types[block.memory_type_index as usize]
+                .heap_index as usize;
+            reserved_bytes[heap_index] += block.size;
+        }
+        reserved_bytes
+    }
+
+    pub fn stats(&self) -> MemoryStats {
+        let blocks = self.blocks.lock().unwrap();
+        blocks
+            .iter()
+            .fold(MemoryStats::default(), |mut stats, block| {
+                let free_bytes = block.free_bytes();
+                stats.block_count += 1;
+                stats.allocation_count += block.allocation_count;
+                stats.reserved_bytes += block.size;
+                stats.used_bytes += block.size - free_bytes;
+                stats.free_range_count += block.free_ranges.len() as u32;
+                stats.largest_free_range = block
+                    .free_ranges
+                    .iter()
+                    .map(|range| range.size)
+                    .fold(stats.largest_free_range, u64::max);
+                stats
+            })
+    }
+
+    // Frees every remaining block, whether or not the resources in it were destroyed
+    pub(crate) fn destroy(&self, device: &ash::Device) {
+        for block in self.blocks.lock().unwrap().drain(..) {
+            unsafe { device.free_memory(block.raw, None) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AllocationKind, FreeRange, MemoryBlock};
+    use ash::vk;
+
+    fn block(size: u64) -> MemoryBlock {
+        MemoryBlock {
+            raw: vk::DeviceMemory::null(),
+            memory_type_index: 0,
+            kind: AllocationKind::Buffer,
+            size,
+            free_ranges: vec![FreeRange { offset: 0, size }],
+            allocation_count: 0,
+            ptr: None,
+        }
+    }
+
+    fn free_ranges(block: &MemoryBlock) -> Vec<(u64, u64)> {
+        block
+            .free_ranges
+            .iter()
+            .map(|range| (range.offset, range.size))
+            .collect()
+    }
+
+    #[test]
+    fn aligned_allocation_leaves_padding_free() {
+        let mut block = block(1024);
+        assert_eq!(block.allocate(10, 1), Some(0));
+        assert_eq!(block.allocate(16, 256), Some(256));
+        assert_eq!(free_ranges(&block), [(10, 246), (272, 752)]);
+
+        // The padding is merged back once its neighbours are freed
+        block.free(256, 16);
+        block.free(0, 10);
+        assert_eq!(free_ranges(&block), [(0, 1024)]);
+    }
+
+    #[test]
+    fn frees_in_any_order_merge_into_one_range() {
+        let orders = [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ];
+        for order in orders {
+            let mut block = block(1024);
+            let allocations = [100, 200, 300].map(|size| (block.allocate(size, 1).unwrap(), size));
+            for idx in order {
+                let (offset, size) = allocations[idx];
+                block.free(offset, size);
+            }
+            assert_eq!(free_ranges(&block), [(0, 1024)], "freed in order {order:?}");
+            assert_eq!(block.allocation_count, 0);
+        }
+    }
+
+    #[test]
+    fn fragmented_space_does_not_fit_larger_allocations() {
+        let mut block = block(300);
+        let [first, _, last] = [0; 3].map(|_| block.allocate(100, 1).unwrap());
+        block.free(first, 100);
+        block.free(last, 100);
+
+        assert_eq!(block.free_bytes(), 200);
+        assert_eq!(block.allocate(150, 1), None);
+        assert_eq!(block.allocate(100, 1), Some(first));
+    }
+}
@@ -1,5 +1,9 @@
+mod allocator;
+
+pub(crate) use allocator::AllocationKind;
+pub use allocator::{HeapBudget, MemoryAllocator, MemoryStats};
+
 use crate::{device::Device, util::MemoryMappablePointer};
-use anyhow::Result;
 use ash::vk;
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
@@ -31,30 +35,22 @@ impl From<MemoryType> for vk::MemoryPropertyFlags {
     }
 }
 
+// A range of a `VkDeviceMemory` block owned by the device's `MemoryAllocator`
 pub struct Memory {
+    // Shared with every other allocation in the same block
     pub raw: vk::DeviceMemory,
     pub req: vk::MemoryRequirements,
+    pub offset: u64,
+    ptr: Option<MemoryMappablePointer>,
 }
 
 impl Memory {
-    pub fn ptr(&self, device: &ash::Device) -> Result<MemoryMappablePointer> {
-        unsafe {
-            let ptr = device.map_memory(self.raw, 0, self.req.size, vk::MemoryMapFlags::empty())?;
-            Ok(MemoryMappablePointer::from_raw_ptr(ptr))
-        }
-    }
-
-    pub(crate) fn set_name(&self, device: &Device, name: &str) {
-        device.set_name(
-            vk::ObjectType::DEVICE_MEMORY,
-            self.raw,
-            &format!("{name} [Device Memory]"),
-        );
+    // `None` unless the memory type is host visible
+    pub fn ptr(&self) -> Option<MemoryMappablePointer> {
+        self.ptr
     }
 
     pub(crate) fn destroy(&self, device: &Device) {
-        unsafe {
-            device.raw().free_memory(self.raw, None);
-        }
+        device.free_memory(self);
     }
 }
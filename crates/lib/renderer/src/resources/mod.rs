@@ -1,10 +1,13 @@
 pub mod bind_group;
 pub mod buffer;
+pub mod frame_buffered;
 pub mod image;
 pub mod manager;
 pub mod memory;
 pub mod pipeline;
 pub mod sampler;
 pub mod shader;
+pub mod texture_streamer;
+pub mod transient;
 
-pub use manager::ResourceManager;
+pub use manager::{ManagedResource, ResourceManager};
@@ -0,0 +1,105 @@
+use super::{get_pipeline_layout, BindGroupData, PipelineCommon};
+use crate::{
+    device::{Device, MAX_BINDLESS_RESOURCES},
+    resources::{bind_group::BindGroupSet, shader::Shader},
+};
+use anyhow::Result;
+use ash::vk;
+use std::ffi::CStr;
+
+#[derive(Debug, Clone)]
+pub struct ComputePipelineDescription {
+    pub name: Option<String>,
+    // Descriptors allocated by default for a variable-sized bindless array
+    pub max_bindless_images: u32,
+    // (set, binding) pairs of uniform buffers bound with dynamic offsets
+    pub dynamic_uniforms: Vec<(BindGroupSet, u32)>,
+    // Set whose descriptors are pushed with `CommandList::push_descriptor_set` instead of
+    // being allocated, requires `Device::supports_push_descriptor`
+    pub push_descriptor_set: Option<BindGroupSet>,
+}
+
+impl Default for ComputePipelineDescription {
+    fn default() -> Self {
+        Self {
+            name: None,
+            max_bindless_images: MAX_BINDLESS_RESOURCES,
+            dynamic_uniforms: Default::default(),
+            push_descriptor_set: None,
+        }
+    }
+}
+
+pub struct ComputePipeline {
+    pub common: PipelineCommon,
+    pub desc: ComputePipelineDescription,
+}
+
+impl ComputePipeline {
+    fn create_raw_pipeline(
+        device: &Device,
+        shader: &Shader,
+        pipeline_layout: vk::PipelineLayout,
+    ) -> Result<vk::Pipeline> {
+        let shader_entry_name = unsafe { CStr::from_bytes_with_nul_unchecked(b"main\0") };
+        let stage = vk::PipelineShaderStageCreateInfo {
+            module: shader.module,
+            p_name: shader_entry_name.as_ptr(),
+            stage: vk::ShaderStageFlags::COMPUTE,
+            ..Default::default()
+        };
+
+        let compute_pipeline_info = vk::ComputePipelineCreateInfo::builder()
+            .stage(stage)
+            .layout(pipeline_layout)
+            .build();
+
+        let compute_pipelines = unsafe {
+            device.raw().create_compute_pipelines(
+                device.pipeline_cache,
+                &[compute_pipeline_info],
+                None,
+            )
+        }
+        .map_err(|(_, err)| err)?;
+
+        Ok(compute_pipelines[0])
+    }
+
+    pub fn bind_group_data(&self, idx: usize) -> Option<&BindGroupData> {
+        self.common.bind_group_data(idx)
+    }
+
+    pub(crate) fn create(
+        device: &Device,
+        shader: &Shader,
+        desc: ComputePipelineDescription,
+    ) -> Result<Self> {
+        let (pipeline_layout, common_data) = get_pipeline_layout(
+            device,
+            &[shader],
+            &desc.name,
+            desc.max_bindless_images,
+            &desc.dynamic_uniforms,
+            desc.push_descriptor_set,
+        )?;
+
+        let pipeline = Self::create_raw_pipeline(device, shader, pipeline_layout)?;
+
+        let common =
+            PipelineCommon::new(device, pipeline_layout, pipeline, common_data, &desc.name);
+
+        Ok(ComputePipeline { common, desc })
+    }
+
+    pub fn recreate(&mut self, shader: &Shader, device: &Device) -> Result<vk::Pipeline> {
+        let new_pipeline = Self::create_raw_pipeline(device, shader, self.common.pipeline_layout)?;
+        let old = self.common.pipeline;
+        self.common.pipeline = new_pipeline;
+        Ok(old)
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        self.common.destroy(device);
+    }
+}
@@ -1,12 +1,14 @@
-use super::{get_pipeline_layout, BindGroupData, PipelineCommon};
-use crate::device::Device;
+use super::{get_pipeline_layout, BindGroupData, PipelineCommon, PipelineCommonData};
+use crate::device::{Device, SampleCount, MAX_BINDLESS_RESOURCES};
 
 use crate::resources::{
+    bind_group::BindGroupSet,
     image::{reflect_format_to_vk, Format},
     shader::Shader,
 };
 use anyhow::Result;
 use ash::vk;
+use bitflags::bitflags;
 use resource_manager::ResourceId;
 use std::ffi::CStr;
 use thiserror::Error;
@@ -15,12 +17,41 @@ use thiserror::Error;
 pub enum GraphicsPipelineError {
     #[error("shader for handle not in resource pool: {0:?}")]
     ShaderNotInResourcePool(ResourceId<Shader>),
+    #[error("depth clamp is not supported by this device")]
+    DepthClampNotSupported,
+    #[error("base pipeline was not created with `allow_derivatives`")]
+    DerivativesNotAllowed,
+}
+
+bitflags! {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct ColorComponents: u32 {
+        const R = 0x00000001;
+        const G = 0x00000002;
+        const B = 0x00000004;
+        const A = 0x00000008;
+        const RGBA = Self::R.bits() | Self::G.bits() | Self::B.bits() | Self::A.bits();
+    }
+}
+
+impl Default for ColorComponents {
+    fn default() -> Self {
+        Self::RGBA
+    }
+}
+
+impl From<ColorComponents> for vk::ColorComponentFlags {
+    fn from(value: ColorComponents) -> Self {
+        vk::ColorComponentFlags::from_raw(value.bits())
+    }
 }
 
-#[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct ColorBlendState {
     state: vk::PipelineColorBlendAttachmentState,
+    // Channels the pipeline writes to the attachment. Empty keeps the attachment as it is, e.g. for
+    // a depth prepass that shares its render pass with color attachments
+    pub color_write_mask: ColorComponents,
 }
 
 impl Default for ColorBlendState {
@@ -40,20 +71,14 @@ impl ColorBlendState {
                 .src_alpha_blend_factor(vk::BlendFactor::ZERO)
                 .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
                 .alpha_blend_op(vk::BlendOp::ADD)
-                .color_write_mask(vk::ColorComponentFlags::RGBA)
                 .build(),
+            color_write_mask: ColorComponents::RGBA,
         }
     }
 
     pub fn pma() -> Self {
         Self {
             state: vk::PipelineColorBlendAttachmentState::builder()
-                .color_write_mask(
-                    vk::ColorComponentFlags::R
-                        | vk::ColorComponentFlags::G
-                        | vk::ColorComponentFlags::B
-                        | vk::ColorComponentFlags::A,
-                )
                 .blend_enable(true)
                 .src_color_blend_factor(vk::BlendFactor::ONE)
                 .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
@@ -61,8 +86,20 @@ impl ColorBlendState {
                 .src_alpha_blend_factor(vk::BlendFactor::ONE)
                 .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
                 .alpha_blend_op(vk::BlendOp::ADD)
-                .color_write_mask(vk::ColorComponentFlags::RGBA)
                 .build(),
+            color_write_mask: ColorComponents::RGBA,
+        }
+    }
+
+    pub fn with_color_write_mask(mut self, color_write_mask: ColorComponents) -> Self {
+        self.color_write_mask = color_write_mask;
+        self
+    }
+
+    fn attachment_state(&self) -> vk::PipelineColorBlendAttachmentState {
+        vk::PipelineColorBlendAttachmentState {
+            color_write_mask: self.color_write_mask.into(),
+            ..self.state
         }
     }
 }
@@ -98,6 +135,166 @@ impl From<CullMode> for vk::CullModeFlags {
     }
 }
 
+// Winding of front-facing triangles, as seen in the framebuffer
+#[derive(Debug, Copy, Clone)]
+pub enum FrontFace {
+    Clockwise,
+    CounterClockwise,
+}
+
+impl Default for FrontFace {
+    fn default() -> Self {
+        Self::Clockwise
+    }
+}
+
+impl From<FrontFace> for vk::FrontFace {
+    fn from(value: FrontFace) -> Self {
+        match value {
+            FrontFace::Clockwise => vk::FrontFace::CLOCKWISE,
+            FrontFace::CounterClockwise => vk::FrontFace::COUNTER_CLOCKWISE,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum PrimitiveTopology {
+    PointList,
+    LineList,
+    LineStrip,
+    TriangleList,
+    TriangleStrip,
+}
+
+impl Default for PrimitiveTopology {
+    fn default() -> Self {
+        Self::TriangleList
+    }
+}
+
+impl From<PrimitiveTopology> for vk::PrimitiveTopology {
+    fn from(value: PrimitiveTopology) -> Self {
+        match value {
+            PrimitiveTopology::PointList => vk::PrimitiveTopology::POINT_LIST,
+            PrimitiveTopology::LineList => vk::PrimitiveTopology::LINE_LIST,
+            PrimitiveTopology::LineStrip => vk::PrimitiveTopology::LINE_STRIP,
+            PrimitiveTopology::TriangleList => vk::PrimitiveTopology::TRIANGLE_LIST,
+            PrimitiveTopology::TriangleStrip => vk::PrimitiveTopology::TRIANGLE_STRIP,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum CompareOp {
+    Never,
+    Less,
+    Equal,
+    LessOrEqual,
+    Greater,
+    NotEqual,
+    GreaterOrEqual,
+    Always,
+}
+
+impl From<CompareOp> for vk::CompareOp {
+    fn from(value: CompareOp) -> Self {
+        match value {
+            CompareOp::Never => vk::CompareOp::NEVER,
+            CompareOp::Less => vk::CompareOp::LESS,
+            CompareOp::Equal => vk::CompareOp::EQUAL,
+            CompareOp::LessOrEqual => vk::CompareOp::LESS_OR_EQUAL,
+            CompareOp::Greater => vk::CompareOp::GREATER,
+            CompareOp::NotEqual => vk::CompareOp::NOT_EQUAL,
+            CompareOp::GreaterOrEqual => vk::CompareOp::GREATER_OR_EQUAL,
+            CompareOp::Always => vk::CompareOp::ALWAYS,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum StencilOp {
+    Keep,
+    Zero,
+    Replace,
+    IncrementAndClamp,
+    DecrementAndClamp,
+    Invert,
+    IncrementAndWrap,
+    DecrementAndWrap,
+}
+
+impl From<StencilOp> for vk::StencilOp {
+    fn from(value: StencilOp) -> Self {
+        match value {
+            StencilOp::Keep => vk::StencilOp::KEEP,
+            StencilOp::Zero => vk::StencilOp::ZERO,
+            StencilOp::Replace => vk::StencilOp::REPLACE,
+            StencilOp::IncrementAndClamp => vk::StencilOp::INCREMENT_AND_CLAMP,
+            StencilOp::DecrementAndClamp => vk::StencilOp::DECREMENT_AND_CLAMP,
+            StencilOp::Invert => vk::StencilOp::INVERT,
+            StencilOp::IncrementAndWrap => vk::StencilOp::INCREMENT_AND_WRAP,
+            StencilOp::DecrementAndWrap => vk::StencilOp::DECREMENT_AND_WRAP,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct StencilFaceState {
+    pub fail_op: StencilOp,
+    pub pass_op: StencilOp,
+    pub depth_fail_op: StencilOp,
+    pub compare_op: CompareOp,
+    pub compare_mask: u32,
+    pub write_mask: u32,
+    pub reference: u32,
+}
+
+impl Default for StencilFaceState {
+    fn default() -> Self {
+        Self {
+            fail_op: StencilOp::Keep,
+            pass_op: StencilOp::Keep,
+            depth_fail_op: StencilOp::Keep,
+            compare_op: CompareOp::Always,
+            compare_mask: 0xff,
+            write_mask: 0xff,
+            reference: 0,
+        }
+    }
+}
+
+impl From<StencilFaceState> for vk::StencilOpState {
+    fn from(value: StencilFaceState) -> Self {
+        vk::StencilOpState {
+            fail_op: value.fail_op.into(),
+            pass_op: value.pass_op.into(),
+            depth_fail_op: value.depth_fail_op.into(),
+            compare_op: value.compare_op.into(),
+            compare_mask: value.compare_mask,
+            write_mask: value.write_mask,
+            reference: value.reference,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct StencilState {
+    pub front: StencilFaceState,
+    pub back: StencilFaceState,
+    // If set, the face `reference` values are ignored and set with `CommandList::set_stencil_reference`
+    pub dynamic_reference: bool,
+}
+
+impl StencilState {
+    pub fn both(face: StencilFaceState) -> Self {
+        Self {
+            front: face,
+            back: face,
+            dynamic_reference: false,
+        }
+    }
+}
+
 pub type VertexInputRate = vk::VertexInputRate;
 pub type VertexBindingDesc = vk::VertexInputBindingDescription;
 pub type VertexAttributeDescription = vk::VertexInputAttributeDescription;
@@ -111,12 +308,41 @@ pub struct VertexDescription {
 #[derive(Debug, Clone)]
 pub struct GraphicsPipelineDescription {
     pub name: Option<String>,
-    pub blending: ColorBlendState,
-    pub color_format: Option<Format>,
+    // One per color attachment, attachments without an entry use `ColorBlendState::default()`
+    pub blending: Vec<ColorBlendState>,
+    // Color attachment formats, in the order the attachments are added to the render pass
+    pub color_formats: Vec<Format>,
     pub depth_format: Option<Format>,
+    // Depth state, only used with a `depth_format`. The default is a reversed depth test, where
+    // nearer fragments have greater depth
+    pub depth_test: bool,
+    pub depth_write: bool,
+    pub depth_compare: CompareOp,
+    // Clamps fragment depth instead of clipping against the near and far planes, requires
+    // `DeviceCapabilities::depth_clamp`
+    pub depth_clamp: bool,
+    pub topology: PrimitiveTopology,
     pub cull_mode: CullMode,
+    pub front_face: FrontFace,
     pub depth_bias: Option<DepthBiasInfo>,
+    // Requires a depth format with a stencil component
+    pub stencil: Option<StencilState>,
+    // Line width is set with `CommandList::set_line_width` instead of being 1 pixel, for line
+    // topologies
+    pub dynamic_line_width: bool,
     pub vertex_desc: Option<VertexDescription>,
+    // Descriptors allocated by default for a variable-sized bindless array
+    pub max_bindless_images: u32,
+    // (set, binding) pairs of uniform buffers bound with dynamic offsets
+    pub dynamic_uniforms: Vec<(BindGroupSet, u32)>,
+    // Set whose descriptors are pushed with `CommandList::push_descriptor_set` instead of
+    // being allocated, requires `Device::supports_push_descriptor`
+    pub push_descriptor_set: Option<BindGroupSet>,
+    // Must match the sample count of the attachments the pipeline renders to
+    pub samples: SampleCount,
+    // Lets other pipelines be created as derivatives of this one, which can be faster to create
+    // and switch between
+    pub allow_derivatives: bool,
 }
 
 impl Default for GraphicsPipelineDescription {
@@ -124,83 +350,47 @@ impl Default for GraphicsPipelineDescription {
         Self {
             name: None,
             blending: Default::default(),
-            color_format: Some(Format::B8G8R8A8_UNORM),
+            color_formats: vec![Format::B8G8R8A8_UNORM],
             depth_format: None,
+            depth_test: true,
+            depth_write: true,
+            depth_compare: CompareOp::Greater,
+            depth_clamp: false,
+            topology: Default::default(),
             cull_mode: Default::default(),
+            front_face: Default::default(),
             depth_bias: None,
+            stencil: None,
+            dynamic_line_width: false,
             vertex_desc: None,
+            max_bindless_images: MAX_BINDLESS_RESOURCES,
+            dynamic_uniforms: Default::default(),
+            push_descriptor_set: None,
+            samples: SampleCount::X1,
+            allow_derivatives: false,
         }
     }
 }
 
+// A pipeline to create with `Device::create_graphics_pipelines`
+pub struct GraphicsPipelineInfo<'a> {
+    pub vertex_shader: &'a Shader,
+    pub fragment_shader: Option<&'a Shader>,
+    pub desc: GraphicsPipelineDescription,
+}
+
 pub struct GraphicsPipeline {
     pub common: PipelineCommon,
     pub desc: GraphicsPipelineDescription,
 }
 
 impl GraphicsPipeline {
-    fn create_raw_pipeline(
+    fn create_layout(
         device: &Device,
         vertex_shader: &Shader,
         fragment_shader: Option<&Shader>,
         desc: &GraphicsPipelineDescription,
-        pipeline_layout: vk::PipelineLayout,
-    ) -> Result<vk::Pipeline> {
-        let atttributes = vertex_shader.reflect_data.get_vertex_attributes();
-        if let Some(vertex_desc) = &desc.vertex_desc {
-            inner_create_raw_pipeline(
-                device,
-                vertex_shader,
-                fragment_shader,
-                desc,
-                pipeline_layout,
-                &vertex_desc.binding_desc,
-                &vertex_desc.attribute_desc,
-            )
-        } else {
-            let binding = 0;
-            let vertex_input_binding_descriptions = vec![vk::VertexInputBindingDescription {
-                binding,
-                stride: atttributes.stride / 8,
-                input_rate: vk::VertexInputRate::VERTEX,
-            }];
-            let vertex_input_attribute_descriptions = atttributes
-                .atts
-                .iter()
-                .enumerate()
-                .map(|(location, att)| vk::VertexInputAttributeDescription {
-                    location: location as u32,
-                    binding,
-                    format: reflect_format_to_vk(att.format),
-                    offset: att.offset / 8,
-                })
-                .collect::<Vec<_>>();
-
-            inner_create_raw_pipeline(
-                device,
-                vertex_shader,
-                fragment_shader,
-                desc,
-                pipeline_layout,
-                &vertex_input_binding_descriptions,
-                &vertex_input_attribute_descriptions,
-            )
-        }
-    }
-
-    pub fn bind_group_data(&self, idx: usize) -> Option<&BindGroupData> {
-        self.common.bind_group_data(idx)
-    }
-
-    pub(crate) fn create(
-        device: &Device,
-        vertex_shader: &Shader,
-        fragment_shader: Option<&Shader>,
-        desc: GraphicsPipelineDescription,
-    ) -> Result<Self> {
-        //
-        // Pipeline stuff, pretty temp
-        //
+    ) -> Result<(vk::PipelineLayout, PipelineCommonData)> {
         let default_shader = Shader::default();
         let shaders = [
             vertex_shader,
@@ -210,7 +400,7 @@ impl GraphicsPipeline {
                 &default_shader
             },
         ];
-        let (pipeline_layout, common_data) = get_pipeline_layout(
+        get_pipeline_layout(
             device,
             if fragment_shader.is_some() {
                 &shaders
@@ -218,15 +408,70 @@ impl GraphicsPipeline {
                 &shaders[0..1]
             },
             &desc.name,
+            desc.max_bindless_images,
+            &desc.dynamic_uniforms,
+            desc.push_descriptor_set,
+        )
+    }
+
+    fn create_raw_pipeline(
+        device: &Device,
+        vertex_shader: &Shader,
+        fragment_shader: Option<&Shader>,
+        desc: &GraphicsPipelineDescription,
+        pipeline_layout: vk::PipelineLayout,
+        base_pipeline: Option<vk::Pipeline>,
+    ) -> Result<vk::Pipeline> {
+        let mut state = RawPipelineState::new(
+            device,
+            vertex_shader,
+            fragment_shader,
+            desc,
+            pipeline_layout,
         )?;
+        let mut create_info = state.create_info(desc.allow_derivatives);
+        if let Some(base_pipeline) = base_pipeline {
+            create_info.flags |= vk::PipelineCreateFlags::DERIVATIVE;
+            create_info.base_pipeline_handle = base_pipeline;
+        }
+        let pipelines = create_raw_pipelines(device, &[create_info])?;
+        Ok(pipelines[0])
+    }
+
+    pub fn bind_group_data(&self, idx: usize) -> Option<&BindGroupData> {
+        self.common.bind_group_data(idx)
+    }
+
+    pub(crate) fn create(
+        device: &Device,
+        vertex_shader: &Shader,
+        fragment_shader: Option<&Shader>,
+        desc: GraphicsPipelineDescription,
+        base_pipeline: Option<&GraphicsPipeline>,
+    ) -> Result<Self> {
+        if let Some(base_pipeline) = base_pipeline {
+            if !base_pipeline.desc.allow_derivatives {
+                return Err(GraphicsPipelineError::DerivativesNotAllowed.into());
+            }
+        }
 
-        let pipeline = Self::create_raw_pipeline(
+        let (pipeline_layout, common_data) =
+            Self::create_layout(device, vertex_shader, fragment_shader, &desc)?;
+
+        let pipeline = match Self::create_raw_pipeline(
             device,
             vertex_shader,
             fragment_shader,
             &desc,
             pipeline_layout,
-        )?;
+            base_pipeline.map(|base_pipeline| base_pipeline.common.pipeline),
+        ) {
+            Ok(pipeline) => pipeline,
+            Err(err) => {
+                destroy_layouts(device, vec![(pipeline_layout, common_data)]);
+                return Err(err);
+            }
+        };
 
         let common =
             PipelineCommon::new(device, pipeline_layout, pipeline, common_data, &desc.name);
@@ -234,18 +479,104 @@ impl GraphicsPipeline {
         Ok(GraphicsPipeline { common, desc })
     }
 
+    // All pipelines are created in a single call, as derivatives of the first one. The first
+    // pipeline always allows derivatives, and its stored description says so
+    pub(crate) fn create_batch(
+        device: &Device,
+        infos: &[GraphicsPipelineInfo<'_>],
+    ) -> Result<Vec<Self>> {
+        let mut layouts = Vec::with_capacity(infos.len());
+        let mut states = Vec::with_capacity(infos.len());
+        for info in infos {
+            let layout =
+                Self::create_layout(device, info.vertex_shader, info.fragment_shader, &info.desc);
+            let (pipeline_layout, common_data) = match layout {
+                Ok(layout) => layout,
+                Err(err) => {
+                    destroy_layouts(device, layouts);
+                    return Err(err);
+                }
+            };
+            layouts.push((pipeline_layout, common_data));
+            let state = RawPipelineState::new(
+                device,
+                info.vertex_shader,
+                info.fragment_shader,
+                &info.desc,
+                pipeline_layout,
+            );
+            match state {
+                Ok(state) => states.push(state),
+                Err(err) => {
+                    destroy_layouts(device, layouts);
+                    return Err(err);
+                }
+            }
+        }
+
+        // `states` can't move after this, the create infos point into it
+        let create_infos = states
+            .iter_mut()
+            .enumerate()
+            .map(|(idx, state)| {
+                if idx == 0 {
+                    state.create_info(true)
+                } else {
+                    let mut create_info = state.create_info(infos[idx].desc.allow_derivatives);
+                    create_info.flags |= vk::PipelineCreateFlags::DERIVATIVE;
+                    create_info.base_pipeline_index = 0;
+                    create_info
+                }
+            })
+            .collect::<Vec<_>>();
+        let pipelines = match create_raw_pipelines(device, &create_infos) {
+            Ok(pipelines) => pipelines,
+            Err(err) => {
+                destroy_layouts(device, layouts);
+                return Err(err);
+            }
+        };
+
+        Ok(infos
+            .iter()
+            .zip(layouts)
+            .zip(pipelines)
+            .enumerate()
+            .map(
+                |(idx, ((info, (pipeline_layout, common_data)), pipeline))| {
+                    let common = PipelineCommon::new(
+                        device,
+                        pipeline_layout,
+                        pipeline,
+                        common_data,
+                        &info.desc.name,
+                    );
+                    GraphicsPipeline {
+                        common,
+                        desc: GraphicsPipelineDescription {
+                            allow_derivatives: idx == 0 || info.desc.allow_derivatives,
+                            ..info.desc.clone()
+                        },
+                    }
+                },
+            )
+            .collect())
+    }
+
     pub fn recreate(
         &mut self,
         vertex_shader: &Shader,
         fragment_shader: Option<&Shader>,
         device: &Device,
     ) -> Result<vk::Pipeline> {
+        // Recreated pipelines are no longer derivatives, their base may have been recreated too
         let new_pipeline = Self::create_raw_pipeline(
             device,
             vertex_shader,
             fragment_shader,
             &self.desc,
             self.common.pipeline_layout,
+            None,
         )?;
         let old = self.common.pipeline;
         self.common.pipeline = new_pipeline;
@@ -257,137 +588,268 @@ impl GraphicsPipeline {
     }
 }
 
-fn inner_create_raw_pipeline(
+// For pipelines that failed to be created after their layouts were
+fn destroy_layouts(device: &Device, layouts: Vec<(vk::PipelineLayout, PipelineCommonData)>) {
+    for (pipeline_layout, common_data) in layouts {
+        common_data.destroy(device.raw());
+        unsafe { device.raw().destroy_pipeline_layout(pipeline_layout, None) };
+    }
+}
+
+fn create_raw_pipelines(
     device: &Device,
-    vertex_shader: &Shader,
-    fragment_shader: Option<&Shader>,
-    desc: &GraphicsPipelineDescription,
+    create_infos: &[vk::GraphicsPipelineCreateInfo],
+) -> Result<Vec<vk::Pipeline>> {
+    unsafe {
+        device
+            .raw()
+            .create_graphics_pipelines(device.pipeline_cache, create_infos, None)
+    }
+    .map_err(|(pipelines, err)| {
+        // Pipelines that were created before the failure still have to be destroyed
+        for pipeline in pipelines {
+            if pipeline != vk::Pipeline::null() {
+                unsafe {
+                    device.raw().destroy_pipeline(pipeline, None);
+                }
+            }
+        }
+        err.into()
+    })
+}
+
+// Everything a `vk::GraphicsPipelineCreateInfo` points to, so several pipelines can be created
+// in a single call
+struct RawPipelineState {
+    vertex_input_binding_descriptions: Vec<vk::VertexInputBindingDescription>,
+    vertex_input_attribute_descriptions: Vec<vk::VertexInputAttributeDescription>,
+    color_blend_attachment_states: Vec<vk::PipelineColorBlendAttachmentState>,
+    dynamic_state: Vec<vk::DynamicState>,
+    color_attachment_formats: Vec<vk::Format>,
+    depth_format: Option<vk::Format>,
+    stencil_format: Option<vk::Format>,
+    // Filled in by `create_info`, these point into the fields above
+    vertex_input_state_info: vk::PipelineVertexInputStateCreateInfo,
+    vertex_input_assembly_state_info: vk::PipelineInputAssemblyStateCreateInfo,
+    viewport_state_info: vk::PipelineViewportStateCreateInfo,
+    rasterization_info: vk::PipelineRasterizationStateCreateInfo,
+    depth_state_info: vk::PipelineDepthStencilStateCreateInfo,
+    color_blend_state: vk::PipelineColorBlendStateCreateInfo,
+    dynamic_state_info: vk::PipelineDynamicStateCreateInfo,
+    multisample_state_info: vk::PipelineMultisampleStateCreateInfo,
+    shader_stage_create_infos: Vec<vk::PipelineShaderStageCreateInfo>,
+    pipeline_rendering_ci: vk::PipelineRenderingCreateInfo,
     pipeline_layout: vk::PipelineLayout,
-    vertex_input_binding_descriptions: &[vk::VertexInputBindingDescription],
-    vertex_input_attribute_descriptions: &[vk::VertexInputAttributeDescription],
-) -> Result<vk::Pipeline> {
-    let vertex_input_state_info = if !vertex_input_attribute_descriptions.is_empty() {
-        vk::PipelineVertexInputStateCreateInfo::builder()
-            .vertex_attribute_descriptions(&vertex_input_attribute_descriptions)
-            .vertex_binding_descriptions(&vertex_input_binding_descriptions)
-            .build()
-    } else {
-        vk::PipelineVertexInputStateCreateInfo::builder().build()
-    };
-
-    let vertex_input_assembly_state_info = vk::PipelineInputAssemblyStateCreateInfo::builder()
-        .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
-    let viewport_state_info = vk::PipelineViewportStateCreateInfo::builder()
-        .viewport_count(1)
-        .scissor_count(1);
-    let rasterization_info = vk::PipelineRasterizationStateCreateInfo::builder()
-        .depth_clamp_enable(false)
-        .rasterizer_discard_enable(false)
-        .polygon_mode(vk::PolygonMode::FILL)
-        .cull_mode(desc.cull_mode.into())
-        .front_face(vk::FrontFace::CLOCKWISE)
-        .line_width(1.0);
-
-    let rasterization_info = if let Some(info) = desc.depth_bias {
-        rasterization_info
-            .depth_bias_enable(true)
-            .depth_bias_constant_factor(info.constant_factor)
-            .depth_bias_slope_factor(info.slope_factor)
-    } else {
-        rasterization_info.depth_bias_enable(false)
-    };
-    let depth_state_info = if desc.depth_format.is_some() {
-        vk::PipelineDepthStencilStateCreateInfo::builder()
-            .depth_test_enable(true)
-            .depth_write_enable(true)
-            .depth_compare_op(vk::CompareOp::GREATER)
-            .build()
-    } else {
-        vk::PipelineDepthStencilStateCreateInfo::builder()
-            .depth_test_enable(false)
-            .depth_write_enable(false)
-            .depth_compare_op(vk::CompareOp::ALWAYS)
-            .build()
-    };
-
-    let color_blend_attachment_states = [desc.blending.state];
-    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
-        .logic_op(vk::LogicOp::CLEAR)
-        .attachments(&color_blend_attachment_states);
-    let dynamic_state = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
-    let dynamic_state_info =
-        vk::PipelineDynamicStateCreateInfo::builder().dynamic_states(&dynamic_state);
-    let multisample_state_info = vk::PipelineMultisampleStateCreateInfo::builder()
-        .rasterization_samples(vk::SampleCountFlags::TYPE_1);
-
-    let shader_entry_name = unsafe { CStr::from_bytes_with_nul_unchecked(b"main\0") };
-    let shader_stage_create_infos = [
-        vk::PipelineShaderStageCreateInfo {
+}
+
+impl RawPipelineState {
+    fn new(
+        device: &Device,
+        vertex_shader: &Shader,
+        fragment_shader: Option<&Shader>,
+        desc: &GraphicsPipelineDescription,
+        pipeline_layout: vk::PipelineLayout,
+    ) -> Result<Self> {
+        if desc.depth_clamp && !device.capabilities().depth_clamp {
+            return Err(GraphicsPipelineError::DepthClampNotSupported.into());
+        }
+
+        let (vertex_input_binding_descriptions, vertex_input_attribute_descriptions) =
+            if let Some(vertex_desc) = &desc.vertex_desc {
+                (
+                    vertex_desc.binding_desc.clone(),
+                    vertex_desc.attribute_desc.clone(),
+                )
+            } else {
+                let atttributes = vertex_shader.reflect_data.get_vertex_attributes();
+                let binding = 0;
+                let vertex_input_binding_descriptions = vec![vk::VertexInputBindingDescription {
+                    binding,
+                    stride: atttributes.stride / 8,
+                    input_rate: vk::VertexInputRate::VERTEX,
+                }];
+                let vertex_input_attribute_descriptions = atttributes
+                    .atts
+                    .iter()
+                    .enumerate()
+                    .map(|(location, att)| vk::VertexInputAttributeDescription {
+                        location: location as u32,
+                        binding,
+                        format: reflect_format_to_vk(att.format),
+                        offset: att.offset / 8,
+                    })
+                    .collect::<Vec<_>>();
+                (
+                    vertex_input_binding_descriptions,
+                    vertex_input_attribute_descriptions,
+                )
+            };
+
+        let vertex_input_assembly_state_info = vk::PipelineInputAssemblyStateCreateInfo::builder()
+            .topology(desc.topology.into())
+            .build();
+        let viewport_state_info = vk::PipelineViewportStateCreateInfo::builder()
+            .viewport_count(1)
+            .scissor_count(1)
+            .build();
+        let rasterization_info = vk::PipelineRasterizationStateCreateInfo::builder()
+            .depth_clamp_enable(desc.depth_clamp)
+            .rasterizer_discard_enable(false)
+            .polygon_mode(vk::PolygonMode::FILL)
+            .cull_mode(desc.cull_mode.into())
+            .front_face(desc.front_face.into())
+            .line_width(1.0);
+
+        let rasterization_info = if let Some(info) = desc.depth_bias {
+            rasterization_info
+                .depth_bias_enable(true)
+                .depth_bias_constant_factor(info.constant_factor)
+                .depth_bias_slope_factor(info.slope_factor)
+                .build()
+        } else {
+            rasterization_info.depth_bias_enable(false).build()
+        };
+        let depth_state_info = if desc.depth_format.is_some() {
+            vk::PipelineDepthStencilStateCreateInfo::builder()
+                .depth_test_enable(desc.depth_test)
+                .depth_write_enable(desc.depth_write)
+                .depth_compare_op(desc.depth_compare.into())
+        } else {
+            vk::PipelineDepthStencilStateCreateInfo::builder()
+                .depth_test_enable(false)
+                .depth_write_enable(false)
+                .depth_compare_op(vk::CompareOp::ALWAYS)
+        };
+        let depth_state_info = if let Some(stencil) = desc.stencil {
+            depth_state_info
+                .stencil_test_enable(true)
+                .front(stencil.front.into())
+                .back(stencil.back.into())
+                .build()
+        } else {
+            depth_state_info.stencil_test_enable(false).build()
+        };
+
+        let color_blend_attachment_states = (0..desc.color_formats.len())
+            .map(|idx| {
+                desc.blending
+                    .get(idx)
+                    .copied()
+                    .unwrap_or_default()
+                    .attachment_state()
+            })
+            .collect::<Vec<_>>();
+        let mut dynamic_state = vec![vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        if desc
+            .stencil
+            .map_or(false, |stencil| stencil.dynamic_reference)
+        {
+            dynamic_state.push(vk::DynamicState::STENCIL_REFERENCE);
+        }
+        if desc.dynamic_line_width {
+            dynamic_state.push(vk::DynamicState::LINE_WIDTH);
+        }
+        let multisample_state_info = vk::PipelineMultisampleStateCreateInfo::builder()
+            .rasterization_samples(desc.samples.into())
+            .build();
+
+        let shader_entry_name = unsafe { CStr::from_bytes_with_nul_unchecked(b"main\0") };
+        let mut shader_stage_create_infos = vec![vk::PipelineShaderStageCreateInfo {
             module: vertex_shader.module,
             p_name: shader_entry_name.as_ptr(),
             stage: vk::ShaderStageFlags::VERTEX,
             ..Default::default()
-        },
+        }];
         if let Some(fragment_shader) = fragment_shader {
-            vk::PipelineShaderStageCreateInfo {
+            shader_stage_create_infos.push(vk::PipelineShaderStageCreateInfo {
                 module: fragment_shader.module,
                 p_name: shader_entry_name.as_ptr(),
                 stage: vk::ShaderStageFlags::FRAGMENT,
                 ..Default::default()
-            }
-        } else {
-            Default::default()
-        },
-    ];
-
-    let color_attachment_formats = if let Some(color_format) = desc.color_format {
-        [color_format.into()]
-    } else {
-        [Default::default()]
-    };
-    let mut pipeline_rendering_ci = {
-        let mut builder = vk::PipelineRenderingCreateInfo::builder();
-        if desc.color_format.is_some() {
-            builder = builder.color_attachment_formats(&color_attachment_formats);
-        }
-        if let Some(depth_format) = desc.depth_format {
-            builder = builder.depth_attachment_format(depth_format.into());
+            });
         }
-        builder.build()
-    };
 
-    let graphic_pipeline_infos = vk::GraphicsPipelineCreateInfo::builder()
-        .push_next(&mut pipeline_rendering_ci)
-        .stages(if fragment_shader.is_some() {
-            &shader_stage_create_infos
-        } else {
-            &shader_stage_create_infos[..1]
+        let color_attachment_formats = desc
+            .color_formats
+            .iter()
+            .map(|format| (*format).into())
+            .collect::<Vec<vk::Format>>();
+        let depth_format = desc.depth_format.map(vk::Format::from);
+        let stencil_format = desc
+            .depth_format
+            .filter(|depth_format| depth_format.has_stencil())
+            .map(vk::Format::from);
+
+        Ok(Self {
+            vertex_input_binding_descriptions,
+            vertex_input_attribute_descriptions,
+            color_blend_attachment_states,
+            dynamic_state,
+            color_attachment_formats,
+            depth_format,
+            stencil_format,
+            vertex_input_state_info: Default::default(),
+            vertex_input_assembly_state_info,
+            viewport_state_info,
+            rasterization_info,
+            depth_state_info,
+            color_blend_state: Default::default(),
+            dynamic_state_info: Default::default(),
+            multisample_state_info,
+            shader_stage_create_infos,
+            pipeline_rendering_ci: Default::default(),
+            pipeline_layout,
         })
-        .vertex_input_state(&vertex_input_state_info)
-        .input_assembly_state(&vertex_input_assembly_state_info)
-        .viewport_state(&viewport_state_info)
-        .rasterization_state(&rasterization_info)
-        .multisample_state(&multisample_state_info)
-        .depth_stencil_state(&depth_state_info)
-        .color_blend_state(&color_blend_state)
-        .dynamic_state(&dynamic_state_info)
-        .layout(pipeline_layout)
-        .build();
-
-    let graphics_pipelines = unsafe {
-        device.raw().create_graphics_pipelines(
-            device.pipeline_cache,
-            &[graphic_pipeline_infos],
-            None,
-        )
-    }
-    .map_err(|(_, err)| err)?;
-    let pipeline = graphics_pipelines[0];
-    for pipeline in graphics_pipelines.iter().skip(1) {
-        unsafe {
-            device.raw().destroy_pipeline(*pipeline, None);
-        }
     }
 
-    Ok(pipeline)
+    // Points into `self`, which must not move until the pipeline is created
+    fn create_info(&mut self, allow_derivatives: bool) -> vk::GraphicsPipelineCreateInfo {
+        let flags = if allow_derivatives {
+            vk::PipelineCreateFlags::ALLOW_DERIVATIVES
+        } else {
+            vk::PipelineCreateFlags::empty()
+        };
+        self.vertex_input_state_info = if !self.vertex_input_attribute_descriptions.is_empty() {
+            vk::PipelineVertexInputStateCreateInfo::builder()
+                .vertex_attribute_descriptions(&self.vertex_input_attribute_descriptions)
+                .vertex_binding_descriptions(&self.vertex_input_binding_descriptions)
+                .build()
+        } else {
+            vk::PipelineVertexInputStateCreateInfo::builder().build()
+        };
+        self.color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+            .logic_op(vk::LogicOp::CLEAR)
+            .attachments(&self.color_blend_attachment_states)
+            .build();
+        self.dynamic_state_info = vk::PipelineDynamicStateCreateInfo::builder()
+            .dynamic_states(&self.dynamic_state)
+            .build();
+        self.pipeline_rendering_ci = {
+            let mut builder = vk::PipelineRenderingCreateInfo::builder()
+                .color_attachment_formats(&self.color_attachment_formats);
+            if let Some(depth_format) = self.depth_format {
+                builder = builder.depth_attachment_format(depth_format);
+            }
+            if let Some(stencil_format) = self.stencil_format {
+                builder = builder.stencil_attachment_format(stencil_format);
+            }
+            builder.build()
+        };
+
+        vk::GraphicsPipelineCreateInfo::builder()
+            .flags(flags)
+            .push_next(&mut self.pipeline_rendering_ci)
+            .stages(&self.shader_stage_create_infos)
+            .vertex_input_state(&self.vertex_input_state_info)
+            .input_assembly_state(&self.vertex_input_assembly_state_info)
+            .viewport_state(&self.viewport_state_info)
+            .rasterization_state(&self.rasterization_info)
+            .multisample_state(&self.multisample_state_info)
+            .depth_stencil_state(&self.depth_state_info)
+            .color_blend_state(&self.color_blend_state)
+            .dynamic_state(&self.dynamic_state_info)
+            .layout(self.pipeline_layout)
+            .base_pipeline_index(-1)
+            .build()
+    }
 }
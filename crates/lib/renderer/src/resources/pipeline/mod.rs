@@ -1,3 +1,4 @@
+pub mod compute;
 pub mod graphics;
 pub mod push_constant;
 
@@ -5,7 +6,7 @@ use super::bind_group::{BindGroupData, BindGroupMap, BindGroupSet};
 use crate::{
     device::Device,
     resources::{
-        bind_group::{BindGroupBindingData, BindGroupLayout},
+        bind_group::{BindGroupBindingData, BindGroupLayout, BindGroupType},
         pipeline::push_constant::PushConstant,
         shader::{Shader, ShaderStage},
     },
@@ -19,6 +20,8 @@ use thiserror::Error;
 pub enum PipelineError {
     #[error("invalid push constant")]
     InvalidPushConstant,
+    #[error("push constant data is {size} bytes, but at most {max} bytes fit")]
+    PushConstantTooLarge { size: usize, max: u32 },
     #[error("invalid pipeline handle")]
     InvalidPipelineHandle,
     #[error("no bound pipeline")]
@@ -107,11 +110,22 @@ pub fn get_pipeline_layout(
     device: &Device,
     shaders: &[&Shader],
     name: &Option<String>,
+    max_bindless_count: u32,
+    dynamic_uniforms: &[(BindGroupSet, u32)],
+    push_descriptor_set: Option<BindGroupSet>,
 ) -> Result<(vk::PipelineLayout, PipelineCommonData)> {
     let push_constants = {
         let mut map = HashMap::new();
         for shader in shaders {
             for (idx, pc) in shader.push_constants()?.into_iter().enumerate() {
+                let max = device.capabilities().max_push_constants_size;
+                if pc.offset + pc.size > max {
+                    return Err(PipelineError::PushConstantTooLarge {
+                        size: (pc.offset + pc.size) as usize,
+                        max,
+                    }
+                    .into());
+                }
                 map.insert((shader.stage(), idx as u32), pc);
             }
         }
@@ -125,20 +139,39 @@ pub fn get_pipeline_layout(
                 shader.bind_group_descriptions(device.descriptor_indexing_properties())?
             {
                 let entry = data_map.entry(set).or_insert_with(Vec::new);
-                entry.extend(data);
+                entry.extend(data.into_iter().map(|mut binding_data| {
+                    if matches!(binding_data.ty, BindGroupType::UniformBuffer)
+                        && dynamic_uniforms.contains(&(set, binding_data.binding))
+                    {
+                        binding_data.ty = BindGroupType::DynamicUniformBuffer;
+                    }
+                    binding_data
+                }));
             }
         }
 
         let mut bind_group_map = BindGroupMap::default();
-        for (i, layout_data) in data_map.values().enumerate() {
-            let count = layout_data.last().unwrap().count;
-            let layout = BindGroupLayout::new(device, layout_data)?;
+        for (i, (set, layout_data)) in data_map.iter().enumerate() {
+            let max_count = layout_data.last().unwrap().count;
+            let count = if max_count > 1 {
+                max_count.min(max_bindless_count)
+            } else {
+                max_count
+            };
+            let layout =
+                BindGroupLayout::new(device, layout_data, push_descriptor_set == Some(*set))?;
             if let Some(name) = name {
                 layout.set_name(device, &format!("{name} [Descriptor Set Layout {i}]"));
             }
-            bind_group_map
-                .map
-                .insert(i, BindGroupData { count, layout });
+            bind_group_map.map.insert(
+                i,
+                BindGroupData {
+                    count,
+                    max_count,
+                    bindings: layout_data.clone(),
+                    layout,
+                },
+            );
         }
         bind_group_map
     };
@@ -47,6 +47,7 @@ impl From<AddressMode> for vk::SamplerAddressMode {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
 pub enum BorderColor {
     Black,
     White,
@@ -67,6 +68,7 @@ impl From<BorderColor> for vk::BorderColor {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
 pub enum MipmapMode {
     Linear,
     Nearest,
@@ -87,13 +89,35 @@ impl From<MipmapMode> for vk::SamplerMipmapMode {
     }
 }
 
-#[derive(Default)]
+#[derive(Debug, Clone, Copy)]
 pub struct SamplerDescription {
     pub name: Option<&'static str>,
     pub filter: Filter,
     pub address_mode: AddressMode,
     pub border_color: BorderColor,
     pub mipmap_mode: MipmapMode,
+    // Clamped to the device's `maxSamplerAnisotropy`
+    pub max_anisotropy: Option<f32>,
+    // Added to the computed level of detail before it is clamped to `min_lod..=max_lod`
+    pub mip_lod_bias: f32,
+    pub min_lod: f32,
+    pub max_lod: f32,
+}
+
+impl Default for SamplerDescription {
+    fn default() -> Self {
+        Self {
+            name: None,
+            filter: Default::default(),
+            address_mode: Default::default(),
+            border_color: Default::default(),
+            mipmap_mode: Default::default(),
+            max_anisotropy: None,
+            mip_lod_bias: 0.0,
+            min_lod: 0.0,
+            max_lod: vk::LOD_CLAMP_NONE,
+        }
+    }
 }
 
 pub struct Sampler {
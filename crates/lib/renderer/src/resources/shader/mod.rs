@@ -158,7 +158,8 @@ impl Shader {
                                     p_device_descriptor_indexing_properties
                                         .max_per_stage_descriptor_update_after_bind_storage_images
                                 }
-                                BindGroupType::UniformBuffer => {
+                                BindGroupType::UniformBuffer
+                                | BindGroupType::DynamicUniformBuffer => {
                                     p_device_descriptor_indexing_properties
                                         .max_per_stage_descriptor_update_after_bind_uniform_buffers
                                 }
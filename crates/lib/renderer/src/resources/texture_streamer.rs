@@ -0,0 +1,289 @@
+use super::{
+    bind_group::{BindGroup, BindGroupBindInfo, BindGroupWriteData},
+    buffer::Buffer,
+    image::{Format, Image, ImageDescription, Layout},
+    sampler::{Sampler, SamplerDescription},
+};
+use crate::{
+    command_queue::{CommandList, CommandQueue},
+    device::{Device, Fence},
+    renderer::WHITE_TEXTURE_INDEX,
+};
+use anyhow::Result;
+use math::size::Size2D;
+use std::{
+    collections::HashSet,
+    sync::mpsc::{self, Receiver, Sender},
+    thread::JoinHandle,
+};
+use thiserror::Error;
+
+// Runs on the streamer's worker thread, e.g. reading and decoding an image file
+pub type TextureLoader = Box<dyn FnOnce() -> Result<TextureData> + Send>;
+
+pub struct TextureData {
+    pub size: Size2D<u32>,
+    pub bytes: Vec<u8>,
+    // Overrides the format of `TextureStreamerDescription::image`, e.g. sRGB for color textures
+    pub format: Option<Format>,
+}
+
+#[derive(Debug, Error)]
+pub enum TextureStreamerError {
+    #[error("bindless array element {0} was already requested")]
+    AlreadyRequested(u32),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TextureStreamerDescription {
+    // Bindless binding the textures are written to
+    pub dst_binding: u32,
+    // Array element returned by `texture_index` until a texture is resident. The streamer doesn't
+    // write it, e.g. `WHITE_TEXTURE_INDEX` after `Renderer::write_default_textures`
+    pub placeholder_index: u32,
+    // Uploads recorded per frame, bounds the time `update` takes
+    pub uploads_per_frame: usize,
+    pub sampler: SamplerDescription,
+    pub image: ImageDescription,
+}
+
+impl Default for TextureStreamerDescription {
+    fn default() -> Self {
+        Self {
+            dst_binding: 0,
+            placeholder_index: WHITE_TEXTURE_INDEX,
+            uploads_per_frame: 4,
+            sampler: Default::default(),
+            image: Default::default(),
+        }
+    }
+}
+
+// Uploads submitted together, finished once `fence` is signaled
+struct UploadBatch {
+    cmd_list: CommandList,
+    fence: Fence,
+    staging_buffer: Buffer,
+    images: Vec<(u32, Image)>,
+}
+
+// Streams textures into a bindless bind group without blocking the frame. Loaders run on a worker
+// thread, and the decoded data is uploaded over the following frames. Uploads are recorded on the
+// graphics queue rather than a dedicated transfer queue: mipmaps are generated with blits, which
+// transfer-only queues can't record, so a transfer queue would still need a release and acquire
+// of every image by the graphics queue, and a semaphore wait, before it could be used.
+// Until a texture is resident, `texture_index` points to the placeholder element. The bindless
+// binding is `UPDATE_UNUSED_WHILE_PENDING`, so writing the elements of textures that were never
+// drawn is safe while earlier frames are still in flight. Each element can only be requested once,
+// since replacing a resident texture would rewrite an element those frames may sample
+pub struct TextureStreamer {
+    desc: TextureStreamerDescription,
+    bind_group: BindGroup,
+    sampler: Sampler,
+    job_sender: Option<Sender<(u32, TextureLoader)>>,
+    loaded_receiver: Receiver<(u32, Result<TextureData>)>,
+    worker: Option<JoinHandle<()>>,
+    in_flight: Vec<UploadBatch>,
+    free_batches: Vec<(CommandList, Fence)>,
+    resident: Vec<Option<Image>>,
+    requested: HashSet<u32>,
+    // Textures whose loader failed, they keep sampling the placeholder
+    failed: Vec<(u32, anyhow::Error)>,
+    finished: usize,
+}
+
+impl TextureStreamer {
+    pub fn new(
+        device: &Device,
+        bind_group: BindGroup,
+        desc: TextureStreamerDescription,
+    ) -> Result<Self> {
+        let sampler = device.create_sampler(desc.sampler)?;
+        let (job_sender, job_receiver) = mpsc::channel::<(u32, TextureLoader)>();
+        let (loaded_sender, loaded_receiver) = mpsc::channel();
+        let worker = std::thread::spawn(move || {
+            // Stops once the streamer drops its sender
+            while let Ok((index, loader)) = job_receiver.recv() {
+                if loaded_sender.send((index, loader())).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            desc,
+            bind_group,
+            sampler,
+            job_sender: Some(job_sender),
+            loaded_receiver,
+            worker: Some(worker),
+            in_flight: Default::default(),
+            free_batches: Default::default(),
+            resident: Default::default(),
+            requested: Default::default(),
+            failed: Default::default(),
+            finished: 0,
+        })
+    }
+
+    // Queues a texture for bindless array element `index`, each element can only be requested once
+    pub fn request(
+        &mut self,
+        index: u32,
+        loader: impl FnOnce() -> Result<TextureData> + Send + 'static,
+    ) -> Result<(), TextureStreamerError> {
+        if !self.requested.insert(index) {
+            return Err(TextureStreamerError::AlreadyRequested(index));
+        }
+        self.job_sender
+            .as_ref()
+            .unwrap()
+            .send((index, Box::new(loader)))
+            .expect("Texture streamer worker stopped");
+        Ok(())
+    }
+
+    // Bindless array element to sample for `index` this frame
+    pub fn texture_index(&self, index: u32) -> u32 {
+        match self.resident.get(index as usize) {
+            Some(Some(_)) => index,
+            _ => self.desc.placeholder_index,
+        }
+    }
+
+    pub fn is_resident(&self, index: u32) -> bool {
+        self.texture_index(index) == index
+    }
+
+    // Number of requested textures that have not been made resident or failed to load yet
+    pub fn pending_count(&self) -> usize {
+        self.requested.len() - self.finished
+    }
+
+    // Textures that failed to load, with the error their loader returned
+    pub fn failed(&self) -> &[(u32, anyhow::Error)] {
+        &self.failed
+    }
+
+    // Must be called once per frame, makes finished uploads resident and records new ones
+    pub fn update(&mut self, device: &Device, command_queue: &CommandQueue) -> Result<()> {
+        let mut idx = 0;
+        while idx < self.in_flight.len() {
+            if device.fence_signaled(self.in_flight[idx].fence)? {
+                let batch = self.in_flight.swap_remove(idx);
+                self.finish_batch(device, batch)?;
+            } else {
+                idx += 1;
+            }
+        }
+
+        let mut loaded = Vec::new();
+        while loaded.len() < self.desc.uploads_per_frame {
+            let Ok((index, data)) = self.loaded_receiver.try_recv() else {
+                break;
+            };
+            match data {
+                Ok(data) => loaded.push((index, data)),
+                Err(err) => {
+                    self.failed.push((index, err));
+                    self.finished += 1;
+                }
+            }
+        }
+        if loaded.is_empty() {
+            return Ok(());
+        }
+
+        let (cmd_list, fence) = match self.free_batches.pop() {
+            Some((cmd_list, fence)) => {
+                device.reset_fences(&[fence])?;
+                cmd_list.begin(device)?;
+                (cmd_list, fence)
+            }
+            None => (
+                command_queue.get_immediate_command_list(device)?,
+                device.create_fence(false)?,
+            ),
+        };
+        let image_data = loaded
+            .iter()
+            .map(|(_, data)| {
+                let desc = ImageDescription {
+                    format: data.format.unwrap_or(self.desc.image.format),
+                    ..self.desc.image
+                };
+                (data.size, data.bytes.as_slice(), desc)
+            })
+            .collect::<Vec<_>>();
+        let (images, staging_buffer) =
+            device.create_images_with_descriptions(&image_data, &cmd_list)?;
+        command_queue.submit(device, cmd_list, &[], &[], Some(fence))?;
+        self.in_flight.push(UploadBatch {
+            cmd_list,
+            fence,
+            staging_buffer,
+            images: loaded.iter().map(|(index, _)| *index).zip(images).collect(),
+        });
+        Ok(())
+    }
+
+    fn finish_batch(&mut self, device: &Device, batch: UploadBatch) -> Result<()> {
+        batch.staging_buffer.destroy(device);
+        // Draws recorded from now on sample the real texture, frames in flight keep the placeholder
+        let writes = batch
+            .images
+            .iter()
+            .map(|(index, image)| BindGroupBindInfo {
+                group: self.bind_group,
+                dst_binding: self.desc.dst_binding,
+                data: BindGroupWriteData::SampledImage(image.bind_info(
+                    &self.sampler,
+                    Layout::ShaderReadOnly,
+                    Some(*index),
+                )),
+            })
+            .collect::<Vec<_>>();
+        device.write_bind_group(&writes)?;
+
+        for (index, image) in batch.images {
+            let index = index as usize;
+            if index >= self.resident.len() {
+                self.resident.resize_with(index + 1, Default::default);
+            }
+            // Every element is only requested once
+            debug_assert!(self.resident[index].is_none());
+            self.resident[index] = Some(image);
+            self.finished += 1;
+        }
+        self.free_batches.push((batch.cmd_list, batch.fence));
+        Ok(())
+    }
+
+    // Expects the device to be idle, e.g. from `App::cleanup`
+    pub fn destroy(&mut self, device: &Device) -> Result<()> {
+        self.job_sender = None;
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+
+        let fences = self
+            .in_flight
+            .iter()
+            .map(|batch| batch.fence)
+            .collect::<Vec<_>>();
+        if !fences.is_empty() {
+            device.wait_fences(&fences)?;
+        }
+        for batch in std::mem::take(&mut self.in_flight) {
+            self.finish_batch(device, batch)?;
+        }
+        for (_, fence) in self.free_batches.drain(..) {
+            fence.destroy(device);
+        }
+        for mut image in self.resident.drain(..).flatten() {
+            image.destroy(device);
+        }
+        self.sampler.destroy(device);
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+use super::{
+    image::{Image, ImageDescription, ImageError},
+    memory::{AllocationKind, Memory},
+};
+use crate::{device::Device, util::find_memory_type_index};
+use anyhow::Result;
+use ash::vk;
+use math::size::Size2D;
+
+// Index of an image created with `RenderGraph::create_transient_image`, only valid for the frame it
+// was created in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TransientId(pub usize);
+
+// A transient image needed this frame, and the memory slot it was assigned by the render graph.
+// Transients in the same slot have lifetimes that don't overlap
+#[derive(Debug, Clone, Copy)]
+pub struct TransientImageRequest {
+    pub size: Size2D<u32>,
+    pub desc: ImageDescription,
+    pub slot: usize,
+}
+
+impl TransientImageRequest {
+    fn same_image(&self, other: &Self) -> bool {
+        self.slot == other.slot && self.size == other.size && self.desc == other.desc
+    }
+}
+
+// One memory allocation, and every image created so far that aliases it
+#[derive(Default)]
+struct TransientSlot {
+    memory: Option<Memory>,
+    memory_type_index: u32,
+    images: Vec<Image>,
+}
+
+impl TransientSlot {
+    fn fits(&self, req: &vk::MemoryRequirements) -> bool {
+        self.memory.as_ref().is_some_and(|memory| {
+            memory.req.size >= req.size
+                && memory.offset % req.alignment == 0
+                && req.memory_type_bits & (1 << self.memory_type_index) != 0
+        })
+    }
+
+    fn find_image(&self, request: &TransientImageRequest) -> Option<usize> {
+        self.images
+            .iter()
+            .position(|image| image.size == request.size && image.desc == request.desc)
+    }
+
+    fn destroy(&mut self, device: &Device) {
+        for mut image in self.images.drain(..) {
+            image.destroy(device);
+        }
+        if let Some(memory) = self.memory.take() {
+            memory.destroy(device);
+        }
+    }
+}
+
+// Backs the render graph's transient images. Images are cached between frames, so a graph that
+// requests the same transients every frame doesn't create anything after the first one
+#[derive(Default)]
+pub struct TransientImagePool {
+    slots: Vec<TransientSlot>,
+    // (slot, image) of every transient of the current frame, indexed by `TransientId`
+    frame_images: Vec<(usize, usize)>,
+}
+
+impl TransientImagePool {
+    pub fn prepare(&mut self, device: &Device, requests: &[TransientImageRequest]) -> Result<()> {
+        self.frame_images.clear();
+        let slot_count = requests.iter().map(|request| request.slot + 1).max();
+        if let Some(slot_count) = slot_count.filter(|count| *count > self.slots.len()) {
+            self.slots.resize_with(slot_count, Default::default);
+        }
+
+        // Images that don't exist yet, with the memory they need. Identical requests in the same
+        // slot resolve to the same image
+        let mut pending: Vec<(usize, vk::Image, vk::MemoryRequirements)> = Vec::new();
+        for (idx, request) in requests.iter().enumerate() {
+            let is_duplicate = pending
+                .iter()
+                .any(|(pending_idx, _, _)| requests[*pending_idx].same_image(request));
+            if !is_duplicate && self.slots[request.slot].find_image(request).is_none() {
+                let (image, req) = Image::create_unbound(device, request.size, request.desc)?;
+                pending.push((idx, image, req));
+            }
+        }
+
+        // Slots too small for their new images are reallocated. This only happens while the
+        // transients requested by the app settle, so waiting for the GPU beats deferring the
+        // destruction of every image aliasing the old memory
+        for (slot_idx, slot) in self.slots.iter_mut().enumerate() {
+            let fits = pending
+                .iter()
+                .filter(|(idx, _, _)| requests[*idx].slot == slot_idx)
+                .all(|(_, _, req)| slot.fits(req));
+            if fits {
+                continue;
+            }
+
+            if slot.memory.is_some() {
+                device.wait_idle()?;
+            }
+            slot.destroy(device);
+            for (idx, request) in requests.iter().enumerate() {
+                let is_pending = pending
+                    .iter()
+                    .any(|(pending_idx, _, _)| requests[*pending_idx].same_image(request));
+                if request.slot == slot_idx && !is_pending {
+                    let (image, req) = Image::create_unbound(device, request.size, request.desc)?;
+                    pending.push((idx, image, req));
+                }
+            }
+
+            let req = pending
+                .iter()
+                .filter(|(idx, _, _)| requests[*idx].slot == slot_idx)
+                .fold(
+                    vk::MemoryRequirements {
+                        size: 0,
+                        alignment: 1,
+                        memory_type_bits: !0,
+                    },
+                    |acc, (_, _, req)| vk::MemoryRequirements {
+                        size: acc.size.max(req.size),
+                        alignment: acc.alignment.max(req.alignment),
+                        memory_type_bits: acc.memory_type_bits & req.memory_type_bits,
+                    },
+                );
+            // Every request in a slot shares the same `MemoryType`, the render graph makes sure of it
+            let memory_ty = requests
+                .iter()
+                .find(|request| request.slot == slot_idx)
+                .map(|request| request.desc.memory_ty)
+                .unwrap_or_default();
+            slot.memory_type_index =
+                find_memory_type_index(&req, device.memopry_properties(), memory_ty.into())
+                    .ok_or(ImageError::NoSuitableMemoryType)?;
+            slot.memory =
+                Some(device.allocate_memory(req, slot.memory_type_index, AllocationKind::Image)?);
+        }
+
+        for (idx, image, _) in pending {
+            let request = &requests[idx];
+            let slot = &mut self.slots[request.slot];
+            let memory = slot.memory.as_ref().unwrap();
+            unsafe {
+                device
+                    .raw()
+                    .bind_image_memory(image, memory.raw, memory.offset)?;
+            }
+            slot.images.push(Image::from_bound(
+                device,
+                image,
+                request.size,
+                request.desc,
+                None,
+            )?);
+        }
+
+        for request in requests {
+            let image = self.slots[request.slot].find_image(request).unwrap();
+            self.frame_images.push((request.slot, image));
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, id: TransientId) -> Option<&Image> {
+        let (slot, image) = self.frame_images.get(id.0)?;
+        self.slots[*slot].images.get(*image)
+    }
+
+    // Number of memory allocations backing transient images
+    pub fn slot_count(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.memory.is_some())
+            .count()
+    }
+
+    pub fn frame_image_count(&self) -> usize {
+        self.frame_images.len()
+    }
+
+    pub fn reserved_bytes(&self) -> u64 {
+        self.slots
+            .iter()
+            .filter_map(|slot| slot.memory.as_ref())
+            .map(|memory| memory.req.size)
+            .sum()
+    }
+
+    // Must only be called once the GPU is done with every transient, i.e. after a resize
+    pub fn destroy(&mut self, device: &Device) {
+        for slot in &mut self.slots {
+            slot.destroy(device);
+        }
+        self.slots.clear();
+        self.frame_images.clear();
+    }
+}
@@ -1,5 +1,8 @@
 use crate::{
-    resources::{pipeline::graphics::GraphicsPipeline, shader::Shader},
+    resources::{
+        pipeline::{compute::ComputePipeline, graphics::GraphicsPipeline},
+        shader::Shader,
+    },
     ResourceId,
 };
 use anyhow::Result;
@@ -45,19 +48,61 @@ pub struct ShaderHotReloader {
     _watcher: Debouncer<RecommendedWatcher>,
     program_map: HashMap<ResourceId<Shader>, PipelineShaderIdSet>,
     to_be_updated: Arc<Mutex<UpdateList>>,
+    errors: Arc<Mutex<ShaderErrors>>,
+}
+
+// Latest compile error of every shader that is currently broken, in the order they broke
+#[derive(Debug, Default)]
+struct ShaderErrors {
+    errors: Vec<(ResourceId<Shader>, String)>,
+}
+
+impl ShaderErrors {
+    fn set(&mut self, handle: ResourceId<Shader>, error: String) {
+        match self.errors.iter_mut().find(|(h, _)| *h == handle) {
+            Some((_, old)) => *old = error,
+            None => self.errors.push((handle, error)),
+        }
+    }
+
+    fn clear(&mut self, handle: ResourceId<Shader>) {
+        self.errors.retain(|(h, _)| *h != handle);
+    }
+
+    fn message(&self) -> Option<String> {
+        if self.errors.is_empty() {
+            return None;
+        }
+        let errors: Vec<&str> = self.errors.iter().map(|(_, e)| e.as_str()).collect();
+        Some(errors.join("\n\n"))
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
-pub struct PipelineShaderIdSet {
+pub struct GraphicsShaderIdSet {
     pub pipeline_handle: ResourceId<GraphicsPipeline>,
     pub vertex_handle: ResourceId<Shader>,
     pub fragment_handle: ResourceId<Shader>,
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct ComputeShaderIdSet {
+    pub pipeline_handle: ResourceId<ComputePipeline>,
+    pub shader_handle: ResourceId<Shader>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PipelineShaderIdSet {
+    Graphics(GraphicsShaderIdSet),
+    Compute(ComputeShaderIdSet),
+}
+
 pub struct ShaderHotReloaderRunner {
     watcher: Debouncer<RecommendedWatcher>,
     receiver: Receiver<Result<Vec<DebouncedEvent>, Vec<notify::Error>>>,
     shader_map: HashMap<PathBuf, (ResourceId<Shader>, ShaderStage)>,
+    // Maps an included file to every shader that depends on it
+    dependency_map: HashMap<PathBuf, Vec<PathBuf>>,
     program_map: HashMap<ResourceId<Shader>, PipelineShaderIdSet>,
 }
 
@@ -69,6 +114,7 @@ impl ShaderHotReloaderRunner {
             watcher,
             receiver,
             shader_map: Default::default(),
+            dependency_map: Default::default(),
             program_map: Default::default(),
         })
     }
@@ -81,11 +127,11 @@ impl ShaderHotReloaderRunner {
         fragment_handle: ResourceId<Shader>,
         pipeline_handle: ResourceId<GraphicsPipeline>,
     ) -> Result<(), notify::Error> {
-        let pipeline_shader_set = PipelineShaderIdSet {
+        let pipeline_shader_set = PipelineShaderIdSet::Graphics(GraphicsShaderIdSet {
             pipeline_handle,
             vertex_handle,
             fragment_handle,
-        };
+        });
 
         let absolute_vertex_path = absolute_vertex_path.as_ref();
         debug_assert!(
@@ -100,6 +146,7 @@ impl ShaderHotReloaderRunner {
             (vertex_handle, ShaderStage::Vertex),
         );
         self.program_map.insert(vertex_handle, pipeline_shader_set);
+        self.watch_includes(absolute_vertex_path, absolute_vertex_path)?;
 
         let absolute_fragment_path = absolute_fragment_path.as_ref();
         debug_assert!(
@@ -115,7 +162,95 @@ impl ShaderHotReloaderRunner {
         );
         self.program_map
             .insert(fragment_handle, pipeline_shader_set);
+        self.watch_includes(absolute_fragment_path, absolute_fragment_path)?;
+
+        Ok(())
+    }
+
+    pub fn set_compute(
+        &mut self,
+        absolute_path: impl AsRef<Path>,
+        shader_handle: ResourceId<Shader>,
+        pipeline_handle: ResourceId<ComputePipeline>,
+    ) -> Result<(), notify::Error> {
+        let pipeline_shader_set = PipelineShaderIdSet::Compute(ComputeShaderIdSet {
+            pipeline_handle,
+            shader_handle,
+        });
+
+        let absolute_path = absolute_path.as_ref();
+        debug_assert!(
+            absolute_path.is_absolute(),
+            "paths passed to shader hot reloader must be absolute: {absolute_path:?}"
+        );
+        self.watcher
+            .watcher()
+            .watch(absolute_path, RecursiveMode::NonRecursive)?;
+        self.shader_map.insert(
+            absolute_path.to_path_buf(),
+            (shader_handle, ShaderStage::Compute),
+        );
+        self.program_map.insert(shader_handle, pipeline_shader_set);
+        self.watch_includes(absolute_path, absolute_path)?;
+
+        Ok(())
+    }
+
+    pub fn add_dependency(
+        &mut self,
+        absolute_parent_path: impl AsRef<Path>,
+        absolute_included_path: impl AsRef<Path>,
+    ) -> Result<(), notify::Error> {
+        let absolute_parent_path = absolute_parent_path.as_ref();
+        let absolute_included_path = absolute_included_path.as_ref();
+        debug_assert!(
+            absolute_included_path.is_absolute(),
+            "paths passed to shader hot reloader must be absolute: {absolute_included_path:?}"
+        );
 
+        let parents = self
+            .dependency_map
+            .entry(absolute_included_path.to_path_buf())
+            .or_default();
+        if parents.iter().any(|path| path == absolute_parent_path) {
+            return Ok(());
+        }
+        let first_dependent = parents.is_empty();
+        parents.push(absolute_parent_path.to_path_buf());
+
+        if first_dependent && !self.shader_map.contains_key(absolute_included_path) {
+            self.watcher
+                .watcher()
+                .watch(absolute_included_path, RecursiveMode::NonRecursive)?;
+        }
+
+        Ok(())
+    }
+
+    fn watch_includes(
+        &mut self,
+        shader_path: &Path,
+        current_path: &Path,
+    ) -> Result<(), notify::Error> {
+        for include in parse_includes(current_path) {
+            // Includes the compiler resolves from its own search paths aren't next to the shader
+            if !include.exists() {
+                println!(
+                    "Not watching {}, included by {}: file not found",
+                    include.display(),
+                    current_path.display()
+                );
+                continue;
+            }
+            let already_tracked = self.dependency_map.get(&include).map_or(false, |parents| {
+                parents.iter().any(|path| path == shader_path)
+            });
+            if already_tracked {
+                continue;
+            }
+            self.add_dependency(shader_path, &include)?;
+            self.watch_includes(shader_path, &include)?;
+        }
         Ok(())
     }
 
@@ -123,7 +258,8 @@ impl ShaderHotReloaderRunner {
         let Self {
             watcher,
             receiver,
-            mut shader_map,
+            shader_map,
+            dependency_map,
             program_map,
         } = self;
 
@@ -132,23 +268,57 @@ impl ShaderHotReloaderRunner {
                 .expect("Could not create shader compiler");
         let to_be_updated = Arc::<Mutex<_>>::default();
         let to_be_updated_arc = Arc::clone(&to_be_updated);
+        let errors = Arc::<Mutex<_>>::default();
+        let errors_arc = Arc::clone(&errors);
         std::thread::spawn(move || loop {
             match receiver.recv() {
                 Ok(event) => {
                     match event {
                         Ok(events) => {
                             for event in &events {
-                                if let Some((handle, stage)) = shader_map.get_mut(&event.path) {
+                                let mut to_compile = Vec::new();
+                                if shader_map.contains_key(&event.path) {
+                                    to_compile.push(event.path.clone());
+                                }
+                                if let Some(parents) = dependency_map.get(&event.path) {
+                                    to_compile.extend(parents.iter().cloned());
+                                }
+                                if !to_compile.is_empty() {
                                     println!("{event:#?}");
-                                    let artifact = shader_compiler
-                                        .compile_shader(&event.path, *stage)
-                                        .expect("failed to compiler shader");
-                                    let mut lock: MutexGuard<UpdateList> =
-                                        to_be_updated_arc.lock().expect("mutex lock poisoned");
-                                    lock.push(UpdateData {
-                                        shader_handle: *handle,
-                                        bytes: artifact.as_binary_u8().to_vec(),
-                                    });
+                                }
+                                for path in &to_compile {
+                                    let Some((handle, stage)) = shader_map.get(path) else {
+                                        continue;
+                                    };
+                                    // Compiled before taking any lock, the main thread reads the
+                                    // errors every frame
+                                    match shader_compiler.compile_shader(path, *stage) {
+                                        Ok(artifact) => {
+                                            errors_arc
+                                                .lock()
+                                                .expect("mutex lock poisoned")
+                                                .clear(*handle);
+                                            let mut lock: MutexGuard<UpdateList> =
+                                                to_be_updated_arc
+                                                    .lock()
+                                                    .expect("mutex lock poisoned");
+                                            lock.push(UpdateData {
+                                                shader_handle: *handle,
+                                                bytes: artifact.as_binary_u8().to_vec(),
+                                            });
+                                        }
+                                        Err(err) => {
+                                            let error = format!(
+                                                "Failed to compile {}:\n{err:?}",
+                                                path.display()
+                                            );
+                                            println!("{error}");
+                                            errors_arc
+                                                .lock()
+                                                .expect("mutex lock poisoned")
+                                                .set(*handle, error);
+                                        }
+                                    }
                                 }
                             }
                         }
@@ -168,6 +338,7 @@ impl ShaderHotReloaderRunner {
             _watcher: watcher,
             program_map,
             to_be_updated,
+            errors,
         }
     }
 }
@@ -182,6 +353,32 @@ impl ShaderHotReloader {
     pub fn get_pipeline(&self, handle: ResourceId<Shader>) -> Option<&PipelineShaderIdSet> {
         self.program_map.get(&handle)
     }
+
+    // Every shader that failed its last compile, `None` once all of them compile again
+    pub fn error(&self) -> Option<String> {
+        self.errors.lock().expect("Mutex lock poisoned").message()
+    }
+}
+
+fn parse_includes(path: &Path) -> Vec<PathBuf> {
+    let Ok(source) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    source
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim_start().strip_prefix("#include")?.trim();
+            let name = rest
+                .strip_prefix('"')
+                .and_then(|rest| rest.split('"').next())
+                .or_else(|| {
+                    rest.strip_prefix('<')
+                        .and_then(|rest| rest.split('>').next())
+                })?;
+            Some(parent.join(name))
+        })
+        .collect()
 }
 
 #[derive(Debug, Error)]
@@ -243,6 +440,35 @@ impl HotReloaderState {
         }
     }
 
+    pub fn set_compute(
+        &mut self,
+        absolute_path: impl AsRef<Path>,
+        shader_handle: ResourceId<Shader>,
+        pipeline_handle: ResourceId<ComputePipeline>,
+    ) -> Result<(), ShaderHotReloadError> {
+        match self {
+            HotReloaderState::Init(runner) => {
+                runner.set_compute(absolute_path, shader_handle, pipeline_handle)?;
+                Ok(())
+            }
+            HotReloaderState::Running(_) => Err(ShaderHotReloadError::ShouldBeInit),
+        }
+    }
+
+    pub fn add_dependency(
+        &mut self,
+        absolute_parent_path: impl AsRef<Path>,
+        absolute_included_path: impl AsRef<Path>,
+    ) -> Result<(), ShaderHotReloadError> {
+        match self {
+            HotReloaderState::Init(runner) => {
+                runner.add_dependency(absolute_parent_path, absolute_included_path)?;
+                Ok(())
+            }
+            HotReloaderState::Running(_) => Err(ShaderHotReloadError::ShouldBeInit),
+        }
+    }
+
     pub fn drain(&mut self) -> Result<impl Iterator<Item = UpdateData>, ShaderHotReloadError> {
         match self {
             HotReloaderState::Init(_) => {
@@ -266,4 +492,11 @@ impl HotReloaderState {
             HotReloaderState::Running(reloader) => reloader.program_map.get(&handle),
         }
     }
+
+    pub fn error(&self) -> Option<String> {
+        match self {
+            HotReloaderState::Init(_) => None,
+            HotReloaderState::Running(reloader) => reloader.error(),
+        }
+    }
 }
@@ -1,9 +1,36 @@
 use crate::{
-    command_queue::{set_image_memory_barrier, CommandList},
-    device::Device,
+    command_queue::{set_image_memory_barrier, CommandList, CommandQueue},
+    device::{Device, Semaphore},
+    resources::{
+        buffer::{BufferDescription, BufferUsage},
+        image::{Image, ImageDescription, ImageUsage},
+    },
 };
 use anyhow::Result;
 use ash::vk;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SwapchainError {
+    #[error("Swapchain is out of date and must be recreated")]
+    OutOfDate,
+    #[error("Swapchain does not render into a headless image")]
+    NotHeadless,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapchainStatus {
+    Optimal,
+    // Presentation still works, but the swapchain no longer matches the surface exactly
+    Suboptimal,
+    OutOfDate,
+}
+
+impl SwapchainStatus {
+    pub fn needs_recreation(&self) -> bool {
+        !matches!(self, SwapchainStatus::Optimal)
+    }
+}
 
 type SwapchainStructures = (
     vk::SwapchainKHR,
@@ -29,7 +56,12 @@ fn create_swapchain_structures(
     };
 
     let swapchain_create_info = vk::SwapchainCreateInfoKHR::builder()
-        .surface(device.surface().surface)
+        .surface(
+            device
+                .surface()
+                .expect("Swapchains require a device with a surface")
+                .surface,
+        )
         .min_image_count(device.surface_data.desired_image_count)
         .image_color_space(device.surface_data.surface_format.color_space)
         .image_format(device.surface_data.surface_format.format)
@@ -112,14 +144,19 @@ fn create_swapchain_structures(
 pub struct SwapchainImage {
     pub(crate) _image: vk::Image,
     pub(crate) image_view: vk::ImageView,
+    pub(crate) format: vk::Format,
     pub(crate) index: u32,
-    pub(crate) _is_suboptimal: bool,
+    pub(crate) is_suboptimal: bool,
 }
 
 impl SwapchainImage {
     pub fn index(&self) -> u32 {
         self.index
     }
+
+    pub fn is_suboptimal(&self) -> bool {
+        self.is_suboptimal
+    }
 }
 
 pub struct Swapchain {
@@ -128,6 +165,8 @@ pub struct Swapchain {
     pub present_images: Vec<vk::Image>,
     pub present_image_views: Vec<vk::ImageView>,
     pub present_image_layouts: Vec<vk::ImageLayout>,
+    // Offscreen render target used in place of the swapchain images on headless devices
+    headless_image: Option<Image>,
 }
 
 impl Swapchain {
@@ -135,6 +174,18 @@ impl Swapchain {
         let swapchain_loader =
             ash::extensions::khr::Swapchain::new(device.instance().raw(), device.raw());
 
+        if device.is_headless() {
+            let image = Self::create_headless_image(device)?;
+            return Ok(Self {
+                swapchain_loader,
+                swapchain: vk::SwapchainKHR::null(),
+                present_images: vec![image.raw],
+                present_image_views: vec![image.view],
+                present_image_layouts: vec![vk::ImageLayout::UNDEFINED],
+                headless_image: Some(image),
+            });
+        }
+
         let (swapchain, present_images, present_image_views, present_image_layouts) =
             create_swapchain_structures(device, &swapchain_loader, None)?;
 
@@ -144,11 +195,61 @@ impl Swapchain {
             present_images,
             present_image_views,
             present_image_layouts,
+            headless_image: None,
         };
 
         Ok(ret)
     }
 
+    fn create_headless_image(device: &Device) -> Result<Image> {
+        Image::create(
+            device,
+            device.surface_data().size(),
+            ImageDescription {
+                format: device.surface_data().format(),
+                usage: ImageUsage::ColorAttachment,
+                name: Some("Headless Render Target"),
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.headless_image.is_some()
+    }
+
+    // Copies the last presented frame of a headless swapchain into tightly packed pixels, in the
+    // format of `device.surface_data().format()`
+    pub fn read_headless_image(
+        &self,
+        device: &Device,
+        command_queue: &CommandQueue,
+    ) -> Result<Vec<u8>> {
+        let image = self
+            .headless_image
+            .as_ref()
+            .ok_or(SwapchainError::NotHeadless)?;
+        let buffer = device.create_buffer(
+            image.size.width() as u64
+                * image.size.height() as u64
+                * image.desc.format.bytes_per_pixel() as u64,
+            BufferDescription {
+                usage: BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+        )?;
+
+        let cmd_list = command_queue.get_immediate_command_list(device)?;
+        cmd_list.copy_image_to_buffer(device, image, &buffer);
+        cmd_list.end(device)?;
+        cmd_list.immediate_submit(device, device.present_queue())?;
+        cmd_list.reset(device)?;
+
+        let bytes = buffer.read_bytes();
+        buffer.destroy(device);
+        Ok(bytes?)
+    }
+
     pub fn num_images(&self) -> usize {
         self.present_images.len()
     }
@@ -158,20 +259,35 @@ impl Swapchain {
         device: &Device,
         command_list: &CommandList,
     ) -> Result<SwapchainImage> {
-        let (index, is_suboptimal) = unsafe {
-            self.swapchain_loader.acquire_next_image(
-                self.swapchain,
-                std::u64::MAX,
-                device.image_acquired_semaphore(),
-                vk::Fence::null(),
-            )
-        }?;
+        let (index, is_suboptimal) = if self.is_headless() {
+            (0, false)
+        } else {
+            match unsafe {
+                self.swapchain_loader.acquire_next_image(
+                    self.swapchain,
+                    std::u64::MAX,
+                    device.image_acquired_semaphore(),
+                    vk::Fence::null(),
+                )
+            } {
+                Ok(ret) => ret,
+                Err(err) => {
+                    // The frame is abandoned, so nothing will wait on this frame's compute work
+                    device.discard_compute_wait()?;
+                    return Err(match err {
+                        vk::Result::ERROR_OUT_OF_DATE_KHR => SwapchainError::OutOfDate.into(),
+                        err => err.into(),
+                    });
+                }
+            }
+        };
 
         let swapchain_image = SwapchainImage {
             index,
             _image: self.present_images[index as usize],
             image_view: self.present_image_views[index as usize],
-            _is_suboptimal: is_suboptimal,
+            format: device.surface_data().surface_format.format,
+            is_suboptimal,
         };
 
         self.transition_image(device, command_list, swapchain_image);
@@ -184,26 +300,98 @@ impl Swapchain {
         device: &Device,
         cmd_list: CommandList,
         image: SwapchainImage,
-    ) -> Result<bool> {
+    ) -> Result<SwapchainStatus> {
+        self.present_with_semaphores(device, cmd_list, image, &[], &[])
+    }
+
+    // Same as `present`, with the frame submission also waiting on `wait`, e.g. for rendering done
+    // outside of the renderer, and signaling `signal` once the frame is rendered, e.g. for an
+    // encoder reading the frame back. The renderer's own semaphores are consumed by presentation
+    pub fn present_with_semaphores(
+        &mut self,
+        device: &Device,
+        cmd_list: CommandList,
+        image: SwapchainImage,
+        wait: &[Semaphore],
+        signal: &[Semaphore],
+    ) -> Result<SwapchainStatus> {
         self.transition_image(device, &cmd_list, image);
 
         cmd_list.end(device)?;
 
-        let render_complete_fence = device.command_buffer_executed_fence();
-        let render_complete_semaphore = [device.render_complete_semaphore()];
+        // Headless frames have no image to acquire and nothing to present
+        let render_complete_semaphore = if self.is_headless() {
+            vec![]
+        } else {
+            vec![device.render_complete_semaphore()]
+        };
 
         let command_buffers = [cmd_list.buffer()];
-        let wait_semaphores = [device.image_acquired_semaphore()];
-        let wait_dst_stage_mask = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+        let (mut wait_semaphores, mut wait_dst_stage_mask) = if self.is_headless() {
+            (vec![], vec![])
+        } else {
+            (
+                vec![device.image_acquired_semaphore()],
+                vec![vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT],
+            )
+        };
+        if let Some(compute_complete_semaphore) = device.take_compute_wait() {
+            wait_semaphores.push(compute_complete_semaphore);
+            wait_dst_stage_mask.push(
+                vk::PipelineStageFlags::DRAW_INDIRECT
+                    | vk::PipelineStageFlags::VERTEX_INPUT
+                    | vk::PipelineStageFlags::VERTEX_SHADER
+                    | vk::PipelineStageFlags::FRAGMENT_SHADER,
+            );
+        }
+        for semaphore in device
+            .take_external_waits()
+            .into_iter()
+            .chain(wait.iter().map(|semaphore| semaphore.0))
+        {
+            wait_semaphores.push(semaphore);
+            wait_dst_stage_mask.push(vk::PipelineStageFlags::ALL_COMMANDS);
+        }
+        let mut binary_signal_semaphores = render_complete_semaphore.clone();
+        binary_signal_semaphores.extend(signal.iter().map(|semaphore| semaphore.0));
+
+        // With a timeline semaphore, frame completion is tracked by the submit value instead of the fence
+        let submit_value = device.next_submit_value();
+        let (render_complete_fence, signal_semaphores, signal_values) =
+            match device.timeline_semaphore() {
+                Some(timeline_semaphore) => {
+                    // Values for binary semaphores are ignored
+                    let mut signal_values = vec![0; binary_signal_semaphores.len()];
+                    signal_values.push(submit_value);
+                    let mut signal_semaphores = binary_signal_semaphores;
+                    signal_semaphores.push(timeline_semaphore);
+                    (vk::Fence::null(), signal_semaphores, signal_values)
+                }
+                None => (
+                    device.command_buffer_executed_fence(),
+                    binary_signal_semaphores,
+                    vec![],
+                ),
+            };
+        let mut timeline_submit_info = vk::TimelineSemaphoreSubmitInfo::builder()
+            .signal_semaphore_values(&signal_values)
+            .build();
 
-        let submit_info = vk::SubmitInfo::builder()
+        let mut submit_info = vk::SubmitInfo::builder()
             .command_buffers(&command_buffers)
             .wait_semaphores(&wait_semaphores)
             .wait_dst_stage_mask(&wait_dst_stage_mask)
-            .signal_semaphores(&render_complete_semaphore)
-            .build();
+            .signal_semaphores(&signal_semaphores);
+        if device.supports_timeline_semaphore() {
+            submit_info = submit_info.push_next(&mut timeline_submit_info);
+        }
+        let submit_info = submit_info.build();
 
         unsafe {
+            // Reset right before submitting, so frames that are never submitted leave it signaled
+            if render_complete_fence != vk::Fence::null() {
+                device.raw().reset_fences(&[render_complete_fence])?;
+            }
             device.raw().queue_submit(
                 device.present_queue(),
                 &[submit_info],
@@ -211,16 +399,27 @@ impl Swapchain {
             )
         }?;
 
+        if self.is_headless() {
+            // Frames are read back right after being rendered, so there is nothing to overlap with
+            unsafe { device.raw().queue_wait_idle(device.present_queue()) }?;
+            return Ok(SwapchainStatus::Optimal);
+        }
+
         let present_info = vk::PresentInfoKHR::builder()
             .wait_semaphores(&render_complete_semaphore)
             .swapchains(&[self.swapchain])
             .image_indices(&[image.index])
             .build();
 
-        Ok(unsafe {
+        match unsafe {
             self.swapchain_loader
                 .queue_present(device.present_queue(), &present_info)
-        }?)
+        } {
+            Ok(false) if !image.is_suboptimal => Ok(SwapchainStatus::Optimal),
+            Ok(_) => Ok(SwapchainStatus::Suboptimal),
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => Ok(SwapchainStatus::OutOfDate),
+            Err(err) => Err(err.into()),
+        }
     }
 
     fn transition_image(
@@ -234,7 +433,11 @@ impl Swapchain {
         let to_present = vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL == *layout;
 
         let new_layout = if to_present {
-            vk::ImageLayout::PRESENT_SRC_KHR
+            if self.is_headless() {
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL
+            } else {
+                vk::ImageLayout::PRESENT_SRC_KHR
+            }
         } else {
             vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
         };
@@ -259,6 +462,14 @@ impl Swapchain {
     }
 
     pub fn resize(&mut self, device: &Device) -> Result<()> {
+        if let Some(image) = &mut self.headless_image {
+            image.resize(device, device.surface_data().size())?;
+            self.present_images = vec![image.raw];
+            self.present_image_views = vec![image.view];
+            self.present_image_layouts = vec![vk::ImageLayout::UNDEFINED];
+            return Ok(());
+        }
+
         self.clean_images(device.raw());
 
         let (swapchain, present_images, present_image_views, present_image_layouts) =
@@ -281,6 +492,11 @@ impl Swapchain {
     }
 
     pub fn destroy(&mut self, device: &Device) {
+        if let Some(image) = &mut self.headless_image {
+            image.destroy(device);
+            return;
+        }
+
         self.clean_images(device.raw());
         unsafe {
             self.swapchain_loader
@@ -0,0 +1,184 @@
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+// When set, reference images are overwritten with the rendered output instead of compared
+pub const UPDATE_REFERENCES_ENV: &str = "CINDER_UPDATE_REFERENCES";
+
+#[derive(Debug, Error)]
+pub enum TestingError {
+    #[error("reference image {0:?} does not exist, set CINDER_UPDATE_REFERENCES to create it")]
+    MissingReference(PathBuf),
+    #[error("rendered image has {actual} bytes, reference has {expected}")]
+    SizeMismatch { actual: usize, expected: usize },
+    #[error("{mismatched_pixels} pixels differ from {path:?} by up to {max_difference}")]
+    ImageMismatch {
+        mismatched_pixels: usize,
+        max_difference: u8,
+        path: PathBuf,
+    },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ImageDiff {
+    // Pixels with at least one channel differing by more than the tolerance
+    pub mismatched_pixels: usize,
+    // Largest per-channel difference over the whole image
+    pub max_difference: u8,
+}
+
+impl ImageDiff {
+    pub fn is_match(&self) -> bool {
+        self.mismatched_pixels == 0
+    }
+}
+
+// Compares two images with the same layout channel by channel
+pub fn diff_images(
+    pixels: &[u8],
+    reference: &[u8],
+    bytes_per_pixel: usize,
+    tolerance: u8,
+) -> ImageDiff {
+    pixels
+        .chunks_exact(bytes_per_pixel)
+        .zip(reference.chunks_exact(bytes_per_pixel))
+        .fold(ImageDiff::default(), |mut diff, (pixel, reference)| {
+            let max_difference = pixel
+                .iter()
+                .zip(reference)
+                .map(|(a, b)| a.abs_diff(*b))
+                .max()
+                .unwrap_or(0);
+            if max_difference > tolerance {
+                diff.mismatched_pixels += 1;
+            }
+            diff.max_difference = diff.max_difference.max(max_difference);
+            diff
+        })
+}
+
+// Compares 4-byte-per-pixel `pixels` against the reference stored at `reference_path`.
+// A missing reference is an error, references are only written when `UPDATE_REFERENCES_ENV`
+// is set
+pub fn check_against_reference(
+    pixels: &[u8],
+    reference_path: impl AsRef<Path>,
+    tolerance: u8,
+) -> Result<(), TestingError> {
+    compare_or_update(
+        pixels,
+        reference_path.as_ref(),
+        tolerance,
+        std::env::var_os(UPDATE_REFERENCES_ENV).is_some(),
+    )
+}
+
+fn compare_or_update(
+    pixels: &[u8],
+    reference_path: &Path,
+    tolerance: u8,
+    update: bool,
+) -> Result<(), TestingError> {
+    if update {
+        if let Some(parent) = reference_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(reference_path, pixels)?;
+        eprintln!("Wrote reference image {reference_path:?}");
+        return Ok(());
+    }
+    if !reference_path.exists() {
+        return Err(TestingError::MissingReference(reference_path.to_owned()));
+    }
+
+    let reference = std::fs::read(reference_path)?;
+    if reference.len() != pixels.len() {
+        return Err(TestingError::SizeMismatch {
+            actual: pixels.len(),
+            expected: reference.len(),
+        });
+    }
+
+    let diff = diff_images(pixels, &reference, 4, tolerance);
+    if diff.is_match() {
+        Ok(())
+    } else {
+        Err(TestingError::ImageMismatch {
+            mismatched_pixels: diff.mismatched_pixels,
+            max_difference: diff.max_difference,
+            path: reference_path.to_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_or_update, diff_images, ImageDiff, TestingError};
+    use std::path::PathBuf;
+
+    fn temp_reference(name: &str) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("cinder-testing-{}", std::process::id()))
+            .join(name);
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn differences_within_tolerance_match() {
+        let pixels = [10, 20, 30, 255, 0, 0, 0, 255];
+        let reference = [12, 18, 30, 255, 0, 0, 0, 255];
+        let diff = diff_images(&pixels, &reference, 4, 2);
+        assert_eq!(
+            diff,
+            ImageDiff {
+                mismatched_pixels: 0,
+                max_difference: 2,
+            }
+        );
+        assert!(diff.is_match());
+    }
+
+    #[test]
+    fn pixels_over_tolerance_are_counted_once() {
+        // Every channel of the first pixel is off, only one channel of the second
+        let pixels = [0, 0, 0, 0, 100, 100, 100, 255];
+        let reference = [9, 9, 9, 9, 100, 107, 100, 255];
+        let diff = diff_images(&pixels, &reference, 4, 2);
+        assert_eq!(diff.mismatched_pixels, 2);
+        assert_eq!(diff.max_difference, 9);
+        assert!(!diff.is_match());
+    }
+
+    #[test]
+    fn missing_reference_fails() {
+        let path = temp_reference("missing.bgra");
+        let result = compare_or_update(&[0; 4], &path, 0, false);
+        assert!(matches!(result, Err(TestingError::MissingReference(_))));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn updated_reference_is_compared_against() {
+        let path = temp_reference("updated.bgra");
+        compare_or_update(&[1, 2, 3, 4], &path, 0, true).unwrap();
+        compare_or_update(&[1, 2, 3, 4], &path, 0, false).unwrap();
+        assert!(matches!(
+            compare_or_update(&[1, 2, 3, 9], &path, 2, false),
+            Err(TestingError::ImageMismatch {
+                mismatched_pixels: 1,
+                max_difference: 5,
+                ..
+            })
+        ));
+        assert!(matches!(
+            compare_or_update(&[1, 2, 3, 4, 5, 6, 7, 8], &path, 0, false),
+            Err(TestingError::SizeMismatch {
+                actual: 8,
+                expected: 4,
+            })
+        ));
+    }
+}
@@ -1,7 +1,12 @@
 use ash::vk;
 use math::rect::Rect2D;
 use num::ToPrimitive;
-use std::ffi::c_void;
+use std::{
+    collections::HashMap,
+    ffi::c_void,
+    panic::Location,
+    sync::{Mutex, OnceLock},
+};
 
 fn calc_padding(adr: vk::DeviceSize, align: vk::DeviceSize) -> vk::DeviceSize {
     (align - adr % align) % align
@@ -63,6 +68,10 @@ impl MemoryMappablePointer {
     pub fn copy_from<T: Copy>(&self, data: &[T], size: usize) {
         unsafe { self.0.copy_from(data.as_ptr() as *mut c_void, size) };
     }
+
+    pub fn read_bytes(&self, size: usize) -> Vec<u8> {
+        unsafe { std::slice::from_raw_parts(self.0 as *const u8, size) }.to_vec()
+    }
 }
 
 pub fn rect_to_vk<N: num::Num + Copy + ToPrimitive, M: num::Num + Copy + ToPrimitive>(
@@ -79,3 +88,31 @@ pub fn rect_to_vk<N: num::Num + Copy + ToPrimitive, M: num::Num + Copy + ToPrimi
         },
     })
 }
+
+// Debug name for a resource created without one, from where it was created, e.g.
+// `buffer@bindless/main.rs:210`. Names are kept for the lifetime of the program so descriptions
+// can hold on to them, which is bounded by the number of call sites
+pub(crate) fn caller_name(
+    kind: &'static str,
+    location: &'static Location<'static>,
+) -> &'static str {
+    static NAMES: OnceLock<Mutex<HashMap<(&'static str, Location<'static>), &'static str>>> =
+        OnceLock::new();
+    let mut names = NAMES
+        .get_or_init(Default::default)
+        .lock()
+        .expect("Mutex lock poisoned");
+    names.entry((kind, *location)).or_insert_with(|| {
+        // Keep the crate directory and the path within its `src`, dropping the rest
+        let components = location.file().split(['/', '\\']).collect::<Vec<_>>();
+        let path = match components.iter().rposition(|component| *component == "src") {
+            Some(src) if src > 0 => {
+                let mut path = vec![components[src - 1]];
+                path.extend(&components[src + 1..]);
+                path.join("/")
+            }
+            _ => location.file().to_owned(),
+        };
+        Box::leak(format!("{kind}@{path}:{}", location.line()).into_boxed_str())
+    })
+}
@@ -30,7 +30,10 @@ impl<T> ResourceId<T> {
 
 impl<T> Debug for ResourceId<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("ResourceId").field("id", &self.id).finish()
+        f.debug_struct("ResourceId")
+            .field("id", &self.id)
+            .field("generation", &self.generation)
+            .finish()
     }
 }
 
@@ -48,7 +51,7 @@ impl<T> Copy for ResourceId<T> {}
 
 impl<T> PartialEq for ResourceId<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+        self.id == other.id && self.generation == other.generation
     }
 }
 impl<T> Eq for ResourceId<T> {}
@@ -56,17 +59,18 @@ impl<T> Eq for ResourceId<T> {}
 impl<T> Hash for ResourceId<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.id.hash(state);
+        self.generation.hash(state);
     }
 }
 
 impl<T> PartialOrd for ResourceId<T> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.id.partial_cmp(&other.id)
+        Some(self.cmp(other))
     }
 }
 impl<T> Ord for ResourceId<T> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.id.cmp(&other.id)
+        (self.id, self.generation).cmp(&(other.id, other.generation))
     }
 }
 
@@ -1,22 +1,14 @@
 use crate::ResourceId;
 
+// Each slot keeps its generation after being freed, so ids of the resource it used to hold stay
+// invalid once the slot is reused
 enum Resource<T> {
     Alive { raw: T, generation: u32 },
-    Dead,
-}
-
-impl<T> Resource<T> {
-    fn is_alive(&self) -> bool {
-        match self {
-            Resource::Alive { .. } => true,
-            Resource::Dead => false,
-        }
-    }
+    Dead { generation: u32 },
 }
 
 pub struct ResourcePool<T> {
     resources: Vec<Resource<T>>,
-    generation: u32,
     free_indices: Vec<usize>,
 }
 
@@ -24,7 +16,6 @@ impl<T> Default for ResourcePool<T> {
     fn default() -> Self {
         Self {
             resources: Default::default(),
-            generation: 0,
             free_indices: Default::default(),
         }
     }
@@ -36,19 +27,25 @@ impl<T> ResourcePool<T> {
     }
 
     pub fn insert(&mut self, resource: T) -> ResourceId<T> {
-        let new_res = Resource::Alive {
-            raw: resource,
-            generation: self.generation,
-        };
         match self.free_indices.pop() {
             Some(id) => {
-                self.resources[id] = new_res;
-                ResourceId::new(id, self.generation)
+                let generation = match self.resources[id] {
+                    Resource::Dead { generation } => generation,
+                    Resource::Alive { .. } => unreachable!(),
+                };
+                self.resources[id] = Resource::Alive {
+                    raw: resource,
+                    generation,
+                };
+                ResourceId::new(id, generation)
             }
             None => {
                 let id = self.resources.len();
-                self.resources.push(new_res);
-                ResourceId::new(id, self.generation)
+                self.resources.push(Resource::Alive {
+                    raw: resource,
+                    generation: 0,
+                });
+                ResourceId::new(id, 0)
             }
         }
     }
@@ -86,7 +83,7 @@ impl<T> ResourcePool<T> {
                     );
                     match old {
                         Resource::Alive { raw, .. } => Some(raw),
-                        Resource::Dead => unreachable!(),
+                        Resource::Dead { .. } => unreachable!(),
                     }
                 }
                 _ => None,
@@ -101,11 +98,15 @@ impl<T> ResourcePool<T> {
             match self.resources[handle.id()] {
                 Resource::Alive { raw: _, generation } if generation == handle.generation() => {
                     self.free_indices.push(handle.id());
-                    self.generation += 1;
-                    let old = std::mem::replace(&mut self.resources[handle.id()], Resource::Dead);
+                    let old = std::mem::replace(
+                        &mut self.resources[handle.id()],
+                        Resource::Dead {
+                            generation: generation.wrapping_add(1),
+                        },
+                    );
                     match old {
                         Resource::Alive { raw, .. } => Some(raw),
-                        Resource::Dead => unreachable!(),
+                        Resource::Dead { .. } => unreachable!(),
                     }
                 }
                 _ => None,
@@ -113,21 +114,47 @@ impl<T> ResourcePool<T> {
         }
     }
 
+    // Every slot is killed in place rather than dropped, so ids handed out before the drain can
+    // never match a resource inserted after it
     pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
-        self.generation += 1;
-        self.free_indices.extend(
-            self.resources
-                .iter()
-                .enumerate()
-                .filter(|(_, res)| res.is_alive())
-                .map(|(idx, _)| idx),
-        );
-        self.resources
-            .drain(..)
-            .filter(|res| res.is_alive())
-            .map(|res| match res {
-                Resource::Alive { raw, .. } => raw,
-                Resource::Dead => unreachable!(),
+        self.free_indices = (0..self.resources.len()).rev().collect();
+        let drained = self
+            .resources
+            .iter_mut()
+            .filter_map(|res| {
+                let generation = match res {
+                    Resource::Alive { generation, .. } => *generation,
+                    Resource::Dead { .. } => return None,
+                };
+                match std::mem::replace(
+                    res,
+                    Resource::Dead {
+                        generation: generation.wrapping_add(1),
+                    },
+                ) {
+                    Resource::Alive { raw, .. } => Some(raw),
+                    Resource::Dead { .. } => unreachable!(),
+                }
             })
+            .collect::<Vec<_>>();
+        drained.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResourcePool;
+
+    #[test]
+    fn stale_id_after_slot_reuse() {
+        let mut pool = ResourcePool::default();
+        let old_id = pool.insert("old image");
+        assert_eq!(pool.remove(old_id), Some("old image"));
+
+        let new_id = pool.insert("new image");
+        assert_eq!(new_id.id(), old_id.id());
+        assert_ne!(new_id, old_id);
+        assert!(pool.get(old_id).is_none());
+        assert_eq!(pool.get(new_id), Some(&"new image"));
     }
 }
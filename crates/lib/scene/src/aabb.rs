@@ -0,0 +1,118 @@
+use math::{mat::Mat4, vec::Vec3};
+use math_ext::Mat4Ext;
+use rkyv::{Archive, Deserialize, Serialize};
+
+// TODO: This belongs in `math`, it lives here for now since scenes need to be archivable
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self {
+            min: [min.x(), min.y(), min.z()],
+            max: [max.x(), max.y(), max.z()],
+        }
+    }
+
+    // An inverted box that any `union` will overwrite
+    pub fn empty() -> Self {
+        Self {
+            min: [f32::INFINITY; 3],
+            max: [f32::NEG_INFINITY; 3],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|i| self.min[i] > self.max[i])
+    }
+
+    pub fn min(&self) -> Vec3 {
+        Vec3::new(self.min[0], self.min[1], self.min[2])
+    }
+
+    pub fn max(&self) -> Vec3 {
+        Vec3::new(self.max[0], self.max[1], self.max[2])
+    }
+
+    pub fn union(&self, other: &Aabb) -> Self {
+        let mut ret = *self;
+        for i in 0..3 {
+            ret.min[i] = f32::min(ret.min[i], other.min[i]);
+            ret.max[i] = f32::max(ret.max[i], other.max[i]);
+        }
+        ret
+    }
+
+    pub fn union_point(&self, point: [f32; 3]) -> Self {
+        let mut ret = *self;
+        for i in 0..3 {
+            ret.min[i] = f32::min(ret.min[i], point[i]);
+            ret.max[i] = f32::max(ret.max[i], point[i]);
+        }
+        ret
+    }
+
+    pub fn center(&self) -> Vec3 {
+        Vec3::new(
+            (self.min[0] + self.max[0]) / 2.0,
+            (self.min[1] + self.max[1]) / 2.0,
+            (self.min[2] + self.max[2]) / 2.0,
+        )
+    }
+
+    // Half-size of the box along each axis
+    pub fn extents(&self) -> Vec3 {
+        Vec3::new(
+            (self.max[0] - self.min[0]) / 2.0,
+            (self.max[1] - self.min[1]) / 2.0,
+            (self.max[2] - self.min[2]) / 2.0,
+        )
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        let point = [point.x(), point.y(), point.z()];
+        (0..3).all(|i| self.min[i] <= point[i] && point[i] <= self.max[i])
+    }
+
+    pub fn transformed(&self, transform: &Mat4) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+        let m = transform.to_cols_array();
+        let mut ret = Self::empty();
+        for corner in 0..8 {
+            let p = [
+                if corner & 1 == 0 {
+                    self.min[0]
+                } else {
+                    self.max[0]
+                },
+                if corner & 2 == 0 {
+                    self.min[1]
+                } else {
+                    self.max[1]
+                },
+                if corner & 4 == 0 {
+                    self.min[2]
+                } else {
+                    self.max[2]
+                },
+            ];
+            let mut transformed = [0.0; 3];
+            for (row, value) in transformed.iter_mut().enumerate() {
+                *value = m[row] * p[0] + m[4 + row] * p[1] + m[8 + row] * p[2] + m[12 + row];
+            }
+            ret = ret.union_point(transformed);
+        }
+        ret
+    }
+}
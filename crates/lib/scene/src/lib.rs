@@ -1,5 +1,7 @@
+mod aabb;
 mod material;
 mod mesh;
+mod quantized_vertex;
 mod vertex;
 
 use anyhow::Result;
@@ -8,7 +10,7 @@ use rkyv::{Archive, Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 use zero_copy_assets::{try_decoded_file, ImageData, LoadFromPath, ZeroCopyError};
-pub use {material::*, mesh::*, vertex::*};
+pub use {aabb::*, material::*, mesh::*, quantized_vertex::*, vertex::*};
 
 #[derive(Debug, Error)]
 pub enum SceneError {
@@ -20,8 +22,7 @@ pub enum SceneError {
 
 #[derive(Archive, Serialize, Deserialize, Debug)]
 pub struct Scene<V: Vertex> {
-    pub min_pos: [f32; 3],
-    pub max_pos: [f32; 3],
+    pub bounds: Aabb,
     pub meshes: Vec<Mesh<V>>,
     pub materials: Vec<Material>,
 }
@@ -34,60 +35,79 @@ where
         let path = path.as_ref();
         let file = file.as_ref();
         let file_path = path.join(file);
-        debug_assert!(file_path.exists(), "Path does not exist: {file_path:?}");
+        if !file_path.exists() {
+            return Err(ZeroCopyError::NotFound(file_path).into());
+        }
         let (models, materials) = tobj::load_obj(file_path, &tobj::GPU_LOAD_OPTIONS)?;
         let materials = materials;
         let materials = if let Ok(materials) = materials {
             materials
                 .into_par_iter()
                 .map(|material| {
-                    let diffuse: Result<Option<ImageData>, SceneError> =
-                        if let Some(diffuse_texture) = material.diffuse_texture {
-                            let material_path = diffuse_texture
-                                .replace('\\', &format!("{}", std::path::MAIN_SEPARATOR));
-                            let image_path = path.join(material_path);
-                            let image_stem = image_path.file_stem().unwrap();
-                            let image = try_decoded_file::<ImageData>(
-                                &image_path,
-                                PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-                                    .join("assets")
-                                    .join("gen")
-                                    .join(format!("{}.adi", image_stem.to_str().unwrap())),
-                            )
-                            .unwrap();
-                            Ok(Some(image))
-                        } else {
-                            Ok(None)
-                        };
-                    let diffuse = diffuse?;
-                    Ok(Material { diffuse })
+                    let load_texture = |texture: Option<String>| {
+                        texture
+                            .map(|texture| {
+                                let material_path = texture
+                                    .replace('\\', &format!("{}", std::path::MAIN_SEPARATOR));
+                                let image_path = path.join(material_path);
+                                let image_stem = image_path
+                                    .file_stem()
+                                    .and_then(|stem| stem.to_str())
+                                    .ok_or_else(|| {
+                                        ZeroCopyError::InvalidUtf8(image_path.clone())
+                                    })?;
+                                try_decoded_file::<ImageData>(
+                                    &image_path,
+                                    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                                        .join("assets")
+                                        .join("gen")
+                                        .join(format!("{image_stem}.adi")),
+                                )
+                            })
+                            .transpose()
+                    };
+                    Ok(Material {
+                        diffuse: load_texture(material.diffuse_texture)?,
+                        normal: load_texture(material.normal_texture)?,
+                    })
                 })
                 .collect::<Result<Vec<_>, SceneError>>()?
         } else {
             vec![]
         };
 
-        let mut min_pos = [f32::INFINITY; 3];
-        let mut max_pos = [f32::NEG_INFINITY; 3];
+        let mut bounds = Aabb::empty();
         let mut meshes = Vec::with_capacity(models.len());
         for model in models {
             let mesh = Mesh::from_obj_model(model);
-
-            for i in 0..3 {
-                min_pos[i] = f32::min(min_pos[i], mesh.min_pos[i]);
-                max_pos[i] = f32::max(max_pos[i], mesh.max_pos[i]);
-            }
-
+            bounds = bounds.union(&mesh.world_bounds());
             meshes.push(mesh);
         }
 
         Ok(Self {
             meshes,
             materials,
-            min_pos,
-            max_pos,
+            bounds,
         })
     }
+
+    // Bindless array elements for the materials' textures in `slot`, at most `max_textures` of them
+    // starting at `first_slot`. See `TextureSlots`
+    pub fn texture_slots(
+        &self,
+        slot: MaterialSlot,
+        first_slot: u32,
+        max_textures: u32,
+        fallback_slot: u32,
+    ) -> TextureSlots {
+        TextureSlots::new(
+            &self.materials,
+            slot,
+            first_slot,
+            max_textures,
+            fallback_slot,
+        )
+    }
 }
 
 impl<V> LoadFromPath for Scene<V>
@@ -1,7 +1,110 @@
 use rkyv::{Archive, Deserialize, Serialize};
 use zero_copy_assets::ImageData;
 
+// How the bytes of a texture are interpreted when sampled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    // Color data authored in sRGB, decoded to linear by the sampler
+    Srgb,
+    // Non-color data like normals or metallic-roughness, sampled as-is
+    Linear,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaterialSlot {
+    Diffuse,
+    Emissive,
+    Normal,
+    MetallicRoughness,
+}
+
+impl MaterialSlot {
+    pub fn color_space(&self) -> ColorSpace {
+        match self {
+            MaterialSlot::Diffuse | MaterialSlot::Emissive => ColorSpace::Srgb,
+            MaterialSlot::Normal | MaterialSlot::MetallicRoughness => ColorSpace::Linear,
+        }
+    }
+}
+
 #[derive(Archive, Serialize, Deserialize, Debug)]
 pub struct Material {
     pub diffuse: Option<ImageData>,
+    pub normal: Option<ImageData>,
+}
+
+impl Material {
+    pub fn texture(&self, slot: MaterialSlot) -> Option<&ImageData> {
+        match slot {
+            MaterialSlot::Diffuse => self.diffuse.as_ref(),
+            MaterialSlot::Normal => self.normal.as_ref(),
+            MaterialSlot::Emissive | MaterialSlot::MetallicRoughness => None,
+        }
+    }
+}
+
+// Packs the textures materials have in one slot into consecutive bindless array elements, so
+// materials without a texture don't leave holes or shift the ones after them. Elements before
+// `first_slot` are left for fallbacks, like the renderer's default textures, and meshes whose
+// material has no texture sample `fallback_slot`
+#[derive(Debug, Clone)]
+pub struct TextureSlots {
+    // Array element of each material's texture, `None` when it has none or didn't fit
+    material_slots: Vec<Option<u32>>,
+    first_slot: u32,
+    texture_count: u32,
+    fallback_slot: u32,
+}
+
+impl TextureSlots {
+    pub fn new(
+        materials: &[Material],
+        slot: MaterialSlot,
+        first_slot: u32,
+        max_textures: u32,
+        fallback_slot: u32,
+    ) -> Self {
+        let mut texture_count = 0;
+        let material_slots = materials
+            .iter()
+            .map(|material| {
+                if material.texture(slot).is_some() && texture_count < max_textures {
+                    texture_count += 1;
+                    Some(first_slot + texture_count - 1)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        Self {
+            material_slots,
+            first_slot,
+            texture_count,
+            fallback_slot,
+        }
+    }
+
+    pub fn texture_count(&self) -> u32 {
+        self.texture_count
+    }
+
+    // Array elements needed to hold every texture, including the ones before `first_slot`
+    pub fn array_len(&self) -> u32 {
+        self.first_slot + self.texture_count
+    }
+
+    pub fn fallback_slot(&self) -> u32 {
+        self.fallback_slot
+    }
+
+    pub fn texture_slot(&self, material_index: usize) -> Option<u32> {
+        self.material_slots.get(material_index).copied().flatten()
+    }
+
+    // Array element sampled by meshes with `material_index`, the fallback if it has no texture
+    pub fn slot(&self, material_index: Option<u32>) -> u32 {
+        material_index
+            .and_then(|index| self.texture_slot(index as usize))
+            .unwrap_or(self.fallback_slot)
+    }
 }
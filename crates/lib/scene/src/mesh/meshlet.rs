@@ -0,0 +1,176 @@
+use crate::Aabb;
+use rkyv::{Archive, Deserialize, Serialize};
+
+// Limits recommended for mesh shaders on most desktop GPUs
+pub const MAX_MESHLET_VERTICES: usize = 64;
+pub const MAX_MESHLET_TRIANGLES: usize = 124;
+
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct MeshletBounds {
+    pub center: [f32; 3],
+    pub radius: f32,
+    // A camera at `camera_position` sees no front faces of the meshlet when
+    // `dot(center - camera_position, cone_axis) >= cone_cutoff * length(center - camera_position) + radius`
+    pub cone_axis: [f32; 3],
+    pub cone_cutoff: f32,
+}
+
+// A small cluster of a mesh's triangles. `vertex_offset` indexes `Meshlets::vertices`, which holds
+// indices into the mesh's vertices, and `triangle_offset` indexes `Meshlets::triangles`, which
+// holds three local vertex indices per triangle
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Meshlet {
+    pub vertex_offset: u32,
+    pub vertex_count: u32,
+    pub triangle_offset: u32,
+    pub triangle_count: u32,
+    pub bounds: MeshletBounds,
+}
+
+#[derive(Archive, Serialize, Deserialize, Debug, Default)]
+pub struct Meshlets {
+    pub meshlets: Vec<Meshlet>,
+    pub vertices: Vec<u32>,
+    pub triangles: Vec<u8>,
+}
+
+impl Meshlets {
+    // Greedily fills meshlets with triangles in index order, which keeps neighbouring triangles
+    // together for meshes that were already optimized for vertex cache locality
+    pub fn build(indices: &[u32], positions: &[[f32; 3]]) -> Self {
+        let mut ret = Self::default();
+        // Local index of each mesh vertex in the current meshlet, if it is in it
+        let mut local_indices = vec![None::<u8>; positions.len()];
+        let mut current = Meshlet::default();
+
+        for triangle in indices.chunks_exact(3) {
+            let new_vertices = triangle
+                .iter()
+                .enumerate()
+                .filter(|(idx, vertex)| {
+                    local_indices[**vertex as usize].is_none()
+                        && !triangle[..*idx].contains(*vertex)
+                })
+                .count();
+            if current.vertex_count as usize + new_vertices > MAX_MESHLET_VERTICES
+                || current.triangle_count as usize + 1 > MAX_MESHLET_TRIANGLES
+            {
+                ret.finish_meshlet(&mut current, &mut local_indices, positions);
+            }
+
+            for vertex in triangle {
+                let local = match local_indices[*vertex as usize] {
+                    Some(local) => local,
+                    None => {
+                        let local = current.vertex_count as u8;
+                        local_indices[*vertex as usize] = Some(local);
+                        ret.vertices.push(*vertex);
+                        current.vertex_count += 1;
+                        local
+                    }
+                };
+                ret.triangles.push(local);
+            }
+            current.triangle_count += 1;
+        }
+        if current.triangle_count > 0 {
+            ret.finish_meshlet(&mut current, &mut local_indices, positions);
+        }
+        ret
+    }
+
+    fn finish_meshlet(
+        &mut self,
+        current: &mut Meshlet,
+        local_indices: &mut [Option<u8>],
+        positions: &[[f32; 3]],
+    ) {
+        let vertices = &self.vertices[current.vertex_offset as usize..];
+        for vertex in vertices {
+            local_indices[*vertex as usize] = None;
+        }
+        let triangles = &self.triangles[current.triangle_offset as usize..];
+        current.bounds = meshlet_bounds(vertices, triangles, positions);
+        self.meshlets.push(*current);
+
+        *current = Meshlet {
+            vertex_offset: self.vertices.len() as u32,
+            triangle_offset: self.triangles.len() as u32,
+            ..Default::default()
+        };
+    }
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(a: [f32; 3]) -> Option<[f32; 3]> {
+    let length = dot(a, a).sqrt();
+    (length > f32::EPSILON).then(|| [a[0] / length, a[1] / length, a[2] / length])
+}
+
+fn meshlet_bounds(vertices: &[u32], triangles: &[u8], positions: &[[f32; 3]]) -> MeshletBounds {
+    let position = |local: u8| positions[vertices[local as usize] as usize];
+
+    let aabb = vertices.iter().fold(Aabb::empty(), |aabb, vertex| {
+        aabb.union_point(positions[*vertex as usize])
+    });
+    let center = [
+        (aabb.min[0] + aabb.max[0]) / 2.0,
+        (aabb.min[1] + aabb.max[1]) / 2.0,
+        (aabb.min[2] + aabb.max[2]) / 2.0,
+    ];
+    let radius = vertices
+        .iter()
+        .map(|vertex| {
+            let offset = sub(positions[*vertex as usize], center);
+            dot(offset, offset)
+        })
+        .fold(0.0, f32::max)
+        .sqrt();
+
+    let normals = triangles
+        .chunks_exact(3)
+        .filter_map(|triangle| {
+            let a = position(triangle[0]);
+            let b = position(triangle[1]);
+            let c = position(triangle[2]);
+            normalize(cross(sub(b, a), sub(c, a)))
+        })
+        .collect::<Vec<_>>();
+    let axis = normals.iter().fold([0.0; 3], |acc, n| {
+        [acc[0] + n[0], acc[1] + n[1], acc[2] + n[2]]
+    });
+    // Degenerate or widely spread normals can't be culled as a group
+    let (cone_axis, cone_cutoff) = match normalize(axis) {
+        Some(axis) => {
+            let min_dot = normals.iter().map(|n| dot(*n, axis)).fold(1.0, f32::min);
+            if min_dot <= 0.1 {
+                (axis, 1.0)
+            } else {
+                (axis, (1.0 - min_dot * min_dot).sqrt())
+            }
+        }
+        None => ([0.0, 0.0, 1.0], 1.0),
+    };
+
+    MeshletBounds {
+        center,
+        radius,
+        cone_axis,
+        cone_cutoff,
+    }
+}
@@ -1,14 +1,34 @@
-use crate::Vertex;
+mod meshlet;
+mod optimize;
+
+pub use meshlet::*;
+pub use optimize::average_cache_miss_ratio;
+
+use crate::{Aabb, Vertex};
+use math::mat::Mat4;
+use math_ext::Mat4Ext;
 use rkyv::{Archive, Deserialize, Serialize};
 use tobj::Model;
 
+#[rustfmt::skip]
+const IDENTITY: [f32; 16] = [
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 1.0, 0.0,
+    0.0, 0.0, 0.0, 1.0,
+];
+
 #[derive(Archive, Serialize, Deserialize, Debug)]
 pub struct Mesh<V: Vertex> {
     pub indices: Vec<u32>,
     pub vertices: Vec<V>,
     pub material_index: Option<u32>,
-    pub min_pos: [f32; 3],
-    pub max_pos: [f32; 3],
+    // In the mesh's local space, before `transform` is applied
+    pub bounds: Aabb,
+    // Column-major data of the mesh's model matrix, since `Mat4` isn't archivable
+    pub transform: [f32; 16],
+    // Clusters of `indices`, for GPU culling and mesh shaders
+    pub meshlets: Meshlets,
 }
 
 impl<V> Mesh<V>
@@ -18,28 +38,71 @@ where
     pub fn from_obj_model(model: Model) -> Self {
         let obj_mesh = model.mesh;
 
-        let mut mesh_min_pos = [f32::INFINITY; 3];
-        let mut mesh_max_pos = [f32::NEG_INFINITY; 3];
-        let mut vertices = Vec::with_capacity(obj_mesh.positions.len() / 3);
+        let positions = obj_mesh
+            .positions
+            .chunks_exact(3)
+            .map(|p| [p[0], p[1], p[2]])
+            .collect::<Vec<_>>();
+        let bounds = positions
+            .iter()
+            .fold(Aabb::empty(), |bounds, p| bounds.union_point(*p));
 
-        for i in 0..obj_mesh.positions.len() / 3 {
-            let vertex = V::from_obj_mesh_index(&obj_mesh, i);
-            let pos = vertex.pos_3d();
+        // Triangles are reordered for the post-transform cache, and vertices then renumbered in
+        // the order those triangles use them
+        let mut indices = optimize::optimize_vertex_cache(&obj_mesh.indices, positions.len());
+        let remap = optimize::optimize_vertex_fetch(&mut indices, positions.len());
+        let vertices = remap
+            .iter()
+            .map(|i| V::from_obj_mesh_index(&obj_mesh, *i as usize, &bounds))
+            .collect();
+        let positions = remap
+            .iter()
+            .map(|i| positions[*i as usize])
+            .collect::<Vec<_>>();
 
-            vertices.push(vertex);
-
-            for i in 0..3 {
-                mesh_min_pos[i] = f32::min(mesh_min_pos[i], pos[i]);
-                mesh_max_pos[i] = f32::max(mesh_max_pos[i], pos[i]);
-            }
-        }
+        let meshlets = Meshlets::build(&indices, &positions);
 
         Self {
-            indices: obj_mesh.indices,
+            indices,
             vertices,
             material_index: obj_mesh.material_id.map(|i| i as u32),
-            min_pos: mesh_min_pos,
-            max_pos: mesh_max_pos,
+            bounds,
+            transform: IDENTITY,
+            meshlets,
+        }
+    }
+
+    pub fn transform(&self) -> Mat4 {
+        Mat4::from_cols_array(&self.transform)
+    }
+
+    pub fn set_transform(&mut self, transform: &Mat4) {
+        self.transform = transform.to_cols_array();
+    }
+
+    pub fn with_transform(mut self, transform: &Mat4) -> Self {
+        self.set_transform(transform);
+        self
+    }
+
+    pub fn world_bounds(&self) -> Aabb {
+        self.bounds.transformed(&self.transform())
+    }
+}
+
+// Read in place from a `zero_copy_assets::MappedArchive`, without deserializing the mesh
+impl<V> ArchivedMesh<V>
+where
+    V: Vertex + Archive,
+{
+    pub fn material_index(&self) -> Option<u32> {
+        self.material_index.as_ref().copied()
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        Aabb {
+            min: self.bounds.min,
+            max: self.bounds.max,
         }
     }
 }
@@ -0,0 +1,169 @@
+// Vertex cache and vertex fetch optimization, following Tom Forsyth's "Linear-Speed Vertex Cache
+// Optimisation". Triangles are emitted greedily by a score that favours vertices recently used,
+// which are likely still in the post-transform cache, and vertices with few triangles left
+
+const CACHE_SIZE: usize = 32;
+const CACHE_DECAY_POWER: f32 = 1.5;
+// Vertices of the last triangle are scored lower, so strips don't keep reusing the same edge
+const LAST_TRIANGLE_SCORE: f32 = 0.75;
+const VALENCE_BOOST_SCALE: f32 = 2.0;
+const VALENCE_BOOST_POWER: f32 = 0.5;
+
+fn vertex_score(cache_position: Option<usize>, remaining_triangles: u32) -> f32 {
+    if remaining_triangles == 0 {
+        return -1.0;
+    }
+    let cache_score = match cache_position {
+        None => 0.0,
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) => {
+            let scale = 1.0 / (CACHE_SIZE - 3) as f32;
+            (1.0 - (position - 3) as f32 * scale).powf(CACHE_DECAY_POWER)
+        }
+    };
+    cache_score + VALENCE_BOOST_SCALE * (remaining_triangles as f32).powf(-VALENCE_BOOST_POWER)
+}
+
+// Reorders triangles so consecutive triangles share vertices, returning the new indices
+pub fn optimize_vertex_cache(indices: &[u32], vertex_count: usize) -> Vec<u32> {
+    let triangle_count = indices.len() / 3;
+
+    // Triangles not yet emitted that use each vertex, in a single buffer where the triangles of
+    // vertex `v` start at `offsets[v]`
+    let mut remaining = vec![0u32; vertex_count];
+    for index in indices {
+        remaining[*index as usize] += 1;
+    }
+    let mut offsets = vec![0usize; vertex_count];
+    for vertex in 1..vertex_count {
+        offsets[vertex] = offsets[vertex - 1] + remaining[vertex - 1] as usize;
+    }
+    let mut vertex_triangles = vec![0u32; indices.len()];
+    let mut filled = vec![0usize; vertex_count];
+    for (triangle, vertices) in indices.chunks_exact(3).enumerate() {
+        for vertex in vertices {
+            let vertex = *vertex as usize;
+            vertex_triangles[offsets[vertex] + filled[vertex]] = triangle as u32;
+            filled[vertex] += 1;
+        }
+    }
+
+    let mut cache_positions = vec![None::<usize>; vertex_count];
+    let mut vertex_scores = remaining
+        .iter()
+        .map(|remaining| vertex_score(None, *remaining))
+        .collect::<Vec<_>>();
+    let mut triangle_scores = indices
+        .chunks_exact(3)
+        .map(|vertices| {
+            vertices
+                .iter()
+                .map(|vertex| vertex_scores[*vertex as usize])
+                .sum::<f32>()
+        })
+        .collect::<Vec<_>>();
+    let mut emitted = vec![false; triangle_count];
+    // Triangles are picked in input order when none of the cached vertices has any left
+    let mut next_unemitted = 0;
+    let mut cache = Vec::<u32>::with_capacity(CACHE_SIZE + 3);
+    let mut best_triangle = None;
+    let mut ret = Vec::with_capacity(indices.len());
+
+    while ret.len() < triangle_count * 3 {
+        let triangle = match best_triangle {
+            Some(triangle) => triangle,
+            None => {
+                while emitted[next_unemitted] {
+                    next_unemitted += 1;
+                }
+                next_unemitted
+            }
+        };
+        emitted[triangle] = true;
+        let vertices = &indices[triangle * 3..triangle * 3 + 3];
+        ret.extend_from_slice(vertices);
+
+        for vertex in vertices {
+            let vertex = *vertex as usize;
+            let start = offsets[vertex];
+            let triangles = &mut vertex_triangles[start..start + remaining[vertex] as usize];
+            let position = triangles
+                .iter()
+                .position(|other| *other as usize == triangle)
+                .unwrap();
+            let last = triangles.len() - 1;
+            triangles.swap(position, last);
+            remaining[vertex] -= 1;
+        }
+
+        // The triangle's vertices move to the front of the cache, pushing the oldest ones out
+        let mut new_cache = vertices.to_vec();
+        new_cache.extend(cache.iter().filter(|vertex| !vertices.contains(vertex)));
+        for (position, vertex) in new_cache.iter().enumerate() {
+            cache_positions[*vertex as usize] = (position < CACHE_SIZE).then_some(position);
+        }
+
+        for vertex in &new_cache {
+            let vertex = *vertex as usize;
+            let score = vertex_score(cache_positions[vertex], remaining[vertex]);
+            let difference = score - vertex_scores[vertex];
+            vertex_scores[vertex] = score;
+            let start = offsets[vertex];
+            for other in &vertex_triangles[start..start + remaining[vertex] as usize] {
+                triangle_scores[*other as usize] += difference;
+            }
+        }
+
+        new_cache.truncate(CACHE_SIZE);
+        best_triangle = None;
+        let mut best_score = f32::MIN;
+        for vertex in &new_cache {
+            let vertex = *vertex as usize;
+            let start = offsets[vertex];
+            for other in &vertex_triangles[start..start + remaining[vertex] as usize] {
+                let score = triangle_scores[*other as usize];
+                if score > best_score {
+                    best_triangle = Some(*other as usize);
+                    best_score = score;
+                }
+            }
+        }
+        cache = new_cache;
+    }
+
+    ret
+}
+
+// Renumbers vertices in the order `indices` first uses them, so vertex fetches stay close
+// together in memory. Returns the old index of each new vertex, vertices that aren't used by any
+// triangle are dropped
+pub fn optimize_vertex_fetch(indices: &mut [u32], vertex_count: usize) -> Vec<u32> {
+    let mut remap = vec![None::<u32>; vertex_count];
+    let mut order = Vec::with_capacity(vertex_count);
+    for index in indices.iter_mut() {
+        *index = *remap[*index as usize].get_or_insert_with(|| {
+            order.push(*index);
+            order.len() as u32 - 1
+        });
+    }
+    order
+}
+
+// Average number of vertices transformed per triangle with a FIFO post-transform cache of
+// `cache_size` vertices. 3 when no vertex is ever reused, and 0.5 at best for regular grids
+pub fn average_cache_miss_ratio(indices: &[u32], vertex_count: usize, cache_size: usize) -> f32 {
+    if indices.len() < 3 {
+        return 0.0;
+    }
+    // Each vertex's cache entry is valid while fewer than `cache_size` misses happened since it
+    let mut cached_at = vec![None::<usize>; vertex_count];
+    let mut misses = 0;
+    for index in indices {
+        let cached = cached_at[*index as usize].is_some_and(|at| misses - at < cache_size);
+        if !cached {
+            cached_at[*index as usize] = Some(misses);
+            misses += 1;
+        }
+    }
+    misses as f32 / (indices.len() / 3) as f32
+}
@@ -0,0 +1,229 @@
+use crate::{Aabb, ObjMesh, Vertex};
+use rkyv::{Archive, Deserialize, Serialize};
+
+// 24 bytes per vertex, meant to be read through a buffer device address and decoded in the vertex
+// shader with `unpackUnorm2x16`, `unpackSnorm2x16`, `unpackHalf2x16` and `unpackUnorm4x8`.
+// Positions are 16-bit unorm relative to the mesh's `bounds`, normals are 16-bit snorm. UVs are
+// half floats instead of unorm, since tiling textures use coordinates outside of `[0, 1]`
+// The archived vertex has the same layout, so mapped archives upload their vertices as-is
+#[repr(C)]
+#[derive(Archive, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[archive_attr(repr(C), derive(Clone, Copy))]
+pub struct QuantizedVertex {
+    // `w` is padding, keeps the layout aligned to `uvec2`
+    pub position: [u16; 4],
+    // `w` is padding
+    pub normal: [i16; 4],
+    pub uv: [u16; 2],
+    pub color: [u8; 4],
+}
+
+const _: () = assert!(
+    std::mem::size_of::<ArchivedQuantizedVertex>() == std::mem::size_of::<QuantizedVertex>()
+);
+
+impl QuantizedVertex {
+    pub fn new(
+        position: [f32; 3],
+        normal: [f32; 3],
+        uv: [f32; 2],
+        color: [f32; 3],
+        bounds: &Aabb,
+    ) -> Self {
+        let extent = bounds_extent(bounds);
+        Self {
+            position: [
+                quantize_unorm16((position[0] - bounds.min[0]) / extent[0]),
+                quantize_unorm16((position[1] - bounds.min[1]) / extent[1]),
+                quantize_unorm16((position[2] - bounds.min[2]) / extent[2]),
+                0,
+            ],
+            normal: [
+                quantize_snorm16(normal[0]),
+                quantize_snorm16(normal[1]),
+                quantize_snorm16(normal[2]),
+                0,
+            ],
+            uv: [quantize_half(uv[0]), quantize_half(uv[1])],
+            color: [
+                quantize_unorm8(color[0]),
+                quantize_unorm8(color[1]),
+                quantize_unorm8(color[2]),
+                u8::MAX,
+            ],
+        }
+    }
+
+    pub fn position(&self, bounds: &Aabb) -> [f32; 3] {
+        let extent = bounds_extent(bounds);
+        let normalized = self.pos_3d();
+        [
+            bounds.min[0] + normalized[0] * extent[0],
+            bounds.min[1] + normalized[1] * extent[1],
+            bounds.min[2] + normalized[2] * extent[2],
+        ]
+    }
+
+    pub fn normal(&self) -> [f32; 3] {
+        [
+            dequantize_snorm16(self.normal[0]),
+            dequantize_snorm16(self.normal[1]),
+            dequantize_snorm16(self.normal[2]),
+        ]
+    }
+
+    pub fn uv(&self) -> [f32; 2] {
+        [dequantize_half(self.uv[0]), dequantize_half(self.uv[1])]
+    }
+
+    pub fn color(&self) -> [f32; 3] {
+        [
+            dequantize_unorm8(self.color[0]),
+            dequantize_unorm8(self.color[1]),
+            dequantize_unorm8(self.color[2]),
+        ]
+    }
+}
+
+impl Vertex for QuantizedVertex {
+    fn from_obj_mesh_index(mesh: &ObjMesh, i: usize, bounds: &Aabb) -> Self {
+        let position = [
+            mesh.positions[i * 3],
+            mesh.positions[i * 3 + 1],
+            mesh.positions[i * 3 + 2],
+        ];
+
+        let normal = if !mesh.normals.is_empty() {
+            [
+                mesh.normals[i * 3],
+                mesh.normals[i * 3 + 1],
+                mesh.normals[i * 3 + 2],
+            ]
+        } else {
+            [0.0, 0.0, 1.0]
+        };
+
+        let uv = if !mesh.texcoords.is_empty() {
+            [mesh.texcoords[i * 2], 1.0 - mesh.texcoords[i * 2 + 1]]
+        } else {
+            [0.0; 2]
+        };
+
+        let color = if !mesh.vertex_color.is_empty() {
+            [
+                mesh.vertex_color[i * 3],
+                mesh.vertex_color[i * 3 + 1],
+                mesh.vertex_color[i * 3 + 2],
+            ]
+        } else {
+            [1.0; 3]
+        };
+
+        Self::new(position, normal, uv, color, bounds)
+    }
+
+    // Normalized to `[0, 1]` within the mesh's bounds, use `position` for the actual position
+    fn pos_3d(&self) -> [f32; 3] {
+        [
+            dequantize_unorm16(self.position[0]),
+            dequantize_unorm16(self.position[1]),
+            dequantize_unorm16(self.position[2]),
+        ]
+    }
+
+    // Takes a position normalized to `[0, 1]` within the mesh's bounds
+    fn set_pos_3d(mut self, x: f32, y: f32, z: f32) -> Self {
+        self.position[0] = quantize_unorm16(x);
+        self.position[1] = quantize_unorm16(y);
+        self.position[2] = quantize_unorm16(z);
+        self
+    }
+
+    fn set_uv(mut self, u: f32, v: f32) -> Self {
+        self.uv = [quantize_half(u), quantize_half(v)];
+        self
+    }
+}
+
+// Flat meshes have a zero-sized axis, which must not divide by zero
+fn bounds_extent(bounds: &Aabb) -> [f32; 3] {
+    [
+        (bounds.max[0] - bounds.min[0]).max(f32::EPSILON),
+        (bounds.max[1] - bounds.min[1]).max(f32::EPSILON),
+        (bounds.max[2] - bounds.min[2]).max(f32::EPSILON),
+    ]
+}
+
+pub fn quantize_unorm8(v: f32) -> u8 {
+    (v.clamp(0.0, 1.0) * u8::MAX as f32).round() as u8
+}
+
+pub fn dequantize_unorm8(v: u8) -> f32 {
+    v as f32 / u8::MAX as f32
+}
+
+pub fn quantize_unorm16(v: f32) -> u16 {
+    (v.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+}
+
+pub fn dequantize_unorm16(v: u16) -> f32 {
+    v as f32 / u16::MAX as f32
+}
+
+// Matches `unpackSnorm2x16`, which maps both `-32768` and `-32767` to `-1.0`
+pub fn quantize_snorm16(v: f32) -> i16 {
+    (v.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16
+}
+
+pub fn dequantize_snorm16(v: i16) -> f32 {
+    (v as f32 / i16::MAX as f32).max(-1.0)
+}
+
+// IEEE 754 binary16, rounding to nearest with ties away from zero
+pub fn quantize_half(v: f32) -> u16 {
+    let bits = v.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x007f_ffff;
+
+    // Infinity and NaN
+    if exponent == 0xff {
+        let nan = if mantissa != 0 { 0x0200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        // Too small even for a subnormal half
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x0080_0000;
+        let shift = (14 - exponent) as u32;
+        let round = (mantissa >> (shift - 1)) & 1;
+        return sign | ((mantissa >> shift) + round) as u16;
+    }
+
+    // A carry out of the mantissa correctly bumps the exponent
+    let round = (mantissa >> 12) & 1;
+    sign | ((((exponent as u32) << 10) | (mantissa >> 13)) + round) as u16
+}
+
+pub fn dequantize_half(v: u16) -> f32 {
+    let sign = ((v & 0x8000) as u32) << 16;
+    let exponent = ((v >> 10) & 0x1f) as u32;
+    let mantissa = (v & 0x03ff) as u32;
+
+    let bits = match exponent {
+        0 => {
+            let value = mantissa as f32 / (1 << 24) as f32;
+            return if sign != 0 { -value } else { value };
+        }
+        0x1f => sign | 0x7f80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
+}
@@ -1,7 +1,10 @@
+use crate::Aabb;
+
 pub use tobj::Mesh as ObjMesh;
 
 pub trait Vertex: Default {
-    fn from_obj_mesh_index(mesh: &ObjMesh, i: usize) -> Self;
+    // `bounds` are the mesh's local bounds, for formats that store positions relative to them
+    fn from_obj_mesh_index(mesh: &ObjMesh, i: usize, bounds: &Aabb) -> Self;
 
     fn pos_3d(&self) -> [f32; 3];
 
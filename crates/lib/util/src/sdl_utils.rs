@@ -1,9 +1,13 @@
 use anyhow::Result;
-use sdl2::{video::Window, EventPump, Sdl};
+use sdl2::{
+    controller::GameController, event::Event, video::Window, EventPump, GameControllerSubsystem,
+    Sdl,
+};
 
 #[derive(Debug)]
 pub struct WindowDescription<'a> {
     pub title: &'a str,
+    // Starts with the mouse captured, see `SdlContext::set_mouse_captured`
     pub capture_mouse: bool,
 }
 
@@ -16,10 +20,33 @@ impl<'a> Default for WindowDescription<'a> {
     }
 }
 
+// Keeps connected game controllers open so SDL reports their events
+pub struct GameControllers {
+    subsystem: GameControllerSubsystem,
+    controllers: Vec<GameController>,
+}
+
+impl GameControllers {
+    pub fn on_event(&mut self, event: &Event) {
+        match event {
+            Event::ControllerDeviceAdded { which, .. } => match self.subsystem.open(*which) {
+                Ok(controller) => self.controllers.push(controller),
+                Err(err) => println!("Failed to open game controller {which}: {err}"),
+            },
+            Event::ControllerDeviceRemoved { which, .. } => {
+                self.controllers
+                    .retain(|controller| controller.instance_id() != *which);
+            }
+            _ => {}
+        }
+    }
+}
+
 pub struct SdlContext {
     pub sdl: Sdl,
     pub event_pump: EventPump,
     pub window: Window,
+    pub game_controllers: GameControllers,
 }
 
 impl SdlContext {
@@ -40,12 +67,29 @@ impl SdlContext {
 
         sdl.mouse()
             .warp_mouse_in_window(&window, width as i32 / 2, height as i32 / 2);
-        sdl.mouse().capture(window_description.capture_mouse);
 
-        Ok(Self {
+        let game_controllers = GameControllers {
+            subsystem: sdl.game_controller().unwrap(),
+            controllers: Default::default(),
+        };
+
+        let sdl_context = Self {
             sdl,
             event_pump,
             window,
-        })
+            game_controllers,
+        };
+        sdl_context.set_mouse_captured(window_description.capture_mouse);
+        Ok(sdl_context)
+    }
+
+    // A captured mouse is hidden and kept inside the window, and keeps reporting relative motion
+    // when it reaches its edges
+    pub fn set_mouse_captured(&self, captured: bool) {
+        self.sdl.mouse().set_relative_mouse_mode(captured);
+    }
+
+    pub fn is_mouse_captured(&self) -> bool {
+        self.sdl.mouse().relative_mouse_mode()
     }
 }
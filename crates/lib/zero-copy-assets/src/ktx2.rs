@@ -0,0 +1,108 @@
+use crate::{LoadFromPath, ZeroCopyError};
+use rkyv::{Archive, Deserialize, Serialize};
+use std::path::Path;
+
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+// Identifier, nine `u32` header fields, and the `u32`/`u64` pairs of the index
+const KTX2_LEVEL_INDEX_OFFSET: usize = 80;
+// Byte offset, byte length and uncompressed byte length, all `u64`
+const KTX2_LEVEL_INDEX_ENTRY_SIZE: usize = 24;
+// Raw `VkFormat`s of the color formats in `renderer::Format`, keep in sync with
+// `Format::from_vk_raw`
+const SUPPORTED_VK_FORMATS: [u32; 12] = [
+    37,  // VK_FORMAT_R8G8B8A8_UNORM
+    43,  // VK_FORMAT_R8G8B8A8_SRGB
+    44,  // VK_FORMAT_B8G8R8A8_UNORM
+    83,  // VK_FORMAT_R16G16_SFLOAT
+    97,  // VK_FORMAT_R16G16B16A16_SFLOAT
+    100, // VK_FORMAT_R32_SFLOAT
+    103, // VK_FORMAT_R32G32_SFLOAT
+    106, // VK_FORMAT_R32G32B32_SFLOAT
+    109, // VK_FORMAT_R32G32B32A32_SFLOAT
+    141, // VK_FORMAT_BC5_UNORM_BLOCK
+    145, // VK_FORMAT_BC7_UNORM_BLOCK
+    146, // VK_FORMAT_BC7_SRGB_BLOCK
+];
+
+// GPU-ready image data, uploaded without decoding. `levels` holds every mip level, largest first
+#[derive(Archive, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "validation", archive(check_bytes))]
+pub struct CompressedImageData {
+    pub width: u32,
+    pub height: u32,
+    // Raw `VkFormat`, e.g. `VK_FORMAT_BC7_UNORM_BLOCK`
+    pub vk_format: u32,
+    pub levels: Vec<Vec<u8>>,
+}
+
+// Only supports single layer 2D textures without supercompression
+impl LoadFromPath for CompressedImageData {
+    fn from_resource_path(path: impl AsRef<Path>) -> Result<Self, ZeroCopyError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)?;
+        let invalid = |reason: &'static str| ZeroCopyError::InvalidKtx2 {
+            path: path.to_owned(),
+            reason,
+        };
+
+        let read_u32 = |offset: usize| -> Result<u32, ZeroCopyError> {
+            bytes
+                .get(offset..offset + 4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+                .ok_or_else(|| invalid("truncated file"))
+        };
+        let read_u64 = |offset: usize| -> Result<u64, ZeroCopyError> {
+            bytes
+                .get(offset..offset + 8)
+                .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+                .ok_or_else(|| invalid("truncated file"))
+        };
+
+        if bytes.get(..KTX2_IDENTIFIER.len()) != Some(&KTX2_IDENTIFIER[..]) {
+            return Err(invalid("missing KTX2 identifier"));
+        }
+        let vk_format = read_u32(12)?;
+        let width = read_u32(20)?;
+        let height = read_u32(24)?;
+        let depth = read_u32(28)?;
+        let layer_count = read_u32(32)?;
+        let face_count = read_u32(36)?;
+        let level_count = read_u32(40)?;
+        let supercompression_scheme = read_u32(44)?;
+
+        if vk_format == 0 {
+            return Err(invalid("Basis Universal textures must be transcoded first"));
+        }
+        if !SUPPORTED_VK_FORMATS.contains(&vk_format) {
+            return Err(invalid("unsupported vk_format"));
+        }
+        if supercompression_scheme != 0 {
+            return Err(invalid("supercompression is not supported"));
+        }
+        if depth > 1 || layer_count > 1 || face_count != 1 {
+            return Err(invalid("only single layer 2D textures are supported"));
+        }
+
+        // A level count of 0 asks the loader to generate mips, which compressed formats can't do
+        let levels = (0..level_count.max(1) as usize)
+            .map(|level| {
+                let entry = KTX2_LEVEL_INDEX_OFFSET + level * KTX2_LEVEL_INDEX_ENTRY_SIZE;
+                let offset = read_u64(entry)? as usize;
+                let length = read_u64(entry + 8)? as usize;
+                bytes
+                    .get(offset..offset.saturating_add(length))
+                    .map(<[u8]>::to_vec)
+                    .ok_or_else(|| invalid("mip level out of bounds"))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            width,
+            height,
+            vk_format,
+            levels,
+        })
+    }
+}
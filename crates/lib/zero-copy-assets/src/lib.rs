@@ -1,5 +1,14 @@
-use std::path::Path;
+mod ktx2;
 
+use std::{
+    collections::HashMap,
+    io::Write,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+};
+
+pub use ktx2::CompressedImageData;
 use memmap2::Mmap;
 pub use rkyv;
 use rkyv::{
@@ -13,10 +22,18 @@ use thiserror::Error;
 
 const SCRATCH_SPACE: usize = 4096;
 
+// Bump whenever an archived type changes, so stale decoded files are regenerated
+pub const SCHEMA_VERSION: u32 = 4;
+const MAGIC: [u8; 8] = *b"CINDERZC";
+// Magic, schema version and padding. A multiple of 16 bytes, so the archive after it stays aligned
+const HEADER_SIZE: usize = 16;
+
 #[derive(Debug, Error)]
 pub enum ZeroCopyError {
     #[error(transparent)]
     IoError(#[from] std::io::Error),
+    #[error("Asset not found: {0:?}")]
+    NotFound(std::path::PathBuf),
     #[error(transparent)]
     ImageError(#[from] image::ImageError),
     #[error("Invalid UTF-8 in path {0:?}")]
@@ -32,10 +49,38 @@ pub enum ZeroCopyError {
             SharedSerializeMapError,
         >,
     ),
+    #[error("{path:?} is not a decoded asset file")]
+    InvalidHeader { path: std::path::PathBuf },
+    #[error("{path:?} has schema version {found}, expected {expected}")]
+    VersionMismatch {
+        path: std::path::PathBuf,
+        found: u32,
+        expected: u32,
+    },
+    #[error("{path:?} is not a supported KTX2 file: {reason}")]
+    InvalidKtx2 {
+        path: std::path::PathBuf,
+        reason: &'static str,
+    },
+    #[error("{path:?} failed validation: {err}")]
+    ValidationError {
+        path: std::path::PathBuf,
+        err: String,
+    },
     #[error("{0:?}")]
     Fallback(String),
 }
 
+impl ZeroCopyError {
+    // The decoded file was written by an older version, and should be regenerated
+    pub fn is_stale(&self) -> bool {
+        matches!(
+            self,
+            ZeroCopyError::InvalidHeader { .. } | ZeroCopyError::VersionMismatch { .. }
+        )
+    }
+}
+
 pub trait LoadFromPath: Sized {
     fn from_resource_path(path: impl AsRef<Path>) -> Result<Self, ZeroCopyError>;
 }
@@ -53,6 +98,25 @@ impl LoadFromPath for ImageData {
     }
 }
 
+// Strips and validates the header of a decoded file
+fn archive_bytes<'a>(path: &Path, bytes: &'a [u8]) -> Result<&'a [u8], ZeroCopyError> {
+    if bytes.len() < HEADER_SIZE || bytes[..MAGIC.len()] != MAGIC {
+        return Err(ZeroCopyError::InvalidHeader {
+            path: path.to_owned(),
+        });
+    }
+    let version = u32::from_le_bytes(bytes[MAGIC.len()..MAGIC.len() + 4].try_into().unwrap());
+    if version != SCHEMA_VERSION {
+        return Err(ZeroCopyError::VersionMismatch {
+            path: path.to_owned(),
+            found: version,
+            expected: SCHEMA_VERSION,
+        });
+    }
+    Ok(&bytes[HEADER_SIZE..])
+}
+
+// Trusts the archive, only use on files this program wrote
 pub fn from_decoded_file<T>(path: impl AsRef<Path>) -> Result<T, ZeroCopyError>
 where
     T: Archive,
@@ -61,20 +125,113 @@ where
     let path = path.as_ref();
     let file = std::fs::File::open(path)?;
     let mmap = unsafe { Mmap::map(&file) }?;
-    let ret = unsafe { rkyv::from_bytes_unchecked(&mmap) }?;
+    let ret = unsafe { rkyv::from_bytes_unchecked(archive_bytes(path, &mmap)?) }?;
+    Ok(ret)
+}
+
+// Validates the whole archive before deserializing it, so truncated or corrupt files are an error
+#[cfg(feature = "validation")]
+pub fn from_decoded_file_checked<T>(path: impl AsRef<Path>) -> Result<T, ZeroCopyError>
+where
+    T: Archive,
+    T::Archived: for<'a> rkyv::CheckBytes<rkyv::validation::validators::DefaultValidator<'a>>
+        + Deserialize<T, SharedDeserializeMap>,
+{
+    let path = path.as_ref();
+    let file = std::fs::File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let archived = rkyv::check_archived_root::<T>(archive_bytes(path, &mmap)?).map_err(|err| {
+        ZeroCopyError::ValidationError {
+            path: path.to_owned(),
+            err: err.to_string(),
+        }
+    })?;
+    let ret = archived.deserialize(&mut SharedDeserializeMap::new())?;
     Ok(ret)
 }
 
+// A decoded file mapped into memory, whose archived data is read in place instead of being
+// deserialized. Only the header is checked by `open`, the archive is either trusted or validated
+// once by `open_checked`
+pub struct MappedArchive<T> {
+    mmap: Mmap,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Archive> MappedArchive<T> {
+    /// # Safety
+    /// - the archive after the header must be a valid archive of `T`, e.g. a complete file written
+    ///   by `write` for `T` that nothing else modifies while it is mapped
+    pub unsafe fn open(path: impl AsRef<Path>) -> Result<Self, ZeroCopyError> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)?;
+        let mmap = Mmap::map(&file)?;
+        archive_bytes(path, &mmap)?;
+        Ok(Self {
+            mmap,
+            _marker: PhantomData,
+        })
+    }
+
+    // Validates the whole archive once, so truncated or corrupt files are an error
+    #[cfg(feature = "validation")]
+    pub fn open_checked(path: impl AsRef<Path>) -> Result<Self, ZeroCopyError>
+    where
+        T::Archived: for<'a> rkyv::CheckBytes<rkyv::validation::validators::DefaultValidator<'a>>,
+    {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)?;
+        let mmap = unsafe { Mmap::map(&file) }?;
+        rkyv::check_archived_root::<T>(archive_bytes(path, &mmap)?).map_err(|err| {
+            ZeroCopyError::ValidationError {
+                path: path.to_owned(),
+                err: err.to_string(),
+            }
+        })?;
+        Ok(Self {
+            mmap,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn archived(&self) -> &T::Archived {
+        // Valid archive, either trusted by the caller of `open` or checked by `open_checked`
+        unsafe { rkyv::archived_root::<T>(&self.mmap[HEADER_SIZE..]) }
+    }
+}
+
 pub fn write<T>(resource: &T, path: impl AsRef<Path>) -> Result<(), ZeroCopyError>
 where
     T: Serialize<AllocSerializer<SCRATCH_SPACE>>,
 {
     let path = path.as_ref();
     let bytes = rkyv::to_bytes::<_, SCRATCH_SPACE>(resource)?;
-    std::fs::write(path, bytes)?;
+    let mut header = [0; HEADER_SIZE];
+    header[..MAGIC.len()].copy_from_slice(&MAGIC);
+    header[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&SCHEMA_VERSION.to_le_bytes());
+    // Written next to the destination and renamed, so readers never see a partial file
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let mut file = std::fs::File::create(&tmp_path)?;
+    file.write_all(&header)?;
+    file.write_all(&bytes)?;
+    drop(file);
+    std::fs::rename(&tmp_path, path)?;
     Ok(())
 }
 
+// One lock per decoded file, so threads decoding the same asset don't race to write it
+fn decoded_path_lock(path: &Path) -> Arc<Mutex<()>> {
+    static LOCKS: OnceLock<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>> = OnceLock::new();
+    LOCKS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(path.to_owned())
+        .or_default()
+        .clone()
+}
+
 pub fn try_decoded_file<T>(
     original_path: impl AsRef<Path>,
     decoded_path: impl AsRef<Path>,
@@ -85,20 +242,68 @@ where
 {
     let original_path = original_path.as_ref();
     let decoded_path = decoded_path.as_ref();
+    // Later callers block until the first one has written the file, then read it
+    let lock = decoded_path_lock(decoded_path);
+    let _guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
     if decoded_path.exists() {
-        from_decoded_file(decoded_path)
-    } else {
-        let ret = T::from_resource_path(original_path)?;
-        let parent = decoded_path
-            .parent()
-            .ok_or_else(|| ZeroCopyError::InvalidUtf8(decoded_path.to_owned()))?;
-        std::fs::create_dir_all(parent)?;
-        write(&ret, decoded_path)?;
-        Ok(ret)
+        match from_decoded_file(decoded_path) {
+            Err(err) if err.is_stale() => {
+                println!("Regenerating {decoded_path:?}: {err}");
+            }
+            ret => return ret,
+        }
     }
+    decode_to_file(original_path, decoded_path)
+}
+
+/// Same as `try_decoded_file`, mapping the decoded file instead of deserializing it. A file that
+/// has to be generated first is written out and then mapped
+///
+/// # Safety
+/// - an existing `decoded_path` is opened with `MappedArchive::open`, and must meet its contract
+pub unsafe fn try_mapped_file<T>(
+    original_path: impl AsRef<Path>,
+    decoded_path: impl AsRef<Path>,
+) -> Result<MappedArchive<T>, ZeroCopyError>
+where
+    T: Archive + Serialize<AllocSerializer<SCRATCH_SPACE>> + LoadFromPath,
+{
+    let original_path = original_path.as_ref();
+    let decoded_path = decoded_path.as_ref();
+    let lock = decoded_path_lock(decoded_path);
+    let _guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+    if decoded_path.exists() {
+        match MappedArchive::open(decoded_path) {
+            Err(err) if err.is_stale() => {
+                println!("Regenerating {decoded_path:?}: {err}");
+            }
+            ret => return ret,
+        }
+    }
+    decode_to_file::<T>(original_path, decoded_path)?;
+    // Just written by `write` for `T`
+    MappedArchive::open(decoded_path)
+}
+
+// Loads the original asset and writes it to `decoded_path`, the caller must hold its lock
+fn decode_to_file<T>(original_path: &Path, decoded_path: &Path) -> Result<T, ZeroCopyError>
+where
+    T: Serialize<AllocSerializer<SCRATCH_SPACE>> + LoadFromPath,
+{
+    if !original_path.exists() {
+        return Err(ZeroCopyError::NotFound(original_path.to_owned()));
+    }
+    let ret = T::from_resource_path(original_path)?;
+    let parent = decoded_path
+        .parent()
+        .ok_or_else(|| ZeroCopyError::InvalidUtf8(decoded_path.to_owned()))?;
+    std::fs::create_dir_all(parent)?;
+    write(&ret, decoded_path)?;
+    Ok(ret)
 }
 
 #[derive(Archive, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "validation", archive(check_bytes))]
 pub struct ImageData {
     pub width: u32,
     pub height: u32,